@@ -1,4 +1,5 @@
 pub mod cors;
 pub mod jwt;
+pub mod oidc;
 pub mod session;
 pub mod state;
@@ -1,7 +1,5 @@
-mod cleanup;
 mod store;
 mod utils;
 
-pub use cleanup::SessionCleanupJob;
 pub use store::PrismaSessionStore;
 pub use utils::{delete_cookie_header, get_session_layer, SESSION_USER_KEY};
@@ -5,6 +5,7 @@ use prisma_client_rust::chrono::{DateTime, Duration, FixedOffset, Utc};
 use stump_core::{
 	config::StumpConfig,
 	db::entity::User,
+	job::SessionCleanupJob,
 	prisma::{session, user, PrismaClient},
 	Ctx,
 };
@@ -15,7 +16,7 @@ use tower_sessions::{
 	SessionStore,
 };
 
-use super::{SessionCleanupJob, SESSION_USER_KEY};
+use super::SESSION_USER_KEY;
 
 // TODO(axum-upgrade): Refactor this store. See https://github.com/maxcountryman/tower-sessions-stores/blob/main/sqlx-store/src/sqlite_store.rs
 // TODO(axum-upgrade): refactor error variants
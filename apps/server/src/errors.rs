@@ -119,6 +119,10 @@ pub enum APIError {
 	Unauthorized,
 	#[error("{0}")]
 	Forbidden(String),
+	#[error("{0}")]
+	Conflict(String),
+	#[error("{0}")]
+	TooManyRequests(String),
 	#[error("This functionality has not been implemented yet")]
 	NotImplemented,
 	#[error("This functionality is not supported")]
@@ -148,6 +152,8 @@ impl APIError {
 			APIError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
 			APIError::Unauthorized => StatusCode::UNAUTHORIZED,
 			APIError::Forbidden(_) => StatusCode::FORBIDDEN,
+			APIError::Conflict(_) => StatusCode::CONFLICT,
+			APIError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
 			APIError::NotImplemented => StatusCode::NOT_IMPLEMENTED,
 			APIError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
 			APIError::BadGateway(_) => StatusCode::BAD_GATEWAY,
@@ -213,7 +219,9 @@ impl From<TryFromIntError> for APIError {
 impl From<CoreError> for APIError {
 	fn from(err: CoreError) -> Self {
 		match err {
+			CoreError::BadRequest(err) => APIError::BadRequest(err),
 			CoreError::InternalError(err) => APIError::InternalServerError(err),
+			CoreError::NotFound(err) => APIError::NotFound(err),
 			CoreError::IoError(err) => APIError::InternalServerError(err.to_string()),
 			CoreError::MigrationError(err) => APIError::InternalServerError(err),
 			CoreError::QueryError(err) => APIError::InternalServerError(err.to_string()),
@@ -361,4 +369,14 @@ pub mod api_error_message {
 		"Your account is locked. Please contact an administrator to unlock your account.";
 	pub const FORBIDDEN_ACTION: &str =
 		"You do not have permission to perform this action.";
+	pub const TWO_FACTOR_REQUIRED: &str =
+		"A two-factor authentication code is required to log in.";
+	pub const INVALID_TWO_FACTOR_CODE: &str =
+		"The two-factor authentication code is invalid or has already been used.";
+	pub const TWO_FACTOR_ENROLLMENT_REQUIRED: &str =
+		"You must enable two-factor authentication for your account before continuing.";
+	pub const TWO_FACTOR_API_KEY_REQUIRED: &str =
+		"Accounts with two-factor authentication enabled must use an API key instead of a password.";
+	pub const API_KEY_SCOPE_FORBIDDEN: &str =
+		"The API key is not allowed to be used for this request.";
 }
@@ -1,11 +1,12 @@
 use std::str::FromStr;
 
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
 use serde::{de, Deserialize, Serialize};
 use serde_untagged::UntaggedEnumVisitor;
 use serde_with::skip_serializing_none;
 use specta::Type;
 use stump_core::db::{
-	entity::{age_rating_deserializer, LogLevel},
+	entity::{age_rating_deserializer, AuditAction, AuditTargetType, LogLevel},
 	query::ordering::QueryOrder,
 };
 use utoipa::ToSchema;
@@ -346,6 +347,19 @@ pub struct LogFilter {
 	pub timestamp: Option<ValueOrRange<String>>,
 }
 
+#[skip_serializing_none]
+#[derive(Default, Debug, Clone, Deserialize, Serialize, ToSchema, Type)]
+pub struct AuditLogFilter {
+	pub action: Option<AuditAction>,
+	pub actor_id: Option<String>,
+	pub target_type: Option<AuditTargetType>,
+	pub target_id: Option<String>,
+	/// Only include entries recorded at or after this time
+	pub since: Option<DateTime<FixedOffset>>,
+	/// Only include entries recorded before this time
+	pub until: Option<DateTime<FixedOffset>>,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use axum::{
 	body::Body,
 	extract::{OriginalUri, Path, Request, State},
-	http::{header, StatusCode},
+	http::{header, Method, StatusCode},
 	middleware::Next,
 	response::{IntoResponse, Redirect, Response},
 	Extension, Json,
@@ -13,7 +13,7 @@ use prefixed_api_key::{PrefixedApiKey, PrefixedApiKeyController};
 use prisma_client_rust::or;
 use serde::Deserialize;
 use stump_core::{
-	db::entity::{APIKeyPermissions, User, UserPermission, API_KEY_PREFIX},
+	db::entity::{APIKeyPermissions, APIKeyScopes, User, UserPermission, API_KEY_PREFIX},
 	opds::v2_0::{
 		authentication::{
 			OPDSAuthenticationDocumentBuilder, OPDSSupportedAuthFlow,
@@ -32,7 +32,10 @@ use crate::{
 		state::AppState,
 	},
 	errors::{api_error_message, APIError, APIResult},
-	routers::{enforce_max_sessions, relative_favicon_path},
+	routers::{
+		enforce_max_sessions, has_two_factor_enabled, is_two_factor_required,
+		relative_favicon_path,
+	},
 	utils::{
 		current_utc_time, decode_base64_credentials, get_session_user,
 		user_has_all_permissions, verify_password,
@@ -69,6 +72,22 @@ impl RequestContext {
 		self.api_key.clone()
 	}
 
+	/// Create a context for the given user, for testing handlers without the middleware
+	#[cfg(test)]
+	pub(crate) fn for_user(user: User) -> Self {
+		Self {
+			user,
+			api_key: None,
+		}
+	}
+
+	/// Get the scopes of the API key the current user authenticated with, if any. The library
+	/// and series restrictions are applied by the filters for the user, e.g.
+	/// `library_not_hidden_from_user_filter`
+	pub fn api_key_scopes(&self) -> Option<&APIKeyScopes> {
+		self.user.api_key_scopes.as_ref()
+	}
+
 	/// Enforce that the current user has all the permissions provided, otherwise return an error
 	#[tracing::instrument(skip(self))]
 	pub fn enforce_permissions(&self, permissions: &[UserPermission]) -> APIResult<()> {
@@ -179,6 +198,9 @@ pub async fn auth_middleware(
 		}
 
 		if !user.is_locked {
+			enforce_two_factor_enrollment(&ctx, &user, &request_uri)
+				.await
+				.map_err(|e| e.into_response())?;
 			req.extensions_mut().insert(RequestContext {
 				user,
 				api_key: None,
@@ -216,7 +238,7 @@ pub async fn auth_middleware(
 	let req_ctx = match auth_header {
 		_ if auth_header.starts_with("Bearer ") && auth_header.len() > 7 => {
 			let token = auth_header[7..].to_owned();
-			handle_bearer_auth(token, &ctx.db)
+			handle_bearer_auth(token, &ctx.db, req.method(), &request_uri)
 				.await
 				.map_err(|e| e.into_response())?
 		},
@@ -250,11 +272,45 @@ pub async fn auth_middleware(
 		}
 	}
 
+	// API keys are the alternative for clients which can't prompt for a second factor, so
+	// they aren't subject to the enrollment requirement
+	if req_ctx.api_key.is_none() {
+		enforce_two_factor_enrollment(&ctx, &req_ctx.user, &request_uri)
+			.await
+			.map_err(|e| e.into_response())?;
+	}
+
 	req.extensions_mut().insert(req_ctx);
 
 	Ok(next.run(req).await)
 }
 
+/// Reject the request if the server requires the user to use two-factor authentication and
+/// they haven't enabled it yet. The auth routes are still allowed, so the user can enroll
+async fn enforce_two_factor_enrollment(
+	ctx: &AppState,
+	user: &User,
+	request_uri: &str,
+) -> APIResult<()> {
+	if !is_two_factor_required(user, &ctx.config)
+		|| request_uri.starts_with("/api/v1/auth/")
+	{
+		return Ok(());
+	}
+
+	if has_two_factor_enabled(&ctx.db, &user.id).await? {
+		Ok(())
+	} else {
+		tracing::error!(
+			username = &user.username,
+			"User has not enabled required two-factor authentication, denying access"
+		);
+		Err(APIError::Forbidden(
+			api_error_message::TWO_FACTOR_ENROLLMENT_REQUIRED.to_string(),
+		))
+	}
+}
+
 #[derive(Debug, Deserialize)]
 pub struct APIKeyPath(HashMap<String, String>);
 
@@ -289,7 +345,12 @@ pub async fn api_key_middleware(
 		return Err(APIError::Unauthorized.into_response());
 	};
 
-	let user = validate_api_key(pak, &ctx.db)
+	let method = req.method().clone();
+	let path = req.extensions().get::<OriginalUri>().cloned().map_or_else(
+		|| req.uri().path().to_owned(),
+		|uri| uri.0.path().to_owned(),
+	);
+	let user = validate_api_key(pak, &ctx.db, Some((&method, &path)))
 		.await
 		.map_err(|e| e.into_response())?;
 
@@ -328,9 +389,14 @@ pub async fn server_owner_middleware(
 	Ok(next.run(req).await)
 }
 
+/// Validate an API key, returning the user it belongs to with the permissions and scopes of the
+/// key applied. The `request` is the method and path of the request the key is being used for,
+/// which must be allowed by the scopes of the key. It is `None` when a key is only being
+/// checked, rather than used
 pub async fn validate_api_key(
 	pak: PrefixedApiKey,
 	client: &PrismaClient,
+	request: Option<(&Method, &str)>,
 ) -> APIResult<User> {
 	let controller = PrefixedApiKeyController::configure()
 		.prefix(API_KEY_PREFIX.to_owned())
@@ -358,6 +424,12 @@ pub async fn validate_api_key(
 		.ok_or(APIError::Unauthorized)?;
 	let key_user = api_key.user().ok().ok_or(APIError::Unauthorized)?;
 	let api_key_permissions = APIKeyPermissions::try_from(api_key.permissions.clone())?;
+	let api_key_scopes = api_key
+		.scopes
+		.clone()
+		.map(APIKeyScopes::try_from)
+		.transpose()?
+		.unwrap_or_default();
 
 	// Note: we check as a precaution. If a user had the permission revoked, that logic should also
 	// clean up keys.
@@ -374,6 +446,19 @@ pub async fn validate_api_key(
 		return Err(APIError::Unauthorized);
 	}
 
+	if let Some((method, path)) = request {
+		if !api_key_scopes.allows_request(method.as_str(), path) {
+			tracing::error!(
+				?method,
+				path,
+				"API key is not allowed to be used for request"
+			);
+			return Err(APIError::Forbidden(
+				api_error_message::API_KEY_SCOPE_FORBIDDEN.to_string(),
+			));
+		}
+	}
+
 	let update_result = client
 		.api_key()
 		.update(
@@ -387,12 +472,20 @@ pub async fn validate_api_key(
 		tracing::error!(error = ?e, "Failed to update API key");
 	}
 
+	// Note: The scopes are carried on the user so that the filters for the user restrict
+	// the content to the libraries and series of the key
+	let api_key_scopes =
+		(api_key_scopes != APIKeyScopes::default()).then_some(api_key_scopes);
 	let constructed_user = match api_key_permissions {
-		APIKeyPermissions::Inherit(_) => User::from(key_user.clone()),
+		APIKeyPermissions::Inherit(_) => User {
+			api_key_scopes,
+			..User::from(key_user.clone())
+		},
 		// Note: we don't construct permission sets for inferred permissions. What you
 		// give to your API key is what it gets.
 		APIKeyPermissions::Custom(permissions) => User {
 			permissions,
+			api_key_scopes,
 			..User::from(key_user.clone())
 		},
 	};
@@ -406,10 +499,12 @@ pub async fn validate_api_key(
 async fn handle_bearer_auth(
 	token: String,
 	client: &PrismaClient,
+	method: &Method,
+	request_uri: &str,
 ) -> APIResult<RequestContext> {
 	match PrefixedApiKey::from_string(token.as_str()) {
 		Ok(api_key) if api_key.prefix() == API_KEY_PREFIX => {
-			return validate_api_key(api_key, client)
+			return validate_api_key(api_key, client, Some((method, request_uri)))
 				.await
 				.map(|user| RequestContext {
 					user,
@@ -500,6 +595,17 @@ async fn handle_basic_auth(
 		return Err(APIError::Unauthorized);
 	}
 
+	// A password alone can't satisfy two-factor authentication, so these users need an API key
+	if has_two_factor_enabled(client, &user.id).await? {
+		tracing::error!(
+			username = &user.username,
+			"User has two-factor authentication enabled, denying basic authentication"
+		);
+		return Err(APIError::Forbidden(
+			api_error_message::TWO_FACTOR_API_KEY_REQUIRED.to_string(),
+		));
+	}
+
 	tracing::trace!(username = &user.username, "Basic authentication successful");
 
 	if save_session {
@@ -626,7 +732,11 @@ mod tests {
 	use axum_test::{TestServer, TestServerConfig};
 	use header::{HeaderName, HeaderValue};
 	use prisma_client_rust::MockStore;
-	use stump_core::{config::StumpConfig, db::entity::APIKey, Ctx};
+	use stump_core::{
+		config::StumpConfig,
+		db::entity::{APIKey, APIKeyRouteScope},
+		Ctx,
+	};
 	use time::Duration;
 	use tower_sessions::{cookie::SameSite, Expiry, MemoryStore, SessionManagerLayer};
 
@@ -1093,6 +1203,7 @@ mod tests {
 			user: Some(Box::new(create_prisma_user(for_user, String::default()))),
 			permissions: serde_json::to_vec(&key.permissions)
 				.expect("Failed to serialize"),
+			scopes: Some(serde_json::to_vec(&key.scopes).expect("Failed to serialize")),
 			expires_at: key.expires_at,
 			created_at: key.created_at,
 			last_used_at: key.last_used_at,
@@ -1156,6 +1267,59 @@ mod tests {
 		assert_eq!(response.status_code().as_u16(), 200);
 	}
 
+	#[tokio::test]
+	async fn test_auth_middleware_with_out_of_scope_api_key() {
+		let user = User {
+			id: "oromei-id".to_string(),
+			username: "oromei".to_string(),
+			is_server_owner: true,
+			..Default::default()
+		};
+
+		let (client, mock_store, server) = setup_test_app();
+
+		let (pak, api_key_raw, mut api_key) = create_key();
+		api_key.scopes = APIKeyScopes {
+			routes: vec![APIKeyRouteScope::OPDS],
+			..Default::default()
+		};
+
+		mock_store
+			.expect(
+				client
+					.api_key()
+					.find_first(vec![
+						api_key::short_token::equals(pak.short_token().to_string()),
+						api_key::long_token_hash::equals(api_key.long_token_hash.clone()),
+						api_key::user::is(vec![
+							user::deleted_at::equals(None),
+							user::is_locked::equals(false),
+						]),
+						or![
+							api_key::expires_at::gte(current_utc_time().into()),
+							api_key::expires_at::equals(None),
+						],
+					])
+					.with(api_key::user::fetch()),
+				Some(key_data(&api_key, &user)),
+			)
+			.await;
+
+		// The key is restricted to the OPDS routes, so it is rejected before it is marked
+		// as used
+
+		let response = server
+			.get("/test")
+			.add_header(
+				HeaderName::from_str("Authorization").expect("Failed to create header"),
+				HeaderValue::from_str(&format!("Bearer {api_key_raw}"))
+					.expect("Failed to create header"),
+			)
+			.await;
+
+		assert_eq!(response.status_code().as_u16(), 403);
+	}
+
 	#[tokio::test]
 	async fn test_auth_middleware_with_non_existent_api_key() {
 		let (client, mock_store, server) = setup_test_app();
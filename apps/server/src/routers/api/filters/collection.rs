@@ -0,0 +1,43 @@
+use prisma_client_rust::{and, or};
+use stump_core::{
+	db::entity::{EntityVisibility, User, UserPermission},
+	prisma::{
+		collection::{self, WhereParam},
+		collection_item, user,
+	},
+};
+
+use crate::{
+	routers::api::filters::{
+		apply_media_restrictions_for_user, apply_series_restrictions_for_user,
+	},
+	utils::user_has_permission,
+};
+
+/// Generates the conditions which restrict collections to those visible to the user. Users
+/// who manage collections can see all of them, otherwise only public collections and shared
+/// collections which include the user are visible.
+pub(crate) fn collection_visible_to_user_filter(user: &User) -> Vec<WhereParam> {
+	if user_has_permission(user, UserPermission::ManageCollections) {
+		return vec![];
+	}
+
+	vec![or![
+		collection::visibility::equals(EntityVisibility::Public.to_string()),
+		and![
+			collection::visibility::equals(EntityVisibility::Shared.to_string()),
+			collection::shared_with_users::some(vec![user::id::equals(user.id.clone())])
+		]
+	]]
+}
+
+/// Generates a condition which restricts collection items to the books and series the
+/// user has access to, i.e. respecting hidden libraries and age restrictions
+pub(crate) fn collection_item_accessible_to_user_filter(
+	user: &User,
+) -> collection_item::WhereParam {
+	or![
+		collection_item::media::is(apply_media_restrictions_for_user(user)),
+		collection_item::series::is(apply_series_restrictions_for_user(user))
+	]
+}
@@ -1,5 +1,6 @@
+use prisma_client_rust::operator;
 use stump_core::{
-	db::entity::User,
+	db::entity::{APIKeyScopes, User},
 	prisma::{
 		library::{self, WhereParam},
 		user,
@@ -41,8 +42,22 @@ pub(crate) fn apply_library_relation_filters(
 	)
 }
 
+/// A condition for the libraries which the user can access, i.e. those which aren't hidden from
+/// them and are within the scopes of the API key they authenticated with (if any)
 pub(crate) fn library_not_hidden_from_user_filter(user: &User) -> WhereParam {
-	library::hidden_from_users::none(vec![user::id::equals(user.id.clone())])
+	let not_hidden =
+		library::hidden_from_users::none(vec![user::id::equals(user.id.clone())]);
+	let scope_params = user
+		.api_key_scopes
+		.as_ref()
+		.map(APIKeyScopes::library_params)
+		.unwrap_or_default();
+
+	if scope_params.is_empty() {
+		not_hidden
+	} else {
+		operator::and(std::iter::once(not_hidden).chain(scope_params).collect())
+	}
 }
 
 // FIXME: hidden libraries introduced a bug here, need to fix!
@@ -1,10 +1,13 @@
 use prisma_client_rust::{
 	and,
 	operator::{self, or},
-	or,
+	or, PrismaValue,
 };
 use stump_core::{
-	db::{entity::User, query::pagination::Pagination},
+	db::{
+		entity::User,
+		query::{pagination::Pagination, raw::RawSql},
+	},
 	prisma::{
 		active_reading_session, finished_reading_session,
 		media::{self, WhereParam},
@@ -19,7 +22,7 @@ use crate::{
 	},
 	routers::api::filters::{
 		apply_media_metadata_base_filters, apply_series_filters,
-		library_not_hidden_from_user_filter,
+		series_not_hidden_from_user_filter,
 	},
 };
 
@@ -114,9 +117,9 @@ pub(crate) fn apply_media_filters(filters: MediaFilter) -> Vec<WhereParam> {
 pub(crate) fn apply_media_library_not_hidden_for_user_filter(
 	user: &User,
 ) -> Vec<WhereParam> {
-	vec![media::series::is(vec![series::library::is(vec![
-		library_not_hidden_from_user_filter(user),
-	])])]
+	vec![media::series::is(vec![series_not_hidden_from_user_filter(
+		user,
+	)])]
 }
 
 pub(crate) fn apply_media_filters_for_user(
@@ -143,9 +146,8 @@ pub(crate) fn apply_media_filters_for_user(
 	// instead. This was a regression from the exclusion feature I need to tackle
 	vec![and![
 		base_filters,
-		media::series::is(vec![series::library::is(vec![
-			library_not_hidden_from_user_filter(user),
-		])])
+		media::deleted_at::equals(None),
+		media::series::is(vec![series_not_hidden_from_user_filter(user)])
 	]]
 }
 
@@ -247,9 +249,82 @@ pub fn apply_media_restrictions_for_user(user: &User) -> Vec<WhereParam> {
 		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
 
 	chain_optional_iter(
-		[media::series::is(vec![series::library::is(vec![
-			library_not_hidden_from_user_filter(user),
-		])])],
+		[
+			media::deleted_at::equals(None),
+			media::series::is(vec![series_not_hidden_from_user_filter(user)]),
+		],
 		[age_restrictions],
 	)
 }
+
+/// The raw SQL equivalent of [apply_media_age_restriction], as a condition over `media m`
+fn media_age_restriction_sql(min_age: i32, restrict_on_unset: bool) -> RawSql {
+	let rated_under_age = RawSql::new(
+		"EXISTS (SELECT 1 FROM media_metadata amm WHERE amm.media_id = m.id AND amm.age_rating <= {})",
+		vec![PrismaValue::Int(min_age.into())],
+	);
+
+	let defer_to_series = if restrict_on_unset {
+		RawSql::new(
+			"EXISTS (SELECT 1 FROM media_metadata amm WHERE amm.media_id = m.id AND amm.age_rating IS NULL) \
+			AND EXISTS (SELECT 1 FROM series_metadata asm WHERE asm.series_id = m.series_id AND asm.age_rating <= {})",
+			vec![PrismaValue::Int(min_age.into())],
+		)
+	} else {
+		RawSql::new(
+			"NOT EXISTS (SELECT 1 FROM media_metadata amm WHERE amm.media_id = m.id AND amm.age_rating IS NOT NULL) \
+			AND NOT EXISTS (SELECT 1 FROM series_metadata asm WHERE asm.series_id = m.series_id AND asm.age_rating > {})",
+			vec![PrismaValue::Int(min_age.into())],
+		)
+	};
+
+	RawSql::or([defer_to_series, rated_under_age])
+}
+
+/// The raw SQL equivalent of [apply_media_restrictions_for_user], as a condition over
+/// `media m`. This is used by the queries which Prisma can't express, so it must be kept in
+/// sync with the where params above
+pub(crate) fn media_restrictions_sql_for_user(user: &User) -> RawSql {
+	let scopes = user.api_key_scopes.as_ref();
+	let string_values = |ids: &[String]| -> Vec<PrismaValue> {
+		ids.iter().cloned().map(PrismaValue::String).collect()
+	};
+
+	let series_conditions = chain_optional_iter(
+		[
+			RawSql::new("s.id = m.series_id AND s.library_id IS NOT NULL", vec![]),
+			RawSql::new(
+				"NOT EXISTS (SELECT 1 FROM _LibraryToUser lu WHERE lu.A = s.library_id AND lu.B = {})",
+				vec![PrismaValue::String(user.id.clone())],
+			),
+		],
+		[
+			scopes
+				.filter(|scopes| !scopes.library_ids.is_empty())
+				.map(|scopes| {
+					RawSql::in_list("s.library_id", string_values(&scopes.library_ids))
+				}),
+			// A series scope also limits the libraries to those of the scoped series, which
+			// this already implies
+			scopes
+				.filter(|scopes| !scopes.series_ids.is_empty())
+				.map(|scopes| RawSql::in_list("s.id", string_values(&scopes.series_ids))),
+		],
+	);
+	let series_not_hidden = RawSql::new("EXISTS (SELECT 1 FROM series s WHERE ", vec![])
+		.append(RawSql::and(series_conditions))
+		.push(")");
+
+	let age_restriction = user
+		.age_restriction
+		.as_ref()
+		.map(|ar| media_age_restriction_sql(ar.age, ar.restrict_on_unset));
+
+	RawSql::and(chain_optional_iter(
+		[
+			RawSql::new("m.deleted_at IS NULL", vec![]),
+			series_not_hidden,
+		],
+		[age_restriction],
+	))
+}
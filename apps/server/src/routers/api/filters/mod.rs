@@ -1,8 +1,10 @@
+mod collection;
 mod library;
 mod media;
 mod metadata;
 mod series;
 
+pub(crate) use collection::*;
 pub(crate) use library::*;
 pub(crate) use media::*;
 pub(crate) use metadata::*;
@@ -1,6 +1,6 @@
 use prisma_client_rust::{and, operator, or};
 use stump_core::{
-	db::entity::User,
+	db::entity::{APIKeyScopes, User},
 	prisma::{
 		media::{self},
 		media_metadata,
@@ -73,12 +73,32 @@ pub(crate) fn apply_series_filters(filters: SeriesFilter) -> Vec<WhereParam> {
 		.collect()
 }
 
+/// A condition for the series which the user can access, i.e. those in a library which isn't
+/// hidden from them and within the scopes of the API key they authenticated with (if any)
+pub(crate) fn series_not_hidden_from_user_filter(user: &User) -> WhereParam {
+	let library_not_hidden =
+		series::library::is(vec![library_not_hidden_from_user_filter(user)]);
+	let scope_params = user
+		.api_key_scopes
+		.as_ref()
+		.map(APIKeyScopes::series_params)
+		.unwrap_or_default();
+
+	if scope_params.is_empty() {
+		library_not_hidden
+	} else {
+		operator::and(
+			std::iter::once(library_not_hidden)
+				.chain(scope_params)
+				.collect(),
+		)
+	}
+}
+
 pub(crate) fn apply_series_library_not_hidden_for_user_filter(
 	user: &User,
 ) -> Vec<WhereParam> {
-	vec![series::library::is(vec![
-		library_not_hidden_from_user_filter(user),
-	])]
+	vec![series_not_hidden_from_user_filter(user)]
 }
 
 // TODO: this is wrong
@@ -130,9 +150,7 @@ pub fn apply_series_restrictions_for_user(user: &User) -> Vec<WhereParam> {
 		.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset));
 
 	chain_optional_iter(
-		[series::library::is(vec![
-			library_not_hidden_from_user_filter(user),
-		])],
+		[series_not_hidden_from_user_filter(user)],
 		[age_restrictions],
 	)
 }
@@ -156,8 +174,5 @@ pub(crate) fn apply_series_filters_for_user(
 	// TODO: This is not ideal, I am adding an _additional_ relation filter for
 	// the library exclusion, when I need to merge any requested filters with this one,
 	// instead. This was a regression from the exclusion feature I need to tackle
-	vec![and![
-		base_filters,
-		series::library::is(vec![library_not_hidden_from_user_filter(user)])
-	]]
+	vec![and![base_filters, series_not_hidden_from_user_filter(user)]]
 }
@@ -26,18 +26,26 @@ mod tests {
 		config::jwt::CreatedToken,
 		filter::*,
 		routers::api::v1::{
+			annotation::*,
 			api_key::*,
 			auth::*,
 			book_club::*,
+			collection::*,
 			config::*,
 			emailer::*,
 			epub::*,
+			invitation::*,
 			job::*,
 			library::*,
-			media::{individual::*, thumbnails::*},
+			media::{bulk, duplicates, individual::*, thumbnails::*},
 			metadata::*,
+			metadata_provider::*,
+			password_reset::*,
+			review::*,
+			search::*,
 			series::*,
 			smart_list::*,
+			stats::*,
 			user::*,
 			ClaimResponse, StumpVersion, UpdateCheck,
 		},
@@ -83,6 +91,21 @@ mod tests {
 		file.write_all(
 			format!("{}\n\n", ts_export::<LoginOrRegisterArgs>()?).as_bytes(),
 		)?;
+		file.write_all(format!("{}\n\n", ts_export::<OidcStatus>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<TwoFactorStatus>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<TwoFactorEnrollment>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<TwoFactorCode>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<TwoFactorRecoveryCodes>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<RequestPasswordReset>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ConfirmPasswordReset>()?).as_bytes(),
+		)?;
 		file.write_all(format!("{}\n\n", ts_export::<CreateUser>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<UpdateUser>()?).as_bytes())?;
 		file.write_all(
@@ -116,6 +139,7 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<PatchEmailDevice>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<LogFilter>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<AuditLogFilter>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<LibraryBaseFilter>()?).as_bytes())?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<LibraryRelationFilter>()?).as_bytes(),
@@ -164,6 +188,23 @@ mod tests {
 		file.write_all(
 			format!("{}\n\n", ts_export::<PutMediaCompletionStatus>()?).as_bytes(),
 		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<WriteMetadataParams>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<bulk::WriteMediaMetadataToFiles>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<duplicates::StartDuplicateAnalysis>()?)
+				.as_bytes(),
+		)?;
+		file.write_all(
+			format!(
+				"{}\n\n",
+				ts_export::<duplicates::ResolveDuplicateCluster>()?
+			)
+			.as_bytes(),
+		)?;
 		file.write_all(format!("{}\n\n", ts_export::<MediaIsComplete>()?).as_bytes())?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<MediaMetadataOverview>()?).as_bytes(),
@@ -174,8 +215,36 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<DeleteBookmark>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<SeriesIsComplete>()?).as_bytes())?;
 		file.write_all(
-			format!("{}\n\n", ts_export::<UpdateSchedulerConfig>()?).as_bytes(),
+			format!("{}\n\n", ts_export::<CreateOrUpdateJobSchedule>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CreateOrUpdateReview>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<TopRatedParams>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<RatedMedia>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<RatedSeries>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CreateOrUpdateAnnotation>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<AnnotationExportParams>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CreateServerInvitation>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CreatedServerInvitation>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<RedeemServerInvitation>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CreateOrUpdateCollection>()?).as_bytes(),
+		)?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<CollectionItemInput>()?).as_bytes(),
 		)?;
+		file.write_all(format!("{}\n\n", ts_export::<SetCollectionItems>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<GetBookClubsParams>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<CreateBookClub>()?).as_bytes())?;
@@ -231,6 +300,20 @@ mod tests {
 			format!("{}\n\n", ts_export::<CreateOrUpdateSmartListView>()?).as_bytes(),
 		)?;
 
+		file.write_all(format!("{}\n\n", ts_export::<MediaSearchParams>()?).as_bytes())?;
+
+		file.write_all(format!("{}\n\n", ts_export::<FetchMetadata>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<ApplyMetadataCandidate>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<LockMetadataField>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataHistoryParams>()?).as_bytes(),
+		)?;
+
+		file.write_all(format!("{}\n\n", ts_export::<ReadingStatsParams>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<YearInReviewParams>()?).as_bytes())?;
+
 		file.write_all(format!("{}\n\n", ts_export::<UploadConfig>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<StumpConfig>()?).as_bytes())?;
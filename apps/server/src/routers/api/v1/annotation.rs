@@ -0,0 +1,577 @@
+use axum::{
+	extract::{Path, Query, State},
+	http::header,
+	middleware,
+	response::IntoResponse,
+	routing::get,
+	Extension, Json, Router,
+};
+use prisma_client_rust::Direction;
+use serde::Deserialize;
+use specta::Type;
+use stump_core::{
+	db::{
+		entity::{export_annotations, AnnotationExportFormat, MediaAnnotation, User},
+		query::pagination::{Pageable, Pagination, PaginationQuery},
+	},
+	prisma::{media, media_annotation, user},
+};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::filters::apply_media_restrictions_for_user,
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/annotations", get(get_annotations))
+		.route("/annotations/export", get(export_user_annotations))
+		.route(
+			"/annotations/{id}",
+			get(get_annotation_by_id)
+				.put(update_annotation)
+				.delete(delete_annotation),
+		)
+		.route(
+			"/media/{id}/annotations",
+			get(get_media_annotations).post(create_media_annotation),
+		)
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+/// The conditions which restrict annotations to those the user is allowed to see: their own
+/// annotations, for books the user still has access to
+fn annotations_for_user_filter(user: &User) -> Vec<media_annotation::WhereParam> {
+	vec![
+		media_annotation::user_id::equals(user.id.clone()),
+		media_annotation::media::is(apply_media_restrictions_for_user(user)),
+	]
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct CreateOrUpdateAnnotation {
+	/// The text that was highlighted, if any
+	#[serde(default)]
+	pub highlighted_text: Option<String>,
+	/// The epubcfi of the annotation, for EPUBs
+	#[serde(default)]
+	pub epubcfi: Option<String>,
+	/// The 1-based page of the annotation, for image-based books
+	#[serde(default)]
+	pub page: Option<i32>,
+	/// The x coordinate of the annotation on the page, as a percentage of the page width
+	#[serde(default)]
+	pub page_coordinates_x: Option<f64>,
+	/// The y coordinate of the annotation on the page, as a percentage of the page height
+	#[serde(default)]
+	pub page_coordinates_y: Option<f64>,
+	/// The user notes for the annotation
+	#[serde(default)]
+	pub notes: Option<String>,
+}
+
+impl CreateOrUpdateAnnotation {
+	fn validate(&self) -> APIResult<()> {
+		if self.epubcfi().is_none() && self.page.is_none() {
+			return Err(APIError::BadRequest(String::from(
+				"An annotation must have either an epubcfi or a page",
+			)));
+		}
+
+		if self.page.is_some_and(|page| page < 1) {
+			return Err(APIError::BadRequest(String::from(
+				"Page must be greater than 0",
+			)));
+		}
+
+		let is_valid_coordinate = |coordinate: Option<f64>| {
+			coordinate.is_none_or(|c| (0.0..=100.0).contains(&c))
+		};
+		if !is_valid_coordinate(self.page_coordinates_x)
+			|| !is_valid_coordinate(self.page_coordinates_y)
+		{
+			return Err(APIError::BadRequest(String::from(
+				"Page coordinates must be between 0 and 100",
+			)));
+		}
+
+		Ok(())
+	}
+
+	/// Validate that the page (if any) exists in a book with the given number of pages. Books
+	/// which have not been analyzed may report 0 pages, in which case the page is not checked
+	fn validate_page(&self, book_pages: i32) -> APIResult<()> {
+		if self
+			.page
+			.is_some_and(|page| book_pages > 0 && page > book_pages)
+		{
+			return Err(APIError::BadRequest(format!(
+				"Page must be between 1 and {book_pages}"
+			)));
+		}
+
+		Ok(())
+	}
+
+	fn epubcfi(&self) -> Option<String> {
+		trimmed(self.epubcfi.as_deref())
+	}
+
+	fn highlighted_text(&self) -> Option<String> {
+		trimmed(self.highlighted_text.as_deref())
+	}
+
+	fn notes(&self) -> Option<String> {
+		trimmed(self.notes.as_deref())
+	}
+
+	fn set_params(&self) -> Vec<media_annotation::SetParam> {
+		vec![
+			media_annotation::highlighted_text::set(self.highlighted_text()),
+			media_annotation::epubcfi::set(self.epubcfi()),
+			media_annotation::page::set(self.page),
+			media_annotation::page_coordinates_x::set(self.page_coordinates_x),
+			media_annotation::page_coordinates_y::set(self.page_coordinates_y),
+			media_annotation::notes::set(self.notes()),
+		]
+	}
+}
+
+/// Trim a value, treating blank values as no value
+fn trimmed(value: Option<&str>) -> Option<String> {
+	value
+		.map(str::trim)
+		.filter(|value| !value.is_empty())
+		.map(String::from)
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct AnnotationExportParams {
+	/// The format to export the annotations as. Defaults to Markdown
+	#[serde(default)]
+	pub format: AnnotationExportFormat,
+	/// Only export the annotations of this book
+	#[serde(default)]
+	pub media_id: Option<String>,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/annotations",
+	tag = "annotation",
+	params(
+		("pagination_query" = Option<PaginationQuery>, Query, description = "The pagination options")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched annotations", body = [MediaAnnotation]),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get all of the requesting user's annotations across every book they have access to, most
+/// recently updated first
+async fn get_annotations(
+	State(ctx): State<AppState>,
+	pagination_query: Query<PaginationQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<MediaAnnotation>>>> {
+	let user = req.user();
+	let pagination = pagination_query.0.get();
+	let is_unpaged = pagination.is_unpaged();
+	let where_params = annotations_for_user_filter(user);
+
+	let mut query = ctx
+		.db
+		.media_annotation()
+		.find_many(where_params.clone())
+		.with(media_annotation::media::fetch().with(media::metadata::fetch()))
+		.order_by(media_annotation::updated_at::order(Direction::Desc));
+
+	match &pagination {
+		Pagination::Page(page_query) => {
+			let (skip, take) = page_query.get_skip_take();
+			query = query.skip(skip).take(take);
+		},
+		Pagination::Cursor(cursor_query) => {
+			if let Some(cursor) = cursor_query.cursor.as_deref() {
+				query = query
+					.cursor(media_annotation::id::equals(cursor.to_string()))
+					.skip(1);
+			}
+			if let Some(limit) = cursor_query.limit {
+				query = query.take(limit);
+			}
+		},
+		_ => {},
+	}
+
+	let annotations = query
+		.exec()
+		.await?
+		.into_iter()
+		.map(MediaAnnotation::from)
+		.collect::<Vec<_>>();
+
+	if is_unpaged {
+		return Ok(Json(Pageable::from(annotations)));
+	}
+
+	let count = ctx.db.media_annotation().count(where_params).exec().await?;
+
+	Ok(Json(Pageable::from((annotations, count, pagination))))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/annotations/export",
+	tag = "annotation",
+	params(
+		("format" = Option<String>, Query, description = "The export format: markdown, json or koreader"),
+		("media_id" = Option<String>, Query, description = "Only export the annotations of this book"),
+	),
+	responses(
+		(status = 200, description = "Successfully exported annotations", body = String),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Export the requesting user's annotations as a downloadable file
+async fn export_user_annotations(
+	State(ctx): State<AppState>,
+	Query(params): Query<AnnotationExportParams>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<impl IntoResponse> {
+	let user = req.user();
+
+	let mut where_params = annotations_for_user_filter(user);
+	if let Some(media_id) = params.media_id {
+		where_params.push(media_annotation::media_id::equals(media_id));
+	}
+
+	let annotations = ctx
+		.db
+		.media_annotation()
+		.find_many(where_params)
+		.with(media_annotation::media::fetch().with(media::metadata::fetch()))
+		.order_by(media_annotation::created_at::order(Direction::Asc))
+		.exec()
+		.await?
+		.into_iter()
+		.map(MediaAnnotation::from)
+		.collect::<Vec<_>>();
+
+	let format = params.format;
+	let output = export_annotations(annotations, format)?;
+	let disposition = format!(
+		"attachment; filename=\"annotations.{}\"",
+		format.extension()
+	);
+
+	Ok((
+		[
+			(header::CONTENT_TYPE, format.content_type().to_string()),
+			(header::CONTENT_DISPOSITION, disposition),
+		],
+		output,
+	))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/annotations/{id}",
+	tag = "annotation",
+	params(
+		("id" = String, Path, description = "The ID of the annotation")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched annotation", body = MediaAnnotation),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Annotation not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get one of the requesting user's annotations by its ID
+async fn get_annotation_by_id(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<MediaAnnotation>> {
+	let user = req.user();
+
+	let annotation = ctx
+		.db
+		.media_annotation()
+		.find_first(
+			[media_annotation::id::equals(id)]
+				.into_iter()
+				.chain(annotations_for_user_filter(user))
+				.collect(),
+		)
+		.with(media_annotation::media::fetch())
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Annotation not found")))?;
+
+	Ok(Json(MediaAnnotation::from(annotation)))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/annotations/{id}",
+	tag = "annotation",
+	params(
+		("id" = String, Path, description = "The ID of the annotation to update")
+	),
+	request_body = CreateOrUpdateAnnotation,
+	responses(
+		(status = 200, description = "Successfully updated annotation", body = MediaAnnotation),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Annotation not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Update one of the requesting user's annotations
+async fn update_annotation(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateAnnotation>,
+) -> APIResult<Json<MediaAnnotation>> {
+	input.validate()?;
+
+	let user = req.user();
+	let client = &ctx.db;
+
+	let annotation = client
+		.media_annotation()
+		.find_first(
+			[media_annotation::id::equals(id)]
+				.into_iter()
+				.chain(annotations_for_user_filter(user))
+				.collect(),
+		)
+		.with(media_annotation::media::fetch())
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Annotation not found")))?;
+	input.validate_page(annotation.media()?.pages)?;
+
+	let updated_annotation = client
+		.media_annotation()
+		.update(
+			media_annotation::id::equals(annotation.id),
+			input.set_params(),
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(MediaAnnotation::from(updated_annotation)))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/annotations/{id}",
+	tag = "annotation",
+	params(
+		("id" = String, Path, description = "The ID of the annotation to delete")
+	),
+	responses(
+		(status = 200, description = "Successfully deleted annotation", body = MediaAnnotation),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Annotation not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Delete one of the requesting user's annotations
+async fn delete_annotation(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<MediaAnnotation>> {
+	let user = req.user();
+	let client = &ctx.db;
+
+	let annotation = client
+		.media_annotation()
+		.find_first(vec![
+			media_annotation::id::equals(id),
+			media_annotation::user_id::equals(user.id.clone()),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Annotation not found")))?;
+
+	let deleted_annotation = client
+		.media_annotation()
+		.delete(media_annotation::id::equals(annotation.id))
+		.exec()
+		.await?;
+
+	Ok(Json(MediaAnnotation::from(deleted_annotation)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/{id}/annotations",
+	tag = "annotation",
+	params(
+		("id" = String, Path, description = "The ID of the media to get annotations for")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched annotations", body = [MediaAnnotation]),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the requesting user's annotations for a book, ordered by page and then creation time
+async fn get_media_annotations(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MediaAnnotation>>> {
+	let user = req.user();
+
+	let annotations = ctx
+		.db
+		.media_annotation()
+		.find_many(
+			[media_annotation::media_id::equals(id)]
+				.into_iter()
+				.chain(annotations_for_user_filter(user))
+				.collect(),
+		)
+		.order_by(media_annotation::page::order(Direction::Asc))
+		.order_by(media_annotation::created_at::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	Ok(Json(
+		annotations.into_iter().map(MediaAnnotation::from).collect(),
+	))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/{id}/annotations",
+	tag = "annotation",
+	params(
+		("id" = String, Path, description = "The ID of the media to annotate")
+	),
+	request_body = CreateOrUpdateAnnotation,
+	responses(
+		(status = 200, description = "Successfully created annotation", body = MediaAnnotation),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Create an annotation of a book for the requesting user
+async fn create_media_annotation(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateAnnotation>,
+) -> APIResult<Json<MediaAnnotation>> {
+	input.validate()?;
+
+	let user = req.user();
+	let client = &ctx.db;
+
+	let book = client
+		.media()
+		.find_first(
+			[media::id::equals(id)]
+				.into_iter()
+				.chain(apply_media_restrictions_for_user(user))
+				.collect(),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Media not found")))?;
+
+	input.validate_page(book.pages)?;
+
+	let created_annotation = client
+		.media_annotation()
+		.create(
+			user::id::equals(user.id.clone()),
+			media::id::equals(book.id),
+			input.set_params(),
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(MediaAnnotation::from(created_annotation)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn input() -> CreateOrUpdateAnnotation {
+		CreateOrUpdateAnnotation {
+			highlighted_text: None,
+			epubcfi: None,
+			page: None,
+			page_coordinates_x: None,
+			page_coordinates_y: None,
+			notes: None,
+		}
+	}
+
+	#[test]
+	fn test_validate_annotation() {
+		assert!(input().validate().is_err());
+		assert!(CreateOrUpdateAnnotation {
+			epubcfi: Some("  ".to_string()),
+			..input()
+		}
+		.validate()
+		.is_err());
+		assert!(CreateOrUpdateAnnotation {
+			epubcfi: Some("epubcfi(/6/4!/4/2/1:0)".to_string()),
+			..input()
+		}
+		.validate()
+		.is_ok());
+		assert!(CreateOrUpdateAnnotation {
+			page: Some(0),
+			..input()
+		}
+		.validate()
+		.is_err());
+		assert!(CreateOrUpdateAnnotation {
+			page: Some(3),
+			page_coordinates_x: Some(120.0),
+			..input()
+		}
+		.validate()
+		.is_err());
+		assert!(CreateOrUpdateAnnotation {
+			page: Some(3),
+			page_coordinates_x: Some(50.0),
+			page_coordinates_y: Some(25.5),
+			..input()
+		}
+		.validate()
+		.is_ok());
+	}
+
+	#[test]
+	fn test_validate_page() {
+		let with_page = CreateOrUpdateAnnotation {
+			page: Some(3),
+			..input()
+		};
+		assert!(with_page.validate_page(3).is_ok());
+		assert!(with_page.validate_page(2).is_err());
+		// Books which haven't been analyzed don't have a known page count
+		assert!(with_page.validate_page(0).is_ok());
+		assert!(input().validate_page(2).is_ok());
+	}
+}
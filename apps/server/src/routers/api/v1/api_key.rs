@@ -11,14 +11,20 @@ use prefixed_api_key::PrefixedApiKey;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use stump_core::{
-	db::entity::{APIKey, APIKeyPermissions, UserPermission},
-	prisma::{api_key, user},
+	db::entity::{
+		APIKey, APIKeyPermissions, APIKeyScopes, AuditAction, AuditEvent, User,
+		UserPermission,
+	},
+	prisma::{api_key, library, series, user, PrismaClient},
 };
 
 use crate::{
 	config::state::AppState,
-	errors::{APIError, APIResult},
+	errors::{api_error_message, APIError, APIResult},
 	middleware::auth::{auth_middleware, validate_api_key, RequestContext},
+	routers::api::filters::{
+		library_not_hidden_from_user_filter, series_not_hidden_from_user_filter,
+	},
 };
 
 pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
@@ -46,6 +52,12 @@ async fn authorize(req: Request, next: Next) -> APIResult<Response> {
 		.get::<RequestContext>()
 		.ok_or(APIError::Unauthorized)?;
 	ctx.enforce_permissions(&[UserPermission::AccessAPIKeys])?;
+	// A key with scopes could otherwise be used to create a key without them
+	if ctx.api_key_scopes().is_some() {
+		return Err(APIError::Forbidden(
+			api_error_message::API_KEY_SCOPE_FORBIDDEN.to_string(),
+		));
+	}
 	Ok(next.run(req).await)
 }
 
@@ -107,7 +119,7 @@ async fn validate_api_key_handler(
 	let pak = PrefixedApiKey::from_string(api_key)
 		.map_err(|_| APIError::BadRequest("Invalid API key in headers".to_string()))?;
 
-	let is_valid = match validate_api_key(pak, &ctx.db).await {
+	let is_valid = match validate_api_key(pak, &ctx.db, None).await {
 		Ok(key_user) => key_user.is(req.user()),
 		// We swallow errors to avoid showing our hand a bit, i.e. if the key wasn't found
 		// or is expired etc that is not surfaced. This is a bit of a tradeoff since it might
@@ -125,6 +137,11 @@ pub struct CreateOrUpdateAPIKey {
 	name: String,
 	/// The permissions that the API key should have
 	permissions: APIKeyPermissions,
+	/// The scopes which restrict what the API key can be used for. If not provided, the key
+	/// is not restricted beyond its permissions
+	#[serde(default)]
+	#[specta(optional)]
+	scopes: APIKeyScopes,
 	/// The expiration date for the API key, if any
 	#[specta(optional)]
 	expires_at: Option<DateTime<FixedOffset>>,
@@ -139,6 +156,52 @@ pub struct CreatedAPIKey {
 	api_key: String,
 }
 
+/// Validate the scopes requested for an API key, returning them serialized for storage. The
+/// libraries and series must exist and be accessible to the user. Unrestricted scopes are not
+/// stored at all
+async fn validate_api_key_scopes(
+	client: &PrismaClient,
+	user: &User,
+	scopes: &APIKeyScopes,
+) -> APIResult<Option<Vec<u8>>> {
+	if scopes == &APIKeyScopes::default() {
+		return Ok(None);
+	}
+
+	let libraries_count = client
+		.library()
+		.count(vec![
+			library::id::in_vec(scopes.library_ids.clone()),
+			library_not_hidden_from_user_filter(user),
+		])
+		.exec()
+		.await?;
+	if libraries_count != scopes.library_ids.len() as i64 {
+		return Err(APIError::BadRequest(String::from(
+			"Some library IDs were not found",
+		)));
+	}
+
+	let series_count = client
+		.series()
+		.count(vec![
+			series::id::in_vec(scopes.series_ids.clone()),
+			series_not_hidden_from_user_filter(user),
+		])
+		.exec()
+		.await?;
+	if series_count != scopes.series_ids.len() as i64 {
+		return Err(APIError::BadRequest(String::from(
+			"Some series IDs were not found",
+		)));
+	}
+
+	serde_json::to_vec(scopes).map(Some).map_err(|e| {
+		tracing::error!(?e, "Failed to serialize scopes");
+		APIError::BadRequest("Invalid scopes requested".to_string())
+	})
+}
+
 /// Create a new API key for the current user
 async fn create_api_key(
 	State(ctx): State<AppState>,
@@ -161,8 +224,10 @@ async fn create_api_key(
 		APIError::BadRequest("Invalid permissions requested".to_string())
 	})?;
 
+	let scopes = validate_api_key_scopes(client, user, &body.scopes).await?;
+
 	let (pek, hash) = APIKey::create_prefixed_key()?;
-	let _api_key = client
+	let created_api_key = client
 		.api_key()
 		.create(
 			body.name,
@@ -170,10 +235,21 @@ async fn create_api_key(
 			hash,
 			permissions,
 			user::id::equals(user.id.clone()),
-			vec![api_key::expires_at::set(body.expires_at)],
+			vec![
+				api_key::scopes::set(scopes),
+				api_key::expires_at::set(body.expires_at),
+			],
 		)
 		.exec()
 		.await?;
+	let created_api_key = APIKey::try_from(created_api_key)?;
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::APIKeyCreated, Some(user))
+			.with_target(created_api_key.id, &created_api_key.name)
+			.with_changes(None, Some(&created_api_key)),
+	)
+	.await;
 
 	Ok(Json(CreatedAPIKey {
 		api_key: pek.to_string(),
@@ -213,6 +289,8 @@ async fn update_api_key(
 		APIError::BadRequest("Invalid permissions requested".to_string())
 	})?;
 
+	let scopes = validate_api_key_scopes(client, user, &body.scopes).await?;
+
 	let updated_api_key = client
 		.api_key()
 		.update(
@@ -220,13 +298,22 @@ async fn update_api_key(
 			vec![
 				api_key::name::set(body.name),
 				api_key::permissions::set(permissions),
+				api_key::scopes::set(scopes),
 				api_key::expires_at::set(body.expires_at),
 			],
 		)
 		.exec()
 		.await?;
+	let updated_api_key = APIKey::try_from(updated_api_key)?;
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::APIKeyUpdated, Some(user))
+			.with_target(updated_api_key.id, &updated_api_key.name)
+			.with_changes(Some(&APIKey::try_from(api_key)?), Some(&updated_api_key)),
+	)
+	.await;
 
-	Ok(Json(APIKey::try_from(updated_api_key)?))
+	Ok(Json(updated_api_key))
 }
 
 /// Delete an existing API key for the current user
@@ -254,6 +341,12 @@ async fn delete_api_key(
 		.exec()
 		.await?;
 
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::APIKeyDeleted, Some(user))
+			.with_target(api_key.id, &api_key.name),
+	)
+	.await;
+
 	Ok(())
 }
 
@@ -290,6 +383,12 @@ async fn regenerate_api_key_secret(
 		.exec()
 		.await?;
 
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::APIKeySecretRegenerated, Some(user))
+			.with_target(api_key.id, &api_key.name),
+	)
+	.await;
+
 	Ok(Json(CreatedAPIKey {
 		api_key: pek.to_string(),
 	}))
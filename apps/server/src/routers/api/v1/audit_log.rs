@@ -0,0 +1,139 @@
+use axum::{extract::State, middleware, routing::get, Extension, Json, Router};
+use serde_qs::axum::QsQuery;
+use stump_core::{
+	db::{
+		entity::AuditLog,
+		query::{
+			ordering::QueryOrder,
+			pagination::{Pageable, Pagination, PaginationQuery},
+		},
+	},
+	prisma::audit_log::{self, OrderByParam as AuditLogOrderByParam, WhereParam},
+};
+
+use crate::{
+	config::state::AppState,
+	errors::APIResult,
+	filter::{chain_optional_iter, AuditLogFilter},
+	middleware::auth::{auth_middleware, RequestContext},
+};
+
+// Note: The audit log is append-only, so there are intentionally no routes to modify or delete
+// entries. Old entries are removed according to the configured retention period
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/audit-logs", get(get_audit_logs))
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+pub(crate) fn apply_audit_log_filters(filters: AuditLogFilter) -> Vec<WhereParam> {
+	chain_optional_iter(
+		[],
+		[
+			filters
+				.action
+				.map(|action| audit_log::action::equals(action.to_string())),
+			filters
+				.actor_id
+				.map(|actor_id| audit_log::actor_id::equals(Some(actor_id))),
+			filters.target_type.map(|target_type| {
+				audit_log::target_type::equals(target_type.to_string())
+			}),
+			filters
+				.target_id
+				.map(|target_id| audit_log::target_id::equals(Some(target_id))),
+			filters.since.map(audit_log::timestamp::gte),
+			filters.until.map(audit_log::timestamp::lt),
+		],
+	)
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/audit-logs",
+	tag = "audit-log",
+	params(
+		("filters" = Option<AuditLogFilter>, Query, description = "The filters to apply to the audit log"),
+		("order" = Option<QueryOrder>, Query, description = "The order to return the entries in"),
+		("pagination" = Option<PaginationQuery>, Query, description = "The pagination options"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched audit log entries"),
+		(status = 401, description = "Unauthorized."),
+		(status = 403, description = "Forbidden."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get the entries of the audit log. Only the server owner may view the audit log
+async fn get_audit_logs(
+	State(ctx): State<AppState>,
+	filters: QsQuery<AuditLogFilter>,
+	order: QsQuery<QueryOrder>,
+	pagination: QsQuery<PaginationQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<AuditLog>>>> {
+	req.enforce_server_owner()?;
+
+	let pagination = pagination.0.get();
+	let order = order.0;
+	tracing::trace!(?pagination, ?order, "get_audit_logs");
+
+	let db = &ctx.db;
+	let is_unpaged = pagination.is_unpaged();
+	let order_by_param: AuditLogOrderByParam = order.try_into()?;
+
+	let pagination_cloned = pagination.clone();
+	let where_params = apply_audit_log_filters(filters.0);
+
+	let (entries, count) = db
+		._transaction()
+		.run(|client| async move {
+			let mut query = client
+				.audit_log()
+				.find_many(where_params.clone())
+				.order_by(order_by_param);
+
+			if !is_unpaged {
+				match pagination_cloned {
+					Pagination::Page(page_query) => {
+						let (skip, take) = page_query.get_skip_take();
+						query = query.skip(skip).take(take);
+					},
+					Pagination::Cursor(cursor_query) => {
+						if let Some(cursor) = cursor_query.cursor {
+							query = query.cursor(audit_log::id::equals(cursor)).skip(1);
+						}
+						if let Some(limit) = cursor_query.limit {
+							query = query.take(limit);
+						}
+					},
+					_ => unreachable!(),
+				}
+			}
+
+			let entries = query.exec().await?;
+
+			if is_unpaged {
+				return Ok((entries, None));
+			}
+
+			client
+				.audit_log()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (entries, Some(count)))
+		})
+		.await?;
+
+	let entries = entries
+		.into_iter()
+		.map(AuditLog::try_from)
+		.collect::<Result<Vec<_>, _>>()?;
+
+	if let Some(count) = count {
+		return Ok(Json(Pageable::from((entries, count, pagination))));
+	}
+
+	Ok(Json(Pageable::from(entries)))
+}
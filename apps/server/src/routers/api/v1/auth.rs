@@ -1,19 +1,33 @@
+use std::collections::HashSet;
+
 use axum::{
-	extract::{ConnectInfo, Query, State},
-	http::{Response, StatusCode},
+	extract::{ConnectInfo, Path, Query, State},
+	http::{header, Response, StatusCode},
 	middleware,
-	response::IntoResponse,
-	routing::{get, post},
+	response::{IntoResponse, Redirect},
+	routing::{delete, get, post},
 	Extension, Json, Router,
 };
-use axum_extra::{headers::UserAgent, TypedHeader};
+use axum_extra::{
+	headers::{Cookie, UserAgent},
+	TypedHeader,
+};
 use chrono::{DateTime, Duration, FixedOffset, Utc};
-use prisma_client_rust::Direction;
+use prisma_client_rust::{or, Direction};
+use rand::distributions::{Alphanumeric, DistString};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use stump_core::{
-	db::entity::User,
-	prisma::{session, user, user_login_activity, user_preferences, PrismaClient},
+	config::StumpConfig,
+	db::entity::{
+		decrypt_two_factor_secret, encrypt_two_factor_secret, AuditAction, AuditEvent,
+		ExternalIdentity, PermissionSet, User, UserPermission,
+	},
+	prisma::{
+		age_restriction, session, two_factor_recovery_code, user, user_external_identity,
+		user_login_activity, user_preferences, user_two_factor, PrismaClient,
+	},
+	NotifierEvent,
 };
 use tower_sessions::Session;
 use tracing::error;
@@ -22,13 +36,21 @@ use utoipa::ToSchema;
 use crate::{
 	config::{
 		jwt::{create_user_jwt, CreatedToken},
+		oidc::{
+			authorization_url, delete_flow_cookie, discover, exchange_code, http_client,
+			mapped_age_restriction, mapped_permissions, validate_id_token, IdTokenClaims,
+			OidcClientConfig, OidcFlowState, OIDC_FLOW_COOKIE,
+		},
 		session::{delete_cookie_header, SESSION_USER_KEY},
 		state::AppState,
 	},
 	errors::{api_error_message, APIError, APIResult},
 	http_server::StumpRequestInfo,
 	middleware::auth::{auth_middleware, RequestContext},
-	utils::{default_true, get_session_user, hash_password, verify_password},
+	utils::{
+		default_true, generate_recovery_codes, generate_totp_secret, get_session_user,
+		hash_password, hash_recovery_code, totp_uri, verify_password, verify_totp,
+	},
 };
 
 pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
@@ -37,12 +59,37 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 		Router::new()
 			.route(
 				"/me",
-				get(viewer)
-					.layer(middleware::from_fn_with_state(app_state, auth_middleware)),
+				get(viewer).layer(middleware::from_fn_with_state(
+					app_state.clone(),
+					auth_middleware,
+				)),
 			)
 			.route("/login", post(login))
 			.route("/logout", post(logout))
-			.route("/register", post(register)),
+			.route("/register", post(register))
+			.route("/oidc", get(oidc_status))
+			.route("/oidc/login", get(oidc_login))
+			.route("/oidc/callback", get(oidc_callback))
+			.nest(
+				"/oidc/identities",
+				Router::new()
+					.route("/", get(get_external_identities))
+					.route("/{id}", delete(delete_external_identity))
+					.layer(middleware::from_fn_with_state(
+						app_state.clone(),
+						auth_middleware,
+					)),
+			)
+			.nest(
+				"/two-factor",
+				Router::new()
+					.route("/", get(get_two_factor_status))
+					.route("/enroll", post(enroll_two_factor))
+					.route("/confirm", post(confirm_two_factor))
+					.route("/disable", post(disable_two_factor))
+					.route("/recovery-codes", post(regenerate_recovery_codes))
+					.layer(middleware::from_fn_with_state(app_state, auth_middleware)),
+			),
 	)
 }
 
@@ -79,6 +126,11 @@ pub async fn enforce_max_sessions(
 pub struct LoginOrRegisterArgs {
 	pub username: String,
 	pub password: String,
+	/// A TOTP or recovery code, which is required to log in to an account with two-factor
+	/// authentication enabled
+	#[serde(default)]
+	#[specta(optional)]
+	pub two_factor_code: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Type, ToSchema)]
@@ -125,6 +177,30 @@ async fn handle_login_attempt(
 	Ok(login_activity)
 }
 
+/// Whether the user agent has never been used for a successful login by the user. A user's
+/// very first login is not considered to be from a new device
+async fn is_new_device_for_user(
+	client: &PrismaClient,
+	user_id: &str,
+	user_agent: &UserAgent,
+) -> APIResult<bool> {
+	let (previous_logins, previous_logins_from_device) = client
+		._batch((
+			client.user_login_activity().count(vec![
+				user_login_activity::user_id::equals(user_id.to_string()),
+				user_login_activity::authentication_successful::equals(true),
+			]),
+			client.user_login_activity().count(vec![
+				user_login_activity::user_id::equals(user_id.to_string()),
+				user_login_activity::authentication_successful::equals(true),
+				user_login_activity::user_agent::equals(user_agent.to_string()),
+			]),
+		))
+		.await?;
+
+	Ok(previous_logins > 0 && previous_logins_from_device == 0)
+}
+
 async fn handle_remove_earliest_session(
 	client: &PrismaClient,
 	for_user_id: String,
@@ -149,6 +225,180 @@ async fn handle_remove_earliest_session(
 	}
 }
 
+/// Whether the server's configuration requires a user to use two-factor authentication
+pub fn is_two_factor_required(user: &User, config: &StumpConfig) -> bool {
+	(config.two_factor_required_for_owner && user.is_server_owner)
+		|| (config.two_factor_required_for_managers
+			&& user.permissions.iter().any(|permission| {
+				matches!(
+					permission,
+					UserPermission::ManageServer | UserPermission::ManageUsers
+				)
+			}))
+}
+
+/// Whether a user has enabled two-factor authentication, i.e. has confirmed their enrollment
+pub async fn has_two_factor_enabled(
+	client: &PrismaClient,
+	user_id: &str,
+) -> APIResult<bool> {
+	let count = client
+		.user_two_factor()
+		.count(vec![
+			user_two_factor::user_id::equals(user_id.to_string()),
+			user_two_factor::confirmed_at::not(None),
+		])
+		.exec()
+		.await?;
+	Ok(count > 0)
+}
+
+async fn get_enabled_two_factor(
+	client: &PrismaClient,
+	user_id: &str,
+) -> APIResult<Option<user_two_factor::Data>> {
+	Ok(client
+		.user_two_factor()
+		.find_first(vec![
+			user_two_factor::user_id::equals(user_id.to_string()),
+			user_two_factor::confirmed_at::not(None),
+		])
+		.exec()
+		.await?)
+}
+
+/// Verify a TOTP or recovery code against a user's two-factor authentication. A TOTP code
+/// can't be used again once accepted, and a recovery code is spent
+async fn verify_two_factor_code(
+	state: &AppState,
+	two_factor: &user_two_factor::Data,
+	code: &str,
+) -> APIResult<bool> {
+	let client = &state.db;
+	let secret = decrypt_two_factor_secret(two_factor, state).await?;
+	let last_used_step = two_factor.last_used_step.map(|step| step as u64);
+
+	if let Some(step) =
+		verify_totp(&secret, code, Utc::now().timestamp() as u64, last_used_step)
+	{
+		let step = step as i32;
+		// The step is checked again while updating, so concurrent requests can't both use a code
+		let updated_count = client
+			.user_two_factor()
+			.update_many(
+				vec![
+					user_two_factor::id::equals(two_factor.id.clone()),
+					or![
+						user_two_factor::last_used_step::equals(None),
+						user_two_factor::last_used_step::lt(step),
+					],
+				],
+				vec![user_two_factor::last_used_step::set(Some(step))],
+			)
+			.exec()
+			.await?;
+		return Ok(updated_count > 0);
+	}
+
+	let used_count = client
+		.two_factor_recovery_code()
+		.update_many(
+			vec![
+				two_factor_recovery_code::user_id::equals(two_factor.user_id.clone()),
+				two_factor_recovery_code::code_hash::equals(hash_recovery_code(code)),
+				two_factor_recovery_code::used_at::equals(None),
+			],
+			vec![two_factor_recovery_code::used_at::set(Some(
+				Utc::now().into(),
+			))],
+		)
+		.exec()
+		.await?;
+	Ok(used_count > 0)
+}
+
+/// Verify the second factor of a login, once the password has been verified. A missing code
+/// is an error rather than a failed attempt, so clients know to prompt for one
+async fn verify_login_two_factor(
+	state: &AppState,
+	user_id: &str,
+	code: Option<&str>,
+) -> APIResult<bool> {
+	let Some(two_factor) = get_enabled_two_factor(&state.db, user_id).await? else {
+		return Ok(true);
+	};
+
+	match code.filter(|code| !code.trim().is_empty()) {
+		Some(code) => verify_two_factor_code(state, &two_factor, code).await,
+		None => Err(APIError::Forbidden(
+			api_error_message::TWO_FACTOR_REQUIRED.to_string(),
+		)),
+	}
+}
+
+/// The last steps of a successful login, which are shared by every way of logging in. The
+/// user must be fetched with their unexpired sessions, so the maximum number of sessions can
+/// be enforced
+async fn complete_login(
+	state: &AppState,
+	db_user: user::Data,
+	user_agent: UserAgent,
+	request_info: StumpRequestInfo,
+) -> APIResult<User> {
+	let client = &state.db;
+	let user_id = db_user.id.clone();
+
+	enforce_max_sessions(&db_user, client).await?;
+
+	let updated_user = client
+		.user()
+		.update(
+			user::id::equals(db_user.id.clone()),
+			vec![user::last_login::set(Some(Utc::now().into()))],
+		)
+		.with(user::user_preferences::fetch())
+		.with(user::age_restriction::fetch())
+		.exec()
+		.await
+		.unwrap_or_else(|err| {
+			error!(error = ?err, "Failed to update user last login!");
+			user::Data {
+				last_login: Some(Utc::now().into()),
+				..db_user
+			}
+		});
+
+	match is_new_device_for_user(client, &user_id, &user_agent).await {
+		Ok(true) => state.send_notifier_event(
+			NotifierEvent::NewDeviceLogin {
+				username: updated_user.username.clone(),
+				user_agent: user_agent.to_string(),
+				ip_address: request_info.ip_addr.to_string(),
+			},
+			None,
+		),
+		Ok(false) => {},
+		Err(err) => {
+			error!(error = ?err, "Failed to check for new device login");
+		},
+	}
+
+	let login_track_result = handle_login_attempt(
+		client,
+		updated_user.clone(),
+		user_agent,
+		request_info,
+		true,
+	)
+	.await;
+	// I don't want to kill the login here, so not bubbling up the error
+	if let Err(err) = login_track_result {
+		error!(error = ?err, "Failed to track login attempt!");
+	}
+
+	Ok(User::from(updated_user))
+}
+
 #[derive(Debug, Serialize, Type, ToSchema)]
 #[serde(untagged)]
 pub enum LoginResponse {
@@ -163,7 +413,8 @@ pub enum LoginResponse {
 	request_body = LoginOrRegisterArgs,
 	responses(
 		(status = 200, description = "Authenticates the user and returns the user object.", body = User),
-		(status = 401, description = "Invalid username or password."),
+		(status = 401, description = "Invalid username, password or two-factor code."),
+		(status = 403, description = "A two-factor code is required to log in, or the account is locked."),
 		(status = 500, description = "An internal server error occurred.")
 	)
 )]
@@ -235,7 +486,15 @@ async fn login(
 		},
 		Some(db_user) if !db_user.is_locked => {
 			let user_id = db_user.id.clone();
-			let matches = verify_password(&db_user.hashed_password, &input.password)?;
+			let mut matches = verify_password(&db_user.hashed_password, &input.password)?;
+			if matches {
+				matches = verify_login_two_factor(
+					&state,
+					&user_id,
+					input.two_factor_code.as_deref(),
+				)
+				.await?;
+			}
 			if !matches {
 				// TODO: make this configurable via environment variable so knowledgeable attackers can't bypass this
 				let should_lock_account = db_user
@@ -254,7 +513,7 @@ async fn login(
 					.await?;
 
 				if should_lock_account {
-					let _locked_user = client
+					let locked_user = client
 						.user()
 						.update(
 							user::id::equals(user_id.clone()),
@@ -272,47 +531,21 @@ async fn login(
 						?removed_sessions_count,
 						?user_id,
 						"Locked user account and removed all associated sessions"
-					)
+					);
+
+					// The account is locked automatically, so there is no actor
+					state
+						.record_audit_event(
+							AuditEvent::new(AuditAction::UserLocked, None)
+								.with_target(&locked_user.id, &locked_user.username),
+						)
+						.await;
 				}
 
 				return Err(APIError::Unauthorized);
 			}
 
-			enforce_max_sessions(&db_user, &client).await?;
-
-			let updated_user = state
-				.db
-				.user()
-				.update(
-					user::id::equals(db_user.id.clone()),
-					vec![user::last_login::set(Some(Utc::now().into()))],
-				)
-				.with(user::user_preferences::fetch())
-				.with(user::age_restriction::fetch())
-				.exec()
-				.await
-				.unwrap_or_else(|err| {
-					error!(error = ?err, "Failed to update user last login!");
-					user::Data {
-						last_login: Some(Utc::now().into()),
-						..db_user
-					}
-				});
-
-			let login_track_result = handle_login_attempt(
-				&state.db,
-				updated_user.clone(),
-				user_agent,
-				request_info,
-				true,
-			)
-			.await;
-			// I don't want to kill the login here, so not bubbling up the error
-			if let Err(err) = login_track_result {
-				error!(error = ?err, "Failed to track login attempt!");
-			}
-
-			let user = User::from(updated_user);
+			let user = complete_login(&state, db_user, user_agent, request_info).await?;
 
 			if create_session {
 				session.insert(SESSION_USER_KEY, user.clone()).await?;
@@ -441,5 +674,803 @@ pub async fn register(
 			"Failed to fetch user after registration.".to_string(),
 		))?;
 
+	ctx.send_notifier_event(
+		NotifierEvent::UserRegistered {
+			username: user.username.clone(),
+		},
+		None,
+	);
+
 	Ok(Json(user.into()))
 }
+
+#[derive(Debug, Serialize, Type, ToSchema)]
+pub struct OidcStatus {
+	/// Whether logging in with an OpenID Connect provider is configured
+	enabled: bool,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/auth/oidc",
+	tag = "auth",
+	responses(
+		(status = 200, description = "Returns whether single sign-on is enabled.", body = OidcStatus)
+	)
+)]
+/// Returns whether logging in with an OpenID Connect provider is enabled, so clients know
+/// whether to offer it
+async fn oidc_status(State(ctx): State<AppState>) -> Json<OidcStatus> {
+	Json(OidcStatus {
+		enabled: OidcClientConfig::from_config(&ctx.config).is_some(),
+	})
+}
+
+fn oidc_client_config(ctx: &AppState) -> APIResult<OidcClientConfig> {
+	OidcClientConfig::from_config(&ctx.config)
+		.ok_or_else(|| APIError::NotFound("Single sign-on is not configured".to_string()))
+}
+
+/// Whether a redirect is to a path on this server, rather than to another site
+fn is_local_redirect(redirect: &str) -> bool {
+	redirect.starts_with('/') && !redirect.starts_with("//") && !redirect.contains('\\')
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcLoginParams {
+	/// Whether to link the identity to the logged in user, rather than logging in with it
+	#[serde(default)]
+	link: bool,
+	/// The path to send the user to once they are logged in
+	redirect: Option<String>,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/auth/oidc/login",
+	tag = "auth",
+	params(
+		("link" = Option<bool>, Query, description = "Whether to link the identity to the logged in user"),
+		("redirect" = Option<String>, Query, description = "The path to send the user to once they are logged in")
+	),
+	responses(
+		(status = 303, description = "Redirects to the OpenID Connect provider."),
+		(status = 401, description = "No user is logged in to link the identity to."),
+		(status = 404, description = "Single sign-on is not configured."),
+		(status = 502, description = "The OpenID Connect provider could not be reached.")
+	)
+)]
+/// Starts a login with the OpenID Connect provider by redirecting to it. When `link` is set,
+/// the identity the user logs in with at the provider is linked to the logged in user instead
+async fn oidc_login(
+	session: Session,
+	State(ctx): State<AppState>,
+	Query(params): Query<OidcLoginParams>,
+) -> APIResult<impl IntoResponse> {
+	let config = oidc_client_config(&ctx)?;
+
+	if params.link && get_session_user(&session).await?.is_none() {
+		return Err(APIError::Unauthorized);
+	}
+
+	let redirect = params
+		.redirect
+		.filter(|redirect| is_local_redirect(redirect));
+	let flow = OidcFlowState::new(params.link, redirect);
+
+	let http = http_client();
+	let metadata = discover(http, &config.issuer_url).await?;
+	let url = authorization_url(&metadata, &config, &flow)?;
+
+	Ok((
+		[(header::SET_COOKIE, flow.to_cookie()?)],
+		Redirect::to(&url),
+	))
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcCallbackParams {
+	code: Option<String>,
+	state: Option<String>,
+	error: Option<String>,
+	error_description: Option<String>,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/auth/oidc/callback",
+	tag = "auth",
+	responses(
+		(status = 303, description = "Logs the user in and redirects them."),
+		(status = 400, description = "The login is invalid or has expired."),
+		(status = 401, description = "The ID token is invalid."),
+		(status = 403, description = "No account is linked to the identity, or the account is locked."),
+		(status = 409, description = "The identity is already linked to another account.")
+	)
+)]
+/// The endpoint the OpenID Connect provider redirects back to after a login. The ID token is
+/// validated, and the user linked to the identity is logged in. Users are created for new
+/// identities when auto provisioning is enabled.
+async fn oidc_callback(
+	TypedHeader(user_agent): TypedHeader<UserAgent>,
+	ConnectInfo(request_info): ConnectInfo<StumpRequestInfo>,
+	cookies: Option<TypedHeader<Cookie>>,
+	session: Session,
+	State(ctx): State<AppState>,
+	Query(params): Query<OidcCallbackParams>,
+) -> APIResult<impl IntoResponse> {
+	let config = oidc_client_config(&ctx)?;
+	let flow = cookies
+		.as_ref()
+		.and_then(|TypedHeader(cookies)| cookies.get(OIDC_FLOW_COOKIE))
+		.ok_or_else(|| APIError::BadRequest("No login is in progress".to_string()))
+		.and_then(OidcFlowState::from_cookie)?;
+
+	if let Some(error) = params.error {
+		tracing::error!(
+			error,
+			description = ?params.error_description,
+			"The OpenID Connect provider returned an error"
+		);
+		return Err(APIError::BadRequest(format!(
+			"The OpenID Connect provider returned an error: {error}"
+		)));
+	}
+
+	let (Some(code), Some(state)) = (params.code, params.state) else {
+		return Err(APIError::BadRequest(
+			"The callback is missing the code or state".to_string(),
+		));
+	};
+	if state != flow.state {
+		return Err(APIError::BadRequest(
+			"The state does not match the login in progress".to_string(),
+		));
+	}
+
+	let http = http_client();
+	let metadata = discover(http, &config.issuer_url).await?;
+	let id_token =
+		exchange_code(http, &metadata, &config, &code, &flow.code_verifier).await?;
+	let claims =
+		validate_id_token(http, &metadata, &config, &id_token, &flow.nonce).await?;
+
+	let client = &ctx.db;
+	let identity = client
+		.user_external_identity()
+		.find_unique(user_external_identity::issuer_subject(
+			claims.iss.clone(),
+			claims.sub.clone(),
+		))
+		.exec()
+		.await?;
+
+	// The user the identity is being newly linked to, which is audited once the link is made
+	let mut linking_user = None;
+	let user_id = match (identity, flow.link) {
+		(existing, true) => {
+			// Only a logged in user can link an identity to their account
+			let session_user = get_session_user(&session)
+				.await?
+				.ok_or(APIError::Unauthorized)?;
+			if existing
+				.as_ref()
+				.is_some_and(|identity| identity.user_id != session_user.id)
+			{
+				return Err(APIError::Conflict(
+					"This identity is already linked to another account".to_string(),
+				));
+			}
+			let user_id = session_user.id.clone();
+			if existing.is_none() {
+				linking_user = Some(session_user);
+			}
+			user_id
+		},
+		(Some(identity), false) => identity.user_id,
+		(None, false) if ctx.config.oidc_auto_provision => {
+			provision_oidc_user(&ctx, &claims).await?
+		},
+		(None, false) => {
+			return Err(APIError::Forbidden(
+				"No account is linked to this identity".to_string(),
+			))
+		},
+	};
+
+	let now: DateTime<FixedOffset> = Utc::now().into();
+	client
+		.user_external_identity()
+		.upsert(
+			user_external_identity::issuer_subject(
+				claims.iss.clone(),
+				claims.sub.clone(),
+			),
+			(
+				claims.iss.clone(),
+				claims.sub.clone(),
+				user::id::equals(user_id.clone()),
+				vec![
+					user_external_identity::email::set(claims.email.clone()),
+					user_external_identity::last_login_at::set(Some(now)),
+				],
+			),
+			vec![
+				user_external_identity::email::set(claims.email.clone()),
+				user_external_identity::last_login_at::set(Some(now)),
+			],
+		)
+		.exec()
+		.await?;
+
+	let redirect = flow.redirect.unwrap_or_else(|| "/".to_string());
+	let delete_flow_cookie = [(header::SET_COOKIE, delete_flow_cookie())];
+
+	if flow.link {
+		if let Some(user) = linking_user {
+			let linked = serde_json::json!({ "external_identity": claims.iss });
+			ctx.record_audit_event(
+				AuditEvent::new(AuditAction::UserUpdated, Some(&user))
+					.with_target(&user.id, &user.username)
+					.with_changes(None, Some(&linked)),
+			)
+			.await;
+		}
+		return Ok((delete_flow_cookie, Redirect::to(&redirect)));
+	}
+
+	let existing_user = client
+		.user()
+		.find_first(vec![
+			user::id::equals(user_id.clone()),
+			user::deleted_at::equals(None),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::Unauthorized)?;
+	if existing_user.is_locked {
+		return Err(APIError::Forbidden(
+			api_error_message::LOCKED_ACCOUNT.to_string(),
+		));
+	}
+	// The server owner always has every permission, so their account is left alone
+	if !existing_user.is_server_owner {
+		sync_oidc_claim_mappings(client, &user_id, &claims, &ctx.config).await?;
+	}
+
+	let db_user = client
+		.user()
+		.find_unique(user::id::equals(user_id))
+		.with(user::user_preferences::fetch())
+		.with(user::age_restriction::fetch())
+		.with(user::sessions::fetch(vec![session::expiry_time::gt(
+			Utc::now().into(),
+		)]))
+		.exec()
+		.await?
+		.ok_or(APIError::Unauthorized)?;
+
+	let user = complete_login(&ctx, db_user, user_agent, request_info).await?;
+	session.insert(SESSION_USER_KEY, user).await?;
+
+	Ok((delete_flow_cookie, Redirect::to(&redirect)))
+}
+
+/// Create a user for an identity which is logging in for the first time. The username is
+/// taken from the claims of the identity, with a number added if it is already taken
+async fn provision_oidc_user(
+	ctx: &AppState,
+	claims: &IdTokenClaims,
+) -> APIResult<String> {
+	let client = &ctx.db;
+
+	let preferred_username = [
+		claims.preferred_username.as_deref(),
+		claims.email.as_deref(),
+		claims.name.as_deref(),
+	]
+	.into_iter()
+	.flatten()
+	.map(str::trim)
+	.find(|username| !username.is_empty())
+	.unwrap_or(claims.sub.as_str())
+	.to_string();
+
+	let taken_usernames = client
+		.user()
+		.find_many(vec![user::username::starts_with(
+			preferred_username.clone(),
+		)])
+		.exec()
+		.await?
+		.into_iter()
+		.map(|user| user.username)
+		.collect::<HashSet<_>>();
+	let mut username = preferred_username.clone();
+	let mut suffix = 1;
+	while taken_usernames.contains(&username) {
+		suffix += 1;
+		username = format!("{preferred_username}-{suffix}");
+	}
+
+	// The user can only log in with the provider, unless a password is set for them later
+	let password = Alphanumeric.sample_string(&mut rand::thread_rng(), 32);
+	let hashed_password = hash_password(&password, &ctx.config)?;
+
+	let created_user = client
+		.user()
+		.create(username, hashed_password, vec![])
+		.exec()
+		.await?;
+
+	// TODO(prisma-nested-create): Refactor once nested create is supported
+	let _user_preferences = client
+		.user_preferences()
+		.create(vec![
+			user_preferences::user::connect(user::id::equals(created_user.id.clone())),
+			user_preferences::user_id::set(Some(created_user.id.clone())),
+		])
+		.exec()
+		.await?;
+
+	tracing::debug!(
+		username = created_user.username,
+		"Provisioned a user for an OpenID Connect identity"
+	);
+	// The user is created by the login itself, so there is no actor
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserCreated, None)
+			.with_target(&created_user.id, &created_user.username),
+	)
+	.await;
+	ctx.send_notifier_event(
+		NotifierEvent::UserRegistered {
+			username: created_user.username,
+		},
+		None,
+	);
+
+	Ok(created_user.id)
+}
+
+/// Update the permissions and age restriction of a user from the claims of their ID token,
+/// for whichever mappings are configured. Anything the token has no valid claim for is left
+/// as it is
+async fn sync_oidc_claim_mappings(
+	client: &PrismaClient,
+	user_id: &str,
+	claims: &IdTokenClaims,
+	config: &StumpConfig,
+) -> APIResult<()> {
+	if let Some(permissions) = mapped_permissions(claims, config) {
+		client
+			.user()
+			.update(
+				user::id::equals(user_id.to_string()),
+				vec![user::permissions::set(
+					PermissionSet::new(permissions).resolve_into_string(),
+				)],
+			)
+			.exec()
+			.await?;
+	}
+
+	if let Some(age) = mapped_age_restriction(claims, config) {
+		client
+			.age_restriction()
+			.upsert(
+				age_restriction::user_id::equals(user_id.to_string()),
+				(age, user::id::equals(user_id.to_string()), vec![]),
+				vec![age_restriction::age::set(age)],
+			)
+			.exec()
+			.await?;
+	}
+
+	Ok(())
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/auth/oidc/identities",
+	tag = "auth",
+	responses(
+		(status = 200, description = "Returns the identities linked to the user.", body = [ExternalIdentity]),
+		(status = 401, description = "No user is logged in (unauthorized).")
+	)
+)]
+/// Returns the OpenID Connect identities which are linked to the logged in user
+async fn get_external_identities(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<ExternalIdentity>>> {
+	let identities = ctx
+		.db
+		.user_external_identity()
+		.find_many(vec![user_external_identity::user_id::equals(req.id())])
+		.order_by(user_external_identity::created_at::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	Ok(Json(
+		identities.into_iter().map(ExternalIdentity::from).collect(),
+	))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/auth/oidc/identities/{id}",
+	tag = "auth",
+	params(
+		("id" = String, Path, description = "The ID of the identity to unlink")
+	),
+	responses(
+		(status = 200, description = "Unlinks the identity from the user.", body = ExternalIdentity),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 404, description = "The identity is not linked to the user.")
+	)
+)]
+/// Unlinks an OpenID Connect identity from the logged in user, so it can no longer be used to
+/// log in as them
+async fn delete_external_identity(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Path(id): Path<String>,
+) -> APIResult<Json<ExternalIdentity>> {
+	let client = &ctx.db;
+
+	let identity = client
+		.user_external_identity()
+		.find_first(vec![
+			user_external_identity::id::equals(id.clone()),
+			user_external_identity::user_id::equals(req.id()),
+		])
+		.exec()
+		.await?
+		.ok_or_else(|| APIError::NotFound("Identity not found".to_string()))?;
+
+	client
+		.user_external_identity()
+		.delete(user_external_identity::id::equals(identity.id.clone()))
+		.exec()
+		.await?;
+
+	let user = req.user();
+	let unlinked = serde_json::json!({ "external_identity": identity.issuer });
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserUpdated, Some(user))
+			.with_target(&user.id, &user.username)
+			.with_changes(Some(&unlinked), None),
+	)
+	.await;
+
+	Ok(Json(ExternalIdentity::from(identity)))
+}
+
+#[derive(Debug, Serialize, Type, ToSchema)]
+pub struct TwoFactorStatus {
+	/// Whether the user has enabled two-factor authentication
+	enabled: bool,
+	/// Whether the server requires the user to enable two-factor authentication
+	required: bool,
+	/// The number of unused recovery codes the user has left
+	recovery_codes_remaining: i64,
+}
+
+/// A started enrollment in two-factor authentication, which is enabled once a code generated
+/// from the secret is confirmed
+#[derive(Debug, Serialize, Type, ToSchema)]
+pub struct TwoFactorEnrollment {
+	/// The base32 encoded TOTP secret, for entering into an authenticator app by hand
+	secret: String,
+	/// The `otpauth://` URI of the secret, which is typically shown as a QR code
+	otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize, Type, ToSchema)]
+pub struct TwoFactorCode {
+	/// A code from the user's authenticator app, or one of their recovery codes
+	code: String,
+}
+
+/// Newly generated recovery codes, which can each be used once in place of a code from an
+/// authenticator app. They are not stored in plain text, so they can only be shown once
+#[derive(Debug, Serialize, Type, ToSchema)]
+pub struct TwoFactorRecoveryCodes {
+	recovery_codes: Vec<String>,
+}
+
+/// Replace a user's recovery codes with newly generated ones
+async fn replace_recovery_codes(
+	client: &PrismaClient,
+	user_id: String,
+) -> APIResult<Vec<String>> {
+	let recovery_codes = generate_recovery_codes();
+	let code_hashes = recovery_codes
+		.iter()
+		.map(|code| hash_recovery_code(code))
+		.collect::<Vec<_>>();
+
+	client
+		._transaction()
+		.run(|tx| async move {
+			tx.two_factor_recovery_code()
+				.delete_many(vec![two_factor_recovery_code::user_id::equals(
+					user_id.clone(),
+				)])
+				.exec()
+				.await?;
+
+			let creates = code_hashes
+				.into_iter()
+				.map(|code_hash| {
+					tx.two_factor_recovery_code().create(
+						code_hash,
+						user::id::equals(user_id.clone()),
+						vec![],
+					)
+				})
+				.collect::<Vec<_>>();
+
+			tx._batch(creates).await
+		})
+		.await?;
+
+	Ok(recovery_codes)
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/auth/two-factor",
+	tag = "auth",
+	responses(
+		(status = 200, description = "Returns the two-factor authentication status of the user.", body = TwoFactorStatus),
+		(status = 401, description = "No user is logged in (unauthorized).")
+	)
+)]
+/// Returns whether the logged in user has enabled two-factor authentication, and whether the
+/// server requires them to
+async fn get_two_factor_status(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<TwoFactorStatus>> {
+	let client = &ctx.db;
+	let user = req.user();
+
+	let (enabled, recovery_codes_remaining) = client
+		._batch((
+			client.user_two_factor().count(vec![
+				user_two_factor::user_id::equals(user.id.clone()),
+				user_two_factor::confirmed_at::not(None),
+			]),
+			client.two_factor_recovery_code().count(vec![
+				two_factor_recovery_code::user_id::equals(user.id.clone()),
+				two_factor_recovery_code::used_at::equals(None),
+			]),
+		))
+		.await?;
+
+	Ok(Json(TwoFactorStatus {
+		enabled: enabled > 0,
+		required: is_two_factor_required(user, &ctx.config),
+		recovery_codes_remaining,
+	}))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/auth/two-factor/enroll",
+	tag = "auth",
+	responses(
+		(status = 200, description = "Starts enrolling the user in two-factor authentication.", body = TwoFactorEnrollment),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 409, description = "Two-factor authentication is already enabled.")
+	)
+)]
+/// Starts enrolling the logged in user in two-factor authentication by generating a new
+/// secret. Two-factor authentication is not enabled until a code is confirmed, and starting
+/// again replaces the secret of an unconfirmed enrollment
+async fn enroll_two_factor(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<TwoFactorEnrollment>> {
+	let client = &ctx.db;
+	let user = req.user();
+
+	if has_two_factor_enabled(client, &user.id).await? {
+		return Err(APIError::Conflict(
+			"Two-factor authentication is already enabled".to_string(),
+		));
+	}
+
+	let secret = generate_totp_secret();
+	let encrypted_secret = encrypt_two_factor_secret(&secret, &ctx).await?;
+
+	client
+		.user_two_factor()
+		.upsert(
+			user_two_factor::user_id::equals(user.id.clone()),
+			user_two_factor::create(
+				encrypted_secret.clone(),
+				user::id::equals(user.id.clone()),
+				vec![],
+			),
+			vec![
+				user_two_factor::secret::set(encrypted_secret),
+				user_two_factor::confirmed_at::set(None),
+				user_two_factor::last_used_step::set(None),
+			],
+		)
+		.exec()
+		.await?;
+
+	Ok(Json(TwoFactorEnrollment {
+		otpauth_uri: totp_uri(&secret, &user.username),
+		secret,
+	}))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/auth/two-factor/confirm",
+	tag = "auth",
+	request_body = TwoFactorCode,
+	responses(
+		(status = 200, description = "Enables two-factor authentication for the user.", body = TwoFactorRecoveryCodes),
+		(status = 400, description = "The code is invalid, or no enrollment was started."),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 409, description = "Two-factor authentication is already enabled.")
+	)
+)]
+/// Confirms the enrollment of the logged in user with a code from their authenticator app,
+/// which enables two-factor authentication. Returns the user's recovery codes
+async fn confirm_two_factor(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<TwoFactorCode>,
+) -> APIResult<Json<TwoFactorRecoveryCodes>> {
+	let client = &ctx.db;
+
+	let two_factor = client
+		.user_two_factor()
+		.find_unique(user_two_factor::user_id::equals(req.id()))
+		.exec()
+		.await?
+		.ok_or_else(|| {
+			APIError::BadRequest(
+				"Enrollment in two-factor authentication has not been started"
+					.to_string(),
+			)
+		})?;
+
+	if two_factor.confirmed_at.is_some() {
+		return Err(APIError::Conflict(
+			"Two-factor authentication is already enabled".to_string(),
+		));
+	}
+
+	let secret = decrypt_two_factor_secret(&two_factor, &ctx).await?;
+	let Some(step) =
+		verify_totp(&secret, &input.code, Utc::now().timestamp() as u64, None)
+	else {
+		return Err(APIError::BadRequest(
+			api_error_message::INVALID_TWO_FACTOR_CODE.to_string(),
+		));
+	};
+
+	client
+		.user_two_factor()
+		.update(
+			user_two_factor::id::equals(two_factor.id),
+			vec![
+				user_two_factor::confirmed_at::set(Some(Utc::now().into())),
+				user_two_factor::last_used_step::set(Some(step as i32)),
+			],
+		)
+		.exec()
+		.await?;
+
+	let recovery_codes = replace_recovery_codes(client, req.id()).await?;
+
+	Ok(Json(TwoFactorRecoveryCodes { recovery_codes }))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/auth/two-factor/recovery-codes",
+	tag = "auth",
+	request_body = TwoFactorCode,
+	responses(
+		(status = 200, description = "Replaces the recovery codes of the user.", body = TwoFactorRecoveryCodes),
+		(status = 400, description = "The code is invalid, or two-factor authentication is not enabled."),
+		(status = 401, description = "No user is logged in (unauthorized).")
+	)
+)]
+/// Replaces the recovery codes of the logged in user with newly generated ones, which
+/// requires a valid code
+async fn regenerate_recovery_codes(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<TwoFactorCode>,
+) -> APIResult<Json<TwoFactorRecoveryCodes>> {
+	let client = &ctx.db;
+
+	let two_factor = get_enabled_two_factor(client, &req.id())
+		.await?
+		.ok_or_else(|| {
+			APIError::BadRequest("Two-factor authentication is not enabled".to_string())
+		})?;
+
+	if !verify_two_factor_code(&ctx, &two_factor, &input.code).await? {
+		return Err(APIError::BadRequest(
+			api_error_message::INVALID_TWO_FACTOR_CODE.to_string(),
+		));
+	}
+
+	let recovery_codes = replace_recovery_codes(client, req.id()).await?;
+
+	Ok(Json(TwoFactorRecoveryCodes { recovery_codes }))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/auth/two-factor/disable",
+	tag = "auth",
+	request_body = TwoFactorCode,
+	responses(
+		(status = 200, description = "Disables two-factor authentication for the user."),
+		(status = 400, description = "The code is invalid, or two-factor authentication is not enabled."),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "The server requires the user to use two-factor authentication.")
+	)
+)]
+/// Disables two-factor authentication for the logged in user, which requires a valid code.
+/// This is not allowed when the server requires the user to use two-factor authentication
+async fn disable_two_factor(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<TwoFactorCode>,
+) -> APIResult<()> {
+	let client = &ctx.db;
+	let user = req.user();
+
+	if is_two_factor_required(user, &ctx.config) {
+		return Err(APIError::Forbidden(
+			"Two-factor authentication is required for your account".to_string(),
+		));
+	}
+
+	let two_factor =
+		get_enabled_two_factor(client, &user.id)
+			.await?
+			.ok_or_else(|| {
+				APIError::BadRequest(
+					"Two-factor authentication is not enabled".to_string(),
+				)
+			})?;
+
+	if !verify_two_factor_code(&ctx, &two_factor, &input.code).await? {
+		return Err(APIError::BadRequest(
+			api_error_message::INVALID_TWO_FACTOR_CODE.to_string(),
+		));
+	}
+
+	client
+		._batch((
+			client
+				.user_two_factor()
+				.delete_many(vec![user_two_factor::user_id::equals(user.id.clone())]),
+			client.two_factor_recovery_code().delete_many(vec![
+				two_factor_recovery_code::user_id::equals(user.id.clone()),
+			]),
+		))
+		.await?;
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserTwoFactorReset, Some(user))
+			.with_target(&user.id, &user.username),
+	)
+	.await;
+
+	Ok(())
+}
@@ -27,6 +27,7 @@ use stump_core::{
 		book_club, book_club_book, book_club_invitation, book_club_member,
 		book_club_schedule, media, user, PrismaClient,
 	},
+	NotifierEvent,
 };
 use utoipa::ToSchema;
 
@@ -824,6 +825,7 @@ async fn create_book_club_schedule(
 		.await?
 		.ok_or(APIError::NotFound("Book club not found".to_string()))?;
 
+	let book_club_name = book_club.name.clone();
 	let interval_days = payload.default_interval_days.unwrap_or(30);
 	let books_to_create = payload.books;
 
@@ -899,6 +901,11 @@ async fn create_book_club_schedule(
 		})
 		.await?;
 
+	ctx.send_notifier_event(
+		NotifierEvent::BookClubScheduleChanged { book_club_name },
+		None,
+	);
+
 	Ok(Json(BookClubSchedule::from(result)))
 }
 
@@ -1071,6 +1078,13 @@ async fn add_books_to_book_club_schedule(
 
 	let created_books = client._batch(create_many_query).await?;
 
+	ctx.send_notifier_event(
+		NotifierEvent::BookClubScheduleChanged {
+			book_club_name: book_club.name,
+		},
+		None,
+	);
+
 	Ok(Json(
 		created_books.into_iter().map(BookClubBook::from).collect(),
 	))
@@ -0,0 +1,651 @@
+use std::collections::HashSet;
+
+use axum::{
+	extract::{DefaultBodyLimit, Multipart, Path, State},
+	middleware,
+	routing::{get, put},
+	Extension, Json, Router,
+};
+use chrono::Utc;
+use prisma_client_rust::Direction;
+use serde::Deserialize;
+use specta::Type;
+use stump_core::{
+	config::StumpConfig,
+	db::entity::{
+		Collection, CollectionItemKind, EntityVisibility, User, UserPermission,
+	},
+	filesystem::{
+		get_thumbnail,
+		image::{place_thumbnail, remove_thumbnails},
+	},
+	prisma::{collection, collection_item, media, series, user, PrismaClient},
+};
+use tokio::fs;
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::{
+		filters::{
+			apply_media_restrictions_for_user, collection_item_accessible_to_user_filter,
+			collection_visible_to_user_filter,
+		},
+		v1::media::thumbnails::get_media_thumbnail_by_id,
+	},
+	utils::{http::ImageResponse, validate_and_load_image},
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/collections", get(get_collections).post(create_collection))
+		.nest(
+			"/collections/{id}",
+			Router::new()
+				.route(
+					"/",
+					get(get_collection_by_id)
+						.put(update_collection)
+						.delete(delete_collection),
+				)
+				.route("/items", put(set_collection_items))
+				.route(
+					"/thumbnail",
+					get(get_collection_thumbnail_handler)
+						.post(replace_collection_thumbnail)
+						.delete(delete_collection_thumbnail)
+						.layer(DefaultBodyLimit::max(
+							app_state.config.max_image_upload_size,
+						)),
+				),
+		)
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct CreateOrUpdateCollection {
+	/// The name of the collection. Must be unique across the server
+	pub name: String,
+	/// The description of the collection
+	#[serde(default)]
+	pub description: Option<String>,
+	/// Who the collection is visible to. Defaults to `PUBLIC`
+	#[serde(default = "default_collection_visibility")]
+	pub visibility: EntityVisibility,
+	/// The IDs of the users a `SHARED` collection is visible to. Ignored for other
+	/// visibilities
+	#[serde(default)]
+	pub shared_with_user_ids: Vec<String>,
+}
+
+fn default_collection_visibility() -> EntityVisibility {
+	EntityVisibility::Public
+}
+
+impl CreateOrUpdateCollection {
+	fn validate(&self) -> APIResult<()> {
+		if self.name.trim().is_empty() {
+			return Err(APIError::BadRequest(
+				"Collection name cannot be empty".to_string(),
+			));
+		}
+
+		Ok(())
+	}
+
+	fn shared_with_params(&self) -> Vec<user::UniqueWhereParam> {
+		if self.visibility != EntityVisibility::Shared {
+			return vec![];
+		}
+
+		self.shared_with_user_ids
+			.iter()
+			.cloned()
+			.map(user::id::equals)
+			.collect()
+	}
+}
+
+/// A single item to place in a collection
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct CollectionItemInput {
+	/// Whether the item is a book or a series
+	pub kind: CollectionItemKind,
+	/// The ID of the book or series
+	pub id: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct SetCollectionItems {
+	/// The items of the collection, in the order they should be displayed
+	pub items: Vec<CollectionItemInput>,
+}
+
+/// Fetch a collection which is visible to the user, including the items the user has
+/// access to. Returns a 404 if the collection does not exist or is not visible.
+pub(crate) async fn fetch_collection_for_user(
+	client: &PrismaClient,
+	id: &str,
+	user: &User,
+) -> APIResult<collection::Data> {
+	client
+		.collection()
+		.find_first(
+			[collection::id::equals(id.to_string())]
+				.into_iter()
+				.chain(collection_visible_to_user_filter(user))
+				.collect(),
+		)
+		.with(
+			collection::items::fetch(vec![collection_item_accessible_to_user_filter(
+				user,
+			)])
+			.order_by(collection_item::display_order::order(Direction::Asc))
+			.with(collection_item::media::fetch().with(media::metadata::fetch()))
+			.with(collection_item::series::fetch().with(series::metadata::fetch())),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Collection not found")))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/collections",
+	tag = "collection",
+	responses(
+		(status = 200, description = "Successfully fetched collections", body = [Collection]),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get all collections visible to the requesting user, ordered by name. Items are not
+/// included in the response
+async fn get_collections(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<Collection>>> {
+	let user = req.user();
+
+	let collections = ctx
+		.db
+		.collection()
+		.find_many(collection_visible_to_user_filter(user))
+		.order_by(collection::name::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	Ok(Json(
+		collections
+			.into_iter()
+			.map(Collection::try_from)
+			.collect::<Result<Vec<_>, _>>()?,
+	))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/collections",
+	tag = "collection",
+	request_body = CreateOrUpdateCollection,
+	responses(
+		(status = 200, description = "Successfully created collection", body = Collection),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Create a new, empty collection
+async fn create_collection(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateCollection>,
+) -> APIResult<Json<Collection>> {
+	req.enforce_permissions(&[UserPermission::ManageCollections])?;
+	input.validate()?;
+
+	let created_collection = ctx
+		.db
+		.collection()
+		.create(
+			input.name.trim().to_string(),
+			vec![
+				collection::description::set(input.description.clone()),
+				collection::visibility::set(input.visibility.to_string()),
+				collection::shared_with_users::connect(input.shared_with_params()),
+			],
+		)
+		.with(collection::items::fetch(vec![]))
+		.with(collection::shared_with_users::fetch(vec![]))
+		.exec()
+		.await?;
+
+	Ok(Json(Collection::try_from(created_collection)?))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/collections/{id}",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched collection", body = Collection),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Collection not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get a collection by its ID, including the items the requesting user has access to
+async fn get_collection_by_id(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Collection>> {
+	let collection = fetch_collection_for_user(&ctx.db, &id, req.user()).await?;
+	Ok(Json(Collection::try_from(collection)?))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/collections/{id}",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection")
+	),
+	request_body = CreateOrUpdateCollection,
+	responses(
+		(status = 200, description = "Successfully updated collection", body = Collection),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Collection not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Update the details of a collection. The items of the collection are not affected
+async fn update_collection(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateCollection>,
+) -> APIResult<Json<Collection>> {
+	req.enforce_permissions(&[UserPermission::ManageCollections])?;
+	input.validate()?;
+
+	let client = &ctx.db;
+
+	let collection = client
+		.collection()
+		.find_unique(collection::id::equals(id))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Collection not found")))?;
+
+	let updated_collection = client
+		.collection()
+		.update(
+			collection::id::equals(collection.id),
+			vec![
+				collection::name::set(input.name.trim().to_string()),
+				collection::description::set(input.description.clone()),
+				collection::visibility::set(input.visibility.to_string()),
+				collection::shared_with_users::set(input.shared_with_params()),
+			],
+		)
+		.with(collection::shared_with_users::fetch(vec![]))
+		.exec()
+		.await?;
+
+	Ok(Json(Collection::try_from(updated_collection)?))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/collections/{id}",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection")
+	),
+	responses(
+		(status = 200, description = "Successfully deleted collection", body = Collection),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Collection not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Delete a collection, including its cover image. The books and series in the collection
+/// are not affected
+async fn delete_collection(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Collection>> {
+	req.enforce_permissions(&[UserPermission::ManageCollections])?;
+
+	let deleted_collection = ctx
+		.db
+		.collection()
+		.delete(collection::id::equals(id))
+		.exec()
+		.await?;
+
+	if let Err(e) = remove_thumbnails(
+		&[deleted_collection.id.clone()],
+		&ctx.config.get_thumbnails_dir(),
+	)
+	.await
+	{
+		tracing::error!(?e, "Failed to remove collection thumbnail");
+	}
+
+	Ok(Json(Collection::try_from(deleted_collection)?))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/collections/{id}/items",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection")
+	),
+	request_body = SetCollectionItems,
+	responses(
+		(status = 200, description = "Successfully updated collection items", body = Collection),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Collection not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Replace the items of a collection. The order of the items in the request determines
+/// the display order of the collection
+async fn set_collection_items(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<SetCollectionItems>,
+) -> APIResult<Json<Collection>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageCollections])?;
+	let client = &ctx.db;
+
+	let collection = client
+		.collection()
+		.find_unique(collection::id::equals(id))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Collection not found")))?;
+
+	let mut seen = HashSet::new();
+	if let Some(duplicate) = input
+		.items
+		.iter()
+		.find(|item| !seen.insert((item.kind, item.id.as_str())))
+	{
+		return Err(APIError::BadRequest(format!(
+			"Item {} appears more than once in the collection",
+			duplicate.id
+		)));
+	}
+
+	let (media_ids, series_ids): (Vec<_>, Vec<_>) = input
+		.items
+		.iter()
+		.partition(|item| item.kind == CollectionItemKind::Media);
+	let media_ids = media_ids
+		.into_iter()
+		.map(|i| i.id.clone())
+		.collect::<Vec<_>>();
+	let series_ids = series_ids
+		.into_iter()
+		.map(|i| i.id.clone())
+		.collect::<Vec<_>>();
+
+	let (media_count, series_count) = client
+		._batch((
+			client
+				.media()
+				.count(vec![media::id::in_vec(media_ids.clone())]),
+			client
+				.series()
+				.count(vec![series::id::in_vec(series_ids.clone())]),
+		))
+		.await?;
+	if media_count != media_ids.len() as i64 || series_count != series_ids.len() as i64 {
+		return Err(APIError::BadRequest(
+			"One or more items do not exist".to_string(),
+		));
+	}
+
+	let collection_id = collection.id.clone();
+	client
+		._transaction()
+		.run(|tx| async move {
+			tx.collection_item()
+				.delete_many(vec![collection_item::collection_id::equals(
+					collection_id.clone(),
+				)])
+				.exec()
+				.await?;
+
+			let item_creates = input
+				.items
+				.into_iter()
+				.enumerate()
+				.map(|(idx, item)| {
+					let relation = match item.kind {
+						CollectionItemKind::Media => {
+							collection_item::media::connect(media::id::equals(item.id))
+						},
+						CollectionItemKind::Series => {
+							collection_item::series::connect(series::id::equals(item.id))
+						},
+					};
+
+					tx.collection_item().create(
+						idx as i32,
+						collection::id::equals(collection_id.clone()),
+						vec![relation],
+					)
+				})
+				.collect::<Vec<_>>();
+			tx._batch(item_creates).await?;
+
+			tx.collection()
+				.update(
+					collection::id::equals(collection_id),
+					vec![collection::updated_at::set(Utc::now().into())],
+				)
+				.exec()
+				.await
+		})
+		.await?;
+
+	let updated_collection =
+		fetch_collection_for_user(client, &collection.id, &user).await?;
+
+	Ok(Json(Collection::try_from(updated_collection)?))
+}
+
+/// Get the cover image of a collection. If a cover was not explicitly uploaded, the
+/// thumbnail of the first item the user has access to is used instead
+pub(crate) async fn get_collection_thumbnail(
+	client: &PrismaClient,
+	collection: &collection::Data,
+	user: &User,
+	config: &StumpConfig,
+) -> APIResult<ImageResponse> {
+	let thumbnails_dir = config.get_thumbnails_dir();
+
+	if let Some(thumbnail) = get_thumbnail(&thumbnails_dir, &collection.id, None).await? {
+		return Ok(ImageResponse::from(thumbnail));
+	}
+
+	let first_item = collection
+		.items()
+		.ok()
+		.and_then(|items| items.first())
+		.ok_or(APIError::NotFound(
+			"Collection does not have a thumbnail".to_string(),
+		))?;
+
+	let book_id = match (&first_item.media_id, &first_item.series_id) {
+		(Some(media_id), _) => media_id.clone(),
+		(None, Some(series_id)) => {
+			if let Some(thumbnail) =
+				get_thumbnail(&thumbnails_dir, series_id, None).await?
+			{
+				return Ok(ImageResponse::from(thumbnail));
+			}
+
+			client
+				.media()
+				.find_first(
+					[media::series_id::equals(Some(series_id.clone()))]
+						.into_iter()
+						.chain(apply_media_restrictions_for_user(user))
+						.collect(),
+				)
+				.order_by(media::name::order(Direction::Asc))
+				.exec()
+				.await?
+				.map(|book| book.id)
+				.ok_or(APIError::NotFound(
+					"Collection does not have a thumbnail".to_string(),
+				))?
+		},
+		(None, None) => {
+			return Err(APIError::NotFound(
+				"Collection does not have a thumbnail".to_string(),
+			))
+		},
+	};
+
+	get_media_thumbnail_by_id(book_id, client, user, config)
+		.await
+		.map(ImageResponse::from)
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/collections/{id}/thumbnail",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched collection thumbnail"),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Collection not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the cover image of a collection
+async fn get_collection_thumbnail_handler(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<ImageResponse> {
+	let user = req.user();
+	let collection = fetch_collection_for_user(&ctx.db, &id, user).await?;
+	get_collection_thumbnail(&ctx.db, &collection, user, &ctx.config).await
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/collections/{id}/thumbnail",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection")
+	),
+	responses(
+		(status = 200, description = "Successfully replaced collection thumbnail"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Collection not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Upload a cover image for a collection, replacing any existing one
+async fn replace_collection_thumbnail(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	mut upload: Multipart,
+) -> APIResult<ImageResponse> {
+	req.enforce_permissions(&[
+		UserPermission::UploadFile,
+		UserPermission::ManageCollections,
+	])?;
+
+	let collection = ctx
+		.db
+		.collection()
+		.find_unique(collection::id::equals(id))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Collection not found")))?;
+
+	let upload_data =
+		validate_and_load_image(&mut upload, Some(ctx.config.max_image_upload_size))
+			.await?;
+	let ext = upload_data.content_type.extension();
+
+	if let Err(e) =
+		remove_thumbnails(&[collection.id.clone()], &ctx.config.get_thumbnails_dir())
+			.await
+	{
+		tracing::error!(?e, "Failed to remove existing collection thumbnail");
+	}
+
+	let path_buf =
+		place_thumbnail(&collection.id, ext, &upload_data.bytes, &ctx.config).await?;
+
+	Ok(ImageResponse::from((
+		upload_data.content_type,
+		fs::read(path_buf).await?,
+	)))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/collections/{id}/thumbnail",
+	tag = "collection",
+	params(
+		("id" = String, Path, description = "The ID of the collection")
+	),
+	responses(
+		(status = 200, description = "Successfully removed collection thumbnail"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Collection not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Remove the uploaded cover image of a collection, reverting to the thumbnail of its
+/// first item
+async fn delete_collection_thumbnail(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<()>> {
+	req.enforce_permissions(&[UserPermission::ManageCollections])?;
+
+	let collection = ctx
+		.db
+		.collection()
+		.find_unique(collection::id::equals(id))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Collection not found")))?;
+
+	remove_thumbnails(&[collection.id], &ctx.config.get_thumbnails_dir()).await?;
+
+	Ok(Json(()))
+}
@@ -12,9 +12,9 @@ use serde_qs::axum::QsQuery;
 use specta::Type;
 use stump_core::{
 	db::entity::{
-		AttachmentMeta, EmailerConfig, EmailerConfigInput, EmailerSendRecord,
-		EmailerSendTo, Media, Notifier, RegisteredEmailDevice, SMTPEmailer, User,
-		UserPermission,
+		AttachmentMeta, AuditAction, AuditEvent, AuditLogChange, EmailerConfig,
+		EmailerConfigInput, EmailerSendRecord, EmailerSendTo, Media, Notifier,
+		RegisteredEmailDevice, SMTPEmailer, User, UserPermission,
 	},
 	filesystem::{ContentType, FileParts, PathUtils},
 	prisma::{emailer, emailer_send_record, registered_email_device, user, PrismaClient},
@@ -206,7 +206,16 @@ async fn create_emailer(
 		)
 		.exec()
 		.await?;
-	Ok(Json(SMTPEmailer::try_from(emailer)?))
+	let emailer = SMTPEmailer::try_from(emailer)?;
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::EmailerCreated, Some(req.user()))
+			.with_target(emailer.id, &emailer.name)
+			.with_changes(None, Some(&emailer)),
+	)
+	.await;
+
+	Ok(Json(emailer))
 }
 
 /// Update an existing emailer by ID
@@ -235,11 +244,19 @@ async fn update_emailer(
 ) -> APIResult<Json<SMTPEmailer>> {
 	req.enforce_permissions(&[UserPermission::EmailerManage])?;
 
-	if payload.config.password.is_some() {
+	let password_changed = payload.config.password.is_some();
+	if password_changed {
 		tracing::warn!(?id, "The password for the emailer is being updated!");
 	}
 
 	let client = &ctx.db;
+	let emailer_before = client
+		.emailer()
+		.find_unique(emailer::id::equals(id))
+		.exec()
+		.await?
+		.map(SMTPEmailer::try_from)
+		.transpose()?;
 	let config = EmailerConfig::from_client_config(payload.config, &ctx).await?;
 	let updated_emailer = client
 		.emailer()
@@ -265,7 +282,22 @@ async fn update_emailer(
 		)
 		.exec()
 		.await?;
-	Ok(Json(SMTPEmailer::try_from(updated_emailer)?))
+	let updated_emailer = SMTPEmailer::try_from(updated_emailer)?;
+
+	let mut event = AuditEvent::new(AuditAction::EmailerUpdated, Some(req.user()))
+		.with_target(updated_emailer.id, &updated_emailer.name)
+		.with_changes(emailer_before.as_ref(), Some(&updated_emailer));
+	if password_changed {
+		// The password is never serialized, so only the fact that it changed is recorded
+		event.changes.push(AuditLogChange {
+			field: "password".to_string(),
+			before: None,
+			after: None,
+		});
+	}
+	ctx.record_audit_event(event).await;
+
+	Ok(Json(updated_emailer))
 }
 
 // #[derive(Deserialize, ToSchema, Type)]
@@ -330,8 +362,16 @@ async fn delete_emailer(
 		.delete(emailer::id::equals(id))
 		.exec()
 		.await?;
+	let deleted_emailer = SMTPEmailer::try_from(deleted_emailer)?;
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::EmailerDeleted, Some(req.user()))
+			.with_target(deleted_emailer.id, &deleted_emailer.name)
+			.with_changes(Some(&deleted_emailer), None),
+	)
+	.await;
 
-	Ok(Json(SMTPEmailer::try_from(deleted_emailer)?))
+	Ok(Json(deleted_emailer))
 }
 
 #[derive(Debug, Deserialize, ToSchema, Type)]
@@ -470,6 +510,7 @@ async fn send_attachment_email(
 		.ok_or(APIError::NotFound("Primary emailer not found".to_string()))?;
 	let emailer = SMTPEmailer::try_from(emailer)?;
 	let emailer_id = emailer.id;
+	let emailer_name = emailer.name.clone();
 	let max_attachment_size_bytes = emailer.config.max_attachment_size_bytes;
 
 	let expected_books_len = payload.media_ids.len();
@@ -513,6 +554,7 @@ async fn send_attachment_email(
 	// TODO: Refactor this to chunk the books and send them in batches according to
 	// the max attachments per email limit
 
+	let sent_media_ids = books.iter().map(|book| book.id.clone()).collect::<Vec<_>>();
 	for book in books {
 		let FileParts {
 			file_name,
@@ -599,6 +641,22 @@ async fn send_attachment_email(
 	}
 
 	let sent_emails_count = record_creates.len();
+	if sent_emails_count > 0 {
+		let sent = serde_json::json!({
+			"recipients": record_creates
+				.iter()
+				.map(|(_, recipient, _)| recipient.clone())
+				.collect::<std::collections::BTreeSet<_>>(),
+			"media_ids": sent_media_ids,
+			"sent_emails_count": sent_emails_count,
+		});
+		ctx.record_audit_event(
+			AuditEvent::new(AuditAction::EmailSent, Some(&by_user))
+				.with_target(emailer_id, emailer_name)
+				.with_changes(None, Some(&sent)),
+		)
+		.await;
+	}
 	// Note: create_many threw a strange error...
 	let audit_result = client
 		._batch(record_creates.into_iter().map(|(eid, recipient, params)| {
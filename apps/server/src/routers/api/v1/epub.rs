@@ -6,6 +6,7 @@ use axum::{
 	routing::{get, put},
 	Extension, Json, Router,
 };
+use prisma_client_rust::chrono::Utc;
 use serde::Deserialize;
 use specta::Type;
 use stump_core::{
@@ -13,7 +14,7 @@ use stump_core::{
 		ActiveReadingSession, Bookmark, Epub, FinishedReadingSession,
 		ProgressUpdateReturn, UpdateEpubProgress,
 	},
-	filesystem::media::EpubProcessor,
+	filesystem::media::{epub_locator::resolve_epub_locator, EpubProcessor},
 	prisma::{
 		active_reading_session, bookmark, finished_reading_session, media,
 		media_annotation, user,
@@ -81,6 +82,9 @@ async fn get_epub_by_id(
 /// Update the progress of an epub. This is separate from media progress updates
 /// since there is enough epub-specific data that needs to be updated that would
 /// convolute the media progress update.
+///
+/// The epubcfi is located within the book, so that the progress can also be picked up by
+/// KOReader and OPDS clients.
 async fn update_epub_progress(
 	Path(id): Path<String>,
 	State(ctx): State<AppState>,
@@ -109,7 +113,9 @@ async fn update_epub_progress(
 
 				tx.finished_reading_session()
 					.create(
-						deleted_session.map(|s| s.started_at).unwrap_or_default(),
+						deleted_session
+							.map(|s| s.started_at)
+							.unwrap_or_else(|| Utc::now().into()),
 						media::id::equals(id.clone()),
 						user::id::equals(user_id.clone()),
 						vec![],
@@ -124,6 +130,37 @@ async fn update_epub_progress(
 			FinishedReadingSession::from(finished_session),
 		)))
 	} else {
+		let book = client
+			.media()
+			.find_unique(media::id::equals(id.clone()))
+			.exec()
+			.await?
+			.ok_or(APIError::NotFound(format!("Media with id {id} not found")))?;
+
+		let locator_params = match resolve_epub_locator(&book.path, &input.epubcfi) {
+			Ok(locator) => locator.into_session_params(),
+			Err(error) => {
+				// Any previous locator is cleared, since it would no longer match the epubcfi
+				tracing::warn!(
+					?error,
+					epubcfi = input.epubcfi,
+					"Failed to locate epubcfi"
+				);
+				vec![
+					active_reading_session::epubcfi::set(Some(input.epubcfi.clone())),
+					active_reading_session::koreader_progress::set(None),
+					active_reading_session::locator_href::set(None),
+					active_reading_session::locator_progression::set(None),
+				]
+			},
+		};
+		let set_params = locator_params
+			.into_iter()
+			.chain([active_reading_session::percentage_completed::set(Some(
+				input.percentage,
+			))])
+			.collect::<Vec<_>>();
+
 		let active_session = client
 			.active_reading_session()
 			.upsert(
@@ -131,19 +168,9 @@ async fn update_epub_progress(
 				(
 					media::id::equals(id.clone()),
 					user::id::equals(user_id.clone()),
-					vec![
-						active_reading_session::epubcfi::set(Some(input.epubcfi.clone())),
-						active_reading_session::percentage_completed::set(Some(
-							input.percentage,
-						)),
-					],
+					set_params.clone(),
 				),
-				vec![
-					active_reading_session::epubcfi::set(Some(input.epubcfi)),
-					active_reading_session::percentage_completed::set(Some(
-						input.percentage,
-					)),
-				],
+				set_params,
 			)
 			.exec()
 			.await?;
@@ -0,0 +1,465 @@
+use axum::{
+	extract::{Path, State},
+	middleware,
+	routing::{delete, get, post},
+	Extension, Json, Router,
+};
+use prisma_client_rust::{
+	chrono::{Duration, Utc},
+	Direction,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use stump_core::{
+	db::entity::{
+		AgeRestriction, AuditAction, AuditEvent, PermissionSet, SMTPEmailer,
+		ServerInvitation, User, UserPermission, DEFAULT_INVITATION_EXPIRY_DAYS,
+	},
+	prisma::{
+		age_restriction, emailer, library, server_invitation, user, user_preferences,
+	},
+	InvitationPayload, NotifierEvent,
+};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	utils::hash_password,
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/invitations", get(get_invitations).post(create_invitation))
+		.route("/invitations/{id}", delete(delete_invitation))
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+		// Note: Redeeming an invitation is how someone without an account registers, so it
+		// must not require authentication
+		.route("/invitations/redeem", post(redeem_invitation))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct CreateServerInvitation {
+	/// The email address to send the invitation to. If not provided, no email is sent and
+	/// the invitation link must be shared manually
+	#[serde(default)]
+	pub email: Option<String>,
+	/// The permissions to grant the invited user
+	#[serde(default)]
+	pub permissions: Vec<UserPermission>,
+	/// The age restriction to apply to the invited user
+	#[serde(default)]
+	pub age_restriction: Option<AgeRestriction>,
+	/// The IDs of the libraries to hide from the invited user
+	#[serde(default)]
+	pub hidden_library_ids: Vec<String>,
+	/// The number of days the invitation is valid for. Defaults to 7
+	#[serde(default)]
+	pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema, Type)]
+pub struct CreatedServerInvitation {
+	pub invitation: ServerInvitation,
+	/// The token used to redeem the invitation. This is only ever returned once
+	pub token: String,
+	/// The link to redeem the invitation, if the server has a public URL configured
+	pub invitation_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct RedeemServerInvitation {
+	/// The token which was sent to the invitee
+	pub token: String,
+	pub username: String,
+	pub password: String,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/invitations",
+	tag = "invitation",
+	responses(
+		(status = 200, description = "Successfully fetched invitations", body = [ServerInvitation]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get all of the invitations which have not yet been redeemed, most recently created first
+async fn get_invitations(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<ServerInvitation>>> {
+	req.enforce_permissions(&[UserPermission::ManageUsers])?;
+
+	let invitations = ctx
+		.db
+		.server_invitation()
+		.find_many(vec![])
+		.with(server_invitation::hidden_libraries::fetch(vec![]))
+		.order_by(server_invitation::created_at::order(Direction::Desc))
+		.exec()
+		.await?;
+
+	Ok(Json(
+		invitations
+			.into_iter()
+			.map(ServerInvitation::from)
+			.collect(),
+	))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/invitations",
+	tag = "invitation",
+	request_body = CreateServerInvitation,
+	responses(
+		(status = 200, description = "Successfully created invitation", body = CreatedServerInvitation),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Create an invitation for someone to register an account on the server. If an email is
+/// provided, the invitation is sent to it using the primary emailer
+async fn create_invitation(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateServerInvitation>,
+) -> APIResult<Json<CreatedServerInvitation>> {
+	req.enforce_permissions(&[UserPermission::ManageUsers])?;
+
+	let client = &ctx.db;
+
+	let expires_in_days = input
+		.expires_in_days
+		.unwrap_or(DEFAULT_INVITATION_EXPIRY_DAYS);
+	if expires_in_days < 1 {
+		return Err(APIError::BadRequest(String::from(
+			"Invitations must be valid for at least one day",
+		)));
+	}
+
+	let email = input
+		.email
+		.as_deref()
+		.map(str::trim)
+		.filter(|email| !email.is_empty())
+		.map(String::from);
+
+	let public_url = client
+		.server_config()
+		.find_first(vec![])
+		.exec()
+		.await?
+		.and_then(|config| config.public_url);
+	let emailer = match email {
+		Some(_) => {
+			if public_url.is_none() {
+				return Err(APIError::BadRequest(String::from(
+					"A public URL must be configured to send invitation emails",
+				)));
+			}
+			let emailer = client
+				.emailer()
+				.find_first(vec![emailer::is_primary::equals(true)])
+				.exec()
+				.await?
+				.ok_or(APIError::NotFound("Primary emailer not found".to_string()))?;
+			Some(SMTPEmailer::try_from(emailer)?)
+		},
+		None => None,
+	};
+
+	let hidden_libraries_count = client
+		.library()
+		.count(vec![library::id::in_vec(input.hidden_library_ids.clone())])
+		.exec()
+		.await?;
+	if hidden_libraries_count != input.hidden_library_ids.len() as i64 {
+		return Err(APIError::BadRequest(String::from(
+			"Some library IDs were not found",
+		)));
+	}
+
+	let (token, secret) = ServerInvitation::create_token();
+	let expires_at = Utc::now() + Duration::days(expires_in_days);
+	let granted_permissions = PermissionSet::new(input.permissions).resolve_into_string();
+
+	let created_invitation = client
+		.server_invitation()
+		.create(
+			secret,
+			expires_at.into(),
+			vec![
+				server_invitation::email::set(email.clone()),
+				server_invitation::granted_permissions::set(granted_permissions),
+				server_invitation::age_restriction::set(
+					input.age_restriction.as_ref().map(|ar| ar.age),
+				),
+				server_invitation::restrict_on_unset::set(
+					input
+						.age_restriction
+						.as_ref()
+						.is_some_and(|ar| ar.restrict_on_unset),
+				),
+				server_invitation::hidden_libraries::connect(
+					input
+						.hidden_library_ids
+						.into_iter()
+						.map(library::id::equals)
+						.collect(),
+				),
+			],
+		)
+		.with(server_invitation::hidden_libraries::fetch(vec![]))
+		.exec()
+		.await?;
+	let invitation = ServerInvitation::from(created_invitation);
+
+	let invitation_url = public_url.map(|url| {
+		format!(
+			"{}/auth/invitation?token={token}",
+			url.trim_end_matches('/')
+		)
+	});
+
+	if let (Some(emailer), Some(email), Some(invitation_url)) =
+		(emailer, email, invitation_url.clone())
+	{
+		let (emailer_id, emailer_name) = (emailer.id, emailer.name.clone());
+		let emailer_client = emailer.into_client(&ctx).await?;
+		let send_result = emailer_client
+			.send_invitation(
+				&email,
+				InvitationPayload {
+					invitation_url,
+					expires_at: expires_at.format("%B %-d, %Y").to_string(),
+				},
+			)
+			.await;
+
+		// An invitation which never reached the invitee is of no use, so it is removed to
+		// avoid leaving a redeemable invitation around
+		if let Err(error) = send_result {
+			tracing::error!(?error, "Failed to send invitation email");
+			client
+				.server_invitation()
+				.delete(server_invitation::id::equals(invitation.id.clone()))
+				.exec()
+				.await?;
+			return Err(APIError::InternalServerError(format!(
+				"Failed to send invitation email: {error}"
+			)));
+		}
+
+		let sent = serde_json::json!({
+			"recipients": [email],
+			"invitation_id": invitation.id,
+		});
+		ctx.record_audit_event(
+			AuditEvent::new(AuditAction::EmailSent, Some(req.user()))
+				.with_target(emailer_id, emailer_name)
+				.with_changes(None, Some(&sent)),
+		)
+		.await;
+	}
+
+	Ok(Json(CreatedServerInvitation {
+		invitation,
+		token,
+		invitation_url,
+	}))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/invitations/{id}",
+	tag = "invitation",
+	params(
+		("id" = String, Path, description = "The ID of the invitation to revoke")
+	),
+	responses(
+		(status = 200, description = "Successfully revoked invitation", body = ServerInvitation),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Invitation not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Revoke an invitation, so that it can no longer be redeemed
+async fn delete_invitation(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<ServerInvitation>> {
+	req.enforce_permissions(&[UserPermission::ManageUsers])?;
+
+	let client = &ctx.db;
+
+	client
+		.server_invitation()
+		.find_unique(server_invitation::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Invitation not found")))?;
+
+	let deleted_invitation = client
+		.server_invitation()
+		.delete(server_invitation::id::equals(id))
+		.exec()
+		.await?;
+
+	Ok(Json(ServerInvitation::from(deleted_invitation)))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/invitations/redeem",
+	tag = "invitation",
+	request_body = RedeemServerInvitation,
+	responses(
+		(status = 200, description = "Successfully registered the invited user", body = User),
+		(status = 400, description = "Bad request"),
+		(status = 404, description = "Invitation not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Redeem an invitation to register a new user. The user is created with the permissions,
+/// age restriction and hidden libraries of the invitation, which is then removed
+async fn redeem_invitation(
+	State(ctx): State<AppState>,
+	Json(input): Json<RedeemServerInvitation>,
+) -> APIResult<Json<User>> {
+	let client = &ctx.db;
+
+	let invitation = client
+		.server_invitation()
+		.find_unique(server_invitation::secret::equals(
+			ServerInvitation::hash_token(&input.token),
+		))
+		.with(server_invitation::hidden_libraries::fetch(vec![]))
+		.exec()
+		.await?
+		.map(ServerInvitation::from)
+		.ok_or(APIError::NotFound(String::from("Invitation not found")))?;
+
+	if invitation.is_expired() {
+		return Err(APIError::BadRequest(String::from(
+			"This invitation has expired",
+		)));
+	}
+
+	let username = input.username.trim().to_string();
+	if username.is_empty() || input.password.is_empty() {
+		return Err(APIError::BadRequest(String::from(
+			"A username and password are required",
+		)));
+	}
+
+	let existing_user = client
+		.user()
+		.find_unique(user::username::equals(username.clone()))
+		.exec()
+		.await?;
+	if existing_user.is_some() {
+		return Err(APIError::BadRequest(String::from(
+			"The username is already taken",
+		)));
+	}
+
+	let hashed_password = hash_password(&input.password, &ctx.config)?;
+
+	let created_user = client
+		._transaction()
+		.run(|client| async move {
+			// Removing the invitation first ensures it can only ever be redeemed once, since a
+			// concurrent redemption will fail to delete it
+			client
+				.server_invitation()
+				.delete(server_invitation::id::equals(invitation.id))
+				.exec()
+				.await?;
+
+			let permissions =
+				PermissionSet::new(invitation.granted_permissions).resolve_into_string();
+			let hidden_libraries = invitation
+				.hidden_library_ids
+				.unwrap_or_default()
+				.into_iter()
+				.map(library::id::equals)
+				.collect();
+
+			let created_user = client
+				.user()
+				.create(
+					username,
+					hashed_password,
+					vec![
+						user::is_server_owner::set(false),
+						user::permissions::set(permissions),
+						user::libraries_hidden_from_user::connect(hidden_libraries),
+					],
+				)
+				.exec()
+				.await?;
+
+			if let Some(ar) = invitation.age_restriction {
+				client
+					.age_restriction()
+					.create(
+						ar.age,
+						user::id::equals(created_user.id.clone()),
+						vec![age_restriction::restrict_on_unset::set(
+							ar.restrict_on_unset,
+						)],
+					)
+					.exec()
+					.await?;
+			}
+
+			client
+				.user_preferences()
+				.create(vec![
+					user_preferences::user::connect(user::id::equals(
+						created_user.id.clone(),
+					)),
+					user_preferences::user_id::set(Some(created_user.id.clone())),
+				])
+				.exec()
+				.await?;
+
+			client
+				.user()
+				.find_unique(user::id::equals(created_user.id))
+				.with(user::user_preferences::fetch())
+				.with(user::age_restriction::fetch())
+				.exec()
+				.await
+		})
+		.await?
+		.ok_or(APIError::InternalServerError(
+			"Failed to fetch user after registration.".to_string(),
+		))?;
+	let created_user = User::from(created_user);
+
+	// The user is created by redeeming the invitation, so there is no actor
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserCreated, None)
+			.with_target(&created_user.id, &created_user.username)
+			.with_changes(None, Some(&created_user)),
+	)
+	.await;
+	ctx.send_notifier_event(
+		NotifierEvent::UserRegistered {
+			username: created_user.username.clone(),
+		},
+		None,
+	);
+
+	Ok(Json(created_user))
+}
@@ -1,24 +1,25 @@
 use axum::{
 	extract::{Path, Query, State},
 	middleware,
-	routing::{delete, get},
+	routing::{delete, get, post},
 	Json, Router,
 };
+use prisma_client_rust::chrono::{DateTime, FixedOffset, Utc};
 use serde::{Deserialize, Serialize};
 use serde_qs::axum::QsQuery;
 use specta::Type;
 use stump_core::{
 	db::{
-		entity::{JobSchedulerConfig, PersistedJob},
+		entity::{JobSchedule, PersistedJob, ScheduledJobConfig, ScheduledJobKind},
 		query::{
 			ordering::QueryOrder,
 			pagination::{Pageable, Pagination, PaginationQuery},
 		},
 	},
-	job::{AcknowledgeableCommand, JobControllerCommand},
+	job::{AcknowledgeableCommand, JobControllerCommand, JobScheduler, ScheduleTrigger},
 	prisma::{
 		job::{self, OrderByParam as JobOrderByParam},
-		job_schedule_config, library, server_config,
+		job_schedule, library,
 	},
 };
 use tokio::sync::oneshot;
@@ -27,7 +28,6 @@ use utoipa::ToSchema;
 use crate::{
 	config::state::AppState,
 	errors::{APIError, APIResult},
-	filter::chain_optional_iter,
 	middleware::auth::{auth_middleware, server_owner_middleware},
 };
 
@@ -43,9 +43,23 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 						.route("/", get(get_job_by_id).delete(delete_job_by_id))
 						.route("/cancel", delete(cancel_job_by_id)),
 				)
-				.route(
-					"/scheduler-config",
-					get(get_scheduler_config).post(update_scheduler_config),
+				.nest(
+					"/schedules",
+					Router::new()
+						.route("/", get(get_job_schedules).post(create_job_schedule))
+						.nest(
+							"/{id}",
+							Router::new()
+								.route(
+									"/",
+									get(get_job_schedule_by_id)
+										.put(update_job_schedule)
+										.delete(delete_job_schedule),
+								)
+								.route("/pause", post(pause_job_schedule))
+								.route("/resume", post(resume_job_schedule))
+								.route("/trigger", post(trigger_job_schedule)),
+						),
 				),
 		)
 		// TODO: consider permissions around job management
@@ -252,144 +266,417 @@ async fn cancel_job_by_id(
 
 #[utoipa::path(
 	get,
-	path = "/api/v1/jobs/scheduler-config",
+	path = "/api/v1/jobs/schedules",
 	tag = "job",
 	responses(
-		(status = 200, description = "Successfully fetched JobSchedulerConfig", body = JobSchedulerConfig),
-		(status = 401, description = "No user is not logged in (unauthorized)."),
+		(status = 200, description = "Successfully fetched job schedules", body = [JobSchedule]),
+		(status = 401, description = "No user is logged in (unauthorized)."),
 		(status = 403, description = "User does not have permission to access this resource."),
 		(status = 500, description = "Internal server error."),
 	)
 )]
-async fn get_scheduler_config(
+/// Get all job schedules, including their targeted libraries
+async fn get_job_schedules(
 	State(ctx): State<AppState>,
-) -> APIResult<Json<JobSchedulerConfig>> {
-	let client = &ctx.db;
+) -> APIResult<Json<Vec<JobSchedule>>> {
+	let schedules = ctx
+		.db
+		.job_schedule()
+		.find_many(vec![])
+		.with(job_schedule::included_libraries::fetch(vec![]))
+		.with(job_schedule::excluded_libraries::fetch(vec![]))
+		.exec()
+		.await?
+		.into_iter()
+		.map(JobSchedule::try_from)
+		.collect::<Result<Vec<_>, _>>()?;
 
-	let server_config = client
-		.server_config()
-		.find_first(vec![])
-		.with(
-			server_config::job_schedule_config::fetch()
-				.with(job_schedule_config::excluded_libraries::fetch(vec![])),
+	Ok(Json(schedules))
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Type)]
+pub struct CreateOrUpdateJobSchedule {
+	/// The unique, human-readable name of the schedule
+	pub name: String,
+	/// The kind of job to run
+	pub job_kind: ScheduledJobKind,
+	/// A cron expression, e.g. "0 3 * * *". Takes precedence over `interval_secs`
+	pub cron_expression: Option<String>,
+	/// The interval (in seconds) between runs
+	pub interval_secs: Option<i32>,
+	/// Job-specific options for the schedule
+	#[serde(default)]
+	pub config: ScheduledJobConfig,
+	/// The libraries to restrict the schedule to. If empty, all libraries are targeted
+	#[serde(default)]
+	pub included_library_ids: Vec<String>,
+	/// The libraries to skip
+	#[serde(default)]
+	pub excluded_library_ids: Vec<String>,
+	#[serde(default)]
+	pub is_paused: bool,
+}
+
+impl CreateOrUpdateJobSchedule {
+	/// Validate the schedule and compute when it should next run. A schedule is rejected if
+	/// its trigger is invalid or would never fire, or if it targets libraries for a job kind
+	/// which does not operate on libraries.
+	fn validate(&self) -> APIResult<DateTime<FixedOffset>> {
+		let has_library_targets = !self.included_library_ids.is_empty()
+			|| !self.excluded_library_ids.is_empty();
+		if has_library_targets && !self.job_kind.targets_libraries() {
+			return Err(APIError::BadRequest(format!(
+				"{} schedules cannot target libraries",
+				self.job_kind
+			)));
+		}
+
+		let trigger =
+			ScheduleTrigger::new(self.cron_expression.as_deref(), self.interval_secs)?;
+		trigger
+			.next_after(Utc::now())
+			.map(DateTime::<FixedOffset>::from)
+			.ok_or(APIError::BadRequest(
+				"The schedule would never run".to_string(),
+			))
+	}
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/jobs/schedules",
+	tag = "job",
+	request_body = CreateOrUpdateJobSchedule,
+	responses(
+		(status = 200, description = "Successfully created job schedule", body = JobSchedule),
+		(status = 400, description = "Invalid cron expression or interval."),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Create a new job schedule
+async fn create_job_schedule(
+	State(ctx): State<AppState>,
+	Json(input): Json<CreateOrUpdateJobSchedule>,
+) -> APIResult<Json<JobSchedule>> {
+	let next_run_at = input.validate()?;
+
+	let created_schedule = ctx
+		.db
+		.job_schedule()
+		.create(
+			input.name,
+			input.job_kind.to_string(),
+			vec![
+				job_schedule::cron_expression::set(input.cron_expression),
+				job_schedule::interval_secs::set(input.interval_secs),
+				job_schedule::config::set(Some(input.config.into_bytes()?)),
+				job_schedule::is_paused::set(input.is_paused),
+				job_schedule::next_run_at::set(Some(next_run_at)),
+				job_schedule::included_libraries::connect(
+					input
+						.included_library_ids
+						.into_iter()
+						.map(library::id::equals)
+						.collect(),
+				),
+				job_schedule::excluded_libraries::connect(
+					input
+						.excluded_library_ids
+						.into_iter()
+						.map(library::id::equals)
+						.collect(),
+				),
+			],
 		)
+		.with(job_schedule::included_libraries::fetch(vec![]))
+		.with(job_schedule::excluded_libraries::fetch(vec![]))
+		.exec()
+		.await?;
+
+	Ok(Json(JobSchedule::try_from(created_schedule)?))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/jobs/schedules/{id}",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule.")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched job schedule", body = JobSchedule),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Get a job schedule by its ID
+async fn get_job_schedule_by_id(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+) -> APIResult<Json<JobSchedule>> {
+	let schedule = ctx
+		.db
+		.job_schedule()
+		.find_unique(job_schedule::id::equals(id))
+		.with(job_schedule::included_libraries::fetch(vec![]))
+		.with(job_schedule::excluded_libraries::fetch(vec![]))
 		.exec()
 		.await?
-		.ok_or(APIError::InternalServerError(
-			"Server preferences have not been initialized".to_string(),
-		))?;
-
-	let config = server_config
-		.job_schedule_config()?
-		.map(|c| c.to_owned())
-		.ok_or(APIError::NotFound(
-			"Job scheduler config has not been initialized".to_string(),
-		))?;
-
-	Ok(Json(JobSchedulerConfig::from(config)))
+		.ok_or(APIError::NotFound("Job schedule not found".to_string()))?;
+
+	Ok(Json(JobSchedule::try_from(schedule)?))
 }
 
-#[derive(Debug, Deserialize, Serialize, ToSchema, specta::Type)]
-pub struct UpdateSchedulerConfig {
-	pub interval_secs: Option<i32>,
-	pub excluded_library_ids: Option<Vec<String>>,
+#[utoipa::path(
+	put,
+	path = "/api/v1/jobs/schedules/{id}",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule.")
+	),
+	request_body = CreateOrUpdateJobSchedule,
+	responses(
+		(status = 200, description = "Successfully updated job schedule", body = JobSchedule),
+		(status = 400, description = "Invalid cron expression or interval."),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Update a job schedule. The next run is recomputed relative to now.
+async fn update_job_schedule(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+	Json(input): Json<CreateOrUpdateJobSchedule>,
+) -> APIResult<Json<JobSchedule>> {
+	let next_run_at = input.validate()?;
+
+	let updated_schedule = ctx
+		.db
+		.job_schedule()
+		.update(
+			job_schedule::id::equals(id),
+			vec![
+				job_schedule::name::set(input.name),
+				job_schedule::job_kind::set(input.job_kind.to_string()),
+				job_schedule::cron_expression::set(input.cron_expression),
+				job_schedule::interval_secs::set(input.interval_secs),
+				job_schedule::config::set(Some(input.config.into_bytes()?)),
+				job_schedule::is_paused::set(input.is_paused),
+				job_schedule::next_run_at::set(Some(next_run_at)),
+				job_schedule::included_libraries::set(
+					input
+						.included_library_ids
+						.into_iter()
+						.map(library::id::equals)
+						.collect(),
+				),
+				job_schedule::excluded_libraries::set(
+					input
+						.excluded_library_ids
+						.into_iter()
+						.map(library::id::equals)
+						.collect(),
+				),
+			],
+		)
+		.with(job_schedule::included_libraries::fetch(vec![]))
+		.with(job_schedule::excluded_libraries::fetch(vec![]))
+		.exec()
+		.await?;
+
+	Ok(Json(JobSchedule::try_from(updated_schedule)?))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/jobs/schedules/{id}",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule.")
+	),
+	responses(
+		(status = 200, description = "Successfully deleted job schedule", body = JobSchedule),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Delete a job schedule. Jobs which were already enqueued by the schedule are not affected.
+async fn delete_job_schedule(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+) -> APIResult<Json<JobSchedule>> {
+	let deleted_schedule = ctx
+		.db
+		.job_schedule()
+		.delete(job_schedule::id::equals(id))
+		.exec()
+		.await?;
+
+	Ok(Json(JobSchedule::try_from(deleted_schedule)?))
 }
 
 #[utoipa::path(
 	post,
-	path = "/api/v1/jobs/scheduler-config",
+	path = "/api/v1/jobs/schedules/{id}/pause",
 	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule.")
+	),
 	responses(
-		(status = 200, description = "Successfully updated JobSchedulerConfig", body = Option<JobSchedulerConfig>),
-		(status = 401, description = "No user is not logged in (unauthorized)."),
+		(status = 200, description = "Successfully paused job schedule", body = JobSchedule),
+		(status = 401, description = "No user is logged in (unauthorized)."),
 		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
 		(status = 500, description = "Internal server error."),
 	)
 )]
-async fn update_scheduler_config(
+/// Pause a job schedule. Paused schedules may still be triggered manually.
+async fn pause_job_schedule(
 	State(ctx): State<AppState>,
-	Json(input): Json<UpdateSchedulerConfig>,
-) -> APIResult<Json<Option<JobSchedulerConfig>>> {
-	let db = &ctx.db;
+	Path(id): Path<String>,
+) -> APIResult<Json<JobSchedule>> {
+	let updated_schedule = ctx
+		.db
+		.job_schedule()
+		.update(
+			job_schedule::id::equals(id),
+			vec![job_schedule::is_paused::set(true)],
+		)
+		.with(job_schedule::included_libraries::fetch(vec![]))
+		.with(job_schedule::excluded_libraries::fetch(vec![]))
+		.exec()
+		.await?;
 
-	let should_remove_config =
-		input.excluded_library_ids.is_none() && input.interval_secs.is_none();
+	Ok(Json(JobSchedule::try_from(updated_schedule)?))
+}
 
-	tracing::trace!(should_remove_config, ?input, "update_scheduler_config");
+#[utoipa::path(
+	post,
+	path = "/api/v1/jobs/schedules/{id}/resume",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule.")
+	),
+	responses(
+		(status = 200, description = "Successfully resumed job schedule", body = JobSchedule),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Resume a paused job schedule. The next run is computed relative to now, so runs which
+/// were missed while paused are skipped.
+async fn resume_job_schedule(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+) -> APIResult<Json<JobSchedule>> {
+	let client = &ctx.db;
 
-	let result: Result<Option<JobSchedulerConfig>, APIError> = db
-		._transaction()
-		.run(|client| async move {
-			let server_config = client
-				.server_config()
-				.find_first(vec![])
-				.with(server_config::job_schedule_config::fetch())
-				.exec()
-				.await?
-				.ok_or(APIError::InternalServerError(String::from(
-					"Server preferences are missing!",
-				)))?;
-
-			let existing_config = server_config.job_schedule_config()?.cloned();
-
-			if should_remove_config {
-				client
-					.job_schedule_config()
-					.delete_many(vec![])
-					.exec()
-					.await?;
-				Ok(None)
-			} else if let Some(config) = existing_config {
-				let updated_config = client
-					.job_schedule_config()
-					.update(
-						job_schedule_config::id::equals(config.id),
-						chain_optional_iter(
-							[],
-							[
-								input
-									.interval_secs
-									.map(job_schedule_config::interval_secs::set),
-								input.excluded_library_ids.map(|list| {
-									job_schedule_config::excluded_libraries::set(
-										list.into_iter()
-											.map(library::id::equals)
-											.collect(),
-									)
-								}),
-							],
-						),
-					)
-					.with(job_schedule_config::excluded_libraries::fetch(vec![]))
-					.exec()
-					.await?;
-				Ok(Some(JobSchedulerConfig::from(updated_config)))
-			} else {
-				let created_config = client
-					.job_schedule_config()
-					.create(chain_optional_iter(
-						[job_schedule_config::server_config::connect(
-							server_config::id::equals(server_config.id),
-						)],
-						[
-							input
-								.interval_secs
-								.map(job_schedule_config::interval_secs::set),
-							input.excluded_library_ids.map(|list| {
-								job_schedule_config::excluded_libraries::connect(
-									list.into_iter().map(library::id::equals).collect(),
-								)
-							}),
-						],
-					))
-					.with(job_schedule_config::excluded_libraries::fetch(vec![]))
-					.exec()
-					.await?;
-				Ok(Some(JobSchedulerConfig::from(created_config)))
-			}
-		})
-		.await;
-	let updated_or_deleted_config = result?;
+	let schedule = client
+		.job_schedule()
+		.find_unique(job_schedule::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("Job schedule not found".to_string()))?;
+	let next_run_at = JobSchedule::try_from(schedule)?
+		.trigger()?
+		.next_after(Utc::now())
+		.map(DateTime::<FixedOffset>::from);
+
+	let updated_schedule = client
+		.job_schedule()
+		.update(
+			job_schedule::id::equals(id),
+			vec![
+				job_schedule::is_paused::set(false),
+				job_schedule::next_run_at::set(next_run_at),
+			],
+		)
+		.with(job_schedule::included_libraries::fetch(vec![]))
+		.with(job_schedule::excluded_libraries::fetch(vec![]))
+		.exec()
+		.await?;
+
+	Ok(Json(JobSchedule::try_from(updated_schedule)?))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/jobs/schedules/{id}/trigger",
+	tag = "job",
+	params(
+		("id" = String, Path, description = "The ID of the job schedule.")
+	),
+	responses(
+		(status = 200, description = "Successfully triggered job schedule", body = JobSchedule),
+		(status = 401, description = "No user is logged in (unauthorized)."),
+		(status = 403, description = "User does not have permission to access this resource."),
+		(status = 404, description = "Job schedule not found."),
+		(status = 500, description = "Internal server error."),
+	)
+)]
+/// Manually run a job schedule now, even if it is paused
+async fn trigger_job_schedule(
+	State(ctx): State<AppState>,
+	Path(id): Path<String>,
+) -> APIResult<Json<JobSchedule>> {
+	let schedule = JobScheduler::run_now(&ctx, id).await?;
+	Ok(Json(schedule))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn input() -> CreateOrUpdateJobSchedule {
+		CreateOrUpdateJobSchedule {
+			name: "Nightly".to_string(),
+			job_kind: ScheduledJobKind::LibraryScan,
+			cron_expression: Some("0 3 * * *".to_string()),
+			interval_secs: None,
+			config: ScheduledJobConfig::default(),
+			included_library_ids: vec![],
+			excluded_library_ids: vec![],
+			is_paused: false,
+		}
+	}
 
-	Ok(Json(updated_or_deleted_config))
+	#[test]
+	fn test_validate_job_schedule() {
+		assert!(input().validate().is_ok());
+		assert!(CreateOrUpdateJobSchedule {
+			included_library_ids: vec!["library".to_string()],
+			..input()
+		}
+		.validate()
+		.is_ok());
+		assert!(CreateOrUpdateJobSchedule {
+			job_kind: ScheduledJobKind::SessionCleanup,
+			excluded_library_ids: vec!["library".to_string()],
+			..input()
+		}
+		.validate()
+		.is_err());
+		assert!(CreateOrUpdateJobSchedule {
+			cron_expression: Some("not a cron".to_string()),
+			..input()
+		}
+		.validate()
+		.is_err());
+		assert!(CreateOrUpdateJobSchedule {
+			cron_expression: Some("0 0 0 1 1 * 2000".to_string()),
+			..input()
+		}
+		.validate()
+		.is_err());
+	}
 }
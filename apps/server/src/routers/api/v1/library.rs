@@ -24,8 +24,8 @@ use stump_core::{
 				library_series_ids_media_ids_include, library_tags_select,
 				library_thumbnails_deletion_include, series_or_library_thumbnail,
 			},
-			FileStatus, Library, LibraryConfig, LibraryScanMode, LibraryStats, Media,
-			Series, TagName, User, UserPermission,
+			AuditAction, AuditEvent, FileStatus, Library, LibraryConfig, LibraryScanMode,
+			LibraryStats, Media, Series, TagName, User, UserPermission,
 		},
 		query::pagination::{
 			Pageable, PageableLibraries, PageableSeries, Pagination, PaginationQuery,
@@ -62,7 +62,7 @@ use crate::{
 	routers::api::filters::{
 		apply_library_filters_for_user, apply_media_age_restriction, apply_media_filters,
 		apply_media_pagination, apply_series_age_restriction, apply_series_filters,
-		library_not_hidden_from_user_filter,
+		library_not_hidden_from_user_filter, series_not_hidden_from_user_filter,
 	},
 	utils::{http::ImageResponse, validate_and_load_image},
 };
@@ -410,7 +410,7 @@ async fn get_library_series(
 		.chain(chain_optional_iter(
 			[
 				series::library_id::equals(Some(id.clone())),
-				series::library::is(vec![library_not_hidden_from_user_filter(user)]),
+				series_not_hidden_from_user_filter(user),
 			],
 			[age_restrictions],
 		))
@@ -486,7 +486,7 @@ async fn get_library_media(
 		.into_iter()
 		.chain([media::series::is(vec![
 			series::library_id::equals(Some(id.clone())),
-			series::library::is(vec![library_not_hidden_from_user_filter(user)]),
+			series_not_hidden_from_user_filter(user),
 		])])
 		.collect::<Vec<media::WhereParam>>();
 
@@ -576,7 +576,7 @@ async fn get_library_thumbnail_handler(
 	let series_filters = chain_optional_iter(
 		[
 			series::library_id::equals(Some(id.clone())),
-			series::library::is(vec![library_not_hidden_from_user_filter(user)]),
+			series_not_hidden_from_user_filter(user),
 		],
 		[age_restriction
 			.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset))],
@@ -659,7 +659,7 @@ async fn patch_library_thumbnail(
 		.find_first(vec![
 			media::series::is(vec![
 				series::library_id::equals(Some(id.clone())),
-				series::library::is(vec![library_not_hidden_from_user_filter(&user)]),
+				series_not_hidden_from_user_filter(&user),
 			]),
 			media::id::equals(body.media_id),
 		])
@@ -968,6 +968,10 @@ async fn update_library_excluded_users(
 	}
 
 	let hidden_from_users = library.hidden_from_users()?.to_owned();
+	let excluded_usernames_before = hidden_from_users
+		.iter()
+		.map(|u| u.username.clone())
+		.collect::<Vec<_>>();
 	let user_ids = input.user_ids;
 
 	let to_add = user_ids
@@ -999,6 +1003,21 @@ async fn update_library_excluded_users(
 		.exec()
 		.await?;
 
+	let excluded_usernames_after = updated_library
+		.hidden_from_users()?
+		.iter()
+		.map(|u| u.username.clone())
+		.collect::<Vec<_>>();
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::LibraryAccessChanged, Some(req.user()))
+			.with_target(&updated_library.id, &updated_library.name)
+			.with_changes(
+				Some(&serde_json::json!({ "excluded_users": excluded_usernames_before })),
+				Some(&serde_json::json!({ "excluded_users": excluded_usernames_after })),
+			),
+	)
+	.await;
+
 	Ok(Json(Library::from(updated_library)))
 }
 
@@ -1503,6 +1522,13 @@ async fn create_library(
 		.await;
 
 	let library = transaction_result?;
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::LibraryCreated, Some(req.user()))
+			.with_target(&library.id, &library.name)
+			.with_changes(None, Some(&library)),
+	)
+	.await;
+
 	let scan_mode = input.scan_mode.unwrap_or_default();
 	if scan_mode != LibraryScanMode::None {
 		ctx.enqueue_job(LibraryScanJob::new(
@@ -1599,6 +1625,14 @@ async fn update_library(
 		.await?
 		.ok_or(APIError::NotFound("Library not found".to_string()))?;
 	let existing_tags = existing_library.tags;
+	let library_before = db
+		.library()
+		.find_unique(library::id::equals(id.clone()))
+		.with(library::tags::fetch(vec![]))
+		.with(library::config::fetch())
+		.exec()
+		.await?
+		.map(Library::from);
 
 	let watch = input.config.watch;
 	let path = input.path.clone();
@@ -1742,12 +1776,19 @@ async fn update_library(
 				.library()
 				.update(library::id::equals(id), set_params)
 				.with(library::tags::fetch(vec![]))
+				.with(library::config::fetch())
 				.exec()
 				.await
 				.map(Library::from)?)
 		})
 		.await;
 	let updated_library = update_result?;
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::LibraryUpdated, Some(&user))
+			.with_target(&updated_library.id, &updated_library.name)
+			.with_changes(library_before.as_ref(), Some(&updated_library)),
+	)
+	.await;
 
 	let scan_mode = input.scan_mode.unwrap_or_default();
 
@@ -1819,7 +1860,7 @@ async fn delete_library(
 	// TODO: This is not ideal, but `delete_many` only returns affected rows, so
 	// I can't do the exact same ops. I want to revisit this though, this API is one
 	// of the older ones and could use a refactor
-	let _can_access = db
+	let library_to_delete = db
 		.library()
 		.find_first(vec![
 			library::id::equals(id.clone()),
@@ -1827,6 +1868,7 @@ async fn delete_library(
 		])
 		.exec()
 		.await?
+		.map(Library::from)
 		.ok_or(APIError::NotFound("Library not found".to_string()))?;
 
 	let deleted_library = db
@@ -1835,6 +1877,12 @@ async fn delete_library(
 		.include(library_series_ids_media_ids_include::include())
 		.exec()
 		.await?;
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::LibraryDeleted, Some(&user))
+			.with_target(&library_to_delete.id, &library_to_delete.name)
+			.with_changes(Some(&library_to_delete), None),
+	)
+	.await;
 
 	let media_ids = deleted_library
 		.series
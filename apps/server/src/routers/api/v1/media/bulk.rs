@@ -1,25 +1,30 @@
 use axum::{extract::State, Extension, Json};
 use axum_extra::extract::Query;
 use prisma_client_rust::{raw, Direction, PrismaValue};
+use serde::Deserialize;
 use serde_qs::axum::QsQuery;
+use specta::Type;
 use stump_core::{
 	db::{
-		entity::Media,
+		entity::{Media, UserPermission},
 		query::pagination::{
 			PageQuery, Pageable, PageableMedia, Pagination, PaginationQuery,
 		},
 		CountQueryReturn,
 	},
+	filesystem::write_metadata_job::WriteMetadataJob,
 	prisma::{
 		active_reading_session, finished_reading_session,
 		media::{self, OrderByParam as MediaOrderByParam, WhereParam},
 	},
 };
 
+use utoipa::ToSchema;
+
 use crate::{
 	config::state::AppState,
 	errors::{APIError, APIResult},
-	filter::{FilterableMediaQuery, FilterableQuery, MediaFilter},
+	filter::{chain_optional_iter, FilterableMediaQuery, FilterableQuery, MediaFilter},
 	middleware::auth::RequestContext,
 	routers::api::filters::{
 		apply_media_age_restriction, apply_media_filters_for_user,
@@ -145,6 +150,9 @@ pub(crate) async fn get_media(
 /// the file, so if a 2 comic books had say the same first 6 pages it might return a
 /// false positive). This is a paginated request, and has various pagination
 /// params available, but hopefully you won't have that many duplicates ;D
+///
+/// For a more reliable comparison, see the duplicate analysis job and the clusters it
+/// produces (`/api/v1/media/duplicates/clusters`)
 pub(crate) async fn get_duplicate_media(
 	pagination: Query<PageQuery>,
 	State(ctx): State<AppState>,
@@ -178,7 +186,7 @@ pub(crate) async fn get_duplicate_media(
 			r"
 			SELECT COUNT(*) as count FROM media
 			WHERE hash IN (
-				SELECT hash FROM media GROUP BY hash HAVING COUNT(*) s> 1
+				SELECT hash FROM media GROUP BY hash HAVING COUNT(*) > 1
 			)"
 		))
 		.exec()
@@ -370,3 +378,75 @@ pub(crate) async fn get_recently_added_media(
 
 	Ok(Json(Pageable::from(media)))
 }
+
+/// The books to write metadata to file for
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub(crate) struct WriteMediaMetadataToFiles {
+	pub media_ids: Vec<String>,
+	/// Whether to convert RAR archives to CBZ so their metadata can be written. RAR archives
+	/// are skipped otherwise
+	#[serde(default)]
+	pub convert_rar_to_zip: bool,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/metadata/write",
+	tag = "media",
+	request_body = WriteMediaMetadataToFiles,
+	responses(
+		(status = 200, description = "Successfully started writing metadata to files"),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "One or more media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Write the metadata of multiple books, as it is stored in Stump, to a ComicInfo.xml inside
+/// of each book's file. Books which can't be written to (e.g. EPUBs) are skipped and
+/// reported in the job's logs
+pub(crate) async fn write_media_metadata_to_files(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(payload): Json<WriteMediaMetadataToFiles>,
+) -> APIResult<()> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let mut media_ids = payload.media_ids;
+	media_ids.sort();
+	media_ids.dedup();
+	if media_ids.is_empty() {
+		return Err(APIError::BadRequest(
+			"At least one media ID is required".to_string(),
+		));
+	}
+
+	let age_restrictions = user
+		.age_restriction
+		.as_ref()
+		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
+	let where_params = chain_optional_iter(
+		[media::id::in_vec(media_ids.clone())]
+			.into_iter()
+			.chain(apply_media_library_not_hidden_for_user_filter(&user))
+			.collect::<Vec<WhereParam>>(),
+		[age_restrictions],
+	);
+
+	let found_count = ctx.db.media().count(where_params).exec().await?;
+	if found_count != media_ids.len() as i64 {
+		return Err(APIError::NotFound(
+			"One or more media not found".to_string(),
+		));
+	}
+
+	ctx.enqueue_job(WriteMetadataJob::new(media_ids, payload.convert_rar_to_zip))
+		.map_err(|e| {
+			let err = "Failed to enqueue write metadata job";
+			tracing::error!(?e, err);
+			APIError::InternalServerError(err.to_string())
+		})?;
+
+	Ok(())
+}
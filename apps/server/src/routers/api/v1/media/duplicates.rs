@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+
+use axum::{
+	extract::{Path, State},
+	Extension, Json,
+};
+use prisma_client_rust::{chrono::Utc, Direction};
+use serde::Deserialize;
+use specta::Type;
+use stump_core::{
+	db::entity::{DuplicateCluster, Media, UserPermission},
+	filesystem::duplicate_analysis_job::DuplicateAnalysisJob,
+	prisma::{
+		active_reading_session, duplicate_cluster, finished_reading_session, media,
+		PrismaClient,
+	},
+};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::RequestContext,
+	routers::api::filters::apply_media_restrictions_for_user,
+};
+
+/// Options for the duplicate analysis job
+#[derive(Debug, Default, Deserialize, ToSchema, Type)]
+pub(crate) struct StartDuplicateAnalysis {
+	/// Whether to recompute the checksums and cover hashes of books which have already been
+	/// hashed. Only books which are missing a hash are hashed otherwise
+	#[serde(default)]
+	pub force: bool,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/duplicates/analyze",
+	tag = "media",
+	request_body = StartDuplicateAnalysis,
+	responses(
+		(status = 200, description = "Successfully started duplicate analysis"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Start a job which computes the full checksums and cover hashes of books, then groups
+/// likely duplicates into clusters. Any existing clusters are replaced once the job completes
+pub(crate) async fn start_duplicate_analysis(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(payload): Json<StartDuplicateAnalysis>,
+) -> APIResult<()> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	ctx.enqueue_job(DuplicateAnalysisJob::new(payload.force))
+		.map_err(|e| {
+			let err = "Failed to enqueue duplicate analysis job";
+			tracing::error!(?e, err);
+			APIError::InternalServerError(err.to_string())
+		})?;
+
+	Ok(())
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/duplicates/clusters",
+	tag = "media",
+	responses(
+		(status = 200, description = "Successfully fetched duplicate clusters", body = [DuplicateCluster]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the clusters of likely duplicate books found by the last duplicate analysis, ordered
+/// from most to least confident. Only the books the user has access to are included
+pub(crate) async fn get_duplicate_clusters(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<DuplicateCluster>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let user = req.user();
+
+	let clusters = ctx
+		.db
+		.duplicate_cluster()
+		.find_many(vec![])
+		.with(
+			duplicate_cluster::media::fetch(apply_media_restrictions_for_user(user))
+				.with(media::metadata::fetch()),
+		)
+		.order_by(duplicate_cluster::confidence::order(Direction::Desc))
+		.exec()
+		.await?
+		.into_iter()
+		.filter(is_cluster_visible)
+		.map(DuplicateCluster::from)
+		.collect();
+
+	Ok(Json(clusters))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/media/duplicates/clusters/{id}",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the cluster to dismiss")
+	),
+	responses(
+		(status = 200, description = "Successfully dismissed duplicate cluster", body = DuplicateCluster),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Cluster not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Dismiss a cluster without changing any of its books, e.g. because they are not actually
+/// duplicates. The books may be clustered again by a later analysis
+pub(crate) async fn delete_duplicate_cluster(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<DuplicateCluster>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let user = req.user();
+	let client = &ctx.db;
+
+	client
+		.duplicate_cluster()
+		.find_unique(duplicate_cluster::id::equals(id.clone()))
+		.with(duplicate_cluster::media::fetch(
+			apply_media_restrictions_for_user(user),
+		))
+		.exec()
+		.await?
+		.filter(is_cluster_visible)
+		.ok_or(APIError::NotFound(
+			"Duplicate cluster not found".to_string(),
+		))?;
+
+	let deleted_cluster = client
+		.duplicate_cluster()
+		.delete(duplicate_cluster::id::equals(id))
+		.exec()
+		.await?;
+
+	Ok(Json(DuplicateCluster::from(deleted_cluster)))
+}
+
+/// How to resolve a cluster of duplicate books
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub(crate) struct ResolveDuplicateCluster {
+	/// The ID of the book to keep. It must belong to the cluster
+	pub keep_media_id: String,
+	/// Whether to soft-delete the other books in the cluster. Soft-deleted books are hidden
+	/// from users, but their files are left untouched
+	#[serde(default)]
+	pub soft_delete_others: bool,
+	/// Whether to move the reading progress of the other books to the kept book. For each
+	/// user, the most advanced active session is kept and all finished sessions are moved
+	#[serde(default)]
+	pub merge_progress: bool,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/duplicates/clusters/{id}/resolve",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the cluster to resolve")
+	),
+	request_body = ResolveDuplicateCluster,
+	responses(
+		(status = 200, description = "Successfully resolved duplicate cluster", body = Media),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Cluster not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Resolve a cluster of duplicate books by keeping one of them. The other books may be
+/// soft-deleted, and their reading progress merged into the kept book. The cluster is
+/// deleted once resolved. Books the user does not have access to are left untouched
+pub(crate) async fn resolve_duplicate_cluster(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(payload): Json<ResolveDuplicateCluster>,
+) -> APIResult<Json<Media>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+	let user = req.user();
+
+	let cluster = ctx
+		.db
+		.duplicate_cluster()
+		.find_unique(duplicate_cluster::id::equals(id.clone()))
+		.with(duplicate_cluster::media::fetch(
+			apply_media_restrictions_for_user(user),
+		))
+		.exec()
+		.await?
+		.filter(is_cluster_visible)
+		.ok_or(APIError::NotFound(
+			"Duplicate cluster not found".to_string(),
+		))?;
+
+	let cluster_media = cluster.media().cloned().unwrap_or_default();
+	let Some(kept) = cluster_media
+		.iter()
+		.find(|m| m.id == payload.keep_media_id)
+		.cloned()
+	else {
+		let is_accessible = ctx
+			.db
+			.media()
+			.count(
+				[media::id::equals(payload.keep_media_id.clone())]
+					.into_iter()
+					.chain(apply_media_restrictions_for_user(user))
+					.collect(),
+			)
+			.exec()
+			.await? > 0;
+
+		return Err(if is_accessible {
+			APIError::BadRequest(
+				"The book to keep must belong to the cluster".to_string(),
+			)
+		} else {
+			APIError::NotFound("Media not found".to_string())
+		});
+	};
+	let other_ids = cluster_media
+		.into_iter()
+		.filter(|m| m.id != kept.id)
+		.map(|m| m.id)
+		.collect::<Vec<_>>();
+
+	let restrictions = apply_media_restrictions_for_user(user);
+	let kept_media = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			if payload.merge_progress {
+				merge_reading_progress(&client, &kept, &other_ids).await?;
+			}
+
+			if payload.soft_delete_others {
+				client
+					.media()
+					.update_many(
+						[media::id::in_vec(other_ids)]
+							.into_iter()
+							.chain(restrictions)
+							.collect(),
+						vec![media::deleted_at::set(Some(Utc::now().into()))],
+					)
+					.exec()
+					.await?;
+			}
+
+			client
+				.duplicate_cluster()
+				.delete(duplicate_cluster::id::equals(id))
+				.exec()
+				.await?;
+
+			Ok::<_, prisma_client_rust::QueryError>(kept)
+		})
+		.await?;
+
+	Ok(Json(Media::from(kept_media)))
+}
+
+/// Move the reading sessions of the duplicate books to the kept book. Every finished session
+/// is moved as-is, but only one active session may exist per user and book, so the most
+/// advanced one (by percentage, then page, then recency) is kept and the rest are deleted
+async fn merge_reading_progress(
+	client: &PrismaClient,
+	kept: &media::Data,
+	other_ids: &[String],
+) -> Result<(), prisma_client_rust::QueryError> {
+	client
+		.finished_reading_session()
+		.update_many(
+			vec![finished_reading_session::media_id::in_vec(
+				other_ids.to_vec(),
+			)],
+			vec![finished_reading_session::media_id::set(kept.id.clone())],
+		)
+		.exec()
+		.await?;
+
+	let sessions = client
+		.active_reading_session()
+		.find_many(vec![active_reading_session::media_id::in_vec(
+			other_ids
+				.iter()
+				.cloned()
+				.chain(std::iter::once(kept.id.clone()))
+				.collect(),
+		)])
+		.exec()
+		.await?;
+
+	let mut most_advanced = HashMap::<String, active_reading_session::Data>::new();
+	for session in sessions {
+		match most_advanced.get(&session.user_id) {
+			Some(current) if !is_further_along(&session, current) => {},
+			_ => {
+				most_advanced.insert(session.user_id.clone(), session);
+			},
+		}
+	}
+	let kept_session_ids = most_advanced
+		.values()
+		.map(|session| session.id.clone())
+		.collect::<Vec<_>>();
+
+	client
+		.active_reading_session()
+		.delete_many(vec![
+			active_reading_session::media_id::in_vec(
+				other_ids
+					.iter()
+					.cloned()
+					.chain(std::iter::once(kept.id.clone()))
+					.collect(),
+			),
+			active_reading_session::id::not_in_vec(kept_session_ids),
+		])
+		.exec()
+		.await?;
+
+	for session in most_advanced.into_values() {
+		if session.media_id == kept.id {
+			continue;
+		}
+
+		// The duplicate may not have exactly the same number of pages as the kept book
+		let page = session.page.map(|page| page.min(kept.pages));
+		client
+			.active_reading_session()
+			.update(
+				active_reading_session::id::equals(session.id),
+				vec![
+					active_reading_session::media::connect(media::id::equals(
+						kept.id.clone(),
+					)),
+					active_reading_session::page::set(page),
+				],
+			)
+			.exec()
+			.await?;
+	}
+
+	Ok(())
+}
+
+fn is_further_along(
+	session: &active_reading_session::Data,
+	other: &active_reading_session::Data,
+) -> bool {
+	let percentage = session.percentage_completed.unwrap_or_default();
+	let other_percentage = other.percentage_completed.unwrap_or_default();
+
+	percentage
+		.total_cmp(&other_percentage)
+		.then(session.page.cmp(&other.page))
+		.then(session.updated_at.cmp(&other.updated_at))
+		.is_gt()
+}
+
+/// A cluster is only shown to a user if they can access at least two of its books, since
+/// there is nothing for them to resolve otherwise
+fn is_cluster_visible(cluster: &duplicate_cluster::Data) -> bool {
+	cluster.media().map(Vec::len).unwrap_or_default() > 1
+}
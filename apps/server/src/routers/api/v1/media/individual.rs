@@ -4,23 +4,36 @@ use axum::{
 	extract::{Path, Query, State},
 	Extension, Json,
 };
-use prisma_client_rust::{chrono::Duration, Direction};
+use prisma_client_rust::{
+	chrono::{Duration, Utc},
+	Direction,
+};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use specta::Type;
 use stump_core::{
-	db::entity::{
-		macros::{
-			finished_reading_session_with_book_pages, media_id_select,
-			reading_session_with_book_pages,
+	db::{
+		entity::{
+			macros::{
+				finished_reading_session_with_book_pages, media_id_select,
+				reading_session_with_book_pages,
+			},
+			ActiveReadingSession, FinishedReadingSession, Media, MediaMetadata,
+			MediaMetadataField, MetadataSource, PageDimension, PageDimensionsEntity,
+			PdfOutlineItem, ProgressUpdateReturn, User, UserPermission,
 		},
-		ActiveReadingSession, FinishedReadingSession, Media, MediaMetadata,
-		PageDimension, PageDimensionsEntity, ProgressUpdateReturn, User, UserPermission,
+		metadata::{write_media_metadata, MetadataChange, MetadataChangeActor},
+		search::index_media,
 	},
 	filesystem::{
 		analyze_media_job::AnalyzeMediaJob,
 		get_page_async,
-		image::{resize_image, ScaledDimensionResize},
+		image::{
+			get_transformed_page, resize_image, PageTransformOptions,
+			ScaledDimensionResize,
+		},
+		pdf::PdfProcessor,
+		write_metadata_job::WriteMetadataJob,
 	},
 	prisma::{
 		active_reading_session, finished_reading_session, library,
@@ -29,6 +42,7 @@ use stump_core::{
 	},
 	Ctx,
 };
+use tokio::task::spawn_blocking;
 use tracing::error;
 use utoipa::ToSchema;
 
@@ -319,26 +333,43 @@ impl RequestPageScaled {
 	tag = "media",
 	params(
 		("id" = String, Path, description = "The ID of the media to get"),
-		("page" = i32, Path, description = "The page to get")
+		("page" = i32, Path, description = "The page to get"),
+		("max_width" = Option<u32>, Query, description = "The maximum width of the page"),
+		("max_height" = Option<u32>, Query, description = "The maximum height of the page"),
+		("upscale" = Option<bool>, Query, description = "Whether to upscale pages smaller than the maximum dimensions"),
+		("format" = Option<String>, Query, description = "The format to encode the page as: Webp, Jpeg or Png"),
+		("quality" = Option<f32>, Query, description = "The quality to encode the page with, between 0 and 100"),
+		("trim" = Option<bool>, Query, description = "Whether to trim the white borders of the page"),
+		("split" = Option<String>, Query, description = "Which half of a double-page spread to return: left or right"),
 	),
 	responses(
 		(status = 200, description = "Successfully fetched media"),
+		(status = 400, description = "Bad request"),
 		(status = 401, description = "Unauthorized"),
 		(status = 403, description = "Forbidden"),
 		(status = 404, description = "Media not found"),
 		(status = 500, description = "Internal server error"),
 	)
 )]
-/// Get a page of a media
+/// Get a page of a media. Transformations (resizing, trimming, splitting and re-encoding)
+/// can be requested with the query parameters, in which case the transformed page is
+/// cached on disk for subsequent requests
 pub(crate) async fn get_media_page(
 	Path((id, page)): Path<(String, i32)>,
 	State(ctx): State<AppState>,
 	Extension(req): Extension<RequestContext>,
 	Query(requested_scale): Query<RequestPageScaled>,
+	Query(transform): Query<PageTransformOptions>,
 ) -> APIResult<ImageResponse> {
 	let db = &ctx.db;
 
-	tracing::trace!(?id, ?page, ?requested_scale, "Fetching media page");
+	tracing::trace!(
+		?id,
+		?page,
+		?requested_scale,
+		?transform,
+		"Fetching media page"
+	);
 
 	let user = req.user();
 	let user_id = user.id.clone();
@@ -368,6 +399,26 @@ pub(crate) async fn get_media_page(
 		Err(APIError::BadRequest(format!(
 			"Page {page} is out of bounds for media {id}"
 		)))
+	} else if !transform.is_empty() {
+		// The version ensures cached pages are not served after the file changes
+		let version = format!(
+			"{}:{}",
+			media.size,
+			media
+				.modified_at
+				.map(|date| date.timestamp())
+				.unwrap_or_default()
+		);
+		let (content_type, data) = get_transformed_page(
+			&media.id,
+			&media.path,
+			&version,
+			page,
+			transform,
+			&ctx.config,
+		)
+		.await?;
+		Ok(ImageResponse { content_type, data })
 	} else {
 		let (content_type, buf) = get_page_async(&media.path, page, &ctx.config).await?;
 		let scaled_buf = match requested_scale.to_scaled_dimension() {
@@ -441,8 +492,15 @@ pub(crate) async fn update_media_progress(
 					],
 				),
 			),
+			// Any KOReader progress or locator would be stale after this update. KOReader will
+			// fall back to the page (or a translation of the epubcfi) instead
 			chain_optional_iter(
-				[active_reading_session::page::set(Some(page))],
+				[
+					active_reading_session::page::set(Some(page)),
+					active_reading_session::koreader_progress::set(None),
+					active_reading_session::locator_href::set(None),
+					active_reading_session::locator_progression::set(None),
+				],
 				[
 					epubcfi.map(|cfi| active_reading_session::epubcfi::set(Some(cfi))),
 					elapsed_seconds
@@ -475,7 +533,9 @@ pub(crate) async fn update_media_progress(
 
 				tx.finished_reading_session()
 					.create(
-						deleted_session.map(|s| s.started_at).unwrap_or_default(),
+						deleted_session
+							.map(|s| s.started_at)
+							.unwrap_or_else(|| Utc::now().into()),
 						media::id::equals(id.clone()),
 						user::id::equals(user_id.clone()),
 						chain_optional_iter(
@@ -698,7 +758,9 @@ pub(crate) async fn put_media_complete_status(
 
 				tx.finished_reading_session()
 					.create(
-						deleted_session.map(|s| s.started_at).unwrap_or_default(),
+						deleted_session
+							.map(|s| s.started_at)
+							.unwrap_or_else(|| Utc::now().into()),
 						media::id::equals(id.clone()),
 						user::id::equals(user_id.clone()),
 						vec![],
@@ -842,6 +904,65 @@ pub(crate) async fn get_media_page_dimensions(
 	Ok(Json(page_dimension.to_owned()))
 }
 
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/{id}/outline",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media to get the outline for")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched media outline", body = [PdfOutlineItem]),
+		(status = 400, description = "Media is not a PDF"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the outline (bookmarks) of a PDF, which typically serves as its table of contents.
+/// An empty list is returned if the PDF does not have an outline
+pub(crate) async fn get_media_outline(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<PdfOutlineItem>>> {
+	let user = req.user();
+	let age_restrictions = user
+		.age_restriction
+		.as_ref()
+		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
+	let where_params = chain_optional_iter(
+		[media::id::equals(id.clone())]
+			.into_iter()
+			.chain(apply_media_library_not_hidden_for_user_filter(user))
+			.collect::<Vec<WhereParam>>(),
+		[age_restrictions],
+	);
+
+	let media = ctx
+		.db
+		.media()
+		.find_first(where_params)
+		.select(media::select!({ path extension }))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Media not found")))?;
+
+	if !media.extension.eq_ignore_ascii_case("pdf") {
+		return Err(APIError::BadRequest(String::from(
+			"Outlines are only available for PDF files",
+		)));
+	}
+
+	let config = ctx.config.as_ref().clone();
+	let outline = spawn_blocking(move || PdfProcessor::get_outline(&media.path, &config))
+		.await
+		.map_err(|e| APIError::InternalServerError(e.to_string()))??;
+
+	Ok(Json(outline))
+}
+
 async fn fetch_media_page_dimensions_with_permissions(
 	ctx: &Arc<Ctx>,
 	user: &User,
@@ -946,7 +1067,8 @@ pub(crate) async fn get_media_metadata(
 	)
 )]
 /// Update the metadata for a media record. This is a full update, so any existing metadata
-/// will be replaced with the new metadata.
+/// will be replaced with the new metadata. The fields which changed are locked, so they
+/// aren't overwritten by the metadata in the file the next time it is scanned
 pub(crate) async fn put_media_metadata(
 	Path(id): Path<String>,
 	State(ctx): State<AppState>,
@@ -977,23 +1099,100 @@ pub(crate) async fn put_media_metadata(
 		.await?
 		.ok_or(APIError::NotFound(String::from("Media not found")))?;
 
-	let set_params = metadata.into_prisma();
+	// Edited fields are locked, so the next scan doesn't replace them with what is in the file
+	let change = MetadataChange::new(
+		MetadataSource::User,
+		Some(MetadataChangeActor::User(user.id.clone())),
+	)
+	.with_lock(true);
+	let (written, meta) = db
+		._transaction()
+		.run(|client| async move {
+			write_media_metadata(
+				&client,
+				&book.id,
+				&metadata,
+				MediaMetadataField::ALL,
+				&change,
+			)
+			.await
+		})
+		.await?;
+	if !written.applied.is_empty() {
+		index_media(db, &[id]).await?;
+	}
+
+	Ok(Json(meta.map(MediaMetadata::from).unwrap_or_default()))
+}
 
-	let meta = db
-		.media_metadata()
-		.upsert(
-			media_metadata::media_id::equals(id.clone()),
-			set_params
-				.clone()
-				.into_iter()
-				.chain(vec![media_metadata::media::connect(media::id::equals(
-					book.id.clone(),
-				))])
-				.collect::<Vec<_>>(),
-			set_params.clone(),
-		)
+/// Options for writing the metadata of books to their files
+#[derive(Default, Debug, Deserialize, ToSchema, Type)]
+pub(crate) struct WriteMetadataParams {
+	/// Whether to convert RAR archives to CBZ so their metadata can be written. RAR archives
+	/// are skipped otherwise
+	#[serde(default)]
+	pub convert_rar_to_zip: bool,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/{id}/metadata/write",
+	tag = "media",
+	params(
+		("id" = String, Path, description = "The ID of the media"),
+		("convert_rar_to_zip" = Option<bool>, Query, description = "Whether to convert a RAR archive to CBZ so its metadata can be written")
+	),
+	responses(
+		(status = 200, description = "Successfully started writing metadata to file"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Write the metadata of a book, as it is stored in Stump, to a ComicInfo.xml inside of
+/// the book's file. Only CBZ/ZIP archives are supported, unless `convert_rar_to_zip` is set
+/// (in which case RAR archives are converted to CBZ first). The write happens in a
+/// background job
+pub(crate) async fn write_media_metadata_to_file(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Query(params): Query<WriteMetadataParams>,
+) -> APIResult<()> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let user = req.user();
+	let age_restrictions = user
+		.age_restriction
+		.as_ref()
+		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
+	let where_params = chain_optional_iter(
+		[media::id::equals(id.clone())]
+			.into_iter()
+			.chain(apply_media_library_not_hidden_for_user_filter(user))
+			.collect::<Vec<WhereParam>>(),
+		[age_restrictions],
+	);
+
+	let book = ctx
+		.db
+		.media()
+		.find_first(where_params)
+		.select(media_id_select::select())
 		.exec()
-		.await?;
+		.await?
+		.ok_or(APIError::NotFound(String::from("Media not found")))?;
+
+	ctx.enqueue_job(WriteMetadataJob::new(
+		vec![book.id],
+		params.convert_rar_to_zip,
+	))
+	.map_err(|e| {
+		let err = "Failed to enqueue write metadata job";
+		error!(?e, err);
+		APIError::InternalServerError(err.to_string())
+	})?;
 
-	Ok(Json(MediaMetadata::from(meta)))
+	APIResult::Ok(())
 }
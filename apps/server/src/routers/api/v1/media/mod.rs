@@ -1,11 +1,12 @@
 pub(crate) mod bulk;
+pub(crate) mod duplicates;
 pub(crate) mod individual;
 pub(crate) mod thumbnails;
 
 use axum::{
 	extract::{DefaultBodyLimit, Extension},
 	middleware,
-	routing::{get, post, put},
+	routing::{delete, get, post, put},
 	Router,
 };
 
@@ -17,8 +18,28 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 	Router::new()
 		.route("/media", get(bulk::get_media))
 		.route("/media/duplicates", get(bulk::get_duplicate_media))
+		.route(
+			"/media/duplicates/analyze",
+			post(duplicates::start_duplicate_analysis),
+		)
+		.route(
+			"/media/duplicates/clusters",
+			get(duplicates::get_duplicate_clusters),
+		)
+		.route(
+			"/media/duplicates/clusters/{id}",
+			delete(duplicates::delete_duplicate_cluster),
+		)
+		.route(
+			"/media/duplicates/clusters/{id}/resolve",
+			post(duplicates::resolve_duplicate_cluster),
+		)
 		.route("/media/keep-reading", get(bulk::get_in_progress_media))
 		.route("/media/recently-added", get(bulk::get_recently_added_media))
+		.route(
+			"/media/metadata/write",
+			post(bulk::write_media_metadata_to_files),
+		)
 		.route("/media/path/{path}", get(individual::get_media_by_path))
 		.nest(
 			"/media/{id}",
@@ -49,6 +70,7 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 						.put(individual::put_media_complete_status),
 				)
 				.route("/dimensions", get(individual::get_media_dimensions))
+				.route("/outline", get(individual::get_media_outline))
 				.route(
 					"/page/{page}/dimensions",
 					get(individual::get_media_page_dimensions),
@@ -57,6 +79,10 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 					"/metadata",
 					get(individual::get_media_metadata)
 						.put(individual::put_media_metadata),
+				)
+				.route(
+					"/metadata/write",
+					post(individual::write_media_metadata_to_file),
 				),
 		)
 		.layer(Extension(QsQueryConfig::new(5, false)))
@@ -0,0 +1,709 @@
+use std::collections::HashSet;
+
+use axum::{
+	extract::{Path, Query, State},
+	middleware,
+	routing::{delete, get, post, put},
+	Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use stump_core::{
+	db::{
+		entity::{
+			MediaMetadataField, MetadataCandidate, MetadataFieldChange,
+			MetadataFieldState, MetadataProviderType, SeriesMetadataField, User,
+			UserPermission,
+		},
+		metadata::{
+			apply_media_candidate, apply_series_candidate, get_field_changes,
+			get_field_states, set_field_lock, AppliedMetadata, MetadataChangeActor,
+			MetadataTarget,
+		},
+	},
+	filesystem::media::metadata_fetch_job::{
+		available_metadata_providers, MetadataFetchJob, MetadataFetchJobVariant,
+		MetadataFetchOptions,
+	},
+	prisma::{library, media, metadata_candidate, series, PrismaClient},
+};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::filters::{
+		apply_media_restrictions_for_user, apply_series_restrictions_for_user,
+		library_not_hidden_from_user_filter,
+	},
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.nest(
+			"/metadata",
+			Router::new()
+				.route("/providers", get(get_metadata_providers))
+				.route("/fetch", post(fetch_metadata))
+				.nest(
+					"/candidates/{id}",
+					Router::new()
+						.route("/", delete(delete_metadata_candidate))
+						.route("/apply", post(apply_metadata_candidate)),
+				),
+		)
+		.nest(
+			"/media/{id}/metadata",
+			Router::new()
+				.route("/candidates", get(get_media_metadata_candidates))
+				.route("/fields", get(get_media_metadata_fields))
+				.route("/history", get(get_media_metadata_history))
+				.route("/fields/{field}/lock", put(lock_media_metadata_field)),
+		)
+		.nest(
+			"/series/{id}/metadata",
+			Router::new()
+				.route("/candidates", get(get_series_metadata_candidates))
+				.route("/fields", get(get_series_metadata_fields))
+				.route("/history", get(get_series_metadata_history))
+				.route("/fields/{field}/lock", put(lock_series_metadata_field)),
+		)
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+/// Ensure the book or series a metadata request targets is visible to the user. Managing
+/// libraries doesn't lift the restrictions of a user (e.g. hidden libraries or an age
+/// restriction), so content they can't see is reported as not found
+async fn enforce_target_access(
+	client: &PrismaClient,
+	user: &User,
+	target: &MetadataTarget,
+) -> APIResult<()> {
+	match target {
+		MetadataTarget::Media(id) => {
+			client
+				.media()
+				.find_first(
+					[media::id::equals(id.clone())]
+						.into_iter()
+						.chain(apply_media_restrictions_for_user(user))
+						.collect(),
+				)
+				.exec()
+				.await?
+				.ok_or(APIError::NotFound(String::from("Media not found")))?;
+		},
+		MetadataTarget::Series(id) => {
+			client
+				.series()
+				.find_first(
+					[series::id::equals(id.clone())]
+						.into_iter()
+						.chain(apply_series_restrictions_for_user(user))
+						.collect(),
+				)
+				.exec()
+				.await?
+				.ok_or(APIError::NotFound(String::from("Series not found")))?;
+		},
+	}
+
+	Ok(())
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/metadata/providers",
+	tag = "metadata",
+	responses(
+		(status = 200, description = "Successfully fetched the available metadata providers", body = [MetadataProviderType]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+	)
+)]
+/// Get the metadata providers which can be searched with the current configuration
+async fn get_metadata_providers(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataProviderType>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+	Ok(Json(available_metadata_providers(&ctx.config)))
+}
+
+#[derive(Debug, Deserialize, Serialize, Type, ToSchema)]
+pub struct FetchMetadata {
+	/// The books to fetch metadata for
+	#[serde(default)]
+	pub media_ids: Vec<String>,
+	/// The series whose books should have metadata fetched
+	pub series_id: Option<String>,
+	/// The library whose books should have metadata fetched
+	pub library_id: Option<String>,
+	#[serde(flatten)]
+	pub options: MetadataFetchOptions,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/metadata/fetch",
+	tag = "metadata",
+	request_body = FetchMetadata,
+	responses(
+		(status = 200, description = "Successfully started fetching metadata"),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Target not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Start a job which searches the metadata providers for books. Exactly one of the books,
+/// series or library to fetch for must be given
+async fn fetch_metadata(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<FetchMetadata>,
+) -> APIResult<()> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let variant = match (input.media_ids, input.series_id, input.library_id) {
+		(media_ids, None, None) if !media_ids.is_empty() => {
+			MetadataFetchJobVariant::Media(media_ids)
+		},
+		(media_ids, Some(series_id), None) if media_ids.is_empty() => {
+			MetadataFetchJobVariant::Series(series_id)
+		},
+		(media_ids, None, Some(library_id)) if media_ids.is_empty() => {
+			MetadataFetchJobVariant::Library(library_id)
+		},
+		_ => {
+			return Err(APIError::BadRequest(
+				"Exactly one of media_ids, series_id or library_id must be provided"
+					.to_string(),
+			))
+		},
+	};
+
+	if input
+		.options
+		.auto_apply_threshold
+		.is_some_and(|threshold| !(0.0..=1.0).contains(&threshold))
+	{
+		return Err(APIError::BadRequest(
+			"The auto apply threshold must be between 0 and 1".to_string(),
+		));
+	}
+
+	let client = &ctx.db;
+	let user = req.user();
+	match &variant {
+		MetadataFetchJobVariant::Media(media_ids) => {
+			let requested = media_ids.iter().collect::<HashSet<_>>().len() as i64;
+			let accessible = client
+				.media()
+				.count(
+					[media::id::in_vec(media_ids.clone())]
+						.into_iter()
+						.chain(apply_media_restrictions_for_user(user))
+						.collect(),
+				)
+				.exec()
+				.await?;
+			if accessible != requested {
+				return Err(APIError::NotFound(String::from("Media not found")));
+			}
+		},
+		MetadataFetchJobVariant::Series(series_id) => {
+			enforce_target_access(
+				client,
+				user,
+				&MetadataTarget::Series(series_id.clone()),
+			)
+			.await?;
+		},
+		MetadataFetchJobVariant::Library(library_id) => {
+			client
+				.library()
+				.find_first(vec![
+					library::id::equals(library_id.clone()),
+					library_not_hidden_from_user_filter(user),
+				])
+				.exec()
+				.await?
+				.ok_or(APIError::NotFound(String::from("Library not found")))?;
+		},
+	}
+
+	let job = MetadataFetchJob::new(variant, input.options, &ctx.config)?;
+	ctx.enqueue_job(job).map_err(|e| {
+		let err = "Failed to enqueue metadata fetch job";
+		error!(?e, err);
+		APIError::InternalServerError(err.to_string())
+	})?;
+
+	Ok(())
+}
+
+async fn get_candidates(
+	client: &PrismaClient,
+	filter: metadata_candidate::WhereParam,
+) -> APIResult<Vec<MetadataCandidate>> {
+	let candidates = client
+		.metadata_candidate()
+		.find_many(vec![filter])
+		.order_by(metadata_candidate::confidence::order(
+			prisma_client_rust::Direction::Desc,
+		))
+		.exec()
+		.await?
+		.into_iter()
+		.map(MetadataCandidate::try_from)
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(candidates)
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/{id}/metadata/candidates",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the media")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched metadata candidates", body = [MetadataCandidate]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the metadata candidates found for a book, from the most to the least confident
+async fn get_media_metadata_candidates(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataCandidate>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+	enforce_target_access(&ctx.db, req.user(), &MetadataTarget::Media(id.clone()))
+		.await?;
+	let candidates =
+		get_candidates(&ctx.db, metadata_candidate::media_id::equals(Some(id))).await?;
+	Ok(Json(candidates))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/series/{id}/metadata/candidates",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the series")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched metadata candidates", body = [MetadataCandidate]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the metadata candidates found for a series, from the most to the least confident
+async fn get_series_metadata_candidates(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataCandidate>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+	enforce_target_access(&ctx.db, req.user(), &MetadataTarget::Series(id.clone()))
+		.await?;
+	let candidates =
+		get_candidates(&ctx.db, metadata_candidate::series_id::equals(Some(id))).await?;
+	Ok(Json(candidates))
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Type, ToSchema)]
+pub struct ApplyMetadataCandidate {
+	/// The fields to apply, e.g. `["title", "summary"]`. Every field the candidate has a
+	/// value for is applied if none are given
+	pub fields: Option<Vec<String>>,
+	/// Whether to lock the applied fields
+	#[serde(default)]
+	pub lock: bool,
+}
+
+/// The book or series a candidate was found for
+fn candidate_target(data: &metadata_candidate::Data) -> APIResult<MetadataTarget> {
+	match (&data.media_id, &data.series_id) {
+		(Some(media_id), _) => Ok(MetadataTarget::Media(media_id.clone())),
+		(None, Some(series_id)) => Ok(MetadataTarget::Series(series_id.clone())),
+		(None, None) => Err(APIError::InternalServerError(
+			"Candidate does not belong to a book or series".to_string(),
+		)),
+	}
+}
+
+fn parse_fields<F: std::str::FromStr<Err = String>>(
+	fields: Option<Vec<String>>,
+) -> APIResult<Option<Vec<F>>> {
+	fields
+		.map(|fields| {
+			fields
+				.iter()
+				.map(|field| field.parse::<F>())
+				.collect::<Result<Vec<_>, _>>()
+		})
+		.transpose()
+		.map_err(APIError::BadRequest)
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/metadata/candidates/{id}/apply",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the candidate to apply")
+	),
+	request_body = ApplyMetadataCandidate,
+	responses(
+		(status = 200, description = "Successfully applied the candidate", body = AppliedMetadata),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Candidate not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Apply a candidate to the metadata of the book or series it was found for. Locked fields
+/// are not changed, and are returned separately from the applied fields
+async fn apply_metadata_candidate(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<ApplyMetadataCandidate>,
+) -> APIResult<Json<AppliedMetadata>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let client = &ctx.db;
+	let data = client
+		.metadata_candidate()
+		.find_unique(metadata_candidate::id::equals(id))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Candidate not found")))?;
+	let target = candidate_target(&data)?;
+	enforce_target_access(client, req.user(), &target).await?;
+	let candidate = MetadataCandidate::parse_data(&data.data)?;
+
+	let applied = match target {
+		MetadataTarget::Media(media_id) => {
+			let fields = parse_fields::<MediaMetadataField>(input.fields)?;
+			apply_media_candidate(
+				client,
+				&media_id,
+				&candidate,
+				fields.as_deref(),
+				Some(MetadataChangeActor::User(req.user().id.clone())),
+				input.lock,
+			)
+			.await?
+		},
+		MetadataTarget::Series(series_id) => {
+			let fields = parse_fields::<SeriesMetadataField>(input.fields)?;
+			apply_series_candidate(
+				client,
+				&series_id,
+				&candidate,
+				fields.as_deref(),
+				Some(MetadataChangeActor::User(req.user().id.clone())),
+				input.lock,
+			)
+			.await?
+		},
+	};
+
+	Ok(Json(applied))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/metadata/candidates/{id}",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the candidate to delete")
+	),
+	responses(
+		(status = 200, description = "Successfully deleted the candidate"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Candidate not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Dismiss a candidate which is not a match
+async fn delete_metadata_candidate(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<()> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let client = &ctx.db;
+	let data = client
+		.metadata_candidate()
+		.find_unique(metadata_candidate::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Candidate not found")))?;
+	enforce_target_access(client, req.user(), &candidate_target(&data)?).await?;
+
+	client
+		.metadata_candidate()
+		.delete(metadata_candidate::id::equals(id))
+		.exec()
+		.await?;
+
+	Ok(())
+}
+
+async fn get_fields(
+	client: &PrismaClient,
+	target: MetadataTarget,
+) -> APIResult<Vec<MetadataFieldState>> {
+	Ok(get_field_states(client, &target)
+		.await?
+		.into_iter()
+		.map(MetadataFieldState::from)
+		.collect())
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/{id}/metadata/fields",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the media")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched the metadata field states", body = [MetadataFieldState]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the lock and source of the metadata fields of a book. Fields which have not changed
+/// since the book was first scanned, and have never been locked, are omitted
+async fn get_media_metadata_fields(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataFieldState>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let target = MetadataTarget::Media(id);
+	enforce_target_access(&ctx.db, req.user(), &target).await?;
+	Ok(Json(get_fields(&ctx.db, target).await?))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/series/{id}/metadata/fields",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the series")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched the metadata field states", body = [MetadataFieldState]),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the lock and source of the metadata fields of a series. Fields which have not changed
+/// since the series was first scanned, and have never been locked, are omitted
+async fn get_series_metadata_fields(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataFieldState>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let target = MetadataTarget::Series(id);
+	enforce_target_access(&ctx.db, req.user(), &target).await?;
+	Ok(Json(get_fields(&ctx.db, target).await?))
+}
+
+#[derive(Debug, Deserialize, Serialize, Type, ToSchema)]
+pub struct LockMetadataField {
+	pub locked: bool,
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/media/{id}/metadata/fields/{field}/lock",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the media"),
+		("field" = String, Path, description = "The metadata field to lock or unlock")
+	),
+	request_body = LockMetadataField,
+	responses(
+		(status = 200, description = "Successfully updated the field", body = MetadataFieldState),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Lock or unlock a metadata field of a book
+async fn lock_media_metadata_field(
+	Path((id, field)): Path<(String, String)>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<LockMetadataField>,
+) -> APIResult<Json<MetadataFieldState>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let field = field
+		.parse::<MediaMetadataField>()
+		.map_err(APIError::BadRequest)?;
+	let target = MetadataTarget::Media(id);
+	enforce_target_access(&ctx.db, req.user(), &target).await?;
+	let state = set_field_lock(&ctx.db, &target, field.as_str(), input.locked).await?;
+
+	Ok(Json(MetadataFieldState::from(state)))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/series/{id}/metadata/fields/{field}/lock",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the series"),
+		("field" = String, Path, description = "The metadata field to lock or unlock")
+	),
+	request_body = LockMetadataField,
+	responses(
+		(status = 200, description = "Successfully updated the field", body = MetadataFieldState),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Lock or unlock a metadata field of a series
+async fn lock_series_metadata_field(
+	Path((id, field)): Path<(String, String)>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<LockMetadataField>,
+) -> APIResult<Json<MetadataFieldState>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let field = field
+		.parse::<SeriesMetadataField>()
+		.map_err(APIError::BadRequest)?;
+	let target = MetadataTarget::Series(id);
+	enforce_target_access(&ctx.db, req.user(), &target).await?;
+	let state = set_field_lock(&ctx.db, &target, field.as_str(), input.locked).await?;
+
+	Ok(Json(MetadataFieldState::from(state)))
+}
+
+#[derive(Debug, Deserialize, Serialize, Type, ToSchema)]
+pub struct MetadataHistoryParams {
+	/// Only include changes to this field
+	pub field: Option<String>,
+}
+
+async fn get_history(
+	client: &PrismaClient,
+	target: MetadataTarget,
+	field: Option<String>,
+) -> APIResult<Json<Vec<MetadataFieldChange>>> {
+	let changes = get_field_changes(client, &target, field)
+		.await?
+		.into_iter()
+		.map(MetadataFieldChange::from)
+		.collect();
+	Ok(Json(changes))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/{id}/metadata/history",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the media"),
+		("field" = Option<String>, Query, description = "Only include changes to this field")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched the metadata history", body = [MetadataFieldChange]),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the changes made to the metadata of a book after it was first scanned, from the most
+/// to the least recent. Each change records the job or user which made it
+async fn get_media_metadata_history(
+	Path(id): Path<String>,
+	Query(params): Query<MetadataHistoryParams>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataFieldChange>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let field = params
+		.field
+		.map(|field| field.parse::<MediaMetadataField>())
+		.transpose()
+		.map_err(APIError::BadRequest)?;
+	let target = MetadataTarget::Media(id);
+	enforce_target_access(&ctx.db, req.user(), &target).await?;
+	get_history(&ctx.db, target, field.map(|field| field.to_string())).await
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/series/{id}/metadata/history",
+	tag = "metadata",
+	params(
+		("id" = String, Path, description = "The ID of the series"),
+		("field" = Option<String>, Query, description = "Only include changes to this field")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched the metadata history", body = [MetadataFieldChange]),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the changes made to the metadata of a series after it was first scanned, from the most
+/// to the least recent. Each change records the job or user which made it
+async fn get_series_metadata_history(
+	Path(id): Path<String>,
+	Query(params): Query<MetadataHistoryParams>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<MetadataFieldChange>>> {
+	req.enforce_permissions(&[UserPermission::ManageLibrary])?;
+
+	let field = params
+		.field
+		.map(|field| field.parse::<SeriesMetadataField>())
+		.transpose()
+		.map_err(APIError::BadRequest)?;
+	let target = MetadataTarget::Series(id);
+	enforce_target_access(&ctx.db, req.user(), &target).await?;
+	get_history(&ctx.db, target, field.map(|field| field.to_string())).await
+}
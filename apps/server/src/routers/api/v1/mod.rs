@@ -18,22 +18,31 @@ use crate::{
 // also think there is a good amount of duplication which can be trimmed down, like how I did with the OPDS v2 API. A few of those route
 // handlers are one-liners 💅
 
+pub(crate) mod annotation;
 pub(crate) mod api_key;
+pub(crate) mod audit_log;
 pub(crate) mod auth;
 pub(crate) mod book_club;
+pub(crate) mod collection;
 pub(crate) mod config;
 pub(crate) mod emailer;
 pub(crate) mod epub;
 pub(crate) mod filesystem;
+pub(crate) mod invitation;
 pub(crate) mod job;
 pub(crate) mod library;
 pub(crate) mod log;
 pub(crate) mod media;
 pub(crate) mod metadata;
+pub(crate) mod metadata_provider;
 pub(crate) mod notifier;
+pub(crate) mod password_reset;
 pub(crate) mod reading_list;
+pub(crate) mod review;
+pub(crate) mod search;
 pub(crate) mod series;
 pub(crate) mod smart_list;
+pub(crate) mod stats;
 pub(crate) mod tag;
 pub(crate) mod upload;
 pub(crate) mod user;
@@ -41,21 +50,30 @@ pub(crate) mod user;
 pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 	let mut router = Router::new()
 		.merge(auth::mount(app_state.clone()))
+		.merge(password_reset::mount())
 		.merge(api_key::mount(app_state.clone()))
 		.merge(epub::mount(app_state.clone()))
 		.merge(emailer::mount(app_state.clone()))
 		.merge(library::mount(app_state.clone()))
 		.merge(media::mount(app_state.clone()))
 		.merge(metadata::mount(app_state.clone()))
+		.merge(metadata_provider::mount(app_state.clone()))
 		.merge(notifier::mount(app_state.clone()))
 		.merge(filesystem::mount(app_state.clone()))
 		.merge(job::mount(app_state.clone()))
 		.merge(log::mount(app_state.clone()))
+		.merge(audit_log::mount(app_state.clone()))
 		.merge(series::mount(app_state.clone()))
 		.merge(tag::mount(app_state.clone()))
 		.merge(user::mount(app_state.clone()))
 		.merge(reading_list::mount(app_state.clone()))
+		.merge(collection::mount(app_state.clone()))
+		.merge(review::mount(app_state.clone()))
+		.merge(annotation::mount(app_state.clone()))
+		.merge(invitation::mount(app_state.clone()))
 		.merge(smart_list::mount(app_state.clone()))
+		.merge(search::mount(app_state.clone()))
+		.merge(stats::mount(app_state.clone()))
 		.merge(book_club::mount(app_state.clone()))
 		.merge(config::mount(app_state.clone()))
 		.route("/claim", get(claim))
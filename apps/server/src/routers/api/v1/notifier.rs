@@ -1,14 +1,23 @@
 use axum::{
 	extract::{Path, State},
 	middleware,
-	routing::get,
+	routing::{get, post},
 	Extension, Json, Router,
 };
+use axum_extra::extract::Query;
+use prisma_client_rust::Direction;
 use serde::Deserialize;
 use specta::Type;
 use stump_core::{
-	db::entity::{Notifier, NotifierConfigInput, NotifierType, UserPermission},
-	prisma::notifier,
+	db::{
+		entity::{
+			Notifier, NotifierConfigInput, NotifierDelivery, NotifierSubscription,
+			NotifierType, UserPermission,
+		},
+		query::pagination::{PageQuery, Pageable},
+	},
+	prisma::{notifier, notifier_delivery},
+	NotifierEvent,
 };
 use utoipa::ToSchema;
 
@@ -27,13 +36,16 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 				.route("/", get(get_notifiers).post(create_notifier))
 				.nest(
 					"/{id}",
-					Router::new().route(
-						"/",
-						get(get_notifier_by_id)
-							.put(update_notifier)
-							.patch(patch_notifier)
-							.delete(delete_notifier),
-					),
+					Router::new()
+						.route(
+							"/",
+							get(get_notifier_by_id)
+								.put(update_notifier)
+								.patch(patch_notifier)
+								.delete(delete_notifier),
+						)
+						.route("/test", post(send_test_notification))
+						.route("/deliveries", get(get_notifier_deliveries)),
 				),
 		)
 		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
@@ -63,7 +75,7 @@ async fn get_notifiers(
 		.exec()
 		.await?
 		.into_iter()
-		.map(Notifier::try_from)
+		.map(|notifier| Notifier::try_from(notifier).map(Notifier::redacted))
 		.collect::<Vec<Result<Notifier, _>>>();
 	let notifiers = notifiers.into_iter().collect::<Result<Vec<_>, _>>()?;
 
@@ -99,7 +111,7 @@ async fn get_notifier_by_id(
 		.await?
 		.ok_or(APIError::NotFound("Notifier not found".to_string()))?;
 
-	Ok(Json(Notifier::try_from(notifier)?))
+	Ok(Json(Notifier::try_from(notifier)?.redacted()))
 }
 
 #[derive(Deserialize, ToSchema, Type)]
@@ -107,6 +119,9 @@ pub struct CreateOrUpdateNotifier {
 	#[serde(rename = "type")]
 	_type: NotifierType,
 	config: NotifierConfigInput,
+	/// The events (and libraries) the notifier should be sent. Defaults to everything
+	#[serde(default)]
+	subscription: NotifierSubscription,
 }
 
 #[utoipa::path(
@@ -131,13 +146,18 @@ async fn create_notifier(
 
 	let client = &ctx.db;
 	let config = payload.config.into_config(&ctx).await?.into_bytes()?;
+	let subscription = payload.subscription.into_bytes()?;
 	let notifier = client
 		.notifier()
-		.create(payload._type.to_string(), config, vec![])
+		.create(
+			payload._type.to_string(),
+			config,
+			vec![notifier::subscription::set(Some(subscription))],
+		)
 		.exec()
 		.await?;
 
-	Ok(Json(Notifier::try_from(notifier)?))
+	Ok(Json(Notifier::try_from(notifier)?.redacted()))
 }
 
 #[utoipa::path(
@@ -167,6 +187,7 @@ async fn update_notifier(
 
 	let client = &ctx.db;
 	let config = payload.config.into_config(&ctx).await?.into_bytes()?;
+	let subscription = payload.subscription.into_bytes()?;
 	let notifier = client
 		.notifier()
 		.update(
@@ -174,12 +195,13 @@ async fn update_notifier(
 			vec![
 				notifier::r#type::set(payload._type.to_string()),
 				notifier::config::set(config),
+				notifier::subscription::set(Some(subscription)),
 			],
 		)
 		.exec()
 		.await?;
 
-	Ok(Json(Notifier::try_from(notifier)?))
+	Ok(Json(Notifier::try_from(notifier)?.redacted()))
 }
 
 #[derive(Deserialize, ToSchema, Type)]
@@ -187,6 +209,7 @@ pub struct PatchNotifier {
 	#[serde(rename = "type")]
 	_type: Option<NotifierType>,
 	config: Option<NotifierConfigInput>,
+	subscription: Option<NotifierSubscription>,
 }
 
 #[utoipa::path(
@@ -219,6 +242,10 @@ async fn patch_notifier(
 	} else {
 		None
 	};
+	let subscription = payload
+		.subscription
+		.map(NotifierSubscription::into_bytes)
+		.transpose()?;
 
 	let patched_notifier = client
 		.notifier()
@@ -231,13 +258,14 @@ async fn patch_notifier(
 						._type
 						.map(|_type| notifier::r#type::set(_type.to_string())),
 					config.map(notifier::config::set),
+					subscription.map(|bytes| notifier::subscription::set(Some(bytes))),
 				],
 			),
 		)
 		.exec()
 		.await?;
 
-	Ok(Json(Notifier::try_from(patched_notifier)?))
+	Ok(Json(Notifier::try_from(patched_notifier)?.redacted()))
 }
 
 #[utoipa::path(
@@ -269,5 +297,102 @@ async fn delete_notifier(
 		.exec()
 		.await?;
 
-	Ok(Json(Notifier::try_from(deleted_notifier)?))
+	Ok(Json(Notifier::try_from(deleted_notifier)?.redacted()))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/notifiers/{id}/test",
+	tag = "notifier",
+	params(
+		("id" = i32, Path, description = "The notifier ID"),
+	),
+	responses(
+		(status = 200, description = "Successfully sent test notification"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Notifier not found"),
+		(status = 500, description = "Failed to send test notification")
+	)
+)]
+/// Send a test notification using the notifier, regardless of its subscription. This is
+/// useful for verifying that a notifier is configured correctly
+async fn send_test_notification(
+	State(ctx): State<AppState>,
+	Path(id): Path<i32>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<()>> {
+	req.enforce_permissions(&[UserPermission::ManageNotifier])?;
+
+	let client = &ctx.db;
+
+	let notifier = client
+		.notifier()
+		.find_first(vec![notifier::id::equals(id)])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("Notifier not found".to_string()))?;
+
+	Notifier::try_from(notifier)?
+		.send(NotifierEvent::Test, client)
+		.await?;
+
+	Ok(Json(()))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/notifiers/{id}/deliveries",
+	tag = "notifier",
+	params(
+		("id" = i32, Path, description = "The notifier ID"),
+		("pagination" = PageQuery, Query, description = "The pagination params"),
+	),
+	responses(
+		(status = 200, description = "Successfully retrieved deliveries", body = Vec<NotifierDelivery>),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Notifier not found"),
+		(status = 500, description = "Internal server error")
+	)
+)]
+/// Get the delivery log for a notifier, most recent first
+async fn get_notifier_deliveries(
+	State(ctx): State<AppState>,
+	Path(id): Path<i32>,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<NotifierDelivery>>>> {
+	req.enforce_permissions(&[UserPermission::ReadNotifier])?;
+
+	let client = &ctx.db;
+
+	client
+		.notifier()
+		.find_first(vec![notifier::id::equals(id)])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("Notifier not found".to_string()))?;
+
+	let page_params = pagination.0.page_params();
+	let (skip, take) = page_params.get_skip_take();
+	let where_params = vec![notifier_delivery::notifier_id::equals(id)];
+
+	let (deliveries, count) = client
+		._batch((
+			client
+				.notifier_delivery()
+				.find_many(where_params.clone())
+				.order_by(notifier_delivery::timestamp::order(Direction::Desc))
+				.skip(skip)
+				.take(take),
+			client.notifier_delivery().count(where_params),
+		))
+		.await?;
+	let deliveries = deliveries
+		.into_iter()
+		.map(NotifierDelivery::from)
+		.collect::<Vec<_>>();
+
+	Ok(Json(Pageable::from((deliveries, count, page_params))))
 }
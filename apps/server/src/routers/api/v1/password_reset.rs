@@ -0,0 +1,342 @@
+use std::time::Duration as StdDuration;
+
+use axum::{
+	extract::{ConnectInfo, State},
+	routing::post,
+	Json, Router,
+};
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
+use once_cell::sync::Lazy;
+use prisma_client_rust::chrono::{Duration, Utc};
+use ring::digest::{digest, SHA256};
+use serde::Deserialize;
+use specta::Type;
+use stump_core::{
+	db::entity::{AuditAction, AuditEvent, SMTPEmailer},
+	prisma::{emailer, password_reset_token, session, user},
+	PasswordResetPayload,
+};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	http_server::StumpRequestInfo,
+	utils::{hash_password, RateLimiter},
+};
+
+/// The number of minutes a password reset link is valid for
+const PASSWORD_RESET_TOKEN_TTL_MINUTES: i64 = 30;
+/// The window of time the password reset requests are limited within
+const PASSWORD_RESET_WINDOW_MINUTES: i64 = 60;
+/// The number of password reset emails which are sent for an account within the window
+const MAX_PASSWORD_RESETS_PER_ACCOUNT: i64 = 3;
+/// The number of password resets which may be requested from an IP address within the window
+const MAX_PASSWORD_RESETS_PER_IP: usize = 10;
+
+/// The password reset requests made by each IP address. This includes requests for emails
+/// which don't belong to any account, which aren't otherwise stored
+static PASSWORD_RESET_IP_LIMITER: Lazy<RateLimiter> = Lazy::new(|| {
+	RateLimiter::new(
+		MAX_PASSWORD_RESETS_PER_IP,
+		StdDuration::from_secs(PASSWORD_RESET_WINDOW_MINUTES as u64 * 60),
+	)
+});
+
+// Note: None of these routes require authentication, since they are for users who can't log in
+pub(crate) fn mount() -> Router<AppState> {
+	Router::new()
+		.route("/auth/password-reset", post(request_password_reset))
+		.route("/auth/password-reset/confirm", post(confirm_password_reset))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct RequestPasswordReset {
+	/// The email address of the account to reset the password of
+	pub email: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct ConfirmPasswordReset {
+	/// The token which was sent to the user's email
+	pub token: String,
+	/// The new password for the user
+	pub password: String,
+}
+
+/// Generate a new password reset token, returning the token to send to the user and the hash
+/// of it to store in the database
+fn create_reset_token() -> (String, String) {
+	let token = BASE64URL_NOPAD.encode(&rand::random::<[u8; 32]>());
+	let hash = hash_reset_token(&token);
+	(token, hash)
+}
+
+/// Hash a password reset token. Only the hash of a token is stored, so that a leaked database
+/// can't be used to reset passwords
+fn hash_reset_token(token: &str) -> String {
+	HEXLOWER.encode(digest(&SHA256, token.as_bytes()).as_ref())
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/auth/password-reset",
+	tag = "auth",
+	request_body = RequestPasswordReset,
+	responses(
+		(status = 200, description = "The request was accepted. An email is sent if the address belongs to an account"),
+		(status = 429, description = "Too many password resets were requested"),
+		(status = 503, description = "Password resets are not available on this server"),
+	)
+)]
+/// Request a password reset for the account with the given email address, which sends a link
+/// to reset the password to it using the primary emailer. The response is the same whether or
+/// not the email address belongs to an account, so it can't be used to discover accounts
+async fn request_password_reset(
+	ConnectInfo(request_info): ConnectInfo<StumpRequestInfo>,
+	State(ctx): State<AppState>,
+	Json(input): Json<RequestPasswordReset>,
+) -> APIResult<()> {
+	let ip_address = request_info.ip_addr.to_string();
+	if !PASSWORD_RESET_IP_LIMITER.try_attempt(&ip_address) {
+		return Err(APIError::TooManyRequests(
+			"Too many password resets were requested, please try again later".to_string(),
+		));
+	}
+
+	let client = &ctx.db;
+
+	let public_url = client
+		.server_config()
+		.find_first(vec![])
+		.exec()
+		.await?
+		.and_then(|config| config.public_url)
+		.ok_or_else(|| {
+			APIError::ServiceUnavailable(
+				"A public URL must be configured to send password reset emails"
+					.to_string(),
+			)
+		})?;
+	let emailer = client
+		.emailer()
+		.find_first(vec![emailer::is_primary::equals(true)])
+		.exec()
+		.await?
+		.ok_or_else(|| {
+			APIError::ServiceUnavailable(
+				"A primary emailer must be configured to send password reset emails"
+					.to_string(),
+			)
+		})?;
+	let emailer = SMTPEmailer::try_from(emailer)?;
+
+	let window_start = Utc::now() - Duration::minutes(PASSWORD_RESET_WINDOW_MINUTES);
+	// Tokens are only kept around for as long as they count towards the limit per account
+	client
+		.password_reset_token()
+		.delete_many(vec![password_reset_token::created_at::lt(
+			window_start.into(),
+		)])
+		.exec()
+		.await?;
+
+	let email = input.email.trim().to_lowercase();
+	let Some(for_user) = client
+		.user()
+		.find_first(vec![
+			user::email::equals(Some(email.clone())),
+			user::deleted_at::equals(None),
+			user::is_locked::equals(false),
+		])
+		.exec()
+		.await?
+	else {
+		tracing::debug!("No user found for password reset request");
+		return Ok(());
+	};
+
+	let recent_requests_count = client
+		.password_reset_token()
+		.count(vec![
+			password_reset_token::user_id::equals(for_user.id.clone()),
+			password_reset_token::created_at::gte(window_start.into()),
+		])
+		.exec()
+		.await?;
+	if recent_requests_count >= MAX_PASSWORD_RESETS_PER_ACCOUNT {
+		tracing::warn!(
+			username = &for_user.username,
+			"Too many password resets were requested for user"
+		);
+		return Ok(());
+	}
+
+	let (token, token_hash) = create_reset_token();
+	let expires_at = Utc::now() + Duration::minutes(PASSWORD_RESET_TOKEN_TTL_MINUTES);
+	let created_token = client
+		.password_reset_token()
+		.create(
+			token_hash,
+			ip_address,
+			expires_at.into(),
+			user::id::equals(for_user.id.clone()),
+			vec![],
+		)
+		.exec()
+		.await?;
+
+	let payload = PasswordResetPayload {
+		reset_url: format!(
+			"{}/auth/reset-password?token={token}",
+			public_url.trim_end_matches('/')
+		),
+		expires_at: expires_at.format("%B %-d, %Y at %H:%M UTC").to_string(),
+	};
+
+	// The email is sent in the background, so that the time taken to respond doesn't reveal
+	// whether the email address belongs to an account
+	let ctx = ctx.clone();
+	tokio::spawn(async move {
+		let (emailer_id, emailer_name) = (emailer.id, emailer.name.clone());
+		let send_result = match emailer.into_client(&ctx).await {
+			Ok(emailer_client) => emailer_client
+				.send_password_reset(&email, payload)
+				.await
+				.map_err(|error| error.to_string()),
+			Err(error) => Err(error.to_string()),
+		};
+
+		// A link which never reached the user is of no use, so it is removed
+		if let Err(error) = send_result {
+			tracing::error!(?error, "Failed to send password reset email");
+			let delete_result = ctx
+				.db
+				.password_reset_token()
+				.delete(password_reset_token::id::equals(created_token.id))
+				.exec()
+				.await;
+			if let Err(error) = delete_result {
+				tracing::error!(?error, "Failed to delete unsent password reset token");
+			}
+			return;
+		}
+
+		// The reset is requested without a session, so there is no actor
+		let sent = serde_json::json!({
+			"recipients": [email],
+			"user_id": for_user.id,
+		});
+		ctx.record_audit_event(
+			AuditEvent::new(AuditAction::EmailSent, None)
+				.with_target(emailer_id, emailer_name)
+				.with_changes(None, Some(&sent)),
+		)
+		.await;
+	});
+
+	Ok(())
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/auth/password-reset/confirm",
+	tag = "auth",
+	request_body = ConfirmPasswordReset,
+	responses(
+		(status = 200, description = "Successfully reset the password"),
+		(status = 400, description = "The token is invalid or has expired"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Reset the password of a user with a token from a password reset email. The user is logged
+/// out of every session, and the token can't be used again
+async fn confirm_password_reset(
+	State(ctx): State<AppState>,
+	Json(input): Json<ConfirmPasswordReset>,
+) -> APIResult<()> {
+	let client = &ctx.db;
+
+	if input.password.is_empty() {
+		return Err(APIError::BadRequest("A password is required".to_string()));
+	}
+
+	let invalid_token_error = || {
+		APIError::BadRequest(
+			"This password reset link is invalid or has expired".to_string(),
+		)
+	};
+
+	let reset_token = client
+		.password_reset_token()
+		.find_unique(password_reset_token::token_hash::equals(hash_reset_token(
+			&input.token,
+		)))
+		.exec()
+		.await?
+		.filter(|token| token.used_at.is_none() && token.expires_at > Utc::now())
+		.ok_or_else(invalid_token_error)?;
+
+	let hashed_password = hash_password(&input.password, &ctx.config)?;
+	let user_id = reset_token.user_id.clone();
+
+	let (removed_sessions_count, reset_user) = client
+		._transaction()
+		.run(|tx| async move {
+			// Marking the token as used first ensures it can only ever be used once, since a
+			// concurrent reset will fail to update it
+			let used_count = tx
+				.password_reset_token()
+				.update_many(
+					vec![
+						password_reset_token::id::equals(reset_token.id),
+						password_reset_token::used_at::equals(None),
+					],
+					vec![password_reset_token::used_at::set(Some(Utc::now().into()))],
+				)
+				.exec()
+				.await?;
+			if used_count == 0 {
+				return Ok(None);
+			}
+
+			let reset_user = tx
+				.user()
+				.update(
+					user::id::equals(user_id.clone()),
+					vec![user::hashed_password::set(hashed_password)],
+				)
+				.exec()
+				.await?;
+
+			// Any other links sent to the user are no longer needed
+			tx.password_reset_token()
+				.delete_many(vec![
+					password_reset_token::user_id::equals(user_id.clone()),
+					password_reset_token::used_at::equals(None),
+				])
+				.exec()
+				.await?;
+
+			tx.session()
+				.delete_many(vec![session::user_id::equals(user_id)])
+				.exec()
+				.await
+				.map(|count| Some((count, reset_user)))
+		})
+		.await?
+		.ok_or_else(invalid_token_error)?;
+	tracing::debug!(
+		?removed_sessions_count,
+		"Reset password of user and removed all associated sessions"
+	);
+
+	// The reset is made with a token rather than a session, so there is no actor
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserPasswordReset, None)
+			.with_target(&reset_user.id, &reset_user.username),
+	)
+	.await;
+
+	Ok(())
+}
@@ -0,0 +1,628 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use axum::{
+	extract::{Path, Query, State},
+	middleware,
+	routing::get,
+	Extension, Json, Router,
+};
+use prisma_client_rust::{and, or, Direction};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use stump_core::{
+	db::entity::{
+		is_valid_review_rating, macros::review_rating_select, Media, Review, ReviewStats,
+		Series, User, MAX_REVIEW_RATING, MIN_REVIEW_RATING,
+	},
+	prisma::{media, review, series, user},
+};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::filters::{
+		apply_media_restrictions_for_user, apply_series_restrictions_for_user,
+	},
+};
+
+const DEFAULT_TOP_RATED_LIMIT: usize = 20;
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/media/top-rated", get(get_top_rated_media))
+		.route(
+			"/media/{id}/reviews",
+			get(get_media_reviews).post(create_media_review),
+		)
+		.route("/media/{id}/reviews/stats", get(get_media_review_stats))
+		.route("/series/top-rated", get(get_top_rated_series))
+		.route("/series/{id}/reviews/stats", get(get_series_review_stats))
+		.route(
+			"/reviews/{id}",
+			get(get_review_by_id)
+				.put(update_review)
+				.delete(delete_review),
+		)
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+/// A condition which restricts reviews to those the user is allowed to see: their own reviews
+/// and the public reviews of other users, for books the user has access to
+pub(crate) fn review_visible_to_user_filter(user: &User) -> review::WhereParam {
+	and![
+		or![
+			review::is_private::equals(false),
+			review::user_id::equals(user.id.clone())
+		],
+		review::media::is(apply_media_restrictions_for_user(user))
+	]
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct CreateOrUpdateReview {
+	/// The rating of the book, between 1 and 10 (inclusive)
+	pub rating: i32,
+	/// The written review, if any
+	#[serde(default)]
+	pub content: Option<String>,
+	/// Whether the review should only be visible to its author
+	#[serde(default)]
+	pub is_private: bool,
+}
+
+impl CreateOrUpdateReview {
+	fn validate(&self) -> APIResult<()> {
+		if !is_valid_review_rating(self.rating) {
+			return Err(APIError::BadRequest(format!(
+				"Rating must be between {MIN_REVIEW_RATING} and {MAX_REVIEW_RATING}"
+			)));
+		}
+
+		Ok(())
+	}
+
+	/// The trimmed review content, where blank content is treated as no content
+	fn content(&self) -> Option<String> {
+		self.content
+			.as_deref()
+			.map(str::trim)
+			.filter(|content| !content.is_empty())
+			.map(String::from)
+	}
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub struct TopRatedParams {
+	/// The maximum number of entities to return. Defaults to 20
+	#[serde(default)]
+	pub limit: Option<usize>,
+	/// The minimum number of reviews an entity must have to be considered. Defaults to 1
+	#[serde(default)]
+	pub min_reviews: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema, Type)]
+pub struct RatedMedia {
+	pub media: Media,
+	pub stats: ReviewStats,
+}
+
+#[derive(Debug, Serialize, ToSchema, Type)]
+pub struct RatedSeries {
+	pub series: Series,
+	pub stats: ReviewStats,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/{id}/reviews",
+	tag = "review",
+	params(
+		("id" = String, Path, description = "The ID of the media to get reviews for")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched reviews", body = [Review]),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the reviews for a book which are visible to the requesting user, most recently
+/// updated first
+async fn get_media_reviews(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<Review>>> {
+	let user = req.user();
+
+	let reviews = ctx
+		.db
+		.review()
+		.find_many(vec![
+			review::media_id::equals(id),
+			review_visible_to_user_filter(user),
+		])
+		.with(review::user::fetch())
+		.order_by(review::updated_at::order(Direction::Desc))
+		.exec()
+		.await?;
+
+	Ok(Json(reviews.into_iter().map(Review::from).collect()))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/media/{id}/reviews",
+	tag = "review",
+	params(
+		("id" = String, Path, description = "The ID of the media to review")
+	),
+	request_body = CreateOrUpdateReview,
+	responses(
+		(status = 200, description = "Successfully created review", body = Review),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Media not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Create a review of a book for the requesting user. A user may only review a book once,
+/// existing reviews should be updated instead
+async fn create_media_review(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateReview>,
+) -> APIResult<Json<Review>> {
+	input.validate()?;
+
+	let user = req.user();
+	let client = &ctx.db;
+
+	let book = client
+		.media()
+		.find_first(
+			[media::id::equals(id.clone())]
+				.into_iter()
+				.chain(apply_media_restrictions_for_user(user))
+				.collect(),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Media not found")))?;
+
+	let existing_review = client
+		.review()
+		.find_unique(review::user_id_media_id(user.id.clone(), book.id.clone()))
+		.exec()
+		.await?;
+	if existing_review.is_some() {
+		return Err(APIError::BadRequest(String::from(
+			"You have already reviewed this book",
+		)));
+	}
+
+	let content = input.content();
+	let created_review = client
+		.review()
+		.create(
+			input.rating,
+			media::id::equals(book.id),
+			user::id::equals(user.id.clone()),
+			vec![
+				review::content::set(content),
+				review::is_private::set(input.is_private),
+			],
+		)
+		.with(review::user::fetch())
+		.exec()
+		.await?;
+
+	Ok(Json(Review::from(created_review)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/{id}/reviews/stats",
+	tag = "review",
+	params(
+		("id" = String, Path, description = "The ID of the media to get review stats for")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched review stats", body = ReviewStats),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the aggregate rating statistics for a book, considering only the reviews visible to
+/// the requesting user
+async fn get_media_review_stats(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<ReviewStats>> {
+	let user = req.user();
+
+	let reviews = ctx
+		.db
+		.review()
+		.find_many(vec![
+			review::media_id::equals(id),
+			review_visible_to_user_filter(user),
+		])
+		.select(review_rating_select::select())
+		.exec()
+		.await?;
+
+	Ok(Json(ReviewStats::from_ratings(
+		reviews.into_iter().map(|r| r.rating),
+	)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/series/{id}/reviews/stats",
+	tag = "review",
+	params(
+		("id" = String, Path, description = "The ID of the series to get review stats for")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched review stats", body = ReviewStats),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Series not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the aggregate rating statistics across all books in a series, considering only the
+/// reviews visible to the requesting user
+async fn get_series_review_stats(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<ReviewStats>> {
+	let user = req.user();
+	let client = &ctx.db;
+
+	client
+		.series()
+		.find_first(
+			[series::id::equals(id.clone())]
+				.into_iter()
+				.chain(apply_series_restrictions_for_user(user))
+				.collect(),
+		)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Series not found")))?;
+
+	let reviews = client
+		.review()
+		.find_many(vec![
+			review::media::is(vec![media::series_id::equals(Some(id))]),
+			review_visible_to_user_filter(user),
+		])
+		.select(review_rating_select::select())
+		.exec()
+		.await?;
+
+	Ok(Json(ReviewStats::from_ratings(
+		reviews.into_iter().map(|r| r.rating),
+	)))
+}
+
+/// Group ratings by a key and compute the stats for each group, returning the groups
+/// with at least `min_reviews` reviews ordered from highest to lowest average rating.
+/// Ties are broken by the number of reviews.
+fn rank_by_rating(
+	ratings: impl IntoIterator<Item = (String, i32)>,
+	min_reviews: i64,
+	limit: usize,
+) -> Vec<(String, ReviewStats)> {
+	let mut grouped = HashMap::<String, Vec<i32>>::new();
+	for (key, rating) in ratings {
+		grouped.entry(key).or_default().push(rating);
+	}
+
+	let mut ranked = grouped
+		.into_iter()
+		.map(|(key, ratings)| (key, ReviewStats::from_ratings(ratings)))
+		.filter(|(_, stats)| stats.review_count >= min_reviews.max(1))
+		.collect::<Vec<_>>();
+
+	ranked.sort_by(|(_, a), (_, b)| {
+		b.average_rating
+			.partial_cmp(&a.average_rating)
+			.unwrap_or(Ordering::Equal)
+			.then(b.review_count.cmp(&a.review_count))
+	});
+	ranked.truncate(limit);
+
+	ranked
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/media/top-rated",
+	tag = "review",
+	params(
+		("params" = TopRatedParams, Query, description = "The options for the top rated list"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched top rated media", body = [RatedMedia]),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the highest rated books, based on the reviews visible to the requesting user
+async fn get_top_rated_media(
+	State(ctx): State<AppState>,
+	Query(params): Query<TopRatedParams>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<RatedMedia>>> {
+	let user = req.user();
+	let client = &ctx.db;
+
+	let reviews = client
+		.review()
+		.find_many(vec![review_visible_to_user_filter(user)])
+		.select(review_rating_select::select())
+		.exec()
+		.await?;
+
+	let ranked = rank_by_rating(
+		reviews.into_iter().map(|r| (r.media.id, r.rating)),
+		params.min_reviews.unwrap_or(1),
+		params.limit.unwrap_or(DEFAULT_TOP_RATED_LIMIT),
+	);
+
+	let ids = ranked.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+	let mut books = client
+		.media()
+		.find_many(vec![media::id::in_vec(ids)])
+		.with(media::metadata::fetch())
+		.exec()
+		.await?
+		.into_iter()
+		.map(|data| (data.id.clone(), Media::from(data)))
+		.collect::<HashMap<_, _>>();
+
+	Ok(Json(
+		ranked
+			.into_iter()
+			.filter_map(|(id, stats)| {
+				books.remove(&id).map(|media| RatedMedia { media, stats })
+			})
+			.collect(),
+	))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/series/top-rated",
+	tag = "review",
+	params(
+		("params" = TopRatedParams, Query, description = "The options for the top rated list"),
+	),
+	responses(
+		(status = 200, description = "Successfully fetched top rated series", body = [RatedSeries]),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the highest rated series, based on the reviews of their books which are visible to
+/// the requesting user
+async fn get_top_rated_series(
+	State(ctx): State<AppState>,
+	Query(params): Query<TopRatedParams>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Vec<RatedSeries>>> {
+	let user = req.user();
+	let client = &ctx.db;
+
+	let reviews = client
+		.review()
+		.find_many(vec![review_visible_to_user_filter(user)])
+		.select(review_rating_select::select())
+		.exec()
+		.await?;
+
+	let ranked = rank_by_rating(
+		reviews
+			.into_iter()
+			.filter_map(|r| r.media.series_id.map(|series_id| (series_id, r.rating))),
+		params.min_reviews.unwrap_or(1),
+		params.limit.unwrap_or(DEFAULT_TOP_RATED_LIMIT),
+	);
+
+	let ids = ranked.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+	let mut series = client
+		.series()
+		.find_many(vec![series::id::in_vec(ids)])
+		.with(series::metadata::fetch())
+		.exec()
+		.await?
+		.into_iter()
+		.map(|data| (data.id.clone(), Series::from(data)))
+		.collect::<HashMap<_, _>>();
+
+	Ok(Json(
+		ranked
+			.into_iter()
+			.filter_map(|(id, stats)| {
+				series
+					.remove(&id)
+					.map(|series| RatedSeries { series, stats })
+			})
+			.collect(),
+	))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/reviews/{id}",
+	tag = "review",
+	params(
+		("id" = String, Path, description = "The ID of the review")
+	),
+	responses(
+		(status = 200, description = "Successfully fetched review", body = Review),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Review not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get a review by its ID, if it is visible to the requesting user
+async fn get_review_by_id(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Review>> {
+	let user = req.user();
+
+	let review = ctx
+		.db
+		.review()
+		.find_first(vec![
+			review::id::equals(id),
+			review_visible_to_user_filter(user),
+		])
+		.with(review::user::fetch())
+		.with(review::media::fetch())
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Review not found")))?;
+
+	Ok(Json(Review::from(review)))
+}
+
+#[utoipa::path(
+	put,
+	path = "/api/v1/reviews/{id}",
+	tag = "review",
+	params(
+		("id" = String, Path, description = "The ID of the review to update")
+	),
+	request_body = CreateOrUpdateReview,
+	responses(
+		(status = 200, description = "Successfully updated review", body = Review),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 404, description = "Review not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Update a review. Only the author of a review may update it
+async fn update_review(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(input): Json<CreateOrUpdateReview>,
+) -> APIResult<Json<Review>> {
+	input.validate()?;
+
+	let user = req.user();
+	let client = &ctx.db;
+
+	let review = client
+		.review()
+		.find_first(vec![
+			review::id::equals(id),
+			review::user_id::equals(user.id.clone()),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Review not found")))?;
+
+	let content = input.content();
+	let updated_review = client
+		.review()
+		.update(
+			review::id::equals(review.id),
+			vec![
+				review::rating::set(input.rating),
+				review::content::set(content),
+				review::is_private::set(input.is_private),
+			],
+		)
+		.with(review::user::fetch())
+		.exec()
+		.await?;
+
+	Ok(Json(Review::from(updated_review)))
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/reviews/{id}",
+	tag = "review",
+	params(
+		("id" = String, Path, description = "The ID of the review to delete")
+	),
+	responses(
+		(status = 200, description = "Successfully deleted review", body = Review),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "Review not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Delete a review. Reviews may be deleted by their author or the server owner
+async fn delete_review(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Review>> {
+	let user = req.user();
+	let client = &ctx.db;
+
+	let review = client
+		.review()
+		.find_first(vec![
+			review::id::equals(id),
+			review_visible_to_user_filter(user),
+		])
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Review not found")))?;
+
+	if review.user_id != user.id && !user.is_server_owner {
+		return Err(APIError::Forbidden(String::from(
+			"You may only delete your own reviews",
+		)));
+	}
+
+	let deleted_review = client
+		.review()
+		.delete(review::id::equals(review.id))
+		.exec()
+		.await?;
+
+	Ok(Json(Review::from(deleted_review)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rank_by_rating() {
+		let ratings = vec![
+			("a".to_string(), 6),
+			("a".to_string(), 9),
+			("b".to_string(), 10),
+			("c".to_string(), 7),
+			("c".to_string(), 7),
+			("d".to_string(), 7),
+		];
+
+		let ranked = rank_by_rating(ratings.clone(), 1, 10);
+		let keys = ranked.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+		assert_eq!(keys, vec!["b", "a", "c", "d"]);
+
+		let ranked = rank_by_rating(ratings, 2, 1);
+		assert_eq!(ranked.len(), 1);
+		assert_eq!(ranked[0].0, "a");
+	}
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use axum::{
+	extract::{Query, State},
+	middleware,
+	routing::get,
+	Extension, Json, Router,
+};
+use serde::Deserialize;
+use specta::Type;
+use stump_core::{
+	db::{
+		entity::Media,
+		query::pagination::{PageQuery, Pageable},
+		search::{count_media_index_matches, search_media_index, MediaSearchResult},
+	},
+	prisma::{active_reading_session, finished_reading_session, media},
+};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+	routers::api::filters::media_restrictions_sql_for_user,
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/search", get(search_media))
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Type)]
+pub(crate) struct MediaSearchParams {
+	/// The text to search for. Every term must match, "quoted phrases" are matched exactly and
+	/// the last term also matches words it is a prefix of
+	pub query: String,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/search",
+	tag = "search",
+	params(
+		("query" = String, Query, description = "The text to search for"),
+		("pagination" = Option<PageQuery>, Query, description = "The pagination options"),
+	),
+	responses(
+		(status = 200, description = "Successfully searched books", body = [MediaSearchResult]),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Search the full-text index of books, which covers titles, summaries, creators, characters,
+/// tags, series metadata and (if enabled) the text of EPUBs. Results are ordered by relevance,
+/// and include the title and most relevant fragment of each book with the matched terms
+/// highlighted
+async fn search_media(
+	Query(params): Query<MediaSearchParams>,
+	Query(pagination): Query<PageQuery>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<Pageable<Vec<MediaSearchResult>>>> {
+	if params.query.trim().is_empty() {
+		return Err(APIError::BadRequest(
+			"A search query is required".to_string(),
+		));
+	}
+
+	let client = &ctx.db;
+	let user = req.user();
+
+	let page_params = pagination.page_params();
+	let (skip, take) = page_params.get_skip_take();
+	let restrictions = media_restrictions_sql_for_user(user);
+
+	let hits =
+		search_media_index(client, &params.query, restrictions.clone(), skip, take)
+			.await?;
+	let total = count_media_index_matches(client, &params.query, restrictions).await?;
+
+	let hit_ids = hits
+		.iter()
+		.map(|hit| hit.media_id.clone())
+		.collect::<Vec<_>>();
+	let mut hit_media = client
+		.media()
+		.find_many(vec![media::id::in_vec(hit_ids)])
+		.with(media::metadata::fetch())
+		.with(media::active_user_reading_sessions::fetch(vec![
+			active_reading_session::user_id::equals(user.id.clone()),
+		]))
+		.with(media::finished_user_reading_sessions::fetch(vec![
+			finished_reading_session::user_id::equals(user.id.clone()),
+		]))
+		.exec()
+		.await?
+		.into_iter()
+		.map(|data| (data.id.clone(), Media::from(data)))
+		.collect::<HashMap<_, _>>();
+
+	// The index has already ranked the hits, so keep its order rather than the database's
+	let results = hits
+		.into_iter()
+		.filter_map(|hit| {
+			hit_media
+				.remove(&hit.media_id)
+				.map(|media| MediaSearchResult::new(media, hit))
+		})
+		.collect::<Vec<_>>();
+
+	Ok(Json(Pageable::from((results, total, page_params))))
+}
@@ -0,0 +1,222 @@
+use axum::{
+	extract::{Query, State},
+	middleware,
+	routing::get,
+	Extension, Json, Router,
+};
+use prisma_client_rust::chrono::{DateTime, Datelike, FixedOffset, Utc};
+use serde::Deserialize;
+use specta::Type;
+use stump_core::{
+	db::entity::{
+		ReadingStats, ReadingStatsSession, ServerReadingStats, UserPermission,
+		YearInReview,
+	},
+	prisma::{active_reading_session, finished_reading_session, media, PrismaClient},
+};
+use utoipa::ToSchema;
+
+use crate::{
+	config::state::AppState,
+	errors::{APIError, APIResult},
+	middleware::auth::{auth_middleware, RequestContext},
+};
+
+pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
+	Router::new()
+		.route("/stats/reading", get(get_reading_stats))
+		.route("/stats/reading/year-in-review", get(get_year_in_review))
+		.route("/stats/reading/server", get(get_server_reading_stats))
+		.layer(middleware::from_fn_with_state(app_state, auth_middleware))
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema, Type)]
+pub(crate) struct ReadingStatsParams {
+	/// Only include activity on or after this time
+	#[serde(default)]
+	pub from: Option<DateTime<FixedOffset>>,
+	/// Only include activity on or before this time
+	#[serde(default)]
+	pub to: Option<DateTime<FixedOffset>>,
+	/// The offset from UTC, in minutes, used to group activity into days. Defaults to UTC
+	#[serde(default)]
+	pub utc_offset_minutes: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema, Type)]
+pub(crate) struct YearInReviewParams {
+	/// The year to review. Defaults to the current year
+	#[serde(default)]
+	pub year: Option<i32>,
+	/// The offset from UTC, in minutes, used to group activity into days. Defaults to UTC
+	#[serde(default)]
+	pub utc_offset_minutes: Option<i32>,
+}
+
+fn timezone(utc_offset_minutes: Option<i32>) -> APIResult<FixedOffset> {
+	utc_offset_minutes
+		.unwrap_or_default()
+		.checked_mul(60)
+		.and_then(FixedOffset::east_opt)
+		.ok_or(APIError::BadRequest(
+			"The UTC offset must be within 24 hours".to_string(),
+		))
+}
+
+/// Fetch the finished and active reading sessions, optionally for a single user, with the
+/// relations required to compute statistics. Finished sessions are filtered by when they
+/// were completed, and active sessions by when they were last updated
+async fn fetch_stats_sessions(
+	client: &PrismaClient,
+	user_id: Option<String>,
+	from: Option<DateTime<FixedOffset>>,
+	to: Option<DateTime<FixedOffset>>,
+) -> APIResult<Vec<ReadingStatsSession>> {
+	let mut finished_params = vec![];
+	let mut active_params = vec![];
+	if let Some(user_id) = user_id {
+		finished_params.push(finished_reading_session::user_id::equals(user_id.clone()));
+		active_params.push(active_reading_session::user_id::equals(user_id));
+	}
+	if let Some(from) = from {
+		finished_params.push(finished_reading_session::completed_at::gte(from));
+		active_params.push(active_reading_session::updated_at::gte(from));
+	}
+	if let Some(to) = to {
+		finished_params.push(finished_reading_session::completed_at::lte(to));
+		active_params.push(active_reading_session::updated_at::lte(to));
+	}
+
+	let (finished_sessions, active_sessions) = client
+		._batch((
+			client
+				.finished_reading_session()
+				.find_many(finished_params)
+				.with(
+					finished_reading_session::media::fetch()
+						.with(media::metadata::fetch()),
+				)
+				.with(finished_reading_session::device::fetch())
+				.with(finished_reading_session::user::fetch()),
+			client
+				.active_reading_session()
+				.find_many(active_params)
+				.with(
+					active_reading_session::media::fetch().with(media::metadata::fetch()),
+				)
+				.with(active_reading_session::device::fetch())
+				.with(active_reading_session::user::fetch()),
+		))
+		.await?;
+
+	Ok(finished_sessions
+		.into_iter()
+		.map(ReadingStatsSession::from)
+		.chain(active_sessions.into_iter().map(ReadingStatsSession::from))
+		.collect())
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/stats/reading",
+	tag = "stats",
+	params(
+		("from" = Option<String>, Query, description = "Only include activity on or after this time"),
+		("to" = Option<String>, Query, description = "Only include activity on or before this time"),
+		("utc_offset_minutes" = Option<i32>, Query, description = "The offset from UTC, in minutes, used to group activity into days"),
+	),
+	responses(
+		(status = 200, description = "Successfully computed reading statistics", body = ReadingStats),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the reading statistics of the requesting user: books and pages read per day, week and
+/// month, reading streaks, time spent reading, their most read genres, writers and publishers,
+/// and a breakdown by reading device
+async fn get_reading_stats(
+	State(ctx): State<AppState>,
+	Query(params): Query<ReadingStatsParams>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<ReadingStats>> {
+	let timezone = timezone(params.utc_offset_minutes)?;
+	let sessions =
+		fetch_stats_sessions(&ctx.db, Some(req.id()), params.from, params.to).await?;
+
+	let today = Utc::now().with_timezone(&timezone).date_naive();
+	Ok(Json(ReadingStats::compute(&sessions, timezone, today)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/stats/reading/year-in-review",
+	tag = "stats",
+	params(
+		("year" = Option<i32>, Query, description = "The year to review. Defaults to the current year"),
+		("utc_offset_minutes" = Option<i32>, Query, description = "The offset from UTC, in minutes, used to group activity into days"),
+	),
+	responses(
+		(status = 200, description = "Successfully computed year in review", body = YearInReview),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get a summary of the requesting user's reading over a single year
+async fn get_year_in_review(
+	State(ctx): State<AppState>,
+	Query(params): Query<YearInReviewParams>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<YearInReview>> {
+	let timezone = timezone(params.utc_offset_minutes)?;
+	let year = params
+		.year
+		.unwrap_or_else(|| Utc::now().with_timezone(&timezone).year());
+
+	// Sessions are fetched with a day of leeway on either side of the year, since the
+	// boundaries of the year depend on the timezone. They are filtered precisely afterwards
+	let bounds = (
+		DateTime::parse_from_rfc3339(&format!("{}-12-31T00:00:00Z", year - 1)),
+		DateTime::parse_from_rfc3339(&format!("{}-01-02T00:00:00Z", year + 1)),
+	);
+	let (Ok(from), Ok(to)) = bounds else {
+		return Err(APIError::BadRequest("Invalid year".to_string()));
+	};
+	let sessions =
+		fetch_stats_sessions(&ctx.db, Some(req.id()), Some(from), Some(to)).await?;
+
+	Ok(Json(YearInReview::compute(&sessions, year, timezone)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/stats/reading/server",
+	tag = "stats",
+	params(
+		("from" = Option<String>, Query, description = "Only include activity on or after this time"),
+		("to" = Option<String>, Query, description = "Only include activity on or before this time"),
+		("utc_offset_minutes" = Option<i32>, Query, description = "The offset from UTC, in minutes, used to group activity into days"),
+	),
+	responses(
+		(status = 200, description = "Successfully computed server reading statistics", body = ServerReadingStats),
+		(status = 400, description = "Bad request"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Get the reading statistics of every user on the server, along with a summary of each
+/// user's activity
+async fn get_server_reading_stats(
+	State(ctx): State<AppState>,
+	Query(params): Query<ReadingStatsParams>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<ServerReadingStats>> {
+	req.enforce_permissions(&[UserPermission::ManageServer])?;
+
+	let timezone = timezone(params.utc_offset_minutes)?;
+	let sessions = fetch_stats_sessions(&ctx.db, None, params.from, params.to).await?;
+
+	Ok(Json(ServerReadingStats::compute(&sessions, timezone)))
+}
@@ -218,13 +218,15 @@ async fn copy_tempfile_to_location(
 /// will return an error if the file is not the appropriate file type.
 fn validate_book_file(f: &FieldData<NamedTempFile>) -> APIResult<()> {
 	/// Any file extension not in this list will trigger an error
-	const ALLOWED_EXTENSIONS: &[&str] = &["cbr", "cbz", "epub", "pdf"];
+	const ALLOWED_EXTENSIONS: &[&str] = &["cbr", "cbz", "cb7", "cbt", "epub", "pdf"];
 
 	/// Any inferred mime type not in this list will trigger an error
 	const ALLOWED_TYPES: &[&str] = &[
 		"application/zip",
 		"application/vnd.comicbook+zip",
 		"application/vnd.comicbook-rar",
+		"application/x-7z-compressed",
+		"application/x-tar",
 		"application/epub+zip",
 		"application/pdf",
 	];
@@ -365,8 +367,8 @@ fn validate_series_upload_contents(
 fn validate_zip_file(zip_file: &mut ZipFile) -> APIResult<()> {
 	/// Any file extension not in this list will trigger an error
 	const ALLOWED_EXTENSIONS: &[&str] = &[
-		"cbr", "cbz", "epub", "pdf", "xml", "json", "png", "jpg", "jpeg", "webp", "gif",
-		"heif", "jxl", "avif",
+		"cbr", "cbz", "cb7", "cbt", "epub", "pdf", "xml", "json", "png", "jpg", "jpeg",
+		"webp", "gif", "heif", "jxl", "avif",
 	];
 
 	/// Any inferred mime type not in this list will trigger an error
@@ -374,6 +376,8 @@ fn validate_zip_file(zip_file: &mut ZipFile) -> APIResult<()> {
 		"application/zip",
 		"application/vnd.comicbook+zip",
 		"application/vnd.comicbook-rar",
+		"application/x-7z-compressed",
+		"application/x-tar",
 		"application/epub+zip",
 		"application/pdf",
 		"application/xml",
@@ -14,16 +14,18 @@ use stump_core::{
 	config::StumpConfig,
 	db::{
 		entity::{
-			AgeRestriction, Arrangement, LoginActivity, NavigationItem, SupportedFont,
-			User, UserPermission, UserPreferences,
+			AgeRestriction, Arrangement, AuditAction, AuditEvent, AuditLogChange,
+			LoginActivity, NavigationItem, SupportedFont, User, UserPermission,
+			UserPreferences,
 		},
 		query::pagination::{Pageable, Pagination, PaginationQuery},
 	},
 	filesystem::{get_unknown_image, ContentType, FileParts, PathUtils},
 	prisma::{
-		age_restriction, session, user, user_login_activity, user_preferences,
-		PrismaClient,
+		age_restriction, session, two_factor_recovery_code, user, user_login_activity,
+		user_preferences, user_two_factor, PrismaClient,
 	},
+	NotifierEvent,
 };
 use tokio::fs;
 use tower_sessions::Session;
@@ -65,6 +67,7 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 						.delete(delete_user_by_id),
 				)
 				.route("/sessions", delete(delete_user_sessions))
+				.route("/two-factor", delete(delete_user_two_factor))
 				.route("/lock", put(update_user_lock_status))
 				.route("/login-activity", get(get_user_login_activity_by_id))
 				.route(
@@ -251,6 +254,11 @@ async fn delete_user_login_activity(
 pub struct UpdateUser {
 	pub username: String,
 	pub password: Option<String>,
+	/// The email address of the user, which is left unchanged if not provided. An empty
+	/// email address removes it
+	#[serde(default)]
+	#[specta(optional)]
+	pub email: Option<String>,
 	pub avatar_url: Option<String>,
 	#[serde(default)]
 	pub permissions: Vec<UserPermission>,
@@ -259,6 +267,37 @@ pub struct UpdateUser {
 	pub max_sessions_allowed: Option<i32>,
 }
 
+/// Validate an email address for a user, returning it in lowercase. An empty email address
+/// is treated as not having one
+async fn validate_user_email(
+	client: &PrismaClient,
+	email: &str,
+	for_user_id: Option<&str>,
+) -> APIResult<Option<String>> {
+	let email = email.trim().to_lowercase();
+	if email.is_empty() {
+		return Ok(None);
+	} else if !email.contains('@') {
+		return Err(APIError::BadRequest("Invalid email address".to_string()));
+	}
+
+	let existing_user = client
+		.user()
+		.find_first(chain_optional_iter(
+			[user::email::equals(Some(email.clone()))],
+			[for_user_id.map(|id| user::id::not(id.to_string()))],
+		))
+		.exec()
+		.await?;
+	if existing_user.is_some() {
+		return Err(APIError::BadRequest(
+			"The email address is already in use".to_string(),
+		));
+	}
+
+	Ok(Some(email))
+}
+
 async fn update_user(
 	by_user: &User,
 	client: &PrismaClient,
@@ -289,6 +328,10 @@ async fn update_user(
 		let hashed_password = bcrypt::hash(password, config.password_hash_cost)?;
 		update_params.push(user::hashed_password::set(hashed_password));
 	}
+	if let Some(email) = input.email {
+		let email = validate_user_email(client, &email, Some(&for_user_id)).await?;
+		update_params.push(user::email::set(email));
+	}
 
 	let to_update_is_server_owner = by_user.is_server_owner && by_user.id == for_user_id;
 	if to_update_is_server_owner {
@@ -366,6 +409,42 @@ async fn update_user(
 	}
 }
 
+/// Get a user as they were before an update, so the changes can be recorded in the audit log
+async fn get_user_before_update(
+	client: &PrismaClient,
+	id: &str,
+) -> APIResult<Option<User>> {
+	Ok(client
+		.user()
+		.find_unique(user::id::equals(id.to_string()))
+		.with(user::age_restriction::fetch())
+		.exec()
+		.await?
+		.map(User::from))
+}
+
+/// Record an update to a user in the audit log. A user never holds their password, so a
+/// changed password is recorded as a change without any values
+async fn record_user_update(
+	ctx: &AppState,
+	by_user: &User,
+	before: Option<&User>,
+	after: &User,
+	password_changed: bool,
+) {
+	let mut event = AuditEvent::new(AuditAction::UserUpdated, Some(by_user))
+		.with_target(&after.id, &after.username)
+		.with_changes(before, Some(after));
+	if password_changed {
+		event.changes.push(AuditLogChange {
+			field: "password".to_string(),
+			before: None,
+			after: None,
+		});
+	}
+	ctx.record_audit_event(event).await;
+}
+
 async fn update_preferences(
 	client: &PrismaClient,
 	preferences_id: String,
@@ -417,6 +496,10 @@ async fn update_preferences(
 pub struct CreateUser {
 	pub username: String,
 	pub password: String,
+	/// The email address of the user, which is used to send them password resets
+	#[serde(default)]
+	#[specta(optional)]
+	pub email: Option<String>,
 	#[serde(default)]
 	pub permissions: Vec<UserPermission>,
 	pub age_restriction: Option<AgeRestriction>,
@@ -446,6 +529,10 @@ async fn create_user(
 
 	let db = &ctx.db;
 
+	let email = match input.email.as_deref() {
+		Some(email) => validate_user_email(db, email, None).await?,
+		None => None,
+	};
 	let hashed_password = bcrypt::hash(input.password, ctx.config.password_hash_cost)?;
 
 	// TODO: https://github.com/Brendonovich/prisma-client-rust/issues/44
@@ -469,9 +556,12 @@ async fn create_user(
 							user::is_server_owner::set(false),
 							user::max_sessions_allowed::set(input.max_sessions_allowed),
 						],
-						[(!permissions.is_empty()).then(|| {
-							user::permissions::set(Some(permissions.join(",")))
-						})],
+						[
+							(!permissions.is_empty()).then(|| {
+								user::permissions::set(Some(permissions.join(",")))
+							}),
+							email.map(|email| user::email::set(Some(email))),
+						],
 					),
 				)
 				.exec()
@@ -518,8 +608,22 @@ async fn create_user(
 			"Failed to create user".to_string(),
 		))?;
 	tracing::trace!(final_user = ?created_user, "Final user result");
+	let created_user = User::from(created_user);
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserCreated, Some(req.user()))
+			.with_target(&created_user.id, &created_user.username)
+			.with_changes(None, Some(&created_user)),
+	)
+	.await;
+	ctx.send_notifier_event(
+		NotifierEvent::UserRegistered {
+			username: created_user.username.clone(),
+		},
+		None,
+	);
 
-	Ok(Json(created_user.into()))
+	Ok(Json(created_user))
 }
 
 #[utoipa::path(
@@ -544,8 +648,18 @@ async fn update_current_user(
 	let db = &ctx.db;
 	let user = req.user();
 
+	let user_before = get_user_before_update(db, &user.id).await?;
+	let password_changed = input.password.is_some();
 	let updated_user = update_user(user, db, user.id.clone(), input, &ctx.config).await?;
 	debug!(?updated_user, "Updated user");
+	record_user_update(
+		&ctx,
+		user,
+		user_before.as_ref(),
+		&updated_user,
+		password_changed,
+	)
+	.await;
 
 	if get_session_user(&session).await?.is_some() {
 		session
@@ -762,8 +876,15 @@ async fn delete_user_by_id(
 	}?;
 
 	debug!(?deleted_user, "Deleted user");
+	let deleted_user = User::from(deleted_user);
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserDeleted, Some(&user))
+			.with_target(&deleted_user.id, &deleted_user.username),
+	)
+	.await;
 
-	Ok(Json(User::from(deleted_user)))
+	Ok(Json(deleted_user))
 }
 
 #[utoipa::path(
@@ -873,8 +994,18 @@ async fn update_user_handler(
 		return Err(APIError::forbidden_discreet());
 	}
 
+	let user_before = get_user_before_update(db, &id).await?;
+	let password_changed = input.password.is_some();
 	let updated_user = update_user(user, db, id.clone(), input, &ctx.config).await?;
 	debug!(?updated_user, "Updated user");
+	record_user_update(
+		&ctx,
+		user,
+		user_before.as_ref(),
+		&updated_user,
+		password_changed,
+	)
+	.await;
 
 	if user.id == id && get_session_user(&session).await?.is_some() {
 		session
@@ -903,6 +1034,7 @@ async fn update_user_handler(
 		(status = 200, description = "Successfully deleted user sessions"),
 		(status = 401, description = "Unauthorized"),
 		(status = 403, description = "Forbidden"),
+		(status = 404, description = "User not found"),
 		(status = 500, description = "Internal server error"),
 	)
 )]
@@ -911,9 +1043,15 @@ async fn delete_user_sessions(
 	State(ctx): State<AppState>,
 	Extension(req): Extension<RequestContext>,
 ) -> APIResult<()> {
-	req.enforce_server_owner()?;
+	let by_user = req.server_owner_user()?;
 
 	let client = &ctx.db;
+	let for_user = client
+		.user()
+		.find_unique(user::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("User not found".to_string()))?;
 	let removed_sessions = client
 		.session()
 		.delete_many(vec![session::user_id::equals(id)])
@@ -921,6 +1059,68 @@ async fn delete_user_sessions(
 		.await?;
 	tracing::trace!(?removed_sessions, "Removed sessions for user");
 
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserSessionsRevoked, Some(&by_user))
+			.with_target(&for_user.id, &for_user.username),
+	)
+	.await;
+
+	Ok(())
+}
+
+#[utoipa::path(
+	delete,
+	path = "/api/v1/users/{id}/two-factor",
+	tag = "user",
+	params(
+		("id" = String, Path, description = "The user's ID.", example = "1ab2c3d4")
+	),
+	responses(
+		(status = 200, description = "Successfully disabled two-factor authentication for user"),
+		(status = 401, description = "Unauthorized"),
+		(status = 403, description = "Forbidden"),
+		(status = 404, description = "User not found"),
+		(status = 500, description = "Internal server error"),
+	)
+)]
+/// Disables two-factor authentication for a user, e.g. when they have lost access to both
+/// their authenticator app and their recovery codes
+async fn delete_user_two_factor(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<()> {
+	let by_user = req.server_owner_user()?;
+
+	let client = &ctx.db;
+	let for_user = client
+		.user()
+		.find_unique(user::id::equals(id.clone()))
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound("User not found".to_string()))?;
+	let (removed_two_factor, removed_recovery_codes) = client
+		._batch((
+			client
+				.user_two_factor()
+				.delete_many(vec![user_two_factor::user_id::equals(id.clone())]),
+			client
+				.two_factor_recovery_code()
+				.delete_many(vec![two_factor_recovery_code::user_id::equals(id)]),
+		))
+		.await?;
+	tracing::trace!(
+		?removed_two_factor,
+		?removed_recovery_codes,
+		"Removed two-factor authentication for user"
+	);
+
+	ctx.record_audit_event(
+		AuditEvent::new(AuditAction::UserTwoFactorReset, Some(&by_user))
+			.with_target(&for_user.id, &for_user.username),
+	)
+	.await;
+
 	Ok(())
 }
 
@@ -978,6 +1178,17 @@ async fn update_user_lock_status(
 		tracing::trace!(?removed_sessions, "Removed sessions for locked user");
 	}
 
+	let action = if input.lock {
+		AuditAction::UserLocked
+	} else {
+		AuditAction::UserUnlocked
+	};
+	ctx.record_audit_event(
+		AuditEvent::new(action, Some(&user))
+			.with_target(&updated_user.id, &updated_user.username),
+	)
+	.await;
+
 	Ok(Json(User::from(updated_user)))
 }
 
@@ -5,13 +5,15 @@ use axum::{
 	routing::{get, put},
 	Extension, Router,
 };
+use prisma_client_rust::chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use stump_core::{
 	db::entity::{
 		macros::{finished_session_koreader, reading_session_koreader},
-		UserPermission,
+		User, UserPermission,
 	},
+	filesystem::media::epub_locator::resolve_epub_locator,
 	prisma::{
 		active_reading_session, finished_reading_session, media,
 		registered_reading_device, user,
@@ -21,8 +23,8 @@ use stump_core::{
 use crate::{
 	config::state::AppState,
 	errors::{APIError, APIResult},
-	filter::chain_optional_iter,
 	middleware::auth::{api_key_middleware, RequestContext},
+	routers::api::filters::apply_media_restrictions_for_user,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -93,7 +95,8 @@ struct GetProgressResponse {
 	///
 	/// - A page number for page-based books (e.g. "24")
 	/// - An x-pointer for DOM-based books, using their "scrolling" reader. This maps to the location
-	///   in the DOM at the top of the screen at the time of sync. This is **not** an epubcfi string.
+	///   in the DOM at the top of the screen at the time of sync. This is **not** an epubcfi string,
+	///   but Stump translates between the two for EPUBs.
 	///
 	/// Please see this wonderful comment for additional context: https://github.com/stumpapp/stump/issues/239#issuecomment-2428256328
 	progress: Option<String>,
@@ -127,9 +130,10 @@ async fn get_progress(
 				.active_reading_session()
 				.find_first(vec![
 					active_reading_session::user_id::equals(user.id.clone()),
-					active_reading_session::media::is(vec![
-						media::koreader_hash::equals(Some(document_cpy.clone())),
-					]),
+					active_reading_session::media::is(accessible_document_params(
+						user,
+						document_cpy.clone(),
+					)),
 				])
 				.include(reading_session_koreader::include())
 				.exec()
@@ -138,9 +142,10 @@ async fn get_progress(
 			tx.finished_reading_session()
 				.find_first(vec![
 					finished_reading_session::user_id::equals(user.id.clone()),
-					finished_reading_session::media::is(vec![
-						media::koreader_hash::equals(Some(document_cpy)),
-					]),
+					finished_reading_session::media::is(accessible_document_params(
+						user,
+						document_cpy,
+					)),
 				])
 				.include(finished_session_koreader::include())
 				.exec()
@@ -158,6 +163,8 @@ async fn get_progress(
 			device_id: active_session.device.as_ref().map(|d| d.id.clone()),
 			progress: active_session
 				.koreader_progress
+				.clone()
+				.or_else(|| xpointer_for_session(&active_session))
 				.or_else(|| active_session.page.map(|p| p.to_string())),
 		},
 		(_, Some(finished_session)) => GetProgressResponse {
@@ -177,20 +184,41 @@ async fn get_progress(
 	Ok(Json(progress))
 }
 
+/// The conditions for the book with the given KOReader hash, as long as the user is allowed to
+/// access it. Books outside of the restrictions of the user (e.g. the scopes of their API key)
+/// are treated as if they don't exist
+fn accessible_document_params(user: &User, document: String) -> Vec<media::WhereParam> {
+	[media::koreader_hash::equals(Some(document))]
+		.into_iter()
+		.chain(apply_media_restrictions_for_user(user))
+		.collect()
+}
+
+/// Translates the epubcfi of a session into an x-pointer, for progress which was made outside
+/// of KOReader before Stump started locating it (or which could not be located at the time)
+fn xpointer_for_session(session: &reading_session_koreader::Data) -> Option<String> {
+	let cfi = session.epubcfi.as_deref()?;
+	if session.media.extension.to_lowercase() != "epub" {
+		return None;
+	}
+
+	resolve_epub_locator(&session.media.path, cfi)
+		.inspect_err(|error| {
+			tracing::warn!(?error, cfi, "Failed to translate epubcfi to an x-pointer");
+		})
+		.ok()
+		.and_then(|locator| locator.xpointer)
+}
+
 enum NativeProgress {
 	Page(i32),
 	EpubCfi(String),
 }
 
 /// Attempts to parse the progress string into a native progress type. If the progress string
-/// cannot be parsed, it is assumed to be an x-pointer. Stump does not support x-pointers, so
-/// this function will return `None` in that case.
+/// cannot be parsed, it is assumed to be an x-pointer, which must be located within the book
+/// to be translated (see [resolve_epub_locator]), so this function will return `None`.
 fn parse_progress(progress: &str) -> Option<NativeProgress> {
-	// This is a super naive check, but it should be good enough for now. Eventually
-	// I would really like to try and parse the x-pointer and translate it to a valid
-	// epubcfi if possible. The closest I've seen online to an epubcfi parser in rust is
-	// https://github.com/tnahs/readstor/blob/main/src/lib/models/epubcfi.rs. There are others
-	// in JS that can be ported, as well
 	if progress.starts_with("epubcfi(") && progress.ends_with(')') {
 		Some(NativeProgress::EpubCfi(progress.to_string()))
 	} else {
@@ -237,11 +265,27 @@ async fn put_progress(
 
 	let book = client
 		.media()
-		.find_first(vec![media::koreader_hash::equals(Some(document.clone()))])
+		.find_first(accessible_document_params(user, document.clone()))
 		.exec()
 		.await?
 		.ok_or_else(|| APIError::NotFound("Book not found".to_string()))?;
 
+	// Progress in an EPUB is located within the book, so that the web reader (which only
+	// understands epubcfi) can pick up where KOReader left off
+	let locator = match (
+		book.extension.to_lowercase().as_str(),
+		parse_progress(&progress),
+	) {
+		("epub", None | Some(NativeProgress::EpubCfi(_))) => {
+			resolve_epub_locator(&book.path, &progress)
+				.inspect_err(|error| {
+					tracing::debug!(?error, progress, "Failed to locate progress");
+				})
+				.ok()
+		},
+		_ => None,
+	};
+
 	let is_completed = percentage == 1.0;
 	let document_cpy = document.clone();
 	let (active_session, finished_session) = client
@@ -264,9 +308,10 @@ async fn put_progress(
 				.active_reading_session()
 				.find_first(vec![
 					active_reading_session::user_id::equals(user.id.clone()),
-					active_reading_session::media::is(vec![
-						media::koreader_hash::equals(Some(document_cpy.clone())),
-					]),
+					active_reading_session::media::is(accessible_document_params(
+						user,
+						document_cpy.clone(),
+					)),
 				])
 				.exec()
 				.await?;
@@ -285,7 +330,7 @@ async fn put_progress(
 					.create(
 						existing_active_session
 							.map(|s| s.started_at)
-							.unwrap_or_default(),
+							.unwrap_or_else(|| Utc::now().into()),
 						media::id::equals(book.id.clone()),
 						user::id::equals(user.id.clone()),
 						vec![finished_reading_session::device::connect(
@@ -296,25 +341,29 @@ async fn put_progress(
 					.await
 					.map(|session| (None, Some(session)))
 			} else {
-				let native_progress_set_param: Option<active_reading_session::SetParam> =
-					match parse_progress(&progress) {
-						Some(NativeProgress::Page(page)) => {
-							Some(active_reading_session::page::set(Some(page)))
+				let native_progress_set_params: Vec<active_reading_session::SetParam> =
+					match (locator, parse_progress(&progress)) {
+						(Some(locator), _) => locator.into_session_params(),
+						(_, Some(NativeProgress::Page(page))) => {
+							vec![active_reading_session::page::set(Some(page))]
 						},
-						Some(NativeProgress::EpubCfi(cfi)) => {
-							Some(active_reading_session::epubcfi::set(Some(cfi)))
+						(_, Some(NativeProgress::EpubCfi(cfi))) => {
+							vec![active_reading_session::epubcfi::set(Some(cfi))]
 						},
 						_ => {
 							tracing::debug!(
 								progress,
 								"Failed to parse progress string, assuming x-pointer"
 							);
-							None
+							vec![]
 						},
 					};
 
-				let set_params = chain_optional_iter(
-					[
+				// Note that the locator params are applied first, so that the progress is
+				// always stored exactly as KOReader reported it
+				let set_params = native_progress_set_params
+					.into_iter()
+					.chain([
 						active_reading_session::koreader_progress::set(Some(
 							progress.clone(),
 						)),
@@ -324,9 +373,8 @@ async fn put_progress(
 						active_reading_session::device::connect(
 							registered_reading_device::id::equals(device_id.clone()),
 						),
-					],
-					[native_progress_set_param],
-				);
+					])
+					.collect::<Vec<_>>();
 
 				tx.active_reading_session()
 					.upsert(
@@ -366,3 +414,53 @@ async fn put_progress(
 		timestamp,
 	}))
 }
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use stump_core::{db::entity::AgeRestriction, Ctx};
+
+	use super::*;
+
+	#[tokio::test]
+	async fn test_put_progress_for_inaccessible_document() {
+		let (ctx, mock_store) = Ctx::mock();
+		let client = ctx.db.clone();
+
+		let user = User {
+			id: "oromei-id".to_string(),
+			username: "oromei".to_string(),
+			age_restriction: Some(AgeRestriction {
+				age: 13,
+				restrict_on_unset: true,
+			}),
+			..Default::default()
+		};
+
+		// A book with the hash exists, but it is excluded by the restrictions of the user
+		mock_store
+			.expect(
+				client
+					.media()
+					.find_first(accessible_document_params(&user, "hash".to_string())),
+				None,
+			)
+			.await;
+
+		let result = put_progress(
+			State(Arc::new(ctx)),
+			Extension(RequestContext::for_user(user)),
+			Json(PutProgressInput {
+				document: "hash".to_string(),
+				progress: "1".to_string(),
+				percentage: 0.5,
+				device: "Kobo".to_string(),
+				device_id: "kobo-id".to_string(),
+			}),
+		)
+		.await;
+
+		assert!(matches!(result, Err(APIError::NotFound(_))));
+	}
+}
@@ -0,0 +1,496 @@
+//! Facets and advanced search for OPDS book feeds, shared by the v1.2 and v2.0 routers. Free
+//! text search and years are translated into a [SmartFilter], so OPDS clients search books like
+//! the web UI does. Genres, writers and publishers are matched exactly, since facet values are
+//! single items of the comma-separated metadata lists.
+
+use std::str::FromStr;
+
+use prisma_client_rust::{or, PrismaValue};
+use serde::{Deserialize, Serialize};
+use stump_core::{
+	db::{
+		entity::User,
+		filter::{
+			Filter, FilterGroup, FilterJoin, MediaMetadataSmartFilter, MediaSmartFilter,
+			SeriesSmartFilter, SmartFilter,
+		},
+		query::raw::RawSql,
+	},
+	opds::facet::{OPDSFacet, OPDSFacetGroup, OPDSFacetValues},
+	prisma::{media, media_metadata, PrismaClient},
+};
+
+use crate::{
+	errors::APIResult,
+	filter::{chain_optional_iter, ReadStatus},
+	routers::api::filters::{
+		apply_media_read_status_filter, media_restrictions_sql_for_user,
+	},
+};
+
+/// The maximum number of values shown for each metadata facet group
+const FACET_LIMIT: i64 = 25;
+
+/// The kinds of facets which may be applied to a book feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OPDSFacetKind {
+	Genre,
+	Publisher,
+	Writer,
+	Year,
+	ReadStatus,
+}
+
+impl OPDSFacetKind {
+	fn title(&self) -> &'static str {
+		match self {
+			OPDSFacetKind::Genre => "Genre",
+			OPDSFacetKind::Publisher => "Publisher",
+			OPDSFacetKind::Writer => "Writer",
+			OPDSFacetKind::Year => "Year",
+			OPDSFacetKind::ReadStatus => "Read Status",
+		}
+	}
+}
+
+/// The query params of an OPDS book feed. These come from either the facet links Stump
+/// generates or the OpenSearch template, which clients may send back with unfilled optional
+/// params as empty strings. Every param is therefore kept as a string and treated as unset
+/// when blank.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct OPDSBookQuery {
+	/// A free text search, matched against the name, title and series of a book. OPDS 1.2
+	/// clients send this as `search`, while OPDS 2.0 clients send `query`
+	#[serde(default, alias = "query")]
+	search: Option<String>,
+	#[serde(default)]
+	author: Option<String>,
+	#[serde(default)]
+	publisher: Option<String>,
+	#[serde(default)]
+	genre: Option<String>,
+	#[serde(default)]
+	year: Option<String>,
+	#[serde(default)]
+	read_status: Option<String>,
+}
+
+fn non_blank(value: &Option<String>) -> Option<&str> {
+	value
+		.as_deref()
+		.map(str::trim)
+		.filter(|value| !value.is_empty())
+}
+
+impl OPDSBookQuery {
+	pub(crate) fn search(&self) -> Option<&str> {
+		non_blank(&self.search)
+	}
+
+	fn year(&self) -> Option<i32> {
+		non_blank(&self.year).and_then(|year| year.parse().ok())
+	}
+
+	fn read_status(&self) -> Option<ReadStatus> {
+		non_blank(&self.read_status).and_then(|status| ReadStatus::from_str(status).ok())
+	}
+
+	fn value(&self, kind: OPDSFacetKind) -> Option<&str> {
+		match kind {
+			OPDSFacetKind::Genre => non_blank(&self.genre),
+			OPDSFacetKind::Publisher => non_blank(&self.publisher),
+			OPDSFacetKind::Writer => non_blank(&self.author),
+			OPDSFacetKind::Year => non_blank(&self.year),
+			OPDSFacetKind::ReadStatus => non_blank(&self.read_status),
+		}
+	}
+
+	/// Returns a copy of the query with the facet applied, or removed if it is already
+	/// applied. Clients follow these links to toggle facets on and off
+	fn toggled(&self, kind: OPDSFacetKind, value: &str) -> Self {
+		let next = (self.value(kind) != Some(value)).then(|| value.to_string());
+		let mut query = self.clone();
+		match kind {
+			OPDSFacetKind::Genre => query.genre = next,
+			OPDSFacetKind::Publisher => query.publisher = next,
+			OPDSFacetKind::Writer => query.author = next,
+			OPDSFacetKind::Year => query.year = next,
+			OPDSFacetKind::ReadStatus => query.read_status = next,
+		}
+		query
+	}
+
+	/// Whether the query has any search params or facets applied
+	pub(crate) fn is_empty(&self) -> bool {
+		self.to_query_string().is_empty()
+	}
+
+	/// Encode the query as a URL query string, without the leading `?`. Params which would
+	/// be ignored, e.g. a year which isn't a number, are dropped
+	fn to_query_string(&self) -> String {
+		let year = self.year().map(|year| year.to_string());
+		let read_status = self.read_status().and(non_blank(&self.read_status));
+
+		[
+			("search", self.search()),
+			("author", non_blank(&self.author)),
+			("publisher", non_blank(&self.publisher)),
+			("genre", non_blank(&self.genre)),
+			("year", year.as_deref()),
+			("read_status", read_status),
+		]
+		.into_iter()
+		.filter_map(|(key, value)| {
+			value.map(|value| format!("{key}={}", urlencoding::encode(value)))
+		})
+		.collect::<Vec<_>>()
+		.join("&")
+	}
+
+	/// Append the query to the given URL, if there is anything to append
+	pub(crate) fn href(&self, base_url: &str) -> String {
+		let query_string = self.to_query_string();
+		if query_string.is_empty() {
+			base_url.to_string()
+		} else {
+			format!("{base_url}?{query_string}")
+		}
+	}
+
+	/// Translate the search and year of the query into a smart filter. Each criterion is its
+	/// own group, and the groups are joined with [FilterJoin::And]
+	fn to_smart_filter(&self) -> SmartFilter<MediaSmartFilter> {
+		let contains = |value: &str| Filter::Contains {
+			contains: value.to_string(),
+		};
+
+		let search_group = self.search().map(|search| FilterGroup::Or {
+			or: vec![
+				MediaSmartFilter::Name {
+					name: contains(search),
+				},
+				MediaSmartFilter::Metadata {
+					metadata: MediaMetadataSmartFilter::Title {
+						title: contains(search),
+					},
+				},
+				MediaSmartFilter::Series {
+					series: SeriesSmartFilter::Name {
+						name: contains(search),
+					},
+				},
+			],
+		});
+
+		let year_group = self.year().map(|year| FilterGroup::And {
+			and: vec![MediaSmartFilter::Metadata {
+				metadata: MediaMetadataSmartFilter::Year {
+					year: Filter::Equals { equals: year },
+				},
+			}],
+		});
+
+		SmartFilter {
+			groups: chain_optional_iter([], [search_group, year_group]),
+		}
+	}
+
+	/// The where params for the genre, writer and publisher of the query, which are matched
+	/// exactly rather than as substrings (e.g. `Action` should not match `Transaction`)
+	fn metadata_params(&self) -> Vec<media_metadata::WhereParam> {
+		chain_optional_iter(
+			[],
+			[
+				non_blank(&self.author).map(|author| {
+					list_includes(
+						author,
+						media_metadata::writers::equals,
+						media_metadata::writers::starts_with,
+						media_metadata::writers::ends_with,
+						media_metadata::writers::contains,
+					)
+				}),
+				non_blank(&self.publisher).map(|publisher| {
+					media_metadata::publisher::equals(Some(publisher.to_string()))
+				}),
+				non_blank(&self.genre).map(|genre| {
+					list_includes(
+						genre,
+						media_metadata::genre::equals,
+						media_metadata::genre::starts_with,
+						media_metadata::genre::ends_with,
+						media_metadata::genre::contains,
+					)
+				}),
+			],
+		)
+	}
+
+	/// The where params for the books matching the query, for the given user. This does not
+	/// include the restrictions of the user (e.g. age restrictions), which callers are expected
+	/// to apply themselves
+	pub(crate) fn params_for_user(&self, user: &User) -> Vec<media::WhereParam> {
+		let smart_filter = self.to_smart_filter();
+		let filter_params = (!smart_filter.groups.is_empty())
+			.then(|| smart_filter.into_params(FilterJoin::And));
+		let metadata_params = self.metadata_params();
+		let metadata_params =
+			(!metadata_params.is_empty()).then(|| media::metadata::is(metadata_params));
+
+		chain_optional_iter(
+			apply_media_read_status_filter(
+				user.id.clone(),
+				self.read_status().into_iter().collect(),
+			),
+			[filter_params, metadata_params],
+		)
+	}
+
+	/// The raw SQL equivalent of [OPDSBookQuery::params_for_user], along with the restrictions
+	/// of the user, as a condition over `media m`. Facets are counted over the books this
+	/// matches, so it must be kept in sync with the where params
+	fn to_sql_for_user(&self, user: &User) -> RawSql {
+		let contains = |column: &str, value: &str| {
+			RawSql::new(
+				format!("INSTR(LOWER({column}), LOWER({{}})) > 0"),
+				vec![PrismaValue::String(value.to_string())],
+			)
+		};
+		let metadata = |condition: RawSql| {
+			RawSql::new(
+				"EXISTS (SELECT 1 FROM media_metadata fmm WHERE fmm.media_id = m.id AND ",
+				vec![],
+			)
+			.append(condition)
+			.push(")")
+		};
+
+		let search = self.search().map(|search| {
+			RawSql::or([
+				contains("m.name", search),
+				metadata(contains("fmm.title", search)),
+				RawSql::new(
+					"EXISTS (SELECT 1 FROM series fs WHERE fs.id = m.series_id AND ",
+					vec![],
+				)
+				.append(contains("fs.name", search))
+				.push(")"),
+			])
+		});
+		let metadata_conditions = [
+			non_blank(&self.author)
+				.map(|author| list_includes_sql("fmm.writers", author)),
+			non_blank(&self.publisher).map(|publisher| {
+				RawSql::new(
+					"fmm.publisher = {}",
+					vec![PrismaValue::String(publisher.to_string())],
+				)
+			}),
+			non_blank(&self.genre).map(|genre| list_includes_sql("fmm.genre", genre)),
+			self.year().map(|year| {
+				RawSql::new("fmm.year = {}", vec![PrismaValue::Int(year.into())])
+			}),
+		]
+		.map(|condition| condition.map(metadata));
+		let read_status = self
+			.read_status()
+			.map(|status| read_status_sql(&user.id, status));
+
+		RawSql::and(chain_optional_iter(
+			[media_restrictions_sql_for_user(user)],
+			std::iter::once(search)
+				.chain(metadata_conditions)
+				.chain([read_status]),
+		))
+	}
+
+	/// Generate the facet groups for a book feed at `base_url`. Metadata facets are built from
+	/// the most common values among the books in the feed, i.e. those matching the query
+	pub(crate) async fn facet_groups(
+		&self,
+		client: &PrismaClient,
+		user: &User,
+		base_url: &str,
+	) -> APIResult<Vec<OPDSFacetGroup>> {
+		let OPDSFacetValues {
+			genres,
+			publishers,
+			writers,
+			years,
+		} = OPDSFacetValues::load(client, self.to_sql_for_user(user), FACET_LIMIT).await?;
+
+		let with_counts = |values: Vec<(String, i64)>| {
+			values
+				.into_iter()
+				.map(|(value, count)| (value.clone(), value, Some(count)))
+				.collect::<Vec<_>>()
+		};
+		let read_statuses = [
+			("Unread", "unread"),
+			("Reading", "reading"),
+			("Completed", "completed"),
+		]
+		.into_iter()
+		.map(|(title, value)| (title.to_string(), value.to_string(), None))
+		.collect::<Vec<_>>();
+
+		let groups = [
+			(OPDSFacetKind::ReadStatus, read_statuses),
+			(OPDSFacetKind::Genre, with_counts(genres)),
+			(OPDSFacetKind::Publisher, with_counts(publishers)),
+			(OPDSFacetKind::Writer, with_counts(writers)),
+			(
+				OPDSFacetKind::Year,
+				with_counts(
+					years
+						.into_iter()
+						.map(|(year, count)| (year.to_string(), count))
+						.collect(),
+				),
+			),
+		];
+
+		Ok(groups
+			.into_iter()
+			.map(|(kind, values)| self.facet_group(kind, values, base_url))
+			.filter(|group| !group.facets.is_empty())
+			.collect())
+	}
+
+	fn facet_group(
+		&self,
+		kind: OPDSFacetKind,
+		values: Vec<(String, String, Option<i64>)>,
+		base_url: &str,
+	) -> OPDSFacetGroup {
+		let active_value = self.value(kind);
+		// An applied facet might not be among the most common values, but it should still be
+		// listed so that clients can show it as active and remove it
+		let missing_active_value = active_value
+			.filter(|active| !values.iter().any(|(_, value, _)| value == active))
+			.map(|active| (active.to_string(), active.to_string(), None));
+
+		let facets = missing_active_value
+			.into_iter()
+			.chain(values)
+			.map(|(title, value, count)| OPDSFacet {
+				title,
+				href: self.toggled(kind, &value).href(base_url),
+				count,
+				active: active_value == Some(value.as_str()),
+			})
+			.collect();
+
+		OPDSFacetGroup {
+			title: kind.title().to_string(),
+			facets,
+		}
+	}
+}
+
+/// The where param for a comma-separated metadata list which includes `value` as one of its
+/// items, whether or not the items are separated by a space
+fn list_includes(
+	value: &str,
+	equals: fn(Option<String>) -> media_metadata::WhereParam,
+	starts_with: fn(String) -> media_metadata::WhereParam,
+	ends_with: fn(String) -> media_metadata::WhereParam,
+	contains: fn(String) -> media_metadata::WhereParam,
+) -> media_metadata::WhereParam {
+	or![
+		equals(Some(value.to_string())),
+		starts_with(format!("{value},")),
+		ends_with(format!(",{value}")),
+		ends_with(format!(", {value}")),
+		contains(format!(",{value},")),
+		contains(format!(", {value},"))
+	]
+}
+
+/// The raw SQL equivalent of [list_includes]. Spaces around the commas are removed before
+/// looking for the value between them
+fn list_includes_sql(column: &str, value: &str) -> RawSql {
+	RawSql::new(
+		format!(
+			"INSTR(',' || REPLACE(REPLACE(LOWER({column}), ', ', ','), ' ,', ',') || ',', ',' || LOWER({{}}) || ',') > 0"
+		),
+		vec![PrismaValue::String(value.to_string())],
+	)
+}
+
+/// The raw SQL equivalent of [apply_media_read_status_filter] for a single status
+fn read_status_sql(user_id: &str, status: ReadStatus) -> RawSql {
+	let user_id = || PrismaValue::String(user_id.to_string());
+	match status {
+		ReadStatus::Reading => RawSql::new(
+			"EXISTS (SELECT 1 FROM reading_sessions rs WHERE rs.media_id = m.id AND rs.user_id = {})",
+			vec![user_id()],
+		),
+		ReadStatus::Completed => RawSql::new(
+			"EXISTS (SELECT 1 FROM finished_reading_sessions frs WHERE frs.media_id = m.id AND frs.user_id = {})",
+			vec![user_id()],
+		),
+		ReadStatus::Unread => RawSql::new(
+			"NOT EXISTS (SELECT 1 FROM reading_sessions rs WHERE rs.media_id = m.id AND rs.user_id = {}) \
+			AND NOT EXISTS (SELECT 1 FROM finished_reading_sessions frs WHERE frs.media_id = m.id AND frs.user_id = {})",
+			vec![user_id(), user_id()],
+		),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn query(pairs: &str) -> OPDSBookQuery {
+		serde_qs::from_str(pairs).unwrap()
+	}
+
+	#[test]
+	fn test_blank_params_are_ignored() {
+		let query = query("search=batman&author=&publisher=%20&year=%7Bdc%3Adate%3F%7D");
+
+		assert_eq!(query.to_query_string(), "search=batman");
+		assert_eq!(query.year(), None);
+		assert_eq!(query.to_smart_filter().groups.len(), 1);
+	}
+
+	#[test]
+	fn test_into_smart_filter() {
+		let query = query("query=batman&author=Frank%20Miller&year=1986");
+		assert_eq!(query.metadata_params().len(), 1);
+
+		let filter = serde_json::to_value(query.to_smart_filter()).unwrap();
+		assert_eq!(
+			filter,
+			serde_json::json!({
+				"groups": [
+					{
+						"or": [
+							{ "name": { "contains": "batman" } },
+							{ "metadata": { "title": { "contains": "batman" } } },
+							{ "series": { "name": { "contains": "batman" } } }
+						]
+					},
+					{ "and": [{ "metadata": { "year": { "equals": 1986 } } }] }
+				]
+			})
+		);
+	}
+
+	#[test]
+	fn test_toggled_facets() {
+		let query = query("genre=Horror");
+
+		assert_eq!(
+			query.toggled(OPDSFacetKind::Genre, "Horror").href("/books"),
+			"/books"
+		);
+		assert_eq!(
+			query
+				.toggled(OPDSFacetKind::ReadStatus, "unread")
+				.href("/books"),
+			"/books?genre=Horror&read_status=unread"
+		);
+	}
+}
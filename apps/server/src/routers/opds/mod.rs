@@ -2,6 +2,7 @@ use axum::Router;
 
 use crate::config::state::AppState;
 
+mod facets;
 pub(crate) mod v1_2;
 pub(crate) mod v2_0;
 
@@ -8,10 +8,16 @@ use prisma_client_rust::or;
 use prisma_client_rust::{chrono, Direction};
 use serde::{Deserialize, Serialize};
 use stump_core::{
-	db::{entity::UserPermission, query::pagination::PageQuery},
+	db::{
+		entity::{AccessRole, SmartList, UserPermission},
+		query::pagination::PageQuery,
+	},
 	filesystem::{
 		get_page_async,
-		image::{GenericImageProcessor, ImageProcessor, ImageProcessorOptions},
+		image::{
+			resize_image_to_max_width, GenericImageProcessor, ImageProcessor,
+			ImageProcessorOptions,
+		},
 		ContentType,
 	},
 	opds::v1_2::{
@@ -22,7 +28,10 @@ use stump_core::{
 		link::{OpdsLink, OpdsLinkRel, OpdsLinkType},
 		opensearch::OpdsOpenSearch,
 	},
-	prisma::{active_reading_session, library, media, series, series_metadata, user},
+	prisma::{
+		active_reading_session, collection, library, media, series, series_metadata,
+		smart_list, user,
+	},
 };
 use tracing::{debug, trace};
 
@@ -33,15 +42,21 @@ use crate::{
 	middleware::auth::{api_key_middleware, auth_middleware, RequestContext},
 	routers::api::{
 		filters::{
-			apply_in_progress_filter_for_user, apply_media_age_restriction,
-			apply_media_library_not_hidden_for_user_filter, apply_series_age_restriction,
+			apply_in_progress_filter_for_user, apply_media_restrictions_for_user,
+			apply_series_restrictions_for_user, collection_visible_to_user_filter,
 			library_not_hidden_from_user_filter,
 		},
-		v1::media::thumbnails::get_media_thumbnail_by_id,
+		v1::{
+			collection::fetch_collection_for_user,
+			media::thumbnails::get_media_thumbnail_by_id,
+			smart_list::smart_list_access_for_user,
+		},
 	},
 	utils::http::{ImageResponse, NamedFile, Xml},
 };
 
+use super::facets::OPDSBookQuery;
+
 pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 	let primary_router = Router::new()
 		.route("/catalog", get(catalog))
@@ -53,6 +68,12 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 				.route("/", get(get_libraries))
 				.route("/{id}", get(get_library_by_id)),
 		)
+		.nest(
+			"/collections",
+			Router::new()
+				.route("/", get(get_collections))
+				.route("/{id}", get(get_collection_by_id)),
+		)
 		.nest(
 			"/series",
 			Router::new()
@@ -60,6 +81,13 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 				.route("/latest", get(get_latest_series))
 				.route("/{id}", get(get_series_by_id)),
 		)
+		.nest(
+			"/smart-lists",
+			Router::new()
+				.route("/", get(get_smart_lists))
+				.route("/{id}", get(get_smart_list_by_id)),
+		)
+		.route("/books", get(get_books))
 		.nest(
 			"/books/{id}",
 			Router::new()
@@ -125,6 +153,23 @@ struct OPDSSearchQuery {
 	search: Option<String>,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OPDSPageStreamQuery {
+	/// The maximum width of the streamed page. This is kept as a string since clients which
+	/// don't support the `{maxWidth}` template variable send it back verbatim
+	#[serde(default)]
+	max_width: Option<String>,
+}
+
+impl OPDSPageStreamQuery {
+	fn max_width(&self) -> Option<u32> {
+		self.max_width
+			.as_deref()
+			.and_then(|width| width.parse().ok())
+			.filter(|width| *width > 0)
+	}
+}
+
 fn pagination_bounds(page: i64, page_size: i64) -> (i64, i64) {
 	let skip = page * page_size;
 	(skip, page_size)
@@ -147,7 +192,7 @@ fn service_url(req_ctx: &RequestContext) -> String {
 }
 
 async fn catalog(Extension(req): Extension<RequestContext>) -> APIResult<Xml> {
-	let entries = vec![
+	let mut entries = vec![
 		OpdsEntry::new(
 			"keepReading".to_string(),
 			chrono::Utc::now().into(),
@@ -161,6 +206,19 @@ async fn catalog(Extension(req): Extension<RequestContext>) -> APIResult<Xml> {
 			}]),
 			None,
 		),
+		OpdsEntry::new(
+			"allBooks".to_string(),
+			chrono::Utc::now().into(),
+			"All books".to_string(),
+			Some(String::from("Browse and filter all books")),
+			None,
+			Some(vec![OpdsLink {
+				link_type: OpdsLinkType::Acquisition,
+				rel: OpdsLinkRel::Subsection,
+				href: catalog_url(&req, "books"),
+			}]),
+			None,
+		),
 		OpdsEntry::new(
 			"allSeries".to_string(),
 			chrono::Utc::now().into(),
@@ -200,11 +258,37 @@ async fn catalog(Extension(req): Extension<RequestContext>) -> APIResult<Xml> {
 			}]),
 			None,
 		),
-		// TODO: more?
-		// TODO: get user stored searches, so they don't have to redo them over and over?
-		// e.g. /opds/v1.2/series?search={searchTerms}, /opds/v1.2/libraries?search={searchTerms}, etc.
+		OpdsEntry::new(
+			"allCollections".to_string(),
+			chrono::Utc::now().into(),
+			"All collections".to_string(),
+			Some(String::from("Browse by collection")),
+			None,
+			Some(vec![OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::Subsection,
+				href: catalog_url(&req, "collections"),
+			}]),
+			None,
+		),
 	];
 
+	if req.user().has_permission(UserPermission::AccessSmartList) {
+		entries.push(OpdsEntry::new(
+			"smartLists".to_string(),
+			chrono::Utc::now().into(),
+			"Smart lists".to_string(),
+			Some(String::from("Browse your saved smart lists")),
+			None,
+			Some(vec![OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::Subsection,
+				href: catalog_url(&req, "smart-lists"),
+			}]),
+			None,
+		));
+	}
+
 	let links = vec![
 		OpdsLink {
 			link_type: OpdsLinkType::Navigation,
@@ -245,14 +329,19 @@ async fn keep_reading(
 ) -> APIResult<Xml> {
 	let db = &ctx.db;
 
-	let user_id = req.id();
-	let in_progress_filter = vec![apply_in_progress_filter_for_user(user_id)];
+	let user = req.user();
+	let in_progress_filter = vec![apply_in_progress_filter_for_user(user.id.clone())];
 
 	let media = db
 		.media()
-		.find_many(vec![media::active_user_reading_sessions::some(
-			in_progress_filter.clone(),
-		)])
+		.find_many(
+			[media::active_user_reading_sessions::some(
+				in_progress_filter.clone(),
+			)]
+			.into_iter()
+			.chain(apply_media_restrictions_for_user(user))
+			.collect(),
+		)
 		.with(media::active_user_reading_sessions::fetch(
 			in_progress_filter,
 		))
@@ -364,10 +453,8 @@ async fn get_library_by_id(
 	let (skip, take) = pagination_bounds(page.into(), 20);
 
 	let user = req.user();
-	let age_restrictions = user
-		.age_restriction
-		.as_ref()
-		.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset));
+	let library_filter = library_not_hidden_from_user_filter(user);
+	let series_restrictions = apply_series_restrictions_for_user(user);
 
 	debug!(skip, take, page, library_id, "opds get_library_by_id");
 
@@ -376,24 +463,23 @@ async fn get_library_by_id(
 		.run(|client| async move {
 			let library = client
 				.library()
-				.find_unique(library::id::equals(id.clone()))
+				.find_first(vec![library::id::equals(id.clone()), library_filter])
 				.with(
-					library::series::fetch(chain_optional_iter(
-						[],
-						[age_restrictions.clone()],
-					))
-					.skip(skip)
-					.take(take),
+					library::series::fetch(series_restrictions.clone())
+						.skip(skip)
+						.take(take),
 				)
 				.exec()
 				.await?;
 
 			client
 				.series()
-				.count(chain_optional_iter(
-					[series::library_id::equals(Some(id.clone()))],
-					[age_restrictions],
-				))
+				.count(
+					[series::library_id::equals(Some(id.clone()))]
+						.into_iter()
+						.chain(series_restrictions)
+						.collect(),
+				)
 				.exec()
 				.await
 				.map(|count| (library, Some(count)))
@@ -437,6 +523,304 @@ async fn get_library_by_id(
 	}
 }
 
+/// A handler for GET /opds/v1.2/collections, accepts a `search` URL param
+async fn get_collections(
+	State(ctx): State<AppState>,
+	Query(OPDSSearchQuery { search }): Query<OPDSSearchQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let user = req.user();
+	let collections = ctx
+		.db
+		.collection()
+		.find_many(chain_optional_iter(
+			collection_visible_to_user_filter(user),
+			[search.map(collection::name::contains)],
+		))
+		.order_by(collection::name::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	let entries = collections
+		.into_iter()
+		.map(|c| {
+			OpdsEntry::new(
+				c.id.clone(),
+				c.updated_at,
+				c.name,
+				c.description,
+				None,
+				Some(vec![OpdsLink {
+					link_type: OpdsLinkType::Navigation,
+					rel: OpdsLinkRel::Subsection,
+					href: catalog_url(&req, &format!("collections/{}", c.id)),
+				}]),
+				None,
+			)
+		})
+		.collect::<Vec<OpdsEntry>>();
+
+	let feed = OpdsFeed::new(
+		"allCollections".to_string(),
+		"All collections".to_string(),
+		Some(vec![
+			OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::ItSelf,
+				href: catalog_url(&req, "collections"),
+			},
+			OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::Start,
+				href: catalog_url(&req, "catalog"),
+			},
+		]),
+		entries,
+	);
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A handler for GET /opds/v1.2/collections/{id}. The series and books of the collection
+/// are listed in the order they were curated, omitting any the user cannot access
+async fn get_collection_by_id(
+	State(ctx): State<AppState>,
+	Path(OPDSURLParams {
+		params: OPDSIDURLParams { id },
+		..
+	}): Path<OPDSURLParams<OPDSIDURLParams>>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let collection = fetch_collection_for_user(&ctx.db, &id, req.user()).await?;
+
+	let entries = collection
+		.items()
+		.map(|items| items.to_owned())
+		.unwrap_or_default()
+		.into_iter()
+		.filter_map(|item| match (item.media, item.series) {
+			(Some(Some(book)), _) => Some(
+				OPDSEntryBuilder::<media::Data>::new(*book, req.api_key())
+					.into_opds_entry(),
+			),
+			(_, Some(Some(series))) => Some(
+				OPDSEntryBuilder::<series::Data>::new(*series, req.api_key())
+					.into_opds_entry(),
+			),
+			_ => None,
+		})
+		.collect::<Vec<OpdsEntry>>();
+
+	let feed = OpdsFeed::new(
+		collection.id.clone(),
+		collection.name.clone(),
+		Some(vec![
+			OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::ItSelf,
+				href: catalog_url(&req, &format!("collections/{}", collection.id)),
+			},
+			OpdsLink {
+				link_type: OpdsLinkType::Navigation,
+				rel: OpdsLinkRel::Start,
+				href: catalog_url(&req, "catalog"),
+			},
+		]),
+		entries,
+	);
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A helper to fetch a page of books matching the given params and build an acquisition
+/// feed for them. The user's restrictions are always applied on top of the params
+async fn fetch_books_and_generate_feed(
+	ctx: &AppState,
+	req: &RequestContext,
+	where_params: Vec<media::WhereParam>,
+	page: u32,
+	params: OPDSFeedBuilderParams,
+) -> APIResult<OpdsFeed> {
+	let (skip, take) = pagination_bounds(page.into(), 20);
+
+	let user = req.user();
+	let user_id = user.id.clone();
+	let where_params = apply_media_restrictions_for_user(user)
+		.into_iter()
+		.chain(where_params)
+		.collect::<Vec<_>>();
+
+	let (books, count) = ctx
+		.db
+		._transaction()
+		.run(|client| async move {
+			let books = client
+				.media()
+				.find_many(where_params.clone())
+				.with(media::active_user_reading_sessions::fetch(vec![
+					active_reading_session::user_id::equals(user_id),
+				]))
+				.order_by(media::name::order(Direction::Asc))
+				.skip(skip)
+				.take(take)
+				.exec()
+				.await?;
+
+			client
+				.media()
+				.count(where_params)
+				.exec()
+				.await
+				.map(|count| (books, count))
+		})
+		.await?;
+
+	let entries = books
+		.into_iter()
+		.map(|m| OPDSEntryBuilder::<media::Data>::new(m, req.api_key()).into_opds_entry())
+		.collect();
+
+	Ok(
+		OPDSFeedBuilder::new(req.api_key()).paginated(OPDSFeedBuilderParams {
+			entries,
+			page_params: Some(OPDSFeedBuilderPageParams {
+				page: page.into(),
+				count,
+			}),
+			..params
+		})?,
+	)
+}
+
+/// A handler for GET /opds/v1.2/books, accepts a `page` URL param. The books may be narrowed
+/// down with the facets of the feed, or the advanced search params of the OpenSearch template
+async fn get_books(
+	State(ctx): State<AppState>,
+	Query(pagination): Query<PageQuery>,
+	Query(book_query): Query<OPDSBookQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let user = req.user();
+
+	let title = match book_query.search() {
+		Some(search) => format!("Search results for {search}"),
+		None => "All books".to_string(),
+	};
+
+	let feed = fetch_books_and_generate_feed(
+		&ctx,
+		&req,
+		book_query.params_for_user(user),
+		pagination.page.unwrap_or(0),
+		OPDSFeedBuilderParams {
+			id: "books".to_string(),
+			title,
+			href_postfix: book_query.href("books"),
+			..Default::default()
+		},
+	)
+	.await?;
+
+	let facets = book_query
+		.facet_groups(&ctx.db, user, &catalog_url(&req, "books"))
+		.await?;
+
+	Ok(Xml(feed.with_facets(facets).build()?))
+}
+
+/// A handler for GET /opds/v1.2/smart-lists, returns a navigation feed of the smart lists
+/// the user has access to
+async fn get_smart_lists(
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::AccessSmartList])?;
+
+	let smart_lists = ctx
+		.db
+		.smart_list()
+		.find_many(vec![smart_list_access_for_user(
+			&user,
+			AccessRole::Reader.value(),
+		)])
+		.order_by(smart_list::name::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	let entries = smart_lists
+		.into_iter()
+		.map(|smart_list| {
+			OpdsEntry::new(
+				smart_list.id.clone(),
+				chrono::Utc::now().into(),
+				smart_list.name,
+				smart_list.description,
+				None,
+				Some(vec![OpdsLink {
+					link_type: OpdsLinkType::Acquisition,
+					rel: OpdsLinkRel::Subsection,
+					href: catalog_url(&req, &format!("smart-lists/{}", smart_list.id)),
+				}]),
+				None,
+			)
+		})
+		.collect();
+
+	let feed = OPDSFeedBuilder::new(req.api_key()).unpaged(OPDSFeedBuilderParams {
+		id: "smartLists".to_string(),
+		title: "Smart lists".to_string(),
+		entries,
+		href_postfix: "smart-lists".to_string(),
+		..Default::default()
+	})?;
+
+	Ok(Xml(feed.build()?))
+}
+
+/// A handler for GET /opds/v1.2/smart-lists/{id}, accepts a `page` URL param. Returns the
+/// books matched by the smart list's filters
+async fn get_smart_list_by_id(
+	Path(OPDSURLParams {
+		params: OPDSIDURLParams { id },
+		..
+	}): Path<OPDSURLParams<OPDSIDURLParams>>,
+	State(ctx): State<AppState>,
+	Query(pagination): Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Xml> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::AccessSmartList])?;
+
+	let smart_list: SmartList = ctx
+		.db
+		.smart_list()
+		.find_first(vec![
+			smart_list::id::equals(id.clone()),
+			smart_list_access_for_user(&user, AccessRole::Reader.value()),
+		])
+		.exec()
+		.await?
+		.ok_or_else(|| APIError::NotFound("Smart list not found".to_string()))?
+		.try_into()?;
+
+	let title = smart_list.name.clone();
+	let href_postfix = format!("smart-lists/{id}");
+	let feed = fetch_books_and_generate_feed(
+		&ctx,
+		&req,
+		smart_list.into_params_for_user(&user),
+		pagination.page.unwrap_or(0),
+		OPDSFeedBuilderParams {
+			id,
+			title,
+			href_postfix,
+			..Default::default()
+		},
+	)
+	.await?;
+
+	Ok(Xml(feed.build()?))
+}
+
 // FIXME: Based on testing with Panels, it seems like pagination isn't an expected default when
 // a search is present? This feels both odd but understandable to support an "at a glance" view,
 // but I feel like it should still support pagination...
@@ -454,31 +838,22 @@ async fn get_series(
 	let page = pagination.page.unwrap_or(0);
 	let (skip, take) = pagination_bounds(page.into(), 20);
 
-	let user = req.user();
-	let age_restrictions = user
-		.age_restriction
-		.as_ref()
-		.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset));
+	let series_restrictions = apply_series_restrictions_for_user(req.user());
 
-	let search_clone = search.clone();
+	let search_param = search.clone().map(|q| {
+		or![
+			series::name::contains(q.clone()),
+			series::metadata::is(vec![series_metadata::title::contains(q)])
+		]
+	});
 	let (series, count) = db
 		._transaction()
 		.run(|client| async move {
 			let series = client
 				.series()
 				.find_many(chain_optional_iter(
-					[],
-					[
-						age_restrictions.clone(),
-						search_clone.map(|q| {
-							or![
-								series::name::contains(q.clone()),
-								series::metadata::is(vec![
-									series_metadata::title::contains(q),
-								])
-							]
-						}),
-					],
+					series_restrictions.clone(),
+					[search_param],
 				))
 				.skip(skip)
 				.take(take)
@@ -488,7 +863,7 @@ async fn get_series(
 
 			client
 				.series()
-				.count(chain_optional_iter([], [age_restrictions]))
+				.count(series_restrictions)
 				.exec()
 				.await
 				.map(|count| (series, count))
@@ -527,18 +902,14 @@ async fn get_latest_series(
 	let page = pagination.page.unwrap_or(0);
 	let (skip, take) = pagination_bounds(page.into(), 20);
 
-	let user = req.user();
-	let age_restrictions = user
-		.age_restriction
-		.as_ref()
-		.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset));
+	let series_restrictions = apply_series_restrictions_for_user(req.user());
 
 	let (series, count) = db
 		._transaction()
 		.run(|client| async move {
 			let series = client
 				.series()
-				.find_many(chain_optional_iter([], [age_restrictions.clone()]))
+				.find_many(series_restrictions.clone())
 				.order_by(series::updated_at::order(Direction::Desc))
 				.skip(skip)
 				.take(take)
@@ -547,7 +918,7 @@ async fn get_latest_series(
 
 			client
 				.series()
-				.count(chain_optional_iter([], [age_restrictions]))
+				.count(series_restrictions)
 				.exec()
 				.await
 				.map(|count| (series, count))
@@ -591,22 +962,25 @@ async fn get_series_by_id(
 	let page = pagination.page.unwrap_or(0);
 	let (skip, take) = pagination_bounds(page.into(), 20);
 	let user = req.user();
-	let age_restrictions = user
-		.age_restriction
-		.as_ref()
-		.map(|ar| apply_series_age_restriction(ar.age, ar.restrict_on_unset));
+	let series_restrictions = apply_series_restrictions_for_user(user);
+	let media_restrictions = apply_media_restrictions_for_user(user);
 
 	let tx_result = db
 		._transaction()
 		.run(|client| async move {
-			let series = db
+			let series = client
 				.series()
-				.find_first(chain_optional_iter(
-					[series::id::equals(id.clone())],
-					[age_restrictions.clone()],
-				))
+				.find_first(
+					[series::id::equals(id.clone())]
+						.into_iter()
+						.chain(series_restrictions.clone())
+						.collect(),
+				)
 				.with(
-					series::media::fetch(vec![])
+					series::media::fetch(media_restrictions.clone())
+						.with(media::active_user_reading_sessions::fetch(vec![
+							active_reading_session::user_id::equals(user.id.clone()),
+						]))
 						.skip(skip)
 						.take(take)
 						.order_by(media::name::order(Direction::Asc)),
@@ -616,13 +990,20 @@ async fn get_series_by_id(
 
 			client
 				.media()
-				.count(vec![
-					media::series_id::equals(Some(id.clone())),
-					media::series::is(chain_optional_iter(
-						[series::id::equals(id.clone())],
-						[age_restrictions],
-					)),
-				])
+				.count(
+					[
+						media::series_id::equals(Some(id.clone())),
+						media::series::is(
+							[series::id::equals(id.clone())]
+								.into_iter()
+								.chain(series_restrictions)
+								.collect(),
+						),
+					]
+					.into_iter()
+					.chain(media_restrictions)
+					.collect(),
+				)
 				.exec()
 				.await
 				.map(|count| (series, Some(count)))
@@ -694,7 +1075,8 @@ async fn get_book_thumbnail(
 	handle_opds_image_response(content_type, image_buffer)
 }
 
-/// A handler for GET /opds/v1.2/books/{id}/page/{page}, returns the page
+/// A handler for GET /opds/v1.2/books/{id}/page/{page}, returns the page. This is the
+/// OPDS-PSE stream endpoint, so the page may be downscaled to the client's `max_width`
 async fn get_book_page(
 	Path(OPDSURLParams {
 		params: OPDSPageURLParams { id, page },
@@ -702,15 +1084,12 @@ async fn get_book_page(
 	}): Path<OPDSURLParams<OPDSPageURLParams>>,
 	State(ctx): State<AppState>,
 	pagination: Query<PageQuery>,
+	Query(stream_query): Query<OPDSPageStreamQuery>,
 	Extension(req): Extension<RequestContext>,
 ) -> APIResult<ImageResponse> {
 	let client = &ctx.db;
 
 	let user = req.user();
-	let age_restrictions = user
-		.age_restriction
-		.as_ref()
-		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
 
 	// OPDS defaults to zero-indexed pages, I don't even think it allows the
 	// zero_based query param to be set.
@@ -722,13 +1101,12 @@ async fn get_book_page(
 
 	let book = client
 		.media()
-		.find_first(chain_optional_iter(
+		.find_first(
 			[media::id::equals(id.clone())]
 				.into_iter()
-				.chain(apply_media_library_not_hidden_for_user_filter(user))
-				.collect::<Vec<_>>(),
-			[age_restrictions],
-		))
+				.chain(apply_media_restrictions_for_user(user))
+				.collect(),
+		)
 		.exec()
 		.await?
 		.ok_or(APIError::NotFound(String::from("Book not found")))?;
@@ -749,7 +1127,9 @@ async fn get_book_page(
 		let finished_session = client
 			.finished_reading_session()
 			.create(
-				deleted_session.map(|s| s.started_at).unwrap_or_default(),
+				deleted_session
+					.map(|s| s.started_at)
+					.unwrap_or_else(|| chrono::Utc::now().into()),
 				media::id::equals(id.clone()),
 				user::id::equals(user.id.clone()),
 				vec![],
@@ -773,8 +1153,19 @@ async fn get_book_page(
 			.await?;
 	}
 
-	let (content_type, image_buffer) =
+	let (content_type, mut image_buffer) =
 		get_page_async(book.path.as_str(), correct_page, &ctx.config).await?;
+
+	if let Some(max_width) = stream_query.max_width() {
+		// GIFs are left as-is, since resizing would drop their animation
+		if matches!(
+			content_type,
+			ContentType::PNG | ContentType::JPEG | ContentType::WEBP
+		) {
+			image_buffer = resize_image_to_max_width(image_buffer, max_width).await?;
+		}
+	}
+
 	handle_opds_image_response(content_type, image_buffer)
 }
 
@@ -790,19 +1181,17 @@ async fn download_book(
 	let db = &ctx.db;
 
 	let user = req.user_and_enforce_permissions(&[UserPermission::DownloadFile])?;
-	let age_restrictions = user
-		.age_restriction
-		.as_ref()
-		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
 
 	trace!(?id, ?filename, "download_book");
 
 	let book = db
 		.media()
-		.find_first(chain_optional_iter(
-			[media::id::equals(id.clone())],
-			[age_restrictions],
-		))
+		.find_first(
+			[media::id::equals(id.clone())]
+				.into_iter()
+				.chain(apply_media_restrictions_for_user(&user))
+				.collect(),
+		)
 		.exec()
 		.await?
 		.ok_or(APIError::NotFound(String::from("Book not found")))?;
@@ -2,14 +2,13 @@ use std::path::PathBuf;
 
 use axum::{
 	extract::{Path, Query, State},
-	http::{header, HeaderValue},
+	http::{header, HeaderValue, StatusCode},
 	middleware,
 	response::IntoResponse,
 	routing::get,
 	Extension, Json, Router,
 };
-use prisma_client_rust::{and, operator, or, Direction};
-use serde::{Deserialize, Serialize};
+use prisma_client_rust::{and, chrono::Utc, operator, or, Direction};
 use stump_core::{
 	db::{
 		entity::{
@@ -21,17 +20,21 @@ use stump_core::{
 				apply_media_age_restriction,
 				apply_media_library_not_hidden_for_user_filter,
 			},
-			User, UserPermission,
+			AccessRole, SmartList, User, UserPermission,
 		},
 		query::pagination::PageQuery,
 	},
-	filesystem::get_page_async,
+	filesystem::{
+		get_page_async,
+		media::epub_locator::{resolve_epub_locator, EpubLocations},
+	},
 	opds::v2_0::{
 		authentication::{
 			OPDSAuthenticationDocument, OPDSAuthenticationDocumentBuilder,
 			OPDSSupportedAuthFlow, OPDS_AUTHENTICATION_DOCUMENT_TYPE,
 		},
 		books_as_publications,
+		facet::OPDSFeedFacet,
 		feed::{OPDSFeed, OPDSFeedBuilder},
 		group::OPDSFeedGroupBuilder,
 		link::{
@@ -44,7 +47,8 @@ use stump_core::{
 		reading_session_opds_progression,
 	},
 	prisma::{
-		active_reading_session, library, media, media_metadata, series, series_metadata,
+		active_reading_session, collection, finished_reading_session, library, media,
+		registered_reading_device, series, series_metadata, smart_list, user,
 	},
 	Ctx,
 };
@@ -58,15 +62,24 @@ use crate::{
 		host::HostExtractor,
 	},
 	routers::{
-		api::filters::{
-			apply_in_progress_filter_for_user, apply_media_restrictions_for_user,
-			apply_series_restrictions_for_user, library_not_hidden_from_user_filter,
+		api::{
+			filters::{
+				apply_in_progress_filter_for_user, apply_media_restrictions_for_user,
+				apply_series_restrictions_for_user, collection_visible_to_user_filter,
+				library_not_hidden_from_user_filter,
+			},
+			v1::{
+				collection::fetch_collection_for_user,
+				smart_list::smart_list_access_for_user,
+			},
 		},
 		relative_favicon_path,
 	},
 	utils::http::{ImageResponse, NamedFile},
 };
 
+use super::facets::OPDSBookQuery;
+
 const DEFAULT_LIMIT: i64 = 10;
 
 pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
@@ -96,8 +109,18 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 						Router::new().route("/", get(browse_series_by_id)),
 					),
 				)
-				// TODO(OPDS-V2): Support smart list feeds
-				// .nest("/smart-lists", Router::new())
+				.nest(
+					"/collections",
+					Router::new()
+						.route("/", get(browse_collections))
+						.route("/{id}", get(browse_collection_by_id)),
+				)
+				.nest(
+					"/smart-lists",
+					Router::new()
+						.route("/", get(browse_smart_lists))
+						.route("/{id}", get(browse_smart_list_by_id)),
+				)
 				.nest(
 					"/books",
 					Router::new()
@@ -110,8 +133,10 @@ pub(crate) fn mount(app_state: AppState) -> Router<AppState> {
 								.route("/", get(get_book_by_id))
 								.route("/thumbnail", get(get_book_thumbnail))
 								.route("/pages/{page}", get(get_book_page))
-								// TODO: PUT progression
-								.route("/progression", get(get_book_progression))
+								.route(
+									"/progression",
+									get(get_book_progression).put(put_book_progression),
+								)
 								.route("/file", get(download_book)),
 						),
 				),
@@ -140,12 +165,6 @@ impl IntoResponse for OPDSAuthDocWrapper {
 	}
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct OPDSSearchQuery {
-	#[serde(default)]
-	query: Option<String>,
-}
-
 #[tracing::instrument]
 async fn auth(HostExtractor(host): HostExtractor) -> APIResult<OPDSAuthDocWrapper> {
 	Ok(OPDSAuthDocWrapper(
@@ -206,6 +225,47 @@ async fn catalog(
 		)
 		.build()?;
 
+	let collection_conditions = collection_visible_to_user_filter(user);
+	let collections = client
+		.collection()
+		.find_many(collection_conditions.clone())
+		.order_by(collection::name::order(Direction::Asc))
+		.take(DEFAULT_LIMIT)
+		.exec()
+		.await?;
+	let collection_count = client
+		.collection()
+		.count(collection_conditions)
+		.exec()
+		.await?;
+	let collection_group = OPDSFeedGroupBuilder::default()
+		.metadata(
+			OPDSMetadataBuilder::default()
+				.title("Collections".to_string())
+				.pagination(Some(
+					OPDSPaginationMetadataBuilder::default()
+						.number_of_items(collection_count)
+						.items_per_page(DEFAULT_LIMIT)
+						.current_page(1)
+						.build()?,
+				))
+				.build()?,
+		)
+		.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
+			OPDSBaseLinkBuilder::default()
+				.href("/opds/v2.0/collections".to_string())
+				.rel(OPDSLinkRel::SelfLink.item())
+				.build()?,
+		)]))
+		.navigation(
+			collections
+				.into_iter()
+				.map(OPDSNavigationLink::from)
+				.map(|link| link.finalize(&link_finalizer))
+				.collect::<Vec<OPDSNavigationLink>>(),
+		)
+		.build()?;
+
 	let latest_books_conditions = apply_media_restrictions_for_user(user);
 	let latest_books = client
 		.media()
@@ -299,6 +359,40 @@ async fn catalog(
 		.publications(publications)
 		.build()?;
 
+	let mut navigation = vec![
+		OPDSNavigationLinkBuilder::default()
+			.title("Libraries".to_string())
+			.base_link(
+				OPDSBaseLinkBuilder::default()
+					.href(link_finalizer.format_link("/opds/v2.0/libraries"))
+					.rel(OPDSLinkRel::Subsection.item())
+					.build()?,
+			)
+			.build()?,
+		OPDSNavigationLinkBuilder::default()
+			.title("Collections".to_string())
+			.base_link(
+				OPDSBaseLinkBuilder::default()
+					.href(link_finalizer.format_link("/opds/v2.0/collections"))
+					.rel(OPDSLinkRel::Subsection.item())
+					.build()?,
+			)
+			.build()?,
+	];
+	if user.has_permission(UserPermission::AccessSmartList) {
+		navigation.push(
+			OPDSNavigationLinkBuilder::default()
+				.title("Smart Lists".to_string())
+				.base_link(
+					OPDSBaseLinkBuilder::default()
+						.href(link_finalizer.format_link("/opds/v2.0/smart-lists"))
+						.rel(OPDSLinkRel::Subsection.item())
+						.build()?,
+				)
+				.build()?,
+		);
+	}
+
 	Ok(Json(
 		OPDSFeedBuilder::default()
 			.metadata(
@@ -317,128 +411,139 @@ async fn catalog(
 					.rel(OPDSLinkRel::Start.item())
 					.build()?.as_link(),
 				OPDSBaseLinkBuilder::default()
-					.href("/opds/v2.0/search{?query}".to_string())
+					.href(
+						"/opds/v2.0/search{?query,author,publisher,genre,year}".to_string(),
+					)
 					.rel(OPDSLinkRel::Search.item())
 					._type(OPDSLinkType::OpdsJson)
 					.templated(true)
 					.build()?.as_link(),
 			]))
-			.navigation(vec![OPDSNavigationLinkBuilder::default()
-				.title("Libraries".to_string())
-				.base_link(
-					OPDSBaseLinkBuilder::default()
-						.href(link_finalizer.format_link("/opds/v2.0/libraries"))
-						.rel(OPDSLinkRel::Subsection.item())
-						.build()?,
-				)
-				.build()?])
-			.groups(vec![library_group, latest_books_group, keep_reading_group])
+			.navigation(navigation)
+			.groups(vec![
+				library_group,
+				collection_group,
+				latest_books_group,
+				keep_reading_group,
+			])
 			.build()?,
 	))
 }
 
+/// A route handler which searches the libraries, series and books a user has access to. Books
+/// may additionally be filtered by the advanced search params of the search template (author,
+/// publisher, genre and year), in which case libraries and series are only searched if a free
+/// text query was also given
 #[tracing::instrument(err, skip(ctx))]
 async fn search(
 	State(ctx): State<AppState>,
 	HostExtractor(host): HostExtractor,
-	Query(OPDSSearchQuery { query }): Query<OPDSSearchQuery>,
+	Query(book_query): Query<OPDSBookQuery>,
 	Extension(req): Extension<RequestContext>,
 ) -> APIResult<Json<OPDSFeed>> {
 	let client = &ctx.db;
 
 	let user = req.user();
 	let link_finalizer = OPDSLinkFinalizer::from(host);
-	let query = query.ok_or(APIError::BadRequest(
-		"Query parameter is required".to_string(),
-	))?;
+	if book_query.is_empty() {
+		return Err(APIError::BadRequest(
+			"A query or at least one search parameter is required".to_string(),
+		));
+	}
 
-	let library_conditions = vec![
-		library::name::contains(query.clone()),
-		library_not_hidden_from_user_filter(user),
-	];
-	let libraries = client
-		.library()
-		.find_many(library_conditions.clone())
-		.take(DEFAULT_LIMIT)
-		.exec()
-		.await?;
-	let library_count = client.library().count(library_conditions).exec().await?;
-	let library_group = OPDSFeedGroupBuilder::default()
-		.metadata(
-			OPDSMetadataBuilder::default()
-				.title("Libraries".to_string())
-				.pagination(Some(
-					OPDSPaginationMetadataBuilder::default()
-						.number_of_items(library_count)
-						.items_per_page(DEFAULT_LIMIT)
-						.current_page(1)
+	let mut groups = Vec::new();
+
+	if let Some(query) = book_query.search().map(str::to_string) {
+		let library_conditions = vec![
+			library::name::contains(query.clone()),
+			library_not_hidden_from_user_filter(user),
+		];
+		let libraries = client
+			.library()
+			.find_many(library_conditions.clone())
+			.take(DEFAULT_LIMIT)
+			.exec()
+			.await?;
+		let library_count = client.library().count(library_conditions).exec().await?;
+		groups.push(
+			OPDSFeedGroupBuilder::default()
+				.metadata(
+					OPDSMetadataBuilder::default()
+						.title("Libraries".to_string())
+						.pagination(Some(
+							OPDSPaginationMetadataBuilder::default()
+								.number_of_items(library_count)
+								.items_per_page(DEFAULT_LIMIT)
+								.current_page(1)
+								.build()?,
+						))
 						.build()?,
-				))
+				)
+				.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href(format!("/opds/v2.0/libraries/search?query={query}"))
+						.rel(OPDSLinkRel::SelfLink.item())
+						.build()?,
+				)]))
+				.navigation(
+					libraries
+						.into_iter()
+						.map(OPDSNavigationLink::from)
+						.map(|link| link.finalize(&link_finalizer))
+						.collect::<Vec<OPDSNavigationLink>>(),
+				)
 				.build()?,
-		)
-		.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
-				OPDSBaseLinkBuilder::default()
-					.href(format!("/opds/v2.0/libraries/search?query={}", query.clone()))
-					.rel(OPDSLinkRel::SelfLink.item())
-					.build()?,
-			)]))
-		.navigation(
-			libraries
-				.into_iter()
-				.map(OPDSNavigationLink::from)
-				.map(|link| link.finalize(&link_finalizer))
-				.collect::<Vec<OPDSNavigationLink>>(),
-		)
-		.build()?;
-
-	let series_conditions = vec![
-		or![
-			series::name::contains(query.clone()),
-			series::metadata::is(vec![series_metadata::title::contains(query.clone())]),
-		],
-		operator::and(apply_series_restrictions_for_user(user)),
-	];
-	let series = client
-		.series()
-		.find_many(series_conditions.clone())
-		.take(DEFAULT_LIMIT)
-		.exec()
-		.await?;
-	let series_count = client.series().count(series_conditions).exec().await?;
-
-	let series_group = OPDSFeedGroupBuilder::default()
-		.metadata(
-			OPDSMetadataBuilder::default()
-				.title("Series".to_string())
-				.pagination(Some(
-					OPDSPaginationMetadataBuilder::default()
-						.number_of_items(series_count)
-						.items_per_page(DEFAULT_LIMIT)
-						.current_page(1)
+		);
+
+		let series_conditions = vec![
+			or![
+				series::name::contains(query.clone()),
+				series::metadata::is(vec![series_metadata::title::contains(
+					query.clone()
+				)]),
+			],
+			operator::and(apply_series_restrictions_for_user(user)),
+		];
+		let series = client
+			.series()
+			.find_many(series_conditions.clone())
+			.take(DEFAULT_LIMIT)
+			.exec()
+			.await?;
+		let series_count = client.series().count(series_conditions).exec().await?;
+		groups.push(
+			OPDSFeedGroupBuilder::default()
+				.metadata(
+					OPDSMetadataBuilder::default()
+						.title("Series".to_string())
+						.pagination(Some(
+							OPDSPaginationMetadataBuilder::default()
+								.number_of_items(series_count)
+								.items_per_page(DEFAULT_LIMIT)
+								.current_page(1)
+								.build()?,
+						))
 						.build()?,
-				))
+				)
+				.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
+					OPDSBaseLinkBuilder::default()
+						.href(format!("/opds/v2.0/series/search?query={query}"))
+						.rel(OPDSLinkRel::SelfLink.item())
+						.build()?,
+				)]))
+				.navigation(
+					series
+						.into_iter()
+						.map(OPDSNavigationLink::from)
+						.map(|link| link.finalize(&link_finalizer))
+						.collect::<Vec<OPDSNavigationLink>>(),
+				)
 				.build()?,
-		)
-		.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
-				OPDSBaseLinkBuilder::default()
-					.href(format!("/opds/v2.0/series/search?query={}", query.clone()))
-					.rel(OPDSLinkRel::SelfLink.item())
-					.build()?,
-			)]))
-		.navigation(
-			series
-				.into_iter()
-				.map(OPDSNavigationLink::from)
-				.map(|link| link.finalize(&link_finalizer))
-				.collect::<Vec<OPDSNavigationLink>>(),
-		)
-		.build()?;
+		);
+	}
 
 	let book_conditions = vec![
-		or![
-			media::name::contains(query.clone()),
-			media::metadata::is(vec![media_metadata::title::contains(query.clone())]),
-		],
+		operator::and(book_query.params_for_user(user)),
 		operator::and(apply_media_restrictions_for_user(user)),
 	];
 	let books = client
@@ -452,47 +557,56 @@ async fn search(
 	let books_count = client.media().count(book_conditions).exec().await?;
 	let publications =
 		OPDSPublication::vec_from_books(&ctx.db, link_finalizer.clone(), books).await?;
-	let books_group = OPDSFeedGroupBuilder::default()
-		.metadata(
-			OPDSMetadataBuilder::default()
-				.title("Books".to_string())
-				.pagination(Some(
-					OPDSPaginationMetadataBuilder::default()
-						.number_of_items(books_count)
-						.items_per_page(DEFAULT_LIMIT)
-						.current_page(1)
-						.build()?,
-				))
-				.build()?,
-		)
-		.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
+	groups.push(
+		OPDSFeedGroupBuilder::default()
+			.metadata(
+				OPDSMetadataBuilder::default()
+					.title("Books".to_string())
+					.pagination(Some(
+						OPDSPaginationMetadataBuilder::default()
+							.number_of_items(books_count)
+							.items_per_page(DEFAULT_LIMIT)
+							.current_page(1)
+							.build()?,
+					))
+					.build()?,
+			)
+			.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
 				OPDSBaseLinkBuilder::default()
-					.href(format!("/opds/v2.0/books/search?query={}", query.clone()))
+					.href(book_query.href("/opds/v2.0/books/browse"))
 					.rel(OPDSLinkRel::SelfLink.item())
 					.build()?,
 			)]))
-		.publications(publications)
-		.build()?;
+			.publications(publications)
+			.build()?,
+	);
+
+	let title = match book_query.search() {
+		Some(query) => format!("Search - {query}"),
+		None => "Search".to_string(),
+	};
 
 	Ok(Json(
 		OPDSFeedBuilder::default()
 			.metadata(
 				OPDSMetadataBuilder::default()
-					.title(format!("Search - {}", query.clone()))
+					.title(title)
 					.modified(OPDSMetadata::generate_modified())
 					.build()?,
 			)
 			.links(link_finalizer.finalize_all(vec![
-					OPDSBaseLinkBuilder::default()
-						.href(format!("/opds/v2.0/search?query={}", query.clone()))
-						.rel(OPDSLinkRel::SelfLink.item())
-						.build()?.as_link(),
-					OPDSBaseLinkBuilder::default()
-						.href("/opds/v2.0/catalog".to_string())
-						.rel(OPDSLinkRel::Start.item())
-						.build()?.as_link(),
-				]))
-			.groups(vec![library_group, series_group, books_group])
+				OPDSBaseLinkBuilder::default()
+					.href(book_query.href("/opds/v2.0/search"))
+					.rel(OPDSLinkRel::SelfLink.item())
+					.build()?
+					.as_link(),
+				OPDSBaseLinkBuilder::default()
+					.href("/opds/v2.0/catalog".to_string())
+					.rel(OPDSLinkRel::Start.item())
+					.build()?
+					.as_link(),
+			]))
+			.groups(groups)
 			.build()?,
 	))
 }
@@ -757,6 +871,149 @@ async fn browse_library_by_id(
 	))
 }
 
+/// A route handler which returns a feed of the collections visible to the user
+#[tracing::instrument(skip(ctx))]
+async fn browse_collections(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let client = &ctx.db;
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	let user = req.user();
+
+	let (skip, take) = pagination.get_skip_take();
+	let collection_conditions = collection_visible_to_user_filter(user);
+	let collections = client
+		.collection()
+		.find_many(collection_conditions.clone())
+		.order_by(collection::name::order(Direction::Asc))
+		.take(take)
+		.skip(skip)
+		.exec()
+		.await?;
+	let collection_count = client
+		.collection()
+		.count(collection_conditions)
+		.exec()
+		.await?;
+
+	Ok(Json(
+		OPDSFeedBuilder::default()
+			.metadata(
+				OPDSMetadataBuilder::default()
+					.title("Browse Collections".to_string())
+					.pagination(Some(
+						OPDSPaginationMetadataBuilder::default()
+							.number_of_items(collection_count)
+							.items_per_page(take)
+							.current_page(pagination.page.map_or(1, i64::from))
+							.build()?,
+					))
+					.build()?,
+			)
+			.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
+				OPDSBaseLinkBuilder::default()
+					.href("/opds/v2.0/collections".to_string())
+					.rel(OPDSLinkRel::SelfLink.item())
+					.build()?,
+			)]))
+			.navigation(
+				collections
+					.into_iter()
+					.map(OPDSNavigationLink::from)
+					.map(|link| link.finalize(&link_finalizer))
+					.collect::<Vec<OPDSNavigationLink>>(),
+			)
+			.build()?,
+	))
+}
+
+/// A route handler which returns a feed for a single collection. The series in the collection
+/// are listed as navigation and the books as publications, each in their curated order
+#[tracing::instrument(skip(ctx))]
+async fn browse_collection_by_id(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	Path(id): Path<String>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let client = &ctx.db;
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	let user = req.user();
+
+	let collection = fetch_collection_for_user(client, &id, user).await?;
+	let items = collection.items().cloned().unwrap_or_default();
+
+	let collection_series = items
+		.iter()
+		.filter_map(|item| item.series.clone().flatten())
+		.map(|series| OPDSNavigationLink::from(*series))
+		.map(|link| link.finalize(&link_finalizer))
+		.collect::<Vec<OPDSNavigationLink>>();
+	let series_group = OPDSFeedGroupBuilder::default()
+		.metadata(
+			OPDSMetadataBuilder::default()
+				.title("Series".to_string())
+				.build()?,
+		)
+		.navigation(collection_series)
+		.build()?;
+
+	let book_ids = items
+		.iter()
+		.filter_map(|item| item.media_id.clone())
+		.collect::<Vec<String>>();
+	let mut collection_books = client
+		.media()
+		.find_many(
+			[media::id::in_vec(book_ids.clone())]
+				.into_iter()
+				.chain(apply_media_restrictions_for_user(user))
+				.collect(),
+		)
+		.include(books_as_publications::include())
+		.exec()
+		.await?;
+	collection_books.sort_by_key(|book| book_ids.iter().position(|id| id == &book.id));
+	let books_group = OPDSFeedGroupBuilder::default()
+		.metadata(
+			OPDSMetadataBuilder::default()
+				.title("Books".to_string())
+				.build()?,
+		)
+		.publications(
+			OPDSPublication::vec_from_books(
+				&ctx.db,
+				link_finalizer.clone(),
+				collection_books,
+			)
+			.await?,
+		)
+		.build()?;
+
+	Ok(Json(
+		OPDSFeedBuilder::default()
+			.metadata(
+				OPDSMetadataBuilder::default()
+					.title(collection.name)
+					.description(collection.description)
+					.build()?,
+			)
+			.links(link_finalizer.finalize_all(vec![OPDSLink::Link(
+				OPDSBaseLinkBuilder::default()
+					.href(format!("/opds/v2.0/collections/{id}"))
+					.rel(OPDSLinkRel::SelfLink.item())
+					.build()?,
+			)]))
+			.groups(vec![series_group, books_group])
+			.build()?,
+	))
+}
+
 /// A helper function to fetch books and generate an OPDS feed for a user. This is not a route
 #[allow(clippy::too_many_arguments)]
 async fn fetch_books_and_generate_feed(
@@ -795,12 +1052,14 @@ async fn fetch_books_and_generate_feed(
 	let publications =
 		OPDSPublication::vec_from_books(client, link_finalizer.clone(), books).await?;
 
+	// The base URL may already carry a query, e.g. the facets applied to the feed
+	let page_separator = if base_url.contains('?') { '&' } else { '?' };
 	let next_page = pagination.get_next_page();
 	let previous_link = if let Some(page) = pagination.page {
 		Some(
 			link_finalizer.finalize(OPDSLink::Link(
 				OPDSBaseLinkBuilder::default()
-					.href(format!("{base_url}?page={page}"))
+					.href(format!("{base_url}{page_separator}page={page}"))
 					.rel(OPDSLinkRel::Previous.item())
 					.build()?,
 			)),
@@ -825,7 +1084,7 @@ async fn fetch_books_and_generate_feed(
 			),
 			OPDSLink::Link(
 				OPDSBaseLinkBuilder::default()
-					.href(format!("{base_url}?page={next_page}"))
+					.href(format!("{base_url}{page_separator}page={next_page}"))
 					.rel(OPDSLinkRel::Next.item())
 					.build()?,
 			),
@@ -1010,25 +1269,125 @@ async fn browse_series_by_id(
 	.await
 }
 
-/// A route handler which returns a feed of books for a user.
+/// A route handler which returns a feed of books for a user. The books may be narrowed down
+/// with the facets of the feed, which are generated from the metadata of the user's books
 #[tracing::instrument(skip(ctx))]
 async fn browse_books(
 	State(ctx): State<AppState>,
 	HostExtractor(host): HostExtractor,
 	pagination: Query<PageQuery>,
+	Query(book_query): Query<OPDSBookQuery>,
 	Extension(req): Extension<RequestContext>,
 ) -> APIResult<Json<OPDSFeed>> {
 	let user = req.user();
+	let link_finalizer = OPDSLinkFinalizer::from(host);
 
-	fetch_books_and_generate_feed(
+	let Json(feed) = fetch_books_and_generate_feed(
 		&ctx,
-		OPDSLinkFinalizer::from(host),
+		link_finalizer.clone(),
 		user,
-		vec![],
+		book_query.params_for_user(user),
 		media::name::order(Direction::Asc),
 		pagination.0,
 		"Browse All Books",
-		"/opds/v2.0/books/browse",
+		&book_query.href("/opds/v2.0/books/browse"),
+	)
+	.await?;
+
+	let facets = book_query
+		.facet_groups(&ctx.db, user, "/opds/v2.0/books/browse")
+		.await?
+		.into_iter()
+		.map(|group| OPDSFeedFacet::new(group, &link_finalizer))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(Json(feed.with_facets(facets)))
+}
+
+/// A route handler which returns a feed of the smart lists the user has access to
+#[tracing::instrument(skip(ctx))]
+async fn browse_smart_lists(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::AccessSmartList])?;
+	let link_finalizer = OPDSLinkFinalizer::from(host);
+
+	let smart_lists = ctx
+		.db
+		.smart_list()
+		.find_many(vec![smart_list_access_for_user(
+			&user,
+			AccessRole::Reader.value(),
+		)])
+		.order_by(smart_list::name::order(Direction::Asc))
+		.exec()
+		.await?;
+
+	Ok(Json(
+		OPDSFeedBuilder::default()
+			.metadata(
+				OPDSMetadataBuilder::default()
+					.title("Smart Lists".to_string())
+					.build()?,
+			)
+			.links(link_finalizer.finalize_all(vec![
+				OPDSBaseLinkBuilder::default()
+					.href("/opds/v2.0/smart-lists".to_string())
+					.rel(OPDSLinkRel::SelfLink.item())
+					.build()?
+					.as_link(),
+				OPDSBaseLinkBuilder::default()
+					.href("/opds/v2.0/catalog".to_string())
+					.rel(OPDSLinkRel::Start.item())
+					.build()?
+					.as_link(),
+			]))
+			.navigation(
+				smart_lists
+					.into_iter()
+					.map(OPDSNavigationLink::from)
+					.map(|link| link.finalize(&link_finalizer))
+					.collect::<Vec<OPDSNavigationLink>>(),
+			)
+			.build()?,
+	))
+}
+
+/// A route handler which returns a feed of the books matched by a smart list's filters
+#[tracing::instrument(skip(ctx))]
+async fn browse_smart_list_by_id(
+	State(ctx): State<AppState>,
+	HostExtractor(host): HostExtractor,
+	Path(id): Path<String>,
+	pagination: Query<PageQuery>,
+	Extension(req): Extension<RequestContext>,
+) -> APIResult<Json<OPDSFeed>> {
+	let user = req.user_and_enforce_permissions(&[UserPermission::AccessSmartList])?;
+
+	let smart_list: SmartList = ctx
+		.db
+		.smart_list()
+		.find_first(vec![
+			smart_list::id::equals(id.clone()),
+			smart_list_access_for_user(&user, AccessRole::Reader.value()),
+		])
+		.exec()
+		.await?
+		.ok_or_else(|| APIError::NotFound("Smart list not found".to_string()))?
+		.try_into()?;
+
+	let title = smart_list.name.clone();
+	fetch_books_and_generate_feed(
+		&ctx,
+		OPDSLinkFinalizer::from(host),
+		&user,
+		smart_list.into_params_for_user(&user),
+		media::name::order(Direction::Asc),
+		pagination.0,
+		&title,
+		&format!("/opds/v2.0/smart-lists/{id}"),
 	)
 	.await
 }
@@ -1203,6 +1562,7 @@ async fn get_book_progression(
 		.find_first(vec![
 			active_reading_session::media_id::equals(id),
 			apply_in_progress_filter_for_user(user.id.clone()),
+			active_reading_session::media::is(apply_media_restrictions_for_user(user)),
 		])
 		.select(reading_session_opds_progression::select())
 		.exec()
@@ -1214,6 +1574,207 @@ async fn get_book_progression(
 	Ok(Json(OPDSProgression::new(reading_session, link_finalizer)?))
 }
 
+/// A route handler which updates the progression of a book for a user, following the
+/// Readium progression API. Progression which is older than what the server already has
+/// is rejected with a 409, so the client can fetch the newer progression instead.
+///
+/// Page-based books are located by the position (or page link), and EPUBs by an epubcfi
+/// fragment or else the href and progression of the spine resource.
+#[tracing::instrument(skip(ctx, progression))]
+async fn put_book_progression(
+	Path(id): Path<String>,
+	State(ctx): State<AppState>,
+	Extension(req): Extension<RequestContext>,
+	Json(progression): Json<OPDSProgression>,
+) -> APIResult<StatusCode> {
+	let client = &ctx.db;
+
+	let user = req.user();
+	let age_restrictions = user
+		.age_restriction
+		.as_ref()
+		.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
+	let where_params = chain_optional_iter(
+		[media::id::equals(id)]
+			.into_iter()
+			.chain(apply_media_library_not_hidden_for_user_filter(user))
+			.collect::<Vec<media::WhereParam>>(),
+		[age_restrictions],
+	);
+
+	let book = client
+		.media()
+		.find_first(where_params)
+		.exec()
+		.await?
+		.ok_or(APIError::NotFound(String::from("Book not found")))?;
+
+	if let Some(total_progression) = progression.total_progression() {
+		if !(0.0..=1.0).contains(&total_progression) {
+			return Err(APIError::BadRequest(
+				"The total progression must be between 0 and 1".to_string(),
+			));
+		}
+	}
+
+	let existing_session = client
+		.active_reading_session()
+		.find_unique(active_reading_session::user_id_media_id(
+			user.id.clone(),
+			book.id.clone(),
+		))
+		.exec()
+		.await?;
+	if let (Some(session), Some(modified)) = (&existing_session, progression.modified()) {
+		if session.updated_at > modified {
+			return Err(APIError::Conflict(
+				"The server has more recent progression for this book".to_string(),
+			));
+		}
+	}
+
+	let (progress_params, is_completed) = if book.extension.to_lowercase() == "epub" {
+		let locator = match (progression.epubcfi(), progression.href()) {
+			(Some(cfi), _) => resolve_epub_locator(&book.path, cfi),
+			(None, Some(href)) => {
+				EpubLocations::open(&book.path).and_then(|mut locations| {
+					locations.locate_progression(
+						href,
+						progression.progression().unwrap_or_default(),
+					)
+				})
+			},
+			_ => {
+				return Err(APIError::BadRequest(
+					"The progression must include an epubcfi fragment or an href"
+						.to_string(),
+				))
+			},
+		}
+		.map_err(|error| {
+			tracing::debug!(?error, "Failed to locate progression");
+			APIError::BadRequest(
+				"The progression could not be located in the book".to_string(),
+			)
+		})?;
+
+		let percentage = progression
+			.total_progression()
+			.unwrap_or(locator.total_progression);
+		let params = locator
+			.into_session_params()
+			.into_iter()
+			.chain([active_reading_session::percentage_completed::set(Some(
+				percentage,
+			))])
+			.collect::<Vec<_>>();
+		(params, percentage >= 1.0)
+	} else {
+		let page = progression
+			.page()
+			.filter(|page| (1..=book.pages).contains(page))
+			.ok_or(APIError::BadRequest(
+				"The progression must include a valid position or page link".to_string(),
+			))?;
+		let percentage = progression
+			.total_progression()
+			.unwrap_or(page as f64 / book.pages as f64);
+		let params = vec![
+			active_reading_session::page::set(Some(page)),
+			active_reading_session::percentage_completed::set(Some(percentage)),
+		];
+		(params, page == book.pages)
+	};
+
+	let device = progression
+		.device()
+		.map(|(id, name)| (id.to_string(), name.to_string()));
+	let user_id = user.id.clone();
+	let book_id = book.id.clone();
+
+	client
+		._transaction()
+		.run(|tx| async move {
+			let device_id = match device {
+				Some((device_id, name)) => {
+					tx.registered_reading_device()
+						.upsert(
+							registered_reading_device::id::equals(device_id.clone()),
+							(
+								name.clone(),
+								vec![registered_reading_device::id::set(
+									device_id.clone(),
+								)],
+							),
+							vec![registered_reading_device::name::set(name)],
+						)
+						.exec()
+						.await?;
+					Some(device_id)
+				},
+				None => None,
+			};
+
+			if is_completed {
+				if let Some(ref session) = existing_session {
+					tx.active_reading_session()
+						.delete(active_reading_session::id::equals(session.id.clone()))
+						.exec()
+						.await?;
+				}
+
+				tx.finished_reading_session()
+					.create(
+						existing_session
+							.map(|s| s.started_at)
+							.unwrap_or_else(|| Utc::now().into()),
+						media::id::equals(book_id),
+						user::id::equals(user_id),
+						chain_optional_iter(
+							[],
+							[device_id.map(|id| {
+								finished_reading_session::device::connect(
+									registered_reading_device::id::equals(id),
+								)
+							})],
+						),
+					)
+					.exec()
+					.await
+					.map(|_| ())
+			} else {
+				let set_params = chain_optional_iter(
+					progress_params,
+					[device_id.map(|id| {
+						active_reading_session::device::connect(
+							registered_reading_device::id::equals(id),
+						)
+					})],
+				);
+
+				tx.active_reading_session()
+					.upsert(
+						active_reading_session::user_id_media_id(
+							user_id.clone(),
+							book_id.clone(),
+						),
+						(
+							media::id::equals(book_id),
+							user::id::equals(user_id),
+							set_params.clone(),
+						),
+						set_params,
+					)
+					.exec()
+					.await
+					.map(|_| ())
+			}
+		})
+		.await?;
+
+	Ok(StatusCode::NO_CONTENT)
+}
+
 /// A route handler which downloads a book for a user.
 #[tracing::instrument(skip(ctx))]
 async fn download_book(
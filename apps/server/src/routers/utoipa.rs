@@ -3,7 +3,10 @@ use axum::Router;
 use stump_core::db::entity::*;
 // TODO: investigate how to get this working for swagger...
 use stump_core::db::filter::{SmartFilterSchema as SmartFilter, *};
+use stump_core::db::metadata::AppliedMetadata;
 use stump_core::db::query::{ordering::*, pagination::*};
+use stump_core::db::search::MediaSearchResult;
+use stump_core::filesystem::media::metadata_fetch_job::MetadataFetchOptions;
 use stump_core::filesystem::{
 	DirectoryListing, DirectoryListingFile, DirectoryListingInput,
 };
@@ -20,8 +23,9 @@ use crate::middleware::auth::auth_middleware;
 use super::api::{
 	self,
 	v1::{
-		auth::LoginOrRegisterArgs, library::*, media::individual::*, notifier::*,
-		series::*, smart_list::*, user::*, ClaimResponse, StumpVersion,
+		annotation::*, auth::LoginOrRegisterArgs, collection::*, invitation::*, job::*,
+		library::*, media::individual::*, notifier::*, review::*, series::*,
+		smart_list::*, user::*, ClaimResponse, StumpVersion,
 	},
 };
 
@@ -39,14 +43,32 @@ use super::api::{
         api::v1::auth::login,
         api::v1::auth::logout,
         api::v1::auth::register,
+        api::v1::auth::oidc_status,
+        api::v1::auth::oidc_login,
+        api::v1::auth::oidc_callback,
+        api::v1::auth::get_external_identities,
+        api::v1::auth::delete_external_identity,
+        api::v1::auth::get_two_factor_status,
+        api::v1::auth::enroll_two_factor,
+        api::v1::auth::confirm_two_factor,
+        api::v1::auth::regenerate_recovery_codes,
+        api::v1::auth::disable_two_factor,
+        api::v1::password_reset::request_password_reset,
+        api::v1::password_reset::confirm_password_reset,
         // TODO: epub here
         api::v1::filesystem::list_directory,
         api::v1::job::get_jobs,
         api::v1::job::delete_jobs,
         api::v1::job::delete_job_by_id,
         api::v1::job::cancel_job_by_id,
-        api::v1::job::get_scheduler_config,
-        api::v1::job::update_scheduler_config,
+        api::v1::job::get_job_schedules,
+        api::v1::job::create_job_schedule,
+        api::v1::job::get_job_schedule_by_id,
+        api::v1::job::update_job_schedule,
+        api::v1::job::delete_job_schedule,
+        api::v1::job::pause_job_schedule,
+        api::v1::job::resume_job_schedule,
+        api::v1::job::trigger_job_schedule,
         api::v1::library::get_libraries,
         api::v1::library::get_libraries_stats,
         api::v1::library::get_library_by_id,
@@ -61,10 +83,20 @@ use super::api::{
         api::v1::library::delete_library,
         api::v1::log::get_logs,
         api::v1::log::delete_logs,
+        api::v1::audit_log::get_audit_logs,
         api::v1::media::bulk::get_media,
         api::v1::media::bulk::get_duplicate_media,
         api::v1::media::bulk::get_in_progress_media,
         api::v1::media::bulk::get_recently_added_media,
+        api::v1::media::bulk::write_media_metadata_to_files,
+        api::v1::media::duplicates::start_duplicate_analysis,
+        api::v1::media::duplicates::get_duplicate_clusters,
+        api::v1::media::duplicates::delete_duplicate_cluster,
+        api::v1::media::duplicates::resolve_duplicate_cluster,
+        api::v1::search::search_media,
+        api::v1::stats::get_reading_stats,
+        api::v1::stats::get_year_in_review,
+        api::v1::stats::get_server_reading_stats,
         api::v1::media::individual::get_media_by_id,
         api::v1::media::individual::get_media_file,
         api::v1::media::individual::convert_media,
@@ -74,6 +106,8 @@ use super::api::{
         api::v1::media::individual::delete_media_progress,
         api::v1::media::individual::get_is_media_completed,
         api::v1::media::individual::put_media_complete_status,
+        api::v1::media::individual::get_media_outline,
+        api::v1::media::individual::write_media_metadata_to_file,
         api::v1::media::thumbnails::get_media_thumbnail_handler,
         api::v1::metadata::get_metadata_overview,
         api::v1::metadata::get_genres_handler,
@@ -86,17 +120,60 @@ use super::api::{
         api::v1::metadata::get_publishers_handler,
         api::v1::metadata::get_characters_handler,
         api::v1::metadata::get_teams_handler,
+        api::v1::metadata_provider::get_metadata_providers,
+        api::v1::metadata_provider::fetch_metadata,
+        api::v1::metadata_provider::get_media_metadata_candidates,
+        api::v1::metadata_provider::get_series_metadata_candidates,
+        api::v1::metadata_provider::apply_metadata_candidate,
+        api::v1::metadata_provider::delete_metadata_candidate,
+        api::v1::metadata_provider::get_media_metadata_fields,
+        api::v1::metadata_provider::get_series_metadata_fields,
+        api::v1::metadata_provider::lock_media_metadata_field,
+        api::v1::metadata_provider::lock_series_metadata_field,
+        api::v1::metadata_provider::get_media_metadata_history,
+        api::v1::metadata_provider::get_series_metadata_history,
         api::v1::notifier::get_notifiers,
         api::v1::notifier::get_notifier_by_id,
         api::v1::notifier::create_notifier,
         api::v1::notifier::update_notifier,
         api::v1::notifier::patch_notifier,
         api::v1::notifier::delete_notifier,
+        api::v1::notifier::send_test_notification,
+        api::v1::notifier::get_notifier_deliveries,
         api::v1::reading_list::get_reading_list,
         api::v1::reading_list::create_reading_list,
         api::v1::reading_list::get_reading_list_by_id,
         api::v1::reading_list::update_reading_list,
         api::v1::reading_list::delete_reading_list_by_id,
+        api::v1::collection::get_collections,
+        api::v1::collection::create_collection,
+        api::v1::collection::get_collection_by_id,
+        api::v1::collection::update_collection,
+        api::v1::collection::delete_collection,
+        api::v1::collection::set_collection_items,
+        api::v1::collection::get_collection_thumbnail_handler,
+        api::v1::collection::replace_collection_thumbnail,
+        api::v1::collection::delete_collection_thumbnail,
+        api::v1::review::get_media_reviews,
+        api::v1::review::create_media_review,
+        api::v1::review::get_media_review_stats,
+        api::v1::review::get_series_review_stats,
+        api::v1::review::get_top_rated_media,
+        api::v1::review::get_top_rated_series,
+        api::v1::review::get_review_by_id,
+        api::v1::review::update_review,
+        api::v1::review::delete_review,
+        api::v1::annotation::get_annotations,
+        api::v1::annotation::export_user_annotations,
+        api::v1::annotation::get_annotation_by_id,
+        api::v1::annotation::update_annotation,
+        api::v1::annotation::delete_annotation,
+        api::v1::annotation::get_media_annotations,
+        api::v1::annotation::create_media_annotation,
+        api::v1::invitation::get_invitations,
+        api::v1::invitation::create_invitation,
+        api::v1::invitation::delete_invitation,
+        api::v1::invitation::redeem_invitation,
         api::v1::series::get_series,
         api::v1::series::get_series_by_id,
         api::v1::series::get_recently_added_series_handler,
@@ -128,25 +205,44 @@ use super::api::{
         api::v1::user::update_user_handler,
         api::v1::user::get_user_preferences,
         api::v1::user::update_user_preferences,
-        api::v1::user::update_user_lock_status
+        api::v1::user::update_user_lock_status,
+        api::v1::user::delete_user_two_factor
     ),
     components(
         schemas(
-            Library, LibraryConfig, Media, ReadingList, ActiveReadingSession, FinishedReadingSession, Series,
+            Library, LibraryConfig, Media, PdfOutlineItem, ReadingList, ActiveReadingSession, FinishedReadingSession, Series,
             Tag, User, UserPreferences, LibraryPattern, LibraryScanMode, LogLevel, ClaimResponse, StumpVersion,
             FileStatus, PageableDirectoryListing, DirectoryListing, DirectoryListingFile, CursorInfo, PageInfo,
             PageableLibraries, PageableMedia, PageableSeries, LoginOrRegisterArgs, DirectoryListingInput, PageQuery,
             FilterableLibraryQuery, PaginationQuery, QueryOrder, LibraryFilter,Direction, CreateLibrary,
             UpdateLibrary, APIError, MediaFilter, SeriesFilter,FilterableMediaQuery, FilterableSeriesQuery,
             LibraryStats, JobStatus, SeriesQueryRelation, CreateReadingList, UpdateUserPreferences, UpdateUser,
-            CreateTags, CleanLibraryResponse, MediaIsComplete, SeriesIsComplete, PutMediaCompletionStatus, SmartList,
+            CreateTags, CleanLibraryResponse, MediaIsComplete, SeriesIsComplete, PutMediaCompletionStatus, WriteMetadataParams,
+            api::v1::media::bulk::WriteMediaMetadataToFiles, SmartList,
+            api::v1::media::duplicates::StartDuplicateAnalysis, api::v1::media::duplicates::ResolveDuplicateCluster,
+            DuplicateCluster, ReadingStats, YearInReview, ServerReadingStats, MediaSearchResult,
             SmartListMeta, SmartListItems, SmartListView, CreateOrUpdateSmartList, CreateOrUpdateSmartListView,
             SmartListItemGrouping, SmartFilter, FilterJoin, EntityVisibility, SmartListViewConfig,
             ReactTableColumnSort, ReactTableGlobalSort, MediaSmartFilter, MediaMetadataSmartFilter,
             SeriesSmartFilter, SeriesMetadataSmartFilter, LibrarySmartFilter, Notifier, CreateOrUpdateNotifier,
             PatchNotifier, LibraryBaseFilter, LibraryRelationFilter, MediaBaseFilter, MediaRelationFilter,
             SeriesBaseFilter, SeriesRelationFilter, NotifierConfig, NotifierType, ReadingListItem,
-            ReadingListVisibility, SeriesMetadataFilter
+            ReadingListVisibility, SeriesMetadataFilter, JobSchedule, ScheduledJobKind, ScheduledJobConfig,
+            CreateOrUpdateJobSchedule, Review, ReviewStats, ReviewRatingCount, ReviewSmartFilter,
+            CreateOrUpdateReview, TopRatedParams, RatedMedia, RatedSeries, Collection, CollectionItem,
+            CollectionItemKind, CreateOrUpdateCollection, SetCollectionItems, CollectionItemInput,
+            NotifierEventKind, NotifierSubscription, NotifierDelivery, WebhookMethod, WebhookHeader,
+            MediaAnnotation, AnnotatedBook, AnnotationExportFormat, CreateOrUpdateAnnotation,
+            AnnotationExportParams, ServerInvitation, CreateServerInvitation, CreatedServerInvitation,
+            RedeemServerInvitation, MetadataProviderType, MetadataCandidate, MetadataFieldState,
+            MediaMetadataField, SeriesMetadataField, AppliedMetadata, MetadataFetchOptions,
+            api::v1::metadata_provider::FetchMetadata, api::v1::metadata_provider::ApplyMetadataCandidate,
+            api::v1::metadata_provider::LockMetadataField, MetadataSource, MetadataFieldChange,
+            api::v1::metadata_provider::MetadataHistoryParams, api::v1::auth::OidcStatus, ExternalIdentity,
+            api::v1::auth::TwoFactorStatus, api::v1::auth::TwoFactorEnrollment, api::v1::auth::TwoFactorCode,
+            api::v1::auth::TwoFactorRecoveryCodes, api::v1::password_reset::RequestPasswordReset,
+            api::v1::password_reset::ConfirmPasswordReset, AuditLog, AuditAction, AuditTargetType,
+            AuditLogChange, AuditLogFilter
         )
     ),
     tags(
@@ -156,11 +252,19 @@ use super::api::{
         (name = "epub", description = "EPUB API"),
         (name = "filesystem", description = "Filesystem API"),
         (name = "job", description = "Job API"),
+        (name = "audit-log", description = "Audit Log API"),
         (name = "library", description = "Library API"),
         (name = "media", description = "Media API"),
+        (name = "metadata", description = "Metadata API"),
         (name = "series", description = "Series API"),
         (name = "tag", description = "Tag API"),
         (name = "reading-list", description = "Reading List API"),
+        (name = "review", description = "Review API"),
+        (name = "annotation", description = "Annotation API"),
+        (name = "search", description = "Search API"),
+        (name = "stats", description = "Statistics API"),
+        (name = "invitation", description = "Invitation API"),
+        (name = "collection", description = "Collection API"),
         (name = "user", description = "User API"),
         (name = "opds", description = "OPDS API"),
     )
@@ -55,7 +55,7 @@ pub async fn get_session_user(session: &Session) -> APIResult<Option<User>> {
 /// A function to determine whether a user has a specific permission. The permission
 /// is checked against their explicitly assigned permissions, as well as any inherited
 /// ones through permission associations.
-fn user_has_permission(user: &User, permission: UserPermission) -> bool {
+pub fn user_has_permission(user: &User, permission: UserPermission) -> bool {
 	user.is_server_owner
 		|| user
 			.permissions
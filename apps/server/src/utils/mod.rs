@@ -1,15 +1,19 @@
 mod auth;
 pub mod http;
+mod rate_limit;
 mod serde;
 mod signal;
 mod time;
+mod totp;
 mod upload;
 
 #[cfg(test)]
 pub(crate) mod test_utils;
 
 pub(crate) use auth::*;
+pub(crate) use rate_limit::*;
 pub(crate) use serde::*;
 pub(crate) use signal::*;
 pub(crate) use time::*;
+pub(crate) use totp::*;
 pub(crate) use upload::*;
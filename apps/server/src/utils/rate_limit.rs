@@ -0,0 +1,91 @@
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+/// A limit on the number of attempts at something (e.g. by an IP address) within a sliding
+/// window of time. Attempts are only tracked in memory, so they are forgotten on restart
+pub struct RateLimiter {
+	max_attempts: usize,
+	window: Duration,
+	attempts: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+	pub fn new(max_attempts: usize, window: Duration) -> Self {
+		Self {
+			max_attempts,
+			window,
+			attempts: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Record an attempt for the key, returning whether it is within the limit. Attempts which
+	/// are over the limit are not recorded
+	pub fn try_attempt(&self, key: &str) -> bool {
+		self.try_attempt_at(key, Instant::now())
+	}
+
+	fn try_attempt_at(&self, key: &str, now: Instant) -> bool {
+		let mut attempts = self
+			.attempts
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner());
+
+		// Attempts outside of the window are dropped for every key, so that keys which are
+		// no longer used don't accumulate
+		attempts.retain(|_, times| {
+			while times
+				.front()
+				.is_some_and(|time| now.saturating_duration_since(*time) >= self.window)
+			{
+				times.pop_front();
+			}
+			!times.is_empty()
+		});
+
+		let times = attempts.entry(key.to_string()).or_default();
+		if times.len() >= self.max_attempts {
+			return false;
+		}
+		times.push_back(now);
+
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rate_limiter() {
+		let limiter = RateLimiter::new(2, Duration::from_secs(60));
+		let now = Instant::now();
+
+		assert!(limiter.try_attempt_at("127.0.0.1", now));
+		assert!(limiter.try_attempt_at("127.0.0.1", now + Duration::from_secs(10)));
+		assert!(!limiter.try_attempt_at("127.0.0.1", now + Duration::from_secs(20)));
+		// Other keys have their own limit
+		assert!(limiter.try_attempt_at("10.0.0.1", now + Duration::from_secs(20)));
+
+		// The first attempt falls out of the window, which frees up a single attempt
+		assert!(limiter.try_attempt_at("127.0.0.1", now + Duration::from_secs(60)));
+		assert!(!limiter.try_attempt_at("127.0.0.1", now + Duration::from_secs(65)));
+		assert!(limiter.try_attempt_at("127.0.0.1", now + Duration::from_secs(70)));
+	}
+
+	#[test]
+	fn test_rate_limiter_forgets_unused_keys() {
+		let limiter = RateLimiter::new(1, Duration::from_secs(60));
+		let now = Instant::now();
+
+		assert!(limiter.try_attempt_at("127.0.0.1", now));
+		assert!(limiter.try_attempt_at("10.0.0.1", now + Duration::from_secs(60)));
+
+		let attempts = limiter.attempts.lock().unwrap();
+		assert_eq!(attempts.len(), 1);
+		assert!(attempts.contains_key("10.0.0.1"));
+	}
+}
@@ -11,6 +11,7 @@ pub fn create_prisma_user(user: &User, hashed_pass: String) -> user::Data {
 	user::Data {
 		id: user.id.clone(),
 		username: user.username.clone(),
+		email: user.email.clone(),
 		hashed_password: hashed_pass,
 		is_server_owner: user.is_server_owner,
 		avatar_url: user.avatar_url.clone(),
@@ -50,6 +51,7 @@ pub fn create_prisma_user(user: &User, hashed_pass: String) -> user::Data {
 		library_visits: None,
 		smart_lists: None,
 		smart_list_access_rules: None,
+		shared_collections: None,
 		email_usage_history: None,
 		last_login: None,
 		active_reading_sessions: None,
@@ -63,5 +65,10 @@ pub fn create_prisma_user(user: &User, hashed_pass: String) -> user::Data {
 		bookmarks: None,
 		media_annotations: None,
 		api_keys: None,
+		metadata_field_changes: None,
+		external_identities: None,
+		two_factor: None,
+		two_factor_recovery_codes: None,
+		password_reset_tokens: None,
 	}
 }
@@ -0,0 +1,222 @@
+use data_encoding::{BASE32_NOPAD, HEXLOWER};
+use rand::{
+	distributions::{Alphanumeric, DistString},
+	RngCore,
+};
+use ring::{digest, hmac};
+
+/// The issuer shown by authenticator apps for Stump accounts
+pub const TOTP_ISSUER: &str = "Stump";
+/// The number of recovery codes generated for a user at a time
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const TOTP_PERIOD: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+/// The number of time steps either side of the current one for which codes are still accepted,
+/// to allow for clock drift between the server and the user's device
+const TOTP_SKEW: u64 = 1;
+/// The length of a secret in bytes, which is the 160 bits recommended by RFC 4226
+const SECRET_LENGTH: usize = 20;
+
+/// Generate a new random TOTP secret, encoded as base32 so it can be entered into an
+/// authenticator app by hand
+pub fn generate_totp_secret() -> String {
+	let mut secret = [0u8; SECRET_LENGTH];
+	rand::thread_rng().fill_bytes(&mut secret);
+	BASE32_NOPAD.encode(&secret)
+}
+
+/// The `otpauth://` URI for a secret, which authenticator apps accept (usually as a QR code)
+pub fn totp_uri(secret: &str, account_name: &str) -> String {
+	let issuer = urlencoding::encode(TOTP_ISSUER);
+	format!(
+		"otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_PERIOD}",
+		account = urlencoding::encode(account_name),
+	)
+}
+
+/// The TOTP time step for a unix timestamp (in seconds)
+pub fn totp_step(unix_seconds: u64) -> u64 {
+	unix_seconds / TOTP_PERIOD
+}
+
+/// The HOTP value (RFC 4226) of a key for a counter, using HMAC-SHA1 which is what
+/// authenticator apps universally support
+fn hotp(key: &[u8], counter: u64) -> u32 {
+	let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+	let tag = hmac::sign(&key, &counter.to_be_bytes());
+	let digest = tag.as_ref();
+
+	let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+	let truncated = u32::from_be_bytes([
+		digest[offset] & 0x7f,
+		digest[offset + 1],
+		digest[offset + 2],
+		digest[offset + 3],
+	]);
+
+	truncated % 10u32.pow(TOTP_DIGITS)
+}
+
+/// The TOTP code of a base32 secret for a time step. Returns `None` if the secret is not
+/// valid base32
+pub fn totp_code(secret: &str, step: u64) -> Option<String> {
+	let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+	Some(format!(
+		"{:0width$}",
+		hotp(&key, step),
+		width = TOTP_DIGITS as usize
+	))
+}
+
+/// Verify a code against a base32 secret at a unix timestamp (in seconds), returning the time
+/// step the code matched. Codes for a step at or before `last_used_step` are rejected, so a
+/// code can't be used more than once
+pub fn verify_totp(
+	secret: &str,
+	code: &str,
+	unix_seconds: u64,
+	last_used_step: Option<u64>,
+) -> Option<u64> {
+	let code = code.replace(' ', "");
+	if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+		return None;
+	}
+
+	let current_step = totp_step(unix_seconds);
+	let first_step = current_step.saturating_sub(TOTP_SKEW);
+
+	(first_step..=current_step + TOTP_SKEW)
+		.filter(|step| last_used_step.is_none_or(|last| *step > last))
+		.find(|step| totp_code(secret, *step).is_some_and(|expected| expected == code))
+}
+
+/// Generate a new set of recovery codes, formatted like `abcde-12345`. The codes are only
+/// ever shown to the user once, and are stored hashed
+pub fn generate_recovery_codes() -> Vec<String> {
+	let mut rng = rand::thread_rng();
+	(0..RECOVERY_CODE_COUNT)
+		.map(|_| {
+			let code = Alphanumeric.sample_string(&mut rng, 10).to_lowercase();
+			format!("{}-{}", &code[..5], &code[5..])
+		})
+		.collect()
+}
+
+/// Hash a recovery code for storage. Recovery codes are long and random, so a fast hash is
+/// sufficient (unlike passwords). The code is normalized first, so it may be entered without
+/// the dash or in a different case
+pub fn hash_recovery_code(code: &str) -> String {
+	let normalized = code
+		.chars()
+		.filter(|c| c.is_ascii_alphanumeric())
+		.collect::<String>()
+		.to_lowercase();
+	HEXLOWER.encode(digest::digest(&digest::SHA256, normalized.as_bytes()).as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The secret used by the test vectors of RFC 6238 (`12345678901234567890`), as base32
+	const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+	#[test]
+	fn test_totp_code_rfc_vectors() {
+		// The RFC vectors are 8 digits, so only the last 6 are compared
+		let vectors = [
+			(59, "287082"),
+			(1111111109, "081804"),
+			(1111111111, "050471"),
+			(1234567890, "005924"),
+			(2000000000, "279037"),
+			(20000000000, "353130"),
+		];
+
+		for (time, expected) in vectors {
+			assert_eq!(
+				totp_code(RFC_SECRET, totp_step(time)).as_deref(),
+				Some(expected)
+			);
+		}
+	}
+
+	#[test]
+	fn test_totp_code_invalid_secret() {
+		assert_eq!(totp_code("not base32!", 1), None);
+	}
+
+	#[test]
+	fn test_verify_totp() {
+		let step = totp_step(1111111111);
+
+		assert_eq!(
+			verify_totp(RFC_SECRET, "050471", 1111111111, None),
+			Some(step)
+		);
+		assert_eq!(
+			verify_totp(RFC_SECRET, "050 471", 1111111111, None),
+			Some(step)
+		);
+		// The codes of the neighbouring steps are accepted, but no further
+		assert!(verify_totp(RFC_SECRET, "050471", 1111111111 + 30, None).is_some());
+		assert!(verify_totp(RFC_SECRET, "050471", 1111111111 - 30, None).is_some());
+		assert!(verify_totp(RFC_SECRET, "050471", 1111111111 + 60, None).is_none());
+		assert!(verify_totp(RFC_SECRET, "050471", 1111111111 - 60, None).is_none());
+
+		assert!(verify_totp(RFC_SECRET, "000000", 1111111111, None).is_none());
+		assert!(verify_totp(RFC_SECRET, "50471", 1111111111, None).is_none());
+		assert!(verify_totp(RFC_SECRET, "abcdef", 1111111111, None).is_none());
+	}
+
+	#[test]
+	fn test_verify_totp_rejects_reuse() {
+		let step = totp_step(1111111111);
+
+		assert!(verify_totp(RFC_SECRET, "050471", 1111111111, Some(step)).is_none());
+		assert!(verify_totp(RFC_SECRET, "050471", 1111111111, Some(step + 1)).is_none());
+		assert_eq!(
+			verify_totp(RFC_SECRET, "050471", 1111111111, Some(step - 1)),
+			Some(step)
+		);
+	}
+
+	#[test]
+	fn test_generate_totp_secret() {
+		let secret = generate_totp_secret();
+		assert_eq!(BASE32_NOPAD.decode(secret.as_bytes()).unwrap().len(), 20);
+		assert_ne!(secret, generate_totp_secret());
+
+		let now = 1111111111;
+		let code = totp_code(&secret, totp_step(now)).unwrap();
+		assert!(verify_totp(&secret, &code, now, None).is_some());
+	}
+
+	#[test]
+	fn test_totp_uri() {
+		assert_eq!(
+			totp_uri(RFC_SECRET, "oromei@stump.cloud"),
+			"otpauth://totp/Stump:oromei%40stump.cloud?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Stump&algorithm=SHA1&digits=6&period=30"
+		);
+	}
+
+	#[test]
+	fn test_recovery_codes() {
+		let codes = generate_recovery_codes();
+		assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+		for code in &codes {
+			assert_eq!(code.len(), 11);
+			assert_eq!(&code[5..6], "-");
+			assert!(code
+				.chars()
+				.all(|c| c == '-' || c.is_ascii_lowercase() || c.is_ascii_digit()));
+		}
+
+		let hash = hash_recovery_code(&codes[0]);
+		assert_eq!(hash.len(), 64);
+		assert_eq!(hash, hash_recovery_code(&codes[0].replace('-', "")));
+		assert_eq!(hash, hash_recovery_code(&codes[0].to_uppercase()));
+		assert_ne!(hash, hash_recovery_code(&codes[1]));
+	}
+}
@@ -0,0 +1,95 @@
+-- CreateTable
+CREATE TABLE "job_schedules" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "job_kind" TEXT NOT NULL,
+    "cron_expression" TEXT,
+    "interval_secs" INTEGER,
+    "config" BLOB,
+    "is_paused" BOOLEAN NOT NULL DEFAULT false,
+    "last_run_at" DATETIME,
+    "next_run_at" DATETIME,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- CreateTable
+CREATE TABLE "_JobScheduleIncludedLibraries" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL,
+    CONSTRAINT "_JobScheduleIncludedLibraries_A_fkey" FOREIGN KEY ("A") REFERENCES "job_schedules" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "_JobScheduleIncludedLibraries_B_fkey" FOREIGN KEY ("B") REFERENCES "libraries" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "_JobScheduleExcludedLibraries" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL,
+    CONSTRAINT "_JobScheduleExcludedLibraries_A_fkey" FOREIGN KEY ("A") REFERENCES "job_schedules" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "_JobScheduleExcludedLibraries_B_fkey" FOREIGN KEY ("B") REFERENCES "libraries" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- Migrate the legacy single-interval scan config (if any) into a named schedule. The next run is
+-- pushed out by one interval so that the first boot after upgrading does not trigger a scan.
+INSERT INTO "job_schedules" ("id", "name", "job_kind", "interval_secs", "next_run_at")
+SELECT "id",
+    'Scheduled library scan',
+    'LIBRARY_SCAN',
+    "interval_secs",
+    datetime('now', '+' || "interval_secs" || ' seconds')
+FROM "job_schedule_configs";
+
+INSERT INTO "_JobScheduleExcludedLibraries" ("A", "B")
+SELECT "job_schedule_config_id",
+    "id"
+FROM "libraries"
+WHERE "job_schedule_config_id" IS NOT NULL
+    AND "job_schedule_config_id" IN (SELECT "id" FROM "job_schedules");
+
+-- RedefineTables
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_libraries" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "description" TEXT,
+    "path" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'READY',
+    "last_scanned_at" DATETIME,
+    "updated_at" DATETIME NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "emoji" TEXT,
+    "config_id" TEXT NOT NULL,
+    CONSTRAINT "libraries_config_id_fkey" FOREIGN KEY ("config_id") REFERENCES "library_configs" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+INSERT INTO "new_libraries" ("config_id", "created_at", "description", "emoji", "id", "last_scanned_at", "name", "path", "status", "updated_at") SELECT "config_id", "created_at", "description", "emoji", "id", "last_scanned_at", "name", "path", "status", "updated_at" FROM "libraries";
+DROP TABLE "libraries";
+ALTER TABLE "new_libraries" RENAME TO "libraries";
+CREATE UNIQUE INDEX "libraries_name_key" ON "libraries"("name");
+CREATE UNIQUE INDEX "libraries_path_key" ON "libraries"("path");
+CREATE UNIQUE INDEX "libraries_config_id_key" ON "libraries"("config_id");
+CREATE TABLE "new_server_config" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "public_url" TEXT,
+    "initial_wal_setup_complete" BOOLEAN NOT NULL DEFAULT false,
+    "encryption_key" TEXT
+);
+INSERT INTO "new_server_config" ("encryption_key", "id", "initial_wal_setup_complete", "public_url") SELECT "encryption_key", "id", "initial_wal_setup_complete", "public_url" FROM "server_config";
+DROP TABLE "server_config";
+ALTER TABLE "new_server_config" RENAME TO "server_config";
+DROP TABLE "job_schedule_configs";
+PRAGMA foreign_key_check;
+PRAGMA foreign_keys=ON;
+
+-- CreateIndex
+CREATE UNIQUE INDEX "job_schedules_name_key" ON "job_schedules"("name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_JobScheduleIncludedLibraries_AB_unique" ON "_JobScheduleIncludedLibraries"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_JobScheduleIncludedLibraries_B_index" ON "_JobScheduleIncludedLibraries"("B");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_JobScheduleExcludedLibraries_AB_unique" ON "_JobScheduleExcludedLibraries"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_JobScheduleExcludedLibraries_B_index" ON "_JobScheduleExcludedLibraries"("B");
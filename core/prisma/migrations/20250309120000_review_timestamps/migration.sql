@@ -0,0 +1,22 @@
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_reviews" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "rating" INTEGER NOT NULL,
+    "content" TEXT,
+    "is_private" BOOLEAN NOT NULL DEFAULT false,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "media_id" TEXT NOT NULL,
+    "user_id" TEXT NOT NULL,
+    CONSTRAINT "reviews_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "reviews_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_reviews" ("content", "id", "is_private", "media_id", "rating", "user_id") SELECT "content", "id", "is_private", "media_id", "rating", "user_id" FROM "reviews";
+DROP TABLE "reviews";
+ALTER TABLE "new_reviews" RENAME TO "reviews";
+CREATE INDEX "reviews_media_id_idx" ON "reviews"("media_id");
+CREATE UNIQUE INDEX "reviews_user_id_media_id_key" ON "reviews"("user_id", "media_id");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
@@ -0,0 +1,49 @@
+-- CreateTable
+CREATE TABLE "collection_items" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "display_order" INTEGER NOT NULL,
+    "collection_id" TEXT NOT NULL,
+    "media_id" TEXT,
+    "series_id" TEXT,
+    CONSTRAINT "collection_items_collection_id_fkey" FOREIGN KEY ("collection_id") REFERENCES "collections" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "collection_items_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "collection_items_series_id_fkey" FOREIGN KEY ("series_id") REFERENCES "series" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "_CollectionSharedUsers" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL,
+    CONSTRAINT "_CollectionSharedUsers_A_fkey" FOREIGN KEY ("A") REFERENCES "collections" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "_CollectionSharedUsers_B_fkey" FOREIGN KEY ("B") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_collections" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "description" TEXT,
+    "visibility" TEXT NOT NULL DEFAULT 'PUBLIC',
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" DATETIME NOT NULL
+);
+INSERT INTO "new_collections" ("description", "id", "name", "updated_at") SELECT "description", "id", "name", "updated_at" FROM "collections";
+DROP TABLE "collections";
+ALTER TABLE "new_collections" RENAME TO "collections";
+CREATE UNIQUE INDEX "collections_name_key" ON "collections"("name");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
+
+-- CreateIndex
+CREATE UNIQUE INDEX "collection_items_collection_id_media_id_key" ON "collection_items"("collection_id", "media_id");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "collection_items_collection_id_series_id_key" ON "collection_items"("collection_id", "series_id");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_CollectionSharedUsers_AB_unique" ON "_CollectionSharedUsers"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_CollectionSharedUsers_B_index" ON "_CollectionSharedUsers"("B");
@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "notifiers" ADD COLUMN "subscription" BLOB;
@@ -0,0 +1,15 @@
+-- CreateTable
+CREATE TABLE "notifier_deliveries" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "event" TEXT NOT NULL,
+    "successful" BOOLEAN NOT NULL,
+    "attempts" INTEGER NOT NULL,
+    "status_code" INTEGER,
+    "error" TEXT,
+    "timestamp" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "notifier_id" INTEGER NOT NULL,
+    CONSTRAINT "notifier_deliveries_notifier_id_fkey" FOREIGN KEY ("notifier_id") REFERENCES "notifiers" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "notifier_deliveries_notifier_id_idx" ON "notifier_deliveries"("notifier_id");
@@ -0,0 +1,3 @@
+-- AlterTable
+ALTER TABLE "media_metadata" ADD COLUMN "identifiers" TEXT;
+ALTER TABLE "media_metadata" ADD COLUMN "language" TEXT;
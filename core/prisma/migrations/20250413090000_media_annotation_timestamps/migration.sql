@@ -0,0 +1,24 @@
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_media_annotations" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "highlighted_text" TEXT,
+    "epubcfi" TEXT,
+    "page" INTEGER,
+    "page_coordinates_x" REAL,
+    "page_coordinates_y" REAL,
+    "notes" TEXT,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "user_id" TEXT NOT NULL,
+    "media_id" TEXT NOT NULL,
+    CONSTRAINT "media_annotations_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "media_annotations_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_media_annotations" ("epubcfi", "highlighted_text", "id", "media_id", "notes", "page", "page_coordinates_x", "page_coordinates_y", "user_id") SELECT "epubcfi", "highlighted_text", "id", "media_id", "notes", "page", "page_coordinates_x", "page_coordinates_y", "user_id" FROM "media_annotations";
+DROP TABLE "media_annotations";
+ALTER TABLE "new_media_annotations" RENAME TO "media_annotations";
+CREATE INDEX "media_annotations_user_id_idx" ON "media_annotations"("user_id");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
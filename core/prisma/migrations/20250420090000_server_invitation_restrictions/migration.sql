@@ -0,0 +1,20 @@
+-- AlterTable
+ALTER TABLE "server_invitations" ADD COLUMN "age_restriction" INTEGER;
+ALTER TABLE "server_invitations" ADD COLUMN "restrict_on_unset" BOOLEAN NOT NULL DEFAULT false;
+
+-- CreateTable
+CREATE TABLE "_ServerInvitationHiddenLibraries" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL,
+    CONSTRAINT "_ServerInvitationHiddenLibraries_A_fkey" FOREIGN KEY ("A") REFERENCES "libraries" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "_ServerInvitationHiddenLibraries_B_fkey" FOREIGN KEY ("B") REFERENCES "server_invitations" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "server_invitations_secret_key" ON "server_invitations"("secret");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_ServerInvitationHiddenLibraries_AB_unique" ON "_ServerInvitationHiddenLibraries"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_ServerInvitationHiddenLibraries_B_index" ON "_ServerInvitationHiddenLibraries"("B");
@@ -0,0 +1,28 @@
+-- AlterTable
+ALTER TABLE "media" ADD COLUMN "checksum" TEXT;
+ALTER TABLE "media" ADD COLUMN "cover_hash" TEXT;
+
+-- CreateTable
+CREATE TABLE "duplicate_clusters" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "confidence" REAL NOT NULL,
+    "reason" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- CreateTable
+CREATE TABLE "_DuplicateClusterMedia" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL,
+    CONSTRAINT "_DuplicateClusterMedia_A_fkey" FOREIGN KEY ("A") REFERENCES "duplicate_clusters" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "_DuplicateClusterMedia_B_fkey" FOREIGN KEY ("B") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "media_checksum_idx" ON "media"("checksum");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_DuplicateClusterMedia_AB_unique" ON "_DuplicateClusterMedia"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_DuplicateClusterMedia_B_index" ON "_DuplicateClusterMedia"("B");
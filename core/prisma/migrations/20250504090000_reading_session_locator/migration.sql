@@ -0,0 +1,3 @@
+-- AlterTable
+ALTER TABLE "reading_sessions" ADD COLUMN "locator_href" TEXT;
+ALTER TABLE "reading_sessions" ADD COLUMN "locator_progression" REAL;
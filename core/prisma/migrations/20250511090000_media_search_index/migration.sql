@@ -0,0 +1,32 @@
+-- CreateTable
+CREATE TABLE "media_search_documents" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "media_id" TEXT NOT NULL,
+    "title" TEXT NOT NULL DEFAULT '',
+    "series" TEXT NOT NULL DEFAULT '',
+    "creators" TEXT NOT NULL DEFAULT '',
+    "characters" TEXT NOT NULL DEFAULT '',
+    "tags" TEXT NOT NULL DEFAULT '',
+    "summary" TEXT NOT NULL DEFAULT '',
+    "content" TEXT NOT NULL DEFAULT '',
+    "indexed_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "media_search_documents_media_id_key" ON "media_search_documents"("media_id");
+
+-- Backfill the documents of existing books. The FTS5 index itself is created and rebuilt from
+-- these documents at startup
+INSERT INTO "media_search_documents" ("media_id", "title", "series", "creators", "characters", "tags", "summary")
+SELECT
+    m.id,
+    COALESCE(NULLIF(TRIM(mm.title), ''), m.name),
+    TRIM(COALESCE(s.name, '') || ' ' || COALESCE(sm.title, '') || ' ' || COALESCE(mm.series, '') || ' ' || COALESCE(sm.publisher, '') || ' ' || COALESCE(sm.summary, '')),
+    TRIM(COALESCE(mm.writers, '') || ' ' || COALESCE(mm.pencillers, '') || ' ' || COALESCE(mm.inkers, '') || ' ' || COALESCE(mm.colorists, '') || ' ' || COALESCE(mm.letterers, '') || ' ' || COALESCE(mm.cover_artists, '') || ' ' || COALESCE(mm.editors, '') || ' ' || COALESCE(mm.publisher, '')),
+    TRIM(COALESCE(mm.characters, '') || ' ' || COALESCE(mm.teams, '')),
+    TRIM(COALESCE(mm.genre, '') || ' ' || COALESCE((SELECT GROUP_CONCAT(t.name, ' ') FROM "_MediaToTag" mt INNER JOIN tags t ON t.id = mt.B WHERE mt.A = m.id), '')),
+    COALESCE(mm.summary, '')
+FROM media m
+LEFT JOIN media_metadata mm ON mm.media_id = m.id
+LEFT JOIN series s ON s.id = m.series_id
+LEFT JOIN series_metadata sm ON sm.series_id = s.id;
@@ -0,0 +1,38 @@
+-- CreateTable
+CREATE TABLE "metadata_candidates" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "provider" TEXT NOT NULL,
+    "provider_id" TEXT NOT NULL,
+    "confidence" REAL NOT NULL,
+    "data" BLOB NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "media_id" TEXT,
+    "series_id" TEXT,
+    CONSTRAINT "metadata_candidates_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "metadata_candidates_series_id_fkey" FOREIGN KEY ("series_id") REFERENCES "series" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "metadata_field_states" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "field" TEXT NOT NULL,
+    "locked" BOOLEAN NOT NULL DEFAULT false,
+    "source" TEXT,
+    "updated_at" DATETIME NOT NULL,
+    "media_id" TEXT,
+    "series_id" TEXT,
+    CONSTRAINT "metadata_field_states_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "metadata_field_states_series_id_fkey" FOREIGN KEY ("series_id") REFERENCES "series" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "metadata_candidates_media_id_idx" ON "metadata_candidates"("media_id");
+
+-- CreateIndex
+CREATE INDEX "metadata_candidates_series_id_idx" ON "metadata_candidates"("series_id");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "metadata_field_states_media_id_field_key" ON "metadata_field_states"("media_id", "field");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "metadata_field_states_series_id_field_key" ON "metadata_field_states"("series_id", "field");
@@ -0,0 +1,22 @@
+-- CreateTable
+CREATE TABLE "metadata_field_changes" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "field" TEXT NOT NULL,
+    "source" TEXT NOT NULL,
+    "old_value" TEXT,
+    "new_value" TEXT,
+    "changed_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "job_id" TEXT,
+    "user_id" TEXT,
+    "media_id" TEXT,
+    "series_id" TEXT,
+    CONSTRAINT "metadata_field_changes_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE SET NULL ON UPDATE CASCADE,
+    CONSTRAINT "metadata_field_changes_media_id_fkey" FOREIGN KEY ("media_id") REFERENCES "media" ("id") ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT "metadata_field_changes_series_id_fkey" FOREIGN KEY ("series_id") REFERENCES "series" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "metadata_field_changes_media_id_idx" ON "metadata_field_changes"("media_id");
+
+-- CreateIndex
+CREATE INDEX "metadata_field_changes_series_id_idx" ON "metadata_field_changes"("series_id");
@@ -0,0 +1,17 @@
+-- CreateTable
+CREATE TABLE "user_external_identities" (
+    "id" TEXT NOT NULL PRIMARY KEY,
+    "issuer" TEXT NOT NULL,
+    "subject" TEXT NOT NULL,
+    "email" TEXT,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "last_login_at" DATETIME,
+    "user_id" TEXT NOT NULL,
+    CONSTRAINT "user_external_identities_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "user_external_identities_user_id_idx" ON "user_external_identities"("user_id");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "user_external_identities_issuer_subject_key" ON "user_external_identities"("issuer", "subject");
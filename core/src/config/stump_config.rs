@@ -35,6 +35,25 @@ pub mod env_keys {
 	pub const MAX_IMAGE_UPLOAD_SIZE_KEY: &str = "STUMP_MAX_IMAGE_UPLOAD_SIZE";
 	pub const ENABLE_UPLOAD_KEY: &str = "STUMP_ENABLE_UPLOAD";
 	pub const MAX_FILE_UPLOAD_SIZE_KEY: &str = "STUMP_MAX_FILE_UPLOAD_SIZE";
+	pub const PAGE_CACHE_SIZE_KEY: &str = "STUMP_PAGE_CACHE_SIZE";
+	pub const INDEX_EPUB_CONTENT_KEY: &str = "STUMP_INDEX_EPUB_CONTENT";
+	pub const GOOGLE_BOOKS_API_KEY_KEY: &str = "STUMP_GOOGLE_BOOKS_API_KEY";
+	pub const COMICVINE_API_KEY_KEY: &str = "STUMP_COMICVINE_API_KEY";
+	pub const OIDC_ISSUER_URL_KEY: &str = "STUMP_OIDC_ISSUER_URL";
+	pub const OIDC_CLIENT_ID_KEY: &str = "STUMP_OIDC_CLIENT_ID";
+	pub const OIDC_CLIENT_SECRET_KEY: &str = "STUMP_OIDC_CLIENT_SECRET";
+	pub const OIDC_REDIRECT_URI_KEY: &str = "STUMP_OIDC_REDIRECT_URI";
+	pub const OIDC_SCOPES_KEY: &str = "STUMP_OIDC_SCOPES";
+	pub const OIDC_AUTO_PROVISION_KEY: &str = "STUMP_OIDC_AUTO_PROVISION";
+	pub const OIDC_PERMISSIONS_CLAIM_KEY: &str = "STUMP_OIDC_PERMISSIONS_CLAIM";
+	pub const OIDC_PERMISSION_MAPPINGS_KEY: &str = "STUMP_OIDC_PERMISSION_MAPPINGS";
+	pub const OIDC_AGE_RESTRICTION_CLAIM_KEY: &str = "STUMP_OIDC_AGE_RESTRICTION_CLAIM";
+	pub const TWO_FACTOR_REQUIRED_FOR_OWNER_KEY: &str =
+		"STUMP_TWO_FACTOR_REQUIRED_FOR_OWNER";
+	pub const TWO_FACTOR_REQUIRED_FOR_MANAGERS_KEY: &str =
+		"STUMP_TWO_FACTOR_REQUIRED_FOR_MANAGERS";
+	pub const AUDIT_LOG_RETENTION_DAYS_KEY: &str = "STUMP_AUDIT_LOG_RETENTION_DAYS";
+	pub const AUDIT_LOG_NOTIFICATIONS_KEY: &str = "STUMP_AUDIT_LOG_NOTIFICATIONS";
 }
 use env_keys::*;
 
@@ -48,6 +67,13 @@ pub mod defaults {
 	pub const DEFAULT_MAX_IMAGE_UPLOAD_SIZE: usize = 20 * 1024 * 1024; // 20 MB
 	pub const DEFAULT_ENABLE_UPLOAD: bool = false;
 	pub const DEFAULT_MAX_FILE_UPLOAD_SIZE: usize = 20 * 1024 * 1024; // 20 MB
+	pub const DEFAULT_PAGE_CACHE_SIZE: u64 = 1024 * 1024 * 1024; // 1 GB
+	pub const DEFAULT_INDEX_EPUB_CONTENT: bool = false;
+	pub const DEFAULT_OIDC_AUTO_PROVISION: bool = false;
+	pub const DEFAULT_TWO_FACTOR_REQUIRED_FOR_OWNER: bool = false;
+	pub const DEFAULT_TWO_FACTOR_REQUIRED_FOR_MANAGERS: bool = false;
+	pub const DEFAULT_AUDIT_LOG_RETENTION_DAYS: u32 = 365;
+	pub const DEFAULT_AUDIT_LOG_NOTIFICATIONS: bool = false;
 }
 use defaults::*;
 
@@ -194,6 +220,110 @@ pub struct StumpConfig {
 	#[default_value(DEFAULT_MAX_FILE_UPLOAD_SIZE)]
 	#[env_key(MAX_FILE_UPLOAD_SIZE_KEY)]
 	pub max_file_upload_size: usize,
+
+	/// The maximum size, in bytes, of the on-disk cache of transformed (e.g., resized) pages.
+	/// The least recently used pages are evicted once the cache exceeds this size. A value of
+	/// 0 disables the cache.
+	#[default_value(DEFAULT_PAGE_CACHE_SIZE)]
+	#[env_key(PAGE_CACHE_SIZE_KEY)]
+	pub page_cache_size: u64,
+
+	/// Whether or not the text of EPUB chapters is extracted into the search index when books
+	/// are scanned. This makes the content of books searchable, at the cost of slower scans and
+	/// a larger database.
+	#[default_value(DEFAULT_INDEX_EPUB_CONTENT)]
+	#[env_key(INDEX_EPUB_CONTENT_KEY)]
+	pub index_epub_content: bool,
+
+	/// An optional API key for Google Books, used when fetching metadata. Google Books can be
+	/// used without one, but anonymous requests share a much smaller quota.
+	#[default_value(None)]
+	#[env_key(GOOGLE_BOOKS_API_KEY_KEY)]
+	#[serde(skip_serializing)]
+	pub google_books_api_key: Option<String>,
+
+	/// An optional API key for ComicVine, used when fetching metadata. ComicVine is not
+	/// available as a metadata provider without one.
+	#[default_value(None)]
+	#[env_key(COMICVINE_API_KEY_KEY)]
+	#[serde(skip_serializing)]
+	pub comicvine_api_key: Option<String>,
+
+	/// The URL of an OpenID Connect provider to allow logging in with. The provider is
+	/// discovered from its `/.well-known/openid-configuration`.
+	#[default_value(None)]
+	#[env_key(OIDC_ISSUER_URL_KEY)]
+	pub oidc_issuer_url: Option<String>,
+
+	/// The client ID Stump is registered with at the OpenID Connect provider.
+	#[default_value(None)]
+	#[env_key(OIDC_CLIENT_ID_KEY)]
+	pub oidc_client_id: Option<String>,
+
+	/// The client secret Stump is registered with at the OpenID Connect provider, if it is a
+	/// confidential client.
+	#[default_value(None)]
+	#[env_key(OIDC_CLIENT_SECRET_KEY)]
+	#[serde(skip_serializing)]
+	pub oidc_client_secret: Option<String>,
+
+	/// The URL the OpenID Connect provider redirects back to after a login, which must be
+	/// `/api/v1/auth/oidc/callback` on the public URL of the server.
+	#[default_value(None)]
+	#[env_key(OIDC_REDIRECT_URI_KEY)]
+	pub oidc_redirect_uri: Option<String>,
+
+	/// Additional scopes to request from the OpenID Connect provider. The `openid`, `profile`
+	/// and `email` scopes are always requested.
+	#[default_value(vec![])]
+	#[env_key(OIDC_SCOPES_KEY)]
+	pub oidc_scopes: Vec<String>,
+
+	/// Whether to create a user the first time someone logs in with the OpenID Connect
+	/// provider without a linked account.
+	#[default_value(DEFAULT_OIDC_AUTO_PROVISION)]
+	#[env_key(OIDC_AUTO_PROVISION_KEY)]
+	pub oidc_auto_provision: bool,
+
+	/// The ID token claim (e.g. `groups`) whose values are mapped to permissions using
+	/// `oidc_permission_mappings`.
+	#[default_value(None)]
+	#[env_key(OIDC_PERMISSIONS_CLAIM_KEY)]
+	pub oidc_permissions_claim: Option<String>,
+
+	/// Mappings from values of the permissions claim to permissions, each in the form
+	/// `value=permission`, e.g. `stump-admins=library:manage`.
+	#[default_value(vec![])]
+	#[env_key(OIDC_PERMISSION_MAPPINGS_KEY)]
+	pub oidc_permission_mappings: Vec<String>,
+
+	/// The ID token claim holding the minimum age to restrict a user to.
+	#[default_value(None)]
+	#[env_key(OIDC_AGE_RESTRICTION_CLAIM_KEY)]
+	pub oidc_age_restriction_claim: Option<String>,
+
+	/// Whether server owners must enroll in two-factor authentication before they can use
+	/// the API.
+	#[default_value(DEFAULT_TWO_FACTOR_REQUIRED_FOR_OWNER)]
+	#[env_key(TWO_FACTOR_REQUIRED_FOR_OWNER_KEY)]
+	pub two_factor_required_for_owner: bool,
+
+	/// Whether users with the `server:manage` or `user:manage` permission must enroll in
+	/// two-factor authentication before they can use the API.
+	#[default_value(DEFAULT_TWO_FACTOR_REQUIRED_FOR_MANAGERS)]
+	#[env_key(TWO_FACTOR_REQUIRED_FOR_MANAGERS_KEY)]
+	pub two_factor_required_for_managers: bool,
+
+	/// The number of days to keep audit log entries for. Older entries are removed as new
+	/// ones are recorded. Set to 0 to keep entries forever.
+	#[default_value(DEFAULT_AUDIT_LOG_RETENTION_DAYS)]
+	#[env_key(AUDIT_LOG_RETENTION_DAYS_KEY)]
+	pub audit_log_retention_days: u32,
+
+	/// Whether audit log entries should also be sent to notifiers subscribed to them.
+	#[default_value(DEFAULT_AUDIT_LOG_NOTIFICATIONS)]
+	#[env_key(AUDIT_LOG_NOTIFICATIONS_KEY)]
+	pub audit_log_notifications: bool,
 }
 
 impl StumpConfig {
@@ -244,17 +374,34 @@ impl StumpConfig {
 		// Save configuration to Stump.toml
 		let stump_toml = config_dir.join("Stump.toml");
 
-		std::fs::write(
-			stump_toml.as_path(),
-			toml::to_string(&self).map_err(|e| {
-				eprintln!("Failed to serialize StumpConfig to toml: {e}");
-				CoreError::InitializationError(e.to_string())
-			})?,
-		)?;
+		let mut contents = toml::to_string(&self).map_err(|e| {
+			eprintln!("Failed to serialize StumpConfig to toml: {e}");
+			CoreError::InitializationError(e.to_string())
+		})?;
+		// Secrets are skipped when serializing so they are never sent to clients, but they
+		// still need to be persisted. Every value is a top-level key, so they can be appended
+		for (key, secret) in self.secrets() {
+			if let Some(secret) = secret {
+				let value = toml::Value::String(secret.clone());
+				contents.push_str(&format!("{key} = {value}\n"));
+			}
+		}
+
+		std::fs::write(stump_toml.as_path(), contents)?;
 
 		Ok(())
 	}
 
+	/// Returns the configured secrets, keyed by their name in Stump.toml. These are never
+	/// serialized, e.g. when the config is sent to a client.
+	fn secrets(&self) -> [(&'static str, &Option<String>); 3] {
+		[
+			("google_books_api_key", &self.google_books_api_key),
+			("comicvine_api_key", &self.comicvine_api_key),
+			("oidc_client_secret", &self.oidc_client_secret),
+		]
+	}
+
 	/// Returns True if the configuration profile is "debug" and False otherwise.
 	pub fn is_debug(&self) -> bool {
 		self.profile.as_str() == "debug"
@@ -270,6 +417,11 @@ impl StumpConfig {
 		PathBuf::from(&self.config_dir).join("cache")
 	}
 
+	/// Returns a `PathBuf` to the directory of the transformed page cache.
+	pub fn get_page_cache_dir(&self) -> PathBuf {
+		PathBuf::from(&self.config_dir).join("page-cache")
+	}
+
 	/// Returns a `PathBuf` to the Stump thumbnails directory.
 	pub fn get_thumbnails_dir(&self) -> PathBuf {
 		PathBuf::from(&self.config_dir).join("thumbnails")
@@ -340,6 +492,23 @@ mod tests {
 			max_image_upload_size: None,
 			enable_upload: None,
 			max_file_upload_size: None,
+			page_cache_size: None,
+			index_epub_content: None,
+			google_books_api_key: Some("not_a_real_key".to_string()),
+			comicvine_api_key: None,
+			oidc_issuer_url: None,
+			oidc_client_id: None,
+			oidc_client_secret: Some("not_a_real_secret".to_string()),
+			oidc_redirect_uri: None,
+			oidc_scopes: None,
+			oidc_auto_provision: None,
+			oidc_permissions_claim: None,
+			oidc_permission_mappings: None,
+			oidc_age_restriction_claim: None,
+			two_factor_required_for_owner: None,
+			two_factor_required_for_managers: None,
+			audit_log_retention_days: None,
+			audit_log_notifications: None,
 		};
 		partial_config.apply_to_config(&mut config);
 
@@ -378,7 +547,28 @@ mod tests {
 				max_thumbnail_concurrency: Some(DEFAULT_MAX_THUMBNAIL_CONCURRENCY),
 				max_image_upload_size: Some(DEFAULT_MAX_IMAGE_UPLOAD_SIZE),
 				enable_upload: Some(DEFAULT_ENABLE_UPLOAD),
-				max_file_upload_size: Some(DEFAULT_MAX_FILE_UPLOAD_SIZE)
+				max_file_upload_size: Some(DEFAULT_MAX_FILE_UPLOAD_SIZE),
+				page_cache_size: Some(DEFAULT_PAGE_CACHE_SIZE),
+				index_epub_content: Some(DEFAULT_INDEX_EPUB_CONTENT),
+				google_books_api_key: Some("not_a_real_key".to_string()),
+				comicvine_api_key: None,
+				oidc_issuer_url: None,
+				oidc_client_id: None,
+				oidc_client_secret: Some("not_a_real_secret".to_string()),
+				oidc_redirect_uri: None,
+				oidc_scopes: Some(vec![]),
+				oidc_auto_provision: Some(DEFAULT_OIDC_AUTO_PROVISION),
+				oidc_permissions_claim: None,
+				oidc_permission_mappings: Some(vec![]),
+				oidc_age_restriction_claim: None,
+				two_factor_required_for_owner: Some(
+					DEFAULT_TWO_FACTOR_REQUIRED_FOR_OWNER
+				),
+				two_factor_required_for_managers: Some(
+					DEFAULT_TWO_FACTOR_REQUIRED_FOR_MANAGERS
+				),
+				audit_log_retention_days: Some(DEFAULT_AUDIT_LOG_RETENTION_DAYS),
+				audit_log_notifications: Some(DEFAULT_AUDIT_LOG_NOTIFICATIONS),
 			}
 		);
 
@@ -388,6 +578,19 @@ mod tests {
 			.expect("Failed to delete temporary directory");
 	}
 
+	#[test]
+	fn test_secrets_are_not_serialized() {
+		let mut config = StumpConfig::new("not_a_real_dir".to_string());
+		config.google_books_api_key = Some("not_a_real_key".to_string());
+		config.comicvine_api_key = Some("not_a_real_key".to_string());
+		config.oidc_client_secret = Some("not_a_real_secret".to_string());
+
+		let serialized = serde_json::to_value(&config).unwrap();
+		assert!(serialized.get("google_books_api_key").is_none());
+		assert!(serialized.get("comicvine_api_key").is_none());
+		assert!(serialized.get("oidc_client_secret").is_none());
+	}
+
 	#[test]
 	fn test_simulate_first_boot() {
 		temp_env::with_vars(
@@ -433,6 +636,25 @@ mod tests {
 						max_image_upload_size: DEFAULT_MAX_IMAGE_UPLOAD_SIZE,
 						enable_upload: DEFAULT_ENABLE_UPLOAD,
 						max_file_upload_size: DEFAULT_MAX_FILE_UPLOAD_SIZE,
+						page_cache_size: DEFAULT_PAGE_CACHE_SIZE,
+						index_epub_content: DEFAULT_INDEX_EPUB_CONTENT,
+						google_books_api_key: None,
+						comicvine_api_key: None,
+						oidc_issuer_url: None,
+						oidc_client_id: None,
+						oidc_client_secret: None,
+						oidc_redirect_uri: None,
+						oidc_scopes: vec![],
+						oidc_auto_provision: DEFAULT_OIDC_AUTO_PROVISION,
+						oidc_permissions_claim: None,
+						oidc_permission_mappings: vec![],
+						oidc_age_restriction_claim: None,
+						two_factor_required_for_owner:
+							DEFAULT_TWO_FACTOR_REQUIRED_FOR_OWNER,
+						two_factor_required_for_managers:
+							DEFAULT_TWO_FACTOR_REQUIRED_FOR_MANAGERS,
+						audit_log_retention_days: DEFAULT_AUDIT_LOG_RETENTION_DAYS,
+						audit_log_notifications: DEFAULT_AUDIT_LOG_NOTIFICATIONS,
 					}
 				);
 			},
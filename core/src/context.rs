@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use prisma_client_rust::not;
+use integrations::NotifierEvent;
 use tokio::sync::{
 	broadcast::{channel, Receiver, Sender},
 	mpsc::error::SendError,
@@ -8,12 +8,16 @@ use tokio::sync::{
 
 use crate::{
 	config::StumpConfig,
-	db,
+	db::{
+		self,
+		entity::{dispatch_notifier_event, AuditEvent},
+	},
 	event::CoreEvent,
 	filesystem::scanner::LibraryWatcher,
 	job::{Executor, JobController, JobControllerCommand},
-	prisma::{self, server_config},
-	CoreError, CoreResult,
+	prisma,
+	utils::get_encryption_key,
+	CoreResult,
 };
 
 type EventChannel = (Sender<CoreEvent>, Receiver<CoreEvent>);
@@ -216,18 +220,67 @@ impl Ctx {
 		}
 	}
 
-	pub async fn get_encryption_key(&self) -> CoreResult<String> {
-		let server_config = self
-			.db
-			.server_config()
-			.find_first(vec![not![server_config::encryption_key::equals(None)]])
+	/// Send a [`NotifierEvent`] to any notifiers subscribed to it. Library events should
+	/// provide the ID of the library, so notifiers can filter on it
+	pub fn send_notifier_event(&self, event: NotifierEvent, library_id: Option<String>) {
+		dispatch_notifier_event(self.db.clone(), event, library_id);
+	}
+
+	/// Record an [`AuditEvent`] in the audit log. If enabled, the event is also sent to any
+	/// notifiers subscribed to it.
+	/// Failures are logged rather than returned, since the audited action has already happened
+	pub async fn record_audit_event(&self, event: AuditEvent) {
+		if let Err(error) = self.persist_audit_event(&event).await {
+			tracing::error!(?error, action = %event.action, "Failed to record audit event");
+		}
+
+		if self.config.audit_log_notifications {
+			let AuditEvent {
+				action,
+				actor_username,
+				target_id,
+				target_name,
+				..
+			} = event;
+			self.send_notifier_event(
+				NotifierEvent::AuditEvent {
+					action: action.to_string(),
+					actor: actor_username
+						.unwrap_or_else(|| "an unknown user".to_string()),
+					target: target_name
+						.or(target_id)
+						.unwrap_or_else(|| action.target_type().to_string()),
+				},
+				None,
+			);
+		}
+	}
+
+	async fn persist_audit_event(&self, event: &AuditEvent) -> CoreResult<()> {
+		let changes = (!event.changes.is_empty())
+			.then(|| serde_json::to_vec(&event.changes))
+			.transpose()?;
+
+		self.db
+			.audit_log()
+			.create(
+				event.action.to_string(),
+				event.action.target_type().to_string(),
+				vec![
+					prisma::audit_log::actor_id::set(event.actor_id.clone()),
+					prisma::audit_log::actor_username::set(event.actor_username.clone()),
+					prisma::audit_log::target_id::set(event.target_id.clone()),
+					prisma::audit_log::target_name::set(event.target_name.clone()),
+					prisma::audit_log::changes::set(changes),
+				],
+			)
 			.exec()
 			.await?;
 
-		let encryption_key = server_config
-			.and_then(|config| config.encryption_key)
-			.ok_or(CoreError::EncryptionKeyNotSet)?;
+		Ok(())
+	}
 
-		Ok(encryption_key)
+	pub async fn get_encryption_key(&self) -> CoreResult<String> {
+		get_encryption_key(&self.db).await
 	}
 }
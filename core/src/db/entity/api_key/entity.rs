@@ -5,7 +5,8 @@ use specta::Type;
 
 use crate::{
 	db::entity::{User, UserPermission},
-	prisma::api_key,
+	prisma::{api_key, library, series},
+	utils::chain_optional_iter,
 	CoreError, CoreResult,
 };
 
@@ -37,6 +38,103 @@ impl Default for APIKeyPermissions {
 	}
 }
 
+/// A family of routes which an API key can be restricted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum APIKeyRouteScope {
+	/// The OPDS catalogs, under `/opds`
+	#[serde(rename = "opds")]
+	OPDS,
+	/// The KOReader sync API, under `/koreader`
+	#[serde(rename = "koreader")]
+	KOReader,
+	/// The routes which read and update reading progress, e.g. `/api/v1/media/{id}/progress`
+	/// and the progress routes of the OPDS and KOReader APIs
+	#[serde(rename = "progress_sync")]
+	ProgressSync,
+}
+
+impl APIKeyRouteScope {
+	/// Whether a request path belongs to this family of routes
+	pub fn matches_path(&self, path: &str) -> bool {
+		let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+		match self {
+			APIKeyRouteScope::OPDS => segments.first() == Some(&"opds"),
+			APIKeyRouteScope::KOReader => segments.first() == Some(&"koreader"),
+			APIKeyRouteScope::ProgressSync => matches!(
+				segments.as_slice(),
+				["api", "v1", "media" | "epub", _, "progress", ..]
+					| ["opds", .., "progression"]
+					| ["koreader", _, "syncs", "progress", ..]
+					| ["koreader", _, "users", "auth"]
+			),
+		}
+	}
+}
+
+/// The scopes of an API key, which restrict what it can be used for on top of its permissions.
+/// A key with the default scopes is unrestricted, i.e. empty lists do not restrict anything
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct APIKeyScopes {
+	/// The IDs of the libraries the key can access content from
+	#[serde(default)]
+	pub library_ids: Vec<String>,
+	/// The IDs of the series the key can access content from
+	#[serde(default)]
+	pub series_ids: Vec<String>,
+	/// Whether the key can only be used for requests which don't change anything, i.e. `GET`
+	/// requests
+	#[serde(default)]
+	pub read_only: bool,
+	/// The families of routes the key can be used for
+	#[serde(default)]
+	pub routes: Vec<APIKeyRouteScope>,
+}
+
+impl APIKeyScopes {
+	/// Whether the scopes restrict which libraries or series the key can access
+	pub fn restricts_content(&self) -> bool {
+		!self.library_ids.is_empty() || !self.series_ids.is_empty()
+	}
+
+	/// Whether a request with the given method and path is allowed by the scopes
+	pub fn allows_request(&self, method: &str, path: &str) -> bool {
+		if self.read_only && !matches!(method, "GET" | "HEAD" | "OPTIONS") {
+			return false;
+		}
+
+		self.routes.is_empty() || self.routes.iter().any(|route| route.matches_path(path))
+	}
+
+	/// The conditions for the libraries which the key can access. A library containing one
+	/// of the series the key is restricted to can be accessed, although only that series
+	/// within it can be
+	pub fn library_params(&self) -> Vec<library::WhereParam> {
+		chain_optional_iter(
+			[],
+			[
+				(!self.library_ids.is_empty())
+					.then(|| library::id::in_vec(self.library_ids.clone())),
+				(!self.series_ids.is_empty()).then(|| {
+					library::series::some(vec![series::id::in_vec(
+						self.series_ids.clone(),
+					)])
+				}),
+			],
+		)
+	}
+
+	/// The conditions for the series which the key can access. This should be used alongside
+	/// [APIKeyScopes::library_params] for the library of the series
+	pub fn series_params(&self) -> Vec<series::WhereParam> {
+		chain_optional_iter(
+			[],
+			[(!self.series_ids.is_empty())
+				.then(|| series::id::in_vec(self.series_ids.clone()))],
+		)
+	}
+}
+
 /// An API key which can be used to interact with the API. API keys are scoped to a user,
 /// so all actions taken with an API key are done as if the user was taking them.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, Type)]
@@ -48,6 +146,8 @@ pub struct APIKey {
 	/// The permissions for the API key, either inherited from the user or custom
 	/// permissions set on the key
 	pub permissions: APIKeyPermissions,
+	/// The scopes of the API key, which restrict what it can be used for
+	pub scopes: APIKeyScopes,
 	/// The hashed long token for the API key
 	#[serde(skip_serializing)]
 	pub long_token_hash: String,
@@ -137,6 +237,19 @@ impl TryFrom<Vec<u8>> for APIKeyPermissions {
 	}
 }
 
+impl TryFrom<Vec<u8>> for APIKeyScopes {
+	type Error = CoreError;
+
+	fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+		serde_json::from_slice(&value).map_err(|e| {
+			CoreError::InternalError(format!(
+				"Failed to deserialize API key scopes: {}",
+				e
+			))
+		})
+	}
+}
+
 impl TryFrom<api_key::Data> for APIKey {
 	type Error = CoreError;
 
@@ -145,6 +258,11 @@ impl TryFrom<api_key::Data> for APIKey {
 			id: data.id,
 			name: data.name,
 			permissions: serde_json::from_slice(&data.permissions)?,
+			scopes: data
+				.scopes
+				.map(APIKeyScopes::try_from)
+				.transpose()?
+				.unwrap_or_default(),
 			long_token_hash: data.long_token_hash,
 			user_id: data.user_id,
 			created_at: data.created_at,
@@ -185,6 +303,72 @@ mod tests {
 		assert_eq!(custom, r#"["feature:api_keys"]"#);
 	}
 
+	#[test]
+	fn test_deserialize_api_key_scopes() {
+		let scopes: APIKeyScopes =
+			serde_json::from_str("{}").expect("Failed to deserialize empty scopes");
+		assert_eq!(scopes, APIKeyScopes::default());
+		assert!(!scopes.restricts_content());
+
+		let scopes: APIKeyScopes = serde_json::from_str(
+			r#"{"series_ids":["1"],"read_only":true,"routes":["opds","progress_sync"]}"#,
+		)
+		.expect("Failed to deserialize scopes");
+		assert!(scopes.restricts_content());
+		assert!(scopes.read_only);
+		assert_eq!(
+			scopes.routes,
+			vec![APIKeyRouteScope::OPDS, APIKeyRouteScope::ProgressSync]
+		);
+	}
+
+	#[test]
+	fn test_route_scope_matches_path() {
+		assert!(APIKeyRouteScope::OPDS.matches_path("/opds/v2.0/catalog"));
+		assert!(APIKeyRouteScope::OPDS.matches_path("/opds/stump_abc_123/v1.2/catalog"));
+		assert!(!APIKeyRouteScope::OPDS.matches_path("/api/v1/libraries"));
+
+		assert!(
+			APIKeyRouteScope::KOReader.matches_path("/koreader/stump_abc_123/users/auth")
+		);
+		assert!(!APIKeyRouteScope::KOReader.matches_path("/opds/v2.0/catalog"));
+
+		let progress_sync = APIKeyRouteScope::ProgressSync;
+		assert!(progress_sync.matches_path("/api/v1/media/1/progress"));
+		assert!(progress_sync.matches_path("/api/v1/media/1/progress/complete"));
+		assert!(progress_sync.matches_path("/api/v1/epub/1/progress"));
+		assert!(progress_sync.matches_path("/opds/v2.0/books/1/progression"));
+		assert!(progress_sync.matches_path("/koreader/stump_abc_123/syncs/progress"));
+		assert!(progress_sync.matches_path("/koreader/stump_abc_123/syncs/progress/abc"));
+		assert!(progress_sync.matches_path("/koreader/stump_abc_123/users/auth"));
+		assert!(!progress_sync.matches_path("/api/v1/media/1/page/1"));
+		assert!(!progress_sync.matches_path("/opds/v2.0/books/1/file"));
+		assert!(!progress_sync.matches_path("/api/v1/users/1"));
+	}
+
+	#[test]
+	fn test_scopes_allows_request() {
+		let unrestricted = APIKeyScopes::default();
+		assert!(unrestricted.allows_request("GET", "/api/v1/libraries"));
+		assert!(unrestricted.allows_request("DELETE", "/api/v1/libraries/1"));
+
+		let read_only = APIKeyScopes {
+			read_only: true,
+			..Default::default()
+		};
+		assert!(read_only.allows_request("GET", "/api/v1/libraries"));
+		assert!(!read_only.allows_request("POST", "/api/v1/libraries"));
+		assert!(!read_only.allows_request("PUT", "/api/v1/media/1/progress"));
+
+		let opds_only = APIKeyScopes {
+			routes: vec![APIKeyRouteScope::OPDS],
+			..Default::default()
+		};
+		assert!(opds_only.allows_request("GET", "/opds/v2.0/catalog"));
+		assert!(opds_only.allows_request("PUT", "/opds/v2.0/books/1/progression"));
+		assert!(!opds_only.allows_request("GET", "/api/v1/libraries"));
+	}
+
 	#[test]
 	fn test_validate() {
 		let (pek, hash) = APIKey::create_prefixed_key().expect("Failed to create key");
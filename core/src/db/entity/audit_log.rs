@@ -0,0 +1,414 @@
+use std::{fmt, str::FromStr};
+
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{prisma::audit_log, CoreError, CoreResult};
+
+use super::{Cursor, User};
+
+/// The kinds of entities an audit log entry can be about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, Type)]
+pub enum AuditTargetType {
+	#[serde(rename = "LIBRARY")]
+	Library,
+	#[serde(rename = "USER")]
+	User,
+	#[serde(rename = "API_KEY")]
+	APIKey,
+	#[serde(rename = "EMAILER")]
+	Emailer,
+}
+
+impl fmt::Display for AuditTargetType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			AuditTargetType::Library => write!(f, "LIBRARY"),
+			AuditTargetType::User => write!(f, "USER"),
+			AuditTargetType::APIKey => write!(f, "API_KEY"),
+			AuditTargetType::Emailer => write!(f, "EMAILER"),
+		}
+	}
+}
+
+impl FromStr for AuditTargetType {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_uppercase().as_str() {
+			"LIBRARY" => Ok(AuditTargetType::Library),
+			"USER" => Ok(AuditTargetType::User),
+			"API_KEY" => Ok(AuditTargetType::APIKey),
+			"EMAILER" => Ok(AuditTargetType::Emailer),
+			_ => Err(format!("Invalid AuditTargetType: {s}")),
+		}
+	}
+}
+
+/// The administrative and security actions which are recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, Type)]
+pub enum AuditAction {
+	#[serde(rename = "LIBRARY_CREATED")]
+	LibraryCreated,
+	#[serde(rename = "LIBRARY_UPDATED")]
+	LibraryUpdated,
+	#[serde(rename = "LIBRARY_DELETED")]
+	LibraryDeleted,
+	#[serde(rename = "LIBRARY_ACCESS_CHANGED")]
+	LibraryAccessChanged,
+	#[serde(rename = "USER_CREATED")]
+	UserCreated,
+	#[serde(rename = "USER_UPDATED")]
+	UserUpdated,
+	#[serde(rename = "USER_DELETED")]
+	UserDeleted,
+	#[serde(rename = "USER_LOCKED")]
+	UserLocked,
+	#[serde(rename = "USER_UNLOCKED")]
+	UserUnlocked,
+	#[serde(rename = "USER_SESSIONS_REVOKED")]
+	UserSessionsRevoked,
+	#[serde(rename = "USER_TWO_FACTOR_RESET")]
+	UserTwoFactorReset,
+	#[serde(rename = "USER_PASSWORD_RESET")]
+	UserPasswordReset,
+	#[serde(rename = "API_KEY_CREATED")]
+	APIKeyCreated,
+	#[serde(rename = "API_KEY_UPDATED")]
+	APIKeyUpdated,
+	#[serde(rename = "API_KEY_DELETED")]
+	APIKeyDeleted,
+	#[serde(rename = "API_KEY_SECRET_REGENERATED")]
+	APIKeySecretRegenerated,
+	#[serde(rename = "EMAILER_CREATED")]
+	EmailerCreated,
+	#[serde(rename = "EMAILER_UPDATED")]
+	EmailerUpdated,
+	#[serde(rename = "EMAILER_DELETED")]
+	EmailerDeleted,
+	#[serde(rename = "EMAIL_SENT")]
+	EmailSent,
+}
+
+impl AuditAction {
+	const ALL: [AuditAction; 20] = [
+		AuditAction::LibraryCreated,
+		AuditAction::LibraryUpdated,
+		AuditAction::LibraryDeleted,
+		AuditAction::LibraryAccessChanged,
+		AuditAction::UserCreated,
+		AuditAction::UserUpdated,
+		AuditAction::UserDeleted,
+		AuditAction::UserLocked,
+		AuditAction::UserUnlocked,
+		AuditAction::UserSessionsRevoked,
+		AuditAction::UserTwoFactorReset,
+		AuditAction::UserPasswordReset,
+		AuditAction::APIKeyCreated,
+		AuditAction::APIKeyUpdated,
+		AuditAction::APIKeyDeleted,
+		AuditAction::APIKeySecretRegenerated,
+		AuditAction::EmailerCreated,
+		AuditAction::EmailerUpdated,
+		AuditAction::EmailerDeleted,
+		AuditAction::EmailSent,
+	];
+
+	/// A stable, machine-readable name for the action, e.g. `LIBRARY_DELETED`
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			AuditAction::LibraryCreated => "LIBRARY_CREATED",
+			AuditAction::LibraryUpdated => "LIBRARY_UPDATED",
+			AuditAction::LibraryDeleted => "LIBRARY_DELETED",
+			AuditAction::LibraryAccessChanged => "LIBRARY_ACCESS_CHANGED",
+			AuditAction::UserCreated => "USER_CREATED",
+			AuditAction::UserUpdated => "USER_UPDATED",
+			AuditAction::UserDeleted => "USER_DELETED",
+			AuditAction::UserLocked => "USER_LOCKED",
+			AuditAction::UserUnlocked => "USER_UNLOCKED",
+			AuditAction::UserSessionsRevoked => "USER_SESSIONS_REVOKED",
+			AuditAction::UserTwoFactorReset => "USER_TWO_FACTOR_RESET",
+			AuditAction::UserPasswordReset => "USER_PASSWORD_RESET",
+			AuditAction::APIKeyCreated => "API_KEY_CREATED",
+			AuditAction::APIKeyUpdated => "API_KEY_UPDATED",
+			AuditAction::APIKeyDeleted => "API_KEY_DELETED",
+			AuditAction::APIKeySecretRegenerated => "API_KEY_SECRET_REGENERATED",
+			AuditAction::EmailerCreated => "EMAILER_CREATED",
+			AuditAction::EmailerUpdated => "EMAILER_UPDATED",
+			AuditAction::EmailerDeleted => "EMAILER_DELETED",
+			AuditAction::EmailSent => "EMAIL_SENT",
+		}
+	}
+
+	/// The kind of entity the action is performed on
+	pub fn target_type(&self) -> AuditTargetType {
+		match self {
+			AuditAction::LibraryCreated
+			| AuditAction::LibraryUpdated
+			| AuditAction::LibraryDeleted
+			| AuditAction::LibraryAccessChanged => AuditTargetType::Library,
+			AuditAction::UserCreated
+			| AuditAction::UserUpdated
+			| AuditAction::UserDeleted
+			| AuditAction::UserLocked
+			| AuditAction::UserUnlocked
+			| AuditAction::UserSessionsRevoked
+			| AuditAction::UserTwoFactorReset
+			| AuditAction::UserPasswordReset => AuditTargetType::User,
+			AuditAction::APIKeyCreated
+			| AuditAction::APIKeyUpdated
+			| AuditAction::APIKeyDeleted
+			| AuditAction::APIKeySecretRegenerated => AuditTargetType::APIKey,
+			AuditAction::EmailerCreated
+			| AuditAction::EmailerUpdated
+			| AuditAction::EmailerDeleted
+			| AuditAction::EmailSent => AuditTargetType::Emailer,
+		}
+	}
+}
+
+impl fmt::Display for AuditAction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+impl FromStr for AuditAction {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let uppercase = s.to_uppercase();
+		AuditAction::ALL
+			.into_iter()
+			.find(|action| action.as_str() == uppercase)
+			.ok_or_else(|| format!("Invalid AuditAction: {s}"))
+	}
+}
+
+/// A change to a single field of the target of an audit log entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema, Type)]
+pub struct AuditLogChange {
+	/// The name of the field, e.g. `name` or `permissions`
+	pub field: String,
+	/// The previous value of the field, serialized as JSON
+	pub before: Option<String>,
+	/// The new value of the field, serialized as JSON
+	pub after: Option<String>,
+}
+
+impl AuditLogChange {
+	/// Compute the top-level fields which differ between two serialized snapshots of an
+	/// entity. A missing snapshot means the entity was created or deleted, so every field of
+	/// the other snapshot is included. Fields which are only present in one of two snapshots,
+	/// e.g. relations which were only loaded for one of them, are ignored
+	pub fn diff<T: Serialize>(before: Option<&T>, after: Option<&T>) -> Vec<Self> {
+		let to_fields = |value: Option<&T>| {
+			value.and_then(|value| match serde_json::to_value(value) {
+				Ok(serde_json::Value::Object(fields)) => Some(fields),
+				_ => None,
+			})
+		};
+		let to_string = |value: &serde_json::Value| value.to_string();
+
+		match (to_fields(before), to_fields(after)) {
+			(Some(before), Some(after)) => before
+				.iter()
+				.filter_map(|(field, before_value)| {
+					let after_value = after.get(field)?;
+					(before_value != after_value).then(|| Self {
+						field: field.clone(),
+						before: Some(to_string(before_value)),
+						after: Some(to_string(after_value)),
+					})
+				})
+				.collect(),
+			(Some(before), None) => before
+				.iter()
+				.map(|(field, value)| Self {
+					field: field.clone(),
+					before: Some(to_string(value)),
+					after: None,
+				})
+				.collect(),
+			(None, Some(after)) => after
+				.iter()
+				.map(|(field, value)| Self {
+					field: field.clone(),
+					before: None,
+					after: Some(to_string(value)),
+				})
+				.collect(),
+			(None, None) => vec![],
+		}
+	}
+}
+
+/// An event to record in the audit log, see [`crate::Ctx::record_audit_event`]
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+	pub action: AuditAction,
+	pub actor_id: Option<String>,
+	pub actor_username: Option<String>,
+	pub target_id: Option<String>,
+	pub target_name: Option<String>,
+	pub changes: Vec<AuditLogChange>,
+}
+
+impl AuditEvent {
+	pub fn new(action: AuditAction, actor: Option<&User>) -> Self {
+		Self {
+			action,
+			actor_id: actor.map(|user| user.id.clone()),
+			actor_username: actor.map(|user| user.username.clone()),
+			target_id: None,
+			target_name: None,
+			changes: vec![],
+		}
+	}
+
+	pub fn with_target(self, id: impl ToString, name: impl Into<String>) -> Self {
+		Self {
+			target_id: Some(id.to_string()),
+			target_name: Some(name.into()),
+			..self
+		}
+	}
+
+	/// Record the fields which changed between the snapshots of the target before and after
+	/// the action. See [`AuditLogChange::diff`]
+	pub fn with_changes<T: Serialize>(
+		self,
+		before: Option<&T>,
+		after: Option<&T>,
+	) -> Self {
+		Self {
+			changes: AuditLogChange::diff(before, after),
+			..self
+		}
+	}
+}
+
+/// A persisted record of an administrative or security event, e.g. a library being deleted
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Type)]
+pub struct AuditLog {
+	pub id: String,
+	/// The action which was performed
+	pub action: AuditAction,
+	/// The ID of the user who performed the action, if any. The user may have since been deleted
+	pub actor_id: Option<String>,
+	/// The username of the user at the time they performed the action
+	pub actor_username: Option<String>,
+	/// The kind of entity the action was performed on
+	pub target_type: AuditTargetType,
+	/// The ID of the entity the action was performed on
+	pub target_id: Option<String>,
+	/// The name of the entity at the time of the action, e.g. the library name or username
+	pub target_name: Option<String>,
+	/// The fields of the entity which were changed by the action
+	pub changes: Vec<AuditLogChange>,
+	pub timestamp: DateTime<FixedOffset>,
+}
+
+impl TryFrom<audit_log::Data> for AuditLog {
+	type Error = CoreError;
+
+	fn try_from(data: audit_log::Data) -> CoreResult<Self> {
+		Ok(Self {
+			action: data.action.parse().map_err(CoreError::InternalError)?,
+			target_type: data.target_type.parse().map_err(CoreError::InternalError)?,
+			changes: data
+				.changes
+				.map(|bytes| serde_json::from_slice(&bytes))
+				.transpose()?
+				.unwrap_or_default(),
+			id: data.id,
+			actor_id: data.actor_id,
+			actor_username: data.actor_username,
+			target_id: data.target_id,
+			target_name: data.target_name,
+			timestamp: data.timestamp,
+		})
+	}
+}
+
+impl Cursor for AuditLog {
+	fn cursor(&self) -> String {
+		self.id.clone()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Serialize)]
+	struct Snapshot {
+		name: String,
+		is_locked: bool,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		sessions: Option<i32>,
+	}
+
+	#[test]
+	fn test_diff_updated_fields() {
+		let before = Snapshot {
+			name: String::from("oromei"),
+			is_locked: false,
+			sessions: Some(2),
+		};
+		let after = Snapshot {
+			name: String::from("oromei"),
+			is_locked: true,
+			sessions: None,
+		};
+
+		assert_eq!(
+			AuditLogChange::diff(Some(&before), Some(&after)),
+			vec![AuditLogChange {
+				field: String::from("is_locked"),
+				before: Some(String::from("false")),
+				after: Some(String::from("true")),
+			}]
+		);
+	}
+
+	#[test]
+	fn test_diff_created_and_deleted() {
+		let snapshot = Snapshot {
+			name: String::from("Comics"),
+			is_locked: false,
+			sessions: None,
+		};
+
+		let created = AuditLogChange::diff(None, Some(&snapshot));
+		assert_eq!(created.len(), 2);
+		assert!(created
+			.iter()
+			.all(|change| change.before.is_none() && change.after.is_some()));
+
+		let deleted = AuditLogChange::diff(Some(&snapshot), None);
+		assert_eq!(deleted.len(), 2);
+		assert!(deleted
+			.iter()
+			.all(|change| change.before.is_some() && change.after.is_none()));
+
+		assert!(AuditLogChange::diff::<Snapshot>(None, None).is_empty());
+	}
+
+	#[test]
+	fn test_action_round_trip() {
+		for action in AuditAction::ALL {
+			assert_eq!(action.to_string().parse::<AuditAction>(), Ok(action));
+			assert_eq!(
+				serde_json::to_string(&action).unwrap(),
+				format!("\"{}\"", action.as_str())
+			);
+		}
+		assert_eq!(
+			"api_key".parse::<AuditTargetType>(),
+			Ok(AuditTargetType::APIKey)
+		);
+	}
+}
@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{EntityVisibility, Media, PartialUser, Series},
+	prisma::{collection, collection_item},
+	CoreError,
+};
+
+///////////////////////////////////////////////
+//////////////////// MODELS ///////////////////
+///////////////////////////////////////////////
+
+/// The kind of entity a collection item refers to
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type, ToSchema,
+)]
+pub enum CollectionItemKind {
+	#[serde(rename = "MEDIA")]
+	Media,
+	#[serde(rename = "SERIES")]
+	Series,
+}
+
+/// A single entry in a collection, which is either a book or an entire series
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct CollectionItem {
+	pub id: String,
+	/// The position of the item within the collection, starting at 0
+	pub display_order: i32,
+	pub kind: CollectionItemKind,
+	pub collection_id: String,
+	/// The id of the book, if the item is a book
+	pub media_id: Option<String>,
+	/// The book. Will be `None` if the item is a series or the relation is not loaded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	#[schema(no_recursion)]
+	pub media: Option<Media>,
+	/// The id of the series, if the item is a series
+	pub series_id: Option<String>,
+	/// The series. Will be `None` if the item is a book or the relation is not loaded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	#[schema(no_recursion)]
+	pub series: Option<Series>,
+}
+
+/// A server-wide collection of series and books, curated by admins. Unlike reading lists,
+/// collections are owned by the server rather than an individual user
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct Collection {
+	pub id: String,
+	/// The name of the collection. ex: "Spider-Man Comics"
+	pub name: String,
+	/// The description of the collection. ex: "All the Spider-Man arcs together in one place"
+	pub description: Option<String>,
+	/// Who the collection is visible to. Public collections are visible to all users, shared
+	/// collections only to the users they are shared with, and private collections only to
+	/// those who manage collections
+	pub visibility: EntityVisibility,
+	pub created_at: DateTime<FixedOffset>,
+	pub updated_at: DateTime<FixedOffset>,
+	/// The items in the collection, in display order. Will be `None` only if the relation
+	/// is not loaded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	pub items: Option<Vec<CollectionItem>>,
+	/// The users a shared collection is visible to. Will be `None` only if the relation is
+	/// not loaded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	pub shared_with_users: Option<Vec<PartialUser>>,
+}
+
+///////////////////////////////////////////////
+////////////////// CONVERSIONS ////////////////
+///////////////////////////////////////////////
+
+impl TryFrom<collection_item::Data> for CollectionItem {
+	type Error = CoreError;
+
+	fn try_from(data: collection_item::Data) -> Result<Self, Self::Error> {
+		let kind = match (&data.media_id, &data.series_id) {
+			(Some(_), None) => CollectionItemKind::Media,
+			(None, Some(_)) => CollectionItemKind::Series,
+			_ => {
+				return Err(CoreError::InternalError(format!(
+					"Collection item {} must refer to exactly one book or series",
+					data.id
+				)))
+			},
+		};
+
+		let media = data
+			.media()
+			.ok()
+			.flatten()
+			.map(|media| Media::from(media.to_owned()));
+		let series = data
+			.series()
+			.ok()
+			.flatten()
+			.map(|series| Series::from(series.to_owned()));
+
+		Ok(Self {
+			id: data.id,
+			display_order: data.display_order,
+			kind,
+			collection_id: data.collection_id,
+			media_id: data.media_id,
+			media,
+			series_id: data.series_id,
+			series,
+		})
+	}
+}
+
+impl TryFrom<collection::Data> for Collection {
+	type Error = CoreError;
+
+	fn try_from(data: collection::Data) -> Result<Self, Self::Error> {
+		let visibility = EntityVisibility::from_str(&data.visibility)
+			.map_err(CoreError::InternalError)?;
+
+		let items = data
+			.items
+			.map(|items| {
+				let mut items = items
+					.into_iter()
+					.map(CollectionItem::try_from)
+					.collect::<Result<Vec<_>, _>>()?;
+				items.sort_by_key(|item| item.display_order);
+				Ok::<_, CoreError>(items)
+			})
+			.transpose()?;
+		let shared_with_users = data
+			.shared_with_users
+			.map(|users| users.into_iter().map(PartialUser::from).collect());
+
+		Ok(Self {
+			id: data.id,
+			name: data.name,
+			description: data.description,
+			visibility,
+			created_at: data.created_at,
+			updated_at: data.updated_at,
+			items,
+			shared_with_users,
+		})
+	}
+}
@@ -0,0 +1,211 @@
+use std::{fmt, str::FromStr};
+
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	job::ScheduleTrigger,
+	prisma::{job_schedule, library},
+	CoreError, CoreResult,
+};
+
+use super::Library;
+
+/// The kinds of jobs which may be run on a schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type, ToSchema)]
+pub enum ScheduledJobKind {
+	#[serde(rename = "LIBRARY_SCAN")]
+	LibraryScan,
+	#[serde(rename = "THUMBNAIL_GENERATION")]
+	ThumbnailGeneration,
+	#[serde(rename = "MEDIA_ANALYSIS")]
+	MediaAnalysis,
+	#[serde(rename = "LOG_PRUNING")]
+	LogPruning,
+	#[serde(rename = "SESSION_CLEANUP")]
+	SessionCleanup,
+}
+
+impl ScheduledJobKind {
+	/// Whether the job kind operates on libraries, i.e. whether the include/exclude lists
+	/// of a schedule are relevant
+	pub fn targets_libraries(&self) -> bool {
+		matches!(
+			self,
+			ScheduledJobKind::LibraryScan
+				| ScheduledJobKind::ThumbnailGeneration
+				| ScheduledJobKind::MediaAnalysis
+		)
+	}
+}
+
+impl fmt::Display for ScheduledJobKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ScheduledJobKind::LibraryScan => write!(f, "LIBRARY_SCAN"),
+			ScheduledJobKind::ThumbnailGeneration => write!(f, "THUMBNAIL_GENERATION"),
+			ScheduledJobKind::MediaAnalysis => write!(f, "MEDIA_ANALYSIS"),
+			ScheduledJobKind::LogPruning => write!(f, "LOG_PRUNING"),
+			ScheduledJobKind::SessionCleanup => write!(f, "SESSION_CLEANUP"),
+		}
+	}
+}
+
+impl FromStr for ScheduledJobKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let uppercase = s.to_uppercase();
+
+		match uppercase.as_str() {
+			"LIBRARY_SCAN" => Ok(ScheduledJobKind::LibraryScan),
+			"THUMBNAIL_GENERATION" => Ok(ScheduledJobKind::ThumbnailGeneration),
+			"MEDIA_ANALYSIS" => Ok(ScheduledJobKind::MediaAnalysis),
+			"LOG_PRUNING" => Ok(ScheduledJobKind::LogPruning),
+			"SESSION_CLEANUP" => Ok(ScheduledJobKind::SessionCleanup),
+			_ => Err(format!("Invalid ScheduledJobKind: {s}")),
+		}
+	}
+}
+
+/// Optional, job-specific options for a schedule. Options which don't apply to the
+/// schedule's job kind are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type, ToSchema)]
+pub struct ScheduledJobConfig {
+	/// Whether existing thumbnails should be regenerated (`THUMBNAIL_GENERATION` only)
+	#[serde(default)]
+	pub force_regenerate: bool,
+	/// The number of days of job logs to keep (`LOG_PRUNING` only)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub retention_days: Option<u32>,
+}
+
+impl ScheduledJobConfig {
+	pub fn into_bytes(self) -> CoreResult<Vec<u8>> {
+		Ok(serde_json::to_vec(&self)?)
+	}
+}
+
+/// A named schedule which periodically enqueues a job of a given kind
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct JobSchedule {
+	pub id: String,
+	/// The human-readable name of the schedule. ex: "Nightly scan"
+	pub name: String,
+	/// The kind of job to run
+	pub job_kind: ScheduledJobKind,
+	/// The cron expression for the schedule, if any. ex: "0 3 * * *"
+	pub cron_expression: Option<String>,
+	/// The interval (in seconds) between runs, if no cron expression is set
+	pub interval_secs: Option<i32>,
+	/// The job-specific options for the schedule
+	pub config: ScheduledJobConfig,
+	/// Whether the schedule is paused. Paused schedules can still be triggered manually
+	pub is_paused: bool,
+	/// The last time the schedule was run
+	pub last_run_at: Option<DateTime<FixedOffset>>,
+	/// The next time the schedule is expected to run
+	pub next_run_at: Option<DateTime<FixedOffset>>,
+	pub created_at: DateTime<FixedOffset>,
+	/// The libraries the schedule is restricted to. If empty, all libraries are targeted.
+	/// Will be `None` only if the relation is not loaded.
+	#[schema(no_recursion)]
+	pub included_libraries: Option<Vec<Library>>,
+	/// The libraries the schedule skips. Will be `None` only if the relation is not loaded.
+	#[schema(no_recursion)]
+	pub excluded_libraries: Option<Vec<Library>>,
+}
+
+impl JobSchedule {
+	/// Get the [`ScheduleTrigger`] which determines when the schedule runs
+	pub fn trigger(&self) -> CoreResult<ScheduleTrigger> {
+		ScheduleTrigger::new(self.cron_expression.as_deref(), self.interval_secs)
+	}
+
+	/// Get the IDs of the included libraries, if the relation was loaded
+	pub fn included_library_ids(&self) -> Vec<String> {
+		self.included_libraries
+			.iter()
+			.flatten()
+			.map(|library| library.id.clone())
+			.collect()
+	}
+
+	/// Get the IDs of the excluded libraries, if the relation was loaded
+	pub fn excluded_library_ids(&self) -> Vec<String> {
+		self.excluded_libraries
+			.iter()
+			.flatten()
+			.map(|library| library.id.clone())
+			.collect()
+	}
+}
+
+fn libraries_from_data(
+	libraries: Result<&Vec<library::Data>, prisma_client_rust::RelationNotFetchedError>,
+) -> Option<Vec<Library>> {
+	libraries
+		.ok()
+		.map(|libraries| libraries.iter().cloned().map(Library::from).collect())
+}
+
+impl TryFrom<job_schedule::Data> for JobSchedule {
+	type Error = CoreError;
+
+	fn try_from(data: job_schedule::Data) -> Result<Self, Self::Error> {
+		let included_libraries = libraries_from_data(data.included_libraries());
+		let excluded_libraries = libraries_from_data(data.excluded_libraries());
+
+		let job_kind = ScheduledJobKind::from_str(&data.job_kind)
+			.map_err(CoreError::InternalError)?;
+		let config = data
+			.config
+			.as_deref()
+			.map(serde_json::from_slice)
+			.transpose()?
+			.unwrap_or_default();
+
+		Ok(Self {
+			id: data.id,
+			name: data.name,
+			job_kind,
+			cron_expression: data.cron_expression,
+			interval_secs: data.interval_secs,
+			config,
+			is_paused: data.is_paused,
+			last_run_at: data.last_run_at,
+			next_run_at: data.next_run_at,
+			created_at: data.created_at,
+			included_libraries,
+			excluded_libraries,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_scheduled_job_kind_round_trip() {
+		for kind in [
+			ScheduledJobKind::LibraryScan,
+			ScheduledJobKind::ThumbnailGeneration,
+			ScheduledJobKind::MediaAnalysis,
+			ScheduledJobKind::LogPruning,
+			ScheduledJobKind::SessionCleanup,
+		] {
+			assert_eq!(ScheduledJobKind::from_str(&kind.to_string()), Ok(kind));
+		}
+	}
+
+	#[test]
+	fn test_scheduled_job_config_defaults() {
+		let config: ScheduledJobConfig =
+			serde_json::from_str("{}").expect("Failed to deserialize config");
+		assert!(!config.force_regenerate);
+		assert!(config.retention_days.is_none());
+	}
+}
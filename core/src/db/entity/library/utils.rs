@@ -1,8 +1,22 @@
+use prisma_client_rust::operator;
+
 use crate::{
-	db::entity::User,
+	db::entity::{APIKeyScopes, User},
 	prisma::{library, user},
 };
 
 pub fn apply_library_not_hidden_from_user_filter(user: &User) -> library::WhereParam {
-	library::hidden_from_users::none(vec![user::id::equals(user.id.clone())])
+	let not_hidden =
+		library::hidden_from_users::none(vec![user::id::equals(user.id.clone())]);
+	let scope_params = user
+		.api_key_scopes
+		.as_ref()
+		.map(APIKeyScopes::library_params)
+		.unwrap_or_default();
+
+	if scope_params.is_empty() {
+		not_hidden
+	} else {
+		operator::and(std::iter::once(not_hidden).chain(scope_params).collect())
+	}
 }
@@ -1,30 +1,42 @@
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use utoipa::ToSchema;
 
 use crate::{db::entity::Media, prisma::media_annotation};
 
-#[derive(Debug, Clone, Deserialize, Serialize, Type, Default, ToSchema)]
+/// A user's annotation of a book, which may be a highlight of some text, a note, or both.
+/// Annotations of EPUBs are located by an epubcfi, while annotations of image-based books
+/// are located by a page and (optionally) coordinates on that page
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
 pub struct MediaAnnotation {
-	id: String,
+	pub id: String,
 	// The text that was highlighted, if any
-	highlighted_text: Option<String>,
+	pub highlighted_text: Option<String>,
 	/// The page number of the annotation. This is a 1-based index for image-based media
-	page: Option<i32>,
+	pub page: Option<i32>,
 	/// The x coordinate of the annotation on the page. This is a percentage of the page width
-	page_coordinates_x: Option<f64>,
+	pub page_coordinates_x: Option<f64>,
 	/// The y coordinate of the annotation on the page. This is a percentage of the page height
-	page_coordinates_y: Option<f64>,
+	pub page_coordinates_y: Option<f64>,
 	/// The epubcfi associated with the annotation. This can be a range or a single point,
 	/// where a range can be inferred as highlighted text
-	epubcfi: Option<String>,
+	pub epubcfi: Option<String>,
 	/// The user notes for the annotation. ex: "This is a note"
-	notes: Option<String>,
+	pub notes: Option<String>,
+	pub created_at: DateTime<FixedOffset>,
+	pub updated_at: DateTime<FixedOffset>,
+
+	/// The id of the user who made the annotation
+	pub user_id: String,
 	// The media this annotation belongs to
-	media_id: String,
+	pub media_id: String,
+	/// The annotated book. This relationship will always exist in the DB, however will not
+	/// always be returned in the API response
 	#[serde(skip_serializing_if = "Option::is_none")]
 	#[specta(optional)]
-	media: Option<Media>,
+	#[schema(no_recursion)]
+	pub media: Option<Media>,
 }
 
 impl From<media_annotation::Data> for MediaAnnotation {
@@ -42,6 +54,9 @@ impl From<media_annotation::Data> for MediaAnnotation {
 			page_coordinates_x: data.page_coordinates_x,
 			page_coordinates_y: data.page_coordinates_y,
 			notes: data.notes,
+			created_at: data.created_at,
+			updated_at: data.updated_at,
+			user_id: data.user_id,
 			media_id: data.media_id,
 			media,
 		}
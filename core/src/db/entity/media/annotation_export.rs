@@ -0,0 +1,333 @@
+//! Export of a user's annotations to formats which can be read outside of Stump: Markdown for
+//! note-taking apps, JSON, and the JSON format written by the KOReader exporter plugin.
+//!
+//! See https://github.com/koreader/koreader/tree/master/plugins/exporter.koplugin
+
+use std::collections::HashMap;
+
+use prisma_client_rust::chrono::Utc;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{db::entity::Media, CoreResult};
+
+use super::MediaAnnotation;
+
+/// The format to export annotations as
+#[derive(
+	Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, Type, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationExportFormat {
+	#[default]
+	Markdown,
+	Json,
+	Koreader,
+}
+
+impl AnnotationExportFormat {
+	pub fn content_type(&self) -> &'static str {
+		match self {
+			AnnotationExportFormat::Markdown => "text/markdown; charset=utf-8",
+			AnnotationExportFormat::Json | AnnotationExportFormat::Koreader => {
+				"application/json"
+			},
+		}
+	}
+
+	pub fn extension(&self) -> &'static str {
+		match self {
+			AnnotationExportFormat::Markdown => "md",
+			AnnotationExportFormat::Json | AnnotationExportFormat::Koreader => "json",
+		}
+	}
+}
+
+/// The annotations of a single book
+#[derive(Debug, Clone, Serialize, Type, ToSchema)]
+pub struct AnnotatedBook {
+	pub media_id: String,
+	pub title: String,
+	pub authors: Vec<String>,
+	pub pages: Option<i32>,
+	#[serde(skip)]
+	pub koreader_hash: Option<String>,
+	/// The annotations of the book, ordered by their position in the book
+	pub annotations: Vec<MediaAnnotation>,
+}
+
+impl AnnotatedBook {
+	fn new(media_id: String, media: Option<&Media>) -> Self {
+		let metadata = media.and_then(|media| media.metadata.as_ref());
+		let title = metadata
+			.and_then(|metadata| metadata.title.clone())
+			.or_else(|| media.map(|media| media.name.clone()))
+			.unwrap_or_else(|| media_id.clone());
+
+		Self {
+			media_id,
+			title,
+			authors: metadata
+				.and_then(|metadata| metadata.writers.clone())
+				.unwrap_or_default(),
+			pages: media.map(|media| media.pages),
+			koreader_hash: media.and_then(|media| media.koreader_hash.clone()),
+			annotations: Vec::new(),
+		}
+	}
+}
+
+/// Group annotations by the book they belong to. Books are kept in the order they first
+/// appear in, and the annotations of each book are ordered by page and then creation time.
+/// The `media` relation should be loaded in order to have the title and authors of each book
+pub fn group_annotations_by_book(
+	annotations: Vec<MediaAnnotation>,
+) -> Vec<AnnotatedBook> {
+	let mut books = Vec::<AnnotatedBook>::new();
+	let mut indices = HashMap::<String, usize>::new();
+
+	for mut annotation in annotations {
+		let media = annotation.media.take();
+		let index = *indices
+			.entry(annotation.media_id.clone())
+			.or_insert_with(|| {
+				books.push(AnnotatedBook::new(
+					annotation.media_id.clone(),
+					media.as_ref(),
+				));
+				books.len() - 1
+			});
+		books[index].annotations.push(annotation);
+	}
+
+	for book in books.iter_mut() {
+		book.annotations.sort_by(|a, b| {
+			a.page
+				.unwrap_or_default()
+				.cmp(&b.page.unwrap_or_default())
+				.then(a.created_at.cmp(&b.created_at))
+		});
+	}
+
+	books
+}
+
+/// Export annotations in the given format
+pub fn export_annotations(
+	annotations: Vec<MediaAnnotation>,
+	format: AnnotationExportFormat,
+) -> CoreResult<String> {
+	let books = group_annotations_by_book(annotations);
+
+	match format {
+		AnnotationExportFormat::Markdown => Ok(to_markdown(&books)),
+		AnnotationExportFormat::Json => Ok(serde_json::to_string_pretty(&books)?),
+		AnnotationExportFormat::Koreader => {
+			Ok(serde_json::to_string_pretty(&KoreaderExport::from(books))?)
+		},
+	}
+}
+
+fn to_markdown(books: &[AnnotatedBook]) -> String {
+	let mut output = String::from("# Annotations\n");
+
+	for book in books {
+		output.push_str(&format!("\n## {}\n", book.title));
+		if !book.authors.is_empty() {
+			output.push_str(&format!("\n*{}*\n", book.authors.join(", ")));
+		}
+
+		for annotation in &book.annotations {
+			output.push('\n');
+
+			if let Some(text) = annotation.highlighted_text.as_deref() {
+				for line in text.trim().lines().map(str::trim) {
+					if line.is_empty() {
+						output.push_str(">\n");
+					} else {
+						output.push_str(&format!("> {line}\n"));
+					}
+				}
+				output.push('\n');
+			}
+
+			if let Some(notes) = annotation.notes.as_deref() {
+				output.push_str(&format!("{}\n\n", notes.trim()));
+			}
+
+			let date = annotation.created_at.format("%Y-%m-%d");
+			match annotation.page {
+				Some(page) => {
+					output.push_str(&format!("<sub>Page {page} · {date}</sub>\n"))
+				},
+				None => output.push_str(&format!("<sub>{date}</sub>\n")),
+			}
+		}
+	}
+
+	output
+}
+
+/// The document written by the KOReader exporter plugin when exporting the highlights of
+/// several books to JSON
+#[derive(Debug, Serialize)]
+struct KoreaderExport {
+	created_on: i64,
+	version: String,
+	documents: Vec<KoreaderDocument>,
+}
+
+#[derive(Debug, Serialize)]
+struct KoreaderDocument {
+	title: String,
+	author: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	md5sum: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	number_of_pages: Option<i32>,
+	entries: Vec<KoreaderEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct KoreaderEntry {
+	page: i32,
+	time: i64,
+	sort: &'static str,
+	drawer: &'static str,
+	text: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	note: Option<String>,
+}
+
+impl From<Vec<AnnotatedBook>> for KoreaderExport {
+	fn from(books: Vec<AnnotatedBook>) -> Self {
+		let documents = books
+			.into_iter()
+			.map(|book| KoreaderDocument {
+				title: book.title,
+				author: book.authors.join(", "),
+				md5sum: book.koreader_hash,
+				number_of_pages: book.pages,
+				entries: book
+					.annotations
+					.into_iter()
+					.map(|annotation| KoreaderEntry {
+						page: annotation.page.unwrap_or_default(),
+						time: annotation.created_at.timestamp(),
+						sort: "highlight",
+						drawer: "lighten",
+						text: annotation.highlighted_text.unwrap_or_default(),
+						note: annotation.notes,
+					})
+					.collect(),
+			})
+			.collect();
+
+		Self {
+			created_on: Utc::now().timestamp(),
+			version: format!("stump-{}", env!("CARGO_PKG_VERSION")),
+			documents,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use prisma_client_rust::chrono::{DateTime, FixedOffset};
+
+	use super::*;
+	use crate::db::entity::MediaMetadata;
+
+	fn annotation(
+		id: &str,
+		media: &Media,
+		page: Option<i32>,
+		created_at: &str,
+	) -> MediaAnnotation {
+		MediaAnnotation {
+			id: id.to_string(),
+			highlighted_text: Some(format!("Highlight {id}")),
+			page,
+			page_coordinates_x: None,
+			page_coordinates_y: None,
+			epubcfi: None,
+			notes: None,
+			created_at: DateTime::<FixedOffset>::parse_from_rfc3339(created_at).unwrap(),
+			updated_at: DateTime::<FixedOffset>::parse_from_rfc3339(created_at).unwrap(),
+			user_id: "user".to_string(),
+			media_id: media.id.clone(),
+			media: Some(media.clone()),
+		}
+	}
+
+	fn book(id: &str, title: &str) -> Media {
+		Media {
+			id: id.to_string(),
+			name: format!("{title}.epub"),
+			pages: 100,
+			metadata: Some(MediaMetadata {
+				title: Some(title.to_string()),
+				writers: Some(vec!["Jane Doe".to_string()]),
+				..Default::default()
+			}),
+			..Default::default()
+		}
+	}
+
+	fn annotations() -> Vec<MediaAnnotation> {
+		let first = book("1", "First Book");
+		let second = book("2", "Second Book");
+		vec![
+			annotation("a", &first, Some(10), "2025-01-02T00:00:00Z"),
+			annotation("b", &second, Some(1), "2025-01-01T00:00:00Z"),
+			annotation("c", &first, Some(2), "2025-01-03T00:00:00Z"),
+		]
+	}
+
+	#[test]
+	fn test_group_annotations_by_book() {
+		let books = group_annotations_by_book(annotations());
+
+		assert_eq!(books.len(), 2);
+		assert_eq!(books[0].title, "First Book");
+		assert_eq!(books[0].authors, vec!["Jane Doe".to_string()]);
+		let ids = books[0]
+			.annotations
+			.iter()
+			.map(|a| a.id.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(ids, vec!["c", "a"]);
+		assert!(books[0].annotations.iter().all(|a| a.media.is_none()));
+		assert_eq!(books[1].title, "Second Book");
+	}
+
+	#[test]
+	fn test_export_markdown() {
+		let output =
+			export_annotations(annotations(), AnnotationExportFormat::Markdown).unwrap();
+
+		assert!(output.starts_with("# Annotations\n"));
+		assert!(output.contains("## First Book\n\n*Jane Doe*\n"));
+		assert!(output.contains("> Highlight c\n"));
+		assert!(output.contains("<sub>Page 2 · 2025-01-03</sub>"));
+		assert!(
+			output.find("Highlight c").unwrap() < output.find("Highlight a").unwrap()
+		);
+	}
+
+	#[test]
+	fn test_export_koreader() {
+		let output =
+			export_annotations(annotations(), AnnotationExportFormat::Koreader).unwrap();
+		let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+		let documents = value["documents"].as_array().unwrap();
+		assert_eq!(documents.len(), 2);
+		assert_eq!(documents[0]["title"], "First Book");
+		assert_eq!(documents[0]["number_of_pages"], 100);
+		assert_eq!(documents[0]["entries"][0]["page"], 2);
+		assert_eq!(documents[0]["entries"][0]["text"], "Highlight c");
+		assert_eq!(documents[0]["entries"][0]["sort"], "highlight");
+	}
+}
@@ -0,0 +1,77 @@
+use std::{fmt, str::FromStr};
+
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::prisma::duplicate_cluster;
+
+use super::Media;
+
+/// Why the media in a [DuplicateCluster] are considered duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type, ToSchema)]
+pub enum DuplicateReason {
+	/// The files are byte-for-byte identical
+	#[serde(rename = "CHECKSUM")]
+	Checksum,
+	/// The covers of the files are visually similar, and the files have a similar size and
+	/// page count
+	#[serde(rename = "COVER_HASH")]
+	CoverHash,
+}
+
+impl fmt::Display for DuplicateReason {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DuplicateReason::Checksum => write!(f, "CHECKSUM"),
+			DuplicateReason::CoverHash => write!(f, "COVER_HASH"),
+		}
+	}
+}
+
+impl FromStr for DuplicateReason {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"CHECKSUM" => Ok(DuplicateReason::Checksum),
+			"COVER_HASH" => Ok(DuplicateReason::CoverHash),
+			_ => Err(()),
+		}
+	}
+}
+
+/// A group of media which are likely duplicates of one another, as found by the duplicate
+/// analysis job
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
+pub struct DuplicateCluster {
+	pub id: String,
+	/// How likely it is that the media are duplicates, from 0 to 1. A confidence of 1 means
+	/// the files are identical
+	pub confidence: f64,
+	pub reason: DuplicateReason,
+	pub created_at: DateTime<FixedOffset>,
+	/// The media in the cluster. Will be `None` only if the relation is not loaded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	pub media: Option<Vec<Media>>,
+}
+
+impl From<duplicate_cluster::Data> for DuplicateCluster {
+	fn from(data: duplicate_cluster::Data) -> Self {
+		let media = data
+			.media()
+			.ok()
+			.map(|media| media.iter().cloned().map(Media::from).collect());
+
+		Self {
+			id: data.id,
+			confidence: data.confidence,
+			reason: DuplicateReason::from_str(&data.reason)
+				.unwrap_or(DuplicateReason::CoverHash),
+			created_at: data.created_at,
+			media,
+		}
+	}
+}
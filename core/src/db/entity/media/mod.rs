@@ -1,11 +1,19 @@
 mod annotation;
+mod annotation_export;
 mod bookmark;
+mod duplicate_cluster;
 mod entity;
 pub(crate) mod prisma_macros;
 mod reading_session;
+mod reading_stats;
+mod review;
 pub(crate) mod utils;
 
 pub use annotation::*;
+pub use annotation_export::*;
 pub use bookmark::*;
+pub use duplicate_cluster::*;
 pub use entity::*;
 pub use reading_session::*;
+pub use reading_stats::*;
+pub use review::*;
@@ -1,4 +1,4 @@
-use crate::prisma::{active_reading_session, finished_reading_session, media};
+use crate::prisma::{active_reading_session, finished_reading_session, media, review};
 
 media::select!(media_id_select { id });
 
@@ -34,8 +34,19 @@ finished_reading_session::select!(finished_reading_session_series_complete {
    completed_at
 });
 
-active_reading_session::include!(reading_session_koreader { device });
+active_reading_session::include!(reading_session_koreader {
+	device
+	media: select { path extension }
+});
 
 finished_reading_session::include!(finished_session_koreader { device });
 
 active_reading_session::select!(active_reading_session_book_id { media_id });
+
+review::select!(review_rating_select {
+   rating
+   media: select {
+	  id
+	  series_id
+   }
+});
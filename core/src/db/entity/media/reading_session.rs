@@ -17,6 +17,8 @@ pub struct ActiveReadingSession {
 	pub epubcfi: Option<String>,
 	// The percentage completed
 	pub percentage_completed: Option<f64>,
+	/// The position in the book as a locator, for EPUBs which have been located
+	pub locator: Option<ReadingLocator>,
 	/// The number of seconds elapsed since the reading session was started.
 	/// This is not just a diff between `started_at` and `completed_at`
 	pub elapsed_seconds: Option<i64>,
@@ -58,6 +60,53 @@ pub struct FinishedReadingSession {
 	pub user: Option<User>,
 }
 
+/// A position within an EPUB, modeled after a Readium locator. It ties together the
+/// different ways a position is expressed by the web reader, OPDS clients and KOReader.
+///
+/// See https://readium.org/architecture/models/locators/
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema, Default, PartialEq)]
+pub struct ReadingLocator {
+	/// The path of the spine resource, relative to the root of the EPUB
+	pub href: String,
+	/// The progression within the resource, from 0.0 to 1.0
+	pub progression: f64,
+	/// The progression within the whole book, from 0.0 to 1.0
+	pub total_progression: f64,
+	/// The position as an epubcfi, used by the web reader
+	pub cfi: Option<String>,
+	/// The position as an x-pointer, used by KOReader
+	pub xpointer: Option<String>,
+}
+
+impl ReadingLocator {
+	fn from_session(
+		href: Option<String>,
+		progression: Option<f64>,
+		total_progression: Option<f64>,
+		cfi: Option<String>,
+		xpointer: Option<String>,
+	) -> Option<Self> {
+		href.map(|href| ReadingLocator {
+			href,
+			progression: progression.unwrap_or_default(),
+			total_progression: total_progression.unwrap_or_default(),
+			cfi,
+			xpointer,
+		})
+	}
+
+	/// The parameters to store the locator on an active reading session. The total
+	/// progression is left to the caller, since it is usually reported by the reader
+	pub fn into_session_params(self) -> Vec<active_reading_session::SetParam> {
+		vec![
+			active_reading_session::locator_href::set(Some(self.href)),
+			active_reading_session::locator_progression::set(Some(self.progression)),
+			active_reading_session::epubcfi::set(self.cfi),
+			active_reading_session::koreader_progress::set(self.xpointer),
+		]
+	}
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
 #[serde(untagged)]
 pub enum ProgressUpdateReturn {
@@ -73,6 +122,13 @@ impl From<active_reading_session::Data> for ActiveReadingSession {
 		};
 
 		let user = data.user().ok().cloned().map(User::from);
+		let locator = ReadingLocator::from_session(
+			data.locator_href,
+			data.locator_progression,
+			data.percentage_completed,
+			data.epubcfi.clone(),
+			data.koreader_progress,
+		);
 
 		ActiveReadingSession {
 			id: data.id,
@@ -81,6 +137,7 @@ impl From<active_reading_session::Data> for ActiveReadingSession {
 			started_at: data.started_at.to_rfc3339(),
 			elapsed_seconds: data.elapsed_seconds,
 			percentage_completed: data.percentage_completed,
+			locator,
 			media_id: data.media_id,
 			media,
 			user_id: data.user_id,
@@ -91,11 +148,20 @@ impl From<active_reading_session::Data> for ActiveReadingSession {
 
 impl From<reading_session_with_book_pages::Data> for ActiveReadingSession {
 	fn from(value: reading_session_with_book_pages::Data) -> Self {
+		let locator = ReadingLocator::from_session(
+			value.locator_href,
+			value.locator_progression,
+			value.percentage_completed,
+			value.epubcfi.clone(),
+			value.koreader_progress,
+		);
+
 		ActiveReadingSession {
 			id: value.id,
 			page: value.page,
 			epubcfi: value.epubcfi,
 			percentage_completed: value.percentage_completed,
+			locator,
 			elapsed_seconds: value.elapsed_seconds,
 			started_at: value.started_at.to_rfc3339(),
 			media_id: value.media_id,
@@ -0,0 +1,617 @@
+//! Aggregation of reading sessions into statistics, e.g. how many books and pages a user read
+//! each day, their reading streaks and their most read genres. Sessions are reduced to a
+//! [ReadingStatsSession] first, so the same aggregation can be used for a single user, a year
+//! in review or the entire server.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use prisma_client_rust::chrono::{
+	DateTime, Datelike, Duration, FixedOffset, NaiveDate, Utc,
+};
+use serde::Serialize;
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::MediaMetadata,
+	prisma::{active_reading_session, finished_reading_session, media},
+};
+
+/// The number of entries returned in each of the top genres, writers and publishers
+pub const TOP_STATS_LIMIT: usize = 10;
+
+/// A reading session reduced to the information needed to compute statistics. A session is
+/// either finished, in which case the entire book counts as read, or active, in which case
+/// only the pages up to the current page count
+#[derive(Debug, Clone)]
+pub struct ReadingStatsSession {
+	pub media_id: String,
+	pub title: String,
+	pub pages: i32,
+	pub user_id: String,
+	/// The username of the reader. Will be `None` if the user relation is not loaded
+	pub username: Option<String>,
+	pub started_at: DateTime<FixedOffset>,
+	/// When the book was finished, or `None` if it is still being read
+	pub completed_at: Option<DateTime<FixedOffset>>,
+	/// When the book was last read. This is the completion time for finished sessions
+	pub last_read_at: DateTime<FixedOffset>,
+	pub pages_read: i64,
+	pub seconds_read: i64,
+	pub device_id: Option<String>,
+	pub device_name: Option<String>,
+	pub genres: Vec<String>,
+	pub writers: Vec<String>,
+	pub publisher: Option<String>,
+}
+
+impl ReadingStatsSession {
+	fn new(
+		media: Option<&media::Data>,
+		media_id: String,
+		user_id: String,
+		started_at: DateTime<FixedOffset>,
+	) -> Self {
+		let metadata = media
+			.and_then(|media| media.metadata().ok().flatten())
+			.map(|metadata| MediaMetadata::from(metadata.to_owned()))
+			.unwrap_or_default();
+		let title = metadata
+			.title
+			.clone()
+			.or_else(|| media.map(|media| media.name.clone()))
+			.unwrap_or_else(|| media_id.clone());
+
+		Self {
+			title,
+			pages: media.map(|media| media.pages).unwrap_or_default(),
+			media_id,
+			user_id,
+			username: None,
+			started_at,
+			completed_at: None,
+			last_read_at: started_at,
+			pages_read: 0,
+			seconds_read: 0,
+			device_id: None,
+			device_name: None,
+			genres: metadata.genre.unwrap_or_default(),
+			writers: metadata.writers.unwrap_or_default(),
+			publisher: metadata.publisher,
+		}
+	}
+}
+
+/// Convert a finished session. The `media` (with its `metadata`), `device` and `user`
+/// relations should be loaded in order to have complete statistics
+impl From<finished_reading_session::Data> for ReadingStatsSession {
+	fn from(data: finished_reading_session::Data) -> Self {
+		let mut session = Self::new(
+			data.media().ok(),
+			data.media_id.clone(),
+			data.user_id.clone(),
+			data.started_at,
+		);
+		session.username = data.user().ok().map(|user| user.username.clone());
+		session.completed_at = Some(data.completed_at);
+		session.last_read_at = data.completed_at;
+		session.pages_read = i64::from(session.pages);
+		session.seconds_read = data.elapsed_seconds.unwrap_or_default();
+		if let Some(device) = data.device().ok().flatten() {
+			session.device_id = Some(device.id.clone());
+			session.device_name = Some(device.name.clone());
+		}
+		session
+	}
+}
+
+/// Convert an active session. The `media` (with its `metadata`), `device` and `user`
+/// relations should be loaded in order to have complete statistics
+impl From<active_reading_session::Data> for ReadingStatsSession {
+	fn from(data: active_reading_session::Data) -> Self {
+		let mut session = Self::new(
+			data.media().ok(),
+			data.media_id.clone(),
+			data.user_id.clone(),
+			data.started_at,
+		);
+		session.username = data.user().ok().map(|user| user.username.clone());
+		session.last_read_at = data.updated_at;
+		session.pages_read = match (data.page, data.percentage_completed) {
+			(Some(page), _) => i64::from(page),
+			(None, Some(percentage)) => {
+				(percentage.clamp(0.0, 1.0) * f64::from(session.pages)).round() as i64
+			},
+			_ => 0,
+		};
+		session.seconds_read = data.elapsed_seconds.unwrap_or_default();
+		if let Some(device) = data.device().ok().flatten() {
+			session.device_id = Some(device.id.clone());
+			session.device_name = Some(device.name.clone());
+		}
+		session
+	}
+}
+
+/// The reading activity within a single day, week or month
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type, ToSchema)]
+pub struct ReadingActivity {
+	/// The period the activity covers, formatted as `2025-04-20` for days, `2025-W16` for
+	/// (ISO) weeks and `2025-04` for months
+	pub period: String,
+	pub books_completed: u64,
+	pub pages_read: i64,
+	pub seconds_read: i64,
+}
+
+/// The number of books completed for a genre, writer or publisher
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type, ToSchema)]
+pub struct ReadingStatsCount {
+	pub name: String,
+	pub books_completed: u64,
+}
+
+/// The reading activity on a single device. Sessions which weren't recorded from a registered
+/// device (e.g. those from the web reader) are grouped under a `None` device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type, ToSchema)]
+pub struct DeviceReadingStats {
+	pub device_id: Option<String>,
+	pub device_name: Option<String>,
+	pub books_completed: u64,
+	pub pages_read: i64,
+	pub seconds_read: i64,
+}
+
+/// Statistics about reading activity. Activity is attributed to the day a book was finished,
+/// or the day it was last read for books which are still in progress
+#[derive(Debug, Clone, Default, Serialize, Type, ToSchema)]
+pub struct ReadingStats {
+	pub books_completed: u64,
+	pub books_in_progress: u64,
+	pub pages_read: i64,
+	pub seconds_read: i64,
+	/// The number of consecutive days, up to and including today (or yesterday, if nothing
+	/// has been read yet today), with any reading activity
+	pub current_streak_days: u32,
+	/// The most consecutive days with any reading activity
+	pub longest_streak_days: u32,
+	pub daily: Vec<ReadingActivity>,
+	pub weekly: Vec<ReadingActivity>,
+	pub monthly: Vec<ReadingActivity>,
+	/// The genres with the most books completed
+	pub top_genres: Vec<ReadingStatsCount>,
+	/// The writers with the most books completed
+	pub top_writers: Vec<ReadingStatsCount>,
+	/// The publishers with the most books completed
+	pub top_publishers: Vec<ReadingStatsCount>,
+	pub devices: Vec<DeviceReadingStats>,
+}
+
+impl ReadingStats {
+	/// Compute statistics for the given sessions. Dates are bucketed in the given timezone,
+	/// and `today` is used to determine whether the current streak is still going
+	pub fn compute(
+		sessions: &[ReadingStatsSession],
+		timezone: FixedOffset,
+		today: NaiveDate,
+	) -> Self {
+		let mut stats = ReadingStats::default();
+
+		let mut daily = BTreeMap::<String, ReadingActivity>::new();
+		let mut weekly = BTreeMap::<String, ReadingActivity>::new();
+		let mut monthly = BTreeMap::<String, ReadingActivity>::new();
+		let mut active_days = BTreeSet::<NaiveDate>::new();
+		let mut devices = BTreeMap::<Option<String>, DeviceReadingStats>::new();
+
+		for session in sessions {
+			let completed = u64::from(session.completed_at.is_some());
+			stats.books_completed += completed;
+			stats.books_in_progress += 1 - completed;
+			stats.pages_read += session.pages_read;
+			stats.seconds_read += session.seconds_read;
+
+			let date = session.last_read_at.with_timezone(&timezone).date_naive();
+			active_days.insert(date);
+			active_days.insert(session.started_at.with_timezone(&timezone).date_naive());
+
+			let week = date.iso_week();
+			let periods = [
+				(&mut daily, date.format("%Y-%m-%d").to_string()),
+				(&mut weekly, format!("{}-W{:02}", week.year(), week.week())),
+				(&mut monthly, date.format("%Y-%m").to_string()),
+			];
+			for (buckets, period) in periods {
+				let activity =
+					buckets
+						.entry(period.clone())
+						.or_insert_with(|| ReadingActivity {
+							period,
+							..Default::default()
+						});
+				activity.books_completed += completed;
+				activity.pages_read += session.pages_read;
+				activity.seconds_read += session.seconds_read;
+			}
+
+			let device = devices.entry(session.device_id.clone()).or_insert_with(|| {
+				DeviceReadingStats {
+					device_id: session.device_id.clone(),
+					device_name: session.device_name.clone(),
+					..Default::default()
+				}
+			});
+			device.books_completed += completed;
+			device.pages_read += session.pages_read;
+			device.seconds_read += session.seconds_read;
+		}
+
+		let (current_streak_days, longest_streak_days) = streaks(&active_days, today);
+		stats.current_streak_days = current_streak_days;
+		stats.longest_streak_days = longest_streak_days;
+		stats.daily = daily.into_values().collect();
+		stats.weekly = weekly.into_values().collect();
+		stats.monthly = monthly.into_values().collect();
+		stats.top_genres = top_counts(sessions, |session| session.genres.clone());
+		stats.top_writers = top_counts(sessions, |session| session.writers.clone());
+		stats.top_publishers = top_counts(sessions, |session| {
+			session.publisher.iter().cloned().collect()
+		});
+		stats.devices = devices.into_values().collect();
+
+		stats
+	}
+}
+
+/// Compute the current and longest streaks of consecutive days
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+	let mut longest = 0;
+	let mut current = 0;
+	let mut previous: Option<NaiveDate> = None;
+
+	for day in days.iter().copied().filter(|day| *day <= today) {
+		current = match previous {
+			Some(previous) if day - previous == Duration::days(1) => current + 1,
+			_ => 1,
+		};
+		longest = longest.max(current);
+		previous = Some(day);
+	}
+
+	let is_ongoing = previous.is_some_and(|last| today - last <= Duration::days(1));
+	(if is_ongoing { current } else { 0 }, longest)
+}
+
+/// Count the distinct completed books for each value returned by `values`, returning the
+/// [TOP_STATS_LIMIT] most common values
+fn top_counts(
+	sessions: &[ReadingStatsSession],
+	values: impl Fn(&ReadingStatsSession) -> Vec<String>,
+) -> Vec<ReadingStatsCount> {
+	let mut books = HashMap::<String, HashSet<&str>>::new();
+	for session in sessions.iter().filter(|s| s.completed_at.is_some()) {
+		for value in values(session) {
+			let value = value.trim();
+			if !value.is_empty() {
+				books
+					.entry(value.to_string())
+					.or_default()
+					.insert(session.media_id.as_str());
+			}
+		}
+	}
+
+	let mut counts = books
+		.into_iter()
+		.map(|(name, books)| ReadingStatsCount {
+			name,
+			books_completed: books.len() as u64,
+		})
+		.collect::<Vec<_>>();
+	counts.sort_by(|a, b| {
+		b.books_completed
+			.cmp(&a.books_completed)
+			.then_with(|| a.name.cmp(&b.name))
+	});
+	counts.truncate(TOP_STATS_LIMIT);
+	counts
+}
+
+/// A book which stood out in a [YearInReview]
+#[derive(Debug, Clone, PartialEq, Serialize, Type, ToSchema)]
+pub struct ReadingStatsBook {
+	pub media_id: String,
+	pub title: String,
+	pub pages: i32,
+	pub completed_at: DateTime<FixedOffset>,
+}
+
+impl From<&ReadingStatsSession> for ReadingStatsBook {
+	fn from(session: &ReadingStatsSession) -> Self {
+		Self {
+			media_id: session.media_id.clone(),
+			title: session.title.clone(),
+			pages: session.pages,
+			completed_at: session.completed_at.unwrap_or(session.last_read_at),
+		}
+	}
+}
+
+/// A summary of a single year of reading
+#[derive(Debug, Clone, Serialize, Type, ToSchema)]
+pub struct YearInReview {
+	pub year: i32,
+	pub books_completed: u64,
+	pub pages_read: i64,
+	pub seconds_read: i64,
+	pub longest_streak_days: u32,
+	/// The month with the most books completed
+	pub busiest_month: Option<ReadingActivity>,
+	pub monthly: Vec<ReadingActivity>,
+	pub top_genres: Vec<ReadingStatsCount>,
+	pub top_writers: Vec<ReadingStatsCount>,
+	pub top_publishers: Vec<ReadingStatsCount>,
+	/// The first book completed in the year
+	pub first_book: Option<ReadingStatsBook>,
+	/// The last book completed in the year
+	pub last_book: Option<ReadingStatsBook>,
+	/// The completed book with the most pages
+	pub longest_book: Option<ReadingStatsBook>,
+}
+
+impl YearInReview {
+	/// Summarize the sessions which were last read during `year` in the given timezone
+	pub fn compute(
+		sessions: &[ReadingStatsSession],
+		year: i32,
+		timezone: FixedOffset,
+	) -> Self {
+		let sessions = sessions
+			.iter()
+			.filter(|s| s.last_read_at.with_timezone(&timezone).year() == year)
+			.cloned()
+			.collect::<Vec<_>>();
+		let end_of_year = NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or_default();
+		let stats = ReadingStats::compute(&sessions, timezone, end_of_year);
+
+		let mut completed = sessions
+			.iter()
+			.filter(|s| s.completed_at.is_some())
+			.collect::<Vec<_>>();
+		completed.sort_by_key(|s| s.last_read_at);
+
+		let busiest_month = stats
+			.monthly
+			.iter()
+			.filter(|m| m.books_completed > 0)
+			.max_by(|a, b| {
+				a.books_completed
+					.cmp(&b.books_completed)
+					.then(a.pages_read.cmp(&b.pages_read))
+					.then(b.period.cmp(&a.period))
+			})
+			.cloned();
+
+		Self {
+			year,
+			books_completed: stats.books_completed,
+			pages_read: stats.pages_read,
+			seconds_read: stats.seconds_read,
+			longest_streak_days: stats.longest_streak_days,
+			busiest_month,
+			first_book: completed.first().copied().map(ReadingStatsBook::from),
+			last_book: completed.last().copied().map(ReadingStatsBook::from),
+			longest_book: completed
+				.iter()
+				.copied()
+				.max_by_key(|s| s.pages)
+				.map(ReadingStatsBook::from),
+			monthly: stats.monthly,
+			top_genres: stats.top_genres,
+			top_writers: stats.top_writers,
+			top_publishers: stats.top_publishers,
+		}
+	}
+}
+
+/// The reading activity of a single user, used in the server-wide statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Type, ToSchema)]
+pub struct UserReadingSummary {
+	pub user_id: String,
+	pub username: Option<String>,
+	pub books_completed: u64,
+	pub books_in_progress: u64,
+	pub pages_read: i64,
+	pub seconds_read: i64,
+	pub last_read_at: Option<DateTime<FixedOffset>>,
+}
+
+/// Reading statistics across every user on the server
+#[derive(Debug, Clone, Serialize, Type, ToSchema)]
+pub struct ServerReadingStats {
+	pub stats: ReadingStats,
+	/// The activity of each user who has read anything, ordered by most books completed
+	pub users: Vec<UserReadingSummary>,
+}
+
+impl ServerReadingStats {
+	pub fn compute(sessions: &[ReadingStatsSession], timezone: FixedOffset) -> Self {
+		let mut users = HashMap::<&str, UserReadingSummary>::new();
+		for session in sessions {
+			let summary = users.entry(session.user_id.as_str()).or_insert_with(|| {
+				UserReadingSummary {
+					user_id: session.user_id.clone(),
+					username: session.username.clone(),
+					..Default::default()
+				}
+			});
+			match session.completed_at {
+				Some(_) => summary.books_completed += 1,
+				None => summary.books_in_progress += 1,
+			}
+			summary.pages_read += session.pages_read;
+			summary.seconds_read += session.seconds_read;
+			summary.last_read_at = summary.last_read_at.max(Some(session.last_read_at));
+		}
+
+		let mut users = users.into_values().collect::<Vec<_>>();
+		users.sort_by(|a, b| {
+			b.books_completed
+				.cmp(&a.books_completed)
+				.then_with(|| b.pages_read.cmp(&a.pages_read))
+				.then_with(|| a.user_id.cmp(&b.user_id))
+		});
+
+		let today = Utc::now().with_timezone(&timezone).date_naive();
+		Self {
+			stats: ReadingStats::compute(sessions, timezone, today),
+			users,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn utc() -> FixedOffset {
+		FixedOffset::east_opt(0).unwrap()
+	}
+
+	fn date(value: &str) -> DateTime<FixedOffset> {
+		DateTime::parse_from_rfc3339(value).unwrap()
+	}
+
+	fn session(
+		media_id: &str,
+		last_read_at: &str,
+		completed: bool,
+		genres: &[&str],
+	) -> ReadingStatsSession {
+		let last_read_at = date(last_read_at);
+		ReadingStatsSession {
+			media_id: media_id.to_string(),
+			title: format!("Book {media_id}"),
+			pages: 100,
+			user_id: "user".to_string(),
+			username: Some("reader".to_string()),
+			started_at: last_read_at,
+			completed_at: completed.then_some(last_read_at),
+			last_read_at,
+			pages_read: if completed { 100 } else { 40 },
+			seconds_read: 600,
+			device_id: None,
+			device_name: None,
+			genres: genres.iter().map(|g| g.to_string()).collect(),
+			writers: vec![],
+			publisher: Some("Image".to_string()),
+		}
+	}
+
+	#[test]
+	fn test_compute_reading_stats() {
+		let sessions = vec![
+			session("1", "2025-04-18T10:00:00Z", true, &["Sci-Fi", "Horror"]),
+			session("2", "2025-04-19T10:00:00Z", true, &["Sci-Fi"]),
+			session("3", "2025-04-20T10:00:00Z", false, &["Fantasy"]),
+			session("4", "2025-03-01T10:00:00Z", true, &["Horror"]),
+		];
+		let today = NaiveDate::from_ymd_opt(2025, 4, 20).unwrap();
+		let stats = ReadingStats::compute(&sessions, utc(), today);
+
+		assert_eq!(stats.books_completed, 3);
+		assert_eq!(stats.books_in_progress, 1);
+		assert_eq!(stats.pages_read, 340);
+		assert_eq!(stats.seconds_read, 2400);
+		assert_eq!(stats.current_streak_days, 3);
+		assert_eq!(stats.longest_streak_days, 3);
+		assert_eq!(stats.daily.len(), 4);
+		assert_eq!(stats.daily[0].period, "2025-03-01");
+		assert_eq!(stats.weekly.last().unwrap().period, "2025-W16");
+		assert_eq!(stats.monthly.len(), 2);
+		assert_eq!(stats.monthly[1].books_completed, 2);
+		assert_eq!(
+			stats.top_genres,
+			vec![
+				ReadingStatsCount {
+					name: "Horror".to_string(),
+					books_completed: 2,
+				},
+				ReadingStatsCount {
+					name: "Sci-Fi".to_string(),
+					books_completed: 2,
+				},
+			]
+		);
+		assert_eq!(stats.top_publishers[0].books_completed, 3);
+		assert_eq!(stats.devices.len(), 1);
+	}
+
+	#[test]
+	fn test_streak_is_broken() {
+		let days = [
+			NaiveDate::from_ymd_opt(2025, 4, 1).unwrap(),
+			NaiveDate::from_ymd_opt(2025, 4, 2).unwrap(),
+			NaiveDate::from_ymd_opt(2025, 4, 10).unwrap(),
+		]
+		.into_iter()
+		.collect::<BTreeSet<_>>();
+
+		let yesterday = NaiveDate::from_ymd_opt(2025, 4, 11).unwrap();
+		assert_eq!(streaks(&days, yesterday), (1, 2));
+		let later = NaiveDate::from_ymd_opt(2025, 4, 12).unwrap();
+		assert_eq!(streaks(&days, later), (0, 2));
+	}
+
+	#[test]
+	fn test_timezone_shifts_days() {
+		let sessions = vec![session("1", "2025-04-18T23:30:00Z", true, &[])];
+		let timezone = FixedOffset::east_opt(2 * 3600).unwrap();
+		let today = NaiveDate::from_ymd_opt(2025, 4, 19).unwrap();
+		let stats = ReadingStats::compute(&sessions, timezone, today);
+
+		assert_eq!(stats.daily[0].period, "2025-04-19");
+	}
+
+	#[test]
+	fn test_year_in_review() {
+		let mut long_book = session("3", "2025-06-01T10:00:00Z", true, &[]);
+		long_book.pages = 500;
+		let sessions = vec![
+			session("1", "2024-12-31T10:00:00Z", true, &[]),
+			session("2", "2025-02-01T10:00:00Z", true, &[]),
+			long_book,
+			session("4", "2025-06-15T10:00:00Z", true, &[]),
+			session("5", "2025-07-01T10:00:00Z", false, &[]),
+		];
+		let review = YearInReview::compute(&sessions, 2025, utc());
+
+		assert_eq!(review.books_completed, 3);
+		assert_eq!(review.busiest_month.unwrap().period, "2025-06");
+		assert_eq!(review.first_book.unwrap().media_id, "2");
+		assert_eq!(review.last_book.unwrap().media_id, "4");
+		assert_eq!(review.longest_book.unwrap().media_id, "3");
+	}
+
+	#[test]
+	fn test_server_reading_stats() {
+		let mut other = session("2", "2025-04-19T10:00:00Z", true, &[]);
+		other.user_id = "other".to_string();
+		let sessions = vec![
+			session("1", "2025-04-18T10:00:00Z", false, &[]),
+			other,
+			session("3", "2025-04-20T10:00:00Z", true, &[]),
+		];
+		let stats = ServerReadingStats::compute(&sessions, utc());
+
+		assert_eq!(stats.stats.books_completed, 2);
+		assert_eq!(stats.users.len(), 2);
+		assert_eq!(stats.users[0].user_id, "user");
+		assert_eq!(stats.users[0].books_completed, 1);
+		assert_eq!(stats.users[0].books_in_progress, 1);
+		assert_eq!(
+			stats.users[0].last_read_at,
+			Some(date("2025-04-20T10:00:00Z"))
+		);
+		assert_eq!(stats.users[1].user_id, "other");
+	}
+}
@@ -0,0 +1,148 @@
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{Media, PartialUser},
+	prisma::review,
+};
+
+/// The lowest rating a review may have
+pub const MIN_REVIEW_RATING: i32 = 1;
+/// The highest rating a review may have. Ratings are out of 10, which allows clients to
+/// render a 5-star scale with half-star precision
+pub const MAX_REVIEW_RATING: i32 = 10;
+
+/// Whether the given rating is within the accepted bounds for a review
+pub fn is_valid_review_rating(rating: i32) -> bool {
+	(MIN_REVIEW_RATING..=MAX_REVIEW_RATING).contains(&rating)
+}
+
+/// A user's review of a book, consisting of a required rating and optional written content
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
+pub struct Review {
+	pub id: String,
+	/// The rating of the book, between 1 and 10 (inclusive)
+	pub rating: i32,
+	/// The written review, if any
+	pub content: Option<String>,
+	/// Whether the review is only visible to the user who wrote it
+	pub is_private: bool,
+	pub created_at: DateTime<FixedOffset>,
+	pub updated_at: DateTime<FixedOffset>,
+
+	/// The id of the reviewed book
+	pub media_id: String,
+	/// The reviewed book. This relationship will always exist in the DB, however will not
+	/// always be returned in the API response
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	#[schema(no_recursion)]
+	pub media: Option<Media>,
+
+	/// The id of the user who wrote the review
+	pub user_id: String,
+	/// The user who wrote the review
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	pub user: Option<PartialUser>,
+}
+
+impl From<review::Data> for Review {
+	fn from(data: review::Data) -> Self {
+		let media = data.media().map(|media| Media::from(media.to_owned())).ok();
+		let user = data
+			.user()
+			.map(|user| PartialUser::from(user.to_owned()))
+			.ok();
+
+		Self {
+			id: data.id,
+			rating: data.rating,
+			content: data.content,
+			is_private: data.is_private,
+			created_at: data.created_at,
+			updated_at: data.updated_at,
+			media_id: data.media_id,
+			media,
+			user_id: data.user_id,
+			user,
+		}
+	}
+}
+
+/// The number of reviews with a specific rating
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Type, ToSchema)]
+pub struct ReviewRatingCount {
+	pub rating: i32,
+	pub count: i64,
+}
+
+/// Aggregate rating statistics for a set of reviews, e.g. all reviews of a book or of the
+/// books in a series
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Type, ToSchema)]
+pub struct ReviewStats {
+	/// The number of reviews which were considered
+	pub review_count: i64,
+	/// The mean rating of the reviews, if there are any
+	pub average_rating: Option<f64>,
+	/// The number of reviews for each possible rating, ordered from lowest to highest
+	pub distribution: Vec<ReviewRatingCount>,
+}
+
+impl ReviewStats {
+	/// Compute the statistics for the given ratings. Ratings outside of the accepted bounds
+	/// are ignored
+	pub fn from_ratings(ratings: impl IntoIterator<Item = i32>) -> Self {
+		let mut distribution = (MIN_REVIEW_RATING..=MAX_REVIEW_RATING)
+			.map(|rating| ReviewRatingCount { rating, count: 0 })
+			.collect::<Vec<_>>();
+
+		let mut review_count = 0;
+		let mut rating_sum = 0;
+		for rating in ratings.into_iter().filter(|r| is_valid_review_rating(*r)) {
+			distribution[(rating - MIN_REVIEW_RATING) as usize].count += 1;
+			review_count += 1;
+			rating_sum += i64::from(rating);
+		}
+
+		let average_rating =
+			(review_count > 0).then(|| rating_sum as f64 / review_count as f64);
+
+		Self {
+			review_count,
+			average_rating,
+			distribution,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_review_stats_empty() {
+		let stats = ReviewStats::from_ratings(vec![]);
+		assert_eq!(stats.review_count, 0);
+		assert!(stats.average_rating.is_none());
+		assert_eq!(stats.distribution.len(), MAX_REVIEW_RATING as usize);
+		assert!(stats.distribution.iter().all(|c| c.count == 0));
+	}
+
+	#[test]
+	fn test_review_stats_from_ratings() {
+		let stats = ReviewStats::from_ratings(vec![10, 8, 8, 5, 0, 11]);
+		assert_eq!(stats.review_count, 4);
+		assert_eq!(stats.average_rating, Some(7.75));
+		assert_eq!(
+			stats.distribution[7],
+			ReviewRatingCount {
+				rating: 8,
+				count: 2
+			}
+		);
+		assert_eq!(stats.distribution[0].count, 0);
+	}
+}
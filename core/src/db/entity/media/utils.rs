@@ -1,5 +1,5 @@
 use crate::{
-	db::entity::{utils::apply_library_not_hidden_from_user_filter, User},
+	db::entity::{utils::apply_library_not_hidden_from_user_filter, APIKeyScopes, User},
 	prisma::{
 		media::{self, WhereParam},
 		media_metadata, series, series_metadata,
@@ -59,7 +59,17 @@ pub fn apply_media_age_restriction(min_age: i32, restrict_on_unset: bool) -> Whe
 }
 
 pub fn apply_media_library_not_hidden_for_user_filter(user: &User) -> Vec<WhereParam> {
-	vec![media::series::is(vec![series::library::is(vec![
-		apply_library_not_hidden_from_user_filter(user),
-	])])]
+	let series_scope_params = user
+		.api_key_scopes
+		.as_ref()
+		.map(APIKeyScopes::series_params)
+		.unwrap_or_default();
+
+	vec![media::series::is(
+		std::iter::once(series::library::is(vec![
+			apply_library_not_hidden_from_user_filter(user),
+		]))
+		.chain(series_scope_params)
+		.collect(),
+	)]
 }
@@ -140,6 +140,9 @@ pub struct MediaMetadata {
 	/// The publisher of the associated media
 	#[serde(alias = "Publisher", skip_serializing_if = "Option::is_none")]
 	pub publisher: Option<String>,
+	/// The language of the associated media, typically an ISO 639 code (e.g. `en`)
+	#[serde(alias = "LanguageISO", skip_serializing_if = "Option::is_none")]
+	pub language: Option<String>,
 
 	/// Link(s) to the associated media, e.g. a comixology link
 	#[serde(
@@ -165,6 +168,13 @@ pub struct MediaMetadata {
 		skip_serializing_if = "Option::is_none"
 	)]
 	pub teams: Option<Vec<String>>,
+	/// Identifiers of the associated media, in the form `scheme:value`. ex: `isbn:9780765326355`
+	#[serde(
+		deserialize_with = "string_list_deserializer",
+		default = "Option::default",
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub identifiers: Option<Vec<String>>,
 
 	/// The number of pages in the associated media. This does *not* take priority over
 	/// the number of pages detected by the file processor.
@@ -201,9 +211,11 @@ impl MediaMetadata {
 			media_metadata::cover_artists::set(self.cover_artists.map(|v| v.join(", "))),
 			media_metadata::editors::set(self.editors.map(|v| v.join(", "))),
 			media_metadata::publisher::set(self.publisher),
+			media_metadata::language::set(self.language),
 			media_metadata::links::set(self.links.map(|v| v.join(", "))),
 			media_metadata::characters::set(self.characters.map(|v| v.join(", "))),
 			media_metadata::teams::set(self.teams.map(|v| v.join(", "))),
+			media_metadata::identifiers::set(self.identifiers.map(|v| v.join(", "))),
 			media_metadata::page_count::set(self.page_count),
 		]
 	}
@@ -237,9 +249,11 @@ impl From<media_metadata::Data> for MediaMetadata {
 			cover_artists: metadata.cover_artists.map(comma_separated_list_to_vec),
 			editors: metadata.editors.map(comma_separated_list_to_vec),
 			publisher: metadata.publisher,
+			language: metadata.language,
 			links: metadata.links.map(comma_separated_list_to_vec),
 			characters: metadata.characters.map(comma_separated_list_to_vec),
 			teams: metadata.teams.map(comma_separated_list_to_vec),
+			identifiers: metadata.identifiers.map(comma_separated_list_to_vec),
 			page_count: metadata.page_count,
 			page_dimensions,
 		}
@@ -286,6 +300,7 @@ impl From<HashMap<String, Vec<String>>> for MediaMetadata {
 				"links" => metadata.links = Some(value),
 				"characters" => metadata.characters = Some(value),
 				"teams" => metadata.teams = Some(value),
+				"language" => metadata.language = value.into_iter().next(),
 				"pagecount" => {
 					metadata.page_count =
 						value.into_iter().next().and_then(|n| n.parse().ok());
@@ -2,9 +2,11 @@ mod common;
 mod media_metadata;
 pub mod page_dimension;
 pub(crate) mod prisma_macros;
+mod provider;
 mod series_metadata;
 
 pub use common::{age_rating_deserializer, parse_age_restriction};
 pub use media_metadata::*;
 pub use page_dimension::{PageDimension, PageDimensionsEntity};
+pub use provider::*;
 pub use series_metadata::*;
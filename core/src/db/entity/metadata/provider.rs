@@ -0,0 +1,653 @@
+use std::{fmt, str::FromStr};
+
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	error::CoreError,
+	prisma::{
+		media_metadata, metadata_candidate, metadata_field_change, metadata_field_state,
+		series_metadata,
+	},
+};
+
+use super::{MediaMetadata, SeriesMetadata};
+
+/// The providers Stump can fetch metadata from
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Type, ToSchema,
+)]
+pub enum MetadataProviderType {
+	#[serde(rename = "GOOGLE_BOOKS")]
+	GoogleBooks,
+	#[serde(rename = "OPEN_LIBRARY")]
+	OpenLibrary,
+	#[serde(rename = "COMIC_VINE")]
+	ComicVine,
+}
+
+impl fmt::Display for MetadataProviderType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", integrations::MetadataProviderKind::from(*self))
+	}
+}
+
+impl FromStr for MetadataProviderType {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		integrations::MetadataProviderKind::from_str(s).map(Self::from)
+	}
+}
+
+impl From<integrations::MetadataProviderKind> for MetadataProviderType {
+	fn from(kind: integrations::MetadataProviderKind) -> Self {
+		match kind {
+			integrations::MetadataProviderKind::GoogleBooks => Self::GoogleBooks,
+			integrations::MetadataProviderKind::OpenLibrary => Self::OpenLibrary,
+			integrations::MetadataProviderKind::ComicVine => Self::ComicVine,
+		}
+	}
+}
+
+impl From<MetadataProviderType> for integrations::MetadataProviderKind {
+	fn from(provider: MetadataProviderType) -> Self {
+		match provider {
+			MetadataProviderType::GoogleBooks => Self::GoogleBooks,
+			MetadataProviderType::OpenLibrary => Self::OpenLibrary,
+			MetadataProviderType::ComicVine => Self::ComicVine,
+		}
+	}
+}
+
+/// Where the value of a metadata field came from
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Type, ToSchema,
+)]
+pub enum MetadataSource {
+	/// The metadata embedded in the file, read during a scan
+	#[serde(rename = "FILE")]
+	File,
+	/// An edit made by a user
+	#[serde(rename = "USER")]
+	User,
+	#[serde(rename = "GOOGLE_BOOKS")]
+	GoogleBooks,
+	#[serde(rename = "OPEN_LIBRARY")]
+	OpenLibrary,
+	#[serde(rename = "COMIC_VINE")]
+	ComicVine,
+}
+
+impl MetadataSource {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::File => "FILE",
+			Self::User => "USER",
+			Self::GoogleBooks => "GOOGLE_BOOKS",
+			Self::OpenLibrary => "OPEN_LIBRARY",
+			Self::ComicVine => "COMIC_VINE",
+		}
+	}
+
+	/// Whether changes from this source may overwrite locked fields. Only users can change
+	/// a locked field, since locks exist to protect their edits from scans and providers
+	pub fn overrides_locks(&self) -> bool {
+		matches!(self, Self::User)
+	}
+}
+
+impl fmt::Display for MetadataSource {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+impl FromStr for MetadataSource {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"FILE" => Ok(Self::File),
+			"USER" => Ok(Self::User),
+			_ => MetadataProviderType::from_str(s).map(Self::from),
+		}
+	}
+}
+
+impl From<MetadataProviderType> for MetadataSource {
+	fn from(provider: MetadataProviderType) -> Self {
+		match provider {
+			MetadataProviderType::GoogleBooks => Self::GoogleBooks,
+			MetadataProviderType::OpenLibrary => Self::OpenLibrary,
+			MetadataProviderType::ComicVine => Self::ComicVine,
+		}
+	}
+}
+
+impl From<integrations::MetadataProviderKind> for MetadataSource {
+	fn from(kind: integrations::MetadataProviderKind) -> Self {
+		Self::from(MetadataProviderType::from(kind))
+	}
+}
+
+/// Generates an enum of the fields of a metadata struct, which is how individual fields are
+/// referred to when applying candidates and locking fields
+macro_rules! metadata_field_enum {
+	($(#[$meta:meta])* $name:ident { $($variant:ident => $field:literal),+ $(,)? }) => {
+		$(#[$meta])*
+		#[derive(
+			Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Type, ToSchema,
+		)]
+		pub enum $name {
+			$(
+				#[serde(rename = $field)]
+				$variant,
+			)+
+		}
+
+		impl $name {
+			pub const ALL: &'static [Self] = &[$(Self::$variant),+];
+
+			pub fn as_str(&self) -> &'static str {
+				match self {
+					$(Self::$variant => $field,)+
+				}
+			}
+		}
+
+		impl fmt::Display for $name {
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				write!(f, "{}", self.as_str())
+			}
+		}
+
+		impl FromStr for $name {
+			type Err = String;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				match s {
+					$($field => Ok(Self::$variant),)+
+					_ => Err(format!("Unknown metadata field: {s}")),
+				}
+			}
+		}
+	};
+}
+
+metadata_field_enum! {
+	/// A single field of [MediaMetadata]
+	MediaMetadataField {
+		Title => "title",
+		Series => "series",
+		Number => "number",
+		Volume => "volume",
+		Summary => "summary",
+		Notes => "notes",
+		AgeRating => "age_rating",
+		Genre => "genre",
+		Year => "year",
+		Month => "month",
+		Day => "day",
+		Writers => "writers",
+		Pencillers => "pencillers",
+		Inkers => "inkers",
+		Colorists => "colorists",
+		Letterers => "letterers",
+		CoverArtists => "cover_artists",
+		Editors => "editors",
+		Publisher => "publisher",
+		Language => "language",
+		Links => "links",
+		Characters => "characters",
+		Teams => "teams",
+		Identifiers => "identifiers",
+		PageCount => "page_count",
+	}
+}
+
+metadata_field_enum! {
+	/// A single field of [SeriesMetadata]
+	SeriesMetadataField {
+		Title => "title",
+		Summary => "summary",
+		Publisher => "publisher",
+		Imprint => "imprint",
+		Comicid => "comicid",
+		Volume => "volume",
+		Booktype => "booktype",
+		AgeRating => "age_rating",
+		Status => "status",
+	}
+}
+
+impl MediaMetadata {
+	/// Whether the given field has a value
+	pub fn has_value(&self, field: MediaMetadataField) -> bool {
+		match field {
+			MediaMetadataField::Title => self.title.is_some(),
+			MediaMetadataField::Series => self.series.is_some(),
+			MediaMetadataField::Number => self.number.is_some(),
+			MediaMetadataField::Volume => self.volume.is_some(),
+			MediaMetadataField::Summary => self.summary.is_some(),
+			MediaMetadataField::Notes => self.notes.is_some(),
+			MediaMetadataField::AgeRating => self.age_rating.is_some(),
+			MediaMetadataField::Genre => self.genre.is_some(),
+			MediaMetadataField::Year => self.year.is_some(),
+			MediaMetadataField::Month => self.month.is_some(),
+			MediaMetadataField::Day => self.day.is_some(),
+			MediaMetadataField::Writers => self.writers.is_some(),
+			MediaMetadataField::Pencillers => self.pencillers.is_some(),
+			MediaMetadataField::Inkers => self.inkers.is_some(),
+			MediaMetadataField::Colorists => self.colorists.is_some(),
+			MediaMetadataField::Letterers => self.letterers.is_some(),
+			MediaMetadataField::CoverArtists => self.cover_artists.is_some(),
+			MediaMetadataField::Editors => self.editors.is_some(),
+			MediaMetadataField::Publisher => self.publisher.is_some(),
+			MediaMetadataField::Language => self.language.is_some(),
+			MediaMetadataField::Links => self.links.is_some(),
+			MediaMetadataField::Characters => self.characters.is_some(),
+			MediaMetadataField::Teams => self.teams.is_some(),
+			MediaMetadataField::Identifiers => self.identifiers.is_some(),
+			MediaMetadataField::PageCount => self.page_count.is_some(),
+		}
+	}
+
+	/// The value of the given field as JSON, or `None` if it has no value
+	pub fn field_value(&self, field: MediaMetadataField) -> Option<serde_json::Value> {
+		field_value(self, field.as_str())
+	}
+
+	/// The param which sets the given field to its value in this metadata
+	pub fn field_set_param(&self, field: MediaMetadataField) -> media_metadata::SetParam {
+		let join = |list: &Option<Vec<String>>| list.as_ref().map(|v| v.join(", "));
+		match field {
+			MediaMetadataField::Title => media_metadata::title::set(self.title.clone()),
+			MediaMetadataField::Series => {
+				media_metadata::series::set(self.series.clone())
+			},
+			MediaMetadataField::Number => media_metadata::number::set(self.number),
+			MediaMetadataField::Volume => media_metadata::volume::set(self.volume),
+			MediaMetadataField::Summary => {
+				media_metadata::summary::set(self.summary.clone())
+			},
+			MediaMetadataField::Notes => media_metadata::notes::set(self.notes.clone()),
+			MediaMetadataField::AgeRating => {
+				media_metadata::age_rating::set(self.age_rating)
+			},
+			MediaMetadataField::Genre => media_metadata::genre::set(join(&self.genre)),
+			MediaMetadataField::Year => media_metadata::year::set(self.year),
+			MediaMetadataField::Month => media_metadata::month::set(self.month),
+			MediaMetadataField::Day => media_metadata::day::set(self.day),
+			MediaMetadataField::Writers => {
+				media_metadata::writers::set(join(&self.writers))
+			},
+			MediaMetadataField::Pencillers => {
+				media_metadata::pencillers::set(join(&self.pencillers))
+			},
+			MediaMetadataField::Inkers => media_metadata::inkers::set(join(&self.inkers)),
+			MediaMetadataField::Colorists => {
+				media_metadata::colorists::set(join(&self.colorists))
+			},
+			MediaMetadataField::Letterers => {
+				media_metadata::letterers::set(join(&self.letterers))
+			},
+			MediaMetadataField::CoverArtists => {
+				media_metadata::cover_artists::set(join(&self.cover_artists))
+			},
+			MediaMetadataField::Editors => {
+				media_metadata::editors::set(join(&self.editors))
+			},
+			MediaMetadataField::Publisher => {
+				media_metadata::publisher::set(self.publisher.clone())
+			},
+			MediaMetadataField::Language => {
+				media_metadata::language::set(self.language.clone())
+			},
+			MediaMetadataField::Links => media_metadata::links::set(join(&self.links)),
+			MediaMetadataField::Characters => {
+				media_metadata::characters::set(join(&self.characters))
+			},
+			MediaMetadataField::Teams => media_metadata::teams::set(join(&self.teams)),
+			MediaMetadataField::Identifiers => {
+				media_metadata::identifiers::set(join(&self.identifiers))
+			},
+			MediaMetadataField::PageCount => {
+				media_metadata::page_count::set(self.page_count)
+			},
+		}
+	}
+}
+
+impl SeriesMetadata {
+	/// Whether the given field has a value
+	pub fn has_value(&self, field: SeriesMetadataField) -> bool {
+		match field {
+			SeriesMetadataField::Title => self.title.is_some(),
+			SeriesMetadataField::Summary => self.summary.is_some(),
+			SeriesMetadataField::Publisher => self.publisher.is_some(),
+			SeriesMetadataField::Imprint => self.imprint.is_some(),
+			SeriesMetadataField::Comicid => self.comicid.is_some(),
+			SeriesMetadataField::Volume => self.volume.is_some(),
+			SeriesMetadataField::Booktype => self.booktype.is_some(),
+			SeriesMetadataField::AgeRating => self.age_rating.is_some(),
+			SeriesMetadataField::Status => self.status.is_some(),
+		}
+	}
+
+	/// The value of the given field as JSON, or `None` if it has no value
+	pub fn field_value(&self, field: SeriesMetadataField) -> Option<serde_json::Value> {
+		field_value(self, field.as_str())
+	}
+
+	/// The param which sets the given field to its value in this metadata
+	pub fn field_set_param(
+		&self,
+		field: SeriesMetadataField,
+	) -> series_metadata::SetParam {
+		match field {
+			SeriesMetadataField::Title => series_metadata::title::set(self.title.clone()),
+			SeriesMetadataField::Summary => {
+				series_metadata::summary::set(self.summary.clone())
+			},
+			SeriesMetadataField::Publisher => {
+				series_metadata::publisher::set(self.publisher.clone())
+			},
+			SeriesMetadataField::Imprint => {
+				series_metadata::imprint::set(self.imprint.clone())
+			},
+			SeriesMetadataField::Comicid => series_metadata::comicid::set(self.comicid),
+			SeriesMetadataField::Volume => series_metadata::volume::set(self.volume),
+			SeriesMetadataField::Booktype => {
+				series_metadata::booktype::set(self.booktype.clone())
+			},
+			SeriesMetadataField::AgeRating => {
+				series_metadata::age_rating::set(self.age_rating)
+			},
+			SeriesMetadataField::Status => {
+				series_metadata::status::set(self.status.clone())
+			},
+		}
+	}
+}
+
+/// Get a field of a metadata struct by its serialized name. The field enums are named after
+/// the struct fields, so their names match the serialized ones
+fn field_value<T: Serialize>(metadata: &T, name: &str) -> Option<serde_json::Value> {
+	serde_json::to_value(metadata)
+		.ok()
+		.and_then(|mut value| value.get_mut(name).map(serde_json::Value::take))
+		.filter(|value| !value.is_null())
+}
+
+/// Convert an empty list to `None`, so that applying a candidate without e.g. any characters
+/// doesn't clear the characters of a book
+fn non_empty(list: &[String]) -> Option<Vec<String>> {
+	(!list.is_empty()).then(|| list.to_vec())
+}
+
+impl From<&integrations::MetadataCandidate> for MediaMetadata {
+	fn from(candidate: &integrations::MetadataCandidate) -> Self {
+		MediaMetadata {
+			title: candidate.title.clone(),
+			series: candidate.series.clone(),
+			number: candidate.number,
+			volume: candidate.volume,
+			summary: candidate.summary.clone(),
+			genre: non_empty(&candidate.genres),
+			year: candidate.year,
+			month: candidate.month,
+			day: candidate.day,
+			writers: non_empty(&candidate.writers),
+			pencillers: non_empty(&candidate.pencillers),
+			inkers: non_empty(&candidate.inkers),
+			colorists: non_empty(&candidate.colorists),
+			letterers: non_empty(&candidate.letterers),
+			cover_artists: non_empty(&candidate.cover_artists),
+			editors: non_empty(&candidate.editors),
+			publisher: candidate.publisher.clone(),
+			language: candidate.language.clone(),
+			links: candidate.url.clone().map(|url| vec![url]),
+			characters: non_empty(&candidate.characters),
+			teams: non_empty(&candidate.teams),
+			identifiers: candidate
+				.isbn
+				.as_deref()
+				.map(|isbn| vec![format!("isbn:{}", integrations::normalize_isbn(isbn))]),
+			page_count: candidate.page_count,
+			..Default::default()
+		}
+	}
+}
+
+impl From<&integrations::MetadataCandidate> for SeriesMetadata {
+	fn from(candidate: &integrations::MetadataCandidate) -> Self {
+		SeriesMetadata {
+			_type: "comicSeries".to_string(),
+			title: candidate.title.clone(),
+			summary: candidate.summary.clone(),
+			publisher: candidate.publisher.clone(),
+			imprint: None,
+			comicid: candidate.series_comicvine_id,
+			volume: None,
+			booktype: None,
+			age_rating: None,
+			status: None,
+		}
+	}
+}
+
+/// A possible match for a book or series found by a metadata provider
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
+pub struct MetadataCandidate {
+	pub id: String,
+	pub provider: MetadataProviderType,
+	/// The ID of the book or series on the provider
+	pub provider_id: String,
+	/// How likely it is that the candidate is the book or series, from 0 to 1. A confidence
+	/// of 1 means an identifier (e.g. the ISBN) matched
+	pub confidence: f64,
+	/// A link to the candidate on the provider's site
+	pub url: Option<String>,
+	/// A link to the cover of the candidate
+	pub cover_url: Option<String>,
+	/// The metadata which would be applied to the book. Only set for candidates of books
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	pub media_metadata: Option<MediaMetadata>,
+	/// The metadata which would be applied to the series. Only set for candidates of series
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	pub series_metadata: Option<SeriesMetadata>,
+	pub media_id: Option<String>,
+	pub series_id: Option<String>,
+	pub created_at: DateTime<FixedOffset>,
+}
+
+impl MetadataCandidate {
+	/// Deserialize the candidate as it was returned by the provider
+	pub fn parse_data(data: &[u8]) -> Result<integrations::MetadataCandidate, CoreError> {
+		Ok(serde_json::from_slice(data)?)
+	}
+}
+
+impl TryFrom<metadata_candidate::Data> for MetadataCandidate {
+	type Error = CoreError;
+
+	fn try_from(data: metadata_candidate::Data) -> Result<Self, Self::Error> {
+		let candidate = Self::parse_data(&data.data)?;
+		let provider = MetadataProviderType::from(candidate.provider);
+		let (media_metadata, series_metadata) = match data.media_id {
+			Some(_) => (Some(MediaMetadata::from(&candidate)), None),
+			None => (None, Some(SeriesMetadata::from(&candidate))),
+		};
+
+		Ok(Self {
+			id: data.id,
+			provider,
+			provider_id: data.provider_id,
+			confidence: data.confidence,
+			url: candidate.url,
+			cover_url: candidate.cover_url,
+			media_metadata,
+			series_metadata,
+			media_id: data.media_id,
+			series_id: data.series_id,
+			created_at: data.created_at,
+		})
+	}
+}
+
+/// The state of a single metadata field of a book or series
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
+pub struct MetadataFieldState {
+	/// The name of the field, e.g. `title` or `writers`
+	pub field: String,
+	/// Whether the field is locked. Locked fields are never overwritten by metadata providers
+	pub locked: bool,
+	/// Where the current value of the field came from
+	pub source: Option<MetadataSource>,
+	pub updated_at: DateTime<FixedOffset>,
+}
+
+impl From<metadata_field_state::Data> for MetadataFieldState {
+	fn from(data: metadata_field_state::Data) -> Self {
+		Self {
+			field: data.field,
+			locked: data.locked,
+			source: data.source.and_then(|source| source.parse().ok()),
+			updated_at: data.updated_at,
+		}
+	}
+}
+
+/// A change to a single metadata field of a book or series
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
+pub struct MetadataFieldChange {
+	pub id: String,
+	/// The name of the field, e.g. `title` or `writers`
+	pub field: String,
+	/// Where the new value came from
+	pub source: Option<MetadataSource>,
+	/// The previous value of the field, serialized as JSON
+	pub old_value: Option<String>,
+	/// The new value of the field, serialized as JSON
+	pub new_value: Option<String>,
+	/// The job which made the change, e.g. a library scan or metadata fetch
+	pub job_id: Option<String>,
+	/// The user who made the change, either by editing the metadata or applying a candidate
+	pub user_id: Option<String>,
+	pub media_id: Option<String>,
+	pub series_id: Option<String>,
+	pub changed_at: DateTime<FixedOffset>,
+}
+
+impl From<metadata_field_change::Data> for MetadataFieldChange {
+	fn from(data: metadata_field_change::Data) -> Self {
+		Self {
+			id: data.id,
+			field: data.field,
+			source: data.source.parse().ok(),
+			old_value: data.old_value,
+			new_value: data.new_value,
+			job_id: data.job_id,
+			user_id: data.user_id,
+			media_id: data.media_id,
+			series_id: data.series_id,
+			changed_at: data.changed_at,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use integrations::MetadataProviderKind;
+
+	use super::*;
+
+	#[test]
+	fn test_media_metadata_from_candidate() {
+		let candidate = integrations::MetadataCandidate {
+			title: Some("Dune".to_string()),
+			isbn: Some("978-0-441-17271-9".to_string()),
+			writers: vec!["Frank Herbert".to_string()],
+			url: Some("https://openlibrary.org/works/OL893415W".to_string()),
+			..integrations::MetadataCandidate::new(
+				MetadataProviderKind::OpenLibrary,
+				"/works/OL893415W",
+			)
+		};
+
+		let metadata = MediaMetadata::from(&candidate);
+		assert_eq!(metadata.title.as_deref(), Some("Dune"));
+		assert_eq!(metadata.writers, Some(vec!["Frank Herbert".to_string()]));
+		assert_eq!(
+			metadata.identifiers,
+			Some(vec!["isbn:9780441172719".to_string()])
+		);
+		// Lists the provider knows nothing about are left alone
+		assert!(!metadata.has_value(MediaMetadataField::Characters));
+		assert!(metadata.has_value(MediaMetadataField::Links));
+	}
+
+	#[test]
+	fn test_field_round_trip() {
+		for field in MediaMetadataField::ALL {
+			assert_eq!(field.as_str().parse::<MediaMetadataField>(), Ok(*field));
+		}
+		for field in SeriesMetadataField::ALL {
+			assert_eq!(field.as_str().parse::<SeriesMetadataField>(), Ok(*field));
+		}
+		assert_eq!(
+			serde_json::to_string(&MediaMetadataField::CoverArtists).unwrap(),
+			"\"cover_artists\""
+		);
+	}
+
+	#[test]
+	fn test_field_value() {
+		let metadata = MediaMetadata {
+			title: Some("Dune".to_string()),
+			writers: Some(vec!["Frank Herbert".to_string()]),
+			..Default::default()
+		};
+		assert_eq!(
+			metadata.field_value(MediaMetadataField::Title),
+			Some(serde_json::json!("Dune"))
+		);
+		assert_eq!(
+			metadata.field_value(MediaMetadataField::Writers),
+			Some(serde_json::json!(["Frank Herbert"]))
+		);
+		assert_eq!(metadata.field_value(MediaMetadataField::Summary), None);
+
+		for field in MediaMetadataField::ALL {
+			let metadata = MediaMetadata {
+				title: Some("Dune".to_string()),
+				..Default::default()
+			};
+			assert_eq!(
+				metadata.field_value(*field).is_some(),
+				metadata.has_value(*field),
+				"{field}"
+			);
+		}
+	}
+
+	#[test]
+	fn test_source_round_trip() {
+		for source in [
+			MetadataSource::File,
+			MetadataSource::User,
+			MetadataSource::ComicVine,
+		] {
+			assert_eq!(source.to_string().parse::<MetadataSource>(), Ok(source));
+		}
+		assert_eq!(
+			MetadataSource::from(MetadataProviderType::GoogleBooks).to_string(),
+			MetadataProviderType::GoogleBooks.to_string()
+		);
+	}
+}
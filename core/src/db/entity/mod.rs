@@ -1,17 +1,22 @@
 mod api_key;
+mod audit_log;
 mod book_club;
+mod collection;
 pub(crate) mod common;
 mod emailer;
 mod epub;
 mod job;
+mod job_schedule;
 mod library;
 mod log;
 mod media;
 mod metadata;
 mod notifier;
+mod pdf;
 mod reading_list;
 mod series;
 mod server_config;
+mod server_invitation;
 mod smart_list;
 mod tag;
 mod user;
@@ -20,16 +25,21 @@ pub use self::epub::*;
 pub use self::log::*;
 
 pub use api_key::*;
+pub use audit_log::*;
 pub use book_club::*;
+pub use collection::*;
 pub use emailer::*;
 pub use job::*;
+pub use job_schedule::*;
 pub use library::*;
 pub use media::*;
 pub use metadata::*;
 pub use notifier::*;
+pub use pdf::*;
 pub use reading_list::*;
 pub use series::*;
 pub use server_config::*;
+pub use server_invitation::*;
 pub use smart_list::*;
 pub use tag::*;
 pub use user::*;
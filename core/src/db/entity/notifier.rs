@@ -1,8 +1,20 @@
-use crate::{prisma::notifier, utils::encrypt_string, CoreError, CoreResult, Ctx};
+use crate::{
+	prisma::{notifier, notifier_delivery, PrismaClient},
+	utils::{decrypt_string, encrypt_string, get_encryption_key},
+	CoreError, CoreResult, Ctx,
+};
+use integrations::{
+	validate_webhook_template, Delivery, DiscordClient, Notifier as _, NotifierError,
+	NotifierEvent, PushClient, RetryPolicy, TelegramClient, WebhookClient,
+	GOTIFY_PRIORITIES, MAX_DELIVERY_ATTEMPTS, NTFY_PRIORITIES,
+};
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use std::fmt;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
+use std::sync::Arc;
 use utoipa::ToSchema;
 
 #[derive(Serialize, Deserialize, ToSchema, Type)]
@@ -16,8 +28,68 @@ pub struct Notifier {
 	/// The config is stored as bytes in the DB, and is deserialized into the correct type when
 	/// needed. If there are sensitive fields, they should be encrypted before being stored.
 	config: NotifierConfig,
+	/// The events (and libraries) the notifier should be sent
+	subscription: NotifierSubscription,
 }
 
+impl Notifier {
+	/// Send an event using the notifier, regardless of its subscription. The outcome is
+	/// recorded in the notifier's delivery log
+	pub async fn send(
+		self,
+		event: NotifierEvent,
+		client: &PrismaClient,
+	) -> CoreResult<Delivery> {
+		let notifier_id = self.id;
+		let event_name = event.name();
+		let result = self.config.send(event, client).await;
+
+		let (successful, attempts, status_code, error) = match &result {
+			Ok(delivery) => (true, delivery.attempts, delivery.status_code, None),
+			Err(CoreError::NotifierError(NotifierError::DeliveryFailed {
+				attempts,
+				status_code,
+				message,
+			})) => (false, *attempts, *status_code, Some(message.clone())),
+			Err(error) => (false, 1, None, Some(error.to_string())),
+		};
+
+		let recorded = client
+			.notifier_delivery()
+			.create(
+				event_name.to_string(),
+				successful,
+				attempts as i32,
+				notifier::id::equals(notifier_id),
+				vec![
+					notifier_delivery::status_code::set(status_code.map(i32::from)),
+					notifier_delivery::error::set(error),
+				],
+			)
+			.exec()
+			.await;
+		if let Err(error) = recorded {
+			tracing::error!(?error, notifier_id, "Failed to record notifier delivery");
+		}
+
+		result
+	}
+
+	/// Replace the values of any webhook headers with a placeholder. Headers are typically
+	/// used for authorization, so their values should never be exposed through the API
+	pub fn redacted(mut self) -> Self {
+		if let NotifierConfig::Webhook(config) = &mut self.config {
+			for header in &mut config.encrypted_headers {
+				header.value = REDACTED_HEADER_VALUE.to_string();
+			}
+		}
+		self
+	}
+}
+
+/// The placeholder which replaces webhook header values in API responses
+pub const REDACTED_HEADER_VALUE: &str = "********";
+
 /// The config for a Discord notifier
 #[derive(Serialize, Deserialize, ToSchema, Type)]
 pub struct DiscordConfig {
@@ -35,17 +107,322 @@ pub struct TelegramConfig {
 	pub chat_id: String,
 }
 
+/// The HTTP methods a webhook notifier may send with
+#[derive(
+	Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, Type,
+)]
+pub enum WebhookMethod {
+	#[default]
+	#[serde(rename = "POST")]
+	Post,
+	#[serde(rename = "PUT")]
+	Put,
+	#[serde(rename = "PATCH")]
+	Patch,
+}
+
+impl fmt::Display for WebhookMethod {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WebhookMethod::Post => write!(f, "POST"),
+			WebhookMethod::Put => write!(f, "PUT"),
+			WebhookMethod::Patch => write!(f, "PATCH"),
+		}
+	}
+}
+
+/// A header sent with every webhook request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Type)]
+pub struct WebhookHeader {
+	pub name: String,
+	pub value: String,
+}
+
+/// The config for a generic webhook notifier
+#[derive(Serialize, Deserialize, ToSchema, Type)]
+pub struct WebhookConfig {
+	/// The URL to send to
+	pub url: String,
+	/// The HTTP method to send with
+	#[serde(default)]
+	pub method: WebhookMethod,
+	/// Additional headers to send, e.g. for authorization. The header values are encrypted
+	/// before being stored, and decrypted when needed.
+	#[serde(default)]
+	pub encrypted_headers: Vec<WebhookHeader>,
+	/// A JSON template for the request body. The placeholders `{{event}}`, `{{title}}` and
+	/// `{{message}}` are replaced with JSON strings. If not set, a body containing each of
+	/// those fields is sent
+	#[serde(default)]
+	pub body_template: Option<String>,
+	/// The encrypted secret used to sign request bodies with HMAC-SHA256. The signature is
+	/// sent in the `X-Stump-Signature` header
+	#[serde(default)]
+	pub encrypted_signing_secret: Option<String>,
+	/// The total number of attempts to make before giving up. Defaults to 3, and may be at
+	/// most 5
+	#[serde(default)]
+	pub max_attempts: Option<u32>,
+}
+
+/// The config for an ntfy notifier
+#[derive(Serialize, Deserialize, ToSchema, Type)]
+pub struct NtfyConfig {
+	/// The URL of the ntfy server, e.g. `https://ntfy.sh`
+	pub server_url: String,
+	/// The topic to publish to
+	pub topic: String,
+	/// The encrypted access token for servers which require authentication
+	#[serde(default)]
+	pub encrypted_access_token: Option<String>,
+	/// The priority of sent messages, from 1 to 5
+	#[serde(default)]
+	pub priority: Option<u8>,
+}
+
+/// The config for a Gotify notifier
+#[derive(Serialize, Deserialize, ToSchema, Type)]
+pub struct GotifyConfig {
+	/// The URL of the Gotify server
+	pub server_url: String,
+	/// The encrypted token of the Gotify application to send as
+	pub encrypted_app_token: String,
+	/// The priority of sent messages, from 0 to 10
+	#[serde(default)]
+	pub priority: Option<u8>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Type)]
 #[serde(untagged)]
 pub enum NotifierConfig {
 	Discord(DiscordConfig),
 	Telegram(TelegramConfig),
+	Webhook(WebhookConfig),
+	Ntfy(NtfyConfig),
+	Gotify(GotifyConfig),
 }
 
 impl NotifierConfig {
 	pub fn into_bytes(self) -> Result<Vec<u8>, CoreError> {
 		Ok(serde_json::to_vec(&self)?)
 	}
+
+	/// Send an event using the client for this config, decrypting any sensitive fields
+	pub async fn send(
+		self,
+		event: NotifierEvent,
+		client: &PrismaClient,
+	) -> CoreResult<Delivery> {
+		// Discord and Telegram don't retry, so a successful send is a single attempt
+		let single_attempt = Delivery {
+			attempts: 1,
+			status_code: None,
+		};
+
+		match self {
+			NotifierConfig::Discord(config) => {
+				DiscordClient::new(config.webhook_url)
+					.send_message(event)
+					.await?;
+				Ok(single_attempt)
+			},
+			NotifierConfig::Telegram(config) => {
+				let encryption_key = get_encryption_key(client).await?;
+				let token = decrypt_string(&config.encrypted_token, &encryption_key)?;
+				TelegramClient::new(token, config.chat_id)
+					.send_message(event)
+					.await?;
+				Ok(single_attempt)
+			},
+			NotifierConfig::Webhook(config) => {
+				let encryption_key = get_encryption_key(client).await?;
+				let signing_secret = config
+					.encrypted_signing_secret
+					.map(|secret| decrypt_string(&secret, &encryption_key))
+					.transpose()?;
+				let headers = config
+					.encrypted_headers
+					.into_iter()
+					.map(|header| {
+						decrypt_string(&header.value, &encryption_key)
+							.map(|value| (header.name, value))
+					})
+					.collect::<CoreResult<Vec<_>>>()?;
+				let retry_policy = config.max_attempts.map_or_else(
+					RetryPolicy::default,
+					|max_attempts| RetryPolicy {
+						max_attempts,
+						..Default::default()
+					},
+				);
+
+				let delivery = WebhookClient::new(config.url)
+					.with_method(&config.method.to_string())?
+					.with_headers(headers)
+					.with_body_template(config.body_template)
+					.with_signing_secret(signing_secret)
+					.with_retry_policy(retry_policy)
+					.deliver(event)
+					.await?;
+				Ok(delivery)
+			},
+			NotifierConfig::Ntfy(config) => {
+				let access_token = match config.encrypted_access_token {
+					Some(token) => {
+						let encryption_key = get_encryption_key(client).await?;
+						Some(decrypt_string(&token, &encryption_key)?)
+					},
+					None => None,
+				};
+				let delivery =
+					PushClient::ntfy(config.server_url, config.topic, access_token)
+						.with_priority(config.priority)?
+						.deliver(event)
+						.await?;
+				Ok(delivery)
+			},
+			NotifierConfig::Gotify(config) => {
+				let encryption_key = get_encryption_key(client).await?;
+				let app_token =
+					decrypt_string(&config.encrypted_app_token, &encryption_key)?;
+				let delivery = PushClient::gotify(config.server_url, app_token)
+					.with_priority(config.priority)?
+					.deliver(event)
+					.await?;
+				Ok(delivery)
+			},
+		}
+	}
+}
+
+/// The kinds of events a notifier can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, Type)]
+pub enum NotifierEventKind {
+	#[serde(rename = "SCAN_COMPLETED")]
+	ScanCompleted,
+	#[serde(rename = "SERIES_CREATED")]
+	SeriesCreated,
+	#[serde(rename = "LIBRARY_MISSING")]
+	LibraryMissing,
+	#[serde(rename = "JOB_FAILED")]
+	JobFailed,
+	#[serde(rename = "USER_REGISTERED")]
+	UserRegistered,
+	#[serde(rename = "NEW_DEVICE_LOGIN")]
+	NewDeviceLogin,
+	#[serde(rename = "BOOK_CLUB_SCHEDULE_CHANGED")]
+	BookClubScheduleChanged,
+	#[serde(rename = "AUDIT_EVENT")]
+	AuditEvent,
+}
+
+impl NotifierEventKind {
+	/// Get the kind of the given event. Test events do not have a kind, since they are only
+	/// ever sent directly to a single notifier
+	pub fn of(event: &NotifierEvent) -> Option<Self> {
+		match event {
+			NotifierEvent::ScanCompleted { .. } => Some(Self::ScanCompleted),
+			NotifierEvent::SeriesCreated { .. } => Some(Self::SeriesCreated),
+			NotifierEvent::LibraryMissing { .. } => Some(Self::LibraryMissing),
+			NotifierEvent::JobFailed { .. } => Some(Self::JobFailed),
+			NotifierEvent::UserRegistered { .. } => Some(Self::UserRegistered),
+			NotifierEvent::NewDeviceLogin { .. } => Some(Self::NewDeviceLogin),
+			NotifierEvent::BookClubScheduleChanged { .. } => {
+				Some(Self::BookClubScheduleChanged)
+			},
+			NotifierEvent::AuditEvent { .. } => Some(Self::AuditEvent),
+			NotifierEvent::Test => None,
+		}
+	}
+}
+
+/// A filter for which events a notifier is sent. The default subscription receives every
+/// event for every library
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema, Type)]
+pub struct NotifierSubscription {
+	/// The events the notifier should be sent. If `None`, all events are sent
+	#[serde(default)]
+	pub events: Option<Vec<NotifierEventKind>>,
+	/// The libraries the notifier should be sent library events for, e.g. scan completions.
+	/// If `None`, events for all libraries are sent. Events which are not tied to a library
+	/// are unaffected
+	#[serde(default)]
+	pub library_ids: Option<Vec<String>>,
+}
+
+impl NotifierSubscription {
+	/// Whether an event of the given kind, optionally tied to a library, should be sent
+	pub fn includes(&self, kind: NotifierEventKind, library_id: Option<&str>) -> bool {
+		let includes_event = self
+			.events
+			.as_ref()
+			.is_none_or(|events| events.contains(&kind));
+		let includes_library = match (&self.library_ids, library_id) {
+			(Some(library_ids), Some(library_id)) => {
+				library_ids.iter().any(|id| id == library_id)
+			},
+			_ => true,
+		};
+
+		includes_event && includes_library
+	}
+
+	pub fn into_bytes(self) -> Result<Vec<u8>, CoreError> {
+		Ok(serde_json::to_vec(&self)?)
+	}
+}
+
+/// Send an event to every notifier subscribed to it. The notifiers are sent to concurrently
+/// in the background, and any failures are logged rather than returned
+pub fn dispatch_notifier_event(
+	client: Arc<PrismaClient>,
+	event: NotifierEvent,
+	library_id: Option<String>,
+) {
+	let Some(kind) = NotifierEventKind::of(&event) else {
+		tracing::warn!(?event, "Ignoring notifier event without a kind");
+		return;
+	};
+
+	tokio::spawn(async move {
+		let notifiers = match client.notifier().find_many(vec![]).exec().await {
+			Ok(notifiers) => notifiers,
+			Err(error) => {
+				tracing::error!(?error, "Failed to fetch notifiers");
+				return;
+			},
+		};
+
+		for notifier in notifiers {
+			let notifier_id = notifier.id;
+			let notifier = match Notifier::try_from(notifier) {
+				Ok(notifier) => notifier,
+				Err(error) => {
+					tracing::error!(?error, notifier_id, "Failed to parse notifier");
+					continue;
+				},
+			};
+
+			if !notifier.subscription.includes(kind, library_id.as_deref()) {
+				continue;
+			}
+
+			// Each notifier is sent to in its own task, so that a slow (or retrying) endpoint
+			// doesn't hold up the others
+			let client = client.clone();
+			let event = event.clone();
+			tokio::spawn(async move {
+				if let Err(error) = notifier.send(event, &client).await {
+					tracing::error!(
+						?error,
+						notifier_id,
+						?kind,
+						"Failed to send notification"
+					);
+				}
+			});
+		}
+	});
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Type)]
@@ -54,11 +431,47 @@ pub struct TelegramConfigInput {
 	pub chat_id: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema, Type)]
+pub struct WebhookConfigInput {
+	pub url: String,
+	#[serde(default)]
+	pub method: WebhookMethod,
+	#[serde(default)]
+	pub headers: Vec<WebhookHeader>,
+	#[serde(default)]
+	pub body_template: Option<String>,
+	#[serde(default)]
+	pub signing_secret: Option<String>,
+	#[serde(default)]
+	pub max_attempts: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Type)]
+pub struct NtfyConfigInput {
+	pub server_url: String,
+	pub topic: String,
+	#[serde(default)]
+	pub access_token: Option<String>,
+	#[serde(default)]
+	pub priority: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Type)]
+pub struct GotifyConfigInput {
+	pub server_url: String,
+	pub app_token: String,
+	#[serde(default)]
+	pub priority: Option<u8>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Type)]
 #[serde(untagged)]
 pub enum NotifierConfigInput {
 	Discord(DiscordConfig),
 	Telegram(TelegramConfigInput),
+	Webhook(WebhookConfigInput),
+	Ntfy(NtfyConfigInput),
+	Gotify(GotifyConfigInput),
 }
 
 impl NotifierConfigInput {
@@ -73,16 +486,101 @@ impl NotifierConfigInput {
 					chat_id: config.chat_id,
 				}))
 			},
+			NotifierConfigInput::Webhook(config) => {
+				if let Some(template) = &config.body_template {
+					validate_webhook_template(template)
+						.map_err(|e| CoreError::BadRequest(e.to_string()))?;
+				}
+				if let Some(max_attempts) = config.max_attempts {
+					if !(1..=MAX_DELIVERY_ATTEMPTS).contains(&max_attempts) {
+						return Err(CoreError::BadRequest(format!(
+							"Webhooks must make between 1 and {MAX_DELIVERY_ATTEMPTS} attempts"
+						)));
+					}
+				}
+				let encryption_key = ctx.get_encryption_key().await?;
+				let encrypted_signing_secret = config
+					.signing_secret
+					.map(|secret| encrypt_string(&secret, &encryption_key))
+					.transpose()?;
+				let encrypted_headers = config
+					.headers
+					.into_iter()
+					.map(|header| {
+						encrypt_string(&header.value, &encryption_key).map(|value| {
+							WebhookHeader {
+								name: header.name,
+								value,
+							}
+						})
+					})
+					.collect::<CoreResult<Vec<_>>>()?;
+				Ok(NotifierConfig::Webhook(WebhookConfig {
+					url: config.url,
+					method: config.method,
+					encrypted_headers,
+					body_template: config.body_template,
+					encrypted_signing_secret,
+					max_attempts: config.max_attempts,
+				}))
+			},
+			NotifierConfigInput::Ntfy(config) => {
+				validate_priority(config.priority, NTFY_PRIORITIES)?;
+				let encrypted_access_token = match config.access_token {
+					Some(token) => {
+						let encryption_key = ctx.get_encryption_key().await?;
+						Some(encrypt_string(&token, &encryption_key)?)
+					},
+					None => None,
+				};
+				Ok(NotifierConfig::Ntfy(NtfyConfig {
+					server_url: config.server_url,
+					topic: config.topic,
+					encrypted_access_token,
+					priority: config.priority,
+				}))
+			},
+			NotifierConfigInput::Gotify(config) => {
+				validate_priority(config.priority, GOTIFY_PRIORITIES)?;
+				let encryption_key = ctx.get_encryption_key().await?;
+				let encrypted_app_token =
+					encrypt_string(&config.app_token, &encryption_key)?;
+				Ok(NotifierConfig::Gotify(GotifyConfig {
+					server_url: config.server_url,
+					encrypted_app_token,
+					priority: config.priority,
+				}))
+			},
 		}
 	}
 }
 
+/// Check that a configured priority is within the range accepted by the push service
+fn validate_priority(priority: Option<u8>, range: RangeInclusive<u8>) -> CoreResult<()> {
+	match priority {
+		Some(priority) if !range.contains(&priority) => {
+			Err(CoreError::BadRequest(format!(
+				"The priority must be between {} and {}",
+				range.start(),
+				range.end()
+			)))
+		},
+		_ => Ok(()),
+	}
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Type)]
 pub enum NotifierType {
 	#[serde(rename = "DISCORD")]
 	Discord,
 	#[serde(rename = "TELEGRAM")]
 	Telegram,
+	#[serde(rename = "WEBHOOK")]
+	Webhook,
+	#[serde(rename = "NTFY")]
+	Ntfy,
+	#[serde(rename = "GOTIFY")]
+	Gotify,
 }
 
 impl fmt::Display for NotifierType {
@@ -90,6 +588,9 @@ impl fmt::Display for NotifierType {
 		match self {
 			NotifierType::Discord => write!(f, "DISCORD"),
 			NotifierType::Telegram => write!(f, "TELEGRAM"),
+			NotifierType::Webhook => write!(f, "WEBHOOK"),
+			NotifierType::Ntfy => write!(f, "NTFY"),
+			NotifierType::Gotify => write!(f, "GOTIFY"),
 		}
 	}
 }
@@ -103,6 +604,9 @@ impl FromStr for NotifierType {
 		match uppercase.as_str() {
 			"DISCORD" => Ok(NotifierType::Discord),
 			"TELEGRAM" => Ok(NotifierType::Telegram),
+			"WEBHOOK" => Ok(NotifierType::Webhook),
+			"NTFY" => Ok(NotifierType::Ntfy),
+			"GOTIFY" => Ok(NotifierType::Gotify),
 			_ => Err(format!("Invalid NotifierType: {s}")),
 		}
 	}
@@ -116,7 +620,140 @@ impl TryFrom<notifier::Data> for Notifier {
 			_type: NotifierType::from_str(&value.r#type)
 				.map_err(|e| CoreError::InternalError(e.to_string()))?,
 			config: serde_json::from_slice(&value.config)?,
+			subscription: value
+				.subscription
+				.map(|bytes| serde_json::from_slice(&bytes))
+				.transpose()?
+				.unwrap_or_default(),
 			id: value.id,
 		})
 	}
 }
+
+/// A record of an attempt to send an event using a notifier
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Type)]
+pub struct NotifierDelivery {
+	pub id: String,
+	/// The name of the event which was sent, e.g. `SCAN_COMPLETED`
+	pub event: String,
+	/// Whether the event was delivered
+	pub successful: bool,
+	/// The number of attempts which were made, including retries
+	pub attempts: i32,
+	/// The HTTP status code of the final response, if there was one
+	pub status_code: Option<i32>,
+	/// The error which caused the delivery to fail, if it did
+	pub error: Option<String>,
+	pub timestamp: DateTime<FixedOffset>,
+	pub notifier_id: i32,
+}
+
+impl From<notifier_delivery::Data> for NotifierDelivery {
+	fn from(data: notifier_delivery::Data) -> Self {
+		Self {
+			id: data.id,
+			event: data.event,
+			successful: data.successful,
+			attempts: data.attempts,
+			status_code: data.status_code,
+			error: data.error,
+			timestamp: data.timestamp,
+			notifier_id: data.notifier_id,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_default_subscription_includes_everything() {
+		let subscription = NotifierSubscription::default();
+		assert!(subscription.includes(NotifierEventKind::JobFailed, None));
+		assert!(subscription.includes(NotifierEventKind::ScanCompleted, Some("library")));
+	}
+
+	#[test]
+	fn test_subscription_filters_events_and_libraries() {
+		let subscription = NotifierSubscription {
+			events: Some(vec![
+				NotifierEventKind::ScanCompleted,
+				NotifierEventKind::UserRegistered,
+			]),
+			library_ids: Some(vec!["comics".to_string()]),
+		};
+
+		assert!(subscription.includes(NotifierEventKind::ScanCompleted, Some("comics")));
+		assert!(!subscription.includes(NotifierEventKind::ScanCompleted, Some("manga")));
+		assert!(!subscription.includes(NotifierEventKind::JobFailed, None));
+		// Events which aren't tied to a library ignore the library filter
+		assert!(subscription.includes(NotifierEventKind::UserRegistered, None));
+	}
+
+	#[test]
+	fn test_redacted_hides_header_values() {
+		let notifier = Notifier {
+			id: 1,
+			_type: NotifierType::Webhook,
+			config: NotifierConfig::Webhook(WebhookConfig {
+				url: "http://localhost/hook".to_string(),
+				method: WebhookMethod::Post,
+				encrypted_headers: vec![WebhookHeader {
+					name: "Authorization".to_string(),
+					value: "encrypted".to_string(),
+				}],
+				body_template: None,
+				encrypted_signing_secret: None,
+				max_attempts: None,
+			}),
+			subscription: NotifierSubscription::default(),
+		}
+		.redacted();
+
+		let NotifierConfig::Webhook(config) = notifier.config else {
+			panic!("Expected a webhook config");
+		};
+		assert_eq!(config.encrypted_headers[0].name, "Authorization");
+		assert_eq!(config.encrypted_headers[0].value, REDACTED_HEADER_VALUE);
+	}
+
+	#[test]
+	fn test_validate_priority() {
+		assert!(validate_priority(None, NTFY_PRIORITIES).is_ok());
+		assert!(validate_priority(Some(1), NTFY_PRIORITIES).is_ok());
+		assert!(validate_priority(Some(0), NTFY_PRIORITIES).is_err());
+		assert!(validate_priority(Some(10), GOTIFY_PRIORITIES).is_ok());
+		assert!(validate_priority(Some(11), GOTIFY_PRIORITIES).is_err());
+	}
+
+	#[test]
+	fn test_config_variants_deserialize() {
+		let config: NotifierConfig = serde_json::from_value(serde_json::json!({
+			"url": "http://localhost/hook",
+			"method": "PUT",
+		}))
+		.unwrap();
+		assert!(matches!(
+			config,
+			NotifierConfig::Webhook(WebhookConfig {
+				method: WebhookMethod::Put,
+				..
+			})
+		));
+
+		let config: NotifierConfig = serde_json::from_value(serde_json::json!({
+			"server_url": "https://ntfy.sh",
+			"topic": "stump",
+		}))
+		.unwrap();
+		assert!(matches!(config, NotifierConfig::Ntfy(_)));
+
+		let config: NotifierConfig = serde_json::from_value(serde_json::json!({
+			"server_url": "https://gotify.local",
+			"encrypted_app_token": "token",
+		}))
+		.unwrap();
+		assert!(matches!(config, NotifierConfig::Gotify(_)));
+	}
+}
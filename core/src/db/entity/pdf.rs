@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+/// An entry in the outline (bookmarks) of a PDF, which typically serves as its table of
+/// contents
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct PdfOutlineItem {
+	/// The title of the entry. ex: "Chapter 1"
+	pub title: String,
+	/// The page the entry points to, indexed from 1. Will be `None` if the entry does not
+	/// point to a page within the document (e.g. an external link)
+	pub page: Option<i32>,
+	/// The nested entries of the outline, e.g. sections of a chapter
+	#[schema(no_recursion)]
+	pub children: Vec<PdfOutlineItem>,
+}
@@ -0,0 +1,97 @@
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
+use prisma_client_rust::chrono::{DateTime, FixedOffset, Utc};
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::entity::{AgeRestriction, PermissionSet, UserPermission},
+	prisma::server_invitation,
+};
+
+/// The number of days an invitation is valid for, unless otherwise specified
+pub const DEFAULT_INVITATION_EXPIRY_DAYS: i64 = 7;
+
+/// An invitation for someone to register an account on the server, even when registration
+/// is otherwise closed. The invited user is created with the permissions, age restriction
+/// and hidden libraries of the invitation. Invitations may only be redeemed once
+#[derive(Debug, Clone, Deserialize, Serialize, Type, ToSchema)]
+pub struct ServerInvitation {
+	pub id: String,
+	/// The email address the invitation was sent to, if any
+	pub email: Option<String>,
+	/// The permissions granted to the invited user
+	pub granted_permissions: Vec<UserPermission>,
+	/// The age restriction applied to the invited user, if any
+	pub age_restriction: Option<AgeRestriction>,
+	/// The IDs of the libraries hidden from the invited user. Will be `None` only if the
+	/// relation is not loaded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[specta(optional)]
+	pub hidden_library_ids: Option<Vec<String>>,
+	pub created_at: DateTime<FixedOffset>,
+	pub expires_at: DateTime<FixedOffset>,
+}
+
+impl ServerInvitation {
+	/// Generate a new invitation token, returning the token to send to the invitee and the
+	/// hash of it to store in the database
+	pub fn create_token() -> (String, String) {
+		let token = BASE64URL_NOPAD.encode(&rand::random::<[u8; 32]>());
+		let hash = ServerInvitation::hash_token(&token);
+		(token, hash)
+	}
+
+	/// Hash an invitation token. Only the hash of a token is stored, so that a leaked database
+	/// can't be used to redeem invitations
+	pub fn hash_token(token: &str) -> String {
+		HEXLOWER.encode(digest(&SHA256, token.as_bytes()).as_ref())
+	}
+
+	pub fn is_expired(&self) -> bool {
+		self.expires_at < Utc::now()
+	}
+}
+
+impl From<server_invitation::Data> for ServerInvitation {
+	fn from(data: server_invitation::Data) -> Self {
+		let hidden_library_ids = data
+			.hidden_libraries()
+			.map(|libraries| libraries.iter().map(|l| l.id.clone()).collect())
+			.ok();
+		let granted_permissions = data
+			.granted_permissions
+			.map(PermissionSet::from)
+			.map(PermissionSet::resolve_into_vec)
+			.unwrap_or_default();
+		let age_restriction = data.age_restriction.map(|age| AgeRestriction {
+			age,
+			restrict_on_unset: data.restrict_on_unset,
+		});
+
+		Self {
+			id: data.id,
+			email: data.email,
+			granted_permissions,
+			age_restriction,
+			hidden_library_ids,
+			created_at: data.created_at,
+			expires_at: data.expires_at,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_create_token() {
+		let (token, hash) = ServerInvitation::create_token();
+
+		assert_eq!(ServerInvitation::hash_token(&token), hash);
+		assert_ne!(token, hash);
+		assert_ne!(ServerInvitation::create_token().0, token);
+	}
+}
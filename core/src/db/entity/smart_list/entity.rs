@@ -8,14 +8,15 @@ use utoipa::ToSchema;
 use crate::{
 	db::{
 		entity::{
-			utils::apply_media_age_restriction, EntityVisibility, Library, Media, Series,
-			User,
+			utils::{
+				apply_media_age_restriction,
+				apply_media_library_not_hidden_for_user_filter,
+			},
+			EntityVisibility, Library, Media, Series, User,
 		},
-		filter::{FilterGroup, FilterJoin, MediaSmartFilter, SmartFilter},
-	},
-	prisma::{
-		active_reading_session, library, media, series, smart_list, user, PrismaClient,
+		filter::{FilterJoin, MediaSmartFilter, SmartFilter},
 	},
+	prisma::{active_reading_session, library, media, series, smart_list, PrismaClient},
 	utils::chain_optional_iter,
 	CoreError, CoreResult,
 };
@@ -41,27 +42,7 @@ pub struct SmartList {
 
 impl SmartList {
 	fn into_params(self) -> media::WhereParam {
-		let where_params = self
-			.filters
-			.groups
-			.into_iter()
-			.map(|filter_group| match filter_group {
-				FilterGroup::Or { or } => prisma_client_rust::operator::or(
-					or.into_iter().map(|f| f.into_params()).collect(),
-				),
-				FilterGroup::And { and } => prisma_client_rust::operator::and(
-					and.into_iter().map(|f| f.into_params()).collect(),
-				),
-				FilterGroup::Not { not } => prisma_client_rust::operator::not(
-					not.into_iter().map(|f| f.into_params()).collect(),
-				),
-			})
-			.collect();
-
-		match self.joiner {
-			FilterJoin::And => operator::and(where_params),
-			FilterJoin::Or => operator::or(where_params),
-		}
+		self.filters.into_params(self.joiner)
 	}
 
 	pub fn into_params_for_user(self, user: &User) -> Vec<media::WhereParam> {
@@ -70,16 +51,10 @@ impl SmartList {
 			.age_restriction
 			.as_ref()
 			.map(|ar| apply_media_age_restriction(ar.age, ar.restrict_on_unset));
-		let library_not_hidden_restriction =
-			library::hidden_from_users::none(vec![user::id::equals(user.id.clone())]);
 
 		let params_for_user = operator::and(chain_optional_iter(
-			[
-				params,
-				media::series::is(vec![series::library::is(vec![
-					library_not_hidden_restriction,
-				])]),
-			],
+			std::iter::once(params)
+				.chain(apply_media_library_not_hidden_for_user_filter(user)),
 			[age_restriction],
 		));
 
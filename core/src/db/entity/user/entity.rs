@@ -4,7 +4,7 @@ use specta::Type;
 use utoipa::ToSchema;
 
 use crate::{
-	db::entity::{ActiveReadingSession, Cursor, FinishedReadingSession},
+	db::entity::{APIKeyScopes, ActiveReadingSession, Cursor, FinishedReadingSession},
 	prisma::user,
 };
 
@@ -21,6 +21,10 @@ pub struct User {
 	///
 	/// Note: This is a unique field.
 	pub username: String,
+	/// The email address of the user, which is used to send them password resets
+	///
+	/// Note: This is a unique field.
+	pub email: Option<String>,
 	/// A boolean to indicate if the user is the server owner
 	pub is_server_owner: bool,
 	/// The URL of the user's avatar, if any
@@ -54,6 +58,10 @@ pub struct User {
 	/// The finished reading sessions for the user. Will be `None` if the relation is not loaded.
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub finished_reading_sessions: Option<Vec<FinishedReadingSession>>,
+	/// The scopes of the API key the user authenticated with, if any, which restrict the
+	/// content they can access for the request
+	#[serde(skip)]
+	pub api_key_scopes: Option<APIKeyScopes>,
 }
 
 impl User {
@@ -168,6 +176,7 @@ impl From<user::Data> for User {
 		User {
 			id: data.id,
 			username: data.username,
+			email: data.email,
 			is_server_owner: data.is_server_owner,
 			permissions: permission_set
 				.map(|ps| ps.resolve_into_vec())
@@ -183,6 +192,7 @@ impl From<user::Data> for User {
 			login_activity,
 			is_locked: data.is_locked,
 			login_sessions_count,
+			api_key_scopes: None,
 		}
 	}
 }
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::prisma;
+
+/// An identity from an external OpenID Connect provider which is linked to a user, and
+/// can be used to log in as them
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct ExternalIdentity {
+	pub id: String,
+	/// The issuer (provider) of the identity
+	pub issuer: String,
+	/// The subject identifier of the user at the provider
+	pub subject: String,
+	pub email: Option<String>,
+	pub created_at: String,
+	pub last_login_at: Option<String>,
+}
+
+impl From<prisma::user_external_identity::Data> for ExternalIdentity {
+	fn from(data: prisma::user_external_identity::Data) -> ExternalIdentity {
+		ExternalIdentity {
+			id: data.id,
+			issuer: data.issuer,
+			subject: data.subject,
+			email: data.email,
+			created_at: data.created_at.to_rfc3339(),
+			last_login_at: data.last_login_at.map(|dt| dt.to_rfc3339()),
+		}
+	}
+}
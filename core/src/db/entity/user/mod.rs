@@ -1,10 +1,14 @@
 mod activity;
 mod entity;
+mod external_identity;
 mod permissions;
 mod preferences;
 pub(crate) mod prisma_macros;
+mod two_factor;
 
 pub use activity::*;
 pub use entity::*;
+pub use external_identity::*;
 pub use permissions::*;
 pub use preferences::*;
+pub use two_factor::*;
@@ -60,6 +60,10 @@ pub enum UserPermission {
 	/// Grant access to access the smart list feature. This includes the ability to create and edit smart lists
 	#[serde(rename = "smartlist:read")]
 	AccessSmartList,
+	/// Grant access to create, edit, and delete server-wide collections. This also grants
+	/// visibility of private collections
+	#[serde(rename = "collection:manage")]
+	ManageCollections,
 	/// Grant access to access the file explorer
 	#[serde(rename = "file:explorer")]
 	FileExplorer,
@@ -163,6 +167,7 @@ impl fmt::Display for UserPermission {
 			UserPermission::EmailSend => write!(f, "email:send"),
 			UserPermission::EmailArbitrarySend => write!(f, "email:arbitrary_send"),
 			UserPermission::AccessSmartList => write!(f, "smartlist:read"),
+			UserPermission::ManageCollections => write!(f, "collection:manage"),
 			UserPermission::FileExplorer => write!(f, "file:explorer"),
 			UserPermission::UploadFile => write!(f, "file:upload"),
 			UserPermission::DownloadFile => write!(f, "file:download"),
@@ -196,6 +201,7 @@ impl From<&str> for UserPermission {
 			"email:send" => UserPermission::EmailSend,
 			"email:arbitrary_send" => UserPermission::EmailArbitrarySend,
 			"smartlist:read" => UserPermission::AccessSmartList,
+			"collection:manage" => UserPermission::ManageCollections,
 			"file:explorer" => UserPermission::FileExplorer,
 			"file:upload" => UserPermission::UploadFile,
 			"file:download" => UserPermission::DownloadFile,
@@ -365,6 +371,10 @@ mod tests {
 			UserPermission::from("smartlist:read"),
 			UserPermission::AccessSmartList
 		);
+		assert_eq!(
+			UserPermission::from("collection:manage"),
+			UserPermission::ManageCollections
+		);
 		assert_eq!(
 			UserPermission::from("file:explorer"),
 			UserPermission::FileExplorer
@@ -0,0 +1,20 @@
+use crate::{
+	prisma::user_two_factor,
+	utils::{decrypt_string, encrypt_string},
+	CoreResult, Ctx,
+};
+
+/// Encrypt the TOTP secret of a user's two-factor authentication, so it can be stored
+pub async fn encrypt_two_factor_secret(secret: &str, ctx: &Ctx) -> CoreResult<String> {
+	let encryption_key = ctx.get_encryption_key().await?;
+	encrypt_string(secret, &encryption_key)
+}
+
+/// Decrypt the stored TOTP secret of a user's two-factor authentication
+pub async fn decrypt_two_factor_secret(
+	two_factor: &user_two_factor::Data,
+	ctx: &Ctx,
+) -> CoreResult<String> {
+	let encryption_key = ctx.get_encryption_key().await?;
+	decrypt_string(&two_factor.secret, &encryption_key)
+}
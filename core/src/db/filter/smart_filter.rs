@@ -9,7 +9,9 @@ use serde::{Deserialize, Serialize};
 use specta::Type;
 use utoipa::ToSchema;
 
-use crate::prisma::{library, media, media_metadata, series, series_metadata, tag};
+use crate::prisma::{
+	library, media, media_metadata, review, series, series_metadata, tag,
+};
 use smart_filter_gen::generate_smart_filter;
 
 // TODO: This rough implementation is not very great. It is very verbose and not very ergonomic. It _technically_
@@ -223,6 +225,33 @@ pub struct SmartFilter<T> {
 
 pub type SmartFilterSchema = SmartFilter<MediaSmartFilter>;
 
+impl SmartFilter<MediaSmartFilter> {
+	/// Convert the filter groups into a single media where param, combining the groups
+	/// with the given joiner
+	pub fn into_params(self, joiner: FilterJoin) -> media::WhereParam {
+		let where_params = self
+			.groups
+			.into_iter()
+			.map(|filter_group| match filter_group {
+				FilterGroup::Or { or } => prisma_client_rust::operator::or(
+					or.into_iter().map(|f| f.into_params()).collect(),
+				),
+				FilterGroup::And { and } => prisma_client_rust::operator::and(
+					and.into_iter().map(|f| f.into_params()).collect(),
+				),
+				FilterGroup::Not { not } => prisma_client_rust::operator::not(
+					not.into_iter().map(|f| f.into_params()).collect(),
+				),
+			})
+			.collect();
+
+		match joiner {
+			FilterJoin::And => prisma_client_rust::operator::and(where_params),
+			FilterJoin::Or => prisma_client_rust::operator::or(where_params),
+		}
+	}
+}
+
 #[generate_smart_filter]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Type, ToSchema)]
 #[serde(untagged)]
@@ -327,6 +356,8 @@ impl SeriesSmartFilter {
 #[serde(untagged)]
 #[prisma_table("media_metadata")]
 pub enum MediaMetadataSmartFilter {
+	#[is_optional]
+	Title { title: String },
 	#[is_optional]
 	Publisher { publisher: String },
 	#[is_optional]
@@ -363,6 +394,30 @@ pub enum TagSmartFilter {
 	Name { name: String },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Type, ToSchema)]
+#[serde(untagged)]
+pub enum ReviewSmartFilter {
+	Rating { rating: Filter<i32> },
+}
+
+impl ReviewSmartFilter {
+	/// Convert the filter into a review where param. Private reviews are never matched, since
+	/// a smart list should not reveal how another user privately rated a book
+	pub fn into_params(self) -> review::WhereParam {
+		let param = match self {
+			ReviewSmartFilter::Rating { rating } => rating.into_numeric_params(
+				review::rating::equals,
+				review::rating::gt,
+				review::rating::gte,
+				review::rating::lt,
+				review::rating::lte,
+			),
+		};
+
+		and![review::is_private::equals(false), param]
+	}
+}
+
 #[generate_smart_filter]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Type, ToSchema)]
 #[serde(untagged)]
@@ -379,6 +434,7 @@ pub enum MediaSmartFilter {
 	Metadata { metadata: MediaMetadataSmartFilter },
 	Series { series: SeriesSmartFilter },
 	Tags { tags: TagSmartFilter },
+	Reviews { reviews: ReviewSmartFilter },
 }
 
 #[cfg(test)]
@@ -537,6 +593,24 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn it_deserializes_review_filter_correctly() {
+		let json = r#"{"and":[{"reviews":{"rating":{"gte":8}}}]}"#;
+
+		let filter: FilterGroup<MediaSmartFilter> = serde_json::from_str(json).unwrap();
+
+		assert_eq!(
+			filter,
+			FilterGroup::And {
+				and: vec![MediaSmartFilter::Reviews {
+					reviews: ReviewSmartFilter::Rating {
+						rating: Filter::NumericFilter(NumericFilter::Gte { gte: 8 }),
+					},
+				}],
+			}
+		);
+	}
+
 	fn default_book(name: &str) -> media::Data {
 		media::Data {
 			id: "test-id".to_string(),
@@ -551,16 +625,23 @@ mod tests {
 			bookmarks: None,
 			created_at: Utc::now().into(),
 			deleted_at: None,
+			duplicate_clusters: None,
 			extension: "CBZ".to_string(),
 			hash: None,
 			koreader_hash: None,
+			checksum: None,
+			cover_hash: None,
 			metadata: None,
+			metadata_candidates: None,
+			metadata_field_states: None,
+			metadata_field_changes: None,
 			modified_at: None,
 			pages: 30,
 			path: "test-path".to_string(),
 			active_user_reading_sessions: None,
 			finished_user_reading_sessions: None,
 			reading_list_items: None,
+			collection_items: None,
 			size: 100,
 			status: "READY".to_string(),
 			tags: None,
@@ -0,0 +1,644 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::{
+		entity::{
+			MediaMetadata, MediaMetadataField, MetadataSource, SeriesMetadata,
+			SeriesMetadataField,
+		},
+		search::index_media,
+	},
+	error::{CoreError, CoreResult},
+	prisma::{
+		media, media_metadata, metadata_candidate, metadata_field_change,
+		metadata_field_state, series, series_metadata, user, PrismaClient,
+	},
+};
+
+/// The book or series which candidates and field states belong to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataTarget {
+	Media(String),
+	Series(String),
+}
+
+impl MetadataTarget {
+	fn candidate_filter(&self) -> metadata_candidate::WhereParam {
+		match self {
+			Self::Media(id) => metadata_candidate::media_id::equals(Some(id.clone())),
+			Self::Series(id) => metadata_candidate::series_id::equals(Some(id.clone())),
+		}
+	}
+
+	fn candidate_connect(&self) -> metadata_candidate::SetParam {
+		match self {
+			Self::Media(id) => {
+				metadata_candidate::media::connect(media::id::equals(id.clone()))
+			},
+			Self::Series(id) => {
+				metadata_candidate::series::connect(series::id::equals(id.clone()))
+			},
+		}
+	}
+
+	fn field_state_filter(&self) -> metadata_field_state::WhereParam {
+		match self {
+			Self::Media(id) => metadata_field_state::media_id::equals(Some(id.clone())),
+			Self::Series(id) => metadata_field_state::series_id::equals(Some(id.clone())),
+		}
+	}
+
+	fn field_state_unique(&self, field: &str) -> metadata_field_state::UniqueWhereParam {
+		match self {
+			Self::Media(id) => {
+				metadata_field_state::media_id_field(id.clone(), field.to_string())
+			},
+			Self::Series(id) => {
+				metadata_field_state::series_id_field(id.clone(), field.to_string())
+			},
+		}
+	}
+
+	fn field_change_filter(&self) -> metadata_field_change::WhereParam {
+		match self {
+			Self::Media(id) => metadata_field_change::media_id::equals(Some(id.clone())),
+			Self::Series(id) => {
+				metadata_field_change::series_id::equals(Some(id.clone()))
+			},
+		}
+	}
+
+	fn field_change_connect(&self) -> metadata_field_change::SetParam {
+		match self {
+			Self::Media(id) => {
+				metadata_field_change::media::connect(media::id::equals(id.clone()))
+			},
+			Self::Series(id) => {
+				metadata_field_change::series::connect(series::id::equals(id.clone()))
+			},
+		}
+	}
+
+	fn field_state_connect(&self) -> metadata_field_state::SetParam {
+		match self {
+			Self::Media(id) => {
+				metadata_field_state::media::connect(media::id::equals(id.clone()))
+			},
+			Self::Series(id) => {
+				metadata_field_state::series::connect(series::id::equals(id.clone()))
+			},
+		}
+	}
+}
+
+/// The outcome of writing metadata to a book or series
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Type, ToSchema)]
+pub struct AppliedMetadata {
+	/// The fields which were changed
+	pub applied: Vec<String>,
+	/// The fields which would have changed, but were not because they are locked
+	pub locked: Vec<String>,
+}
+
+/// Get the field states of a book or series, ordered by field name
+pub async fn get_field_states(
+	client: &PrismaClient,
+	target: &MetadataTarget,
+) -> CoreResult<Vec<metadata_field_state::Data>> {
+	Ok(client
+		.metadata_field_state()
+		.find_many(vec![target.field_state_filter()])
+		.order_by(metadata_field_state::field::order(
+			prisma_client_rust::Direction::Asc,
+		))
+		.exec()
+		.await?)
+}
+
+/// Get the names of the locked fields of a book or series
+async fn get_locked_fields(
+	client: &PrismaClient,
+	target: &MetadataTarget,
+) -> Result<HashSet<String>, prisma_client_rust::QueryError> {
+	Ok(client
+		.metadata_field_state()
+		.find_many(vec![
+			target.field_state_filter(),
+			metadata_field_state::locked::equals(true),
+		])
+		.exec()
+		.await?
+		.into_iter()
+		.map(|state| state.field)
+		.collect())
+}
+
+/// Lock or unlock a field of a book or series. An error is returned if the book or series
+/// doesn't exist, rather than failing to connect the new field state to it
+pub async fn set_field_lock(
+	client: &PrismaClient,
+	target: &MetadataTarget,
+	field: &str,
+	locked: bool,
+) -> CoreResult<metadata_field_state::Data> {
+	let (count, kind) = match target {
+		MetadataTarget::Media(id) => (
+			client
+				.media()
+				.count(vec![media::id::equals(id.clone())])
+				.exec()
+				.await?,
+			"Media",
+		),
+		MetadataTarget::Series(id) => (
+			client
+				.series()
+				.count(vec![series::id::equals(id.clone())])
+				.exec()
+				.await?,
+			"Series",
+		),
+	};
+	if count == 0 {
+		return Err(CoreError::NotFound(format!("{kind} not found")));
+	}
+
+	Ok(client
+		.metadata_field_state()
+		.upsert(
+			target.field_state_unique(field),
+			(
+				field.to_string(),
+				vec![
+					target.field_state_connect(),
+					metadata_field_state::locked::set(locked),
+				],
+			),
+			vec![metadata_field_state::locked::set(locked)],
+		)
+		.exec()
+		.await?)
+}
+
+/// Who made a change to the metadata of a book or series
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataChangeActor {
+	/// A job, e.g. a library scan or metadata fetch
+	Job(String),
+	/// A user, e.g. by editing the metadata or applying a candidate
+	User(String),
+}
+
+/// Where a change to the metadata of a book or series came from, and how to record it
+#[derive(Debug, Clone)]
+pub struct MetadataChange {
+	pub source: MetadataSource,
+	pub actor: Option<MetadataChangeActor>,
+	/// Whether to lock the fields which were changed
+	pub lock: bool,
+}
+
+impl MetadataChange {
+	pub fn new(source: MetadataSource, actor: Option<MetadataChangeActor>) -> Self {
+		Self {
+			source,
+			actor,
+			lock: false,
+		}
+	}
+
+	pub fn with_lock(self, lock: bool) -> Self {
+		Self { lock, ..self }
+	}
+}
+
+/// A field whose value is being written, with its value before and after the write
+struct FieldWrite {
+	field: String,
+	old_value: Option<serde_json::Value>,
+	new_value: Option<serde_json::Value>,
+}
+
+/// Split the fields being written into those which changed and can be written, and those
+/// which would have changed but are locked. Fields whose value is unchanged are dropped
+fn partition_writes(
+	writes: Vec<FieldWrite>,
+	locked_fields: &HashSet<String>,
+	change: &MetadataChange,
+) -> (Vec<FieldWrite>, Vec<FieldWrite>) {
+	let mut seen = HashSet::new();
+	writes
+		.into_iter()
+		.filter(|write| write.old_value != write.new_value)
+		.filter(|write| seen.insert(write.field.clone()))
+		.partition(|write| {
+			change.source.overrides_locks() || !locked_fields.contains(&write.field)
+		})
+}
+
+/// Record the source and history of the fields which were just written, locking them if
+/// requested
+async fn record_field_writes(
+	client: &PrismaClient,
+	target: &MetadataTarget,
+	writes: &[FieldWrite],
+	change: &MetadataChange,
+) -> Result<(), prisma_client_rust::QueryError> {
+	let source = change.source.to_string();
+	let (job_id, user_id) = match &change.actor {
+		Some(MetadataChangeActor::Job(id)) => (Some(id.clone()), None),
+		Some(MetadataChangeActor::User(id)) => (None, Some(id.clone())),
+		None => (None, None),
+	};
+
+	for write in writes {
+		let mut params = vec![metadata_field_state::source::set(Some(source.clone()))];
+		if change.lock {
+			params.push(metadata_field_state::locked::set(true));
+		}
+		client
+			.metadata_field_state()
+			.upsert(
+				target.field_state_unique(&write.field),
+				(
+					write.field.clone(),
+					params
+						.clone()
+						.into_iter()
+						.chain([target.field_state_connect()])
+						.collect(),
+				),
+				params,
+			)
+			.exec()
+			.await?;
+
+		client
+			.metadata_field_change()
+			.create(
+				write.field.clone(),
+				source.clone(),
+				vec![
+					metadata_field_change::old_value::set(
+						write.old_value.as_ref().map(ToString::to_string),
+					),
+					metadata_field_change::new_value::set(
+						write.new_value.as_ref().map(ToString::to_string),
+					),
+					metadata_field_change::job_id::set(job_id.clone()),
+					target.field_change_connect(),
+				]
+				.into_iter()
+				.chain(
+					user_id.clone().map(|id| {
+						metadata_field_change::user::connect(user::id::equals(id))
+					}),
+				)
+				.collect(),
+			)
+			.exec()
+			.await?;
+	}
+
+	Ok(())
+}
+
+fn applied_metadata(applied: &[FieldWrite], locked: &[FieldWrite]) -> AppliedMetadata {
+	AppliedMetadata {
+		applied: applied.iter().map(|write| write.field.clone()).collect(),
+		locked: locked.iter().map(|write| write.field.clone()).collect(),
+	}
+}
+
+/// Write the given fields of `metadata` to a book, skipping any locked fields unless the
+/// source of the change may override locks. Fields which changed have their source and a
+/// history entry recorded. This does not start a transaction, so callers should run it in
+/// one
+pub async fn write_media_metadata(
+	client: &PrismaClient,
+	media_id: &str,
+	metadata: &MediaMetadata,
+	fields: &[MediaMetadataField],
+	change: &MetadataChange,
+) -> Result<(AppliedMetadata, Option<media_metadata::Data>), prisma_client_rust::QueryError>
+{
+	let target = MetadataTarget::Media(media_id.to_string());
+	let existing = client
+		.media_metadata()
+		.find_unique(media_metadata::media_id::equals(media_id.to_string()))
+		.exec()
+		.await?;
+	let current = existing
+		.clone()
+		.map(MediaMetadata::from)
+		.unwrap_or_default();
+	let locked_fields = get_locked_fields(client, &target).await?;
+
+	let writes = fields
+		.iter()
+		.map(|field| FieldWrite {
+			field: field.to_string(),
+			old_value: current.field_value(*field),
+			new_value: metadata.field_value(*field),
+		})
+		.collect();
+	let (applied, locked) = partition_writes(writes, &locked_fields, change);
+	let result = applied_metadata(&applied, &locked);
+	if applied.is_empty() {
+		return Ok((result, existing));
+	}
+
+	let set_params = applied
+		.iter()
+		.filter_map(|write| write.field.parse::<MediaMetadataField>().ok())
+		.map(|field| metadata.field_set_param(field))
+		.collect::<Vec<_>>();
+	let updated = client
+		.media_metadata()
+		.upsert(
+			media_metadata::media_id::equals(media_id.to_string()),
+			set_params
+				.clone()
+				.into_iter()
+				.chain([media_metadata::media::connect(media::id::equals(
+					media_id.to_string(),
+				))])
+				.collect::<Vec<_>>(),
+			set_params,
+		)
+		.exec()
+		.await?;
+	record_field_writes(client, &target, &applied, change).await?;
+
+	Ok((result, Some(updated)))
+}
+
+/// Write the given fields of `metadata` to a series. See [write_media_metadata]
+pub async fn write_series_metadata(
+	client: &PrismaClient,
+	series_id: &str,
+	metadata: &SeriesMetadata,
+	fields: &[SeriesMetadataField],
+	change: &MetadataChange,
+) -> Result<
+	(AppliedMetadata, Option<series_metadata::Data>),
+	prisma_client_rust::QueryError,
+> {
+	let target = MetadataTarget::Series(series_id.to_string());
+	let existing = client
+		.series_metadata()
+		.find_unique(series_metadata::series_id::equals(series_id.to_string()))
+		.exec()
+		.await?;
+	let current = existing.clone().map(SeriesMetadata::from);
+	let locked_fields = get_locked_fields(client, &target).await?;
+
+	let writes = fields
+		.iter()
+		.map(|field| FieldWrite {
+			field: field.to_string(),
+			old_value: current
+				.as_ref()
+				.and_then(|current| current.field_value(*field)),
+			new_value: metadata.field_value(*field),
+		})
+		.collect();
+	let (applied, locked) = partition_writes(writes, &locked_fields, change);
+	let result = applied_metadata(&applied, &locked);
+	if applied.is_empty() {
+		return Ok((result, existing));
+	}
+
+	let set_params = applied
+		.iter()
+		.filter_map(|write| write.field.parse::<SeriesMetadataField>().ok())
+		.map(|field| metadata.field_set_param(field))
+		.collect::<Vec<_>>();
+	let updated = client
+		.series_metadata()
+		.upsert(
+			series_metadata::series_id::equals(series_id.to_string()),
+			(
+				metadata._type.clone(),
+				series::id::equals(series_id.to_string()),
+				set_params.clone(),
+			),
+			set_params,
+		)
+		.exec()
+		.await?;
+	record_field_writes(client, &target, &applied, change).await?;
+
+	Ok((result, Some(updated)))
+}
+
+/// Apply the given fields of a candidate to the metadata of a book, or every field the
+/// candidate has a value for when `fields` is `None`. Locked fields are never overwritten.
+/// If `lock` is set, the applied fields are locked so later scans and fetches won't change
+/// them
+pub async fn apply_media_candidate(
+	client: &PrismaClient,
+	media_id: &str,
+	candidate: &integrations::MetadataCandidate,
+	fields: Option<&[MediaMetadataField]>,
+	actor: Option<MetadataChangeActor>,
+	lock: bool,
+) -> CoreResult<AppliedMetadata> {
+	let metadata = MediaMetadata::from(candidate);
+	let fields = fields
+		.unwrap_or(MediaMetadataField::ALL)
+		.iter()
+		.copied()
+		.filter(|field| metadata.has_value(*field))
+		.collect::<Vec<_>>();
+	let change = MetadataChange::new(candidate.provider.into(), actor).with_lock(lock);
+
+	let (result, _) = client
+		._transaction()
+		.run(|client| async move {
+			write_media_metadata(&client, media_id, &metadata, &fields, &change).await
+		})
+		.await?;
+	if !result.applied.is_empty() {
+		index_media(client, &[media_id.to_string()]).await?;
+	}
+
+	Ok(result)
+}
+
+/// Apply the given fields of a candidate to the metadata of a series, or every field the
+/// candidate has a value for when `fields` is `None`. See [apply_media_candidate]
+pub async fn apply_series_candidate(
+	client: &PrismaClient,
+	series_id: &str,
+	candidate: &integrations::MetadataCandidate,
+	fields: Option<&[SeriesMetadataField]>,
+	actor: Option<MetadataChangeActor>,
+	lock: bool,
+) -> CoreResult<AppliedMetadata> {
+	let metadata = SeriesMetadata::from(candidate);
+	let fields = fields
+		.unwrap_or(SeriesMetadataField::ALL)
+		.iter()
+		.copied()
+		.filter(|field| metadata.has_value(*field))
+		.collect::<Vec<_>>();
+	let change = MetadataChange::new(candidate.provider.into(), actor).with_lock(lock);
+
+	let (result, _) = client
+		._transaction()
+		.run(|client| async move {
+			write_series_metadata(&client, series_id, &metadata, &fields, &change).await
+		})
+		.await?;
+	if result.applied.is_empty() {
+		return Ok(result);
+	}
+
+	// The series metadata is part of the search documents of its books
+	let media_ids = client
+		.media()
+		.find_many(vec![media::series_id::equals(Some(series_id.to_string()))])
+		.select(media::select!({ id }))
+		.exec()
+		.await?
+		.into_iter()
+		.map(|book| book.id)
+		.collect::<Vec<_>>();
+	index_media(client, &media_ids).await?;
+
+	Ok(result)
+}
+
+/// Get the history of changes to the metadata of a book or series, optionally limited to a
+/// single field, from the most to the least recent
+pub async fn get_field_changes(
+	client: &PrismaClient,
+	target: &MetadataTarget,
+	field: Option<String>,
+) -> CoreResult<Vec<metadata_field_change::Data>> {
+	let mut where_params = vec![target.field_change_filter()];
+	if let Some(field) = field {
+		where_params.push(metadata_field_change::field::equals(field));
+	}
+
+	Ok(client
+		.metadata_field_change()
+		.find_many(where_params)
+		.order_by(metadata_field_change::changed_at::order(
+			prisma_client_rust::Direction::Desc,
+		))
+		.exec()
+		.await?)
+}
+
+/// Replace the stored candidates of a book or series
+pub async fn replace_candidates(
+	client: &PrismaClient,
+	target: &MetadataTarget,
+	candidates: &[integrations::MetadataCandidate],
+) -> CoreResult<()> {
+	let creates = candidates
+		.iter()
+		.map(|candidate| {
+			serde_json::to_vec(candidate).map(|data| {
+				(
+					candidate.provider.to_string(),
+					candidate.provider_id.clone(),
+					candidate.confidence,
+					data,
+				)
+			})
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	client
+		._transaction()
+		.run(|client| async move {
+			client
+				.metadata_candidate()
+				.delete_many(vec![target.candidate_filter()])
+				.exec()
+				.await?;
+
+			for (provider, provider_id, confidence, data) in creates {
+				client
+					.metadata_candidate()
+					.create(
+						provider,
+						provider_id,
+						confidence,
+						data,
+						vec![target.candidate_connect()],
+					)
+					.exec()
+					.await?;
+			}
+
+			Ok::<_, prisma_client_rust::QueryError>(())
+		})
+		.await?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	fn write(
+		field: &str,
+		old_value: Option<&str>,
+		new_value: Option<&str>,
+	) -> FieldWrite {
+		FieldWrite {
+			field: field.to_string(),
+			old_value: old_value.map(|v| json!(v)),
+			new_value: new_value.map(|v| json!(v)),
+		}
+	}
+
+	fn fields(writes: &[FieldWrite]) -> Vec<&str> {
+		writes.iter().map(|write| write.field.as_str()).collect()
+	}
+
+	#[test]
+	fn test_partition_writes() {
+		let locked_fields = HashSet::from(["summary".to_string(), "notes".to_string()]);
+		let writes = vec![
+			write("title", Some("Dune"), Some("Dune Messiah")),
+			write("summary", None, Some("A summary")),
+			write("notes", Some("Notes"), Some("Notes")),
+			write("series", Some("Dune"), Some("Dune")),
+			write("title", Some("Dune"), Some("Dune Messiah")),
+		];
+
+		let change = MetadataChange::new(MetadataSource::File, None);
+		let (applied, locked) = partition_writes(writes, &locked_fields, &change);
+		assert_eq!(fields(&applied), vec!["title"]);
+		assert_eq!(fields(&locked), vec!["summary"]);
+	}
+
+	#[test]
+	fn test_partition_writes_user_overrides_locks() {
+		let locked_fields = HashSet::from(["summary".to_string()]);
+		let writes = vec![
+			write("title", Some("Dune"), None),
+			write("summary", None, Some("A summary")),
+		];
+
+		let change = MetadataChange::new(
+			MetadataSource::User,
+			Some(MetadataChangeActor::User("user".to_string())),
+		);
+		let (applied, locked) = partition_writes(writes, &locked_fields, &change);
+		assert_eq!(fields(&applied), vec!["title", "summary"]);
+		assert!(locked.is_empty());
+	}
+}
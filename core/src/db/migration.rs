@@ -1,4 +1,4 @@
-use crate::{error::CoreResult, prisma, CoreError};
+use crate::{db::search::ensure_search_index, error::CoreResult, prisma, CoreError};
 
 pub async fn run_migrations(client: &prisma::PrismaClient) -> CoreResult<()> {
 	tracing::info!("Running migrations...");
@@ -33,5 +33,8 @@ pub async fn run_migrations(client: &prisma::PrismaClient) -> CoreResult<()> {
 		tracing::info!("Database migration completed!");
 	}
 
+	// The search index is an FTS5 virtual table, which Prisma can't manage for us
+	ensure_search_index(client).await?;
+
 	Ok(())
 }
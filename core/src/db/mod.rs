@@ -3,8 +3,10 @@ mod common;
 pub(crate) mod dao;
 pub mod entity;
 pub mod filter;
+pub mod metadata;
 pub mod migration;
 pub mod query;
+pub mod search;
 
 pub use dao::*;
 
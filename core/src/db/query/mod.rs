@@ -1,2 +1,3 @@
 pub mod ordering;
 pub mod pagination;
+pub mod raw;
@@ -4,7 +4,7 @@ use utoipa::ToSchema;
 
 use crate::{
 	error::CoreError,
-	prisma::{job, library, log, media, series},
+	prisma::{audit_log, job, library, log, media, series},
 };
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Type, ToSchema)]
@@ -164,3 +164,24 @@ impl TryInto<log::OrderByParam> for QueryOrder {
 		})
 	}
 }
+
+impl TryInto<audit_log::OrderByParam> for QueryOrder {
+	type Error = CoreError;
+
+	fn try_into(self) -> Result<audit_log::OrderByParam, Self::Error> {
+		let dir: prisma_client_rust::Direction = self.direction.into();
+
+		Ok(match self.order_by.to_lowercase().as_str() {
+			"timestamp" => audit_log::timestamp::order(dir),
+			"action" => audit_log::action::order(dir),
+			"actor_username" => audit_log::actor_username::order(dir),
+			"target_type" => audit_log::target_type::order(dir),
+			_ => {
+				return Err(CoreError::InvalidQuery(format!(
+					"You cannot order audit logs by {:?}",
+					self.order_by
+				)))
+			},
+		})
+	}
+}
@@ -0,0 +1,124 @@
+use prisma_client_rust::{PrismaValue, Raw};
+
+/// A fragment of raw SQL, with a `{}` placeholder for each of its params. This is used to
+/// compose the few queries which Prisma can't express (e.g. aggregates over comma-separated
+/// metadata or the full-text search index) without ever formatting values into the SQL
+#[derive(Debug, Clone)]
+pub struct RawSql {
+	sql: String,
+	params: Vec<PrismaValue>,
+}
+
+impl RawSql {
+	pub fn new(sql: impl Into<String>, params: Vec<PrismaValue>) -> Self {
+		Self {
+			sql: sql.into(),
+			params,
+		}
+	}
+
+	/// A condition which every row satisfies
+	pub fn always() -> Self {
+		Self::new("1 = 1", vec![])
+	}
+
+	/// Join the conditions with `AND`. No conditions at all are always satisfied
+	pub fn and(conditions: impl IntoIterator<Item = Self>) -> Self {
+		Self::join(conditions, " AND ").unwrap_or_else(Self::always)
+	}
+
+	/// Join the conditions with `OR`. No conditions at all are never satisfied
+	pub fn or(conditions: impl IntoIterator<Item = Self>) -> Self {
+		Self::join(conditions, " OR ").unwrap_or_else(|| Self::new("1 = 0", vec![]))
+	}
+
+	fn join(conditions: impl IntoIterator<Item = Self>, separator: &str) -> Option<Self> {
+		conditions.into_iter().fold(None, |joined, condition| {
+			let condition = condition.wrapped();
+			Some(match joined {
+				Some(joined) => joined.push(separator).append(condition),
+				None => condition,
+			})
+		})
+	}
+
+	/// Match `column` against any of the given values. Note that Prisma doesn't support
+	/// [PrismaValue::List] for SQLite, so each value gets its own placeholder
+	pub fn in_list(column: &str, values: Vec<PrismaValue>) -> Self {
+		let placeholders = vec!["{}"; values.len()].join(", ");
+		Self::new(format!("{column} IN ({placeholders})"), values)
+	}
+
+	/// Wrap the SQL in parentheses, so it may be safely combined with other conditions
+	pub fn wrapped(self) -> Self {
+		Self::new(format!("({})", self.sql), self.params)
+	}
+
+	/// Append SQL which has no placeholders of its own
+	pub fn push(mut self, sql: &str) -> Self {
+		self.sql.push_str(sql);
+		self
+	}
+
+	/// Append a placeholder bound to the given value
+	pub fn push_param(mut self, value: PrismaValue) -> Self {
+		self.sql.push_str("{}");
+		self.params.push(value);
+		self
+	}
+
+	/// Append another fragment, along with its params
+	pub fn append(mut self, other: Self) -> Self {
+		self.sql.push_str(&other.sql);
+		self.params.extend(other.params);
+		self
+	}
+
+	/// Build the query which can be passed to `_query_raw` or `_execute_raw`
+	pub fn build(self) -> Raw {
+		Raw::new(&self.sql, self.params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_compose_raw_sql() {
+		let condition = RawSql::and([
+			RawSql::new("m.deleted_at IS NULL", vec![]),
+			RawSql::or([
+				RawSql::in_list(
+					"m.id",
+					vec![
+						PrismaValue::String("a".to_string()),
+						PrismaValue::String("b".to_string()),
+					],
+				),
+				RawSql::new("m.pages > {}", vec![PrismaValue::Int(10)]),
+			]),
+		]);
+
+		let query = RawSql::new("SELECT m.id FROM media m WHERE ", vec![])
+			.append(condition)
+			.push(" LIMIT ")
+			.push_param(PrismaValue::Int(5));
+
+		assert_eq!(
+			query.sql,
+			"SELECT m.id FROM media m WHERE (m.deleted_at IS NULL) AND ((m.id IN ({}, {})) OR (m.pages > {})) LIMIT {}"
+		);
+		assert!(matches!(
+			query.params.as_slice(),
+			[
+				PrismaValue::String(a),
+				PrismaValue::String(b),
+				PrismaValue::Int(10),
+				PrismaValue::Int(5),
+			] if a == "a" && b == "b"
+		));
+		assert_eq!(RawSql::or([]).sql, "1 = 0");
+		assert_eq!(RawSql::and([]).sql, "1 = 1");
+	}
+}
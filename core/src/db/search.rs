@@ -0,0 +1,397 @@
+//! A full-text search index of books, backed by an SQLite FTS5 virtual table. The searchable
+//! text of each book is stored in `media_search_documents` (see the `MediaSearchDocument`
+//! model), which is the external content of the `media_search_index` virtual table. Triggers
+//! on the documents table keep the index in sync, so maintaining the index is only a matter of
+//! upserting documents.
+//!
+//! The virtual table and triggers are created by [ensure_search_index] at startup, since they
+//! can't be described in the Prisma schema.
+
+use prisma_client_rust::{raw, PrismaValue};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	db::{entity::Media, query::raw::RawSql, CountQueryReturn},
+	error::CoreResult,
+	prisma::PrismaClient,
+};
+
+/// The maximum number of characters of extracted EPUB text stored for a single book
+pub const MAX_INDEXED_CONTENT_LENGTH: usize = 512 * 1024;
+
+/// The number of books whose documents are rebuilt in a single statement
+const INDEX_CHUNK_SIZE: usize = 500;
+
+// Private-use characters which mark highlighted terms in the raw output of FTS5. They are
+// replaced with HTML tags once the rest of the text has been escaped
+const HIGHLIGHT_START: char = '\u{E000}';
+const HIGHLIGHT_END: char = '\u{E001}';
+
+// Each statement is executed on its own, since raw queries may only contain a single statement
+const CREATE_SEARCH_INDEX: [&str; 4] = [
+	r"
+CREATE VIRTUAL TABLE IF NOT EXISTS media_search_index USING fts5(
+	title, series, creators, characters, tags, summary, content,
+	content = 'media_search_documents',
+	content_rowid = 'id',
+	tokenize = 'porter unicode61 remove_diacritics 2'
+)",
+	r"
+CREATE TRIGGER IF NOT EXISTS media_search_documents_ai AFTER INSERT ON media_search_documents BEGIN
+	INSERT INTO media_search_index(rowid, title, series, creators, characters, tags, summary, content)
+	VALUES (new.id, new.title, new.series, new.creators, new.characters, new.tags, new.summary, new.content);
+END",
+	r"
+CREATE TRIGGER IF NOT EXISTS media_search_documents_ad AFTER DELETE ON media_search_documents BEGIN
+	INSERT INTO media_search_index(media_search_index, rowid, title, series, creators, characters, tags, summary, content)
+	VALUES ('delete', old.id, old.title, old.series, old.creators, old.characters, old.tags, old.summary, old.content);
+END",
+	r"
+CREATE TRIGGER IF NOT EXISTS media_search_documents_au AFTER UPDATE ON media_search_documents BEGIN
+	INSERT INTO media_search_index(media_search_index, rowid, title, series, creators, characters, tags, summary, content)
+	VALUES ('delete', old.id, old.title, old.series, old.creators, old.characters, old.tags, old.summary, old.content);
+	INSERT INTO media_search_index(rowid, title, series, creators, characters, tags, summary, content)
+	VALUES (new.id, new.title, new.series, new.creators, new.characters, new.tags, new.summary, new.content);
+END",
+];
+
+/// Builds the document of every book matching the `{}` condition. This must stay in sync with
+/// the backfill in the `media_search_index` migration
+const UPSERT_DOCUMENTS: &str = r"
+INSERT INTO media_search_documents (media_id, title, series, creators, characters, tags, summary)
+SELECT
+	m.id,
+	COALESCE(NULLIF(TRIM(mm.title), ''), m.name),
+	TRIM(COALESCE(s.name, '') || ' ' || COALESCE(sm.title, '') || ' ' || COALESCE(mm.series, '') || ' ' || COALESCE(sm.publisher, '') || ' ' || COALESCE(sm.summary, '')),
+	TRIM(COALESCE(mm.writers, '') || ' ' || COALESCE(mm.pencillers, '') || ' ' || COALESCE(mm.inkers, '') || ' ' || COALESCE(mm.colorists, '') || ' ' || COALESCE(mm.letterers, '') || ' ' || COALESCE(mm.cover_artists, '') || ' ' || COALESCE(mm.editors, '') || ' ' || COALESCE(mm.publisher, '')),
+	TRIM(COALESCE(mm.characters, '') || ' ' || COALESCE(mm.teams, '')),
+	TRIM(COALESCE(mm.genre, '') || ' ' || COALESCE((SELECT GROUP_CONCAT(t.name, ' ') FROM _MediaToTag mt INNER JOIN tags t ON t.id = mt.B WHERE mt.A = m.id), '')),
+	COALESCE(mm.summary, '')
+FROM media m
+LEFT JOIN media_metadata mm ON mm.media_id = m.id
+LEFT JOIN series s ON s.id = m.series_id
+LEFT JOIN series_metadata sm ON sm.series_id = s.id
+WHERE {}
+ON CONFLICT (media_id) DO UPDATE SET
+	title = excluded.title,
+	series = excluded.series,
+	creators = excluded.creators,
+	characters = excluded.characters,
+	tags = excluded.tags,
+	summary = excluded.summary,
+	indexed_at = CURRENT_TIMESTAMP
+";
+
+/// Create the FTS5 index and the triggers which keep it in sync with `media_search_documents`,
+/// if they don't already exist. A newly created index is rebuilt from the existing documents.
+/// Documents of books which were deleted since the last scan are also removed
+pub async fn ensure_search_index(client: &PrismaClient) -> CoreResult<()> {
+	let existing = client
+		._query_raw::<CountQueryReturn>(raw!(
+			"SELECT COUNT(*) as count FROM sqlite_master WHERE type = 'table' AND name = 'media_search_index'"
+		))
+		.exec()
+		.await?;
+	let exists = existing.first().is_some_and(|result| result.count > 0);
+
+	for statement in CREATE_SEARCH_INDEX {
+		client._execute_raw(raw!(statement)).exec().await?;
+	}
+
+	if !exists {
+		tracing::info!("Building the search index from existing documents");
+		client
+			._execute_raw(raw!(
+				"INSERT INTO media_search_index(media_search_index) VALUES ('rebuild')"
+			))
+			.exec()
+			.await?;
+	}
+
+	let removed = remove_stale_documents(client).await?;
+	if removed > 0 {
+		tracing::debug!(removed, "Removed stale documents from the search index");
+	}
+
+	Ok(())
+}
+
+fn quoted_id_list(ids: &[String]) -> String {
+	// Note: Prisma (SQLite) doesn't support PrismaValue::List, so we need to manually format this
+	ids.iter()
+		.map(|id| format!("'{}'", id.replace('\'', "''")))
+		.collect::<Vec<_>>()
+		.join(",")
+}
+
+/// Rebuild the search documents of the given books from their current metadata, series and
+/// tags. Extracted content is left untouched, see [index_media_content]
+pub async fn index_media(client: &PrismaClient, media_ids: &[String]) -> CoreResult<u64> {
+	let mut indexed = 0;
+	for chunk in media_ids.chunks(INDEX_CHUNK_SIZE) {
+		let condition = format!("m.id IN ({})", quoted_id_list(chunk));
+		indexed += client
+			._execute_raw(raw!(&UPSERT_DOCUMENTS.replacen("{}", &condition, 1)))
+			.exec()
+			.await? as u64;
+	}
+	Ok(indexed)
+}
+
+/// Set the extracted text content of a book, truncated to [MAX_INDEXED_CONTENT_LENGTH]. The
+/// document of the book must already exist
+pub async fn index_media_content(
+	client: &PrismaClient,
+	media_id: String,
+	content: String,
+) -> CoreResult<()> {
+	let content = match content.char_indices().nth(MAX_INDEXED_CONTENT_LENGTH) {
+		Some((end, _)) => content[..end].to_string(),
+		None => content,
+	};
+
+	client
+		._execute_raw(raw!(
+			"UPDATE media_search_documents SET content = {} WHERE media_id = {}",
+			PrismaValue::String(content),
+			PrismaValue::String(media_id)
+		))
+		.exec()
+		.await?;
+
+	Ok(())
+}
+
+/// Remove the documents of books which no longer exist
+pub async fn remove_stale_documents(client: &PrismaClient) -> CoreResult<u64> {
+	Ok(client
+		._execute_raw(raw!(
+			"DELETE FROM media_search_documents WHERE media_id NOT IN (SELECT id FROM media)"
+		))
+		.exec()
+		.await? as u64)
+}
+
+/// Convert a user provided query into an FTS5 match expression. Every term is quoted, so that
+/// FTS5 syntax in the query (e.g. `AND`, `-` or `:`) is matched literally rather than causing
+/// a syntax error. Quoted phrases are preserved, and the last term is matched as a prefix so
+/// results appear while the user is still typing. Returns `None` if there is nothing to search
+pub fn to_match_expression(query: &str) -> Option<String> {
+	let mut terms = Vec::new();
+	for (index, part) in query.split('"').enumerate() {
+		// Every other part of the query is inside of quotes
+		if index % 2 == 1 {
+			let part = part.split_whitespace().collect::<Vec<_>>().join(" ");
+			if !part.is_empty() {
+				terms.push((part, true));
+			}
+		} else {
+			terms.extend(
+				part.split_whitespace()
+					.map(|term| (term.to_string(), false)),
+			);
+		}
+	}
+
+	let last = terms.len().checked_sub(1)?;
+	Some(
+		terms
+			.into_iter()
+			.enumerate()
+			.map(|(index, (term, is_phrase))| {
+				if index == last && !is_phrase {
+					format!("\"{term}\"*")
+				} else {
+					format!("\"{term}\"")
+				}
+			})
+			.collect::<Vec<_>>()
+			.join(" "),
+	)
+}
+
+/// Escape the raw output of an FTS5 auxiliary function as HTML, wrapping highlighted terms in
+/// `<mark>` tags
+fn to_highlighted_html(text: &str) -> String {
+	let mut html = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			HIGHLIGHT_START => html.push_str("<mark>"),
+			HIGHLIGHT_END => html.push_str("</mark>"),
+			'&' => html.push_str("&amp;"),
+			'<' => html.push_str("&lt;"),
+			'>' => html.push_str("&gt;"),
+			'"' => html.push_str("&quot;"),
+			'\'' => html.push_str("&#39;"),
+			_ => html.push(c),
+		}
+	}
+	html
+}
+
+#[derive(Deserialize)]
+struct SearchIndexRow {
+	media_id: String,
+	score: f64,
+	title: String,
+	snippet: String,
+}
+
+/// A book which matched a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchIndexHit {
+	pub media_id: String,
+	/// The relevance of the match, where higher is more relevant
+	pub score: f64,
+	/// The indexed title of the book, as HTML with the matched terms highlighted
+	pub title: String,
+	/// The most relevant fragment of the indexed text, as HTML with the matched terms
+	/// highlighted
+	pub snippet: String,
+}
+
+/// Search the index for the books matching the `books` condition over `media m`, e.g. the
+/// restrictions of a user, returning a page of hits ordered by relevance. Columns are weighted
+/// so that matches in titles rank above matches in series, creators, characters, tags,
+/// summaries and finally extracted content
+pub async fn search_media_index(
+	client: &PrismaClient,
+	query: &str,
+	books: RawSql,
+	skip: i64,
+	take: i64,
+) -> CoreResult<Vec<SearchIndexHit>> {
+	let Some(expression) = to_match_expression(query) else {
+		return Ok(vec![]);
+	};
+
+	let query = RawSql::new(
+		format!(
+			r"
+			SELECT
+				d.media_id AS media_id,
+				-bm25(media_search_index, 10.0, 5.0, 4.0, 3.0, 3.0, 1.5, 1.0) AS score,
+				highlight(media_search_index, 0, '{HIGHLIGHT_START}', '{HIGHLIGHT_END}') AS title,
+				snippet(media_search_index, -1, '{HIGHLIGHT_START}', '{HIGHLIGHT_END}', '…', 24) AS snippet
+			FROM media_search_index
+			INNER JOIN media_search_documents d ON d.id = media_search_index.rowid
+			INNER JOIN media m ON m.id = d.media_id
+			WHERE media_search_index MATCH {{}} AND "
+		),
+		vec![PrismaValue::String(expression)],
+	)
+	.append(books.wrapped())
+	.push(
+		r"
+			ORDER BY bm25(media_search_index, 10.0, 5.0, 4.0, 3.0, 3.0, 1.5, 1.0)
+			LIMIT ",
+	)
+	.push_param(PrismaValue::Int(take))
+	.push(" OFFSET ")
+	.push_param(PrismaValue::Int(skip));
+
+	let rows = client
+		._query_raw::<SearchIndexRow>(query.build())
+		.exec()
+		.await?;
+
+	Ok(rows
+		.into_iter()
+		.map(|row| SearchIndexHit {
+			media_id: row.media_id,
+			score: row.score,
+			title: to_highlighted_html(&row.title),
+			snippet: to_highlighted_html(&row.snippet),
+		})
+		.collect())
+}
+
+/// Count the books matching both the search and the `books` condition over `media m`, i.e.
+/// the total number of hits [search_media_index] pages through
+pub async fn count_media_index_matches(
+	client: &PrismaClient,
+	query: &str,
+	books: RawSql,
+) -> CoreResult<i64> {
+	let Some(expression) = to_match_expression(query) else {
+		return Ok(0);
+	};
+
+	let query = RawSql::new(
+		r"
+		SELECT COUNT(*) AS count
+		FROM media_search_index
+		INNER JOIN media_search_documents d ON d.id = media_search_index.rowid
+		INNER JOIN media m ON m.id = d.media_id
+		WHERE media_search_index MATCH {} AND ",
+		vec![PrismaValue::String(expression)],
+	)
+	.append(books.wrapped());
+
+	let result = client
+		._query_raw::<CountQueryReturn>(query.build())
+		.exec()
+		.await?;
+
+	Ok(result
+		.first()
+		.map(|result| result.count)
+		.unwrap_or_default())
+}
+
+/// A book returned by a full-text search, with the highlighted text which matched
+#[derive(Debug, Clone, Serialize, Deserialize, Type, ToSchema)]
+pub struct MediaSearchResult {
+	pub media: Media,
+	/// The relevance of the match, where higher is more relevant
+	pub score: f64,
+	/// The title of the book, as HTML with the matched terms wrapped in `<mark>` tags
+	pub highlighted_title: String,
+	/// The most relevant fragment of the book's indexed text, as HTML with the matched terms
+	/// wrapped in `<mark>` tags
+	pub snippet: String,
+}
+
+impl MediaSearchResult {
+	pub fn new(media: Media, hit: SearchIndexHit) -> Self {
+		Self {
+			media,
+			score: hit.score,
+			highlighted_title: hit.title,
+			snippet: hit.snippet,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_to_match_expression() {
+		assert_eq!(to_match_expression("   "), None);
+		assert_eq!(to_match_expression("bat"), Some("\"bat\"*".to_string()));
+		assert_eq!(
+			to_match_expression("dark knight"),
+			Some("\"dark\" \"knight\"*".to_string())
+		);
+		assert_eq!(
+			to_match_expression("\"the  dark knight\" miller"),
+			Some("\"the dark knight\" \"miller\"*".to_string())
+		);
+		assert_eq!(
+			to_match_expression("title:batman -robin"),
+			Some("\"title:batman\" \"-robin\"*".to_string())
+		);
+	}
+
+	#[test]
+	fn test_to_highlighted_html() {
+		assert_eq!(
+			to_highlighted_html("Tom & \u{E000}Jerry\u{E001} <3"),
+			"Tom &amp; <mark>Jerry</mark> &lt;3"
+		);
+	}
+}
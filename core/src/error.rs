@@ -24,6 +24,8 @@ pub enum CoreError {
 	InitializationError(String),
 	#[error("{0}")]
 	EmailerError(#[from] email::EmailError),
+	#[error("{0}")]
+	NotifierError(#[from] integrations::NotifierError),
 	#[error("Query error: {0}")]
 	QueryError(#[from] Box<prisma_client_rust::QueryError>),
 	#[error("Invalid query error: {0}")]
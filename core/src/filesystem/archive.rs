@@ -66,11 +66,10 @@ pub fn create_zip_archive(
 	destination: &Path,
 ) -> zip::result::ZipResult<PathBuf> {
 	// TODO: does it make sense to leave this ext logic up to the caller?
-	let mut ext = "cbz";
-
-	if original_ext != "cbr" {
-		ext = "zip";
-	}
+	let ext = match original_ext {
+		"cbr" | "cb7" | "cbt" => "cbz",
+		_ => "zip",
+	};
 
 	trace!("Calculated extension for zip file: {}", ext);
 
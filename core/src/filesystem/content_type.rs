@@ -20,6 +20,10 @@ pub enum ContentType {
 	COMIC_ZIP,
 	RAR,
 	COMIC_RAR,
+	SEVEN_ZIP,
+	COMIC_SEVEN_ZIP,
+	TAR,
+	COMIC_TAR,
 	AVIF,
 	HEIF,
 	PNG,
@@ -78,6 +82,10 @@ impl ContentType {
 			"cbz" => ContentType::COMIC_ZIP,
 			"rar" => ContentType::RAR,
 			"cbr" => ContentType::COMIC_RAR,
+			"7z" => ContentType::SEVEN_ZIP,
+			"cb7" => ContentType::COMIC_SEVEN_ZIP,
+			"tar" => ContentType::TAR,
+			"cbt" => ContentType::COMIC_TAR,
 			"avif" => ContentType::AVIF,
 			"heif" => ContentType::HEIF,
 			"png" => ContentType::PNG,
@@ -262,6 +270,34 @@ impl ContentType {
 		self == &ContentType::RAR || self == &ContentType::COMIC_RAR
 	}
 
+	/// Returns true if the content type is a 7-Zip archive.
+	///
+	/// ## Example
+	///
+	/// ```no_run
+	/// use stump_core::filesystem::ContentType;
+	///
+	/// let content_type = ContentType::COMIC_SEVEN_ZIP;
+	/// assert!(content_type.is_seven_zip());
+	/// ```
+	pub fn is_seven_zip(&self) -> bool {
+		self == &ContentType::SEVEN_ZIP || self == &ContentType::COMIC_SEVEN_ZIP
+	}
+
+	/// Returns true if the content type is a TAR archive.
+	///
+	/// ## Example
+	///
+	/// ```no_run
+	/// use stump_core::filesystem::ContentType;
+	///
+	/// let content_type = ContentType::COMIC_TAR;
+	/// assert!(content_type.is_tar());
+	/// ```
+	pub fn is_tar(&self) -> bool {
+		self == &ContentType::TAR || self == &ContentType::COMIC_TAR
+	}
+
 	/// Returns true if the content type is an EPUB archive.
 	///
 	/// ## Example
@@ -289,6 +325,10 @@ impl ContentType {
 			ContentType::COMIC_ZIP => "cbz",
 			ContentType::RAR => "rar",
 			ContentType::COMIC_RAR => "cbr",
+			ContentType::SEVEN_ZIP => "7z",
+			ContentType::COMIC_SEVEN_ZIP => "cb7",
+			ContentType::TAR => "tar",
+			ContentType::COMIC_TAR => "cbt",
 			ContentType::HEIF => "heif",
 			ContentType::PNG => "png",
 			ContentType::JPEG => "jpg",
@@ -318,6 +358,10 @@ impl From<&str> for ContentType {
 			"application/vnd.comicbook+zip" => ContentType::COMIC_ZIP,
 			"application/vnd.rar" => ContentType::RAR,
 			"application/vnd.comicbook-rar" => ContentType::COMIC_RAR,
+			"application/x-7z-compressed" => ContentType::SEVEN_ZIP,
+			"application/x-cb7" => ContentType::COMIC_SEVEN_ZIP,
+			"application/x-tar" => ContentType::TAR,
+			"application/x-cbt" => ContentType::COMIC_TAR,
 			"image/heif" => ContentType::HEIF,
 			"image/png" => ContentType::PNG,
 			"image/jpeg" => ContentType::JPEG,
@@ -342,6 +386,10 @@ impl std::fmt::Display for ContentType {
 			ContentType::COMIC_ZIP => write!(f, "application/vnd.comicbook+zip"),
 			ContentType::RAR => write!(f, "application/vnd.rar"),
 			ContentType::COMIC_RAR => write!(f, "application/vnd.comicbook-rar"),
+			ContentType::SEVEN_ZIP => write!(f, "application/x-7z-compressed"),
+			ContentType::COMIC_SEVEN_ZIP => write!(f, "application/x-cb7"),
+			ContentType::TAR => write!(f, "application/x-tar"),
+			ContentType::COMIC_TAR => write!(f, "application/x-cbt"),
 			ContentType::AVIF => write!(f, "image/avif"),
 			ContentType::HEIF => write!(f, "image/heif"),
 			ContentType::PNG => write!(f, "image/png"),
@@ -399,6 +447,12 @@ impl TryFrom<ContentType> for image::ImageFormat {
 			ContentType::COMIC_ZIP => Err(unsupported_error("ContentType::COMIC_ZIP")),
 			ContentType::RAR => Err(unsupported_error("ContentType::RAR")),
 			ContentType::COMIC_RAR => Err(unsupported_error("ContentType::COMIC_RAR")),
+			ContentType::SEVEN_ZIP => Err(unsupported_error("ContentType::SEVEN_ZIP")),
+			ContentType::COMIC_SEVEN_ZIP => {
+				Err(unsupported_error("ContentType::COMIC_SEVEN_ZIP"))
+			},
+			ContentType::TAR => Err(unsupported_error("ContentType::TAR")),
+			ContentType::COMIC_TAR => Err(unsupported_error("ContentType::COMIC_TAR")),
 			ContentType::TXT => Err(unsupported_error("ContentType::TXT")),
 			ContentType::UNKNOWN => Err(unsupported_error("ContentType::UNKNOWN")),
 		}
@@ -420,6 +474,13 @@ mod tests {
 		assert_eq!(ContentType::from_extension("cbz"), ContentType::COMIC_ZIP);
 		assert_eq!(ContentType::from_extension("rar"), ContentType::RAR);
 		assert_eq!(ContentType::from_extension("cbr"), ContentType::COMIC_RAR);
+		assert_eq!(ContentType::from_extension("7z"), ContentType::SEVEN_ZIP);
+		assert_eq!(
+			ContentType::from_extension("cb7"),
+			ContentType::COMIC_SEVEN_ZIP
+		);
+		assert_eq!(ContentType::from_extension("tar"), ContentType::TAR);
+		assert_eq!(ContentType::from_extension("cbt"), ContentType::COMIC_TAR);
 		assert_eq!(ContentType::from_extension("png"), ContentType::PNG);
 		assert_eq!(ContentType::from_extension("jpg"), ContentType::JPEG);
 		assert_eq!(ContentType::from_extension("jpeg"), ContentType::JPEG);
@@ -443,6 +504,11 @@ mod tests {
 		assert_eq!(ContentType::from_file("test.cbz"), ContentType::COMIC_ZIP);
 		assert_eq!(ContentType::from_file("test.rar"), ContentType::RAR);
 		assert_eq!(ContentType::from_file("test.cbr"), ContentType::COMIC_RAR);
+		assert_eq!(
+			ContentType::from_file("test.cb7"),
+			ContentType::COMIC_SEVEN_ZIP
+		);
+		assert_eq!(ContentType::from_file("test.cbt"), ContentType::COMIC_TAR);
 		assert_eq!(ContentType::from_file("test.png"), ContentType::PNG);
 		assert_eq!(ContentType::from_file("test.jpg"), ContentType::JPEG);
 		assert_eq!(ContentType::from_file("test.jpeg"), ContentType::JPEG);
@@ -548,6 +614,22 @@ mod tests {
 			ContentType::COMIC_RAR.mime_type(),
 			"application/vnd.comicbook-rar".to_string()
 		);
+		assert_eq!(
+			ContentType::SEVEN_ZIP.mime_type(),
+			"application/x-7z-compressed".to_string()
+		);
+		assert_eq!(
+			ContentType::COMIC_SEVEN_ZIP.mime_type(),
+			"application/x-cb7".to_string()
+		);
+		assert_eq!(
+			ContentType::TAR.mime_type(),
+			"application/x-tar".to_string()
+		);
+		assert_eq!(
+			ContentType::COMIC_TAR.mime_type(),
+			"application/x-cbt".to_string()
+		);
 		assert_eq!(ContentType::PNG.mime_type(), "image/png".to_string());
 		assert_eq!(ContentType::JPEG.mime_type(), "image/jpeg".to_string());
 		assert_eq!(ContentType::WEBP.mime_type(), "image/webp".to_string());
@@ -580,6 +662,8 @@ mod tests {
 		assert!(!ContentType::COMIC_ZIP.is_image());
 		assert!(!ContentType::RAR.is_image());
 		assert!(!ContentType::COMIC_RAR.is_image());
+		assert!(!ContentType::COMIC_SEVEN_ZIP.is_image());
+		assert!(!ContentType::COMIC_TAR.is_image());
 		assert!(!ContentType::TXT.is_image());
 		assert!(!ContentType::UNKNOWN.is_image());
 	}
@@ -617,6 +701,26 @@ mod tests {
 		assert!(!ContentType::COMIC_ZIP.is_rar());
 	}
 
+	#[test]
+	fn test_content_type_is_seven_zip() {
+		// 7-Zip archives
+		assert!(ContentType::SEVEN_ZIP.is_seven_zip());
+		assert!(ContentType::COMIC_SEVEN_ZIP.is_seven_zip());
+		// Not 7-Zip archives
+		assert!(!ContentType::ZIP.is_seven_zip());
+		assert!(!ContentType::COMIC_TAR.is_seven_zip());
+	}
+
+	#[test]
+	fn test_content_type_is_tar() {
+		// TAR archives
+		assert!(ContentType::TAR.is_tar());
+		assert!(ContentType::COMIC_TAR.is_tar());
+		// Not TAR archives
+		assert!(!ContentType::ZIP.is_tar());
+		assert!(!ContentType::COMIC_SEVEN_ZIP.is_tar());
+	}
+
 	#[test]
 	fn test_content_type_is_epub() {
 		// EPUB archives
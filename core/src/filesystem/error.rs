@@ -14,6 +14,8 @@ pub enum FileError {
 	ZipFileError(#[from] ZipError),
 	#[error("Archive contains no files")]
 	ArchiveEmptyError,
+	#[error("Archive entry would be extracted outside of its directory: {0}")]
+	UnsafeArchiveEntry(String),
 	#[error("Failed to deserialize file: {0}")]
 	DeserializeError(#[from] serde_json::Error),
 	#[error("Unable to open .epub file: {0}")]
@@ -42,6 +44,8 @@ pub enum FileError {
 	RarReadError,
 	#[error("Error reading RAR byte content")]
 	RarByteReadError(#[from] std::str::Utf8Error),
+	#[error("{0}")]
+	SevenZipError(#[from] sevenz_rust::Error),
 	#[error("Unsupported file type: {0}")]
 	UnsupportedFileType(String),
 	#[error("{0}")]
@@ -52,6 +56,8 @@ pub enum FileError {
 	DirectoryReadError,
 	#[error("Incorrect image processor for requested format")]
 	IncorrectProcessorError,
+	#[error("Failed to write metadata: {0}")]
+	MetadataWriteError(String),
 	#[error("An unknown error occurred: {0}")]
 	UnknownError(String),
 }
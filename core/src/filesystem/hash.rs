@@ -59,6 +59,27 @@ pub fn generate(path: &str, bytes: u64) -> Result<String, io::Error> {
 	Ok(encoded_digest)
 }
 
+/// Generate a SHA-256 checksum of the entire file. Unlike [generate], which only samples
+/// a few chunks of the file, this reads every byte and so is suitable for verifying that two
+/// files are identical. It is considerably slower for large files, so it is only computed on
+/// demand.
+pub fn generate_full<P: AsRef<std::path::Path>>(path: P) -> Result<String, io::Error> {
+	let mut file = std::fs::File::open(path)?;
+
+	let mut ring_context = Context::new(&SHA256);
+	let mut buffer = vec![0u8; 64 * 1024];
+
+	loop {
+		let bytes_read = file.read(&mut buffer)?;
+		if bytes_read == 0 {
+			break;
+		}
+		ring_context.update(&buffer[..bytes_read]);
+	}
+
+	Ok(HEXLOWER.encode(ring_context.finish().as_ref()))
+}
+
 /// Generate a hash for a file using a port of the Koreader hash algorithm, which is
 /// originally written in Lua. The algorithm reads the file in 1KB chunks, starting
 /// from the beginning, until it reaches the end of the file or 10 iterations. It isn't
@@ -114,6 +135,14 @@ mod tests {
 		PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("integration-tests/data/tall.pdf")
 	}
 
+	#[test]
+	fn test_generate_full() {
+		let bytes = std::fs::read(epub_path()).unwrap();
+		let expected = HEXLOWER.encode(ring::digest::digest(&SHA256, &bytes).as_ref());
+
+		assert_eq!(generate_full(epub_path()).unwrap(), expected);
+	}
+
 	// https://github.com/koreader/koreader/blob/master/spec/unit/util_spec.lua#L339-L341
 	#[test]
 	fn test_koreader_hash_epub() {
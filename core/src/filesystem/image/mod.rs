@@ -1,5 +1,8 @@
 mod error;
 mod generic;
+mod page;
+mod page_cache;
+mod perceptual;
 mod process;
 mod thumbnail;
 mod webp;
@@ -7,10 +10,17 @@ mod webp;
 pub use self::webp::WebpProcessor;
 pub use error::ProcessorError;
 pub use generic::GenericImageProcessor;
+pub use page::{
+	get_transformed_page, transform_page, PageSplit, PageTransformOptions,
+	DEFAULT_PAGE_QUALITY,
+};
+pub use page_cache::PageCache;
+pub use perceptual::{difference_hash, hamming_distance};
 pub use process::{
 	ImageFormat, ImageProcessor, ImageProcessorOptions, ImageResizeMode,
 	ImageResizeOptions, ScaledDimensionResize,
 };
+use std::io::Cursor;
 pub use thumbnail::*;
 use tokio::{sync::oneshot, task::spawn_blocking};
 
@@ -58,6 +68,23 @@ pub async fn resize_image(
 	Ok(resized_image)
 }
 
+/// Downscale an image so that it is at most `max_width` pixels wide, preserving the aspect
+/// ratio. Images which are already narrow enough are returned untouched
+pub async fn resize_image_to_max_width(
+	buf: Vec<u8>,
+	max_width: u32,
+) -> Result<Vec<u8>, ProcessorError> {
+	let (width, _) = image::ImageReader::new(Cursor::new(&buf))
+		.with_guessed_format()?
+		.into_dimensions()?;
+
+	if width <= max_width {
+		Ok(buf)
+	} else {
+		resize_image(buf, ScaledDimensionResize::Width(max_width)).await
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use std::path::PathBuf;
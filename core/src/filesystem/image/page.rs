@@ -0,0 +1,454 @@
+use std::io::Cursor;
+
+use image::{
+	codecs::jpeg::JpegEncoder, imageops, DynamicImage, EncodableLayout, GenericImageView,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+use webp::Encoder;
+
+use crate::{
+	config::StumpConfig,
+	filesystem::{content_type::ContentType, media::get_page, FileError},
+};
+
+use super::{page_cache::PageCache, ImageFormat, ProcessorError};
+
+/// The quality used when encoding a transformed page, if one isn't requested
+pub const DEFAULT_PAGE_QUALITY: f32 = 90.0;
+/// The largest width or height a page may be transformed to. This bounds the memory used
+/// when resizing, since the dimensions are otherwise controlled by the client
+pub const MAX_PAGE_DIMENSION: u32 = 8192;
+/// Pixels with a luma value at or above this threshold are considered part of a white border
+const TRIM_WHITE_THRESHOLD: u8 = 240;
+/// The fraction of pixels in a row or column which must be non-white for it to be considered
+/// content rather than border. This prevents specks of dust in scans from defeating the trim
+const TRIM_CONTENT_RATIO: f32 = 0.005;
+
+/// Which half of a double-page spread to return when splitting a page
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSplit {
+	Left,
+	Right,
+}
+
+/// Transformations to apply to a page before it is delivered to a client. Transformations
+/// are applied in the following order: split, trim, resize and finally encode.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, Type, ToSchema, PartialEq)]
+pub struct PageTransformOptions {
+	/// The maximum width of the page. The page is scaled to fit, maintaining its aspect ratio
+	#[serde(default)]
+	#[specta(optional)]
+	pub max_width: Option<u32>,
+	/// The maximum height of the page. The page is scaled to fit, maintaining its aspect ratio
+	#[serde(default)]
+	#[specta(optional)]
+	pub max_height: Option<u32>,
+	/// Whether pages smaller than the maximum dimensions should be upscaled to fit them
+	#[serde(default)]
+	pub upscale: bool,
+	/// The format to encode the page as. Omitting this value will keep the format of the
+	/// original page, if it is supported, and otherwise use JPEG
+	#[serde(default)]
+	#[specta(optional)]
+	pub format: Option<ImageFormat>,
+	/// The quality to encode the page with, between 0.0 and 100.0. This is ignored for PNGs.
+	/// Omitting this value will use [`DEFAULT_PAGE_QUALITY`]
+	#[serde(default)]
+	#[specta(optional)]
+	pub quality: Option<f32>,
+	/// Whether to trim the white borders around the page
+	#[serde(default)]
+	pub trim: bool,
+	/// Which half of a double-page spread to return. Pages which are not wider than they are
+	/// tall are considered single pages and are not split
+	#[serde(default)]
+	#[specta(optional)]
+	pub split: Option<PageSplit>,
+}
+
+impl PageTransformOptions {
+	/// Whether no transformations have been requested, in which case the original page
+	/// should be delivered as-is
+	pub fn is_empty(&self) -> bool {
+		self == &PageTransformOptions::default()
+	}
+
+	/// Validate the transform options to ensure that they are valid
+	pub fn validate(&self) -> Result<(), ProcessorError> {
+		if let Some(quality) = self.quality {
+			if !(0.0..=100.0).contains(&quality) {
+				return Err(ProcessorError::InvalidQuality);
+			}
+		}
+
+		if self.max_width == Some(0) || self.max_height == Some(0) {
+			return Err(ProcessorError::InvalidConfiguration(
+				"The maximum width and height must be greater than 0".to_string(),
+			));
+		}
+
+		if self.max_width > Some(MAX_PAGE_DIMENSION)
+			|| self.max_height > Some(MAX_PAGE_DIMENSION)
+		{
+			return Err(ProcessorError::InvalidConfiguration(format!(
+				"The maximum width and height must not exceed {MAX_PAGE_DIMENSION}"
+			)));
+		}
+
+		Ok(())
+	}
+}
+
+/// Split a double-page spread, returning the requested half. Portrait pages are returned
+/// unchanged
+fn split_image(image: DynamicImage, split: PageSplit) -> DynamicImage {
+	let (width, height) = image.dimensions();
+	if width <= height {
+		return image;
+	}
+
+	let half = width / 2;
+	match split {
+		PageSplit::Left => image.crop_imm(0, 0, half, height),
+		PageSplit::Right => image.crop_imm(half, 0, width - half, height),
+	}
+}
+
+/// Find the bounds of the content of the image, as `(x, y, width, height)`, excluding any
+/// white borders. Returns `None` if the image is entirely white
+fn content_bounds(image: &DynamicImage) -> Option<(u32, u32, u32, u32)> {
+	let luma = image.to_luma8();
+	let (width, height) = luma.dimensions();
+
+	let mut row_counts = vec![0u32; height as usize];
+	let mut column_counts = vec![0u32; width as usize];
+	for (x, y, pixel) in luma.enumerate_pixels() {
+		if pixel.0[0] < TRIM_WHITE_THRESHOLD {
+			row_counts[y as usize] += 1;
+			column_counts[x as usize] += 1;
+		}
+	}
+
+	let content_range = |counts: &[u32], length: u32| {
+		let min_count = ((length as f32 * TRIM_CONTENT_RATIO).ceil() as u32).max(1);
+		let start = counts.iter().position(|count| *count >= min_count)?;
+		let end = counts.iter().rposition(|count| *count >= min_count)?;
+		Some((start as u32, (end - start + 1) as u32))
+	};
+
+	let (y, content_height) = content_range(&row_counts, width)?;
+	let (x, content_width) = content_range(&column_counts, height)?;
+
+	Some((x, y, content_width, content_height))
+}
+
+fn trim_image(image: DynamicImage) -> DynamicImage {
+	match content_bounds(&image) {
+		Some((x, y, width, height)) => image.crop_imm(x, y, width, height),
+		None => image,
+	}
+}
+
+/// Calculate the dimensions of the image after fitting it within the maximum dimensions,
+/// maintaining its aspect ratio. Upscaling never grows the image beyond
+/// [`MAX_PAGE_DIMENSION`]. Returns `None` if the image should not be resized
+fn fitted_dimensions(
+	(width, height): (u32, u32),
+	max_width: Option<u32>,
+	max_height: Option<u32>,
+	upscale: bool,
+) -> Option<(u32, u32)> {
+	let width_factor = max_width.map(|max| max as f32 / width as f32);
+	let height_factor = max_height.map(|max| max as f32 / height as f32);
+
+	let mut factor = match (width_factor, height_factor) {
+		(Some(w), Some(h)) => w.min(h),
+		(Some(factor), None) | (None, Some(factor)) => factor,
+		(None, None) => return None,
+	};
+
+	if factor > 1.0 {
+		let max_factor = MAX_PAGE_DIMENSION as f32 / width.max(height) as f32;
+		factor = factor.min(max_factor).max(1.0);
+	}
+
+	if factor == 1.0 || (factor > 1.0 && !upscale) {
+		return None;
+	}
+
+	Some((
+		((width as f32 * factor).round() as u32).max(1),
+		((height as f32 * factor).round() as u32).max(1),
+	))
+}
+
+fn encode_image(
+	image: DynamicImage,
+	format: ImageFormat,
+	quality: f32,
+) -> Result<Vec<u8>, ProcessorError> {
+	match format {
+		ImageFormat::Jpeg => {
+			// JPEG has no alpha channel, and the encoder only supports 8-bit images
+			let image = if image.color().has_color() {
+				DynamicImage::from(image.into_rgb8())
+			} else {
+				DynamicImage::from(image.into_luma8())
+			};
+
+			let mut buffer = Cursor::new(vec![]);
+			let encoder = JpegEncoder::new_with_quality(&mut buffer, quality as u8);
+			image.write_with_encoder(encoder)?;
+			Ok(buffer.into_inner())
+		},
+		ImageFormat::Png => {
+			let mut buffer = Cursor::new(vec![]);
+			image.write_to(&mut buffer, image::ImageFormat::Png)?;
+			Ok(buffer.into_inner())
+		},
+		ImageFormat::Webp => {
+			// The encoder only supports 8-bit RGB(A) images, so e.g. grayscale scans
+			// must be converted first
+			let image = if image.color().has_alpha() {
+				DynamicImage::from(image.into_rgba8())
+			} else {
+				DynamicImage::from(image.into_rgb8())
+			};
+			let encoder = Encoder::from_image(&image)
+				.map_err(|err| FileError::WebpEncodeError(err.to_string()))?;
+			Ok(encoder.encode(quality).as_bytes().to_vec())
+		},
+	}
+}
+
+/// Apply the transformations to the bytes of a page, returning the content type and bytes
+/// of the transformed page
+pub fn transform_page(
+	buf: &[u8],
+	options: &PageTransformOptions,
+) -> Result<(ContentType, Vec<u8>), ProcessorError> {
+	let source_format = image::guess_format(buf)?;
+	let mut image = image::load_from_memory(buf)?;
+
+	if let Some(split) = options.split {
+		image = split_image(image, split);
+	}
+
+	if options.trim {
+		image = trim_image(image);
+	}
+
+	if let Some((width, height)) = fitted_dimensions(
+		image.dimensions(),
+		options.max_width,
+		options.max_height,
+		options.upscale,
+	) {
+		image = image.resize_exact(width, height, imageops::FilterType::Lanczos3);
+	}
+
+	let format = options.format.clone().unwrap_or(match source_format {
+		image::ImageFormat::Png => ImageFormat::Png,
+		image::ImageFormat::WebP => ImageFormat::Webp,
+		_ => ImageFormat::Jpeg,
+	});
+	let quality = options.quality.unwrap_or(DEFAULT_PAGE_QUALITY);
+	let content_type = ContentType::from(format.clone());
+
+	Ok((content_type, encode_image(image, format, quality)?))
+}
+
+/// Get a page of a book with the transformations applied. Transformed pages are stored in
+/// the [`PageCache`], keyed by the book, its `version` and the options, so that repeated
+/// requests don't need to re-process the page.
+///
+/// The `version` should change whenever the file of the book changes, e.g. its size and
+/// modification time, so that stale pages are not served from the cache.
+pub async fn get_transformed_page(
+	media_id: &str,
+	path: &str,
+	version: &str,
+	page: i32,
+	options: PageTransformOptions,
+	config: &StumpConfig,
+) -> Result<(ContentType, Vec<u8>), ProcessorError> {
+	options.validate()?;
+
+	let cache = PageCache::new(config);
+	let key = PageCache::key(media_id, version, page, &options);
+	let path = path.to_string();
+	let config = config.clone();
+
+	tokio::task::spawn_blocking(move || {
+		if let Some(cached) = cache.get(&key) {
+			tracing::trace!(?key, "Serving transformed page from cache");
+			return Ok(cached);
+		}
+
+		let (_, buf) = get_page(&path, page, &config)?;
+		let (content_type, transformed) = transform_page(&buf, &options)?;
+
+		if let Err(error) = cache.put(&key, &transformed) {
+			tracing::warn!(?error, ?key, "Failed to cache transformed page");
+		}
+
+		Ok((content_type, transformed))
+	})
+	.await
+	.map_err(|e| ProcessorError::UnknownError(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::filesystem::image::tests::{get_test_jpg_path, get_test_png_path};
+
+	fn bordered_image() -> DynamicImage {
+		// A 100x60 white image with a black 20x10 block at (30, 20)
+		let mut image = image::RgbImage::from_pixel(100, 60, image::Rgb([255, 255, 255]));
+		for x in 30..50 {
+			for y in 20..30 {
+				image.put_pixel(x, y, image::Rgb([0, 0, 0]));
+			}
+		}
+		DynamicImage::ImageRgb8(image)
+	}
+
+	#[test]
+	fn test_trim_image() {
+		let trimmed = trim_image(bordered_image());
+		assert_eq!(trimmed.dimensions(), (20, 10));
+	}
+
+	#[test]
+	fn test_trim_blank_image() {
+		let image = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+			10,
+			10,
+			image::Rgb([255, 255, 255]),
+		));
+		assert_eq!(trim_image(image).dimensions(), (10, 10));
+	}
+
+	#[test]
+	fn test_split_image() {
+		let left = split_image(bordered_image(), PageSplit::Left);
+		assert_eq!(left.dimensions(), (50, 60));
+		// The black block starts in the left half
+		assert_eq!(left.get_pixel(30, 20).0[0], 0);
+
+		let right = split_image(bordered_image(), PageSplit::Right);
+		assert_eq!(right.dimensions(), (50, 60));
+		assert_eq!(right.get_pixel(0, 20).0[0], 255);
+
+		// Portrait pages are not split
+		let portrait = DynamicImage::new_rgb8(60, 100);
+		assert_eq!(
+			split_image(portrait, PageSplit::Left).dimensions(),
+			(60, 100)
+		);
+	}
+
+	#[test]
+	fn test_fitted_dimensions() {
+		assert_eq!(
+			fitted_dimensions((1000, 2000), Some(500), None, false),
+			Some((500, 1000))
+		);
+		assert_eq!(
+			fitted_dimensions((1000, 2000), Some(500), Some(500), false),
+			Some((250, 500))
+		);
+		assert_eq!(fitted_dimensions((100, 200), Some(500), None, false), None);
+		assert_eq!(
+			fitted_dimensions((100, 200), Some(500), None, true),
+			Some((500, 1000))
+		);
+		assert_eq!(fitted_dimensions((100, 200), None, None, true), None);
+	}
+
+	#[test]
+	fn test_fitted_dimensions_clamps_upscale() {
+		assert_eq!(
+			fitted_dimensions((10, 100), Some(MAX_PAGE_DIMENSION), None, true),
+			Some((819, MAX_PAGE_DIMENSION))
+		);
+		// Images already larger than the limit are left alone rather than upscaled
+		assert_eq!(
+			fitted_dimensions((100, 10_000), Some(500), None, true),
+			None
+		);
+	}
+
+	#[test]
+	fn test_transform_page() {
+		let buf = std::fs::read(get_test_jpg_path()).expect("Failed to read test image");
+		let options = PageTransformOptions {
+			max_width: Some(100),
+			format: Some(ImageFormat::Webp),
+			quality: Some(75.0),
+			..Default::default()
+		};
+
+		let (content_type, transformed) =
+			transform_page(&buf, &options).expect("Failed to transform page");
+		assert_eq!(content_type, ContentType::WEBP);
+
+		let image =
+			image::load_from_memory_with_format(&transformed, image::ImageFormat::WebP)
+				.expect("Transformed page should be a valid webp image");
+		assert_eq!(image.dimensions().0, 100);
+	}
+
+	#[test]
+	fn test_transform_page_keeps_format() {
+		let buf = std::fs::read(get_test_png_path()).expect("Failed to read test image");
+		let options = PageTransformOptions {
+			max_height: Some(50),
+			..Default::default()
+		};
+
+		let (content_type, _) =
+			transform_page(&buf, &options).expect("Failed to transform page");
+		assert_eq!(content_type, ContentType::PNG);
+	}
+
+	#[test]
+	fn test_validate() {
+		assert!(PageTransformOptions::default().is_empty());
+		assert!(PageTransformOptions {
+			quality: Some(101.0),
+			..Default::default()
+		}
+		.validate()
+		.is_err());
+		assert!(PageTransformOptions {
+			max_width: Some(0),
+			..Default::default()
+		}
+		.validate()
+		.is_err());
+		assert!(PageTransformOptions {
+			max_width: Some(MAX_PAGE_DIMENSION + 1),
+			..Default::default()
+		}
+		.validate()
+		.is_err());
+		assert!(PageTransformOptions {
+			max_height: Some(u32::MAX),
+			..Default::default()
+		}
+		.validate()
+		.is_err());
+		assert!(PageTransformOptions {
+			max_width: Some(MAX_PAGE_DIMENSION),
+			max_height: Some(MAX_PAGE_DIMENSION),
+			..Default::default()
+		}
+		.validate()
+		.is_ok());
+	}
+}
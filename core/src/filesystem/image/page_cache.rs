@@ -0,0 +1,277 @@
+use std::{
+	collections::HashMap,
+	fs::{self, File},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::{LazyLock, Mutex, PoisonError},
+	time::SystemTime,
+};
+
+use data_encoding::HEXLOWER;
+use ring::digest::{Context, SHA256};
+
+use crate::{config::StumpConfig, filesystem::content_type::ContentType};
+
+use super::page::PageTransformOptions;
+
+/// The approximate size of each cache directory, shared by every [`PageCache`] using it. A
+/// directory is scanned the first time it is written to, and its size is tracked as entries
+/// are written afterwards. Overwritten entries are counted twice, so the size is corrected
+/// whenever the directory is rescanned for eviction.
+static CACHE_SIZES: LazyLock<Mutex<HashMap<PathBuf, u64>>> =
+	LazyLock::new(Default::default);
+
+/// A size-bounded, least-recently-used cache of transformed pages stored on disk. Each entry
+/// is a single file in the cache directory, and the modification time of the file is used
+/// to track when it was last used. Once the cache grows beyond its maximum size, the least
+/// recently used entries are removed until it is back under the low watermark.
+///
+/// Other than the size of its directory, the cache holds no state in memory, so it is cheap
+/// to construct wherever it is needed.
+#[derive(Debug, Clone)]
+pub struct PageCache {
+	dir: PathBuf,
+	max_size: u64,
+}
+
+impl PageCache {
+	pub fn new(config: &StumpConfig) -> Self {
+		Self {
+			dir: config.get_page_cache_dir(),
+			max_size: config.page_cache_size,
+		}
+	}
+
+	/// Whether the cache is enabled. A maximum size of 0 disables the cache
+	pub fn is_enabled(&self) -> bool {
+		self.max_size > 0
+	}
+
+	/// The size the cache is reduced to once it exceeds its maximum size. Evicting a little
+	/// more than is strictly needed means eviction doesn't run on every insert once full
+	fn low_watermark(&self) -> u64 {
+		self.max_size / 10 * 9
+	}
+
+	/// Generate the key of a transformed page. The `version` of the book should change
+	/// whenever its file changes, so that stale entries are never read
+	pub fn key(
+		media_id: &str,
+		version: &str,
+		page: i32,
+		options: &PageTransformOptions,
+	) -> String {
+		let mut context = Context::new(&SHA256);
+		context.update(media_id.as_bytes());
+		context.update(version.as_bytes());
+		context.update(&page.to_le_bytes());
+		context.update(
+			serde_json::to_string(options)
+				.unwrap_or_default()
+				.as_bytes(),
+		);
+		HEXLOWER.encode(context.finish().as_ref())
+	}
+
+	fn entry_path(&self, key: &str) -> PathBuf {
+		self.dir.join(key)
+	}
+
+	/// Read an entry from the cache, marking it as recently used
+	pub fn get(&self, key: &str) -> Option<(ContentType, Vec<u8>)> {
+		if !self.is_enabled() {
+			return None;
+		}
+
+		let path = self.entry_path(key);
+		let bytes = fs::read(&path).ok()?;
+
+		if let Err(error) = File::options()
+			.write(true)
+			.open(&path)
+			.and_then(|file| file.set_modified(SystemTime::now()))
+		{
+			tracing::debug!(?error, ?path, "Failed to update page cache entry");
+		}
+
+		Some((ContentType::from_bytes(&bytes), bytes))
+	}
+
+	/// Write an entry to the cache, evicting the least recently used entries if the cache
+	/// has grown too large
+	pub fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+		if !self.is_enabled() || bytes.len() as u64 > self.max_size {
+			return Ok(());
+		}
+
+		fs::create_dir_all(&self.dir)?;
+
+		// Write to a uniquely named temporary file first, so that a concurrent read never sees
+		// a partial entry and concurrent writes of the same entry don't clobber each other
+		let mut temp_file = tempfile::NamedTempFile::new_in(&self.dir)?;
+		temp_file.write_all(bytes)?;
+		temp_file
+			.persist(self.entry_path(key))
+			.map_err(|error| error.error)?;
+
+		let mut sizes = CACHE_SIZES.lock().unwrap_or_else(PoisonError::into_inner);
+		let size = match sizes.get_mut(&self.dir) {
+			Some(size) => {
+				*size += bytes.len() as u64;
+				*size
+			},
+			None => {
+				let size = scan_entries(&self.dir)?
+					.iter()
+					.map(|(_, size, _)| size)
+					.sum();
+				sizes.insert(self.dir.clone(), size);
+				size
+			},
+		};
+
+		if size > self.max_size {
+			let size = self.evict()?;
+			sizes.insert(self.dir.clone(), size);
+		}
+
+		Ok(())
+	}
+
+	/// Remove the least recently used entries until the cache is under its low watermark, if
+	/// it has exceeded its maximum size. Returns the size of the cache after eviction
+	fn evict(&self) -> io::Result<u64> {
+		let mut entries = scan_entries(&self.dir)?;
+
+		let mut total_size = entries.iter().map(|(_, size, _)| size).sum::<u64>();
+		if total_size <= self.max_size {
+			return Ok(total_size);
+		}
+
+		entries.sort_by_key(|(_, _, last_used)| *last_used);
+
+		let low_watermark = self.low_watermark();
+		for (path, size, _) in entries {
+			if total_size <= low_watermark {
+				break;
+			}
+
+			match fs::remove_file(&path) {
+				Ok(_) => total_size -= size,
+				Err(error) => {
+					tracing::warn!(?error, ?path, "Failed to evict page cache entry")
+				},
+			}
+		}
+
+		tracing::debug!(total_size, "Evicted entries from the page cache");
+
+		Ok(total_size)
+	}
+}
+
+/// Get the path, size and last use of every entry in the cache directory
+fn scan_entries(dir: &Path) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+	Ok(fs::read_dir(dir)?
+		.filter_map(Result::ok)
+		.filter_map(|entry| {
+			let metadata = entry.metadata().ok()?;
+			let last_used = metadata.modified().ok()?;
+			metadata
+				.is_file()
+				.then(|| (entry.path(), metadata.len(), last_used))
+		})
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cache(dir: PathBuf, max_size: u64) -> PageCache {
+		PageCache { dir, max_size }
+	}
+
+	#[test]
+	fn test_key() {
+		let options = PageTransformOptions {
+			max_width: Some(800),
+			..Default::default()
+		};
+
+		let key = PageCache::key("id", "v1", 1, &options);
+		assert_eq!(key, PageCache::key("id", "v1", 1, &options));
+		assert_ne!(key, PageCache::key("id", "v2", 1, &options));
+		assert_ne!(key, PageCache::key("id", "v1", 2, &options));
+		assert_ne!(
+			key,
+			PageCache::key("id", "v1", 1, &PageTransformOptions::default())
+		);
+	}
+
+	#[test]
+	fn test_get_and_put() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let cache = cache(tempdir.path().join("pages"), 1024);
+
+		assert!(cache.get("missing").is_none());
+
+		cache
+			.put("entry", &[1, 2, 3])
+			.expect("Failed to write entry");
+		let (_, bytes) = cache.get("entry").expect("Entry should be cached");
+		assert_eq!(bytes, vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn test_eviction() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let cache = cache(tempdir.path().to_path_buf(), 100);
+
+		cache.put("first", &[0; 40]).expect("Failed to write entry");
+		// Ensure the entries have distinct modification times
+		std::thread::sleep(std::time::Duration::from_millis(20));
+		cache
+			.put("second", &[0; 40])
+			.expect("Failed to write entry");
+		std::thread::sleep(std::time::Duration::from_millis(20));
+		// Using the first entry should make the second the least recently used
+		assert!(cache.get("first").is_some());
+		std::thread::sleep(std::time::Duration::from_millis(20));
+		cache.put("third", &[0; 40]).expect("Failed to write entry");
+
+		assert!(cache.get("first").is_some());
+		assert!(cache.get("second").is_none());
+		assert!(cache.get("third").is_some());
+	}
+
+	#[test]
+	fn test_concurrent_puts() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let cache = cache(tempdir.path().to_path_buf(), 1024);
+
+		std::thread::scope(|scope| {
+			for _ in 0..8 {
+				scope.spawn(|| {
+					cache.put("entry", &[1; 64]).expect("Failed to write entry")
+				});
+			}
+		});
+
+		let (_, bytes) = cache.get("entry").expect("Entry should be cached");
+		assert_eq!(bytes, vec![1; 64]);
+		// No temporary files should be left behind
+		assert_eq!(fs::read_dir(tempdir.path()).unwrap().count(), 1);
+	}
+
+	#[test]
+	fn test_disabled() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let cache = cache(tempdir.path().to_path_buf(), 0);
+
+		cache
+			.put("entry", &[1, 2, 3])
+			.expect("Failed to write entry");
+		assert!(cache.get("entry").is_none());
+	}
+}
@@ -0,0 +1,89 @@
+use image::{imageops::FilterType, GenericImageView};
+
+use super::ProcessorError;
+
+/// The width of the image a difference hash is computed from. One more than the height, so
+/// that each row produces 8 comparisons of adjacent pixels
+const DHASH_WIDTH: u32 = 9;
+const DHASH_HEIGHT: u32 = 8;
+
+/// Compute a perceptual difference hash (dHash) of an image. The image is reduced to a 9x8
+/// grayscale thumbnail, and each bit of the hash records whether a pixel is brighter than its
+/// right-hand neighbour. Visually similar images, e.g. the same cover at a different
+/// resolution or compression level, produce hashes with a small hamming distance
+pub fn difference_hash(buf: &[u8]) -> Result<u64, ProcessorError> {
+	let image = image::load_from_memory(buf)?.grayscale().resize_exact(
+		DHASH_WIDTH,
+		DHASH_HEIGHT,
+		FilterType::Triangle,
+	);
+
+	let mut hash = 0u64;
+	for y in 0..DHASH_HEIGHT {
+		for x in 0..DHASH_WIDTH - 1 {
+			let left = image.get_pixel(x, y).0[0];
+			let right = image.get_pixel(x + 1, y).0[0];
+			hash = (hash << 1) | u64::from(left > right);
+		}
+	}
+
+	Ok(hash)
+}
+
+/// The number of bits which differ between two perceptual hashes. 0 means the images are
+/// (perceptually) identical, and 64 means they are inverses of one another
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+	(a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+
+	use super::*;
+
+	fn gradient(width: u32, height: u32, reversed: bool) -> RgbImage {
+		RgbImage::from_fn(width, height, |x, _| {
+			let value = (x * 255 / (width - 1)) as u8;
+			let value = if reversed { 255 - value } else { value };
+			Rgb([value, value, value])
+		})
+	}
+
+	fn encode(image: RgbImage, format: ImageFormat) -> Vec<u8> {
+		let mut buf = Vec::new();
+		DynamicImage::ImageRgb8(image)
+			.write_to(&mut Cursor::new(&mut buf), format)
+			.unwrap();
+		buf
+	}
+
+	#[test]
+	fn test_difference_hash_ignores_resolution_and_format() {
+		let large =
+			difference_hash(&encode(gradient(400, 600, true), ImageFormat::Png)).unwrap();
+		let small = difference_hash(&encode(gradient(100, 150, true), ImageFormat::Jpeg))
+			.unwrap();
+
+		assert!(hamming_distance(large, small) <= 4);
+	}
+
+	#[test]
+	fn test_difference_hash_distinguishes_images() {
+		let a = difference_hash(&encode(gradient(200, 300, false), ImageFormat::Png))
+			.unwrap();
+		let b =
+			difference_hash(&encode(gradient(200, 300, true), ImageFormat::Png)).unwrap();
+
+		assert!(hamming_distance(a, b) > 32);
+	}
+
+	#[test]
+	fn test_hamming_distance() {
+		assert_eq!(hamming_distance(0, 0), 0);
+		assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+		assert_eq!(hamming_distance(0, u64::MAX), 64);
+	}
+}
@@ -0,0 +1,382 @@
+//! Serialization of [`MediaMetadata`] into ComicInfo.xml, the inverse of
+//! [`metadata_from_buf`](super::utils::metadata_from_buf).
+//!
+//! See https://anansi-project.github.io/docs/comicinfo/schemas/v2.1
+
+use xml::{
+	common::XmlVersion,
+	reader::{self, EventReader, ParserConfig},
+	writer::XmlEvent,
+	EmitterConfig, EventWriter,
+};
+
+use crate::{db::entity::MediaMetadata, filesystem::error::FileError};
+
+pub const COMIC_INFO_FILE_NAME: &str = "ComicInfo.xml";
+
+/// The order of the elements in the ComicInfo schema. The schema defines the elements as a
+/// sequence, so some readers are strict about the order
+const COMIC_INFO_ELEMENT_ORDER: [&str; 43] = [
+	"Title",
+	"Series",
+	"Number",
+	"Count",
+	"Volume",
+	"AlternateSeries",
+	"AlternateNumber",
+	"AlternateCount",
+	"Summary",
+	"Notes",
+	"Year",
+	"Month",
+	"Day",
+	"Writer",
+	"Penciller",
+	"Inker",
+	"Colorist",
+	"Letterer",
+	"CoverArtist",
+	"Editor",
+	"Translator",
+	"Publisher",
+	"Imprint",
+	"Genre",
+	"Tags",
+	"Web",
+	"PageCount",
+	"LanguageISO",
+	"Format",
+	"BlackAndWhite",
+	"Manga",
+	"Characters",
+	"Teams",
+	"Locations",
+	"ScanInformation",
+	"StoryArc",
+	"StoryArcNumber",
+	"SeriesGroup",
+	"AgeRating",
+	"Pages",
+	"CommunityRating",
+	"MainCharacterOrTeam",
+	"Review",
+];
+
+/// The elements which Stump reads into [`MediaMetadata`], and therefore owns when writing.
+/// Any other elements in an existing ComicInfo.xml are preserved as-is
+const MANAGED_ELEMENTS: [&str; 24] = [
+	"Title",
+	"Series",
+	"Number",
+	"Volume",
+	"Summary",
+	"Notes",
+	"Year",
+	"Month",
+	"Day",
+	"Writer",
+	"Penciller",
+	"Inker",
+	"Colorist",
+	"Letterer",
+	"CoverArtist",
+	"Editor",
+	"Publisher",
+	"Genre",
+	"Web",
+	"PageCount",
+	"LanguageISO",
+	"Characters",
+	"Teams",
+	"AgeRating",
+];
+
+/// Convert a normalized age rating back into a ComicInfo age rating. The returned values
+/// are parsed back into the same number by Stump, where possible
+fn age_rating_to_comic_info(age_rating: i32) -> &'static str {
+	match age_rating {
+		..=9 => "Everyone",
+		10..=12 => "Everyone 10+",
+		13..=14 => "Teen",
+		15 => "MA15+",
+		16 => "Teen+",
+		17 => "Mature 17+",
+		_ => "Adults Only 18+",
+	}
+}
+
+/// Get the (element, value) pairs for the fields which are set on the metadata
+fn managed_elements(metadata: &MediaMetadata) -> Vec<(&'static str, String)> {
+	let list = |values: &Option<Vec<String>>| values.as_ref().map(|v| v.join(", "));
+
+	[
+		("Title", metadata.title.clone()),
+		("Series", metadata.series.clone()),
+		("Number", metadata.number.map(|n| n.to_string())),
+		("Volume", metadata.volume.map(|v| v.to_string())),
+		("Summary", metadata.summary.clone()),
+		("Notes", metadata.notes.clone()),
+		("Year", metadata.year.map(|y| y.to_string())),
+		("Month", metadata.month.map(|m| m.to_string())),
+		("Day", metadata.day.map(|d| d.to_string())),
+		("Writer", list(&metadata.writers)),
+		("Penciller", list(&metadata.pencillers)),
+		("Inker", list(&metadata.inkers)),
+		("Colorist", list(&metadata.colorists)),
+		("Letterer", list(&metadata.letterers)),
+		("CoverArtist", list(&metadata.cover_artists)),
+		("Editor", list(&metadata.editors)),
+		("Publisher", metadata.publisher.clone()),
+		("Genre", list(&metadata.genre)),
+		("Web", list(&metadata.links)),
+		("PageCount", metadata.page_count.map(|p| p.to_string())),
+		("LanguageISO", metadata.language.clone()),
+		("Characters", list(&metadata.characters)),
+		("Teams", list(&metadata.teams)),
+		(
+			"AgeRating",
+			metadata
+				.age_rating
+				.map(|a| age_rating_to_comic_info(a).to_string()),
+		),
+	]
+	.into_iter()
+	.filter_map(|(name, value)| value.map(|value| (name, value)))
+	.collect()
+}
+
+enum ComicInfoElement {
+	/// An element written from the metadata
+	Managed(&'static str, String),
+	/// An element copied from an existing ComicInfo.xml, including its children
+	Preserved(String, Vec<reader::XmlEvent>),
+}
+
+impl ComicInfoElement {
+	fn name(&self) -> &str {
+		match self {
+			ComicInfoElement::Managed(name, _) => name,
+			ComicInfoElement::Preserved(name, _) => name,
+		}
+	}
+
+	fn position(&self) -> usize {
+		COMIC_INFO_ELEMENT_ORDER
+			.iter()
+			.position(|name| *name == self.name())
+			.unwrap_or(usize::MAX)
+	}
+}
+
+/// The parts of an existing ComicInfo.xml which are carried over when writing
+struct ExistingComicInfo {
+	root: reader::XmlEvent,
+	elements: Vec<ComicInfoElement>,
+}
+
+fn parse_existing(contents: &str) -> Result<ExistingComicInfo, String> {
+	let parser = EventReader::new_with_config(
+		contents.trim().as_bytes(),
+		ParserConfig::new().trim_whitespace(true),
+	);
+
+	let mut root = None;
+	let mut elements = Vec::new();
+	let mut current: Option<(String, Vec<reader::XmlEvent>)> = None;
+	let mut depth = 0;
+
+	for event in parser {
+		let event = event.map_err(|e| e.to_string())?;
+		match &event {
+			reader::XmlEvent::StartElement { name, .. } => {
+				depth += 1;
+				if depth == 1 {
+					root = Some(event);
+					continue;
+				} else if depth == 2 {
+					current = Some((name.local_name.clone(), Vec::new()));
+				}
+			},
+			reader::XmlEvent::EndElement { .. } => {
+				depth -= 1;
+				if depth == 1 {
+					if let Some((name, mut events)) = current.take() {
+						events.push(event);
+						if !MANAGED_ELEMENTS.contains(&name.as_str()) {
+							elements.push(ComicInfoElement::Preserved(name, events));
+						}
+					}
+					continue;
+				}
+			},
+			_ => {},
+		}
+
+		if let Some((_, events)) = current.as_mut() {
+			events.push(event);
+		}
+	}
+
+	let root = root.ok_or_else(|| "Missing root element".to_string())?;
+
+	Ok(ExistingComicInfo { root, elements })
+}
+
+fn map_writer_error(error: xml::writer::Error) -> FileError {
+	FileError::MetadataWriteError(error.to_string())
+}
+
+/// Serialize the metadata into the contents of a ComicInfo.xml file. If the contents of an
+/// existing ComicInfo.xml are provided, any elements which Stump does not manage (e.g.
+/// `Pages` or `ScanInformation`) are preserved. Managed elements are always taken from the
+/// metadata, so fields which are unset on the metadata are removed from the file.
+pub fn metadata_to_comic_info(
+	metadata: &MediaMetadata,
+	existing: Option<&str>,
+) -> Result<String, FileError> {
+	let existing = existing.and_then(|contents| match parse_existing(contents) {
+		Ok(existing) => Some(existing),
+		Err(error) => {
+			tracing::warn!(
+				?error,
+				"Failed to parse existing ComicInfo.xml, replacing it"
+			);
+			None
+		},
+	});
+	let (root, mut elements) = match existing {
+		Some(ExistingComicInfo { root, elements }) => (Some(root), elements),
+		None => (None, Vec::new()),
+	};
+
+	elements.extend(
+		managed_elements(metadata)
+			.into_iter()
+			.map(|(name, value)| ComicInfoElement::Managed(name, value)),
+	);
+	elements.sort_by_key(ComicInfoElement::position);
+
+	let mut writer = EventWriter::new_with_config(
+		Vec::new(),
+		EmitterConfig::new().perform_indent(true),
+	);
+
+	writer
+		.write(XmlEvent::StartDocument {
+			version: XmlVersion::Version10,
+			encoding: Some("utf-8"),
+			standalone: None,
+		})
+		.map_err(map_writer_error)?;
+
+	match root.as_ref().and_then(|root| root.as_writer_event()) {
+		Some(root) => writer.write(root),
+		None => writer.write(
+			XmlEvent::start_element("ComicInfo")
+				.ns("xsd", "http://www.w3.org/2001/XMLSchema")
+				.ns("xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+		),
+	}
+	.map_err(map_writer_error)?;
+
+	for element in &elements {
+		match element {
+			ComicInfoElement::Managed(name, value) => {
+				writer
+					.write(XmlEvent::start_element(*name))
+					.map_err(map_writer_error)?;
+				writer
+					.write(XmlEvent::characters(value))
+					.map_err(map_writer_error)?;
+				writer
+					.write(XmlEvent::end_element())
+					.map_err(map_writer_error)?;
+			},
+			ComicInfoElement::Preserved(_, events) => {
+				for event in events.iter().filter_map(reader::XmlEvent::as_writer_event) {
+					writer.write(event).map_err(map_writer_error)?;
+				}
+			},
+		}
+	}
+
+	writer
+		.write(XmlEvent::end_element())
+		.map_err(map_writer_error)?;
+
+	String::from_utf8(writer.into_inner())
+		.map_err(|e| FileError::MetadataWriteError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::filesystem::media::utils::metadata_from_buf;
+
+	fn sample_metadata() -> MediaMetadata {
+		MediaMetadata {
+			title: Some("The Beginning & The End".to_string()),
+			series: Some("Delete".to_string()),
+			number: Some(1.5),
+			volume: Some(2016),
+			summary: Some("A <summary>".to_string()),
+			year: Some(2016),
+			writers: Some(vec![
+				"Jimmy Palmiotti".to_string(),
+				"Justin Gray".to_string(),
+			]),
+			age_rating: Some(13),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_metadata_round_trip() {
+		let metadata = sample_metadata();
+		let contents = metadata_to_comic_info(&metadata, None).unwrap();
+		let parsed = metadata_from_buf(&contents).unwrap();
+
+		assert_eq!(parsed.title, metadata.title);
+		assert_eq!(parsed.series, metadata.series);
+		assert_eq!(parsed.number, metadata.number);
+		assert_eq!(parsed.volume, metadata.volume);
+		assert_eq!(parsed.summary, metadata.summary);
+		assert_eq!(parsed.writers, metadata.writers);
+		assert_eq!(parsed.age_rating, metadata.age_rating);
+	}
+
+	#[test]
+	fn test_preserves_unmanaged_elements() {
+		let existing = r#"<?xml version="1.0"?>
+<ComicInfo xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <Series>Old Series</Series>
+  <Notes>Outdated notes</Notes>
+  <ScanInformation>(digital)</ScanInformation>
+  <Title>Old Title</Title>
+  <Pages>
+    <Page Image="0" ImageSize="907332" Type="FrontCover" />
+  </Pages>
+</ComicInfo>"#;
+
+		let contents =
+			metadata_to_comic_info(&sample_metadata(), Some(existing)).unwrap();
+
+		assert!(contents.contains("<ScanInformation>(digital)</ScanInformation>"));
+		assert!(contents.contains("Type=\"FrontCover\""));
+		assert!(!contents.contains("Old Title"));
+		// Managed fields which are unset are removed
+		assert!(!contents.contains("Outdated notes"));
+		// Elements are written in schema order
+		let title = contents.find("<Title>").unwrap();
+		let series = contents.find("<Series>").unwrap();
+		let pages = contents.find("<Pages>").unwrap();
+		assert!(title < series && series < pages);
+	}
+
+	#[test]
+	fn test_replaces_malformed_existing() {
+		let contents =
+			metadata_to_comic_info(&sample_metadata(), Some("not xml")).unwrap();
+		assert!(metadata_from_buf(&contents).is_some());
+	}
+}
@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+
+use prisma_client_rust::or;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::task::spawn_blocking;
+
+use crate::{
+	db::entity::DuplicateReason,
+	filesystem::{
+		hash,
+		image::{difference_hash, hamming_distance},
+	},
+	job::{
+		error::JobError, JobExecuteLog, JobExt, JobOutputExt, JobProgress, JobTaskOutput,
+		WorkerCtx, WorkingState, WrappedJob,
+	},
+	prisma::{duplicate_cluster, media},
+};
+
+use super::get_page_async;
+
+type MediaID = String;
+
+/// The number of books hashed by a single task
+const HASH_CHUNK_SIZE: usize = 25;
+/// The maximum number of bits which may differ between two cover hashes for the covers to be
+/// considered the same
+pub const MAX_COVER_HASH_DISTANCE: u32 = 8;
+/// The minimum confidence for two books with similar covers to be considered duplicates
+pub const MIN_DUPLICATE_CONFIDENCE: f64 = 0.75;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum DuplicateAnalysisTask {
+	/// Compute the full checksum and cover hash of a chunk of books
+	HashMedia(Vec<MediaID>),
+	/// Group every hashed book into clusters of likely duplicates, replacing any existing
+	/// clusters
+	Cluster,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+// Note: This container attribute is used to ensure future additions to the struct do not break deserialization
+#[serde(default)]
+pub struct DuplicateAnalysisOutput {
+	/// The number of books which had their checksum computed
+	checksums_generated: u64,
+	/// The number of books which had their cover hash computed
+	cover_hashes_generated: u64,
+	/// The number of clusters of likely duplicates which were found
+	clusters_found: u64,
+	/// The number of books which belong to a cluster
+	duplicate_media: u64,
+}
+
+impl JobOutputExt for DuplicateAnalysisOutput {
+	fn update(&mut self, updated: Self) {
+		self.checksums_generated += updated.checksums_generated;
+		self.cover_hashes_generated += updated.cover_hashes_generated;
+		self.clusters_found += updated.clusters_found;
+		self.duplicate_media += updated.duplicate_media;
+	}
+}
+
+/// A job which finds books that are likely duplicates of one another. Unlike the sampled
+/// hash generated during a scan, which can match books that merely share some bytes, this
+/// compares a checksum of the entire file and a perceptual hash of the cover. Books with
+/// matching checksums are certainly duplicates, while books with similar covers are scored
+/// by how similar their covers, page counts and sizes are.
+///
+/// The checksum and cover hash of a book are only computed when missing, unless
+/// [`DuplicateAnalysisJob::force`] is set. They are cleared whenever a scan finds that the
+/// file has changed.
+#[derive(Clone)]
+pub struct DuplicateAnalysisJob {
+	/// Whether to recompute the hashes of books which have already been hashed
+	pub force: bool,
+}
+
+impl DuplicateAnalysisJob {
+	pub fn new(force: bool) -> Box<WrappedJob<DuplicateAnalysisJob>> {
+		WrappedJob::new(Self { force })
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for DuplicateAnalysisJob {
+	const NAME: &'static str = "duplicate_analysis";
+
+	type Output = DuplicateAnalysisOutput;
+	type Task = DuplicateAnalysisTask;
+
+	fn description(&self) -> Option<String> {
+		Some(format!("Duplicate analysis, force: {}", self.force))
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let mut where_params = vec![media::deleted_at::equals(None)];
+		if !self.force {
+			where_params.push(or![
+				media::checksum::equals(None),
+				media::cover_hash::equals(None),
+			]);
+		}
+
+		let media_ids = ctx
+			.db
+			.media()
+			.find_many(where_params)
+			.select(media::select!({ id }))
+			.exec()
+			.await
+			.map_err(|e| JobError::InitFailed(e.to_string()))?
+			.into_iter()
+			.map(|media| media.id)
+			.collect::<Vec<_>>();
+
+		let tasks = media_ids
+			.chunks(HASH_CHUNK_SIZE)
+			.map(|chunk| DuplicateAnalysisTask::HashMedia(chunk.to_vec()))
+			.chain(std::iter::once(DuplicateAnalysisTask::Cluster))
+			.collect::<Vec<_>>();
+
+		Ok(WorkingState {
+			output: Some(Self::Output::default()),
+			tasks: tasks.into(),
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		match task {
+			DuplicateAnalysisTask::HashMedia(media_ids) => {
+				let books = ctx
+					.db
+					.media()
+					.find_many(vec![media::id::in_vec(media_ids)])
+					.exec()
+					.await?;
+
+				let task_count = books.len() as i32;
+				ctx.report_progress(JobProgress::subtask_position_msg(
+					"Hashing books",
+					1,
+					task_count,
+				));
+
+				for (position, book) in books.into_iter().enumerate() {
+					let mut updates = vec![];
+
+					if self.force || book.checksum.is_none() {
+						let path = book.path.clone();
+						match spawn_blocking(move || hash::generate_full(path))
+							.await
+							.map_err(|e| JobError::TaskFailed(e.to_string()))?
+						{
+							Ok(checksum) => {
+								output.checksums_generated += 1;
+								updates.push(media::checksum::set(Some(checksum)));
+							},
+							Err(error) => logs.push(
+								JobExecuteLog::error(format!(
+									"Failed to generate checksum: {error}"
+								))
+								.with_ctx(format!("Media ID: {}", book.id)),
+							),
+						}
+					}
+
+					if self.force || book.cover_hash.is_none() {
+						match generate_cover_hash(&book.path, ctx).await {
+							Ok(cover_hash) => {
+								output.cover_hashes_generated += 1;
+								updates.push(media::cover_hash::set(Some(cover_hash)));
+							},
+							Err(error) => logs.push(
+								JobExecuteLog::warn(&format!(
+									"Failed to generate cover hash: {error}"
+								))
+								.with_ctx(format!("Media ID: {}", book.id)),
+							),
+						}
+					}
+
+					if !updates.is_empty() {
+						ctx.db
+							.media()
+							.update(media::id::equals(book.id), updates)
+							.exec()
+							.await?;
+					}
+
+					ctx.report_progress(JobProgress::subtask_position(
+						position as i32 + 1,
+						task_count,
+					));
+				}
+			},
+			DuplicateAnalysisTask::Cluster => {
+				ctx.report_progress(JobProgress::msg("Clustering duplicate books"));
+
+				let candidates = ctx
+					.db
+					.media()
+					.find_many(vec![
+						media::deleted_at::equals(None),
+						or![media::checksum::not(None), media::cover_hash::not(None)],
+					])
+					.exec()
+					.await?
+					.into_iter()
+					.map(DuplicateCandidate::from)
+					.collect::<Vec<_>>();
+
+				let clusters =
+					spawn_blocking(move || find_duplicate_clusters(&candidates))
+						.await
+						.map_err(|e| JobError::TaskFailed(e.to_string()))?;
+
+				output.clusters_found = clusters.len() as u64;
+				output.duplicate_media =
+					clusters.iter().map(|c| c.media_ids.len() as u64).sum();
+
+				ctx.db
+					._transaction()
+					.run(|client| async move {
+						client
+							.duplicate_cluster()
+							.delete_many(vec![])
+							.exec()
+							.await?;
+
+						for cluster in clusters {
+							client
+								.duplicate_cluster()
+								.create(
+									cluster.confidence,
+									cluster.reason.to_string(),
+									vec![duplicate_cluster::media::connect(
+										cluster
+											.media_ids
+											.into_iter()
+											.map(media::id::equals)
+											.collect(),
+									)],
+								)
+								.exec()
+								.await?;
+						}
+
+						Ok::<_, prisma_client_rust::QueryError>(())
+					})
+					.await?;
+			},
+		}
+
+		Ok(JobTaskOutput {
+			output,
+			logs,
+			subtasks: vec![],
+		})
+	}
+}
+
+/// Compute the perceptual hash of the first page of a book, encoded as 16 hex characters
+async fn generate_cover_hash(path: &str, ctx: &WorkerCtx) -> Result<String, JobError> {
+	let (_, buf) = get_page_async(path, 1, &ctx.config).await?;
+	let cover_hash = spawn_blocking(move || difference_hash(&buf))
+		.await
+		.map_err(|e| JobError::TaskFailed(e.to_string()))?
+		.map_err(|e| JobError::TaskFailed(e.to_string()))?;
+
+	Ok(format!("{cover_hash:016x}"))
+}
+
+/// The subset of a book's data used to determine whether it is a duplicate of another
+#[derive(Debug, Clone)]
+pub struct DuplicateCandidate {
+	pub id: MediaID,
+	pub size: i64,
+	pub pages: i32,
+	pub checksum: Option<String>,
+	pub cover_hash: Option<u64>,
+}
+
+impl From<media::Data> for DuplicateCandidate {
+	fn from(data: media::Data) -> Self {
+		Self {
+			cover_hash: data
+				.cover_hash
+				.and_then(|hash| u64::from_str_radix(&hash, 16).ok()),
+			id: data.id,
+			size: data.size,
+			pages: data.pages,
+			checksum: data.checksum,
+		}
+	}
+}
+
+/// A group of books which are likely duplicates of one another
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoundDuplicateCluster {
+	pub media_ids: Vec<MediaID>,
+	/// The confidence of the weakest match which joined the cluster
+	pub confidence: f64,
+	pub reason: DuplicateReason,
+}
+
+/// Score how likely it is that two books with similar covers are duplicates. The similarity
+/// of the covers accounts for most of the score, but books which share a cover (e.g. a single
+/// issue and the collected edition which reuses its cover) are told apart by their page
+/// count and size. Returns `None` if the covers are too different to be compared
+fn cover_confidence(a: &DuplicateCandidate, b: &DuplicateCandidate) -> Option<f64> {
+	let distance = hamming_distance(a.cover_hash?, b.cover_hash?);
+	if distance > MAX_COVER_HASH_DISTANCE {
+		return None;
+	}
+
+	let cover_similarity = 1.0 - f64::from(distance) / 64.0;
+	let pages_similarity = if a.pages == b.pages { 1.0 } else { 0.0 };
+	let (smaller, larger) = (a.size.min(b.size), a.size.max(b.size));
+	let size_similarity = if larger > 0 {
+		smaller as f64 / larger as f64
+	} else {
+		1.0
+	};
+
+	Some(0.6 * cover_similarity + 0.2 * pages_similarity + 0.2 * size_similarity)
+}
+
+/// Group candidates into clusters of likely duplicates. Books with identical checksums are
+/// always clustered with a confidence of 1, and books with similar covers are clustered when
+/// their [cover_confidence] reaches [MIN_DUPLICATE_CONFIDENCE]. Clusters are transitive, so
+/// if A matches B and B matches C then all three are clustered together.
+///
+/// To avoid comparing every pair of books, cover hashes are split into bands. Two hashes
+/// within [MAX_COVER_HASH_DISTANCE] bits of each other must share at least one band exactly,
+/// so only books which share a band are compared.
+pub fn find_duplicate_clusters(
+	candidates: &[DuplicateCandidate],
+) -> Vec<FoundDuplicateCluster> {
+	let mut clusters = DisjointSet::new(candidates.len());
+
+	let mut by_checksum = HashMap::<&str, Vec<usize>>::new();
+	for (index, candidate) in candidates.iter().enumerate() {
+		if let Some(checksum) = candidate.checksum.as_deref() {
+			by_checksum.entry(checksum).or_default().push(index);
+		}
+	}
+	for indices in by_checksum.values() {
+		for pair in indices.windows(2) {
+			clusters.union(pair[0], pair[1], 1.0, DuplicateReason::Checksum);
+		}
+	}
+
+	let band_count = MAX_COVER_HASH_DISTANCE as u64 + 1;
+	let band_width = 64 / band_count;
+	let mut by_band = HashMap::<(u64, u64), Vec<usize>>::new();
+	for (index, candidate) in candidates.iter().enumerate() {
+		// A uniform image (e.g. a blank or solid colour cover) hashes to 0, and would
+		// otherwise match every other uniform cover
+		let Some(cover_hash) = candidate.cover_hash.filter(|hash| *hash != 0) else {
+			continue;
+		};
+		for band in 0..band_count {
+			let shift = band * band_width;
+			let width = if band == band_count - 1 {
+				64 - shift
+			} else {
+				band_width
+			};
+			let value = (cover_hash >> shift) & (u64::MAX >> (64 - width));
+			by_band.entry((band, value)).or_default().push(index);
+		}
+	}
+	for indices in by_band.values() {
+		for (position, &a) in indices.iter().enumerate() {
+			for &b in &indices[position + 1..] {
+				if let Some(confidence) = cover_confidence(&candidates[a], &candidates[b])
+					.filter(|confidence| *confidence >= MIN_DUPLICATE_CONFIDENCE)
+				{
+					clusters.union(a, b, confidence, DuplicateReason::CoverHash);
+				}
+			}
+		}
+	}
+
+	let mut groups = HashMap::<usize, Vec<usize>>::new();
+	for index in 0..candidates.len() {
+		groups.entry(clusters.find(index)).or_default().push(index);
+	}
+
+	let mut found = groups
+		.into_iter()
+		.filter(|(_, members)| members.len() > 1)
+		.map(|(root, members)| FoundDuplicateCluster {
+			media_ids: members
+				.into_iter()
+				.map(|index| candidates[index].id.clone())
+				.collect(),
+			confidence: clusters.confidence[root],
+			reason: clusters.reason[root],
+		})
+		.collect::<Vec<_>>();
+	found.sort_by(|a, b| {
+		b.confidence
+			.total_cmp(&a.confidence)
+			.then_with(|| a.media_ids.cmp(&b.media_ids))
+	});
+
+	found
+}
+
+/// A union-find structure which tracks, for each cluster, the weakest match which joined it
+/// and whether every match was by checksum
+struct DisjointSet {
+	parent: Vec<usize>,
+	confidence: Vec<f64>,
+	reason: Vec<DuplicateReason>,
+}
+
+impl DisjointSet {
+	fn new(size: usize) -> Self {
+		Self {
+			parent: (0..size).collect(),
+			confidence: vec![1.0; size],
+			reason: vec![DuplicateReason::Checksum; size],
+		}
+	}
+
+	fn find(&mut self, index: usize) -> usize {
+		let mut root = index;
+		while self.parent[root] != root {
+			root = self.parent[root];
+		}
+
+		let mut current = index;
+		while self.parent[current] != root {
+			let next = self.parent[current];
+			self.parent[current] = root;
+			current = next;
+		}
+
+		root
+	}
+
+	fn union(&mut self, a: usize, b: usize, confidence: f64, reason: DuplicateReason) {
+		let (root_a, root_b) = (self.find(a), self.find(b));
+
+		let mut merged_confidence = confidence.min(self.confidence[root_a]);
+		let mut merged_reason = reason;
+		if root_a != root_b {
+			merged_confidence = merged_confidence.min(self.confidence[root_b]);
+			self.parent[root_b] = root_a;
+		}
+		if [self.reason[root_a], self.reason[root_b]]
+			.contains(&DuplicateReason::CoverHash)
+		{
+			merged_reason = DuplicateReason::CoverHash;
+		}
+
+		self.confidence[root_a] = merged_confidence;
+		self.reason[root_a] = merged_reason;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn candidate(
+		id: &str,
+		size: i64,
+		pages: i32,
+		checksum: Option<&str>,
+		cover_hash: Option<u64>,
+	) -> DuplicateCandidate {
+		DuplicateCandidate {
+			id: id.to_string(),
+			size,
+			pages,
+			checksum: checksum.map(String::from),
+			cover_hash,
+		}
+	}
+
+	#[test]
+	fn test_identical_checksums_are_clustered() {
+		let clusters = find_duplicate_clusters(&[
+			candidate("a", 100, 20, Some("abc"), None),
+			candidate("b", 100, 20, Some("abc"), None),
+			candidate("c", 100, 20, Some("def"), None),
+		]);
+
+		assert_eq!(
+			clusters,
+			vec![FoundDuplicateCluster {
+				media_ids: vec!["a".to_string(), "b".to_string()],
+				confidence: 1.0,
+				reason: DuplicateReason::Checksum,
+			}]
+		);
+	}
+
+	#[test]
+	fn test_similar_covers_are_clustered() {
+		let cover = 0xF0F0_F0F0_1234_5678;
+		let clusters = find_duplicate_clusters(&[
+			candidate("a", 1000, 20, Some("abc"), Some(cover)),
+			candidate("b", 900, 20, Some("def"), Some(cover ^ 0b101)),
+			// Same cover, but a different book (e.g. a collected edition)
+			candidate("c", 5000, 120, Some("ghi"), Some(cover)),
+			candidate("d", 1000, 20, Some("jkl"), Some(!cover)),
+		]);
+
+		assert_eq!(clusters.len(), 1);
+		assert_eq!(
+			clusters[0].media_ids,
+			vec!["a".to_string(), "b".to_string()]
+		);
+		assert_eq!(clusters[0].reason, DuplicateReason::CoverHash);
+		assert!(clusters[0].confidence >= MIN_DUPLICATE_CONFIDENCE);
+		assert!(clusters[0].confidence < 1.0);
+	}
+
+	#[test]
+	fn test_blank_covers_are_ignored() {
+		let clusters = find_duplicate_clusters(&[
+			candidate("a", 1000, 20, None, Some(0)),
+			candidate("b", 1000, 20, None, Some(0)),
+		]);
+
+		assert!(clusters.is_empty());
+	}
+
+	#[test]
+	fn test_clusters_are_transitive() {
+		let cover = 0x0123_4567_89AB_CDEF;
+		let clusters = find_duplicate_clusters(&[
+			candidate("a", 1000, 20, Some("abc"), Some(cover)),
+			candidate("b", 1000, 20, Some("abc"), None),
+			candidate("c", 1000, 20, None, Some(cover ^ 1)),
+		]);
+
+		assert_eq!(clusters.len(), 1);
+		assert_eq!(clusters[0].media_ids.len(), 3);
+		assert_eq!(clusters[0].reason, DuplicateReason::CoverHash);
+	}
+}
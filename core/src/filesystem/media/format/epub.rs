@@ -1,5 +1,4 @@
 use merge::Merge;
-use quick_xml::{events::Event, Reader};
 use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf};
 
 const ACCEPTED_EPUB_COVER_MIMES: [&str; 2] = ["image/jpeg", "image/png"];
@@ -17,6 +16,9 @@ use crate::{
 	},
 };
 use epub::doc::EpubDoc;
+use regex::Regex;
+
+use super::opf::metadata_from_opf;
 
 // TODO: lots of smells in this file, needs a touch up :)
 
@@ -90,66 +92,38 @@ impl FileProcessor for EpubProcessor {
 	}
 
 	fn process_metadata(path: &str) -> Result<Option<MediaMetadata>, FileError> {
-		let epub_file = Self::open(path)?;
-		let embedded_metadata = MediaMetadata::from(epub_file.metadata);
+		let mut epub_file = Self::open(path)?;
+
+		// The epub crate only collects a flat subset of the package metadata, so the OPF is
+		// parsed directly. The crate's metadata is only used as a fallback
+		let root_file = epub_file.root_file.clone();
+		let embedded_metadata = match epub_file
+			.get_resource_by_path(&root_file)
+			.map(|buf| metadata_from_opf(&String::from_utf8_lossy(&buf)))
+		{
+			Some(Ok(metadata)) => metadata,
+			result => {
+				if let Some(Err(error)) = result {
+					tracing::warn!(?error, ?root_file, "Failed to parse embedded OPF");
+				}
+				MediaMetadata::from(epub_file.metadata)
+			},
+		};
 
 		// try get opf file
 		let file_path = std::path::Path::new(path).with_extension("opf");
 		if file_path.exists() {
-			// extract OPF data
-			let opf_string = std::fs::read_to_string(file_path)?;
-			let mut reader = Reader::from_str(opf_string.as_str());
-			reader.config_mut().trim_text(true);
-			let mut current_tag = String::new();
-
-			let mut opf_metadata: HashMap<String, Vec<String>> = HashMap::new();
-
-			while let Ok(event) = reader.read_event() {
-				match event {
-					Event::Start(ref e) | Event::Empty(ref e) => {
-						let tag_name =
-							String::from_utf8_lossy(e.name().as_ref()).to_string();
-
-						// normalize tags
-						current_tag = tag_name
-							.strip_prefix("dc:")
-							.unwrap_or(tag_name.as_str())
-							.to_string();
-
-						if let Some(attr) =
-							e.attributes().filter_map(|a| a.ok()).find(|a| {
-								a.key.as_ref() == b"property" || a.key.as_ref() == b"name"
-							}) {
-							current_tag = format!(
-								"{}: {}",
-								current_tag,
-								String::from_utf8_lossy(&attr.value)
-							);
-						}
-					},
-					Event::Text(e) => {
-						if let Ok(text) = e.unescape() {
-							opf_metadata
-								.entry(current_tag.clone())
-								.or_default()
-								.push(text.to_string());
-						}
-					},
-					Event::Eof => {
-						break;
-					},
-					_ => {},
-				}
+			let opf_string = std::fs::read_to_string(&file_path)?;
+			match metadata_from_opf(&opf_string) {
+				Ok(mut combined_metadata) => {
+					// merge opf and embedded, prioritizing opf
+					combined_metadata.merge(embedded_metadata);
+					return Ok(Some(combined_metadata));
+				},
+				Err(error) => {
+					tracing::warn!(?error, ?file_path, "Failed to parse sidecar OPF");
+				},
 			}
-
-			// merge opf and embedded, prioritizing opf
-			let opf_metadata = MediaMetadata::from(opf_metadata);
-			let mut combined_metadata = opf_metadata.clone();
-
-			combined_metadata.id = opf_metadata.id;
-			combined_metadata.merge(embedded_metadata);
-
-			return Ok(Some(combined_metadata));
 		}
 
 		Ok(Some(embedded_metadata))
@@ -396,6 +370,51 @@ impl EpubProcessor {
 		Ok((content_type, content))
 	}
 
+	/// Extract the plain text of every chapter, in reading order, e.g. for the search index.
+	/// Extraction stops once roughly `max_length` characters have been collected
+	pub fn get_text_content(path: &str, max_length: usize) -> Result<String, FileError> {
+		let mut epub_file = Self::open(path)?;
+
+		let mut text = String::new();
+		let mut length = 0;
+		for chapter in 0..epub_file.get_num_pages() {
+			if length >= max_length {
+				break;
+			}
+
+			if !epub_file.set_current_page(chapter) {
+				tracing::warn!(
+					path,
+					chapter,
+					"Failed to set chapter for text extraction"
+				);
+				continue;
+			}
+
+			let Some((content, _)) = epub_file.get_current() else {
+				tracing::warn!(
+					path,
+					chapter,
+					"Failed to read chapter for text extraction"
+				);
+				continue;
+			};
+
+			let chapter_text = html_to_text(&String::from_utf8_lossy(&content));
+			if chapter_text.is_empty() {
+				continue;
+			}
+
+			if !text.is_empty() {
+				text.push('\n');
+			}
+			length += chapter_text.chars().count();
+			text.push_str(&chapter_text);
+		}
+
+		Ok(text)
+	}
+
 	pub fn get_resource_by_id(
 		path: &str,
 		resource_id: &str,
@@ -478,6 +497,41 @@ impl EpubProcessor {
 	}
 }
 
+/// Convert (X)HTML into plain text, dropping scripts, styles and the document head and
+/// collapsing whitespace. This is not a complete HTML parser, but is good enough for indexing
+fn html_to_text(html: &str) -> String {
+	let hidden =
+		Regex::new(r"(?is)<script\b.*?</script>|<style\b.*?</style>|<head\b.*?</head>")
+			.expect("Failed to compile hidden element regex");
+	let tags = Regex::new(r"(?s)<[^>]*>").expect("Failed to compile tag regex");
+	let entities = Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);")
+		.expect("Failed to compile entity regex");
+
+	let visible = hidden.replace_all(html, " ");
+	let text = tags.replace_all(&visible, " ");
+	let decoded = entities.replace_all(&text, |caps: &regex::Captures| {
+		let entity = &caps[1];
+		let decoded = match entity {
+			"amp" => Some('&'),
+			"lt" => Some('<'),
+			"gt" => Some('>'),
+			"quot" => Some('"'),
+			"apos" => Some('\''),
+			"nbsp" => Some(' '),
+			_ => entity
+				.strip_prefix("#x")
+				.or_else(|| entity.strip_prefix("#X"))
+				.map(|hex| u32::from_str_radix(hex, 16))
+				.or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+				.and_then(Result::ok)
+				.and_then(char::from_u32),
+		};
+		decoded.map_or_else(|| caps[0].to_string(), String::from)
+	});
+
+	decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 pub(crate) fn normalize_resource_path(path: PathBuf, root: &str) -> PathBuf {
 	let mut adjusted_path = path.clone();
 
@@ -713,6 +767,11 @@ mod tests {
 					Some("Alice's Adventures in Wonderland - Test OPF".to_string())
 				);
 				assert_eq!(metadata.writers, Some(vec!["Lewis Carroll".to_string()]));
+				assert_eq!(metadata.language, Some("en".to_string()));
+				assert_eq!(metadata.year, Some(2008));
+				assert!(metadata
+					.genre
+					.is_some_and(|genre| genre.contains(&"Fantasy fiction".to_string())));
 			},
 			Ok(None) => panic!("No metadata returned"),
 			Err(e) => panic!("Failed to get metadata: {:?}", e),
@@ -735,6 +794,24 @@ mod tests {
 		assert!(cover.is_ok());
 	}
 
+	#[test]
+	fn test_html_to_text() {
+		let html = r#"<html><head><title>Chapter 1</title><style>p { color: red; }</style></head>
+			<body><h1>Chapter&nbsp;1</h1><p>Tom &amp; Jerry&#39;s <em>great</em>
+			escape&#x21;</p><script>alert("hi")</script></body></html>"#;
+		assert_eq!(html_to_text(html), "Chapter 1 Tom & Jerry's great escape!");
+	}
+
+	#[test]
+	fn test_get_text_content() {
+		let path = get_test_epub_path();
+		let text = EpubProcessor::get_text_content(&path, usize::MAX);
+		assert!(text.is_ok());
+		let text = text.unwrap();
+		assert!(!text.is_empty());
+		assert!(!text.contains('<'));
+	}
+
 	#[test]
 	fn test_get_chapter() {
 		let path = get_test_epub_path();
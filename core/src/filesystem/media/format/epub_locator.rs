@@ -0,0 +1,858 @@
+//! Conversion between the different ways a position within an EPUB is expressed:
+//!
+//! - An EPUB CFI, e.g. `epubcfi(/6/8[chapter3]!/4/2[intro]/3:15)`, which is what the web
+//!   reader uses. See https://idpf.org/epub/linking/cfi/
+//! - A KOReader x-pointer, e.g. `/body/DocFragment[4]/body/div/p[2]/text().15`
+//! - A spine resource and the progression within it, which is how a Readium locator
+//!   describes a position. See https://readium.org/architecture/models/locators/
+//!
+//! Each chapter is parsed into a lightweight DOM, and every position is resolved to a
+//! character offset within the text of its chapter. That offset is what ties the formats
+//! together, so a converted position will land on the same text node but may be a few
+//! characters off, e.g. because KOReader collapses some whitespace.
+
+use std::{fs::File, io::BufReader};
+
+use epub::doc::EpubDoc;
+use quick_xml::{
+	events::{BytesStart, Event},
+	Reader,
+};
+
+use crate::{db::entity::ReadingLocator, filesystem::error::FileError};
+
+use super::epub::EpubProcessor;
+
+/// Elements whose text is never rendered, and so is not counted towards positions
+const NON_RENDERED_ELEMENTS: [&str; 3] = ["head", "script", "style"];
+
+/// The prefix of every KOReader x-pointer into an EPUB. Each spine item is a `DocFragment`
+const XPOINTER_PREFIX: &str = "/body/DocFragment[";
+
+#[derive(Debug)]
+enum Node {
+	Element(Element),
+	Text(String),
+}
+
+#[derive(Debug, Default)]
+struct Element {
+	/// The lowercased local name of the element, e.g. `p`
+	name: String,
+	id: Option<String>,
+	children: Vec<Node>,
+}
+
+impl Element {
+	fn new(start: &BytesStart) -> Self {
+		let id = start
+			.attributes()
+			.filter_map(Result::ok)
+			.find(|attr| attr.key.local_name().as_ref() == b"id")
+			.and_then(|attr| attr.unescape_value().ok())
+			.map(|value| value.to_string());
+
+		Self {
+			name: String::from_utf8_lossy(start.local_name().as_ref()).to_lowercase(),
+			id,
+			children: Vec::new(),
+		}
+	}
+
+	fn push_text(&mut self, text: String) {
+		// Adjacent text (e.g. split by a comment) is a single text node as far as both
+		// CFIs and x-pointers are concerned
+		if let Some(Node::Text(existing)) = self.children.last_mut() {
+			existing.push_str(&text);
+		} else {
+			self.children.push(Node::Text(text));
+		}
+	}
+}
+
+fn is_element(node: &Node) -> bool {
+	matches!(node, Node::Element(_))
+}
+
+/// Whether the node is a text node which KOReader would count. Whitespace-only text between
+/// elements is dropped when KOReader builds its DOM
+fn is_koreader_text(node: &Node) -> bool {
+	matches!(node, Node::Text(text) if !text.trim().is_empty())
+}
+
+/// The 1-based position of the child at `index` among its siblings which match the predicate,
+/// and the number of such siblings
+fn sibling_position(
+	children: &[Node],
+	index: usize,
+	predicate: impl Fn(&Node) -> bool,
+) -> (usize, usize) {
+	let position = children[..index]
+		.iter()
+		.filter(|node| predicate(*node))
+		.count()
+		+ 1;
+	let count = children.iter().filter(|node| predicate(*node)).count();
+	(position, count)
+}
+
+/// A text node of a chapter, flattened out of the DOM in document order
+#[derive(Debug)]
+struct TextSpan {
+	/// The indices of the children to follow from the root element to reach the text node
+	path: Vec<usize>,
+	/// The character offset of the start of the text within the chapter
+	start: usize,
+	len: usize,
+	whitespace: bool,
+}
+
+/// A single chapter (spine item) of an EPUB
+#[derive(Debug)]
+struct ChapterDocument {
+	/// The root `<html>` element of the chapter
+	root: Element,
+	spans: Vec<TextSpan>,
+	/// The number of rendered characters in the chapter
+	len: usize,
+}
+
+impl ChapterDocument {
+	fn parse(contents: &str) -> Result<Self, FileError> {
+		let mut reader = Reader::from_str(contents);
+		reader.config_mut().check_end_names = false;
+
+		// The bottom of the stack is a stand-in for the document itself
+		let mut stack = vec![Element::default()];
+
+		loop {
+			match reader.read_event() {
+				Ok(Event::Start(ref e)) => stack.push(Element::new(e)),
+				Ok(Event::Empty(ref e)) => {
+					if let Some(parent) = stack.last_mut() {
+						parent.children.push(Node::Element(Element::new(e)));
+					}
+				},
+				Ok(Event::End(_)) => close_element(&mut stack),
+				Ok(Event::Text(e)) => {
+					// XHTML may use named entities (e.g. &nbsp;) which are not defined by XML,
+					// in which case the raw text is better than nothing
+					let text = e
+						.unescape()
+						.map(|text| text.to_string())
+						.unwrap_or_else(|_| String::from_utf8_lossy(&e).to_string());
+					if let Some(parent) = stack.last_mut() {
+						parent.push_text(text);
+					}
+				},
+				Ok(Event::CData(e)) => {
+					if let Some(parent) = stack.last_mut() {
+						parent.push_text(String::from_utf8_lossy(&e).to_string());
+					}
+				},
+				Ok(Event::Eof) => break,
+				Err(e) => {
+					tracing::error!(error = ?e, "Failed to parse chapter");
+					return Err(FileError::EpubReadError(e.to_string()));
+				},
+				_ => {},
+			}
+		}
+
+		// Tolerate elements which were never closed
+		while stack.len() > 1 {
+			close_element(&mut stack);
+		}
+
+		let root = stack
+			.pop()
+			.unwrap_or_default()
+			.children
+			.into_iter()
+			.find_map(|node| match node {
+				Node::Element(element) => Some(element),
+				Node::Text(_) => None,
+			})
+			.ok_or_else(|| {
+				FileError::EpubReadError(
+					"Chapter does not have a root element".to_string(),
+				)
+			})?;
+
+		let mut spans = Vec::new();
+		let len = collect_spans(&root, &mut Vec::new(), &mut spans, 0);
+
+		Ok(Self { root, spans, len })
+	}
+
+	/// The progression (0.0 to 1.0) of a character offset through the chapter
+	fn progression(&self, offset: usize) -> f64 {
+		if self.len == 0 {
+			return 0.0;
+		}
+		(offset as f64 / self.len as f64).clamp(0.0, 1.0)
+	}
+
+	/// The character offset at a progression (0.0 to 1.0) through the chapter
+	fn offset_at_progression(&self, progression: f64) -> usize {
+		(progression.clamp(0.0, 1.0) * self.len as f64).round() as usize
+	}
+
+	/// The character offset of a node within the chapter. For a text node, `char_offset` is
+	/// the offset within that node. For an element, it is the offset of its first text
+	fn offset_of(&self, path: &[usize], char_offset: usize) -> usize {
+		// Paths compare in document order, and the descendants of an element come after it
+		match self.spans.iter().find(|span| span.path.as_slice() >= path) {
+			Some(span) if span.path == path => span.start + char_offset.min(span.len),
+			Some(span) => span.start,
+			None => self.len,
+		}
+	}
+
+	/// The text span with content at a character offset, and the offset within it. When the
+	/// offset falls on whitespace between elements, the next span with content is used
+	fn span_at(&self, offset: usize) -> Option<(&TextSpan, usize)> {
+		let mut content_spans = self.spans.iter().filter(|span| !span.whitespace);
+		let span = content_spans
+			.clone()
+			.find(|span| offset < span.start + span.len)
+			.or_else(|| content_spans.next_back())?;
+
+		Some((span, offset.saturating_sub(span.start).min(span.len)))
+	}
+
+	/// Move a character offset onto the text it will resolve to, so that a position and the
+	/// CFI and x-pointer generated for it all agree
+	fn snap(&self, offset: usize) -> usize {
+		self.span_at(offset)
+			.map(|(span, char_offset)| span.start + char_offset)
+			.unwrap_or(offset)
+	}
+
+	/// Resolve the steps of a CFI (after the `!`) to a character offset. Even steps are
+	/// element children, and odd steps are the text between them, e.g. `/3` is the text
+	/// between the first and second elements
+	fn resolve_cfi(&self, steps: &[usize], char_offset: Option<usize>) -> usize {
+		let mut element = &self.root;
+		let mut path = Vec::new();
+
+		for &step in steps {
+			if step % 2 == 0 {
+				let Some(element_index) = (step / 2).checked_sub(1) else {
+					break;
+				};
+				let Some((index, child)) = element
+					.children
+					.iter()
+					.enumerate()
+					.filter_map(|(index, node)| match node {
+						Node::Element(child) => Some((index, child)),
+						Node::Text(_) => None,
+					})
+					.nth(element_index)
+				else {
+					break;
+				};
+				path.push(index);
+				element = child;
+			} else {
+				let preceding_elements = step / 2;
+				let text_index =
+					element
+						.children
+						.iter()
+						.enumerate()
+						.position(|(index, node)| {
+							!is_element(node)
+								&& element.children[..index]
+									.iter()
+									.filter(|node| is_element(node))
+									.count() == preceding_elements
+						});
+				if let Some(index) = text_index {
+					path.push(index);
+					return self.offset_of(&path, char_offset.unwrap_or_default());
+				}
+				break;
+			}
+		}
+
+		self.offset_of(&path, 0)
+	}
+
+	/// Resolve the steps of an x-pointer (after the `DocFragment`) to a character offset
+	fn resolve_xpointer(&self, steps: &[XPointerStep], char_offset: usize) -> usize {
+		let mut element = &self.root;
+		let mut path = Vec::new();
+
+		for step in steps {
+			match step {
+				XPointerStep::Element { name, index } => {
+					let Some((child_index, child)) = element
+						.children
+						.iter()
+						.enumerate()
+						.filter_map(|(index, node)| match node {
+							Node::Element(child) if &child.name == name => {
+								Some((index, child))
+							},
+							_ => None,
+						})
+						.nth(*index)
+					else {
+						break;
+					};
+					path.push(child_index);
+					element = child;
+				},
+				XPointerStep::Text { index } => {
+					let text_index = element
+						.children
+						.iter()
+						.enumerate()
+						.filter(|(_, node)| is_koreader_text(node))
+						.nth(*index)
+						.map(|(index, _)| index);
+					if let Some(index) = text_index {
+						path.push(index);
+						return self.offset_of(&path, char_offset);
+					}
+					break;
+				},
+			}
+		}
+
+		self.offset_of(&path, 0)
+	}
+
+	/// The CFI steps (the part after the `!`) for a character offset
+	fn cfi_steps(&self, offset: usize) -> String {
+		let Some((span, char_offset)) = self.span_at(offset) else {
+			let body_step = self
+				.root
+				.children
+				.iter()
+				.filter(|node| is_element(node))
+				.position(|node| matches!(node, Node::Element(e) if e.name == "body"))
+				.map(|index| (index + 1) * 2)
+				.unwrap_or(4);
+			return format!("/{body_step}");
+		};
+
+		let mut steps = String::new();
+		let mut element = &self.root;
+		for &index in &span.path {
+			let (position, _) = sibling_position(&element.children, index, is_element);
+			match &element.children[index] {
+				Node::Element(child) => {
+					steps.push_str(&format!("/{}", position * 2));
+					if let Some(id) = &child.id {
+						steps.push_str(&format!("[{}]", escape_cfi_assertion(id)));
+					}
+					element = child;
+				},
+				Node::Text(_) => {
+					// The position is that of the next element, so the text before it is
+					// the odd step just below its even step
+					steps.push_str(&format!("/{}:{char_offset}", (position - 1) * 2 + 1));
+				},
+			}
+		}
+
+		steps
+	}
+
+	/// The x-pointer path (the part after the `DocFragment`) for a character offset
+	fn xpointer_path(&self, offset: usize) -> String {
+		let Some((span, char_offset)) = self.span_at(offset) else {
+			return "/body.0".to_string();
+		};
+
+		let mut xpointer = String::new();
+		let mut element = &self.root;
+		for &index in &span.path {
+			match &element.children[index] {
+				Node::Element(child) => {
+					let (position, count) = sibling_position(
+						&element.children,
+						index,
+						|node| matches!(node, Node::Element(e) if e.name == child.name),
+					);
+					xpointer.push('/');
+					xpointer.push_str(&child.name);
+					if count > 1 {
+						xpointer.push_str(&format!("[{position}]"));
+					}
+					element = child;
+				},
+				Node::Text(_) => {
+					let (position, count) =
+						sibling_position(&element.children, index, is_koreader_text);
+					xpointer.push_str("/text()");
+					if count > 1 {
+						xpointer.push_str(&format!("[{position}]"));
+					}
+				},
+			}
+		}
+		xpointer.push_str(&format!(".{char_offset}"));
+
+		xpointer
+	}
+}
+
+fn close_element(stack: &mut Vec<Element>) {
+	if stack.len() > 1 {
+		if let (Some(element), Some(parent)) = (stack.pop(), stack.last_mut()) {
+			parent.children.push(Node::Element(element));
+		}
+	}
+}
+
+/// Flatten the rendered text nodes under an element into spans, returning the offset after
+/// the last one
+fn collect_spans(
+	element: &Element,
+	path: &mut Vec<usize>,
+	spans: &mut Vec<TextSpan>,
+	mut offset: usize,
+) -> usize {
+	for (index, node) in element.children.iter().enumerate() {
+		path.push(index);
+		match node {
+			Node::Text(text) => {
+				let len = text.chars().count();
+				spans.push(TextSpan {
+					path: path.clone(),
+					start: offset,
+					len,
+					whitespace: text.trim().is_empty(),
+				});
+				offset += len;
+			},
+			Node::Element(child)
+				if !NON_RENDERED_ELEMENTS.contains(&child.name.as_str()) =>
+			{
+				offset = collect_spans(child, path, spans, offset);
+			},
+			Node::Element(_) => {},
+		}
+		path.pop();
+	}
+	offset
+}
+
+/// Escape the characters which have a special meaning within a CFI assertion
+fn escape_cfi_assertion(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+	for c in value.chars() {
+		if matches!(c, '^' | '[' | ']' | '(' | ')' | ',' | ';' | '=') {
+			escaped.push('^');
+		}
+		escaped.push(c);
+	}
+	escaped
+}
+
+/// Remove the assertions (e.g. `[chapter3]`) from a CFI path, respecting `^` escapes
+fn strip_cfi_assertions(path: &str) -> String {
+	let mut stripped = String::with_capacity(path.len());
+	let mut in_assertion = false;
+	let mut escaped = false;
+	for c in path.chars() {
+		match (c, in_assertion, escaped) {
+			(_, _, true) => escaped = false,
+			('^', _, false) => escaped = true,
+			('[', false, false) => in_assertion = true,
+			(']', true, false) => in_assertion = false,
+			(_, true, false) => {},
+			(_, false, false) => stripped.push(c),
+		}
+	}
+	stripped
+}
+
+/// A CFI, limited to what is needed to locate a position in the text of a chapter
+#[derive(Debug, PartialEq)]
+struct Cfi {
+	spine_index: usize,
+	steps: Vec<usize>,
+	char_offset: Option<usize>,
+}
+
+fn parse_cfi_path(path: &str) -> Option<(Vec<usize>, Option<usize>)> {
+	let path = strip_cfi_assertions(path);
+	let (path, char_offset) = match path.split_once(':') {
+		Some((path, offset)) => {
+			let digits = offset
+				.chars()
+				.take_while(char::is_ascii_digit)
+				.collect::<String>();
+			(path.to_string(), Some(digits.parse::<usize>().ok()?))
+		},
+		None => (path.clone(), None),
+	};
+	// Temporal and spatial offsets are meaningless for text
+	let path = path.split(['~', '@']).next().unwrap_or_default();
+
+	let steps = path
+		.split('/')
+		.filter(|step| !step.is_empty())
+		.map(|step| step.parse::<usize>().ok())
+		.collect::<Option<Vec<_>>>()?;
+
+	Some((steps, char_offset))
+}
+
+fn parse_cfi(cfi: &str) -> Option<Cfi> {
+	let inner = cfi.trim().strip_prefix("epubcfi(")?.strip_suffix(')')?;
+
+	// A range is written as a parent path followed by the start and end paths, and only the
+	// start of the range is relevant to a reading position
+	let mut parts = inner.split(',');
+	let parent = parts.next()?;
+	let path = format!("{parent}{}", parts.next().unwrap_or_default());
+
+	let (package_path, content_path) = path.split_once('!')?;
+	let (package_steps, _) = parse_cfi_path(package_path)?;
+	// The first step is the spine element of the package, and the second is the itemref
+	let spine_index = match package_steps.get(1) {
+		Some(&step) if step % 2 == 0 => (step / 2).checked_sub(1)?,
+		_ => return None,
+	};
+	let (steps, char_offset) = parse_cfi_path(content_path)?;
+
+	Some(Cfi {
+		spine_index,
+		steps,
+		char_offset,
+	})
+}
+
+#[derive(Debug, PartialEq)]
+enum XPointerStep {
+	/// An element by its name and 0-based index among siblings with the same name
+	Element { name: String, index: usize },
+	/// A text node by its 0-based index among sibling text nodes
+	Text { index: usize },
+}
+
+/// A KOReader x-pointer into an EPUB
+#[derive(Debug, PartialEq)]
+struct XPointer {
+	spine_index: usize,
+	steps: Vec<XPointerStep>,
+	char_offset: usize,
+}
+
+fn parse_xpointer_step(step: &str) -> Option<XPointerStep> {
+	let (name, position) = match step.split_once('[') {
+		Some((name, position)) => (name, position.strip_suffix(']')?.parse().ok()?),
+		None => (step, 1usize),
+	};
+	let index = position.checked_sub(1)?;
+
+	if name == "text()" {
+		Some(XPointerStep::Text { index })
+	} else {
+		Some(XPointerStep::Element {
+			name: name.to_lowercase(),
+			index,
+		})
+	}
+}
+
+fn parse_xpointer(xpointer: &str) -> Option<XPointer> {
+	let rest = xpointer.trim().strip_prefix(XPOINTER_PREFIX)?;
+	let (fragment, rest) = rest.split_once(']')?;
+	let spine_index = fragment.parse::<usize>().ok()?.checked_sub(1)?;
+
+	let (path, char_offset) = match rest.rsplit_once('.') {
+		Some((path, offset))
+			if !offset.is_empty() && offset.chars().all(|c| c.is_ascii_digit()) =>
+		{
+			(path, offset.parse().ok()?)
+		},
+		_ => (rest, 0),
+	};
+
+	let steps = path
+		.split('/')
+		.filter(|step| !step.is_empty())
+		.map(parse_xpointer_step)
+		.collect::<Option<Vec<_>>>()?;
+
+	Some(XPointer {
+		spine_index,
+		steps,
+		char_offset,
+	})
+}
+
+/// Resolves positions within a single EPUB to a [`ReadingLocator`], parsing chapters from
+/// the spine as they are needed
+pub struct EpubLocations {
+	epub: EpubDoc<BufReader<File>>,
+}
+
+impl EpubLocations {
+	pub fn open(path: &str) -> Result<Self, FileError> {
+		Ok(Self {
+			epub: EpubProcessor::open(path)?,
+		})
+	}
+
+	/// Locate a position which is either an EPUB CFI or a KOReader x-pointer
+	pub fn locate(&mut self, position: &str) -> Result<ReadingLocator, FileError> {
+		if position.starts_with("epubcfi(") {
+			self.locate_cfi(position)
+		} else if position.starts_with(XPOINTER_PREFIX) {
+			self.locate_xpointer(position)
+		} else {
+			Err(FileError::EpubReadError(format!(
+				"Unsupported EPUB position: {position}"
+			)))
+		}
+	}
+
+	pub fn locate_cfi(&mut self, cfi: &str) -> Result<ReadingLocator, FileError> {
+		let parsed = parse_cfi(cfi).ok_or_else(|| {
+			FileError::EpubReadError(format!("Failed to parse epubcfi: {cfi}"))
+		})?;
+		let chapter = self.chapter(parsed.spine_index)?;
+		let offset = chapter.resolve_cfi(&parsed.steps, parsed.char_offset);
+
+		Ok(ReadingLocator {
+			cfi: Some(cfi.to_string()),
+			..self.locator(parsed.spine_index, &chapter, offset)?
+		})
+	}
+
+	pub fn locate_xpointer(
+		&mut self,
+		xpointer: &str,
+	) -> Result<ReadingLocator, FileError> {
+		let parsed = parse_xpointer(xpointer).ok_or_else(|| {
+			FileError::EpubReadError(format!("Failed to parse x-pointer: {xpointer}"))
+		})?;
+		let chapter = self.chapter(parsed.spine_index)?;
+		let offset = chapter.resolve_xpointer(&parsed.steps, parsed.char_offset);
+
+		Ok(ReadingLocator {
+			xpointer: Some(xpointer.to_string()),
+			..self.locator(parsed.spine_index, &chapter, offset)?
+		})
+	}
+
+	/// Locate a position by the href of a spine item and the progression (0.0 to 1.0)
+	/// through it. The href may be relative to the root of the EPUB or to the package
+	/// document, and any fragment is ignored
+	pub fn locate_progression(
+		&mut self,
+		href: &str,
+		progression: f64,
+	) -> Result<ReadingLocator, FileError> {
+		let href = href.split('#').next().unwrap_or_default();
+		let spine_index = (0..self.epub.spine.len())
+			.find(|&index| {
+				self.spine_href(index).is_ok_and(|spine_href| {
+					spine_href == href || spine_href.ends_with(&format!("/{href}"))
+				})
+			})
+			.ok_or_else(|| {
+				FileError::EpubReadError(format!("{href} is not in the spine"))
+			})?;
+		let chapter = self.chapter(spine_index)?;
+		let offset = chapter.offset_at_progression(progression);
+
+		self.locator(spine_index, &chapter, offset)
+	}
+
+	fn spine_id(&self, spine_index: usize) -> Result<&String, FileError> {
+		self.epub.spine.get(spine_index).ok_or_else(|| {
+			FileError::EpubReadError(format!("Spine item {spine_index} does not exist"))
+		})
+	}
+
+	/// The path of a spine item, relative to the root of the EPUB
+	fn spine_href(&self, spine_index: usize) -> Result<String, FileError> {
+		let id = self.spine_id(spine_index)?;
+		let (path, _) = self.epub.resources.get(id).ok_or_else(|| {
+			FileError::EpubReadError(format!("Spine item {id} is not a resource"))
+		})?;
+		Ok(path.to_string_lossy().replace('\\', "/"))
+	}
+
+	fn chapter(&mut self, spine_index: usize) -> Result<ChapterDocument, FileError> {
+		let id = self.spine_id(spine_index)?.clone();
+		let (contents, _) = self.epub.get_resource(&id).ok_or_else(|| {
+			FileError::EpubReadError(format!("Failed to read spine item {id}"))
+		})?;
+		ChapterDocument::parse(&String::from_utf8_lossy(&contents))
+	}
+
+	/// Build the locator for a character offset within a chapter. The total progression is
+	/// estimated from the spine, since chapters are not all parsed. Callers which know the
+	/// progression through the whole book (e.g. from the reader) should prefer it
+	fn locator(
+		&self,
+		spine_index: usize,
+		chapter: &ChapterDocument,
+		offset: usize,
+	) -> Result<ReadingLocator, FileError> {
+		let offset = chapter.snap(offset);
+		let progression = chapter.progression(offset);
+		let id = self.spine_id(spine_index)?;
+
+		Ok(ReadingLocator {
+			href: self.spine_href(spine_index)?,
+			progression,
+			total_progression: (spine_index as f64 + progression)
+				/ self.epub.spine.len() as f64,
+			cfi: Some(format!(
+				"epubcfi(/6/{}[{}]!{})",
+				(spine_index + 1) * 2,
+				escape_cfi_assertion(id),
+				chapter.cfi_steps(offset)
+			)),
+			xpointer: Some(format!(
+				"{XPOINTER_PREFIX}{}]{}",
+				spine_index + 1,
+				chapter.xpointer_path(offset)
+			)),
+		})
+	}
+}
+
+/// Resolve a position within the EPUB at the given path, which may be either an EPUB CFI or
+/// a KOReader x-pointer, to a locator which includes both
+pub fn resolve_epub_locator(
+	path: &str,
+	position: &str,
+) -> Result<ReadingLocator, FileError> {
+	EpubLocations::open(path)?.locate(position)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::filesystem::media::tests::get_test_epub_path;
+
+	const CHAPTER: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>Chapter One</title></head>
+<body id="chapter">
+<h1>One</h1>
+<p>The first paragraph.</p>
+<div>
+<p>Some <em>emphasized</em> text.</p>
+<p>The last paragraph.</p>
+</div>
+</body>
+</html>"#;
+
+	#[test]
+	fn test_parse_cfi() {
+		assert_eq!(
+			parse_cfi("epubcfi(/6/8[chap^[3^]]!/4[body]/2/3:15)"),
+			Some(Cfi {
+				spine_index: 3,
+				steps: vec![4, 2, 3],
+				char_offset: Some(15),
+			})
+		);
+		assert_eq!(
+			parse_cfi("epubcfi(/6/4!/4/10,/2/1:3,/3:4)"),
+			Some(Cfi {
+				spine_index: 1,
+				steps: vec![4, 10, 2, 1],
+				char_offset: Some(3),
+			})
+		);
+		assert_eq!(parse_cfi("/6/4!/4/10"), None);
+		assert_eq!(parse_cfi("epubcfi(/6/4/4/10)"), None);
+	}
+
+	#[test]
+	fn test_parse_xpointer() {
+		assert_eq!(
+			parse_xpointer("/body/DocFragment[3]/body/div/p[2]/text().15"),
+			Some(XPointer {
+				spine_index: 2,
+				steps: vec![
+					XPointerStep::Element {
+						name: "body".to_string(),
+						index: 0,
+					},
+					XPointerStep::Element {
+						name: "div".to_string(),
+						index: 0,
+					},
+					XPointerStep::Element {
+						name: "p".to_string(),
+						index: 1,
+					},
+					XPointerStep::Text { index: 0 },
+				],
+				char_offset: 15,
+			})
+		);
+		assert_eq!(
+			parse_xpointer("/body/DocFragment[1]/body/p[4]")
+				.map(|xpointer| (xpointer.steps.len(), xpointer.char_offset)),
+			Some((2, 0))
+		);
+		assert_eq!(parse_xpointer("/body/DocFragment[0]/body.0"), None);
+		assert_eq!(parse_xpointer("epubcfi(/6/4!/4/10)"), None);
+	}
+
+	#[test]
+	fn test_chapter_positions() {
+		let chapter = ChapterDocument::parse(CHAPTER).unwrap();
+
+		let cfi_offset = chapter.resolve_cfi(&[4, 6, 2, 3], Some(2));
+		let xpointer_steps =
+			parse_xpointer("/body/DocFragment[1]/body/div/p[1]/text()[2].2")
+				.unwrap()
+				.steps;
+		assert_eq!(chapter.resolve_xpointer(&xpointer_steps, 2), cfi_offset);
+
+		assert_eq!(chapter.cfi_steps(cfi_offset), "/4[chapter]/6/2/3:2");
+		assert_eq!(
+			chapter.xpointer_path(cfi_offset),
+			"/body/div/p[1]/text()[2].2"
+		);
+
+		let heading_offset = chapter.resolve_cfi(&[4, 2], None);
+		assert_eq!(chapter.xpointer_path(heading_offset), "/body/h1/text().0");
+		assert_eq!(chapter.cfi_steps(heading_offset), "/4[chapter]/2/1:0");
+
+		assert_eq!(chapter.progression(0), 0.0);
+		assert_eq!(chapter.progression(chapter.len), 1.0);
+		assert_eq!(
+			chapter.xpointer_path(chapter.offset_at_progression(1.0)),
+			"/body/div/p[2]/text().19"
+		);
+	}
+
+	#[test]
+	fn test_locate_round_trip() {
+		let mut locations = EpubLocations::open(&get_test_epub_path()).unwrap();
+
+		let start = locations.locate("epubcfi(/6/8!/4/2)").unwrap();
+		assert_eq!(start.progression, 0.0);
+
+		let locator = locations.locate_progression(&start.href, 0.5).unwrap();
+		assert!(locator.progression > 0.4 && locator.progression < 0.6);
+
+		let from_cfi = locations.locate(locator.cfi.as_deref().unwrap()).unwrap();
+		let from_xpointer = locations
+			.locate(locator.xpointer.as_deref().unwrap())
+			.unwrap();
+		assert_eq!(from_cfi.href, locator.href);
+		assert_eq!(from_xpointer.href, locator.href);
+		assert_eq!(from_cfi.progression, locator.progression);
+		assert_eq!(from_xpointer.progression, locator.progression);
+		assert_eq!(from_xpointer.cfi, locator.cfi);
+	}
+}
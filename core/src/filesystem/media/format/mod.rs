@@ -1,4 +1,9 @@
 pub mod epub;
+pub mod epub_locator;
+mod opf;
 pub mod pdf;
 pub mod rar;
+pub mod seven_zip;
+pub mod tar;
+mod xmp;
 pub mod zip;
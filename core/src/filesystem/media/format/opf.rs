@@ -0,0 +1,497 @@
+//! Extraction of [`MediaMetadata`] from an OPF package document, which is the metadata
+//! file inside of an EPUB (and the sidecar file written by tools like Calibre).
+//!
+//! Both EPUB 2 (attributes like `opf:role`) and EPUB 3 (`<meta refines="...">`) styles
+//! are supported. See https://www.w3.org/TR/epub/#sec-pkg-metadata
+
+use std::collections::HashMap;
+
+use prisma_client_rust::chrono::{Datelike, NaiveDate};
+use quick_xml::{
+	events::{BytesStart, Event},
+	Reader,
+};
+
+use crate::{db::entity::MediaMetadata, filesystem::error::FileError};
+
+/// A direct child of the `<metadata>` element of an OPF file
+#[derive(Debug, Default)]
+struct OpfElement {
+	/// The local name of the element, e.g. `creator` for `<dc:creator>`
+	name: String,
+	/// The attributes of the element, keyed by their local name
+	attributes: HashMap<String, String>,
+	text: String,
+}
+
+impl OpfElement {
+	fn new(start: &BytesStart) -> Self {
+		let attributes = start
+			.attributes()
+			.filter_map(Result::ok)
+			.map(|attr| {
+				let key =
+					String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+				let value = attr
+					.unescape_value()
+					.map(|value| value.to_string())
+					.unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string());
+				(key, value)
+			})
+			.collect();
+
+		Self {
+			name: local_name(start),
+			attributes,
+			text: String::new(),
+		}
+	}
+
+	fn attr(&self, name: &str) -> Option<&str> {
+		self.attributes.get(name).map(String::as_str)
+	}
+
+	/// The value of the element, which is the text content for most elements and the
+	/// `content` attribute for EPUB 2 style `<meta name="..." content="..." />` elements
+	fn value(&self) -> Option<String> {
+		let value = match self.attr("content") {
+			Some(content) if self.text.is_empty() => content,
+			_ => self.text.as_str(),
+		}
+		.trim();
+
+		(!value.is_empty()).then(|| value.to_string())
+	}
+}
+
+/// A refinement of another element, from an EPUB 3 `<meta refines="#id" property="...">`
+struct Refinement {
+	property: String,
+	value: String,
+}
+
+fn local_name(start: &BytesStart) -> String {
+	String::from_utf8_lossy(start.local_name().as_ref()).to_string()
+}
+
+/// Whether the element wraps metadata elements. Some older EPUB 2 files nest the Dublin
+/// Core elements in a `<dc-metadata>` element
+fn is_metadata_container(name: &str) -> bool {
+	matches!(name, "metadata" | "dc-metadata" | "x-metadata")
+}
+
+fn read_metadata_elements(contents: &str) -> Result<Vec<OpfElement>, FileError> {
+	let mut reader = Reader::from_str(contents);
+	reader.config_mut().trim_text(true);
+
+	let mut elements = Vec::new();
+	let mut in_metadata = false;
+	let mut current: Option<OpfElement> = None;
+
+	loop {
+		match reader.read_event() {
+			Ok(Event::Start(ref e)) => {
+				if is_metadata_container(&local_name(e)) {
+					in_metadata = true;
+				} else if in_metadata && current.is_none() {
+					current = Some(OpfElement::new(e));
+				}
+			},
+			Ok(Event::Empty(ref e)) => {
+				if in_metadata && current.is_none() {
+					elements.push(OpfElement::new(e));
+				}
+			},
+			Ok(Event::Text(e)) => {
+				if let (Some(element), Ok(text)) = (current.as_mut(), e.unescape()) {
+					element.text.push_str(&text);
+				}
+			},
+			Ok(Event::CData(e)) => {
+				if let Some(element) = current.as_mut() {
+					element.text.push_str(&String::from_utf8_lossy(&e));
+				}
+			},
+			Ok(Event::End(ref e)) => {
+				let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+				if name == "metadata" {
+					in_metadata = false;
+				} else if current.as_ref().is_some_and(|element| element.name == name) {
+					elements.extend(current.take());
+				}
+			},
+			Ok(Event::Eof) => break,
+			Err(e) => return Err(FileError::EpubReadError(e.to_string())),
+			_ => {},
+		}
+	}
+
+	Ok(elements)
+}
+
+fn push_unique(list: &mut Option<Vec<String>>, value: String) {
+	let list = list.get_or_insert_with(Vec::new);
+	if !list.contains(&value) {
+		list.push(value);
+	}
+}
+
+/// Add a creator or contributor to the list matching their MARC relator role. Creators
+/// without a role are assumed to be authors, while contributors without a role are ignored.
+///
+/// See https://www.loc.gov/marc/relators/relaterm.html
+fn push_creator(
+	metadata: &mut MediaMetadata,
+	name: String,
+	role: Option<&str>,
+	is_contributor: bool,
+) {
+	let list = match role.map(str::to_lowercase).as_deref() {
+		Some("aut" | "wri") => &mut metadata.writers,
+		None if !is_contributor => &mut metadata.writers,
+		Some("ill" | "art" | "pnc") => &mut metadata.pencillers,
+		Some("clr") => &mut metadata.colorists,
+		Some("ink") => &mut metadata.inkers,
+		Some("ltr") => &mut metadata.letterers,
+		Some("cov" | "cvr") => &mut metadata.cover_artists,
+		Some("edt" | "edc") => &mut metadata.editors,
+		_ => return,
+	};
+	push_unique(list, name);
+}
+
+/// Normalize an identifier into the form `scheme:value`, returning `None` for identifiers
+/// which are not ISBNs or ASINs (e.g. the UUIDs most EPUBs are identified by)
+pub(super) fn parse_identifier(value: &str, scheme: Option<&str>) -> Option<String> {
+	let normalize = |value: &str| {
+		value
+			.chars()
+			.filter(|c| !c.is_whitespace() && *c != '-')
+			.collect::<String>()
+			.to_uppercase()
+	};
+
+	match scheme.map(str::to_lowercase).as_deref() {
+		// Note: 02 and 15 are the ONIX codes for ISBN-10 and ISBN-13, respectively
+		Some("isbn" | "02" | "15") => return Some(format!("isbn:{}", normalize(value))),
+		Some("asin" | "amazon" | "mobi-asin") => {
+			return Some(format!("asin:{}", normalize(value)))
+		},
+		_ => {},
+	}
+
+	let lowercased = value.to_ascii_lowercase();
+	for (prefix, scheme) in [
+		("urn:isbn:", "isbn"),
+		("isbn:", "isbn"),
+		("urn:asin:", "asin"),
+		("asin:", "asin"),
+		("amazon:", "asin"),
+	] {
+		if lowercased.starts_with(prefix) {
+			return Some(format!("{scheme}:{}", normalize(&value[prefix.len()..])));
+		}
+	}
+
+	let normalized = normalize(value);
+	let is_isbn_13 = normalized.len() == 13
+		&& (normalized.starts_with("978") || normalized.starts_with("979"))
+		&& normalized.chars().all(|c| c.is_ascii_digit());
+	let is_isbn_10 = normalized.len() == 10
+		&& normalized.chars().take(9).all(|c| c.is_ascii_digit())
+		&& normalized.ends_with(|c: char| c.is_ascii_digit() || c == 'X');
+	let is_asin = normalized.len() == 10
+		&& normalized.starts_with("B0")
+		&& normalized.chars().all(|c| c.is_ascii_alphanumeric());
+
+	if is_isbn_13 || is_isbn_10 {
+		Some(format!("isbn:{normalized}"))
+	} else if is_asin {
+		Some(format!("asin:{normalized}"))
+	} else {
+		None
+	}
+}
+
+/// Parse a publication date, which may be a full date (with or without a time) or just a
+/// year and month or a year. Returns the (year, month, day) parts which could be parsed
+pub(super) fn parse_date(value: &str) -> (Option<i32>, Option<i32>, Option<i32>) {
+	let value = value.trim();
+
+	if let Some(date) = value
+		.get(..10)
+		.and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+	{
+		return (
+			Some(date.year()),
+			Some(date.month() as i32),
+			Some(date.day() as i32),
+		);
+	}
+
+	let year = value.get(..4).and_then(|year| year.parse().ok());
+	let month = value
+		.get(5..7)
+		.filter(|_| year.is_some())
+		.and_then(|month| month.parse().ok())
+		.filter(|month| (1..=12).contains(month));
+
+	(year, month, None)
+}
+
+/// Extract the metadata from the contents of an OPF file
+pub(crate) fn metadata_from_opf(contents: &str) -> Result<MediaMetadata, FileError> {
+	let elements = read_metadata_elements(contents)?;
+
+	let mut refinements: HashMap<String, Vec<Refinement>> = HashMap::new();
+	for element in &elements {
+		let (Some(target), Some(property), Some(value)) = (
+			element.attr("refines"),
+			element.attr("property"),
+			element.value(),
+		) else {
+			continue;
+		};
+		refinements
+			.entry(target.trim_start_matches('#').to_string())
+			.or_default()
+			.push(Refinement {
+				property: property.to_string(),
+				value,
+			});
+	}
+	let refinement = |element: &OpfElement, property: &str| {
+		element
+			.attr("id")
+			.and_then(|id| refinements.get(id))
+			.and_then(|refinements| refinements.iter().find(|r| r.property == property))
+	};
+
+	let mut metadata = MediaMetadata::default();
+	let mut main_title = None;
+	let mut calibre_series = None;
+	let mut calibre_series_index = None;
+	let mut collection: Option<(String, Option<f64>)> = None;
+
+	for element in &elements {
+		if element.attr("refines").is_some() {
+			continue;
+		}
+		let Some(value) = element.value() else {
+			continue;
+		};
+
+		match element.name.as_str() {
+			"title" => {
+				let title_type =
+					refinement(element, "title-type").map(|r| r.value.as_str());
+				if title_type == Some("main") {
+					main_title = Some(value);
+				} else if metadata.title.is_none() {
+					metadata.title = Some(value);
+				}
+			},
+			"creator" | "contributor" => {
+				let role = element
+					.attr("role")
+					.map(String::from)
+					.or_else(|| refinement(element, "role").map(|r| r.value.clone()));
+				push_creator(
+					&mut metadata,
+					value,
+					role.as_deref(),
+					element.name == "contributor",
+				);
+			},
+			"subject" => push_unique(&mut metadata.genre, value),
+			"description" => {
+				metadata.summary.get_or_insert(value);
+			},
+			"publisher" => {
+				metadata.publisher.get_or_insert(value);
+			},
+			"language" => {
+				metadata.language.get_or_insert(value);
+			},
+			"date" => {
+				// EPUB 2 files may contain multiple dates, e.g. for modification
+				let event = element.attr("event").map(str::to_lowercase);
+				if metadata.year.is_none()
+					&& matches!(event.as_deref(), None | Some("publication"))
+				{
+					let (year, month, day) = parse_date(&value);
+					metadata.year = year;
+					metadata.month = month;
+					metadata.day = day;
+				}
+			},
+			"identifier" => {
+				let scheme = element.attr("scheme").map(String::from).or_else(|| {
+					refinement(element, "identifier-type").map(|r| r.value.clone())
+				});
+				if let Some(identifier) = parse_identifier(&value, scheme.as_deref()) {
+					push_unique(&mut metadata.identifiers, identifier);
+				}
+			},
+			"meta" => match (element.attr("name"), element.attr("property")) {
+				(Some("calibre:series"), _) => calibre_series = Some(value),
+				(Some("calibre:series_index"), _) => {
+					calibre_series_index = value.parse::<f64>().ok();
+				},
+				(_, Some("belongs-to-collection")) => {
+					let collection_type = refinement(element, "collection-type")
+						.map(|r| r.value.to_lowercase());
+					let position = refinement(element, "group-position")
+						.and_then(|r| r.value.parse::<f64>().ok());
+					// Prefer collections explicitly marked as a series over any other
+					let is_series = collection_type.as_deref() == Some("series");
+					if collection.is_none() || is_series {
+						collection = Some((value, position));
+					}
+				},
+				_ => {},
+			},
+			_ => {},
+		}
+	}
+
+	if main_title.is_some() {
+		metadata.title = main_title;
+	}
+
+	// Calibre's series metadata is the most common in the wild, so it takes priority
+	if let Some(series) = calibre_series {
+		metadata.series = Some(series);
+		metadata.number = calibre_series_index;
+	} else if let Some((series, position)) = collection {
+		metadata.series = Some(series);
+		metadata.number = position;
+	}
+
+	Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EPUB2_OPF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" unique-identifier="uuid_id" version="2.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+    <dc:title>The Way of Kings</dc:title>
+    <dc:creator opf:file-as="Sanderson, Brandon" opf:role="aut">Brandon Sanderson</dc:creator>
+    <dc:contributor opf:role="ill">Michael Whelan</dc:contributor>
+    <dc:contributor opf:role="bkp">calibre (5.0.0) [https://calibre-ebook.com]</dc:contributor>
+    <dc:identifier opf:scheme="uuid" id="uuid_id">8b9f2c5e-3b7a-4b9c-9f3e-2a7d8c1e0f6a</dc:identifier>
+    <dc:identifier opf:scheme="ISBN">978-0-7653-2635-5</dc:identifier>
+    <dc:identifier opf:scheme="AMAZON">B003P2WO5E</dc:identifier>
+    <dc:date opf:event="modification">2020-01-01</dc:date>
+    <dc:date>2010-08-31T00:00:00+00:00</dc:date>
+    <dc:publisher>Tor Books</dc:publisher>
+    <dc:language>en</dc:language>
+    <dc:subject>Fantasy</dc:subject>
+    <dc:subject>Epic</dc:subject>
+    <dc:description>&lt;p&gt;A book, you know?&lt;/p&gt;</dc:description>
+    <meta name="calibre:series" content="The Stormlight Archive"/>
+    <meta name="calibre:series_index" content="1.0"/>
+  </metadata>
+</package>"#;
+
+	const EPUB3_OPF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="pub-id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="pub-id">urn:isbn:9780765326355</dc:identifier>
+    <dc:title id="subtitle">Book One of the Stormlight Archive</dc:title>
+    <meta refines="#subtitle" property="title-type">subtitle</meta>
+    <dc:title id="title">The Way of Kings</dc:title>
+    <meta refines="#title" property="title-type">main</meta>
+    <dc:creator id="creator01">Brandon Sanderson</dc:creator>
+    <meta refines="#creator01" property="role" scheme="marc:relators">aut</meta>
+    <dc:creator id="creator02">Isaac Stewart</dc:creator>
+    <meta refines="#creator02" property="role" scheme="marc:relators">ill</meta>
+    <dc:date>2010-08</dc:date>
+    <dc:language>en-US</dc:language>
+    <meta property="belongs-to-collection" id="c01">The Stormlight Archive</meta>
+    <meta refines="#c01" property="collection-type">series</meta>
+    <meta refines="#c01" property="group-position">1</meta>
+    <meta property="dcterms:modified">2020-01-01T00:00:00Z</meta>
+  </metadata>
+</package>"##;
+
+	#[test]
+	fn test_epub2_opf() {
+		let metadata = metadata_from_opf(EPUB2_OPF).unwrap();
+
+		assert_eq!(metadata.title, Some("The Way of Kings".to_string()));
+		assert_eq!(
+			metadata.writers,
+			Some(vec!["Brandon Sanderson".to_string()])
+		);
+		assert_eq!(
+			metadata.pencillers,
+			Some(vec!["Michael Whelan".to_string()])
+		);
+		assert_eq!(
+			metadata.identifiers,
+			Some(vec![
+				"isbn:9780765326355".to_string(),
+				"asin:B003P2WO5E".to_string()
+			])
+		);
+		assert_eq!(
+			(metadata.year, metadata.month, metadata.day),
+			(Some(2010), Some(8), Some(31))
+		);
+		assert_eq!(metadata.publisher, Some("Tor Books".to_string()));
+		assert_eq!(metadata.language, Some("en".to_string()));
+		assert_eq!(
+			metadata.genre,
+			Some(vec!["Fantasy".to_string(), "Epic".to_string()])
+		);
+		assert_eq!(
+			metadata.summary,
+			Some("<p>A book, you know?</p>".to_string())
+		);
+		assert_eq!(metadata.series, Some("The Stormlight Archive".to_string()));
+		assert_eq!(metadata.number, Some(1.0));
+	}
+
+	#[test]
+	fn test_epub3_opf() {
+		let metadata = metadata_from_opf(EPUB3_OPF).unwrap();
+
+		assert_eq!(metadata.title, Some("The Way of Kings".to_string()));
+		assert_eq!(
+			metadata.writers,
+			Some(vec!["Brandon Sanderson".to_string()])
+		);
+		assert_eq!(metadata.pencillers, Some(vec!["Isaac Stewart".to_string()]));
+		assert_eq!(
+			metadata.identifiers,
+			Some(vec!["isbn:9780765326355".to_string()])
+		);
+		assert_eq!(
+			(metadata.year, metadata.month, metadata.day),
+			(Some(2010), Some(8), None)
+		);
+		assert_eq!(metadata.language, Some("en-US".to_string()));
+		assert_eq!(metadata.series, Some("The Stormlight Archive".to_string()));
+		assert_eq!(metadata.number, Some(1.0));
+	}
+
+	#[test]
+	fn test_parse_identifier() {
+		assert_eq!(
+			parse_identifier("0-7653-2635-X", None),
+			Some("isbn:076532635X".to_string())
+		);
+		assert_eq!(
+			parse_identifier("amazon:B003P2WO5E", None),
+			Some("asin:B003P2WO5E".to_string())
+		);
+		assert_eq!(
+			parse_identifier("urn:uuid:8b9f2c5e-3b7a-4b9c-9f3e-2a7d8c1e0f6a", None),
+			None
+		);
+	}
+}
@@ -5,24 +5,40 @@ use std::{
 	path::{Path, PathBuf},
 };
 
-use pdf::{file::FileOptions, object::ParseOptions};
-use pdfium_render::prelude::{PdfRenderConfig, Pdfium};
+use merge::Merge;
+use pdf::{
+	file::FileOptions,
+	object::{Catalog, FileSpec, InfoDict, ParseOptions, Resolve},
+	primitive::PdfString,
+};
+use pdfium_render::prelude::{PdfBookmark, PdfRenderConfig, Pdfium};
 
 use crate::{
 	config::StumpConfig,
-	db::entity::MediaMetadata,
+	db::entity::{MediaMetadata, PdfOutlineItem},
 	filesystem::{
 		archive::create_zip_archive,
 		error::FileError,
 		hash::{self, generate_koreader_hash},
 		image::ImageFormat,
-		media::process::{
-			FileConverter, FileProcessor, FileProcessorOptions, ProcessedFile,
+		media::{
+			comic_info::COMIC_INFO_FILE_NAME,
+			process::{
+				FileConverter, FileProcessor, FileProcessorOptions, ProcessedFile,
+			},
+			utils::metadata_from_buf,
 		},
 		ContentType, FileParts, PathUtils, ProcessedFileHashes,
 	},
 };
 
+use super::xmp::metadata_from_xmp;
+
+/// The maximum depth of the outline which will be read. Outlines are linked lists in the
+/// file, so this (and [`MAX_OUTLINE_ITEMS`]) guards against malformed, cyclic outlines
+const MAX_OUTLINE_DEPTH: usize = 16;
+const MAX_OUTLINE_ITEMS: usize = 5000;
+
 /// A file processor for PDF files.
 pub struct PdfProcessor;
 
@@ -84,11 +100,17 @@ impl FileProcessor for PdfProcessor {
 	}
 
 	fn process_metadata(path: &str) -> Result<Option<MediaMetadata>, FileError> {
-		let file = FileOptions::cached()
+		let mut file = FileOptions::cached()
 			.parse_options(ParseOptions::tolerant())
 			.open(path)?;
 
-		Ok(file.trailer.info_dict.map(MediaMetadata::from))
+		let info_dict = file.trailer.info_dict.take();
+		let resolver = file.resolver();
+		Ok(PdfProcessor::collect_metadata(
+			file.get_root(),
+			info_dict,
+			&resolver,
+		))
 	}
 
 	fn process(
@@ -96,14 +118,16 @@ impl FileProcessor for PdfProcessor {
 		options: FileProcessorOptions,
 		_: &StumpConfig,
 	) -> Result<ProcessedFile, FileError> {
-		let file = FileOptions::cached()
+		let mut file = FileOptions::cached()
 			.parse_options(ParseOptions::tolerant())
 			.open(path)?;
 
 		let pages = file.pages().count() as i32;
 		// Note: The metadata is already parsed by the PDF library, so might as well use it
-		// PDF metadata is generally poop though
-		let metadata = file.trailer.info_dict.map(MediaMetadata::from);
+		let info_dict = file.trailer.info_dict.take();
+		let resolver = file.resolver();
+		let metadata =
+			PdfProcessor::collect_metadata(file.get_root(), info_dict, &resolver);
 		let ProcessedFileHashes {
 			hash,
 			koreader_hash,
@@ -180,6 +204,50 @@ impl FileProcessor for PdfProcessor {
 }
 
 impl PdfProcessor {
+	/// Collect the metadata of a PDF from each of the places it may be stored, in order of
+	/// priority: an embedded ComicInfo.xml attachment, the XMP metadata stream and finally
+	/// the document information dictionary
+	fn collect_metadata(
+		catalog: &Catalog,
+		info_dict: Option<InfoDict>,
+		resolver: &impl Resolve,
+	) -> Option<MediaMetadata> {
+		let comic_info = read_embedded_comic_info(catalog, resolver)
+			.and_then(|contents| metadata_from_buf(&contents));
+		let xmp = read_xmp_packet(catalog, resolver).and_then(|contents| {
+			metadata_from_xmp(&contents)
+				.inspect_err(|error| {
+					tracing::debug!(?error, "Failed to parse XMP metadata");
+				})
+				.ok()
+		});
+		let info = info_dict.map(MediaMetadata::from);
+
+		[comic_info, xmp, info]
+			.into_iter()
+			.flatten()
+			.reduce(|mut metadata, fallback| {
+				metadata.merge(fallback);
+				metadata
+			})
+	}
+
+	/// Get the outline (bookmarks) of a PDF, which typically serves as its table of contents
+	pub fn get_outline(
+		path: &str,
+		config: &StumpConfig,
+	) -> Result<Vec<PdfOutlineItem>, FileError> {
+		let pdfium = PdfProcessor::renderer(&config.pdfium_path)?;
+		let document = pdfium.load_pdf_from_file(path, None)?;
+
+		let mut remaining = MAX_OUTLINE_ITEMS;
+		Ok(document
+			.bookmarks()
+			.root()
+			.map(|first| collect_outline(first, 0, &mut remaining))
+			.unwrap_or_default())
+	}
+
 	/// Initializes a PDFium renderer. If a path to the PDFium library is not provided
 	pub fn renderer(pdfium_path: &Option<String>) -> Result<Pdfium, FileError> {
 		if let Some(path) = pdfium_path {
@@ -200,6 +268,92 @@ impl PdfProcessor {
 	}
 }
 
+/// Read the XMP metadata stream referenced by the document catalog, if present
+fn read_xmp_packet(catalog: &Catalog, resolver: &impl Resolve) -> Option<String> {
+	let stream = resolver
+		.get(catalog.metadata?)
+		.and_then(|stream| stream.data(resolver))
+		.inspect_err(|error| tracing::debug!(?error, "Failed to read XMP metadata"))
+		.ok()?;
+
+	Some(String::from_utf8_lossy(&stream).to_string())
+}
+
+/// Read the contents of an embedded ComicInfo.xml attachment, if present. Some tools which
+/// convert comics to PDF attach the original ComicInfo.xml to the document
+fn read_embedded_comic_info(
+	catalog: &Catalog,
+	resolver: &impl Resolve,
+) -> Option<String> {
+	let embedded_files = catalog.names.as_ref()?.embedded_files.as_ref()?;
+
+	let mut contents = None;
+	let result =
+		embedded_files.walk(resolver, &mut |name: &PdfString, spec: &FileSpec| {
+			if contents.is_some()
+				|| !name
+					.to_string_lossy()
+					.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME)
+			{
+				return;
+			}
+
+			let Some(file) = spec.ef.as_ref().and_then(|ef| ef.uf.or(ef.f)) else {
+				return;
+			};
+			match resolver.get(file).and_then(|stream| stream.data(resolver)) {
+				Ok(data) => contents = Some(String::from_utf8_lossy(&data).to_string()),
+				Err(error) => {
+					tracing::debug!(?error, "Failed to read embedded ComicInfo.xml");
+				},
+			}
+		});
+
+	if let Err(error) = result {
+		tracing::debug!(?error, "Failed to read embedded files");
+	}
+
+	contents
+}
+
+/// Collect the outline entries starting at `first` and its siblings, recursing into their
+/// children
+fn collect_outline(
+	first: PdfBookmark<'_>,
+	depth: usize,
+	remaining: &mut usize,
+) -> Vec<PdfOutlineItem> {
+	let mut items = Vec::new();
+	let mut current = Some(first);
+
+	while let Some(bookmark) = current {
+		if *remaining == 0 {
+			break;
+		}
+		*remaining -= 1;
+
+		let children = match bookmark.first_child() {
+			Some(child) if depth < MAX_OUTLINE_DEPTH => {
+				collect_outline(child, depth + 1, remaining)
+			},
+			_ => Vec::new(),
+		};
+		let page = bookmark
+			.destination()
+			.and_then(|destination| destination.page_index().ok())
+			.map(|index| i32::from(index) + 1);
+
+		items.push(PdfOutlineItem {
+			title: bookmark.title().unwrap_or_default(),
+			page,
+			children,
+		});
+		current = bookmark.next_sibling();
+	}
+
+	items
+}
+
 impl FileConverter for PdfProcessor {
 	fn to_zip(
 		path: &str,
@@ -0,0 +1,451 @@
+use sevenz_rust::{Password, SevenZReader};
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::Read,
+	path::{Component, Path, PathBuf},
+};
+use tracing::{debug, error, trace, warn};
+
+use crate::{
+	config::StumpConfig,
+	db::entity::MediaMetadata,
+	filesystem::{
+		archive::create_zip_archive,
+		content_type::ContentType,
+		error::FileError,
+		hash::{self, HASH_SAMPLE_COUNT, HASH_SAMPLE_SIZE},
+		image::ImageFormat,
+		media::{
+			comic_info::COMIC_INFO_FILE_NAME,
+			process::{
+				FileConverter, FileProcessor, FileProcessorOptions, ProcessedFile,
+			},
+			utils::{metadata_from_buf, sort_file_names},
+			zip::ZipProcessor,
+		},
+		FileParts, PathUtils, ProcessedFileHashes,
+	},
+};
+
+/// A file processor for 7-Zip (CB7) files.
+pub struct SevenZipProcessor;
+
+impl SevenZipProcessor {
+	fn open(path: &str) -> Result<SevenZReader<File>, FileError> {
+		Ok(SevenZReader::open(path, Password::empty())?)
+	}
+
+	/// Get the names of the non-directory entries in the archive, excluding hidden files
+	fn file_names(path: &str) -> Result<Vec<String>, FileError> {
+		let reader = SevenZipProcessor::open(path)?;
+
+		Ok(reader
+			.archive()
+			.files
+			.iter()
+			.filter(|entry| !entry.is_directory())
+			.map(|entry| entry.name().to_string())
+			.filter(|name| !Path::new(name).is_hidden_file())
+			.collect())
+	}
+
+	/// Get the names of the entries which are pages, sorted in reading order
+	fn page_names(path: &str) -> Result<Vec<String>, FileError> {
+		let mut names = SevenZipProcessor::file_names(path)?
+			.into_iter()
+			.filter(|name| Path::new(name).is_img())
+			.collect::<Vec<_>>();
+		sort_file_names(&mut names);
+		Ok(names)
+	}
+
+	/// Read the contents of the entries with the given names. Most 7-Zip archives are solid,
+	/// meaning an entry can only be decompressed by reading through the entries before it, so
+	/// this reads the archive sequentially and stops once every requested entry has been read.
+	fn read_entries(
+		path: &str,
+		names: &[&str],
+	) -> Result<HashMap<String, Vec<u8>>, FileError> {
+		let mut reader = SevenZipProcessor::open(path)?;
+		let mut contents = HashMap::new();
+
+		reader.for_each_entries(|entry, data| {
+			if names.contains(&entry.name()) {
+				let mut buf = Vec::new();
+				data.read_to_end(&mut buf)?;
+				contents.insert(entry.name().to_string(), buf);
+			} else {
+				std::io::copy(data, &mut std::io::sink())?;
+			}
+
+			Ok(contents.len() < names.len())
+		})?;
+
+		Ok(contents)
+	}
+
+	/// Extract every entry of the archive into the given directory. The extraction fails if
+	/// any entry would be written outside of the directory, e.g. `../../evil.jpg`
+	fn extract_to(path: &str, dest: &Path) -> Result<(), FileError> {
+		let mut reader = SevenZipProcessor::open(path)?;
+		let mut unsafe_entry = None;
+
+		reader.for_each_entries(|entry, data| {
+			let Some(relative_path) = contained_entry_path(entry.name()) else {
+				unsafe_entry = Some(entry.name().to_string());
+				return Ok(false);
+			};
+
+			let entry_path = dest.join(relative_path);
+			if entry.is_directory() {
+				std::fs::create_dir_all(&entry_path)?;
+			} else {
+				if let Some(parent) = entry_path.parent() {
+					std::fs::create_dir_all(parent)?;
+				}
+				std::io::copy(data, &mut File::create(&entry_path)?)?;
+			}
+
+			Ok(true)
+		})?;
+
+		match unsafe_entry {
+			Some(name) => Err(FileError::UnsafeArchiveEntry(name)),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Normalize the name of an archive entry into a relative path. Returns `None` if the path
+/// is absolute or would escape the directory it is extracted into.
+fn contained_entry_path(name: &str) -> Option<PathBuf> {
+	let mut normalized = PathBuf::new();
+	for component in Path::new(&name.replace('\\', "/")).components() {
+		match component {
+			Component::Normal(part) => normalized.push(part),
+			Component::CurDir => {},
+			Component::ParentDir => {
+				if !normalized.pop() {
+					return None;
+				}
+			},
+			Component::RootDir | Component::Prefix(_) => return None,
+		}
+	}
+	Some(normalized)
+}
+
+fn is_comic_info(name: &str) -> bool {
+	Path::new(name)
+		.file_name()
+		.is_some_and(|file_name| file_name == COMIC_INFO_FILE_NAME)
+}
+
+impl FileProcessor for SevenZipProcessor {
+	fn get_sample_size(path: &str) -> Result<u64, FileError> {
+		let file = File::open(path)?;
+
+		let file_size = file.metadata()?.len();
+		let threshold = HASH_SAMPLE_SIZE * HASH_SAMPLE_COUNT;
+
+		if file_size < threshold {
+			return Ok(file_size);
+		}
+
+		let division = file_size / threshold;
+
+		// if the file size is 4x the threshold, we'll take up to the threshold.
+		if division > 4 {
+			Ok(threshold)
+		} else {
+			Ok(file_size / 2)
+		}
+	}
+
+	fn generate_stump_hash(path: &str) -> Option<String> {
+		let sample = SevenZipProcessor::get_sample_size(path).ok()?;
+
+		match hash::generate(path, sample) {
+			Ok(digest) => Some(digest),
+			Err(e) => {
+				debug!(error = ?e, path, "Failed to digest 7-Zip file");
+				None
+			},
+		}
+	}
+
+	fn generate_hashes(
+		path: &str,
+		FileProcessorOptions {
+			generate_file_hashes,
+			..
+		}: FileProcessorOptions,
+	) -> Result<ProcessedFileHashes, FileError> {
+		let hash = generate_file_hashes
+			.then(|| SevenZipProcessor::generate_stump_hash(path))
+			.flatten();
+
+		Ok(ProcessedFileHashes {
+			hash,
+			koreader_hash: None,
+		})
+	}
+
+	fn process_metadata(path: &str) -> Result<Option<MediaMetadata>, FileError> {
+		let Some(name) = SevenZipProcessor::file_names(path)?
+			.into_iter()
+			.find(|name| is_comic_info(name))
+		else {
+			return Ok(None);
+		};
+
+		let mut contents = SevenZipProcessor::read_entries(path, &[name.as_str()])?;
+
+		if let Some(buf) = contents.remove(&name) {
+			let content_str = std::str::from_utf8(&buf)?;
+			Ok(metadata_from_buf(content_str))
+		} else {
+			Ok(None)
+		}
+	}
+
+	fn process(
+		path: &str,
+		options: FileProcessorOptions,
+		config: &StumpConfig,
+	) -> Result<ProcessedFile, FileError> {
+		if options.convert_rar_to_zip {
+			let zip_path_buf = SevenZipProcessor::to_zip(
+				path,
+				options.delete_conversion_source,
+				None,
+				config,
+			)?;
+			let zip_path = zip_path_buf.to_str().ok_or_else(|| {
+				FileError::UnknownError(
+					"Converted 7-Zip file failed to be discovered".to_string(),
+				)
+			})?;
+			return ZipProcessor::process(zip_path, options, config);
+		}
+
+		let ProcessedFileHashes {
+			hash,
+			koreader_hash,
+		} = SevenZipProcessor::generate_hashes(path, options)?;
+
+		let pages = SevenZipProcessor::page_names(path)?.len() as i32;
+		let metadata = if options.process_metadata {
+			SevenZipProcessor::process_metadata(path)?
+		} else {
+			None
+		};
+
+		Ok(ProcessedFile {
+			path: PathBuf::from(path),
+			hash,
+			koreader_hash,
+			metadata,
+			pages,
+		})
+	}
+
+	fn get_page(
+		file: &str,
+		page: i32,
+		_: &StumpConfig,
+	) -> Result<(ContentType, Vec<u8>), FileError> {
+		let page_names = SevenZipProcessor::page_names(file)?;
+		let target = usize::try_from(page - 1)
+			.ok()
+			.and_then(|index| page_names.get(index))
+			.ok_or(FileError::NoImageError)?;
+		let FileParts { extension, .. } = Path::new(target).file_parts();
+
+		let mut contents = SevenZipProcessor::read_entries(file, &[target.as_str()])?;
+		let Some(bytes) = contents.remove(target) else {
+			return Err(FileError::NoImageError);
+		};
+
+		if bytes.len() < 5 {
+			debug!(path = ?file, ?bytes, "File is too small to determine content type");
+			return Err(FileError::NoImageError);
+		}
+		let mut magic_header = [0; 5];
+		magic_header.copy_from_slice(&bytes[0..5]);
+		let content_type =
+			ContentType::from_bytes_with_fallback(&magic_header, &extension);
+
+		Ok((content_type, bytes))
+	}
+
+	fn get_page_count(path: &str, _: &StumpConfig) -> Result<i32, FileError> {
+		Ok(SevenZipProcessor::page_names(path)?.len() as i32)
+	}
+
+	fn get_page_content_types(
+		path: &str,
+		pages: Vec<i32>,
+	) -> Result<HashMap<i32, ContentType>, FileError> {
+		let page_names = SevenZipProcessor::page_names(path)?;
+
+		let content_types = page_names
+			.iter()
+			.enumerate()
+			.map(|(index, name)| (index as i32 + 1, name))
+			.filter(|(page, _)| pages.contains(page))
+			.map(|(page, name)| {
+				let content_type = Path::new(name).naive_content_type();
+				trace!(?name, ?content_type, "found a targeted 7-Zip entry");
+				(page, content_type)
+			})
+			.collect();
+
+		Ok(content_types)
+	}
+}
+
+impl FileConverter for SevenZipProcessor {
+	fn to_zip(
+		path: &str,
+		delete_source: bool,
+		_: Option<ImageFormat>,
+		config: &StumpConfig,
+	) -> Result<PathBuf, FileError> {
+		debug!(path, "Converting 7-Zip to ZIP");
+
+		let path_buf = PathBuf::from(path);
+		let parent = path_buf.parent().unwrap_or_else(|| Path::new("/"));
+		let FileParts {
+			extension,
+			file_stem,
+			file_name,
+		} = path_buf.as_path().file_parts();
+
+		let cache_dir = config.get_cache_dir();
+		let unpacked_path = cache_dir.join(file_stem);
+
+		trace!(?unpacked_path, "Extracting 7-Zip to disk");
+		if let Err(err) = SevenZipProcessor::extract_to(path, &unpacked_path) {
+			// Anything written before the failure should not linger in the cache
+			let _ = std::fs::remove_dir_all(&unpacked_path);
+			return Err(err);
+		}
+
+		let zip_path =
+			create_zip_archive(&unpacked_path, &file_name, &extension, parent)?;
+
+		if delete_source {
+			if let Err(err) = trash::delete(path) {
+				warn!(error = ?err, path, "Failed to delete converted 7-Zip file");
+			}
+		}
+
+		if let Err(err) = std::fs::remove_dir_all(&unpacked_path) {
+			error!(
+				error = ?err, ?cache_dir, ?unpacked_path, "Failed to delete unpacked 7-Zip contents after conversion",
+			);
+		}
+
+		Ok(zip_path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::filesystem::media::tests::create_test_comic_dir;
+
+	fn create_test_cb7(dir: &Path) -> String {
+		let contents = create_test_comic_dir(dir);
+		let path = dir.join("book.cb7");
+		sevenz_rust::compress_to_path(&contents, &path)
+			.expect("Failed to create test CB7 file");
+		path.to_string_lossy().to_string()
+	}
+
+	#[test]
+	fn test_process() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let path = create_test_cb7(tempdir.path());
+		let config = StumpConfig::debug();
+
+		let processed_file = SevenZipProcessor::process(
+			&path,
+			FileProcessorOptions {
+				process_metadata: true,
+				..Default::default()
+			},
+			&config,
+		)
+		.expect("Failed to process CB7 file");
+
+		assert_eq!(processed_file.pages, 2);
+		assert_eq!(
+			processed_file.metadata.and_then(|metadata| metadata.title),
+			Some("Test Comic".to_string())
+		);
+	}
+
+	#[test]
+	fn test_get_page() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let path = create_test_cb7(tempdir.path());
+		let config = StumpConfig::debug();
+
+		let (content_type, bytes) = SevenZipProcessor::get_page(&path, 1, &config)
+			.expect("Failed to get first page");
+		assert_eq!(content_type, ContentType::JPEG);
+		assert!(!bytes.is_empty());
+
+		let (content_type, _) = SevenZipProcessor::get_page(&path, 2, &config)
+			.expect("Failed to get second page");
+		assert_eq!(content_type, ContentType::PNG);
+
+		assert!(SevenZipProcessor::get_page(&path, 3, &config).is_err());
+	}
+
+	#[test]
+	fn test_get_page_content_types() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let path = create_test_cb7(tempdir.path());
+
+		let content_types = SevenZipProcessor::get_page_content_types(&path, vec![1, 2])
+			.expect("Failed to get page content types");
+		assert_eq!(content_types.get(&1), Some(&ContentType::JPEG));
+		assert_eq!(content_types.get(&2), Some(&ContentType::PNG));
+	}
+
+	#[test]
+	fn test_seven_zip_to_zip() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let path = create_test_cb7(tempdir.path());
+		let config = StumpConfig::debug();
+
+		let zip_path = SevenZipProcessor::to_zip(&path, true, None, &config)
+			.expect("Failed to convert CB7 file");
+
+		assert_eq!(
+			zip_path.extension().and_then(|ext| ext.to_str()),
+			Some("cbz")
+		);
+		assert!(!Path::new(&path).exists());
+	}
+
+	#[test]
+	fn test_contained_entry_path() {
+		assert_eq!(
+			contained_entry_path("chapter/001.jpg"),
+			Some(PathBuf::from("chapter/001.jpg"))
+		);
+		assert_eq!(
+			contained_entry_path("./chapter/../001.jpg"),
+			Some(PathBuf::from("001.jpg"))
+		);
+		assert_eq!(contained_entry_path("../001.jpg"), None);
+		assert_eq!(contained_entry_path("chapter/../../001.jpg"), None);
+		assert_eq!(contained_entry_path("..\\..\\001.jpg"), None);
+		assert_eq!(contained_entry_path("/etc/passwd"), None);
+	}
+}
@@ -0,0 +1,385 @@
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::Read,
+	path::{Path, PathBuf},
+};
+use tracing::{debug, error, trace, warn};
+
+use crate::{
+	config::StumpConfig,
+	db::entity::MediaMetadata,
+	filesystem::{
+		archive::create_zip_archive,
+		content_type::ContentType,
+		error::FileError,
+		hash::{self, HASH_SAMPLE_COUNT, HASH_SAMPLE_SIZE},
+		image::ImageFormat,
+		media::{
+			comic_info::COMIC_INFO_FILE_NAME,
+			process::{
+				FileConverter, FileProcessor, FileProcessorOptions, ProcessedFile,
+			},
+			utils::{metadata_from_buf, sort_file_names},
+			zip::ZipProcessor,
+		},
+		FileParts, PathUtils, ProcessedFileHashes,
+	},
+};
+
+/// A file processor for TAR (CBT) files.
+pub struct TarProcessor;
+
+impl TarProcessor {
+	fn open(path: &str) -> Result<tar::Archive<File>, FileError> {
+		Ok(tar::Archive::new(File::open(path)?))
+	}
+
+	/// Get the names of the regular file entries in the archive, excluding hidden files
+	fn file_names(path: &str) -> Result<Vec<String>, FileError> {
+		let mut archive = TarProcessor::open(path)?;
+		let mut names = Vec::new();
+
+		for entry in archive.entries()? {
+			let entry = entry?;
+			if !entry.header().entry_type().is_file() {
+				continue;
+			}
+
+			let entry_path = entry.path()?;
+			if entry_path.is_hidden_file() {
+				continue;
+			}
+
+			names.push(entry_path.to_string_lossy().to_string());
+		}
+
+		Ok(names)
+	}
+
+	/// Get the names of the entries which are pages, sorted in reading order
+	fn page_names(path: &str) -> Result<Vec<String>, FileError> {
+		let mut names = TarProcessor::file_names(path)?
+			.into_iter()
+			.filter(|name| Path::new(name).is_img())
+			.collect::<Vec<_>>();
+		sort_file_names(&mut names);
+		Ok(names)
+	}
+
+	/// Read the contents of the entries with the given names. TAR archives have no central
+	/// directory, so this reads the archive sequentially and stops once every requested entry
+	/// has been read.
+	fn read_entries(
+		path: &str,
+		names: &[&str],
+	) -> Result<HashMap<String, Vec<u8>>, FileError> {
+		let mut archive = TarProcessor::open(path)?;
+		let mut contents = HashMap::new();
+
+		for entry in archive.entries()? {
+			let mut entry = entry?;
+			let name = entry.path()?.to_string_lossy().to_string();
+
+			if names.contains(&name.as_str()) {
+				let mut buf = Vec::new();
+				entry.read_to_end(&mut buf)?;
+				contents.insert(name, buf);
+			}
+
+			if contents.len() == names.len() {
+				break;
+			}
+		}
+
+		Ok(contents)
+	}
+}
+
+fn is_comic_info(name: &str) -> bool {
+	Path::new(name)
+		.file_name()
+		.is_some_and(|file_name| file_name == COMIC_INFO_FILE_NAME)
+}
+
+impl FileProcessor for TarProcessor {
+	fn get_sample_size(path: &str) -> Result<u64, FileError> {
+		let file = File::open(path)?;
+
+		let file_size = file.metadata()?.len();
+		let threshold = HASH_SAMPLE_SIZE * HASH_SAMPLE_COUNT;
+
+		if file_size < threshold {
+			return Ok(file_size);
+		}
+
+		let division = file_size / threshold;
+
+		// if the file size is 4x the threshold, we'll take up to the threshold.
+		if division > 4 {
+			Ok(threshold)
+		} else {
+			Ok(file_size / 2)
+		}
+	}
+
+	fn generate_stump_hash(path: &str) -> Option<String> {
+		let sample = TarProcessor::get_sample_size(path).ok()?;
+
+		match hash::generate(path, sample) {
+			Ok(digest) => Some(digest),
+			Err(e) => {
+				debug!(error = ?e, path, "Failed to digest TAR file");
+				None
+			},
+		}
+	}
+
+	fn generate_hashes(
+		path: &str,
+		FileProcessorOptions {
+			generate_file_hashes,
+			..
+		}: FileProcessorOptions,
+	) -> Result<ProcessedFileHashes, FileError> {
+		let hash = generate_file_hashes
+			.then(|| TarProcessor::generate_stump_hash(path))
+			.flatten();
+
+		Ok(ProcessedFileHashes {
+			hash,
+			koreader_hash: None,
+		})
+	}
+
+	fn process_metadata(path: &str) -> Result<Option<MediaMetadata>, FileError> {
+		let Some(name) = TarProcessor::file_names(path)?
+			.into_iter()
+			.find(|name| is_comic_info(name))
+		else {
+			return Ok(None);
+		};
+
+		let mut contents = TarProcessor::read_entries(path, &[name.as_str()])?;
+
+		if let Some(buf) = contents.remove(&name) {
+			let content_str = std::str::from_utf8(&buf)?;
+			Ok(metadata_from_buf(content_str))
+		} else {
+			Ok(None)
+		}
+	}
+
+	fn process(
+		path: &str,
+		options: FileProcessorOptions,
+		config: &StumpConfig,
+	) -> Result<ProcessedFile, FileError> {
+		if options.convert_rar_to_zip {
+			let zip_path_buf = TarProcessor::to_zip(
+				path,
+				options.delete_conversion_source,
+				None,
+				config,
+			)?;
+			let zip_path = zip_path_buf.to_str().ok_or_else(|| {
+				FileError::UnknownError(
+					"Converted TAR file failed to be discovered".to_string(),
+				)
+			})?;
+			return ZipProcessor::process(zip_path, options, config);
+		}
+
+		let ProcessedFileHashes {
+			hash,
+			koreader_hash,
+		} = TarProcessor::generate_hashes(path, options)?;
+
+		let pages = TarProcessor::page_names(path)?.len() as i32;
+		let metadata = if options.process_metadata {
+			TarProcessor::process_metadata(path)?
+		} else {
+			None
+		};
+
+		Ok(ProcessedFile {
+			path: PathBuf::from(path),
+			hash,
+			koreader_hash,
+			metadata,
+			pages,
+		})
+	}
+
+	fn get_page(
+		file: &str,
+		page: i32,
+		_: &StumpConfig,
+	) -> Result<(ContentType, Vec<u8>), FileError> {
+		let page_names = TarProcessor::page_names(file)?;
+		let target = usize::try_from(page - 1)
+			.ok()
+			.and_then(|index| page_names.get(index))
+			.ok_or(FileError::NoImageError)?;
+		let FileParts { extension, .. } = Path::new(target).file_parts();
+
+		let mut contents = TarProcessor::read_entries(file, &[target.as_str()])?;
+		let Some(bytes) = contents.remove(target) else {
+			return Err(FileError::NoImageError);
+		};
+
+		if bytes.len() < 5 {
+			debug!(path = ?file, ?bytes, "File is too small to determine content type");
+			return Err(FileError::NoImageError);
+		}
+		let mut magic_header = [0; 5];
+		magic_header.copy_from_slice(&bytes[0..5]);
+		let content_type =
+			ContentType::from_bytes_with_fallback(&magic_header, &extension);
+
+		Ok((content_type, bytes))
+	}
+
+	fn get_page_count(path: &str, _: &StumpConfig) -> Result<i32, FileError> {
+		Ok(TarProcessor::page_names(path)?.len() as i32)
+	}
+
+	fn get_page_content_types(
+		path: &str,
+		pages: Vec<i32>,
+	) -> Result<HashMap<i32, ContentType>, FileError> {
+		let page_names = TarProcessor::page_names(path)?;
+
+		let content_types = page_names
+			.iter()
+			.enumerate()
+			.map(|(index, name)| (index as i32 + 1, name))
+			.filter(|(page, _)| pages.contains(page))
+			.map(|(page, name)| {
+				let content_type = Path::new(name).naive_content_type();
+				trace!(?name, ?content_type, "found a targeted TAR entry");
+				(page, content_type)
+			})
+			.collect();
+
+		Ok(content_types)
+	}
+}
+
+impl FileConverter for TarProcessor {
+	fn to_zip(
+		path: &str,
+		delete_source: bool,
+		_: Option<ImageFormat>,
+		config: &StumpConfig,
+	) -> Result<PathBuf, FileError> {
+		debug!(path, "Converting TAR to ZIP");
+
+		let path_buf = PathBuf::from(path);
+		let parent = path_buf.parent().unwrap_or_else(|| Path::new("/"));
+		let FileParts {
+			extension,
+			file_stem,
+			file_name,
+		} = path_buf.as_path().file_parts();
+
+		let cache_dir = config.get_cache_dir();
+		let unpacked_path = cache_dir.join(file_stem);
+
+		trace!(?unpacked_path, "Extracting TAR to disk");
+		// Note: unpack refuses to write entries outside of the destination directory
+		TarProcessor::open(path)?.unpack(&unpacked_path)?;
+
+		let zip_path =
+			create_zip_archive(&unpacked_path, &file_name, &extension, parent)?;
+
+		if delete_source {
+			if let Err(err) = trash::delete(path) {
+				warn!(error = ?err, path, "Failed to delete converted TAR file");
+			}
+		}
+
+		if let Err(err) = std::fs::remove_dir_all(&unpacked_path) {
+			error!(
+				error = ?err, ?cache_dir, ?unpacked_path, "Failed to delete unpacked TAR contents after conversion",
+			);
+		}
+
+		Ok(zip_path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::filesystem::media::tests::create_test_comic_dir;
+
+	fn create_test_cbt(dir: &Path) -> String {
+		let contents = create_test_comic_dir(dir);
+		let path = dir.join("book.cbt");
+		let mut builder =
+			tar::Builder::new(File::create(&path).expect("Failed to create CBT file"));
+		builder
+			.append_dir_all("book", &contents)
+			.expect("Failed to add files to CBT file");
+		builder.finish().expect("Failed to finish CBT file");
+		path.to_string_lossy().to_string()
+	}
+
+	#[test]
+	fn test_process() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let path = create_test_cbt(tempdir.path());
+		let config = StumpConfig::debug();
+
+		let processed_file = TarProcessor::process(
+			&path,
+			FileProcessorOptions {
+				process_metadata: true,
+				..Default::default()
+			},
+			&config,
+		)
+		.expect("Failed to process CBT file");
+
+		assert_eq!(processed_file.pages, 2);
+		assert_eq!(
+			processed_file.metadata.and_then(|metadata| metadata.title),
+			Some("Test Comic".to_string())
+		);
+	}
+
+	#[test]
+	fn test_get_page() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let path = create_test_cbt(tempdir.path());
+		let config = StumpConfig::debug();
+
+		let (content_type, bytes) =
+			TarProcessor::get_page(&path, 1, &config).expect("Failed to get first page");
+		assert_eq!(content_type, ContentType::JPEG);
+		assert!(!bytes.is_empty());
+
+		let (content_type, _) =
+			TarProcessor::get_page(&path, 2, &config).expect("Failed to get second page");
+		assert_eq!(content_type, ContentType::PNG);
+
+		assert!(TarProcessor::get_page(&path, 3, &config).is_err());
+	}
+
+	#[test]
+	fn test_tar_to_zip() {
+		let tempdir = tempfile::tempdir().expect("Failed to create temporary directory");
+		let path = create_test_cbt(tempdir.path());
+		let config = StumpConfig::debug();
+
+		let zip_path = TarProcessor::to_zip(&path, true, None, &config)
+			.expect("Failed to convert CBT file");
+
+		assert_eq!(
+			zip_path.extension().and_then(|ext| ext.to_str()),
+			Some("cbz")
+		);
+		assert!(!Path::new(&path).exists());
+	}
+}
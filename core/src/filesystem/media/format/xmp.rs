@@ -0,0 +1,235 @@
+//! Extraction of [`MediaMetadata`] from an XMP packet, which is the XML metadata stream
+//! embedded in most PDFs created in the last two decades. Dublin Core (`dc:`) properties
+//! are read, as well as PRISM (`prism:`) properties, which are common for magazines.
+//!
+//! See https://developer.adobe.com/xmp/docs/XMPNamespaces/
+
+use std::collections::HashMap;
+
+use quick_xml::{
+	events::{BytesStart, Event},
+	Reader,
+};
+
+use crate::{db::entity::MediaMetadata, filesystem::error::FileError};
+
+use super::opf::{parse_date, parse_identifier};
+
+fn qualified_name(start: &BytesStart) -> String {
+	String::from_utf8_lossy(start.name().as_ref()).to_string()
+}
+
+/// Whether the element (or attribute) is an XMP property, rather than part of the RDF
+/// structure which wraps the properties
+fn is_property(name: &str) -> bool {
+	!(name.starts_with("rdf:") || name.starts_with("x:") || name.starts_with("xmlns"))
+}
+
+fn push_property(properties: &mut HashMap<String, Vec<String>>, name: &str, value: &str) {
+	let value = value.trim();
+	if value.is_empty() {
+		return;
+	}
+
+	let values = properties.entry(name.to_string()).or_default();
+	if !values.iter().any(|existing| existing == value) {
+		values.push(value.to_string());
+	}
+}
+
+/// Simple properties may be written as attributes of `rdf:Description`, e.g.
+/// `<rdf:Description prism:volume="12">`
+fn push_attribute_properties(
+	properties: &mut HashMap<String, Vec<String>>,
+	start: &BytesStart,
+) {
+	for attr in start.attributes().filter_map(Result::ok) {
+		let name = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+		if is_property(&name) {
+			if let Ok(value) = attr.unescape_value() {
+				push_property(properties, &name, &value);
+			}
+		}
+	}
+}
+
+/// Read the properties of an XMP packet, keyed by their qualified name (e.g. `dc:creator`).
+/// The items of arrays (`rdf:Seq`, `rdf:Bag` and `rdf:Alt`) are flattened into the values
+/// of the property which contains them
+fn read_properties(contents: &str) -> Result<HashMap<String, Vec<String>>, FileError> {
+	let mut reader = Reader::from_str(contents);
+	reader.config_mut().trim_text(true);
+
+	let mut properties = HashMap::new();
+	let mut stack: Vec<String> = Vec::new();
+
+	loop {
+		match reader.read_event() {
+			Ok(Event::Start(ref e)) => {
+				let name = qualified_name(e);
+				if name == "rdf:Description" {
+					push_attribute_properties(&mut properties, e);
+				}
+				stack.push(name);
+			},
+			Ok(Event::Empty(ref e)) => {
+				if qualified_name(e) == "rdf:Description" {
+					push_attribute_properties(&mut properties, e);
+				}
+			},
+			Ok(Event::Text(e)) => {
+				let property = stack.iter().rev().find(|name| is_property(name));
+				if let (Some(property), Ok(text)) = (property, e.unescape()) {
+					push_property(&mut properties, property, &text);
+				}
+			},
+			Ok(Event::End(_)) => {
+				stack.pop();
+			},
+			Ok(Event::Eof) => break,
+			Err(e) => return Err(FileError::PdfProcessingError(e.to_string())),
+			_ => {},
+		}
+	}
+
+	Ok(properties)
+}
+
+/// Extract the metadata from the contents of an XMP packet
+pub(crate) fn metadata_from_xmp(contents: &str) -> Result<MediaMetadata, FileError> {
+	let properties = read_properties(contents)?;
+
+	let first = |names: &[&str]| {
+		names
+			.iter()
+			.find_map(|name| properties.get(*name).and_then(|v| v.first().cloned()))
+	};
+	let all = |name: &str| properties.get(name).cloned();
+
+	// Keywords are frequently written as a single subject, e.g. "Fantasy; Magic"
+	let genre = all("dc:subject").map(|subjects| {
+		subjects
+			.iter()
+			.flat_map(|subject| subject.split(';'))
+			.map(|subject| subject.trim().to_string())
+			.filter(|subject| !subject.is_empty())
+			.fold(Vec::new(), |mut genre, subject| {
+				if !genre.contains(&subject) {
+					genre.push(subject);
+				}
+				genre
+			})
+	});
+
+	let identifiers = all("dc:identifier")
+		.unwrap_or_default()
+		.iter()
+		.filter_map(|identifier| parse_identifier(identifier, None))
+		.chain(
+			all("prism:isbn")
+				.unwrap_or_default()
+				.iter()
+				.filter_map(|isbn| parse_identifier(isbn, Some("isbn"))),
+		)
+		.fold(Vec::new(), |mut identifiers, identifier| {
+			if !identifiers.contains(&identifier) {
+				identifiers.push(identifier);
+			}
+			identifiers
+		});
+
+	let (year, month, day) =
+		first(&["prism:coverDate", "prism:publicationDate", "dc:date"])
+			.map(|date| parse_date(&date))
+			.unwrap_or_default();
+
+	Ok(MediaMetadata {
+		title: first(&["dc:title"]),
+		series: first(&["prism:publicationName"]),
+		number: first(&["prism:number", "prism:issueIdentifier"])
+			.and_then(|number| number.parse().ok()),
+		volume: first(&["prism:volume"]).and_then(|volume| volume.parse().ok()),
+		summary: first(&["dc:description"]),
+		genre,
+		year,
+		month,
+		day,
+		writers: all("dc:creator"),
+		publisher: first(&["dc:publisher", "prism:publisher"]),
+		language: first(&["dc:language"]),
+		links: all("prism:url"),
+		identifiers: (!identifiers.is_empty()).then_some(identifiers),
+		page_count: first(&["prism:pageCount"]).and_then(|count| count.parse().ok()),
+		..Default::default()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const XMP_PACKET: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+    <rdf:Description rdf:about=""
+        xmlns:dc="http://purl.org/dc/elements/1.1/"
+        xmlns:prism="http://prismstandard.org/namespaces/basic/2.0/"
+        prism:volume="12"
+        prism:number="3">
+      <dc:title>
+        <rdf:Alt>
+          <rdf:li xml:lang="x-default">Spring Issue</rdf:li>
+        </rdf:Alt>
+      </dc:title>
+      <dc:creator>
+        <rdf:Seq>
+          <rdf:li>Jane Doe</rdf:li>
+          <rdf:li>John Smith</rdf:li>
+        </rdf:Seq>
+      </dc:creator>
+      <dc:subject>
+        <rdf:Bag>
+          <rdf:li>Woodworking; Crafts</rdf:li>
+          <rdf:li>Hobbies</rdf:li>
+        </rdf:Bag>
+      </dc:subject>
+      <dc:language><rdf:Bag><rdf:li>en</rdf:li></rdf:Bag></dc:language>
+      <prism:publicationName>Woodworker Monthly</prism:publicationName>
+      <prism:coverDate>2019-04-01</prism:coverDate>
+      <prism:isbn>978-0-7653-2635-5</prism:isbn>
+    </rdf:Description>
+  </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+	#[test]
+	fn test_metadata_from_xmp() {
+		let metadata = metadata_from_xmp(XMP_PACKET).unwrap();
+
+		assert_eq!(metadata.title, Some("Spring Issue".to_string()));
+		assert_eq!(
+			metadata.writers,
+			Some(vec!["Jane Doe".to_string(), "John Smith".to_string()])
+		);
+		assert_eq!(
+			metadata.genre,
+			Some(vec![
+				"Woodworking".to_string(),
+				"Crafts".to_string(),
+				"Hobbies".to_string()
+			])
+		);
+		assert_eq!(metadata.language, Some("en".to_string()));
+		assert_eq!(metadata.series, Some("Woodworker Monthly".to_string()));
+		assert_eq!(metadata.volume, Some(12));
+		assert_eq!(metadata.number, Some(3.0));
+		assert_eq!(
+			(metadata.year, metadata.month, metadata.day),
+			(Some(2019), Some(4), Some(1))
+		);
+		assert_eq!(
+			metadata.identifiers,
+			Some(vec!["isbn:9780765326355".to_string()])
+		);
+	}
+}
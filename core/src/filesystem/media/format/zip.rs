@@ -1,5 +1,11 @@
-use std::{collections::HashMap, fs::File, io::Read, path::PathBuf};
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::{Read, Write},
+	path::{Path, PathBuf},
+};
 use tracing::{debug, error, trace};
+use zip::{write::FileOptions, CompressionMethod};
 
 use crate::{
 	config::StumpConfig,
@@ -9,6 +15,7 @@ use crate::{
 		error::FileError,
 		hash,
 		media::{
+			comic_info::{metadata_to_comic_info, COMIC_INFO_FILE_NAME},
 			process::{FileProcessor, FileProcessorOptions, ProcessedFile},
 			utils::{metadata_from_buf, sort_file_names},
 		},
@@ -319,6 +326,96 @@ impl FileProcessor for ZipProcessor {
 	}
 }
 
+fn is_comic_info_entry(name: &str) -> bool {
+	Path::new(name)
+		.file_name()
+		.is_some_and(|file_name| file_name == COMIC_INFO_FILE_NAME)
+}
+
+/// Write a copy of the archive to `destination`, with the contents of the entry `entry_name`
+/// replaced (or added, if it doesn't exist)
+fn replace_archive_entry(
+	archive: &mut zip::ZipArchive<File>,
+	entry_name: &str,
+	contents: &str,
+	destination: &Path,
+) -> Result<(), FileError> {
+	let mut writer = zip::ZipWriter::new(File::create(destination)?);
+
+	for i in 0..archive.len() {
+		let file = archive.by_index_raw(i)?;
+		if file.name() == entry_name {
+			continue;
+		}
+		writer.raw_copy_file(file)?;
+	}
+
+	let options: FileOptions<()> =
+		FileOptions::default().compression_method(CompressionMethod::Deflated);
+	writer.start_file(entry_name, options)?;
+	writer.write_all(contents.as_bytes())?;
+	writer.finish()?.sync_all()?;
+
+	Ok(())
+}
+
+impl ZipProcessor {
+	/// Write the metadata to the ComicInfo.xml entry of the archive, creating the entry if it
+	/// doesn't exist. Elements of an existing ComicInfo.xml which Stump doesn't manage are
+	/// preserved.
+	///
+	/// The archive is rewritten to a temporary file alongside the original, which then
+	/// replaces the original. This ensures the original is left untouched if anything fails.
+	/// Entries other than the ComicInfo.xml are copied without being recompressed.
+	pub fn write_metadata(path: &str, metadata: &MediaMetadata) -> Result<(), FileError> {
+		let zip_file = File::open(path)?;
+		let mut archive = zip::ZipArchive::new(zip_file)?;
+
+		let mut existing_entry = None;
+		for i in 0..archive.len() {
+			let mut file = archive.by_index(i)?;
+			if file.is_file() && is_comic_info_entry(file.name()) {
+				let mut contents = Vec::new();
+				file.read_to_end(&mut contents)?;
+				existing_entry = Some((
+					file.name().to_string(),
+					String::from_utf8_lossy(&contents).to_string(),
+				));
+				break;
+			}
+		}
+
+		let (entry_name, existing_contents) = match existing_entry {
+			Some((name, contents)) => (name, Some(contents)),
+			None => (COMIC_INFO_FILE_NAME.to_string(), None),
+		};
+		let contents = metadata_to_comic_info(metadata, existing_contents.as_deref())?;
+
+		let path_buf = PathBuf::from(path);
+		let FileParts { file_name, .. } = path_buf.as_path().file_parts();
+		// Note: The temporary file is hidden so that it is ignored if a scan happens to run
+		// while it exists
+		let temp_path = path_buf
+			.parent()
+			.unwrap_or_else(|| Path::new("/"))
+			.join(format!(".{file_name}.stump-tmp"));
+
+		let write_result =
+			replace_archive_entry(&mut archive, &entry_name, &contents, &temp_path)
+				.and_then(|()| {
+					std::fs::rename(&temp_path, &path_buf).map_err(FileError::from)
+				});
+
+		if write_result.is_err() && temp_path.exists() {
+			if let Err(error) = std::fs::remove_file(&temp_path) {
+				error!(?error, ?temp_path, "Failed to remove temporary archive");
+			}
+		}
+
+		write_result
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -402,6 +499,35 @@ mod tests {
 		assert_eq!(buf.len(), 96623);
 	}
 
+	#[test]
+	fn test_write_metadata() {
+		let temp_dir = tempfile::tempdir().unwrap();
+		let path = temp_dir.path().join("book.cbz");
+		std::fs::copy(get_test_cbz_path(), &path).unwrap();
+		let path = path.to_str().unwrap();
+
+		let config = StumpConfig::debug();
+		let page_count = ZipProcessor::get_page_count(path, &config).unwrap();
+
+		let metadata = MediaMetadata {
+			title: Some("Written by Stump".to_string()),
+			writers: Some(vec!["Aaron".to_string(), "Oleg".to_string()]),
+			..Default::default()
+		};
+		ZipProcessor::write_metadata(path, &metadata).unwrap();
+
+		let written = ZipProcessor::process_metadata(path).unwrap().unwrap();
+		assert_eq!(written.title, metadata.title);
+		assert_eq!(written.writers, metadata.writers);
+		// The pages should be untouched
+		assert_eq!(
+			ZipProcessor::get_page_count(path, &config).unwrap(),
+			page_count
+		);
+		// And the temporary file should be gone
+		assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+	}
+
 	#[test]
 	fn test_get_page_content_types() {
 		let path = get_test_zip_path();
@@ -0,0 +1,521 @@
+use std::sync::Arc;
+
+use integrations::{
+	ComicVineClient, GoogleBooksClient, MetadataCandidate, MetadataProvider,
+	MetadataProviderKind, MetadataQuery, OpenLibraryClient,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+use crate::{
+	config::StumpConfig,
+	db::{
+		entity::{MediaMetadata, MetadataProviderType, SeriesMetadata},
+		metadata::{
+			apply_media_candidate, apply_series_candidate, replace_candidates,
+			MetadataChangeActor, MetadataTarget,
+		},
+	},
+	error::{CoreError, CoreResult},
+	job::{
+		error::JobError, JobExecuteLog, JobExt, JobOutputExt, JobProgress, JobTaskOutput,
+		WorkerCtx, WorkingState, WrappedJob,
+	},
+	prisma::{media, series},
+};
+
+type MediaID = String;
+type SeriesID = String;
+type LibraryID = String;
+
+/// The number of candidates kept from each provider for a single book or series
+const MAX_CANDIDATES_PER_PROVIDER: usize = 3;
+
+/// Get the providers which can be used with the given configuration. ComicVine is only
+/// available when an API key is configured
+pub fn available_metadata_providers(config: &StumpConfig) -> Vec<MetadataProviderType> {
+	let mut providers = vec![
+		MetadataProviderType::GoogleBooks,
+		MetadataProviderType::OpenLibrary,
+	];
+	if config
+		.comicvine_api_key
+		.as_deref()
+		.is_some_and(|key| !key.trim().is_empty())
+	{
+		providers.push(MetadataProviderType::ComicVine);
+	}
+	providers
+}
+
+/// Create clients for the requested providers, or every available provider if none are
+/// requested
+fn build_providers(
+	requested: &[MetadataProviderType],
+	config: &StumpConfig,
+) -> CoreResult<Vec<Arc<dyn MetadataProvider>>> {
+	let available = available_metadata_providers(config);
+	let requested = if requested.is_empty() {
+		available.clone()
+	} else {
+		requested.to_vec()
+	};
+
+	let mut providers: Vec<Arc<dyn MetadataProvider>> = Vec::new();
+	for provider in requested {
+		if !available.contains(&provider) {
+			return Err(CoreError::BadRequest(format!(
+				"The {provider} metadata provider is not configured"
+			)));
+		}
+
+		let client: Arc<dyn MetadataProvider> = match provider.into() {
+			MetadataProviderKind::GoogleBooks => {
+				Arc::new(GoogleBooksClient::new(config.google_books_api_key.clone()))
+			},
+			MetadataProviderKind::OpenLibrary => Arc::new(OpenLibraryClient::new()),
+			MetadataProviderKind::ComicVine => Arc::new(
+				ComicVineClient::new(
+					config.comicvine_api_key.clone().unwrap_or_default(),
+				)
+				.map_err(|error| CoreError::BadRequest(error.to_string()))?,
+			),
+		};
+		if !providers.iter().any(|p| p.kind() == client.kind()) {
+			providers.push(client);
+		}
+	}
+
+	Ok(providers)
+}
+
+#[derive(Clone)]
+pub enum MetadataFetchJobVariant {
+	/// Fetch metadata for the specified books
+	Media(Vec<MediaID>),
+	/// Fetch metadata for the books in a series
+	Series(SeriesID),
+	/// Fetch metadata for the books in a library
+	Library(LibraryID),
+}
+
+/// Options for the metadata fetch job
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type, ToSchema)]
+pub struct MetadataFetchOptions {
+	/// The providers to search. Every available provider is searched if none are specified
+	#[serde(default)]
+	pub providers: Vec<MetadataProviderType>,
+	/// Whether to also search for the series the books belong to. Only ComicVine has series
+	#[serde(default)]
+	pub include_series: bool,
+	/// If set, the best candidate for a book or series is applied automatically when its
+	/// confidence is at least this value (0-1). Locked fields are never overwritten
+	pub auto_apply_threshold: Option<f64>,
+	/// Whether to lock the fields which are applied automatically
+	#[serde(default)]
+	pub lock_applied: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum MetadataFetchTask {
+	/// Search for candidates for a single series
+	FetchSeries(SeriesID),
+	/// Search for candidates for a single book
+	FetchMedia(MediaID),
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+// Note: This container attribute is used to ensure future additions to the struct do not break deserialization
+#[serde(default)]
+pub struct MetadataFetchOutput {
+	/// The number of books which were searched for
+	media_searched: u64,
+	/// The number of series which were searched for
+	series_searched: u64,
+	/// The number of books and series which had too little information to search for
+	skipped: u64,
+	/// The number of candidates which were stored
+	candidates_found: u64,
+	/// The number of books and series which had a candidate applied automatically
+	metadata_applied: u64,
+}
+
+impl JobOutputExt for MetadataFetchOutput {
+	fn update(&mut self, updated: Self) {
+		self.media_searched += updated.media_searched;
+		self.series_searched += updated.series_searched;
+		self.skipped += updated.skipped;
+		self.candidates_found += updated.candidates_found;
+		self.metadata_applied += updated.metadata_applied;
+	}
+}
+
+/// A job which searches metadata providers for books and series, storing the candidates
+/// found for review. The best candidate is applied automatically if it is confident enough
+/// (see [MetadataFetchOptions::auto_apply_threshold])
+#[derive(Clone)]
+pub struct MetadataFetchJob {
+	pub variant: MetadataFetchJobVariant,
+	pub options: MetadataFetchOptions,
+	providers: Vec<Arc<dyn MetadataProvider>>,
+}
+
+impl MetadataFetchJob {
+	/// Create a new [MetadataFetchJob]. An error is returned if a requested provider is not
+	/// available with the given configuration
+	pub fn new(
+		variant: MetadataFetchJobVariant,
+		options: MetadataFetchOptions,
+		config: &StumpConfig,
+	) -> CoreResult<Box<WrappedJob<MetadataFetchJob>>> {
+		let providers = build_providers(&options.providers, config)?;
+		Ok(WrappedJob::new(Self {
+			variant,
+			options,
+			providers,
+		}))
+	}
+
+	/// Search every provider, keeping the best few candidates of each. The candidates are
+	/// ordered from the most to the least confident
+	async fn search(
+		&self,
+		query: &MetadataQuery,
+		for_series: bool,
+		logs: &mut Vec<JobExecuteLog>,
+	) -> Vec<MetadataCandidate> {
+		let mut candidates = Vec::new();
+		for provider in &self.providers {
+			let result = if for_series {
+				provider.search_series(query).await
+			} else {
+				provider.search_books(query).await
+			};
+			match result {
+				Ok(found) => {
+					candidates.extend(found.into_iter().take(MAX_CANDIDATES_PER_PROVIDER))
+				},
+				Err(error) => logs.push(JobExecuteLog::warn(&format!(
+					"Failed to search {}: {error}",
+					provider.kind()
+				))),
+			}
+		}
+		candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+		candidates
+	}
+
+	/// The candidate to apply automatically, if any is confident enough
+	fn auto_apply_candidate<'a>(
+		&self,
+		candidates: &'a [MetadataCandidate],
+	) -> Option<&'a MetadataCandidate> {
+		let threshold = self.options.auto_apply_threshold?;
+		candidates
+			.first()
+			.filter(|candidate| candidate.confidence >= threshold)
+	}
+
+	async fn fetch_media(
+		&self,
+		ctx: &WorkerCtx,
+		id: MediaID,
+		output: &mut MetadataFetchOutput,
+		logs: &mut Vec<JobExecuteLog>,
+	) -> Result<(), JobError> {
+		let book = ctx
+			.db
+			.media()
+			.find_unique(media::id::equals(id.clone()))
+			.with(media::metadata::fetch())
+			.with(media::series::fetch().with(series::metadata::fetch()))
+			.exec()
+			.await?
+			.ok_or_else(|| JobError::TaskFailed(format!("Media not found: {id}")))?;
+
+		let query = media_query(&book);
+		if !query.is_searchable() {
+			output.skipped += 1;
+			return Ok(());
+		}
+
+		output.media_searched += 1;
+		let candidates = self.search(&query, false, logs).await;
+		let target = MetadataTarget::Media(id.clone());
+		replace_candidates(&ctx.db, &target, &candidates).await?;
+		output.candidates_found += candidates.len() as u64;
+
+		if let Some(candidate) = self.auto_apply_candidate(&candidates) {
+			let applied = apply_media_candidate(
+				&ctx.db,
+				&id,
+				candidate,
+				None,
+				Some(MetadataChangeActor::Job(ctx.job_id.clone())),
+				self.options.lock_applied,
+			)
+			.await?;
+			if !applied.applied.is_empty() {
+				output.metadata_applied += 1;
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn fetch_series(
+		&self,
+		ctx: &WorkerCtx,
+		id: SeriesID,
+		output: &mut MetadataFetchOutput,
+		logs: &mut Vec<JobExecuteLog>,
+	) -> Result<(), JobError> {
+		let series = ctx
+			.db
+			.series()
+			.find_unique(series::id::equals(id.clone()))
+			.with(series::metadata::fetch())
+			.exec()
+			.await?
+			.ok_or_else(|| JobError::TaskFailed(format!("Series not found: {id}")))?;
+
+		let query = series_query(&series);
+		if !query.is_searchable() {
+			output.skipped += 1;
+			return Ok(());
+		}
+
+		output.series_searched += 1;
+		let candidates = self.search(&query, true, logs).await;
+		let target = MetadataTarget::Series(id.clone());
+		replace_candidates(&ctx.db, &target, &candidates).await?;
+		output.candidates_found += candidates.len() as u64;
+
+		if let Some(candidate) = self.auto_apply_candidate(&candidates) {
+			let applied = apply_series_candidate(
+				&ctx.db,
+				&id,
+				candidate,
+				None,
+				Some(MetadataChangeActor::Job(ctx.job_id.clone())),
+				self.options.lock_applied,
+			)
+			.await?;
+			if !applied.applied.is_empty() {
+				output.metadata_applied += 1;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Find the ISBN among the identifiers of a book, which are stored as `scheme:value`
+fn isbn_from_identifiers(identifiers: &[String]) -> Option<String> {
+	identifiers.iter().find_map(|identifier| {
+		identifier
+			.split_once(':')
+			.filter(|(scheme, _)| scheme.trim().eq_ignore_ascii_case("isbn"))
+			.map(|(_, value)| value.trim().to_string())
+	})
+}
+
+/// Build a query from what is already known about a book
+fn media_query(book: &media::Data) -> MetadataQuery {
+	let metadata = book
+		.metadata()
+		.ok()
+		.flatten()
+		.map(|metadata| MediaMetadata::from(metadata.to_owned()))
+		.unwrap_or_default();
+	let series = book.series().ok().flatten();
+	let series_metadata = series
+		.and_then(|series| series.metadata().ok().flatten())
+		.map(|metadata| SeriesMetadata::from(metadata.to_owned()));
+
+	MetadataQuery {
+		isbn: metadata
+			.identifiers
+			.as_deref()
+			.and_then(isbn_from_identifiers),
+		title: metadata.title.or_else(|| Some(book.name.clone())),
+		series: metadata
+			.series
+			.or_else(|| series.map(|series| series.name.clone())),
+		number: metadata.number,
+		year: metadata.year,
+		writers: metadata.writers.unwrap_or_default(),
+		comicvine_id: series_metadata.and_then(|metadata| metadata.comicid),
+	}
+}
+
+/// Build a query from what is already known about a series
+fn series_query(series: &series::Data) -> MetadataQuery {
+	let metadata = series
+		.metadata()
+		.ok()
+		.flatten()
+		.map(|metadata| SeriesMetadata::from(metadata.to_owned()));
+
+	MetadataQuery {
+		title: metadata
+			.as_ref()
+			.and_then(|metadata| metadata.title.clone())
+			.or_else(|| Some(series.name.clone())),
+		comicvine_id: metadata.and_then(|metadata| metadata.comicid),
+		..Default::default()
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for MetadataFetchJob {
+	const NAME: &'static str = "metadata_fetch";
+
+	type Output = MetadataFetchOutput;
+	type Task = MetadataFetchTask;
+
+	fn description(&self) -> Option<String> {
+		match &self.variant {
+			MetadataFetchJobVariant::Media(ids) => match ids.as_slice() {
+				[id] => Some(format!("Fetch metadata for media with id: {id}")),
+				ids => Some(format!("Fetch metadata for {} media", ids.len())),
+			},
+			MetadataFetchJobVariant::Series(id) => {
+				Some(format!("Fetch metadata for series with id: {id}"))
+			},
+			MetadataFetchJobVariant::Library(id) => {
+				Some(format!("Fetch metadata for library with id: {id}"))
+			},
+		}
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let media_filter = match &self.variant {
+			MetadataFetchJobVariant::Media(ids) => media::id::in_vec(ids.clone()),
+			MetadataFetchJobVariant::Series(id) => {
+				media::series_id::equals(Some(id.clone()))
+			},
+			MetadataFetchJobVariant::Library(id) => {
+				media::series::is(vec![series::library_id::equals(Some(id.clone()))])
+			},
+		};
+
+		let books = ctx
+			.db
+			.media()
+			.find_many(vec![media_filter, media::deleted_at::equals(None)])
+			.select(media::select!({ id series_id }))
+			.exec()
+			.await
+			.map_err(|e| JobError::InitFailed(e.to_string()))?;
+
+		// Series are fetched first, so a series matched on ComicVine can be used to find the
+		// exact issues of its books
+		let mut series_ids = match &self.variant {
+			_ if !self.options.include_series => vec![],
+			MetadataFetchJobVariant::Series(id) => vec![id.clone()],
+			_ => books
+				.iter()
+				.filter_map(|book| book.series_id.clone())
+				.collect(),
+		};
+		series_ids.sort();
+		series_ids.dedup();
+
+		let tasks = series_ids
+			.into_iter()
+			.map(MetadataFetchTask::FetchSeries)
+			.chain(
+				books
+					.into_iter()
+					.map(|book| MetadataFetchTask::FetchMedia(book.id)),
+			)
+			.collect::<Vec<_>>();
+
+		Ok(WorkingState {
+			output: Some(Self::Output::default()),
+			tasks: tasks.into(),
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		let (result, context) = match task {
+			MetadataFetchTask::FetchSeries(id) => {
+				ctx.report_progress(JobProgress::msg("Fetching series metadata"));
+				let context = format!("Series ID: {id}");
+				(
+					self.fetch_series(ctx, id, &mut output, &mut logs).await,
+					context,
+				)
+			},
+			MetadataFetchTask::FetchMedia(id) => {
+				ctx.report_progress(JobProgress::msg("Fetching media metadata"));
+				let context = format!("Media ID: {id}");
+				(
+					self.fetch_media(ctx, id, &mut output, &mut logs).await,
+					context,
+				)
+			},
+		};
+
+		if let Err(error) = result {
+			logs.push(JobExecuteLog::error(format!(
+				"Failed to fetch metadata: {error}"
+			)));
+		}
+		let logs = logs
+			.into_iter()
+			.map(|log| log.with_ctx(context.clone()))
+			.collect();
+
+		Ok(JobTaskOutput {
+			output,
+			logs,
+			subtasks: vec![],
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_build_providers() {
+		let mut config = StumpConfig::debug();
+		assert!(build_providers(&[MetadataProviderType::ComicVine], &config).is_err());
+		assert_eq!(build_providers(&[], &config).unwrap().len(), 2);
+
+		config.comicvine_api_key = Some("secret".to_string());
+		let providers =
+			build_providers(&[MetadataProviderType::ComicVine], &config).unwrap();
+		assert_eq!(providers.len(), 1);
+		assert_eq!(providers[0].kind(), MetadataProviderKind::ComicVine);
+	}
+
+	#[test]
+	fn test_isbn_from_identifiers() {
+		let identifiers = vec![
+			"asin:B00B7NPRY8".to_string(),
+			"ISBN: 978-0441172719".to_string(),
+		];
+		assert_eq!(
+			isbn_from_identifiers(&identifiers),
+			Some("978-0441172719".to_string())
+		);
+		assert_eq!(isbn_from_identifiers(&identifiers[..1]), None);
+	}
+}
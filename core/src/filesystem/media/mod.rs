@@ -1,18 +1,26 @@
 pub mod analyze_media_job;
 mod builder;
+mod comic_info;
+pub mod duplicate_analysis_job;
 mod format;
+pub mod metadata_fetch_job;
 mod process;
 mod utils;
+pub mod write_metadata_job;
 
 pub use crate::filesystem::media::epub::EpubProcessor;
 pub(crate) use builder::{MediaBuilder, SeriesBuilder};
+pub use comic_info::metadata_to_comic_info;
 pub use format::*;
 pub use process::*;
 pub use utils::is_accepted_cover_name;
 
 #[cfg(test)]
 pub(crate) mod tests {
-	use std::{fs, path::PathBuf};
+	use std::{
+		fs,
+		path::{Path, PathBuf},
+	};
 
 	pub fn get_test_zip_path() -> String {
 		PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -69,6 +77,27 @@ pub(crate) mod tests {
 			.to_string()
 	}
 
+	/// Create a directory within `dir` containing two pages and a ComicInfo.xml, which can
+	/// be used to build test archives for formats which we can't commit fixtures for
+	pub fn create_test_comic_dir(dir: &Path) -> PathBuf {
+		let data_dir =
+			PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("integration-tests/data");
+		let contents = dir.join("contents");
+		fs::create_dir_all(&contents).expect("Failed to create test comic directory");
+
+		fs::copy(data_dir.join("example.jpeg"), contents.join("page01.jpeg"))
+			.expect("Failed to copy first page");
+		fs::copy(data_dir.join("example.png"), contents.join("page02.png"))
+			.expect("Failed to copy second page");
+		fs::write(
+			contents.join("ComicInfo.xml"),
+			"<ComicInfo><Title>Test Comic</Title></ComicInfo>",
+		)
+		.expect("Failed to write ComicInfo.xml");
+
+		contents
+	}
+
 	// Note: each page should be 96623 bytes. The macOS metadata files should be 220 bytes, but
 	// ignored by the processor. Commenting the sizes for posterity.
 	pub fn get_nested_macos_compressed_cbz_path() -> String {
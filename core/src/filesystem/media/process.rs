@@ -18,13 +18,15 @@ use crate::{
 	},
 };
 
-use super::{rar::RarProcessor, zip::ZipProcessor};
+use super::{
+	rar::RarProcessor, seven_zip::SevenZipProcessor, tar::TarProcessor, zip::ZipProcessor,
+};
 
 /// A struct representing the options for processing a file. This is a subset of [`LibraryConfig`]
 /// and is used to pass options to the [`FileProcessor`] implementations.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct FileProcessorOptions {
-	/// Whether to convert RAR files to ZIP files after processing
+	/// Whether to convert RAR, 7-Zip and TAR files to ZIP files after processing
 	pub convert_rar_to_zip: bool,
 	/// Whether to delete the source file after converting it, if [FileProcessorOptions::convert_rar_to_zip] is true
 	pub delete_conversion_source: bool,
@@ -172,6 +174,12 @@ pub fn process(
 		"application/vnd.rar" | "application/vnd.comicbook-rar" => {
 			RarProcessor::process(path_str, options, config)
 		},
+		"application/x-7z-compressed" | "application/x-cb7" => {
+			SevenZipProcessor::process(path_str, options, config)
+		},
+		"application/x-tar" | "application/x-cbt" => {
+			TarProcessor::process(path_str, options, config)
+		},
 		"application/epub+zip" => EpubProcessor::process(path_str, options, config),
 		"application/pdf" => PdfProcessor::process(path_str, options, config),
 		_ => Err(FileError::UnsupportedFileType(path.display().to_string())),
@@ -230,6 +238,12 @@ pub fn process_metadata(
 		"application/vnd.rar" | "application/vnd.comicbook-rar" => {
 			RarProcessor::process_metadata(path_str)
 		},
+		"application/x-7z-compressed" | "application/x-cb7" => {
+			SevenZipProcessor::process_metadata(path_str)
+		},
+		"application/x-tar" | "application/x-cbt" => {
+			TarProcessor::process_metadata(path_str)
+		},
 		"application/epub+zip" => EpubProcessor::process_metadata(path_str),
 		"application/pdf" => PdfProcessor::process_metadata(path_str),
 		_ => Err(FileError::UnsupportedFileType(path_str.to_string())),
@@ -284,6 +298,12 @@ pub fn generate_hashes(
 		"application/vnd.rar" | "application/vnd.comicbook-rar" => {
 			RarProcessor::generate_hashes(path_str, options)
 		},
+		"application/x-7z-compressed" | "application/x-cb7" => {
+			SevenZipProcessor::generate_hashes(path_str, options)
+		},
+		"application/x-tar" | "application/x-cbt" => {
+			TarProcessor::generate_hashes(path_str, options)
+		},
 		"application/epub+zip" => EpubProcessor::generate_hashes(path_str, options),
 		"application/pdf" => PdfProcessor::generate_hashes(path_str, options),
 		_ => Err(FileError::UnsupportedFileType(path_str.to_string())),
@@ -340,6 +360,12 @@ pub fn get_page(
 		"application/vnd.rar" | "application/vnd.comicbook-rar" => {
 			RarProcessor::get_page(path, page, config)
 		},
+		"application/x-7z-compressed" | "application/x-cb7" => {
+			SevenZipProcessor::get_page(path, page, config)
+		},
+		"application/x-tar" | "application/x-cbt" => {
+			TarProcessor::get_page(path, page, config)
+		},
 		"application/epub+zip" => EpubProcessor::get_page(path, page, config),
 		"application/pdf" => PdfProcessor::get_page(path, page, config),
 		_ => Err(FileError::UnsupportedFileType(path.to_string())),
@@ -396,6 +422,12 @@ pub fn get_page_count(path: &str, config: &StumpConfig) -> Result<i32, FileError
 		"application/vnd.rar" | "application/vnd.comicbook-rar" => {
 			RarProcessor::get_page_count(path, config)
 		},
+		"application/x-7z-compressed" | "application/x-cb7" => {
+			SevenZipProcessor::get_page_count(path, config)
+		},
+		"application/x-tar" | "application/x-cbt" => {
+			TarProcessor::get_page_count(path, config)
+		},
 		"application/epub+zip" => EpubProcessor::get_page_count(path, config),
 		"application/pdf" => PdfProcessor::get_page_count(path, config),
 		_ => Err(FileError::UnsupportedFileType(path.to_string())),
@@ -455,6 +487,12 @@ pub fn get_content_types_for_pages(
 		"application/vnd.rar" | "application/vnd.comicbook-rar" => {
 			RarProcessor::get_page_content_types(path, pages)
 		},
+		"application/x-7z-compressed" | "application/x-cb7" => {
+			SevenZipProcessor::get_page_content_types(path, pages)
+		},
+		"application/x-tar" | "application/x-cbt" => {
+			TarProcessor::get_page_content_types(path, pages)
+		},
 		"application/epub+zip" => EpubProcessor::get_page_content_types(path, pages),
 		"application/pdf" => PdfProcessor::get_page_content_types(path, pages),
 		_ => Err(FileError::UnsupportedFileType(path.to_string())),
@@ -479,6 +517,12 @@ fn get_content_type_for_page_sync(
 		"application/vnd.rar" | "application/vnd.comicbook-rar" => {
 			RarProcessor::get_page_content_types(path, [page].to_vec())
 		},
+		"application/x-7z-compressed" | "application/x-cb7" => {
+			SevenZipProcessor::get_page_content_types(path, [page].to_vec())
+		},
+		"application/x-tar" | "application/x-cbt" => {
+			TarProcessor::get_page_content_types(path, [page].to_vec())
+		},
 		"application/epub+zip" => {
 			EpubProcessor::get_page_content_types(path, [page].to_vec())
 		},
@@ -0,0 +1,311 @@
+use std::path::Path;
+
+use prisma_client_rust::chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::task::spawn_blocking;
+
+use crate::{
+	config::StumpConfig,
+	db::entity::MediaMetadata,
+	filesystem::{content_type::ContentType, error::FileError, FileParts, PathUtils},
+	job::{
+		error::JobError, JobExecuteLog, JobExt, JobOutputExt, JobProgress, JobTaskOutput,
+		WorkerCtx, WorkingState, WrappedJob,
+	},
+	prisma::{library, media, series},
+};
+
+use super::{
+	rar::RarProcessor, seven_zip::SevenZipProcessor, tar::TarProcessor,
+	zip::ZipProcessor, FileConverter, FileProcessor,
+};
+
+type MediaID = String;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum WriteMetadataTask {
+	/// Write the metadata of a single book to its file
+	WriteBook(MediaID),
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, Type)]
+// Note: This container attribute is used to ensure future additions to the struct do not break deserialization
+#[serde(default)]
+pub struct WriteMetadataOutput {
+	/// The number of books which were visited
+	visited_files: u64,
+	/// The number of books which had their metadata written to file
+	written_files: u64,
+	/// The number of RAR archives which were converted to CBZ in order to write metadata
+	converted_files: u64,
+	/// The number of books which were skipped, e.g. because of an unsupported format
+	skipped_files: u64,
+}
+
+impl JobOutputExt for WriteMetadataOutput {
+	fn update(&mut self, updated: Self) {
+		self.visited_files += updated.visited_files;
+		self.written_files += updated.written_files;
+		self.converted_files += updated.converted_files;
+		self.skipped_files += updated.skipped_files;
+	}
+}
+
+/// A job which writes the metadata stored in the database back into the files of books,
+/// as a ComicInfo.xml entry. Only CBZ/ZIP archives are supported. RAR, 7-Zip and TAR
+/// archives are converted to CBZ first if [`WriteMetadataJob::convert_rar_to_zip`] is set,
+/// otherwise they are skipped.
+#[derive(Clone)]
+pub struct WriteMetadataJob {
+	pub media_ids: Vec<MediaID>,
+	/// Whether to convert RAR, 7-Zip and TAR archives to CBZ so their metadata can be written.
+	/// The source is deleted after conversion if the library is configured to hard delete
+	/// conversions
+	pub convert_rar_to_zip: bool,
+}
+
+impl WriteMetadataJob {
+	pub fn new(
+		media_ids: Vec<MediaID>,
+		convert_rar_to_zip: bool,
+	) -> Box<WrappedJob<WriteMetadataJob>> {
+		WrappedJob::new(Self {
+			media_ids,
+			convert_rar_to_zip,
+		})
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for WriteMetadataJob {
+	const NAME: &'static str = "write_metadata";
+
+	type Output = WriteMetadataOutput;
+	type Task = WriteMetadataTask;
+
+	fn description(&self) -> Option<String> {
+		match self.media_ids.as_slice() {
+			[id] => Some(format!("Write metadata to file for media with id: {id}")),
+			ids => Some(format!("Write metadata to file for {} media", ids.len())),
+		}
+	}
+
+	async fn init(
+		&mut self,
+		_: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let tasks = self
+			.media_ids
+			.iter()
+			.map(|id| WriteMetadataTask::WriteBook(id.clone()))
+			.collect::<Vec<_>>();
+
+		Ok(WorkingState {
+			output: Some(Self::Output::default()),
+			tasks: tasks.into(),
+			completed_tasks: 0,
+			logs: vec![],
+		})
+	}
+
+	async fn execute_task(
+		&self,
+		ctx: &WorkerCtx,
+		task: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		match task {
+			WriteMetadataTask::WriteBook(id) => {
+				ctx.report_progress(JobProgress::msg("Writing metadata to file"));
+				output.visited_files += 1;
+
+				match write_book_metadata(&id, self.convert_rar_to_zip, ctx).await {
+					Ok(WriteOutcome::Written { converted }) => {
+						output.written_files += 1;
+						if converted {
+							output.converted_files += 1;
+						}
+					},
+					Ok(WriteOutcome::Skipped(reason)) => {
+						output.skipped_files += 1;
+						logs.push(
+							JobExecuteLog::warn(&reason)
+								.with_ctx(format!("Media ID: {id}")),
+						);
+					},
+					Err(error) => {
+						logs.push(
+							JobExecuteLog::error(format!(
+								"Failed to write metadata to file: {error}"
+							))
+							.with_ctx(format!("Media ID: {id}")),
+						);
+					},
+				}
+			},
+		}
+
+		Ok(JobTaskOutput {
+			output,
+			logs,
+			subtasks: vec![],
+		})
+	}
+}
+
+enum WriteOutcome {
+	Written { converted: bool },
+	Skipped(String),
+}
+
+/// Write the metadata of a single book to its file, converting it to CBZ first if
+/// required (and allowed). The book's path, size and hash are updated to reflect the
+/// rewritten file
+async fn write_book_metadata(
+	id: &str,
+	convert_rar_to_zip: bool,
+	ctx: &WorkerCtx,
+) -> Result<WriteOutcome, JobError> {
+	let book = ctx
+		.db
+		.media()
+		.find_unique(media::id::equals(id.to_string()))
+		.with(media::metadata::fetch())
+		.with(
+			media::series::fetch()
+				.with(series::library::fetch().with(library::config::fetch())),
+		)
+		.exec()
+		.await?
+		.ok_or_else(|| JobError::TaskFailed(format!("Media not found: {id}")))?;
+
+	let Some(metadata) = book
+		.metadata()
+		.ok()
+		.flatten()
+		.map(|metadata| MediaMetadata::from(metadata.to_owned()))
+	else {
+		return Ok(WriteOutcome::Skipped(
+			"Book has no metadata to write".to_string(),
+		));
+	};
+
+	let source_type = ContentType::from_extension(&book.extension);
+	let requires_conversion = is_convertible(source_type);
+	if requires_conversion && !convert_rar_to_zip {
+		return Ok(WriteOutcome::Skipped(format!(
+			"{} archives must be converted to CBZ before metadata can be written",
+			book.extension
+		)));
+	} else if !requires_conversion && !source_type.is_zip() {
+		return Ok(WriteOutcome::Skipped(format!(
+			"Writing metadata is not supported for {} files",
+			book.extension
+		)));
+	}
+	let delete_source = book
+		.series()
+		.ok()
+		.flatten()
+		.and_then(|series| series.library().ok().flatten())
+		.and_then(|library| library.config().ok())
+		.is_some_and(|config| config.hard_delete_conversions);
+
+	let path = book.path.clone();
+	let config = ctx.config.as_ref().clone();
+	let written_path = spawn_blocking(move || {
+		write_to_file(&path, source_type, delete_source, &metadata, &config)
+	})
+	.await
+	.map_err(|e| JobError::TaskFailed(e.to_string()))??;
+
+	let written = Path::new(&written_path);
+	let FileParts { extension, .. } = written.file_parts();
+	let file_metadata = written.metadata().map_err(FileError::from)?;
+	let size = i64::try_from(file_metadata.len()).unwrap_or_else(|_| {
+		tracing::error!(?written_path, "Failed to convert file size to i64");
+		0
+	});
+	let modified_at = file_metadata
+		.modified()
+		.ok()
+		.map(|time| DateTime::<FixedOffset>::from(DateTime::<Utc>::from(time)));
+	// The hash is sampled from the file's bytes, so it changes when the file is rewritten
+	let hash = match book.hash {
+		Some(_) => {
+			let written_path = written_path.clone();
+			spawn_blocking(move || ZipProcessor::generate_stump_hash(&written_path))
+				.await
+				.map_err(|e| JobError::TaskFailed(e.to_string()))?
+		},
+		None => None,
+	};
+
+	ctx.db
+		.media()
+		.update(
+			media::id::equals(id.to_string()),
+			vec![
+				media::path::set(written_path),
+				media::extension::set(extension),
+				media::size::set(size),
+				media::modified_at::set(modified_at),
+				media::hash::set(hash),
+				media::checksum::set(None),
+			],
+		)
+		.exec()
+		.await?;
+
+	Ok(WriteOutcome::Written {
+		converted: requires_conversion,
+	})
+}
+
+/// Whether a file of the given type must be converted to CBZ before metadata can be written
+fn is_convertible(content_type: ContentType) -> bool {
+	content_type.is_rar() || content_type.is_seven_zip() || content_type.is_tar()
+}
+
+/// Write the metadata to the file at `path`, returning the path of the written file. This
+/// will differ from `path` if the file was converted to CBZ
+fn write_to_file(
+	path: &str,
+	source_type: ContentType,
+	delete_source: bool,
+	metadata: &MediaMetadata,
+	config: &StumpConfig,
+) -> Result<String, FileError> {
+	let converted_path = if source_type.is_rar() {
+		Some(RarProcessor::to_zip(path, delete_source, None, config)?)
+	} else if source_type.is_seven_zip() {
+		Some(SevenZipProcessor::to_zip(
+			path,
+			delete_source,
+			None,
+			config,
+		)?)
+	} else if source_type.is_tar() {
+		Some(TarProcessor::to_zip(path, delete_source, None, config)?)
+	} else {
+		None
+	};
+
+	let path = match converted_path {
+		Some(converted_path) => {
+			converted_path.to_str().map(String::from).ok_or_else(|| {
+				FileError::UnknownError(
+					"Converted file failed to be discovered".to_string(),
+				)
+			})?
+		},
+		None => path.to_string(),
+	};
+
+	ZipProcessor::write_metadata(&path, metadata)?;
+
+	Ok(path)
+}
@@ -1,5 +1,6 @@
 use std::{collections::VecDeque, path::PathBuf};
 
+use integrations::NotifierEvent;
 use prisma_client_rust::chrono;
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -12,7 +13,8 @@ use utoipa::ToSchema;
 
 use crate::{
 	db::{
-		entity::{CoreJobOutput, LibraryConfig},
+		entity::{macros::library_name, CoreJobOutput, LibraryConfig},
+		search::remove_stale_documents,
 		FileStatus, SeriesDAO, DAO,
 	},
 	filesystem::image::{ThumbnailGenerationJob, ThumbnailGenerationJobParams},
@@ -83,6 +85,21 @@ impl LibraryScanJob {
 			options: options.unwrap_or_default(),
 		})
 	}
+
+	/// Get the name of the library for use in notifications, falling back to its path if
+	/// the name could not be fetched
+	async fn library_name(&self, client: &PrismaClient) -> String {
+		client
+			.library()
+			.find_unique(library::id::equals(self.id.clone()))
+			.select(library_name::select())
+			.exec()
+			.await
+			.ok()
+			.flatten()
+			.map(|library| library.name)
+			.unwrap_or_else(|| self.path.clone())
+	}
 }
 
 /// The data that is collected and updated during the execution of a library scan job
@@ -195,6 +212,13 @@ impl JobExt for LibraryScanJob {
 				JobProgress::msg("Failed to find library on disk").into_worker_send(),
 				CoreEvent::DiscoveredMissingLibrary(self.id.clone()).into_worker_send(),
 			]);
+			ctx.send_notifier_event(
+				NotifierEvent::LibraryMissing {
+					library_name: self.library_name(&ctx.db).await,
+					path: self.path.clone(),
+				},
+				Some(self.id.clone()),
+			);
 			return Err(JobError::InitFailed(
 				"Library could not be found on disk".to_string(),
 			));
@@ -256,6 +280,32 @@ impl JobExt for LibraryScanJob {
 			tracing::error!(error = ?error, "Failed to handle scan completion");
 		}
 
+		if let Err(error) = remove_stale_documents(&ctx.db).await {
+			tracing::error!(error = ?error, "Failed to remove stale search documents");
+		}
+
+		if did_create {
+			let library_name = self.library_name(&ctx.db).await;
+			if output.created_series > 0 {
+				ctx.send_notifier_event(
+					NotifierEvent::SeriesCreated {
+						series_added: output.created_series,
+						library_name: library_name.clone(),
+					},
+					Some(self.id.clone()),
+				);
+			}
+			if output.created_media > 0 {
+				ctx.send_notifier_event(
+					NotifierEvent::ScanCompleted {
+						books_added: output.created_media,
+						library_name,
+					},
+					Some(self.id.clone()),
+				);
+			}
+		}
+
 		match image_options {
 			Some(options) if did_create | did_update => {
 				tracing::trace!("Thumbnail generation job should be enqueued");
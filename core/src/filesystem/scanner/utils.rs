@@ -23,11 +23,14 @@ use walkdir::DirEntry;
 use crate::{
 	config::StumpConfig,
 	db::{
-		entity::{LibraryConfig, Media, Series},
+		entity::{LibraryConfig, Media, MediaMetadataField, MetadataSource, Series},
+		metadata::{write_media_metadata, MetadataChange, MetadataChangeActor},
+		search::{index_media, index_media_content, MAX_INDEXED_CONTENT_LENGTH},
 		FileStatus,
 	},
 	error::{CoreError, CoreResult},
 	filesystem::{
+		media::EpubProcessor,
 		scanner::options::{BookVisitOperation, CustomVisitResult},
 		MediaBuilder, SeriesBuilder,
 	},
@@ -142,30 +145,37 @@ pub(crate) async fn create_media(
 	Ok(result?)
 }
 
-pub(crate) async fn update_media(db: &PrismaClient, media: Media) -> CoreResult<Media> {
+/// The change recorded for metadata read from a file by the given job
+fn file_metadata_change(job_id: &str) -> MetadataChange {
+	MetadataChange::new(
+		MetadataSource::File,
+		Some(MetadataChangeActor::Job(job_id.to_string())),
+	)
+}
+
+/// Updates a book which was rebuilt from disk. Locked metadata fields are left as they are,
+/// and the fields which changed are recorded as coming from the file
+pub(crate) async fn update_media(
+	db: &PrismaClient,
+	media: Media,
+	job_id: &str,
+) -> CoreResult<Media> {
+	let change = file_metadata_change(job_id);
 	let result: Result<Media, QueryError> = db
 		._transaction()
 		.run(|client| async move {
 			let metadata_id = match media.metadata {
 				Some(metadata) => {
-					let params = metadata
-						.into_prisma()
-						.into_iter()
-						.chain(vec![media_metadata::media_id::set(Some(
-							media.id.clone(),
-						))])
-						.collect::<Vec<_>>();
-					let updated_metadata = client
-						.media_metadata()
-						.upsert(
-							media_metadata::media_id::equals(media.id.clone()),
-							params.clone(),
-							params,
-						)
-						.exec()
-						.await?;
-					tracing::trace!(?updated_metadata, "Metadata upserted");
-					Some(updated_metadata.id)
+					let (written, updated_metadata) = write_media_metadata(
+						&client,
+						&media.id,
+						&metadata,
+						MediaMetadataField::ALL,
+						&change,
+					)
+					.await?;
+					tracing::trace!(?written, ?updated_metadata, "Metadata upserted");
+					updated_metadata.map(|metadata| metadata.id)
 				},
 				_ => None,
 			};
@@ -182,6 +192,9 @@ pub(crate) async fn update_media(db: &PrismaClient, media: Media) -> CoreResult<
 							media::pages::set(media.pages),
 							media::hash::set(media.hash.clone()),
 							media::koreader_hash::set(media.koreader_hash.clone()),
+							// The file changed, so the duplicate analysis hashes are stale
+							media::checksum::set(None),
+							media::cover_hash::set(None),
 							media::path::set(media.path.clone()),
 							media::status::set(media.status.to_string()),
 						],
@@ -205,41 +218,25 @@ pub(crate) async fn update_media(db: &PrismaClient, media: Media) -> CoreResult<
 pub(crate) async fn handle_book_visit_operation(
 	db: &PrismaClient,
 	result: BookVisitResult,
+	job_id: &str,
 ) -> CoreResult<()> {
 	match result {
 		BookVisitResult::Custom(custom) => {
 			if let Some(meta) = custom.meta {
-				let params = meta
-					.into_prisma()
-					.into_iter()
-					.chain(vec![media_metadata::media_id::set(Some(custom.id.clone()))])
-					.collect::<Vec<_>>();
 				let id = custom.id.clone();
+				let change = file_metadata_change(job_id);
 
 				let updated_meta = db
 					._transaction()
 					.run(|client| async move {
-						let meta = client
-							.media_metadata()
-							.upsert(
-								media_metadata::media_id::equals(id.clone()),
-								params.clone(),
-								params,
-							)
-							.exec()
-							.await?;
-						client
-							.media()
-							.update(
-								media::id::equals(id),
-								vec![media::metadata::connect(
-									media_metadata::id::equals(meta.id.clone()),
-								)],
-							)
-							.with(media::metadata::fetch())
-							.exec()
-							.await
-							.map(|_| meta)
+						write_media_metadata(
+							&client,
+							&id,
+							&meta,
+							MediaMetadataField::ALL,
+							&change,
+						)
+						.await
 					})
 					.await;
 				tracing::trace!(?updated_meta, "Metadata upserted");
@@ -261,7 +258,7 @@ pub(crate) async fn handle_book_visit_operation(
 			}
 		},
 		BookVisitResult::Built(book) => {
-			let updated_book = update_media(db, *book).await?;
+			let updated_book = update_media(db, *book, job_id).await?;
 			tracing::trace!(?updated_book, "Book updated");
 		},
 	}
@@ -778,6 +775,7 @@ pub(crate) async fn safely_build_and_insert_media(
 	let start = Instant::now();
 
 	let atomic_cursor = Arc::new(AtomicUsize::new(1));
+	let mut created_books = Vec::with_capacity(books.len());
 
 	// TODO: consider small batches of _batch instead?
 	while let Some(book) = books.pop_front() {
@@ -785,6 +783,7 @@ pub(crate) async fn safely_build_and_insert_media(
 		match create_media(&worker_ctx.db, book).await {
 			Ok(created_media) => {
 				output.created_media += 1;
+				created_books.push(SearchIndexBook::from(&created_media));
 				worker_ctx.send_batch(vec![
 					JobProgress::subtask_position(
 						atomic_cursor.fetch_add(1, Ordering::SeqCst) as i32,
@@ -819,6 +818,9 @@ pub(crate) async fn safely_build_and_insert_media(
 	let error_count = output.logs.len() - error_count; // Subtract the errors from the previous step
 	tracing::debug!(success_count, error_count, elapsed = ?start.elapsed(), "Inserted books into database");
 
+	let index_logs = update_search_index(worker_ctx, created_books).await;
+	output.logs.extend(index_logs);
+
 	Ok(output)
 }
 
@@ -951,12 +953,17 @@ pub(crate) async fn visit_and_update_media(
 	let start = Instant::now();
 
 	let atomic_cursor = Arc::new(AtomicUsize::new(1));
+	let mut updated_books = Vec::with_capacity(build_results.len());
 
 	while let Some(result) = build_results.pop_front() {
 		let error_ctx = result.error_ctx();
-		match handle_book_visit_operation(&worker_ctx.db, result).await {
+		let search_index_book = SearchIndexBook::from(&result);
+		match handle_book_visit_operation(&worker_ctx.db, result, &worker_ctx.job_id)
+			.await
+		{
 			Ok(_) => {
 				output.updated_media += 1;
+				updated_books.push(search_index_book);
 			},
 			Err(e) => {
 				tracing::error!(error = ?e, ?error_ctx, "Failed to update media");
@@ -980,5 +987,96 @@ pub(crate) async fn visit_and_update_media(
 	let error_count = output.logs.len() - error_count; // Subtract the errors from the previous step
 	tracing::debug!(elapsed = ?start.elapsed(), success_count, error_count, "Updated books in database");
 
+	let index_logs = update_search_index(worker_ctx, updated_books).await;
+	output.logs.extend(index_logs);
+
 	Ok(output)
 }
+
+/// A book which should be (re)indexed for search after a scan
+struct SearchIndexBook {
+	id: String,
+	/// The path of the book, if it was (re)built from disk and is an EPUB. The content of the
+	/// book is only extracted in this case
+	epub_path: Option<String>,
+}
+
+impl From<&Media> for SearchIndexBook {
+	fn from(book: &Media) -> Self {
+		Self {
+			id: book.id.clone(),
+			epub_path: book
+				.extension
+				.eq_ignore_ascii_case("epub")
+				.then(|| book.path.clone()),
+		}
+	}
+}
+
+impl From<&BookVisitResult> for SearchIndexBook {
+	fn from(result: &BookVisitResult) -> Self {
+		match result {
+			BookVisitResult::Built(book) => Self::from(book.as_ref()),
+			BookVisitResult::Custom(custom) => Self {
+				id: custom.id.clone(),
+				epub_path: None,
+			},
+		}
+	}
+}
+
+/// Updates the search index for the given books, extracting the text of EPUBs if enabled by the
+/// core configuration. Failures are only logged, since they shouldn't fail the scan and the
+/// books will be indexed again the next time they are visited
+async fn update_search_index(
+	worker_ctx: &WorkerCtx,
+	books: Vec<SearchIndexBook>,
+) -> Vec<JobExecuteLog> {
+	let mut logs = vec![];
+	if books.is_empty() {
+		return logs;
+	}
+
+	worker_ctx.report_progress(JobProgress::msg("Updating search index"));
+	let start = Instant::now();
+
+	let ids = books.iter().map(|book| book.id.clone()).collect::<Vec<_>>();
+	if let Err(error) = index_media(&worker_ctx.db, &ids).await {
+		tracing::error!(?error, "Failed to update search index");
+		logs.push(JobExecuteLog::error(format!(
+			"Failed to update search index: {error}"
+		)));
+		return logs;
+	}
+
+	if worker_ctx.config.index_epub_content {
+		let epubs = books
+			.into_iter()
+			.filter_map(|book| book.epub_path.map(|path| (book.id, path)));
+		for (id, path) in epubs {
+			let extracted = spawn_blocking({
+				let path = path.clone();
+				move || EpubProcessor::get_text_content(&path, MAX_INDEXED_CONTENT_LENGTH)
+			})
+			.await
+			.map_err(|e| CoreError::Unknown(e.to_string()))
+			.and_then(|result| result.map_err(CoreError::from));
+
+			let result = match extracted {
+				Ok(content) => index_media_content(&worker_ctx.db, id, content).await,
+				Err(error) => Err(error),
+			};
+			if let Err(error) = result {
+				tracing::warn!(?error, ?path, "Failed to index book content");
+				logs.push(
+					JobExecuteLog::warn("Failed to index book content")
+						.with_ctx(format!("Path: {path:?}")),
+				);
+			}
+		}
+	}
+
+	tracing::debug!(elapsed = ?start.elapsed(), "Updated search index");
+
+	logs
+}
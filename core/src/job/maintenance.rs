@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+
+use prisma_client_rust::chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	job::{
+		error::JobError, JobExecuteLog, JobExt, JobOutputExt, JobTaskOutput, WorkerCtx,
+		WorkingState, WrappedJob,
+	},
+	prisma::{audit_log, log, session},
+};
+
+pub const SESSION_CLEANUP_JOB_NAME: &str = "session_cleanup";
+pub const LOG_PRUNING_JOB_NAME: &str = "log_pruning";
+pub const AUDIT_LOG_PRUNING_JOB_NAME: &str = "audit_log_pruning";
+
+/// The default number of days to retain persisted job logs for when pruning
+pub const DEFAULT_LOG_RETENTION_DAYS: u32 = 30;
+
+/// The data that is collected and updated during the execution of a session cleanup job
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SessionCleanupJobOutput {
+	/// The number of removed sessions
+	removed_sessions: u64,
+}
+
+impl JobOutputExt for SessionCleanupJobOutput {}
+
+#[derive(Clone)]
+pub struct SessionCleanupJob;
+
+impl SessionCleanupJob {
+	pub fn new() -> Box<WrappedJob<SessionCleanupJob>> {
+		WrappedJob::new(Self)
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for SessionCleanupJob {
+	const NAME: &'static str = SESSION_CLEANUP_JOB_NAME;
+
+	type Output = SessionCleanupJobOutput;
+	type Task = ();
+
+	fn description(&self) -> Option<String> {
+		None
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		let affected_rows = ctx
+			.db
+			.session()
+			.delete_many(vec![session::expiry_time::lt(Utc::now().into())])
+			.exec()
+			.await
+			.map_or_else(
+				|e| {
+					logs.push(JobExecuteLog::error(format!(
+						"Failed to delete expired sessions: {:?}",
+						e.to_string()
+					)));
+					0
+				},
+				|count| count as u64,
+			);
+		output.removed_sessions = affected_rows;
+		tracing::debug!(affected_rows = ?affected_rows, "Deleted expired sessions");
+
+		Ok(WorkingState {
+			output: Some(output),
+			tasks: VecDeque::default(),
+			completed_tasks: 0,
+			logs,
+		})
+	}
+
+	async fn execute_task(
+		&self,
+		_: &WorkerCtx,
+		_: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		unreachable!("SessionCleanupJob does not have any tasks! It should not be executed with any tasks!")
+	}
+}
+
+/// The data that is collected and updated during the execution of a log pruning job
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct LogPruningJobOutput {
+	/// The number of removed job logs
+	removed_logs: u64,
+}
+
+impl JobOutputExt for LogPruningJobOutput {}
+
+/// A job that deletes persisted job logs older than the configured retention period
+#[derive(Clone)]
+pub struct LogPruningJob {
+	/// The number of days of logs to keep
+	pub retention_days: u32,
+}
+
+impl LogPruningJob {
+	pub fn new(retention_days: Option<u32>) -> Box<WrappedJob<LogPruningJob>> {
+		WrappedJob::new(Self {
+			retention_days: retention_days.unwrap_or(DEFAULT_LOG_RETENTION_DAYS),
+		})
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for LogPruningJob {
+	const NAME: &'static str = LOG_PRUNING_JOB_NAME;
+
+	type Output = LogPruningJobOutput;
+	type Task = ();
+
+	fn description(&self) -> Option<String> {
+		Some(format!(
+			"Prune job logs older than {} days",
+			self.retention_days
+		))
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		let cutoff = Utc::now() - Duration::days(i64::from(self.retention_days));
+		let affected_rows = ctx
+			.db
+			.log()
+			.delete_many(vec![log::timestamp::lt(cutoff.into())])
+			.exec()
+			.await
+			.map_or_else(
+				|e| {
+					logs.push(JobExecuteLog::error(format!(
+						"Failed to prune job logs: {:?}",
+						e.to_string()
+					)));
+					0
+				},
+				|count| count as u64,
+			);
+		output.removed_logs = affected_rows;
+		tracing::debug!(affected_rows = ?affected_rows, "Pruned job logs");
+
+		Ok(WorkingState {
+			output: Some(output),
+			tasks: VecDeque::default(),
+			completed_tasks: 0,
+			logs,
+		})
+	}
+
+	async fn execute_task(
+		&self,
+		_: &WorkerCtx,
+		_: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		unreachable!("LogPruningJob does not have any tasks! It should not be executed with any tasks!")
+	}
+}
+
+/// The data that is collected and updated during the execution of an audit log pruning job
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct AuditLogPruningJobOutput {
+	/// The number of removed audit log entries
+	removed_entries: u64,
+}
+
+impl JobOutputExt for AuditLogPruningJobOutput {}
+
+/// A job that deletes audit log entries older than the configured retention period
+#[derive(Clone)]
+pub struct AuditLogPruningJob {
+	/// The number of days of audit log entries to keep
+	pub retention_days: u32,
+}
+
+impl AuditLogPruningJob {
+	pub fn new(retention_days: u32) -> Box<WrappedJob<AuditLogPruningJob>> {
+		WrappedJob::new(Self { retention_days })
+	}
+}
+
+#[async_trait::async_trait]
+impl JobExt for AuditLogPruningJob {
+	const NAME: &'static str = AUDIT_LOG_PRUNING_JOB_NAME;
+
+	type Output = AuditLogPruningJobOutput;
+	type Task = ();
+
+	fn description(&self) -> Option<String> {
+		Some(format!(
+			"Prune audit log entries older than {} days",
+			self.retention_days
+		))
+	}
+
+	async fn init(
+		&mut self,
+		ctx: &WorkerCtx,
+	) -> Result<WorkingState<Self::Output, Self::Task>, JobError> {
+		let mut output = Self::Output::default();
+		let mut logs = vec![];
+
+		let cutoff = Utc::now() - Duration::days(i64::from(self.retention_days));
+		let affected_rows = ctx
+			.db
+			.audit_log()
+			.delete_many(vec![audit_log::timestamp::lt(cutoff.into())])
+			.exec()
+			.await
+			.map_or_else(
+				|e| {
+					logs.push(JobExecuteLog::error(format!(
+						"Failed to prune audit log entries: {:?}",
+						e.to_string()
+					)));
+					0
+				},
+				|count| count as u64,
+			);
+		output.removed_entries = affected_rows;
+		tracing::debug!(affected_rows = ?affected_rows, "Pruned audit log entries");
+
+		Ok(WorkingState {
+			output: Some(output),
+			tasks: VecDeque::default(),
+			completed_tasks: 0,
+			logs,
+		})
+	}
+
+	async fn execute_task(
+		&self,
+		_: &WorkerCtx,
+		_: Self::Task,
+	) -> Result<JobTaskOutput<Self>, JobError> {
+		unreachable!("AuditLogPruningJob does not have any tasks! It should not be executed with any tasks!")
+	}
+}
@@ -20,6 +20,7 @@ use serde::{de, Deserialize, Serialize};
 
 mod controller;
 pub mod error;
+mod maintenance;
 mod manager;
 mod progress;
 mod scheduler;
@@ -27,8 +28,9 @@ mod task;
 mod worker;
 
 use error::JobError;
+pub use maintenance::*;
 pub use progress::*;
-pub use scheduler::JobScheduler;
+pub use scheduler::{JobScheduler, ScheduleTrigger, MIN_SCHEDULE_INTERVAL_SECS};
 use specta::Type;
 pub use task::JobTaskOutput;
 use task::{job_task_handler, JobTaskHandlerOutput};
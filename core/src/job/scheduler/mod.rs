@@ -0,0 +1,342 @@
+use std::{
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use prisma_client_rust::chrono::Utc;
+use tokio::time::MissedTickBehavior;
+
+mod trigger;
+
+pub use trigger::{ScheduleTrigger, MIN_SCHEDULE_INTERVAL_SECS};
+
+use crate::{
+	db::entity::{JobSchedule, LibraryConfig, ScheduledJobKind},
+	filesystem::{
+		image::{
+			ImageProcessorOptions, ThumbnailGenerationJob, ThumbnailGenerationJobParams,
+		},
+		media::analyze_media_job::AnalyzeMediaJob,
+		scanner::LibraryScanJob,
+	},
+	job::{AuditLogPruningJob, Executor, LogPruningJob, SessionCleanupJob, WrappedJob},
+	prisma::{job_schedule, library},
+	CoreError, CoreResult, Ctx,
+};
+
+/// The interval (in seconds) in which the scheduler checks for due schedules
+const SCHEDULER_POLL_INTERVAL_SECS: u64 = 30;
+
+/// The interval (in seconds) in which expired audit log entries are pruned
+const AUDIT_LOG_PRUNING_INTERVAL_SECS: u64 = 60 * 60 * 24;
+
+/// The scheduler is responsible for periodically enqueuing jobs per the persisted
+/// [`JobSchedule`]s. Schedules are read from the DB on every poll, so changes made through
+/// the API take effect without needing to restart the scheduler.
+///
+/// Each schedule persists its `last_run_at` and `next_run_at`, so restarting the server does
+/// not trigger every schedule on boot. Only schedules which became due while the server was
+/// offline will run (once) after startup.
+pub struct JobScheduler {
+	pub scheduler_handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl JobScheduler {
+	pub async fn init(core_ctx: Arc<Ctx>) -> CoreResult<Arc<Self>> {
+		let initialized = Self::initialize_next_runs(&core_ctx).await?;
+		tracing::info!(initialized, "Initializing job scheduler");
+
+		let handle = tokio::spawn(async move {
+			let mut interval =
+				tokio::time::interval(Duration::from_secs(SCHEDULER_POLL_INTERVAL_SECS));
+			interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+			let mut last_audit_log_pruning: Option<Instant> = None;
+
+			loop {
+				interval.tick().await;
+
+				if let Err(error) = Self::run_due_schedules(&core_ctx).await {
+					tracing::error!(?error, "Failed to run due schedules");
+				}
+
+				let pruning_due = last_audit_log_pruning.is_none_or(|last| {
+					last.elapsed() >= Duration::from_secs(AUDIT_LOG_PRUNING_INTERVAL_SECS)
+				});
+				if pruning_due {
+					Self::prune_audit_log(&core_ctx);
+					last_audit_log_pruning = Some(Instant::now());
+				}
+			}
+		});
+
+		Ok(Arc::new(Self {
+			scheduler_handle: Some(handle),
+		}))
+	}
+
+	/// Enqueue a job to remove audit log entries older than the configured retention period.
+	/// This runs on startup and then daily, rather than whenever an event is recorded
+	fn prune_audit_log(ctx: &Ctx) {
+		let retention_days = ctx.config.audit_log_retention_days;
+		if retention_days == 0 {
+			return;
+		}
+
+		if let Err(error) = ctx.enqueue_job(AuditLogPruningJob::new(retention_days)) {
+			tracing::error!(?error, "Failed to enqueue audit log pruning job");
+		}
+	}
+
+	/// Compute the `next_run_at` for any schedules which do not yet have one, relative to their
+	/// last run (if any). Returns the number of schedules which were initialized.
+	async fn initialize_next_runs(ctx: &Ctx) -> CoreResult<usize> {
+		let client = &ctx.db;
+		let now = Utc::now();
+
+		let schedules = client
+			.job_schedule()
+			.find_many(vec![job_schedule::next_run_at::equals(None)])
+			.exec()
+			.await?;
+
+		let mut initialized = 0;
+		for data in schedules {
+			let schedule = match JobSchedule::try_from(data) {
+				Ok(schedule) => schedule,
+				Err(error) => {
+					tracing::error!(?error, "Failed to parse job schedule");
+					continue;
+				},
+			};
+
+			// A single invalid schedule should not prevent the others from being initialized
+			let trigger = match schedule.trigger() {
+				Ok(trigger) => trigger,
+				Err(error) => {
+					tracing::error!(
+						?error,
+						schedule_id = %schedule.id,
+						"Skipping job schedule with an invalid trigger"
+					);
+					continue;
+				},
+			};
+
+			let anchor = schedule.last_run_at.map(|ts| ts.to_utc()).unwrap_or(now);
+			let next_run_at = trigger.next_after(anchor);
+			client
+				.job_schedule()
+				.update(
+					job_schedule::id::equals(schedule.id),
+					vec![job_schedule::next_run_at::set(next_run_at.map(Into::into))],
+				)
+				.exec()
+				.await?;
+			initialized += 1;
+		}
+
+		Ok(initialized)
+	}
+
+	/// Run all schedules which are not paused and whose `next_run_at` has passed
+	async fn run_due_schedules(ctx: &Ctx) -> CoreResult<()> {
+		let due_schedules = ctx
+			.db
+			.job_schedule()
+			.find_many(vec![
+				job_schedule::is_paused::equals(false),
+				job_schedule::next_run_at::lte(Utc::now().into()),
+			])
+			.with(job_schedule::included_libraries::fetch(vec![]))
+			.with(job_schedule::excluded_libraries::fetch(vec![]))
+			.exec()
+			.await?;
+
+		for data in due_schedules {
+			let id = data.id.clone();
+
+			// A schedule which cannot be parsed would otherwise fail on every poll, so it is
+			// paused until it is fixed (or resumed) through the API
+			let result = JobSchedule::try_from(data)
+				.and_then(|schedule| schedule.trigger().map(|_| schedule));
+			let schedule = match result {
+				Ok(schedule) => schedule,
+				Err(error) => {
+					tracing::error!(?error, schedule_id = %id, "Pausing invalid job schedule");
+					if let Err(error) = Self::pause(ctx, id).await {
+						tracing::error!(?error, "Failed to pause invalid job schedule");
+					}
+					continue;
+				},
+			};
+
+			if let Err(error) = Self::dispatch(ctx, schedule).await {
+				tracing::error!(?error, schedule_id = %id, "Failed to run job schedule");
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Pause the schedule with the given ID and clear its next run
+	async fn pause(ctx: &Ctx, id: String) -> CoreResult<()> {
+		ctx.db
+			.job_schedule()
+			.update(
+				job_schedule::id::equals(id),
+				vec![
+					job_schedule::is_paused::set(true),
+					job_schedule::next_run_at::set(None),
+				],
+			)
+			.exec()
+			.await?;
+		Ok(())
+	}
+
+	/// Immediately run the schedule with the given ID, regardless of whether it is paused
+	/// or due. The `next_run_at` of the schedule is recomputed relative to now.
+	pub async fn run_now(ctx: &Ctx, id: String) -> CoreResult<JobSchedule> {
+		let schedule = ctx
+			.db
+			.job_schedule()
+			.find_unique(job_schedule::id::equals(id))
+			.with(job_schedule::included_libraries::fetch(vec![]))
+			.with(job_schedule::excluded_libraries::fetch(vec![]))
+			.exec()
+			.await?
+			.ok_or(CoreError::NotFound("Job schedule not found".to_string()))?;
+
+		Self::dispatch(ctx, JobSchedule::try_from(schedule)?).await
+	}
+
+	/// Persist the run timestamps for a schedule and enqueue its job(s). The timestamps are
+	/// persisted first so that a failure to record the run can never cause the same jobs to
+	/// be enqueued again on the next tick.
+	async fn dispatch(ctx: &Ctx, schedule: JobSchedule) -> CoreResult<JobSchedule> {
+		let now = Utc::now();
+		let next_run_at = schedule.trigger()?.next_after(now);
+
+		let jobs = Self::build_jobs(ctx, &schedule).await?;
+
+		let updated_schedule = ctx
+			.db
+			.job_schedule()
+			.update(
+				job_schedule::id::equals(schedule.id),
+				vec![
+					job_schedule::last_run_at::set(Some(now.into())),
+					job_schedule::next_run_at::set(next_run_at.map(Into::into)),
+				],
+			)
+			.with(job_schedule::included_libraries::fetch(vec![]))
+			.with(job_schedule::excluded_libraries::fetch(vec![]))
+			.exec()
+			.await?;
+
+		tracing::debug!(
+			schedule = %updated_schedule.name,
+			job_count = jobs.len(),
+			"Dispatching scheduled jobs"
+		);
+
+		for job in jobs {
+			ctx.enqueue_job(job).map_err(|e| {
+				CoreError::InternalError(format!("Failed to enqueue scheduled job: {e}"))
+			})?;
+		}
+
+		JobSchedule::try_from(updated_schedule)
+	}
+
+	/// Build the jobs which should be enqueued for a schedule. Library-based job kinds
+	/// produce one job per targeted library.
+	async fn build_jobs(
+		ctx: &Ctx,
+		schedule: &JobSchedule,
+	) -> CoreResult<Vec<Box<dyn Executor>>> {
+		let config = &schedule.config;
+
+		let jobs: Vec<Box<dyn Executor>> = match schedule.job_kind {
+			ScheduledJobKind::LogPruning => {
+				vec![LogPruningJob::new(config.retention_days)]
+			},
+			ScheduledJobKind::SessionCleanup => vec![SessionCleanupJob::new()],
+			ScheduledJobKind::LibraryScan => Self::target_libraries(ctx, schedule)
+				.await?
+				.into_iter()
+				.map(|library| -> Box<dyn Executor> {
+					let library_config = library.config().ok().cloned();
+					WrappedJob::new(LibraryScanJob {
+						id: library.id,
+						path: library.path,
+						config: library_config.map(LibraryConfig::from),
+						options: Default::default(),
+					})
+				})
+				.collect(),
+			ScheduledJobKind::ThumbnailGeneration => {
+				Self::target_libraries(ctx, schedule)
+					.await?
+					.into_iter()
+					.map(|library| -> Box<dyn Executor> {
+						let options = library
+							.config()
+							.ok()
+							.cloned()
+							.and_then(|config| config.thumbnail_config)
+							.map(ImageProcessorOptions::try_from)
+							.transpose()
+							.unwrap_or_else(|error| {
+								tracing::error!(
+									?error,
+									library_id = %library.id,
+									"Failed to parse thumbnail config, using defaults"
+								);
+								None
+							})
+							.unwrap_or_default();
+						ThumbnailGenerationJob::new(
+							options,
+							ThumbnailGenerationJobParams::single_library(
+								library.id,
+								config.force_regenerate,
+							),
+						)
+					})
+					.collect()
+			},
+			ScheduledJobKind::MediaAnalysis => Self::target_libraries(ctx, schedule)
+				.await?
+				.into_iter()
+				.map(|library| -> Box<dyn Executor> {
+					AnalyzeMediaJob::analyze_library(library.id)
+				})
+				.collect(),
+		};
+
+		Ok(jobs)
+	}
+
+	/// Fetch the libraries targeted by a schedule, with their configs loaded
+	async fn target_libraries(
+		ctx: &Ctx,
+		schedule: &JobSchedule,
+	) -> CoreResult<Vec<library::Data>> {
+		let included_library_ids = schedule.included_library_ids();
+		let mut where_params =
+			vec![library::id::not_in_vec(schedule.excluded_library_ids())];
+		if !included_library_ids.is_empty() {
+			where_params.push(library::id::in_vec(included_library_ids));
+		}
+
+		Ok(ctx
+			.db
+			.library()
+			.find_many(where_params)
+			.with(library::config::fetch())
+			.exec()
+			.await?)
+	}
+}
@@ -0,0 +1,240 @@
+use std::str::FromStr;
+
+use cron::Schedule;
+use prisma_client_rust::chrono::{DateTime, Duration, Utc};
+
+use crate::{CoreError, CoreResult};
+
+/// The smallest interval a schedule may be configured with. The scheduler only checks for due
+/// schedules periodically, so anything lower would not be honored anyways.
+pub const MIN_SCHEDULE_INTERVAL_SECS: i32 = 60;
+
+/// Describes _when_ a scheduled job should run, either as a cron expression or a fixed
+/// interval (in seconds) since the last run.
+#[derive(Debug, Clone)]
+pub enum ScheduleTrigger {
+	Cron(Box<Schedule>),
+	Interval(Duration),
+}
+
+impl ScheduleTrigger {
+	/// Create a [`ScheduleTrigger`] from the persisted schedule fields. A cron expression takes
+	/// precedence over an interval when both are present.
+	///
+	/// Cron expressions may either be in the standard 5-field format (minute, hour, day of month,
+	/// month, day of week) or the extended 6/7-field format which includes seconds (and years).
+	pub fn new(
+		cron_expression: Option<&str>,
+		interval_secs: Option<i32>,
+	) -> CoreResult<Self> {
+		match (cron_expression, interval_secs) {
+			(Some(expression), _) => Self::cron(expression),
+			(None, Some(secs)) => Self::interval(secs),
+			(None, None) => Err(CoreError::BadRequest(
+				"A schedule requires either a cron expression or an interval".to_string(),
+			)),
+		}
+	}
+
+	fn cron(expression: &str) -> CoreResult<Self> {
+		let expression = expression.trim();
+		let fields = expression.split_whitespace().collect::<Vec<_>>();
+		let normalized =
+			if let [minute, hour, day_of_month, month, day_of_week] = fields.as_slice() {
+				// The cron crate expects a leading seconds field and numbers the days of the
+				// week 1-7 starting on Sunday, rather than the standard 0-7 (with both 0 and
+				// 7 being Sunday)
+				format!(
+					"0 {minute} {hour} {day_of_month} {month} {}",
+					translate_day_of_week(day_of_week)?
+				)
+			} else {
+				expression.to_string()
+			};
+
+		Schedule::from_str(&normalized)
+			.map(|schedule| Self::Cron(Box::new(schedule)))
+			.map_err(|e| {
+				CoreError::BadRequest(format!(
+					"Invalid cron expression '{expression}': {e}"
+				))
+			})
+	}
+
+	fn interval(secs: i32) -> CoreResult<Self> {
+		if secs < MIN_SCHEDULE_INTERVAL_SECS {
+			return Err(CoreError::BadRequest(format!(
+				"The schedule interval must be at least {MIN_SCHEDULE_INTERVAL_SECS} seconds"
+			)));
+		}
+
+		Ok(Self::Interval(Duration::seconds(i64::from(secs))))
+	}
+
+	/// Get the next time the schedule should run strictly after the given time, if any
+	pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+		match self {
+			Self::Cron(schedule) => schedule.after(&after).next(),
+			Self::Interval(interval) => after.checked_add_signed(*interval),
+		}
+	}
+}
+
+/// Translate a standard crontab day of week field (0-7, where both 0 and 7 are Sunday) into
+/// the numbering the cron crate expects (1-7, where 1 is Sunday). Numeric items are expanded
+/// into the explicit days they cover, while names (e.g. `MON-FRI`) and wildcards are passed
+/// through as-is since they mean the same thing in both formats.
+fn translate_day_of_week(field: &str) -> CoreResult<String> {
+	let invalid = || {
+		CoreError::BadRequest(format!(
+			"Invalid day of week in cron expression: '{field}'"
+		))
+	};
+
+	let mut items = Vec::new();
+	let mut days = Vec::new();
+	for item in field.split(',') {
+		let (range, step) = match item.split_once('/') {
+			Some((range, step)) => (
+				range,
+				step.parse::<u32>()
+					.ok()
+					.filter(|s| *s > 0)
+					.ok_or_else(invalid)?,
+			),
+			None => (item, 1),
+		};
+
+		let bounds = match range {
+			"*" | "?" if step == 1 => {
+				items.push(item.to_string());
+				continue;
+			},
+			"*" | "?" => Some((0, 6)),
+			_ => match range.split_once('-') {
+				Some((start, end)) => {
+					start.parse::<u32>().ok().zip(end.parse::<u32>().ok())
+				},
+				None => range
+					.parse::<u32>()
+					.ok()
+					.map(|start| (start, if step > 1 { 7 } else { start })),
+			},
+		};
+
+		let Some((start, end)) = bounds else {
+			// Named days are identical between the two formats
+			items.push(item.to_string());
+			continue;
+		};
+
+		if start > end || end > 7 {
+			return Err(invalid());
+		}
+
+		days.extend((start..=end).step_by(step as usize).map(|day| day % 7 + 1));
+	}
+
+	days.sort_unstable();
+	days.dedup();
+	items.extend(days.into_iter().map(|day| day.to_string()));
+
+	Ok(items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+	use prisma_client_rust::chrono::{Datelike, TimeZone, Weekday};
+
+	use super::*;
+
+	fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+		Utc.with_ymd_and_hms(2025, 3, 1, hour, minute, 0).unwrap()
+	}
+
+	#[test]
+	fn test_requires_cron_or_interval() {
+		assert!(ScheduleTrigger::new(None, None).is_err());
+	}
+
+	#[test]
+	fn test_interval_next_after() {
+		let trigger = ScheduleTrigger::new(None, Some(3600)).unwrap();
+		assert_eq!(trigger.next_after(at(10, 0)), Some(at(11, 0)));
+	}
+
+	#[test]
+	fn test_interval_too_small() {
+		assert!(ScheduleTrigger::new(None, Some(5)).is_err());
+	}
+
+	#[test]
+	fn test_five_field_cron_next_after() {
+		let trigger = ScheduleTrigger::new(Some("30 3 * * *"), None).unwrap();
+		assert_eq!(trigger.next_after(at(1, 0)), Some(at(3, 30)));
+		assert_eq!(
+			trigger.next_after(at(4, 0)),
+			Some(Utc.with_ymd_and_hms(2025, 3, 2, 3, 30, 0).unwrap())
+		);
+	}
+
+	#[test]
+	fn test_five_field_cron_day_of_week() {
+		// 2025-03-01 is a Saturday, so weekdays should skip ahead to Monday
+		let trigger = ScheduleTrigger::new(Some("0 9 * * 1-5"), None).unwrap();
+		let next = trigger.next_after(at(10, 0)).unwrap();
+		assert_eq!(next.weekday(), Weekday::Mon);
+		assert_eq!(next, Utc.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap());
+
+		let upcoming =
+			std::iter::successors(Some(next), |last| trigger.next_after(*last))
+				.take(10)
+				.map(|date| date.weekday())
+				.collect::<Vec<_>>();
+		assert!(upcoming
+			.iter()
+			.all(|day| !matches!(day, Weekday::Sat | Weekday::Sun)));
+
+		for expression in ["0 9 * * 0", "0 9 * * 7", "0 9 * * SUN"] {
+			let trigger = ScheduleTrigger::new(Some(expression), None).unwrap();
+			assert_eq!(
+				trigger.next_after(at(10, 0)).map(|date| date.weekday()),
+				Some(Weekday::Sun)
+			);
+		}
+
+		let trigger = ScheduleTrigger::new(Some("0 9 * * 5-7"), None).unwrap();
+		assert_eq!(
+			trigger.next_after(at(10, 0)).map(|date| date.weekday()),
+			Some(Weekday::Sun)
+		);
+	}
+
+	#[test]
+	fn test_translate_day_of_week() {
+		assert_eq!(translate_day_of_week("*").unwrap(), "*");
+		assert_eq!(translate_day_of_week("1-5").unwrap(), "2,3,4,5,6");
+		assert_eq!(translate_day_of_week("0,7").unwrap(), "1");
+		assert_eq!(translate_day_of_week("*/2").unwrap(), "1,3,5,7");
+		assert_eq!(translate_day_of_week("MON-FRI").unwrap(), "MON-FRI");
+		assert!(translate_day_of_week("5-8").is_err());
+		assert!(translate_day_of_week("5-1").is_err());
+	}
+
+	#[test]
+	fn test_six_field_cron_next_after() {
+		let trigger = ScheduleTrigger::new(Some("0 0 */6 * * *"), None).unwrap();
+		assert_eq!(trigger.next_after(at(1, 0)), Some(at(6, 0)));
+	}
+
+	#[test]
+	fn test_cron_takes_precedence() {
+		let trigger = ScheduleTrigger::new(Some("0 12 * * *"), Some(60)).unwrap();
+		assert!(matches!(trigger, ScheduleTrigger::Cron(_)));
+	}
+
+	#[test]
+	fn test_invalid_cron() {
+		assert!(ScheduleTrigger::new(Some("not a cron"), None).is_err());
+	}
+}
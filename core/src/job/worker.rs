@@ -3,10 +3,12 @@ use std::{
 	time::{Duration, Instant},
 };
 
+use integrations::NotifierEvent;
 use tokio::sync::{broadcast, mpsc, oneshot};
 
 use crate::{
 	config::StumpConfig,
+	db::entity::dispatch_notifier_event,
 	event::CoreEvent,
 	job::{JobError, JobStatus},
 	prisma::{job, PrismaClient},
@@ -119,6 +121,11 @@ impl WorkerCtx {
 		);
 	}
 
+	/// Send a [`NotifierEvent`] to any notifiers subscribed to it
+	pub fn send_notifier_event(&self, event: NotifierEvent, library_id: Option<String>) {
+		dispatch_notifier_event(self.db.clone(), event, library_id);
+	}
+
 	/// Send a batch of [`WorkerSend`] to the appropriate handlers
 	///
 	/// Note that this isn't _really_ batching on the send side, rather just providing
@@ -408,6 +415,15 @@ impl WorkerManager {
 										JobStatus::Failed,
 										&format!("Job failed: {error}"),
 									));
+									if !matches!(error, JobError::Cancelled(_)) {
+										finalizer_ctx.send_notifier_event(
+											NotifierEvent::JobFailed {
+												job_name: returned_executor.name().to_string(),
+												error: error.to_string(),
+											},
+											None,
+										);
+									}
 
 									let result = returned_executor
 										.persist_failure(
@@ -443,6 +459,13 @@ impl WorkerManager {
 								JobStatus::Failed,
 								&format!("Job failed: {join_error}"),
 							));
+							finalizer_ctx.send_notifier_event(
+								NotifierEvent::JobFailed {
+									job_name: job_id.clone(),
+									error: join_error.to_string(),
+								},
+								None,
+							);
 							let _ = handle_failure_status(job_id.clone(), JobStatus::Failed, &finalizer_ctx.db, elapsed).await;
 						}
 					}
@@ -35,7 +35,9 @@ pub use event::CoreEvent;
 
 pub use email::{
 	AttachmentPayload, EmailContentType, EmailerClient, EmailerClientConfig,
+	InvitationPayload, PasswordResetPayload,
 };
+pub use integrations::NotifierEvent;
 
 /// A type alias strictly for explicitness in the return type of `init_journal_mode`.
 type JournalModeChanged = bool;
@@ -252,9 +254,13 @@ mod tests {
 		db::{
 			entity::*,
 			filter::*,
+			metadata::AppliedMetadata,
 			query::{ordering::*, pagination::*},
+			search::*,
+		},
+		filesystem::{
+			image::*, media::metadata_fetch_job::MetadataFetchOptions, scanner::*, *,
 		},
-		filesystem::{image::*, scanner::*, *},
 		job::*,
 		CoreEvent,
 	};
@@ -318,12 +324,20 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<PartialUser>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<UserPermission>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<AgeRestriction>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ServerInvitation>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<APIKey>()?).as_bytes())?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<InheritPermissionValue>()?).as_bytes(),
 		)?;
 		file.write_all(format!("{}\n\n", ts_export::<APIKeyPermissions>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<APIKeyRouteScope>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<APIKeyScopes>()?).as_bytes())?;
+
+		file.write_all(format!("{}\n\n", ts_export::<AuditLog>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<AuditAction>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<AuditTargetType>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<AuditLogChange>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<SupportedFont>()?).as_bytes())?;
 
@@ -340,6 +354,7 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<UserPreferences>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<LoginActivity>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ExternalIdentity>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<EmailerSendTo>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<EmailerConfig>()?).as_bytes())?;
@@ -378,6 +393,42 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<Media>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<Bookmark>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<MediaAnnotation>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<AnnotatedBook>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<AnnotationExportFormat>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<DuplicateReason>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<DuplicateCluster>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReadingActivity>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReadingStatsCount>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<DeviceReadingStats>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReadingStats>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReadingStatsBook>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<YearInReview>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<UserReadingSummary>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ServerReadingStats>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<MediaSearchResult>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataProviderType>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<MediaMetadataField>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<SeriesMetadataField>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<MetadataCandidate>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<MetadataSource>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<MetadataFieldState>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataFieldChange>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<AppliedMetadata>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<MetadataFetchOptions>()?).as_bytes(),
+		)?;
+		file.write_all(format!("{}\n\n", ts_export::<Review>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReviewRatingCount>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReviewStats>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReadingLocator>()?).as_bytes())?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<ActiveReadingSession>()?).as_bytes(),
 		)?;
@@ -414,6 +465,7 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<SmartList>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<SmartFilter<()>>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<TagSmartFilter>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ReviewSmartFilter>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<MediaSmartFilter>()?).as_bytes())?;
 		file.write_all(
 			format!("{}\n\n", ts_export::<MediaMetadataSmartFilter>()?).as_bytes(),
@@ -462,9 +514,12 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<Epub>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<UpdateEpubProgress>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<EpubContent>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<PdfOutlineItem>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<JobStatus>()?).as_bytes())?;
-		file.write_all(format!("{}\n\n", ts_export::<JobSchedulerConfig>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ScheduledJobKind>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<ScheduledJobConfig>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<JobSchedule>()?).as_bytes())?;
 
 		file.write_all(format!("{}\n\n", ts_export::<ReadingListItem>()?).as_bytes())?;
 		file.write_all(
@@ -473,6 +528,10 @@ mod tests {
 		file.write_all(format!("{}\n\n", ts_export::<ReadingList>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<CreateReadingList>()?).as_bytes())?;
 
+		file.write_all(format!("{}\n\n", ts_export::<CollectionItemKind>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<CollectionItem>()?).as_bytes())?;
+		file.write_all(format!("{}\n\n", ts_export::<Collection>()?).as_bytes())?;
+
 		file.write_all(format!("{}\n\n", ts_export::<ImageResizeMode>()?).as_bytes())?;
 		file.write_all(format!("{}\n\n", ts_export::<ImageResizeOptions>()?).as_bytes())?;
 		file.write_all(
@@ -482,6 +541,10 @@ mod tests {
 		file.write_all(
 			format!("{}\n\n", ts_export::<ImageProcessorOptions>()?).as_bytes(),
 		)?;
+		file.write_all(format!("{}\n\n", ts_export::<PageSplit>()?).as_bytes())?;
+		file.write_all(
+			format!("{}\n\n", ts_export::<PageTransformOptions>()?).as_bytes(),
+		)?;
 
 		file.write_all(format!("{}\n\n", ts_export::<DirectoryListing>()?).as_bytes())?;
 		file.write_all(
@@ -0,0 +1,146 @@
+//! This module defines version-agnostic representations of OPDS facets, which clients present
+//! as filters for an acquisition feed. Each OPDS version serializes these in its own way, see
+//! [crate::opds::v1_2::link::OpdsFacetLink] and [crate::opds::v2_0::facet::OPDSFeedFacet].
+
+use prisma_client_rust::PrismaValue;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{db::query::raw::RawSql, prisma::PrismaClient, CoreResult};
+
+/// A single facet, i.e. a link which narrows the current feed down to the books matching
+/// some value
+#[derive(Debug, Clone, PartialEq)]
+pub struct OPDSFacet {
+	pub title: String,
+	pub href: String,
+	/// The number of books which match the facet, if known
+	pub count: Option<i64>,
+	/// Whether the facet is currently applied to the feed
+	pub active: bool,
+}
+
+/// A titled group of related facets, e.g. all of the genres books can be filtered by
+#[derive(Debug, Clone, PartialEq)]
+pub struct OPDSFacetGroup {
+	pub title: String,
+	pub facets: Vec<OPDSFacet>,
+}
+
+/// The metadata values which books can be faceted by, along with the number of books which
+/// have each value
+#[derive(Debug, Default)]
+pub struct OPDSFacetValues {
+	pub genres: Vec<(String, i64)>,
+	pub publishers: Vec<(String, i64)>,
+	pub writers: Vec<(String, i64)>,
+	pub years: Vec<(i32, i64)>,
+}
+
+/// Splits the comma-separated `{column}` of each book matching the `{condition}` into its trimmed
+/// values, and counts the books which have each value
+const LIST_FACET_VALUES: &str = r"
+WITH RECURSIVE split(media_id, value, rest) AS (
+	SELECT m.id, '', mm.{column} || ','
+	FROM media m
+	INNER JOIN media_metadata mm ON mm.media_id = m.id
+	WHERE mm.{column} IS NOT NULL AND {condition}
+	UNION ALL
+	SELECT media_id, TRIM(SUBSTR(rest, 1, INSTR(rest, ',') - 1)), SUBSTR(rest, INSTR(rest, ',') + 1)
+	FROM split
+	WHERE rest <> ''
+)
+SELECT value, COUNT(DISTINCT media_id) AS count
+FROM split
+WHERE value <> ''
+GROUP BY value
+ORDER BY count DESC, value ASC
+LIMIT ";
+
+/// Counts the books matching the `{condition}` which have each value of a single-valued column
+const FACET_VALUES: &str = r"
+SELECT {value} AS value, COUNT(*) AS count
+FROM media m
+INNER JOIN media_metadata mm ON mm.media_id = m.id
+WHERE {value} IS NOT NULL AND {condition}
+GROUP BY value
+ORDER BY count DESC, value ASC
+LIMIT ";
+
+#[derive(Deserialize)]
+struct FacetValueRow<T> {
+	value: T,
+	count: i64,
+}
+
+impl OPDSFacetValues {
+	/// Count the most common facet values among the books matching `books`, a condition over
+	/// `media m`. At most `limit` values are kept for each kind of facet
+	pub async fn load(
+		client: &PrismaClient,
+		books: RawSql,
+		limit: i64,
+	) -> CoreResult<Self> {
+		let genres = list_facet_values(client, "genre", &books, limit).await?;
+		let writers = list_facet_values(client, "writers", &books, limit).await?;
+		let publishers = facet_values(
+			client,
+			"CASE WHEN TRIM(mm.publisher) <> '' THEN mm.publisher END",
+			&books,
+			limit,
+		)
+		.await?;
+		// Years read more naturally newest first, rather than by how common they are
+		let mut years = facet_values(client, "mm.year", &books, limit).await?;
+		years.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+		Ok(Self {
+			genres,
+			publishers,
+			writers,
+			years,
+		})
+	}
+}
+
+async fn list_facet_values(
+	client: &PrismaClient,
+	column: &str,
+	books: &RawSql,
+	limit: i64,
+) -> CoreResult<Vec<(String, i64)>> {
+	let query = LIST_FACET_VALUES.replace("{column}", column);
+	query_facet_values(client, &query, books, limit).await
+}
+
+async fn facet_values<T: DeserializeOwned + 'static>(
+	client: &PrismaClient,
+	value: &str,
+	books: &RawSql,
+	limit: i64,
+) -> CoreResult<Vec<(T, i64)>> {
+	let query = FACET_VALUES.replace("{value}", value);
+	query_facet_values(client, &query, books, limit).await
+}
+
+/// Run a facet query, filling in its `{condition}` with `books` and appending the limit
+async fn query_facet_values<T: DeserializeOwned + 'static>(
+	client: &PrismaClient,
+	query: &str,
+	books: &RawSql,
+	limit: i64,
+) -> CoreResult<Vec<(T, i64)>> {
+	let (before, after) = query
+		.split_once("{condition}")
+		.expect("Facet queries should have a condition");
+	let query = RawSql::new(before, vec![])
+		.append(books.clone().wrapped())
+		.push(after)
+		.push_param(PrismaValue::Int(limit));
+
+	let rows = client
+		._query_raw::<FacetValueRow<T>>(query.build())
+		.exec()
+		.await?;
+
+	Ok(rows.into_iter().map(|row| (row.value, row.count)).collect())
+}
@@ -1,2 +1,3 @@
+pub mod facet;
 pub mod v1_2;
 pub mod v2_0;
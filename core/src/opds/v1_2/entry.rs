@@ -266,8 +266,9 @@ impl IntoOPDSEntry for OPDSEntryBuilder<media::Data> {
 			self.data.pages.to_string(),
 			current_page_link_type.to_string(),
 			current_page.map(|page| page.to_string()),
-			last_read_at.map(|date| date.to_string()),
-		);
+			last_read_at.map(|date| date.to_rfc3339()),
+		)
+		.with_api_key(self.api_key.clone());
 
 		let mib = self.data.size as f64 / (1024.0 * 1024.0);
 
@@ -364,7 +365,7 @@ mod tests {
 				<link type="image/gif"
 							rel="http://opds-spec.org/image/thumbnail"
 							href="/covers/4561.thmb.gif" />
-				<link href="/opds/v1.2/books/123/pages/{pageNumber}?zero_based=true"
+				<link href="/opds/v1.2/books/123/pages/{pageNumber}?zero_based=true&amp;max_width={maxWidth}"
 							type="image/jpeg"
 							rel="http://vaemendis.net/opds-pse/stream"
 							pse:count="35"
@@ -387,8 +388,9 @@ mod tests {
 			description: None,
 			emoji: None,
 			hidden_from_users: None,
-			job_schedule_config: None,
-			job_schedule_config_id: None,
+			hidden_from_invitations: None,
+			included_in_job_schedules: None,
+			excluded_from_job_schedules: None,
 			last_scanned_at: None,
 			scan_history: None,
 			config: None,
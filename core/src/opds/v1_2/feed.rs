@@ -5,9 +5,12 @@ use std::collections::HashMap;
 
 use crate::{
 	error::CoreError,
-	opds::v1_2::{
-		entry::{IntoOPDSEntry, OPDSEntryBuilder},
-		link::OpdsLink,
+	opds::{
+		facet::OPDSFacetGroup,
+		v1_2::{
+			entry::{IntoOPDSEntry, OPDSEntryBuilder},
+			link::{OpdsFacetLink, OpdsLink},
+		},
 	},
 	prisma::{library, series},
 	utils::chain_optional_iter,
@@ -30,6 +33,7 @@ pub struct OpdsFeed {
 	pub title: String,
 	pub entries: Vec<OpdsEntry>,
 	pub links: Option<Vec<OpdsLink>>,
+	pub facets: Vec<OpdsFacetLink>,
 }
 
 impl OpdsFeed {
@@ -44,6 +48,18 @@ impl OpdsFeed {
 			title,
 			entries,
 			links,
+			facets: Vec::new(),
+		}
+	}
+
+	/// Add facet links to the feed, which clients present as filters for its entries
+	pub fn with_facets(self, groups: Vec<OPDSFacetGroup>) -> Self {
+		Self {
+			facets: groups
+				.into_iter()
+				.flat_map(OpdsFacetLink::from_group)
+				.collect(),
+			..self
 		}
 	}
 
@@ -63,7 +79,8 @@ impl OpdsFeed {
 			xml::writer::XmlEvent::start_element("feed")
 				.default_ns("http://www.w3.org/2005/Atom")
 				.ns("opds", "http://opds-spec.org/2010/catalog")
-				.ns("pse", "http://vaemendis.net/opds-pse/ns"),
+				.ns("pse", "http://vaemendis.net/opds-pse/ns")
+				.ns("thr", "http://purl.org/syndication/thread/1.0"),
 		)?;
 
 		util::write_xml_element("id", &self.id, &mut writer)?;
@@ -79,6 +96,10 @@ impl OpdsFeed {
 			}
 		}
 
+		for facet in &self.facets {
+			facet.write(&mut writer)?;
+		}
+
 		for entry in &self.entries {
 			entry.write(&mut writer)?;
 		}
@@ -125,7 +146,8 @@ impl OPDSFeedBuilder {
 	fn format_params(&self, path: &str, params: HashMap<String, String>) -> String {
 		let mut url = self.format_url(path);
 		if !params.is_empty() {
-			url.push('?');
+			// The path may already carry a query, e.g. the facets applied to a feed
+			url.push(if url.contains('?') { '&' } else { '?' });
 			for (idx, (key, value)) in params.iter().enumerate() {
 				if idx == params.len() - 1 {
 					url.push_str(&format!("{}={}", key, value));
@@ -321,7 +343,8 @@ mod tests {
 			<?xml version="1.0" encoding="utf-8"?>
 			<feed xmlns="http://www.w3.org/2005/Atom" 
 						xmlns:opds="http://opds-spec.org/2010/catalog" 
-						xmlns:pse="http://vaemendis.net/opds-pse/ns">
+						xmlns:pse="http://vaemendis.net/opds-pse/ns"
+						xmlns:thr="http://purl.org/syndication/thread/1.0">
 				<id>feed_id</id>
 				<title>Feed Title</title>
 				<updated>{{{INSERT}}}</updated>
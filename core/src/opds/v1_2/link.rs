@@ -2,11 +2,13 @@
 //! specified at https://specs.opds.io/opds-1.2#the-atomlink-element
 //!
 //! It also defines the [`OpdsStreamLink`] struct for representing an OPDS page steaming extension
-//! link element as specified at https://github.com/anansi-project/opds-pse/blob/master/v1.2.md
+//! link element as specified at https://github.com/anansi-project/opds-pse/blob/master/v1.2.md,
+//! and the [`OpdsFacetLink`] struct for representing a facet as specified at
+//! https://specs.opds.io/opds-1.2#4-facets
 
 use xml::{writer::XmlEvent, EventWriter};
 
-use crate::{error::CoreResult, filesystem::ContentType};
+use crate::{error::CoreResult, filesystem::ContentType, opds::facet::OPDSFacetGroup};
 
 use super::util::OpdsEnumStr;
 
@@ -34,6 +36,7 @@ impl OpdsLinkType {
 			"epub" => Some(OpdsLinkType::Epub),
 			// TODO: RARs as ZIP??? Obviously for content type it's different, but does OPDS concern itself with that?
 			"zip" | "cbz" | "rar" | "cbr" => Some(OpdsLinkType::Zip),
+			"7z" | "cb7" | "tar" | "cbt" => Some(OpdsLinkType::OctetStream),
 			_ => None,
 		}
 	}
@@ -84,6 +87,7 @@ pub enum OpdsLinkRel {
 	Image,       // "http://opds-spec.org/image"
 	PageStream,  // "http://vaemendis.net/opds-pse/stream"
 	Search,      // "search"
+	Facet,       // "http://opds-spec.org/facet"
 }
 
 impl OpdsEnumStr for OpdsLinkRel {
@@ -99,14 +103,11 @@ impl OpdsEnumStr for OpdsLinkRel {
 			OpdsLinkRel::Image => "http://opds-spec.org/image",
 			OpdsLinkRel::PageStream => "http://vaemendis.net/opds-pse/stream",
 			OpdsLinkRel::Search => "search",
+			OpdsLinkRel::Facet => "http://opds-spec.org/facet",
 		}
 	}
 }
 
-// TODO: this struct needs to be restructured.
-// I need to be able to output the following:
-// <link xmlns:wstxns3="http://vaemendis.net/opds-pse/ns" href="/opds/v1.2/books/<id>/pages/{pageNumber}?zero_based=true" wstxns3:count="309" type="image/jpeg" rel="http://vaemendis.net/opds-pse/stream"/>
-
 #[derive(Debug)]
 pub struct OpdsLink {
 	pub link_type: OpdsLinkType,
@@ -143,6 +144,8 @@ pub struct OpdsStreamLink {
 	pub mime_type: String,
 	pub last_read: Option<String>,
 	pub last_read_date: Option<String>,
+	/// The API key to embed in the link, for clients which authenticate with one
+	pub api_key: Option<String>,
 }
 
 impl OpdsStreamLink {
@@ -159,14 +162,29 @@ impl OpdsStreamLink {
 			mime_type,
 			last_read,
 			last_read_date,
+			api_key: None,
 		}
 	}
 
-	pub fn write(&self, writer: &mut EventWriter<Vec<u8>>) -> CoreResult<()> {
-		let href = format!(
-			"/opds/v1.2/books/{}/pages/{{pageNumber}}?zero_based=true",
+	pub fn with_api_key(self, api_key: Option<String>) -> Self {
+		Self { api_key, ..self }
+	}
+
+	/// The templated href of the page stream. Clients substitute `{pageNumber}` with the
+	/// zero-based page and, if they support it, `{maxWidth}` with the width of their screen
+	fn href(&self) -> String {
+		let base_url = match &self.api_key {
+			Some(api_key) => format!("/opds/{api_key}/v1.2"),
+			None => "/opds/v1.2".to_string(),
+		};
+		format!(
+			"{base_url}/books/{}/pages/{{pageNumber}}?zero_based=true&max_width={{maxWidth}}",
 			self.book_id
-		);
+		)
+	}
+
+	pub fn write(&self, writer: &mut EventWriter<Vec<u8>>) -> CoreResult<()> {
+		let href = self.href();
 
 		let mut link = XmlEvent::start_element("link")
 			.attr("href", href.as_str())
@@ -189,10 +207,60 @@ impl OpdsStreamLink {
 	}
 }
 
+/// A link which narrows an acquisition feed down to the books matching a facet. Facets are
+/// grouped by their `opds:facetGroup`, and clients highlight the `opds:activeFacet` ones
+#[derive(Debug)]
+pub struct OpdsFacetLink {
+	pub href: String,
+	pub title: String,
+	pub group: String,
+	pub count: Option<i64>,
+	pub active: bool,
+}
+
+impl OpdsFacetLink {
+	/// Create a facet link for each facet in the group
+	pub fn from_group(OPDSFacetGroup { title, facets }: OPDSFacetGroup) -> Vec<Self> {
+		facets
+			.into_iter()
+			.map(|facet| Self {
+				href: facet.href,
+				title: facet.title,
+				group: title.clone(),
+				count: facet.count,
+				active: facet.active,
+			})
+			.collect()
+	}
+
+	pub fn write(&self, writer: &mut EventWriter<Vec<u8>>) -> CoreResult<()> {
+		let count = self.count.map(|count| count.to_string());
+
+		let mut link = XmlEvent::start_element("link")
+			.attr("rel", OpdsLinkRel::Facet.as_str())
+			.attr("href", &self.href)
+			.attr("type", OpdsLinkType::Acquisition.as_str())
+			.attr("title", &self.title)
+			.attr("opds:facetGroup", &self.group);
+
+		if self.active {
+			link = link.attr("opds:activeFacet", "true");
+		}
+
+		if let Some(count) = &count {
+			link = link.attr("thr:count", count);
+		}
+
+		writer.write(link)?;
+		writer.write(XmlEvent::end_element())?; // end of link
+		Ok(())
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::opds::v1_2::tests::normalize_xml;
+	use crate::opds::{facet::OPDSFacet, v1_2::tests::normalize_xml};
 
 	#[test]
 	fn test_opds_link() {
@@ -236,7 +304,7 @@ mod tests {
 		let expected_result = normalize_xml(
 			r#"
 			<?xml version="1.0" encoding="utf-8"?>
-			<link href="/opds/v1.2/books/123/pages/{pageNumber}?zero_based=true"
+			<link href="/opds/v1.2/books/123/pages/{pageNumber}?zero_based=true&amp;max_width={maxWidth}"
 						type="image/jpeg"
 						rel="http://vaemendis.net/opds-pse/stream"
 						pse:count="35"
@@ -248,4 +316,56 @@ mod tests {
 
 		assert_eq!(result, expected_result);
 	}
+
+	#[test]
+	fn test_opds_stream_link_with_api_key() {
+		let link = OpdsStreamLink::new(
+			"123".to_string(),
+			"35".to_string(),
+			"image/jpeg".to_string(),
+			None,
+			None,
+		)
+		.with_api_key(Some("api_key".to_string()));
+
+		assert_eq!(
+			link.href(),
+			"/opds/api_key/v1.2/books/123/pages/{pageNumber}?zero_based=true&max_width={maxWidth}"
+		);
+	}
+
+	#[test]
+	fn test_opds_facet_link() {
+		let links = OpdsFacetLink::from_group(OPDSFacetGroup {
+			title: "Genre".to_string(),
+			facets: vec![OPDSFacet {
+				title: "Horror".to_string(),
+				href: "/opds/v1.2/books?genre=Horror".to_string(),
+				count: Some(12),
+				active: true,
+			}],
+		});
+
+		let mut writer = EventWriter::new(Vec::new());
+		for link in &links {
+			link.write(&mut writer).unwrap();
+		}
+
+		let result = String::from_utf8(writer.into_inner()).unwrap();
+		let expected_result = normalize_xml(
+			r#"
+			<?xml version="1.0" encoding="utf-8"?>
+			<link rel="http://opds-spec.org/facet"
+						href="/opds/v1.2/books?genre=Horror"
+						type="application/atom+xml;profile=opds-catalog;kind=acquisition"
+						title="Horror"
+						opds:facetGroup="Genre"
+						opds:activeFacet="true"
+						thr:count="12"
+			/>
+			"#,
+		);
+
+		assert_eq!(result, expected_result);
+	}
 }
@@ -36,7 +36,9 @@ impl OpdsOpenSearch {
 
 		writer.write(
 			XmlEvent::start_element("OpenSearchDescription")
-				.attr("xmlns", "http://a9.com/-/spec/opensearch/1.1/"),
+				.attr("xmlns", "http://a9.com/-/spec/opensearch/1.1/")
+				.ns("atom", "http://www.w3.org/2005/Atom")
+				.ns("dc", "http://purl.org/dc/elements/1.1/"),
 		)?;
 
 		write_xml_element("ShortName", "Search", &mut writer)?;
@@ -54,10 +56,21 @@ impl OpdsOpenSearch {
 		)?;
 		writer.write(XmlEvent::end_element())?; // end series template URL
 
-		// TODO: more templates? have to look into open search spec to see
-		// if that 'is possible' or if it is only supposed to be one
+		// The book template accepts optional parameters for the author, publisher and year,
+		// which clients that understand them can fill in to refine the search. The
+		// parameters are namespaced as described in the OpenSearch spec:
+		// https://github.com/dewitt/opensearch/blob/master/opensearch-1-1-draft-6.md#parameter-names
+		let books_example = self.format_url(
+			"books?search={searchTerms}&author={atom:author?}&publisher={dc:publisher?}&year={dc:date?}",
+		);
 
-		// https://developer.mozilla.org/en-US/docs/Web/OpenSearch
+		// start of books template URL
+		writer.write(
+			XmlEvent::start_element("Url")
+				.attr("template", &books_example)
+				.attr("type", OpdsLinkType::Acquisition.as_str()),
+		)?;
+		writer.write(XmlEvent::end_element())?; // end books template URL
 
 		writer.write(XmlEvent::end_element())?; // end of feed
 
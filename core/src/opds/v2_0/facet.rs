@@ -0,0 +1,104 @@
+//! A module for representing facets in an OPDS 2.0 feed, as defined by the OPDS 2.0 spec at
+//! https://drafts.opds.io/opds-2.0#24-facets
+
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+use crate::{opds::facet::OPDSFacetGroup, CoreResult};
+
+use super::{
+	link::{OPDSBaseLinkBuilder, OPDSLink, OPDSLinkFinalizer, OPDSLinkRel, OPDSLinkType},
+	metadata::{OPDSMetadata, OPDSMetadataBuilder},
+	properties::{OPDSDynamicProperties, OPDSPropertiesBuilder},
+	OPDSV2Error,
+};
+
+/// A struct representing a facet group, which is a collection of links that narrow the
+/// publications of the feed down to those matching some value.
+///
+/// See https://drafts.opds.io/opds-2.0#24-facets
+#[derive(Debug, Clone, Builder, Serialize, Deserialize)]
+#[builder(build_fn(error = "OPDSV2Error"), setter(into))]
+pub struct OPDSFeedFacet {
+	/// The metadata for the facet group, which must include a title
+	metadata: OPDSMetadata,
+	/// The links of the facet group, one for each facet. The active facet, if any, is marked
+	/// with the `self` rel
+	links: Vec<OPDSLink>,
+}
+
+impl OPDSFeedFacet {
+	pub fn new(
+		OPDSFacetGroup { title, facets }: OPDSFacetGroup,
+		finalizer: &OPDSLinkFinalizer,
+	) -> CoreResult<Self> {
+		let links = facets
+			.into_iter()
+			.map(|facet| {
+				let properties = facet
+					.count
+					.map(|count| {
+						OPDSPropertiesBuilder::default()
+							.dynamic_properties(OPDSDynamicProperties(
+								serde_json::json!({ "numberOfItems": count }),
+							))
+							.build()
+					})
+					.transpose()?;
+
+				OPDSBaseLinkBuilder::default()
+					.title(facet.title)
+					.href(facet.href)
+					.rel(facet.active.then(|| OPDSLinkRel::SelfLink.item()))
+					._type(OPDSLinkType::OpdsJson)
+					.properties(properties)
+					.build()
+					.map(|link| finalizer.finalize(link.as_link()))
+			})
+			.collect::<CoreResult<Vec<_>>>()?;
+
+		Ok(Self {
+			metadata: OPDSMetadataBuilder::default()
+				.title(title)
+				.modified(None::<String>)
+				.build()?,
+			links,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::opds::facet::OPDSFacet;
+
+	#[test]
+	fn test_opds_feed_facet_serialization() {
+		let facet = OPDSFeedFacet::new(
+			OPDSFacetGroup {
+				title: "Genre".to_string(),
+				facets: vec![
+					OPDSFacet {
+						title: "Horror".to_string(),
+						href: "/opds/v2.0/books/browse?genre=Horror".to_string(),
+						count: Some(12),
+						active: true,
+					},
+					OPDSFacet {
+						title: "Romance".to_string(),
+						href: "/opds/v2.0/books/browse?genre=Romance".to_string(),
+						count: None,
+						active: false,
+					},
+				],
+			},
+			&OPDSLinkFinalizer::new("https://example.com".to_string()),
+		)
+		.unwrap();
+
+		assert_eq!(
+			serde_json::to_string(&facet).unwrap(),
+			r#"{"metadata":{"title":"Genre"},"links":[{"title":"Horror","rel":"self","href":"https://example.com/opds/v2.0/books/browse?genre=Horror","type":"application/opds+json","properties":{"numberOfItems":12}},{"title":"Romance","href":"https://example.com/opds/v2.0/books/browse?genre=Romance","type":"application/opds+json"}]}"#
+		);
+	}
+}
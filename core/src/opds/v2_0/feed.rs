@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
 use super::{
+	facet::OPDSFeedFacet,
 	group::OPDSFeedGroup,
 	link::{OPDSLink, OPDSNavigationLink},
 	metadata::OPDSMetadata,
@@ -41,6 +42,11 @@ pub struct OPDSFeed {
 	/// Publications contained within the feed
 	#[builder(default)]
 	publications: Option<Vec<OPDSPublication>>,
+	/// Facets which can be applied to the publications of the feed
+	///
+	/// See https://drafts.opds.io/opds-2.0#24-facets
+	#[builder(default)]
+	facets: Option<Vec<OPDSFeedFacet>>,
 	/// Metadata for the feed
 	metadata: OPDSMetadata,
 
@@ -49,6 +55,16 @@ pub struct OPDSFeed {
 	pub allow_empty: bool,
 }
 
+impl OPDSFeed {
+	/// Add facets to an already built feed, for feeds which are generated by shared helpers
+	pub fn with_facets(self, facets: Vec<OPDSFeedFacet>) -> Self {
+		Self {
+			facets: (!facets.is_empty()).then_some(facets),
+			..self
+		}
+	}
+}
+
 impl OPDSFeedBuilder {
 	fn validate(&self) -> Result<(), OPDSV2Error> {
 		if self.allow_empty.unwrap_or(true) {
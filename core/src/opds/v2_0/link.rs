@@ -7,7 +7,7 @@ use serde_with::skip_serializing_none;
 
 use crate::{
 	filesystem::ContentType,
-	prisma::{library, series},
+	prisma::{collection, library, series, smart_list},
 };
 
 use super::{
@@ -330,6 +330,35 @@ impl From<series::Data> for OPDSNavigationLink {
 	}
 }
 
+// TODO(OPDS-V2): What should rel be?
+impl From<collection::Data> for OPDSNavigationLink {
+	fn from(collection: collection::Data) -> Self {
+		OPDSNavigationLink {
+			title: collection.name,
+			base_link: OPDSBaseLink {
+				href: format!("/opds/v2.0/collections/{}", collection.id),
+				_type: Some(OPDSLinkType::OpdsJson),
+				rel: Some(OPDSLinkRel::Subsection.item()),
+				..Default::default()
+			},
+		}
+	}
+}
+
+impl From<smart_list::Data> for OPDSNavigationLink {
+	fn from(smart_list: smart_list::Data) -> Self {
+		OPDSNavigationLink {
+			title: smart_list.name,
+			base_link: OPDSBaseLink {
+				href: format!("/opds/v2.0/smart-lists/{}", smart_list.id),
+				_type: Some(OPDSLinkType::OpdsJson),
+				rel: Some(OPDSLinkRel::Subsection.item()),
+				..Default::default()
+			},
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::opds::v2_0::properties::{OPDSDynamicProperties, OPDSPropertiesBuilder};
@@ -452,8 +481,9 @@ mod tests {
 			description: None,
 			emoji: None,
 			hidden_from_users: None,
-			job_schedule_config: None,
-			job_schedule_config_id: None,
+			hidden_from_invitations: None,
+			included_in_job_schedules: None,
+			excluded_from_job_schedules: None,
 			last_scanned_at: None,
 			scan_history: None,
 			config: None,
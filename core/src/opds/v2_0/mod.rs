@@ -4,6 +4,7 @@
 
 pub mod authentication;
 mod error;
+pub mod facet;
 pub mod feed;
 pub mod group;
 pub mod link;
@@ -18,5 +19,4 @@ pub use error::OPDSV2Error;
 pub use prisma_macros::*;
 pub use utils::{ArrayOrItem, OPDSV2PrismaExt};
 
-// TODO(OPDS-V2): facet (kinda confused on this one still)
 // TODO(OPDS-V2): constants for the various OPDS 2.0 routes
@@ -18,6 +18,8 @@ active_reading_session::select!(reading_session_opds_progression {
    page
    percentage_completed
    epubcfi
+   locator_href
+   locator_progression
    updated_at
    device: select { id name }
 	media: select {
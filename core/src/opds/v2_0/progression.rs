@@ -1,3 +1,5 @@
+use prisma_client_rust::chrono::{DateTime, FixedOffset};
+
 use crate::CoreResult;
 
 use super::{
@@ -11,9 +13,14 @@ use serde_with::skip_serializing_none;
 
 pub const CANTOOK_PROGRESSION_REL: &str = "http://www.cantook.com/api/progression";
 
+/// The progression of a user through a publication, as described by the Readium progression
+/// API. The same document is returned when fetching the progression, and sent by clients
+/// when updating it.
+///
+/// See https://github.com/readium/architecture/tree/master/models/locators
 #[derive(Debug, Default, Clone, Builder, Serialize, Deserialize)]
 #[builder(build_fn(error = "crate::CoreError"), default, setter(into))]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct OPDSProgression {
 	#[builder(default = "default_now()")]
 	modified: String,
@@ -44,28 +51,33 @@ impl OPDSProgression {
 				("epub", Some(cfi), _) => {
 					// TODO: Lookup chapter without opening file, e.g. epubcfi?
 					let title = "Ebook Progress".to_string();
-					// TODO: Use resource URL for href, e.g. OEBPS/chapter008.xhtml ?
-					let locations = data.percentage_completed.map(|progression| {
-						vec![OPDSProgressionLocation {
-							fragments: Some(vec![cfi]),
-							total_progression: Some(progression),
-							..Default::default()
-						}]
-					});
-					(Some(title), None, Some(OPDSLinkType::Xhtml), locations)
+					// The href is the spine resource within the EPUB (e.g.
+					// OEBPS/chapter008.xhtml), which is only known once the cfi was located
+					let locations = OPDSProgressionLocation {
+						fragments: Some(vec![cfi]),
+						progression: data.locator_progression,
+						total_progression: data.percentage_completed,
+						..Default::default()
+					};
+					(
+						Some(title),
+						data.locator_href,
+						Some(OPDSLinkType::Xhtml),
+						Some(locations),
+					)
 				},
 				(_, None, Some(current_page)) => {
 					let title = format!("Page {}", current_page);
 					let href = link_finalizer.format_link(format!(
 						"/opds/v2.0/books/{book_id}/pages/{current_page}",
 					));
-					let locations = vec![OPDSProgressionLocation {
-						position: Some(current_page.to_string()),
+					let locations = OPDSProgressionLocation {
+						position: Some(current_page),
 						total_progression: data.percentage_completed.or_else(|| {
 							Some(current_page as f64 / data.media.pages as f64)
 						}),
 						..Default::default()
-					}];
+					};
 					// TODO: Don't assume JPEG, use analysis to determine this
 					let _type = OPDSLinkType::ImageJpeg;
 					(Some(title), Some(href), Some(_type), Some(locations))
@@ -74,6 +86,7 @@ impl OPDSProgression {
 			};
 
 		OPDSProgressionBuilder::default()
+			.modified(data.updated_at.to_rfc3339())
 			.device(device)
 			.locator(
 				OPDSProgressionLocatorBuilder::default()
@@ -85,30 +98,85 @@ impl OPDSProgression {
 			)
 			.build()
 	}
+
+	/// When the progression was made, if the client sent a valid timestamp
+	pub fn modified(&self) -> Option<DateTime<FixedOffset>> {
+		DateTime::parse_from_rfc3339(&self.modified).ok()
+	}
+
+	/// The ID and name of the device the progression was made on, if the client sent one
+	pub fn device(&self) -> Option<(&str, &str)> {
+		let OPDSProgressionDevice { id, name } = &self.device;
+		(!id.is_empty()).then_some((id.as_str(), name.as_str()))
+	}
+
+	/// The href of the resource within the publication, e.g. a page link for page-based
+	/// books or a spine resource for EPUBs
+	pub fn href(&self) -> Option<&str> {
+		self.locator.href.as_deref()
+	}
+
+	fn locations(&self) -> Option<&OPDSProgressionLocation> {
+		self.locator.locations.as_ref()
+	}
+
+	/// The progression within the resource referenced by the href (0.0 to 1.0)
+	pub fn progression(&self) -> Option<f64> {
+		self.locations().and_then(|locations| locations.progression)
+	}
+
+	/// The progression within the whole publication (0.0 to 1.0)
+	pub fn total_progression(&self) -> Option<f64> {
+		self.locations()
+			.and_then(|locations| locations.total_progression)
+	}
+
+	/// The 1-based page of a page-based book, taken from the position or else the page link
+	pub fn page(&self) -> Option<i32> {
+		self.locations()
+			.and_then(|locations| locations.position)
+			.or_else(|| {
+				let (_, page) = self.href()?.rsplit_once("/pages/")?;
+				page.split(['?', '#']).next()?.parse().ok()
+			})
+	}
+
+	/// The epubcfi of the position within an EPUB, if one was sent as a fragment
+	pub fn epubcfi(&self) -> Option<&str> {
+		self.locations()
+			.and_then(|locations| locations.fragments.as_ref())
+			.and_then(|fragments| {
+				fragments
+					.iter()
+					.find(|fragment| fragment.starts_with("epubcfi("))
+			})
+			.map(String::as_str)
+	}
 }
 
 // https://readium.org/architecture/schema/locator.schema.json
 #[skip_serializing_none]
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Builder)]
 #[builder(build_fn(error = "crate::CoreError"), default, setter(into))]
+#[serde(default)]
 struct OPDSProgressionLocator {
 	title: Option<String>,
 	href: Option<String>,
 	#[serde(rename = "type")]
 	_type: Option<OPDSLinkType>,
 	#[builder(default)]
-	locations: Option<Vec<OPDSProgressionLocation>>,
+	locations: Option<OPDSProgressionLocation>,
 }
 
 #[skip_serializing_none]
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Builder)]
 #[builder(build_fn(error = "crate::CoreError"), default, setter(into))]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 struct OPDSProgressionLocation {
 	/// A list of fragments within the resource referenced by the [OPDSProgressionLocator] struct.
 	fragments: Option<Vec<String>>,
 	/// An index in the publication (1-based).
-	position: Option<String>,
+	position: Option<i32>,
 	/// Progression in the resource expressed as a percentage (0.0 to 1.0).
 	progression: Option<f64>,
 	/// Progression in the publication expressed as a percentage (0.0 to 1.0).
@@ -116,7 +184,74 @@ struct OPDSProgressionLocation {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 struct OPDSProgressionDevice {
 	id: String,
 	name: String,
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_deserialize_page_progression() {
+		let progression: OPDSProgression = serde_json::from_str(
+			r#"{
+				"modified": "2025-05-01T10:00:00Z",
+				"device": { "id": "device-1", "name": "Reader" },
+				"locator": {
+					"href": "https://example.com/opds/v2.0/books/123/pages/12",
+					"type": "image/jpeg",
+					"locations": { "position": 12, "totalProgression": 0.5 }
+				}
+			}"#,
+		)
+		.unwrap();
+
+		assert!(progression.modified().is_some());
+		assert_eq!(progression.device(), Some(("device-1", "Reader")));
+		assert_eq!(progression.page(), Some(12));
+		assert_eq!(progression.total_progression(), Some(0.5));
+		assert_eq!(progression.epubcfi(), None);
+	}
+
+	#[test]
+	fn test_deserialize_epub_progression() {
+		let progression: OPDSProgression = serde_json::from_str(
+			r#"{
+				"locator": {
+					"href": "OEBPS/chapter3.xhtml",
+					"type": "application/xhtml+xml",
+					"locations": {
+						"fragments": ["t=10", "epubcfi(/6/8!/4/2/1:0)"],
+						"progression": 0.25
+					}
+				}
+			}"#,
+		)
+		.unwrap();
+
+		assert!(progression.modified().is_none());
+		assert_eq!(progression.device(), None);
+		assert_eq!(progression.href(), Some("OEBPS/chapter3.xhtml"));
+		assert_eq!(progression.progression(), Some(0.25));
+		assert_eq!(progression.epubcfi(), Some("epubcfi(/6/8!/4/2/1:0)"));
+		assert_eq!(progression.page(), None);
+	}
+
+	#[test]
+	fn test_page_from_href() {
+		let progression = OPDSProgressionBuilder::default()
+			.locator(
+				OPDSProgressionLocatorBuilder::default()
+					.href(Some("/opds/v2.0/books/123/pages/7".to_string()))
+					.build()
+					.unwrap(),
+			)
+			.build()
+			.unwrap();
+
+		assert_eq!(progression.page(), Some(7));
+	}
+}
@@ -322,6 +322,8 @@ mod tests {
 			status: FileStatus::Ready.to_string(),
 			hash: Some(String::from("hash")),
 			koreader_hash: None,
+			checksum: None,
+			cover_hash: None,
 			series_id: Some("1".to_string()),
 			pages: 0,
 			modified_at: None,
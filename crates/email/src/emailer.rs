@@ -4,7 +4,7 @@ use lettre::{
 	address::AddressError,
 	message::{
 		header::{self, ContentType},
-		Attachment, MultiPart, SinglePart,
+		Attachment, MessageBuilder, MultiPart, SinglePart,
 	},
 	transport::smtp::authentication::Credentials,
 	Message, SmtpTransport, Transport,
@@ -52,6 +52,24 @@ pub struct AttachmentPayload {
 	pub content_type: ContentType,
 }
 
+/// The details of an invitation to join the server, used to render an invitation email
+#[derive(Debug)]
+pub struct InvitationPayload {
+	/// The URL the invitee should visit to accept the invitation
+	pub invitation_url: String,
+	/// A human-readable description of when the invitation expires
+	pub expires_at: String,
+}
+
+/// The details of a password reset, used to render a password reset email
+#[derive(Debug)]
+pub struct PasswordResetPayload {
+	/// The URL the user should visit to reset their password
+	pub reset_url: String,
+	/// A human-readable description of when the reset link expires
+	pub expires_at: String,
+}
+
 /// A client for sending emails
 pub struct EmailerClient {
 	/// The configuration for the email client
@@ -184,16 +202,6 @@ impl EmailerClient {
 		recipient: &str,
 		payloads: Vec<AttachmentPayload>,
 	) -> EmailResult<()> {
-		let from = self
-			.config
-			.sender_email
-			.parse()
-			.map_err(|e: AddressError| EmailError::InvalidEmail(e.to_string()))?;
-
-		let to = recipient
-			.parse()
-			.map_err(|e: AddressError| EmailError::InvalidEmail(e.to_string()))?;
-
 		let html = render_template(
 			EmailTemplate::Attachment,
 			&json!({
@@ -214,12 +222,173 @@ impl EmailerClient {
 			multipart_builder = multipart_builder.singlepart(attachment);
 		}
 
-		let email = Message::builder()
-			.from(from)
-			.to(to)
-			.subject(subject)
+		let email = self
+			.message_builder(subject, recipient)?
 			.multipart(multipart_builder)?;
 
+		match self.transport()?.send(&email) {
+			Ok(res) => {
+				tracing::trace!(?res, "Email with attachments was sent");
+				Ok(())
+			},
+			Err(e) => {
+				tracing::error!(error = ?e, "Failed to send email with attachments");
+				Err(e.into())
+			},
+		}
+	}
+
+	/// Send an email inviting the given recipient to join the server
+	///
+	/// # Example
+	/// ```no_run
+	/// use email::{EmailerClient, EmailerClientConfig, InvitationPayload};
+	/// use std::path::PathBuf;
+	///
+	/// async fn test() {
+	///     let config = EmailerClientConfig {
+	///         sender_email: "aaron@stumpapp.dev".to_string(),
+	///         sender_display_name: "Aaron's Stump Instance".to_string(),
+	///         username: "aaron@stumpapp.dev".to_string(),
+	///         password: Some("decrypted_password".to_string()),
+	///         host: "smtp.stumpapp.dev".to_string(),
+	///         port: 587,
+	///         tls_enabled: true,
+	///         max_attachment_size_bytes: Some(10_000_000),
+	///         max_num_attachments: Some(5),
+	///     };
+	///     let template_dir = PathBuf::from("/templates");
+	///     let emailer = EmailerClient::new(config, template_dir);
+	///
+	///     let result = emailer.send_invitation(
+	///         "friend@stumpapp.dev",
+	///         InvitationPayload {
+	///             invitation_url: "https://stump.example.com/auth/invitation?token=abc".to_string(),
+	///             expires_at: "January 1, 2025".to_string(),
+	///         },
+	///     ).await;
+	///     assert!(result.is_err()); // This will fail because the SMTP server is not real
+	/// }
+	/// ```
+	pub async fn send_invitation(
+		&self,
+		recipient: &str,
+		payload: InvitationPayload,
+	) -> EmailResult<()> {
+		let html = render_template(
+			EmailTemplate::Invitation,
+			&json!({
+				"title": "Stump Invitation",
+				"server_name": self.config.sender_display_name,
+				"invitation_url": payload.invitation_url,
+				"expires_at": payload.expires_at,
+			}),
+			self.template_dir.clone(),
+		)?;
+
+		let email = self
+			.message_builder("You're invited to join Stump", recipient)?
+			.header(header::ContentType::TEXT_HTML)
+			.body(html)?;
+
+		match self.transport()?.send(&email) {
+			Ok(res) => {
+				tracing::trace!(?res, "Invitation email was sent");
+				Ok(())
+			},
+			Err(e) => {
+				tracing::error!(error = ?e, "Failed to send invitation email");
+				Err(e.into())
+			},
+		}
+	}
+
+	/// Send an email with a link to reset their password to the given recipient
+	///
+	/// # Example
+	/// ```no_run
+	/// use email::{EmailerClient, EmailerClientConfig, PasswordResetPayload};
+	/// use std::path::PathBuf;
+	///
+	/// async fn test() {
+	///     let config = EmailerClientConfig {
+	///         sender_email: "aaron@stumpapp.dev".to_string(),
+	///         sender_display_name: "Aaron's Stump Instance".to_string(),
+	///         username: "aaron@stumpapp.dev".to_string(),
+	///         password: Some("decrypted_password".to_string()),
+	///         host: "smtp.stumpapp.dev".to_string(),
+	///         port: 587,
+	///         tls_enabled: true,
+	///         max_attachment_size_bytes: Some(10_000_000),
+	///         max_num_attachments: Some(5),
+	///     };
+	///     let template_dir = PathBuf::from("/templates");
+	///     let emailer = EmailerClient::new(config, template_dir);
+	///
+	///     let result = emailer.send_password_reset(
+	///         "oromei@stumpapp.dev",
+	///         PasswordResetPayload {
+	///             reset_url: "https://stump.example.com/auth/reset-password?token=abc".to_string(),
+	///             expires_at: "January 1, 2025 at 12:30 UTC".to_string(),
+	///         },
+	///     ).await;
+	///     assert!(result.is_err()); // This will fail because the SMTP server is not real
+	/// }
+	/// ```
+	pub async fn send_password_reset(
+		&self,
+		recipient: &str,
+		payload: PasswordResetPayload,
+	) -> EmailResult<()> {
+		let html = render_template(
+			EmailTemplate::PasswordReset,
+			&json!({
+				"title": "Stump Password Reset",
+				"server_name": self.config.sender_display_name,
+				"reset_url": payload.reset_url,
+				"expires_at": payload.expires_at,
+			}),
+			self.template_dir.clone(),
+		)?;
+
+		let email = self
+			.message_builder("Reset your Stump password", recipient)?
+			.header(header::ContentType::TEXT_HTML)
+			.body(html)?;
+
+		match self.transport()?.send(&email) {
+			Ok(res) => {
+				tracing::trace!(?res, "Password reset email was sent");
+				Ok(())
+			},
+			Err(e) => {
+				tracing::error!(error = ?e, "Failed to send password reset email");
+				Err(e.into())
+			},
+		}
+	}
+
+	/// Create a message builder from the configured sender to the given recipient
+	fn message_builder(
+		&self,
+		subject: &str,
+		recipient: &str,
+	) -> EmailResult<MessageBuilder> {
+		let from = self
+			.config
+			.sender_email
+			.parse()
+			.map_err(|e: AddressError| EmailError::InvalidEmail(e.to_string()))?;
+
+		let to = recipient
+			.parse()
+			.map_err(|e: AddressError| EmailError::InvalidEmail(e.to_string()))?;
+
+		Ok(Message::builder().from(from).to(to).subject(subject))
+	}
+
+	/// Create the SMTP transport for the configured server
+	fn transport(&self) -> EmailResult<SmtpTransport> {
 		let password = self
 			.config
 			.password
@@ -241,16 +410,7 @@ impl EmailerClient {
 				.build()
 		};
 
-		match transport.send(&email) {
-			Ok(res) => {
-				tracing::trace!(?res, "Email with attachments was sent");
-				Ok(())
-			},
-			Err(e) => {
-				tracing::error!(error = ?e, "Failed to send email with attachments");
-				Err(e.into())
-			},
-		}
+		Ok(transport)
 	}
 }
 
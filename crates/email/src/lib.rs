@@ -10,10 +10,14 @@ mod error;
 /// A module containing the template rendering functionality, via the `handlebars` crate
 mod template;
 
-pub use emailer::{AttachmentPayload, EmailerClient, EmailerClientConfig};
+pub use emailer::{
+	AttachmentPayload, EmailerClient, EmailerClientConfig, InvitationPayload,
+	PasswordResetPayload,
+};
 pub use error::{EmailError, EmailResult};
 pub use template::{
-	render_template, EmailTemplate, ATTACHMENT_TEMPLATE, BASE_TEMPLATE, TEMPLATES,
+	render_template, EmailTemplate, ATTACHMENT_TEMPLATE, BASE_TEMPLATE,
+	INVITATION_TEMPLATE, PASSWORD_RESET_TEMPLATE, TEMPLATES,
 };
 
 pub use lettre::message::header::ContentType as EmailContentType;
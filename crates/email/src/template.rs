@@ -5,21 +5,34 @@ use handlebars::Handlebars;
 
 pub static BASE_TEMPLATE: &str = include_str!("../templates/base.hbs");
 pub static ATTACHMENT_TEMPLATE: &str = include_str!("../templates/attachment.hbs");
+pub static INVITATION_TEMPLATE: &str = include_str!("../templates/invitation.hbs");
+pub static PASSWORD_RESET_TEMPLATE: &str =
+	include_str!("../templates/password_reset.hbs");
 
-pub static TEMPLATES: &[(&str, &str)] =
-	&[("base", BASE_TEMPLATE), ("attachment", ATTACHMENT_TEMPLATE)];
+pub static TEMPLATES: &[(&str, &str)] = &[
+	("base", BASE_TEMPLATE),
+	("attachment", ATTACHMENT_TEMPLATE),
+	("invitation", INVITATION_TEMPLATE),
+	("password_reset", PASSWORD_RESET_TEMPLATE),
+];
 
 // TODO: expose this enumeration to the public API somehow, so that users can define their own template overrides
 
 pub enum EmailTemplate {
 	/// A template for an email which includes attachment(s), e.g. a book on the server
 	Attachment,
+	/// A template for an email inviting someone to join the server
+	Invitation,
+	/// A template for an email with a link to reset a user's password
+	PasswordReset,
 }
 
 impl AsRef<str> for EmailTemplate {
 	fn as_ref(&self) -> &str {
 		match self {
 			Self::Attachment => "attachment",
+			Self::Invitation => "invitation",
+			Self::PasswordReset => "password_reset",
 		}
 	}
 }
@@ -92,4 +105,41 @@ mod tests {
 
 		assert!(rendered.contains("Stump Attachment"));
 	}
+
+	#[test]
+	fn render_default_template_invitation() {
+		let data = serde_json::json!({
+			"title": "Stump Invitation",
+			"server_name": "Aaron's Stump Instance",
+			"invitation_url": "https://stump.example.com/auth/invitation?token=abc",
+			"expires_at": "January 1, 2025",
+		});
+
+		let rendered =
+			render_template(EmailTemplate::Invitation, &data, PathBuf::new()).unwrap();
+
+		assert!(rendered.contains("Aaron&#x27;s Stump Instance"));
+		assert!(
+			rendered.contains("https://stump.example.com/auth/invitation?token&#x3D;abc")
+		);
+		assert!(rendered.contains("January 1, 2025"));
+	}
+
+	#[test]
+	fn render_default_template_password_reset() {
+		let data = serde_json::json!({
+			"title": "Stump Password Reset",
+			"server_name": "Aaron's Stump Instance",
+			"reset_url": "https://stump.example.com/auth/reset-password?token=abc",
+			"expires_at": "January 1, 2025 at 12:30 UTC",
+		});
+
+		let rendered =
+			render_template(EmailTemplate::PasswordReset, &data, PathBuf::new()).unwrap();
+
+		assert!(rendered.contains("Aaron&#x27;s Stump Instance"));
+		assert!(rendered
+			.contains("https://stump.example.com/auth/reset-password?token&#x3D;abc"));
+		assert!(rendered.contains("January 1, 2025 at 12:30 UTC"));
+	}
 }
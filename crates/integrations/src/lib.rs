@@ -1,7 +1,19 @@
 #![warn(clippy::dbg_macro)]
 
-mod google_books_client;
+mod metadata;
 mod notifier;
+#[cfg(test)]
+mod test_utils;
 
-pub use google_books_client::GoogleBooksClient;
-pub use notifier::{DiscordClient, Notifier, TelegramClient};
+pub use metadata::{
+	normalize_isbn, score_candidate, ComicVineClient, GoogleBooksClient,
+	MetadataCandidate, MetadataProvider, MetadataProviderError, MetadataProviderKind,
+	MetadataProviderResult, MetadataQuery, OpenLibraryClient, METADATA_USER_AGENT,
+};
+pub use notifier::{
+	render_webhook_template, sign_webhook_body, validate_webhook_template, Delivery,
+	DiscordClient, Notifier, NotifierError, NotifierEvent, NotifierResult, PushClient,
+	PushService, RetryPolicy, TelegramClient, WebhookClient, GOTIFY_PRIORITIES,
+	MAX_DELIVERY_ATTEMPTS, NTFY_PRIORITIES, WEBHOOK_EVENT_HEADER,
+	WEBHOOK_SIGNATURE_HEADER,
+};
@@ -0,0 +1,500 @@
+use std::{
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{
+	error::{MetadataProviderError, MetadataProviderResult},
+	parse_date_parts, rank_candidates, strip_html, MetadataCandidate, MetadataProvider,
+	MetadataProviderKind, MetadataQuery, METADATA_USER_AGENT,
+};
+
+const COMICVINE_BASE_URL: &str = "https://comicvine.gamespot.com/api";
+/// ComicVine allows roughly one request per second, and temporarily bans clients which
+/// make requests faster than that
+const DEFAULT_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+const SEARCH_LIMIT: &str = "10";
+const ISSUE_FIELDS: &str =
+	"id,name,issue_number,cover_date,description,volume,site_detail_url,image";
+const VOLUME_FIELDS: &str =
+	"id,name,start_year,publisher,description,site_detail_url,image,count_of_issues";
+
+/// The status code ComicVine returns for a successful request. Errors are reported in the
+/// body of a 200 response
+const STATUS_OK: i32 = 1;
+
+/// A client for the ComicVine API, which requires an API key
+pub struct ComicVineClient {
+	pub api_key: String,
+	pub client: reqwest::Client,
+	base_url: String,
+	request_interval: Duration,
+	next_request_at: Mutex<Option<Instant>>,
+}
+
+impl ComicVineClient {
+	pub fn new(api_key: String) -> MetadataProviderResult<Self> {
+		if api_key.trim().is_empty() {
+			return Err(MetadataProviderError::MissingApiKey(
+				MetadataProviderKind::ComicVine.to_string(),
+			));
+		}
+
+		Ok(Self {
+			api_key,
+			client: reqwest::Client::new(),
+			base_url: COMICVINE_BASE_URL.to_string(),
+			request_interval: DEFAULT_REQUEST_INTERVAL,
+			next_request_at: Mutex::new(None),
+		})
+	}
+
+	/// Send requests to a different base URL, e.g. a server replaying recorded responses
+	pub fn with_base_url(self, base_url: impl Into<String>) -> Self {
+		Self {
+			base_url: base_url.into(),
+			..self
+		}
+	}
+
+	/// Change the minimum time between two requests
+	pub fn with_request_interval(self, request_interval: Duration) -> Self {
+		Self {
+			request_interval,
+			..self
+		}
+	}
+
+	/// Wait until the next request is allowed, reserving the slot after it for whoever
+	/// calls next
+	async fn throttle(&self) {
+		let wait_until = {
+			let mut next_request_at = self
+				.next_request_at
+				.lock()
+				.unwrap_or_else(|poisoned| poisoned.into_inner());
+			let now = Instant::now();
+			let slot = next_request_at.map_or(now, |next| next.max(now));
+			*next_request_at = Some(slot + self.request_interval);
+			slot
+		};
+		tokio::time::sleep_until(wait_until.into()).await;
+	}
+
+	async fn get<T: DeserializeOwned>(
+		&self,
+		path: &str,
+		params: &[(&str, &str)],
+	) -> MetadataProviderResult<T> {
+		self.throttle().await;
+
+		let response = self
+			.client
+			.get(format!("{}{path}", self.base_url))
+			.header(reqwest::header::USER_AGENT, METADATA_USER_AGENT)
+			.query(&[("api_key", self.api_key.as_str()), ("format", "json")])
+			.query(params)
+			.send()
+			.await?;
+		if !response.status().is_success() {
+			return Err(MetadataProviderError::from_response(response).await);
+		}
+
+		let body = response.json::<ComicVineResponse<T>>().await?;
+		match body.results {
+			Some(results) if body.status_code == STATUS_OK => Ok(results),
+			_ => Err(MetadataProviderError::InvalidResponse(format!(
+				"ComicVine returned status {}: {}",
+				body.status_code, body.error
+			))),
+		}
+	}
+
+	async fn search<T: DeserializeOwned>(
+		&self,
+		resource: &str,
+		query: &str,
+		fields: &str,
+	) -> MetadataProviderResult<Vec<T>> {
+		self.get(
+			"/search/",
+			&[
+				("resources", resource),
+				("query", query),
+				("field_list", fields),
+				("limit", SEARCH_LIMIT),
+			],
+		)
+		.await
+	}
+
+	/// Fetch the credits of an issue, which are not included when listing issues
+	async fn get_issue_credits(&self, id: &str) -> MetadataProviderResult<IssueCredits> {
+		self.get(
+			&format!("/issue/4000-{id}/"),
+			&[(
+				"field_list",
+				"person_credits,character_credits,team_credits",
+			)],
+		)
+		.await
+	}
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for ComicVineClient {
+	fn kind(&self) -> MetadataProviderKind {
+		MetadataProviderKind::ComicVine
+	}
+
+	async fn search_books(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<MetadataCandidate>> {
+		let issues = match (query.comicvine_id, query.number) {
+			(Some(volume_id), Some(number)) => {
+				let filter = format!("volume:{volume_id},issue_number:{number}");
+				self.get::<Vec<Issue>>(
+					"/issues/",
+					&[("filter", &filter), ("field_list", ISSUE_FIELDS)],
+				)
+				.await?
+			},
+			_ => {
+				let search_term = match (query.series.as_deref(), query.number) {
+					(Some(series), Some(number)) => format!("{series} {number}"),
+					_ => match query.title.as_deref() {
+						Some(title) => title.to_string(),
+						None => return Ok(vec![]),
+					},
+				};
+				self.search::<Issue>("issue", &search_term, ISSUE_FIELDS)
+					.await?
+			},
+		};
+
+		let candidates = issues.into_iter().map(MetadataCandidate::from).collect();
+		let mut candidates = rank_candidates(query, candidates);
+
+		// Credits are only available from the issue itself, so only the best match is worth
+		// the extra request
+		if let Some(best) = candidates.first_mut() {
+			let id = best.provider_id.clone();
+			match self.get_issue_credits(&id).await {
+				Ok(credits) => credits.apply_to(best),
+				Err(error) => {
+					tracing::warn!(?error, %id, "Failed to fetch issue credits")
+				},
+			}
+		}
+
+		Ok(candidates)
+	}
+
+	async fn search_series(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<MetadataCandidate>> {
+		let volumes = if let Some(volume_id) = query.comicvine_id {
+			let volume = self
+				.get::<Volume>(
+					&format!("/volume/4050-{volume_id}/"),
+					&[("field_list", VOLUME_FIELDS)],
+				)
+				.await?;
+			vec![volume]
+		} else if let Some(title) = query.title.as_deref() {
+			self.search::<Volume>("volume", title, VOLUME_FIELDS)
+				.await?
+		} else {
+			return Ok(vec![]);
+		};
+
+		let candidates = volumes.into_iter().map(MetadataCandidate::from).collect();
+		Ok(rank_candidates(query, candidates))
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct ComicVineResponse<T> {
+	#[serde(default)]
+	error: String,
+	status_code: i32,
+	results: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Named {
+	id: Option<i32>,
+	name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Image {
+	original_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+	id: i64,
+	name: Option<String>,
+	issue_number: Option<String>,
+	cover_date: Option<String>,
+	description: Option<String>,
+	volume: Option<Named>,
+	site_detail_url: Option<String>,
+	image: Option<Image>,
+}
+
+impl From<Issue> for MetadataCandidate {
+	fn from(issue: Issue) -> Self {
+		let (year, month, day) = issue
+			.cover_date
+			.as_deref()
+			.map(parse_date_parts)
+			.unwrap_or_default();
+		let (series, series_comicvine_id) = issue
+			.volume
+			.map(|volume| (volume.name, volume.id))
+			.unwrap_or_default();
+		let number = issue
+			.issue_number
+			.as_deref()
+			.and_then(|number| number.trim().parse::<f64>().ok());
+		// Many issues have no name of their own, so fall back to the series and number
+		let title = issue
+			.name
+			.filter(|name| !name.trim().is_empty())
+			.or_else(|| {
+				series
+					.as_deref()
+					.map(|series| match issue.issue_number.as_deref() {
+						Some(number) => format!("{series} #{number}"),
+						None => series.to_string(),
+					})
+			});
+
+		MetadataCandidate {
+			title,
+			series,
+			number,
+			summary: issue.description.as_deref().map(strip_html),
+			year,
+			month,
+			day,
+			url: issue.site_detail_url,
+			cover_url: issue.image.and_then(|image| image.original_url),
+			series_comicvine_id,
+			..MetadataCandidate::new(
+				MetadataProviderKind::ComicVine,
+				issue.id.to_string(),
+			)
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct Volume {
+	id: i32,
+	name: Option<String>,
+	start_year: Option<String>,
+	publisher: Option<Named>,
+	description: Option<String>,
+	site_detail_url: Option<String>,
+	image: Option<Image>,
+}
+
+impl From<Volume> for MetadataCandidate {
+	fn from(volume: Volume) -> Self {
+		MetadataCandidate {
+			title: volume.name.clone(),
+			series: volume.name,
+			summary: volume.description.as_deref().map(strip_html),
+			publisher: volume.publisher.and_then(|publisher| publisher.name),
+			year: volume
+				.start_year
+				.and_then(|year| year.trim().parse::<i32>().ok()),
+			url: volume.site_detail_url,
+			cover_url: volume.image.and_then(|image| image.original_url),
+			series_comicvine_id: Some(volume.id),
+			..MetadataCandidate::new(
+				MetadataProviderKind::ComicVine,
+				volume.id.to_string(),
+			)
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct PersonCredit {
+	name: String,
+	#[serde(default)]
+	role: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct IssueCredits {
+	person_credits: Vec<PersonCredit>,
+	character_credits: Vec<Named>,
+	team_credits: Vec<Named>,
+}
+
+impl IssueCredits {
+	/// Sort each person into the roles they are credited with. A person can have several
+	/// roles, e.g. `writer, cover`
+	fn apply_to(self, candidate: &mut MetadataCandidate) {
+		for person in self.person_credits {
+			for role in person
+				.role
+				.split(',')
+				.map(|role| role.trim().to_lowercase())
+			{
+				let list = match role.as_str() {
+					"writer" | "plotter" | "scripter" => &mut candidate.writers,
+					"penciler" | "penciller" | "artist" => &mut candidate.pencillers,
+					"inker" => &mut candidate.inkers,
+					"colorist" | "colourist" => &mut candidate.colorists,
+					"letterer" => &mut candidate.letterers,
+					"cover" => &mut candidate.cover_artists,
+					"editor" => &mut candidate.editors,
+					_ => continue,
+				};
+				if !list.contains(&person.name) {
+					list.push(person.name.clone());
+				}
+			}
+		}
+
+		candidate.characters = self
+			.character_credits
+			.into_iter()
+			.filter_map(|character| character.name)
+			.collect();
+		candidate.teams = self
+			.team_credits
+			.into_iter()
+			.filter_map(|team| team.name)
+			.collect();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::TestServer;
+
+	const ISSUES_FIXTURE: &str = include_str!("fixtures/comicvine_issues.json");
+	const ISSUE_FIXTURE: &str = include_str!("fixtures/comicvine_issue.json");
+	const VOLUME_SEARCH_FIXTURE: &str =
+		include_str!("fixtures/comicvine_volume_search.json");
+	const INVALID_KEY_FIXTURE: &str = include_str!("fixtures/comicvine_invalid_key.json");
+
+	fn client(server: &TestServer) -> ComicVineClient {
+		ComicVineClient::new("secret".to_string())
+			.unwrap()
+			.with_base_url(server.url.clone())
+			.with_request_interval(Duration::ZERO)
+	}
+
+	#[test]
+	fn test_requires_api_key() {
+		assert!(matches!(
+			ComicVineClient::new(String::new()),
+			Err(MetadataProviderError::MissingApiKey(_))
+		));
+	}
+
+	#[tokio::test]
+	async fn test_search_issue_in_volume() {
+		let server = TestServer::start_with_fixtures(vec![
+			("/issues/", ISSUES_FIXTURE),
+			("/issue/4000-278472/", ISSUE_FIXTURE),
+		])
+		.await;
+
+		let query = MetadataQuery {
+			series: Some("Batman".to_string()),
+			number: Some(1.0),
+			year: Some(2011),
+			comicvine_id: Some(42721),
+			..Default::default()
+		};
+		let candidates = client(&server).search_books(&query).await.unwrap();
+
+		let requests = server.requests();
+		assert_eq!(requests.len(), 2);
+		assert!(requests[0].path.contains("api_key=secret"));
+		assert!(requests[0]
+			.path
+			.contains("filter=volume%3A42721%2Cissue_number%3A1"));
+		assert_eq!(requests[0].header("user-agent"), Some(METADATA_USER_AGENT));
+
+		assert_eq!(candidates.len(), 1);
+		let issue = &candidates[0];
+		assert_eq!(issue.provider, MetadataProviderKind::ComicVine);
+		assert_eq!(issue.provider_id, "278472");
+		assert_eq!(issue.title.as_deref(), Some("Knightfall"));
+		assert_eq!(issue.series.as_deref(), Some("Batman"));
+		assert_eq!(issue.series_comicvine_id, Some(42721));
+		assert_eq!(issue.number, Some(1.0));
+		assert_eq!((issue.year, issue.month), (Some(2011), Some(11)));
+		assert_eq!(
+			issue.summary.as_deref(),
+			Some("Bruce Wayne returns to Gotham.\nA new villain appears.")
+		);
+		assert_eq!(issue.writers, vec!["Scott Snyder".to_string()]);
+		assert_eq!(issue.pencillers, vec!["Greg Capullo".to_string()],);
+		assert_eq!(issue.cover_artists, vec!["Greg Capullo".to_string()]);
+		assert_eq!(
+			issue.characters,
+			vec!["Batman".to_string(), "Alfred Pennyworth".to_string()]
+		);
+		assert_eq!(issue.teams, vec!["Court of Owls".to_string()]);
+		// The volume and year match, but the title cannot be compared
+		assert_eq!(issue.confidence, 1.0);
+	}
+
+	#[tokio::test]
+	async fn test_search_series() {
+		let server =
+			TestServer::start_with_fixtures(vec![("/search/", VOLUME_SEARCH_FIXTURE)])
+				.await;
+
+		let query = MetadataQuery {
+			title: Some("Saga".to_string()),
+			year: Some(2012),
+			..Default::default()
+		};
+		let candidates = client(&server).search_series(&query).await.unwrap();
+
+		let request = &server.requests()[0];
+		assert!(request.path.contains("resources=volume"));
+		assert!(request.path.contains("query=Saga"));
+
+		assert_eq!(candidates.len(), 2);
+		let best = &candidates[0];
+		assert_eq!(best.provider_id, "49901");
+		assert_eq!(best.title.as_deref(), Some("Saga"));
+		assert_eq!(best.publisher.as_deref(), Some("Image"));
+		assert_eq!(best.series_comicvine_id, Some(49901));
+		assert_eq!(best.confidence, 1.0);
+		assert!(candidates[1].confidence < best.confidence);
+	}
+
+	#[tokio::test]
+	async fn test_error_status() {
+		let server =
+			TestServer::start_with_fixtures(vec![("/search/", INVALID_KEY_FIXTURE)])
+				.await;
+
+		let query = MetadataQuery {
+			title: Some("Saga".to_string()),
+			..Default::default()
+		};
+		let error = client(&server).search_series(&query).await.unwrap_err();
+		assert!(matches!(error, MetadataProviderError::InvalidResponse(_)));
+	}
+}
@@ -0,0 +1,28 @@
+pub type MetadataProviderResult<T> = Result<T, MetadataProviderError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetadataProviderError {
+	#[error("Request failed with error: {0}")]
+	ReqwestError(#[from] reqwest::Error),
+	#[error("Request was unsuccessful ({status}): {message}")]
+	RequestFailed { status: u16, message: String },
+	#[error("Received an invalid response: {0}")]
+	InvalidResponse(String),
+	#[error("An API key is required for {0}")]
+	MissingApiKey(String),
+}
+
+impl MetadataProviderError {
+	/// Build an error from an unsuccessful response, keeping (a prefix of) its body
+	pub(crate) async fn from_response(response: reqwest::Response) -> Self {
+		let status = response.status().as_u16();
+		let message = response
+			.text()
+			.await
+			.unwrap_or_default()
+			.chars()
+			.take(256)
+			.collect();
+		Self::RequestFailed { status, message }
+	}
+}
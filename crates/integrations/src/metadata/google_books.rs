@@ -0,0 +1,251 @@
+use serde::Deserialize;
+
+use super::{
+	error::{MetadataProviderError, MetadataProviderResult},
+	parse_date_parts, rank_candidates, strip_html, MetadataCandidate, MetadataProvider,
+	MetadataProviderKind, MetadataQuery, METADATA_USER_AGENT,
+};
+
+const GOOGLE_BOOKS_BASE_URL: &str = "https://www.googleapis.com/books/v1";
+const MAX_RESULTS: &str = "10";
+
+/// A client for the Google Books API. An API key is optional, but requests without one
+/// share a much smaller quota
+pub struct GoogleBooksClient {
+	pub api_key: Option<String>,
+	pub client: reqwest::Client,
+	base_url: String,
+}
+
+impl GoogleBooksClient {
+	pub fn new(api_key: Option<String>) -> Self {
+		Self {
+			api_key,
+			client: reqwest::Client::new(),
+			base_url: GOOGLE_BOOKS_BASE_URL.to_string(),
+		}
+	}
+
+	/// Send requests to a different base URL, e.g. a server replaying recorded responses
+	pub fn with_base_url(self, base_url: impl Into<String>) -> Self {
+		Self {
+			base_url: base_url.into(),
+			..self
+		}
+	}
+
+	async fn search_volumes(&self, q: &str) -> MetadataProviderResult<Vec<Volume>> {
+		let mut params = vec![("q", q), ("maxResults", MAX_RESULTS)];
+		if let Some(api_key) = self.api_key.as_deref() {
+			params.push(("key", api_key));
+		}
+
+		let response = self
+			.client
+			.get(format!("{}/volumes", self.base_url))
+			.header(reqwest::header::USER_AGENT, METADATA_USER_AGENT)
+			.query(&params)
+			.send()
+			.await?;
+		if !response.status().is_success() {
+			return Err(MetadataProviderError::from_response(response).await);
+		}
+
+		let body = response.json::<VolumesResponse>().await?;
+		Ok(body.items)
+	}
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for GoogleBooksClient {
+	fn kind(&self) -> MetadataProviderKind {
+		MetadataProviderKind::GoogleBooks
+	}
+
+	async fn search_books(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<MetadataCandidate>> {
+		let q = if let Some(isbn) = query.normalized_isbn() {
+			format!("isbn:{isbn}")
+		} else if let Some(title) = query.title.as_deref() {
+			let mut q = format!("intitle:{title}");
+			if let Some(writer) = query.writers.first() {
+				q.push_str(&format!(" inauthor:{writer}"));
+			}
+			q
+		} else {
+			return Ok(vec![]);
+		};
+
+		let volumes = self.search_volumes(&q).await?;
+		let candidates = volumes.into_iter().map(MetadataCandidate::from).collect();
+		Ok(rank_candidates(query, candidates))
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct VolumesResponse {
+	#[serde(default)]
+	items: Vec<Volume>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Volume {
+	id: String,
+	volume_info: VolumeInfo,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct VolumeInfo {
+	title: Option<String>,
+	subtitle: Option<String>,
+	authors: Vec<String>,
+	publisher: Option<String>,
+	published_date: Option<String>,
+	description: Option<String>,
+	industry_identifiers: Vec<IndustryIdentifier>,
+	page_count: Option<i32>,
+	categories: Vec<String>,
+	image_links: Option<ImageLinks>,
+	language: Option<String>,
+	canonical_volume_link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndustryIdentifier {
+	#[serde(rename = "type")]
+	kind: String,
+	identifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageLinks {
+	thumbnail: Option<String>,
+}
+
+impl From<Volume> for MetadataCandidate {
+	fn from(volume: Volume) -> Self {
+		let info = volume.volume_info;
+		let (year, month, day) = info
+			.published_date
+			.as_deref()
+			.map(parse_date_parts)
+			.unwrap_or_default();
+		// Prefer the 13 digit ISBN, which is what most books embed in their metadata
+		let isbn = info
+			.industry_identifiers
+			.iter()
+			.find(|id| id.kind == "ISBN_13")
+			.or_else(|| {
+				info.industry_identifiers
+					.iter()
+					.find(|id| id.kind == "ISBN_10")
+			})
+			.map(|id| id.identifier.clone());
+		let title = match (info.title, info.subtitle) {
+			(Some(title), Some(subtitle)) => Some(format!("{title}: {subtitle}")),
+			(title, _) => title,
+		};
+
+		MetadataCandidate {
+			title,
+			summary: info.description.as_deref().map(strip_html),
+			publisher: info.publisher,
+			year,
+			month,
+			day,
+			language: info.language,
+			page_count: info.page_count,
+			isbn,
+			writers: info.authors,
+			genres: info.categories,
+			url: info.canonical_volume_link,
+			cover_url: info.image_links.and_then(|links| links.thumbnail),
+			..MetadataCandidate::new(MetadataProviderKind::GoogleBooks, volume.id)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::TestServer;
+
+	const VOLUMES_FIXTURE: &str = include_str!("fixtures/google_books_volumes.json");
+
+	#[tokio::test]
+	async fn test_search_by_isbn() {
+		let server =
+			TestServer::start_with_fixtures(vec![("/volumes", VOLUMES_FIXTURE)]).await;
+		let client = GoogleBooksClient::new(Some("secret".to_string()))
+			.with_base_url(server.url.clone());
+
+		let query = MetadataQuery {
+			isbn: Some("978-0-441-17271-9".to_string()),
+			..Default::default()
+		};
+		let candidates = client.search_books(&query).await.unwrap();
+
+		let requests = server.requests();
+		assert_eq!(requests.len(), 1);
+		assert!(requests[0].path.contains("q=isbn%3A9780441172719"));
+		assert!(requests[0].path.contains("key=secret"));
+
+		assert_eq!(candidates.len(), 2);
+		let best = &candidates[0];
+		assert_eq!(best.provider, MetadataProviderKind::GoogleBooks);
+		assert_eq!(best.provider_id, "B1hSG45JCX4C");
+		assert_eq!(best.confidence, 1.0);
+		assert_eq!(best.title.as_deref(), Some("Dune"));
+		assert_eq!(best.writers, vec!["Frank Herbert".to_string()]);
+		assert_eq!(best.year, Some(1990));
+		assert_eq!(best.month, Some(9));
+		assert_eq!(best.page_count, Some(535));
+		assert_eq!(best.isbn.as_deref(), Some("9780441172719"));
+		assert_eq!(
+			best.summary.as_deref(),
+			Some("Set on the desert planet Arrakis.")
+		);
+	}
+
+	#[tokio::test]
+	async fn test_search_by_title() {
+		let server =
+			TestServer::start_with_fixtures(vec![("/volumes", VOLUMES_FIXTURE)]).await;
+		let client = GoogleBooksClient::new(None).with_base_url(server.url.clone());
+
+		let query = MetadataQuery {
+			title: Some("Dune Messiah".to_string()),
+			writers: vec!["Frank Herbert".to_string()],
+			..Default::default()
+		};
+		let candidates = client.search_books(&query).await.unwrap();
+
+		let request = &server.requests()[0];
+		assert!(request
+			.path
+			.contains("intitle%3ADune+Messiah+inauthor%3AFrank+Herbert"));
+		assert!(!request.path.contains("key="));
+		assert_eq!(candidates[0].title.as_deref(), Some("Dune Messiah"));
+		assert!(candidates[0].confidence > candidates[1].confidence);
+	}
+
+	#[tokio::test]
+	async fn test_unsuccessful_response() {
+		let server = TestServer::start(vec![403]).await;
+		let client = GoogleBooksClient::new(None).with_base_url(server.url.clone());
+
+		let query = MetadataQuery {
+			title: Some("Dune".to_string()),
+			..Default::default()
+		};
+		let error = client.search_books(&query).await.unwrap_err();
+		assert!(matches!(
+			error,
+			MetadataProviderError::RequestFailed { status: 403, .. }
+		));
+	}
+}
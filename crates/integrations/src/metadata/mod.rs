@@ -0,0 +1,326 @@
+mod comicvine;
+mod error;
+mod google_books;
+mod open_library;
+mod score;
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+pub use comicvine::ComicVineClient;
+pub use error::{MetadataProviderError, MetadataProviderResult};
+pub use google_books::GoogleBooksClient;
+pub use open_library::OpenLibraryClient;
+pub use score::score_candidate;
+
+/// The user agent sent to metadata providers. ComicVine rejects requests without one
+pub const METADATA_USER_AGENT: &str = "Stump (https://stumpapp.dev)";
+
+/// The supported metadata providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MetadataProviderKind {
+	GoogleBooks,
+	OpenLibrary,
+	ComicVine,
+}
+
+impl MetadataProviderKind {
+	/// Whether the provider cannot be used without an API key
+	pub fn requires_api_key(&self) -> bool {
+		matches!(self, Self::ComicVine)
+	}
+}
+
+impl fmt::Display for MetadataProviderKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::GoogleBooks => write!(f, "GOOGLE_BOOKS"),
+			Self::OpenLibrary => write!(f, "OPEN_LIBRARY"),
+			Self::ComicVine => write!(f, "COMIC_VINE"),
+		}
+	}
+}
+
+impl FromStr for MetadataProviderKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_uppercase().as_str() {
+			"GOOGLE_BOOKS" => Ok(Self::GoogleBooks),
+			"OPEN_LIBRARY" => Ok(Self::OpenLibrary),
+			"COMIC_VINE" => Ok(Self::ComicVine),
+			_ => Err(format!("Unknown metadata provider: {s}")),
+		}
+	}
+}
+
+/// What is known about a book (or series) before asking a provider about it. Providers
+/// use whichever fields they support, preferring exact identifiers over a title search
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataQuery {
+	pub isbn: Option<String>,
+	pub title: Option<String>,
+	pub series: Option<String>,
+	pub number: Option<f64>,
+	pub year: Option<i32>,
+	pub writers: Vec<String>,
+	/// The ComicVine volume ID of the series, if it is already known
+	pub comicvine_id: Option<i32>,
+}
+
+impl MetadataQuery {
+	/// The ISBN of the query with any separators removed
+	pub fn normalized_isbn(&self) -> Option<String> {
+		self.isbn
+			.as_deref()
+			.map(normalize_isbn)
+			.filter(|isbn| !isbn.is_empty())
+	}
+
+	/// Whether the query has enough information to search with
+	pub fn is_searchable(&self) -> bool {
+		self.normalized_isbn().is_some()
+			|| self.comicvine_id.is_some()
+			|| self.title.as_deref().is_some_and(|t| !t.trim().is_empty())
+	}
+}
+
+/// Remove everything but digits and the `X` check digit from an ISBN
+pub fn normalize_isbn(isbn: &str) -> String {
+	isbn.chars()
+		.filter(|c| c.is_ascii_digit() || *c == 'X' || *c == 'x')
+		.map(|c| c.to_ascii_uppercase())
+		.collect()
+}
+
+/// A possible match for a book or series returned by a provider, normalized to the fields
+/// Stump stores. The confidence is a score between 0 and 1 of how well the candidate
+/// matches the query it was found with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataCandidate {
+	pub provider: MetadataProviderKind,
+	pub provider_id: String,
+	pub confidence: f64,
+
+	pub title: Option<String>,
+	pub series: Option<String>,
+	pub number: Option<f64>,
+	pub volume: Option<i32>,
+	pub summary: Option<String>,
+	pub publisher: Option<String>,
+	pub year: Option<i32>,
+	pub month: Option<i32>,
+	pub day: Option<i32>,
+	pub language: Option<String>,
+	pub page_count: Option<i32>,
+	pub isbn: Option<String>,
+
+	#[serde(default)]
+	pub writers: Vec<String>,
+	#[serde(default)]
+	pub pencillers: Vec<String>,
+	#[serde(default)]
+	pub inkers: Vec<String>,
+	#[serde(default)]
+	pub colorists: Vec<String>,
+	#[serde(default)]
+	pub letterers: Vec<String>,
+	#[serde(default)]
+	pub cover_artists: Vec<String>,
+	#[serde(default)]
+	pub editors: Vec<String>,
+	#[serde(default)]
+	pub genres: Vec<String>,
+	#[serde(default)]
+	pub characters: Vec<String>,
+	#[serde(default)]
+	pub teams: Vec<String>,
+
+	/// A link to the candidate on the provider's site
+	pub url: Option<String>,
+	pub cover_url: Option<String>,
+	/// The ComicVine volume ID of the series the candidate belongs to
+	pub series_comicvine_id: Option<i32>,
+}
+
+impl MetadataCandidate {
+	/// Create an empty candidate for the given provider, to be filled in with the fields
+	/// the provider returned
+	pub fn new(provider: MetadataProviderKind, provider_id: impl Into<String>) -> Self {
+		Self {
+			provider,
+			provider_id: provider_id.into(),
+			confidence: 0.0,
+			title: None,
+			series: None,
+			number: None,
+			volume: None,
+			summary: None,
+			publisher: None,
+			year: None,
+			month: None,
+			day: None,
+			language: None,
+			page_count: None,
+			isbn: None,
+			writers: vec![],
+			pencillers: vec![],
+			inkers: vec![],
+			colorists: vec![],
+			letterers: vec![],
+			cover_artists: vec![],
+			editors: vec![],
+			genres: vec![],
+			characters: vec![],
+			teams: vec![],
+			url: None,
+			cover_url: None,
+			series_comicvine_id: None,
+		}
+	}
+}
+
+/// A source of book and series metadata. Implementations return candidates scored against
+/// the query with [`score_candidate`], ordered from the most to the least confident
+#[async_trait::async_trait]
+pub trait MetadataProvider: Send + Sync {
+	fn kind(&self) -> MetadataProviderKind;
+
+	/// Search for books matching the query
+	async fn search_books(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<MetadataCandidate>>;
+
+	/// Search for series matching the query. Providers which have no concept of a series
+	/// return no candidates
+	async fn search_series(
+		&self,
+		_query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<MetadataCandidate>> {
+		Ok(vec![])
+	}
+}
+
+/// Score each candidate against the query, then order them from the most to the least
+/// confident
+pub(crate) fn rank_candidates(
+	query: &MetadataQuery,
+	candidates: Vec<MetadataCandidate>,
+) -> Vec<MetadataCandidate> {
+	let mut candidates = candidates
+		.into_iter()
+		.map(|candidate| {
+			let confidence = score_candidate(query, &candidate);
+			MetadataCandidate {
+				confidence,
+				..candidate
+			}
+		})
+		.collect::<Vec<_>>();
+	candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+	candidates
+}
+
+/// Parse the year, month and day out of a date like `2011`, `2011-05` or `2011-05-03`
+pub(crate) fn parse_date_parts(date: &str) -> (Option<i32>, Option<i32>, Option<i32>) {
+	let mut parts = date
+		.trim()
+		.split('-')
+		.map(|part| part.trim().parse::<i32>().ok());
+	let year = parts.next().flatten();
+	let month = parts.next().flatten().filter(|m| (1..=12).contains(m));
+	let day = parts.next().flatten().filter(|d| (1..=31).contains(d));
+	(year, month, day)
+}
+
+/// Convert the HTML descriptions some providers return into plain text
+pub(crate) fn strip_html(html: &str) -> String {
+	let mut text = String::with_capacity(html.len());
+	let mut in_tag = false;
+	let mut tag = String::new();
+
+	for c in html.chars() {
+		match c {
+			'<' => {
+				in_tag = true;
+				tag.clear();
+			},
+			'>' if in_tag => {
+				in_tag = false;
+				let name = tag
+					.trim_start_matches('/')
+					.split_whitespace()
+					.next()
+					.unwrap_or_default()
+					.to_lowercase();
+				if matches!(
+					name.as_str(),
+					"p" | "br" | "br/" | "div" | "li" | "h1" | "h2" | "h3" | "h4"
+				) && !text.ends_with('\n')
+					&& !text.is_empty()
+				{
+					text.push('\n');
+				}
+			},
+			_ if in_tag => tag.push(c),
+			_ => text.push(c),
+		}
+	}
+
+	text.replace("&amp;", "&")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&apos;", "'")
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&nbsp;", " ")
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty())
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_normalize_isbn() {
+		assert_eq!(normalize_isbn("978-0-306-40615-7"), "9780306406157");
+		assert_eq!(normalize_isbn("0-8044-2957-x"), "080442957X");
+	}
+
+	#[test]
+	fn test_parse_date_parts() {
+		assert_eq!(parse_date_parts("2011"), (Some(2011), None, None));
+		assert_eq!(
+			parse_date_parts("2011-05-03"),
+			(Some(2011), Some(5), Some(3))
+		);
+		assert_eq!(parse_date_parts("2011-13"), (Some(2011), None, None));
+	}
+
+	#[test]
+	fn test_strip_html() {
+		let html = "<p>The <em>first</em> issue &amp; more.</p><p>Second paragraph</p>";
+		assert_eq!(
+			strip_html(html),
+			"The first issue & more.\nSecond paragraph"
+		);
+	}
+
+	#[test]
+	fn test_provider_kind_round_trip() {
+		for kind in [
+			MetadataProviderKind::GoogleBooks,
+			MetadataProviderKind::OpenLibrary,
+			MetadataProviderKind::ComicVine,
+		] {
+			assert_eq!(kind.to_string().parse::<MetadataProviderKind>(), Ok(kind));
+		}
+	}
+}
@@ -0,0 +1,258 @@
+use serde::Deserialize;
+
+use super::{
+	error::{MetadataProviderError, MetadataProviderResult},
+	normalize_isbn, rank_candidates, MetadataCandidate, MetadataProvider,
+	MetadataProviderKind, MetadataQuery, METADATA_USER_AGENT,
+};
+
+const OPEN_LIBRARY_BASE_URL: &str = "https://openlibrary.org";
+const OPEN_LIBRARY_COVERS_URL: &str = "https://covers.openlibrary.org/b/id";
+const SEARCH_FIELDS: &str = "key,title,subtitle,author_name,first_publish_year,isbn,publisher,number_of_pages_median,cover_i,language,subject";
+const SEARCH_LIMIT: &str = "10";
+
+/// A client for the Open Library search API, which does not require an API key
+pub struct OpenLibraryClient {
+	pub client: reqwest::Client,
+	base_url: String,
+}
+
+impl Default for OpenLibraryClient {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl OpenLibraryClient {
+	pub fn new() -> Self {
+		Self {
+			client: reqwest::Client::new(),
+			base_url: OPEN_LIBRARY_BASE_URL.to_string(),
+		}
+	}
+
+	/// Send requests to a different base URL, e.g. a server replaying recorded responses
+	pub fn with_base_url(self, base_url: impl Into<String>) -> Self {
+		Self {
+			base_url: base_url.into(),
+			..self
+		}
+	}
+
+	async fn get<T: serde::de::DeserializeOwned>(
+		&self,
+		path: &str,
+		params: &[(&str, &str)],
+	) -> MetadataProviderResult<T> {
+		let response = self
+			.client
+			.get(format!("{}{path}", self.base_url))
+			.header(reqwest::header::USER_AGENT, METADATA_USER_AGENT)
+			.query(params)
+			.send()
+			.await?;
+		if !response.status().is_success() {
+			return Err(MetadataProviderError::from_response(response).await);
+		}
+		Ok(response.json::<T>().await?)
+	}
+
+	/// Fetch the description of a work, which the search API does not include
+	async fn get_work_description(
+		&self,
+		key: &str,
+	) -> MetadataProviderResult<Option<String>> {
+		let work = self.get::<Work>(&format!("{key}.json"), &[]).await?;
+		Ok(work.description.map(|description| match description {
+			WorkDescription::Text(text) => text,
+			WorkDescription::Typed { value } => value,
+		}))
+	}
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for OpenLibraryClient {
+	fn kind(&self) -> MetadataProviderKind {
+		MetadataProviderKind::OpenLibrary
+	}
+
+	async fn search_books(
+		&self,
+		query: &MetadataQuery,
+	) -> MetadataProviderResult<Vec<MetadataCandidate>> {
+		let isbn = query.normalized_isbn();
+		let mut params = vec![("fields", SEARCH_FIELDS), ("limit", SEARCH_LIMIT)];
+		if let Some(isbn) = isbn.as_deref() {
+			params.push(("isbn", isbn));
+		} else if let Some(title) = query.title.as_deref() {
+			params.push(("title", title));
+			if let Some(writer) = query.writers.first() {
+				params.push(("author", writer));
+			}
+		} else {
+			return Ok(vec![]);
+		}
+
+		let response = self.get::<SearchResponse>("/search.json", &params).await?;
+		let candidates = response
+			.docs
+			.into_iter()
+			.map(|doc| doc.into_candidate(isbn.as_deref()))
+			.collect();
+		let mut candidates = rank_candidates(query, candidates);
+
+		// Descriptions are only available from the work itself, so only the best match is
+		// worth the extra request
+		if let Some(best) = candidates.first_mut() {
+			let key = best.provider_id.clone();
+			match self.get_work_description(&key).await {
+				Ok(summary) => best.summary = summary,
+				Err(error) => tracing::warn!(?error, %key, "Failed to fetch work"),
+			}
+		}
+
+		Ok(candidates)
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+	#[serde(default)]
+	docs: Vec<SearchDoc>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SearchDoc {
+	key: String,
+	title: Option<String>,
+	subtitle: Option<String>,
+	author_name: Vec<String>,
+	first_publish_year: Option<i32>,
+	isbn: Vec<String>,
+	publisher: Vec<String>,
+	number_of_pages_median: Option<i32>,
+	cover_i: Option<i64>,
+	language: Vec<String>,
+	subject: Vec<String>,
+}
+
+impl SearchDoc {
+	/// Convert the document into a candidate. A work lists the ISBNs of all its editions,
+	/// so the ISBN which was searched for is kept when present
+	fn into_candidate(self, isbn: Option<&str>) -> MetadataCandidate {
+		let isbn = isbn
+			.and_then(|expected| {
+				self.isbn
+					.iter()
+					.find(|candidate| normalize_isbn(candidate) == expected)
+			})
+			.or_else(|| self.isbn.iter().find(|candidate| candidate.len() == 13))
+			.or(self.isbn.first())
+			.cloned();
+		let title = match (self.title, self.subtitle) {
+			(Some(title), Some(subtitle)) => Some(format!("{title}: {subtitle}")),
+			(title, _) => title,
+		};
+
+		MetadataCandidate {
+			title,
+			publisher: self.publisher.into_iter().next(),
+			year: self.first_publish_year,
+			language: self.language.into_iter().next(),
+			page_count: self.number_of_pages_median,
+			isbn,
+			writers: self.author_name,
+			genres: self.subject.into_iter().take(10).collect(),
+			url: Some(format!("{OPEN_LIBRARY_BASE_URL}{}", self.key)),
+			cover_url: self
+				.cover_i
+				.map(|id| format!("{OPEN_LIBRARY_COVERS_URL}/{id}-L.jpg")),
+			..MetadataCandidate::new(MetadataProviderKind::OpenLibrary, self.key)
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct Work {
+	description: Option<WorkDescription>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum WorkDescription {
+	Text(String),
+	Typed { value: String },
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::TestServer;
+
+	const SEARCH_FIXTURE: &str = include_str!("fixtures/open_library_search.json");
+	const WORK_FIXTURE: &str = include_str!("fixtures/open_library_work.json");
+
+	async fn fixture_server() -> TestServer {
+		TestServer::start_with_fixtures(vec![
+			("/search.json", SEARCH_FIXTURE),
+			("/works/OL893415W.json", WORK_FIXTURE),
+		])
+		.await
+	}
+
+	#[tokio::test]
+	async fn test_search_by_isbn() {
+		let server = fixture_server().await;
+		let client = OpenLibraryClient::new().with_base_url(server.url.clone());
+
+		let query = MetadataQuery {
+			isbn: Some("0-441-17271-7".to_string()),
+			..Default::default()
+		};
+		let candidates = client.search_books(&query).await.unwrap();
+
+		let requests = server.requests();
+		assert_eq!(requests.len(), 2);
+		assert!(requests[0].path.contains("isbn=0441172717"));
+		assert_eq!(requests[1].path, "/works/OL893415W.json");
+
+		let best = &candidates[0];
+		assert_eq!(best.provider, MetadataProviderKind::OpenLibrary);
+		assert_eq!(best.provider_id, "/works/OL893415W");
+		assert_eq!(best.confidence, 1.0);
+		assert_eq!(best.isbn.as_deref(), Some("0441172717"));
+		assert_eq!(best.writers, vec!["Frank Herbert".to_string()]);
+		assert_eq!(best.year, Some(1965));
+		assert_eq!(
+			best.cover_url.as_deref(),
+			Some("https://covers.openlibrary.org/b/id/11481354-L.jpg")
+		);
+		assert_eq!(
+			best.summary.as_deref(),
+			Some("Set on the desert planet Arrakis, Dune is the story of Paul Atreides.")
+		);
+	}
+
+	#[tokio::test]
+	async fn test_search_by_title() {
+		let server = fixture_server().await;
+		let client = OpenLibraryClient::new().with_base_url(server.url.clone());
+
+		let query = MetadataQuery {
+			title: Some("Dune".to_string()),
+			year: Some(1965),
+			writers: vec!["Frank Herbert".to_string()],
+			..Default::default()
+		};
+		let candidates = client.search_books(&query).await.unwrap();
+
+		let request = &server.requests()[0];
+		assert!(request.path.contains("title=Dune"));
+		assert!(request.path.contains("author=Frank+Herbert"));
+		assert_eq!(candidates.len(), 2);
+		assert_eq!(candidates[0].title.as_deref(), Some("Dune"));
+		assert_eq!(candidates[0].confidence, 1.0);
+		assert!(candidates[1].confidence < 1.0);
+	}
+}
@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use super::{normalize_isbn, MetadataCandidate, MetadataQuery};
+
+const TITLE_WEIGHT: f64 = 0.6;
+const YEAR_WEIGHT: f64 = 0.2;
+const WRITER_WEIGHT: f64 = 0.2;
+
+/// Score how well a candidate matches a query, from 0 (nothing in common) to 1 (certain).
+///
+/// A matching ISBN or ComicVine ID is treated as certain. Otherwise the score is a weighted
+/// combination of the similarity of the titles, how close the years are and how many of
+/// the writers are shared. Only what the query knows about is considered, so a query with
+/// just a title is scored on the title alone
+pub fn score_candidate(query: &MetadataQuery, candidate: &MetadataCandidate) -> f64 {
+	let isbn_matches = query
+		.normalized_isbn()
+		.zip(candidate.isbn.as_deref().map(normalize_isbn))
+		.is_some_and(|(expected, actual)| expected == actual);
+	let comicvine_id_matches = query
+		.comicvine_id
+		.zip(candidate.series_comicvine_id)
+		.is_some_and(|(expected, actual)| expected == actual);
+	if isbn_matches || (comicvine_id_matches && query.number.is_none()) {
+		return 1.0;
+	}
+
+	let mut score = 0.0;
+	let mut total_weight = 0.0;
+
+	if let Some(title) = query.title.as_deref() {
+		total_weight += TITLE_WEIGHT;
+		let similarity = candidate
+			.title
+			.as_deref()
+			.map(|candidate_title| title_similarity(title, candidate_title))
+			.unwrap_or_default();
+		score += TITLE_WEIGHT * similarity;
+	}
+
+	if let Some(year) = query.year {
+		total_weight += YEAR_WEIGHT;
+		score += match candidate
+			.year
+			.map(|candidate_year| (candidate_year - year).abs())
+		{
+			Some(0) => YEAR_WEIGHT,
+			Some(1) => YEAR_WEIGHT / 2.0,
+			_ => 0.0,
+		};
+	}
+
+	if !query.writers.is_empty() {
+		total_weight += WRITER_WEIGHT;
+		score += WRITER_WEIGHT * name_overlap(&query.writers, &candidate.writers);
+	}
+
+	if total_weight == 0.0 {
+		return 0.0;
+	}
+
+	let mut confidence = score / total_weight;
+	// A candidate from the right series but possibly the wrong issue is a better bet than
+	// an unrelated one with a similar title
+	if comicvine_id_matches {
+		confidence = (confidence + 1.0) / 2.0;
+	}
+
+	(confidence * 100.0).round() / 100.0
+}
+
+fn tokens(value: &str) -> HashSet<String> {
+	value
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|token| !token.is_empty())
+		.map(str::to_lowercase)
+		.collect()
+}
+
+/// The Jaccard similarity of the words in two titles
+fn title_similarity(a: &str, b: &str) -> f64 {
+	let (a, b) = (tokens(a), tokens(b));
+	let union = a.union(&b).count();
+	if union == 0 {
+		return 0.0;
+	}
+	a.intersection(&b).count() as f64 / union as f64
+}
+
+/// The fraction of the expected names which appear in the actual names, ignoring case and
+/// the order of the words in each name
+fn name_overlap(expected: &[String], actual: &[String]) -> f64 {
+	let normalize = |name: &String| {
+		let mut words = tokens(name).into_iter().collect::<Vec<_>>();
+		words.sort();
+		words.join(" ")
+	};
+	let actual = actual.iter().map(normalize).collect::<HashSet<_>>();
+	let matches = expected
+		.iter()
+		.filter(|name| actual.contains(&normalize(name)))
+		.count();
+	matches as f64 / expected.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::metadata::MetadataProviderKind;
+
+	fn candidate(title: &str, year: Option<i32>, writers: &[&str]) -> MetadataCandidate {
+		MetadataCandidate {
+			title: Some(title.to_string()),
+			year,
+			writers: writers.iter().map(|w| w.to_string()).collect(),
+			..MetadataCandidate::new(MetadataProviderKind::OpenLibrary, "1")
+		}
+	}
+
+	#[test]
+	fn test_isbn_match_is_certain() {
+		let query = MetadataQuery {
+			isbn: Some("978-0-441-17271-9".to_string()),
+			title: Some("Something else entirely".to_string()),
+			..Default::default()
+		};
+		let candidate = MetadataCandidate {
+			isbn: Some("9780441172719".to_string()),
+			..candidate("Dune", None, &[])
+		};
+		assert_eq!(score_candidate(&query, &candidate), 1.0);
+	}
+
+	#[test]
+	fn test_score_candidate() {
+		let query = MetadataQuery {
+			title: Some("Dune".to_string()),
+			year: Some(1965),
+			writers: vec!["Frank Herbert".to_string()],
+			..Default::default()
+		};
+
+		let exact = candidate("Dune", Some(1965), &["Herbert, Frank"]);
+		assert_eq!(score_candidate(&query, &exact), 1.0);
+
+		let off_by_one = candidate("Dune", Some(1966), &["Frank Herbert"]);
+		assert_eq!(score_candidate(&query, &off_by_one), 0.9);
+
+		let sequel = candidate("Dune Messiah", Some(1969), &["Frank Herbert"]);
+		assert_eq!(score_candidate(&query, &sequel), 0.5);
+
+		let unrelated = candidate("Neuromancer", Some(1984), &["William Gibson"]);
+		assert_eq!(score_candidate(&query, &unrelated), 0.0);
+	}
+
+	#[test]
+	fn test_title_only_query() {
+		let query = MetadataQuery {
+			title: Some("The Left Hand of Darkness".to_string()),
+			..Default::default()
+		};
+		let exact = candidate("The left hand of darkness", Some(1969), &[]);
+		assert_eq!(score_candidate(&query, &exact), 1.0);
+	}
+}
@@ -2,9 +2,13 @@ use serde_json::json;
 
 use super::{
 	error::{NotifierError, NotifierResult},
-	Notifier, NotifierEvent, FAVICON_URL, NOTIFIER_ID,
+	http_client, Notifier, NotifierEvent, FAVICON_URL, NOTIFIER_ID,
 };
 
+// https://birdie0.github.io/discord-webhooks-guide/structure/embed/color.html
+const DEFAULT_COLOR: u32 = 13605239;
+const PROBLEM_COLOR: u32 = 15158332;
+
 pub struct DiscordClient {
 	pub webhook_url: String,
 	pub client: reqwest::Client,
@@ -12,7 +16,7 @@ pub struct DiscordClient {
 
 impl DiscordClient {
 	pub fn new(webhook_url: String) -> Self {
-		let client = reqwest::Client::new();
+		let client = http_client();
 		Self {
 			webhook_url,
 			client,
@@ -25,20 +29,21 @@ impl DiscordClient {
 #[async_trait::async_trait]
 impl Notifier for DiscordClient {
 	fn payload_from_event(event: NotifierEvent) -> NotifierResult<serde_json::Value> {
-		let payload = match event {
-			NotifierEvent::ScanCompleted {
-				books_added,
-				library_name,
-			} => json!({
-				"username" : NOTIFIER_ID,
-				"avatar_url" : FAVICON_URL,
-				"embeds" : [{
-					"title" : "Scan Completed!",
-					"description": format!("{books_added} books added to {library_name}"),
-					"color" : 13605239,
-				}]
-			}),
+		let title = event.title();
+		let color = if event.is_problem() {
+			PROBLEM_COLOR
+		} else {
+			DEFAULT_COLOR
 		};
+		let payload = json!({
+			"username" : NOTIFIER_ID,
+			"avatar_url" : FAVICON_URL,
+			"embeds" : [{
+				"title" : title,
+				"description": event.into_message(),
+				"color" : color,
+			}]
+		});
 		Ok(payload)
 	}
 
@@ -62,8 +67,6 @@ impl Notifier for DiscordClient {
 	}
 }
 
-// https://birdie0.github.io/discord-webhooks-guide/structure/embed/color.html
-
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -100,5 +103,18 @@ mod tests {
 			embeds[0]["description"],
 			String::from("5 books added to test_library")
 		);
+		assert_eq!(embeds[0]["color"], DEFAULT_COLOR);
+	}
+
+	#[test]
+	fn test_library_missing() {
+		let event = NotifierEvent::LibraryMissing {
+			library_name: String::from("test_library"),
+			path: String::from("/books"),
+		};
+		let response = DiscordClient::payload_from_event(event).unwrap();
+		let embeds = response["embeds"].to_owned();
+		assert_eq!(embeds[0]["title"], String::from("Library Missing"));
+		assert_eq!(embeds[0]["color"], PROBLEM_COLOR);
 	}
 }
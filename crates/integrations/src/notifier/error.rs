@@ -8,4 +8,12 @@ pub enum NotifierError {
 	Unimplemented(String),
 	#[error("Request was unsuccessful")]
 	RequestFailed(String),
+	#[error("Invalid notifier config: {0}")]
+	InvalidConfig(String),
+	#[error("Delivery failed after {attempts} attempt(s): {message}")]
+	DeliveryFailed {
+		attempts: u32,
+		status_code: Option<u16>,
+		message: String,
+	},
 }
@@ -1,24 +1,178 @@
+#[derive(Debug, Clone)]
 pub enum NotifierEvent {
 	ScanCompleted {
 		books_added: u64,
 		library_name: String,
 	},
+	SeriesCreated {
+		series_added: u64,
+		library_name: String,
+	},
+	LibraryMissing {
+		library_name: String,
+		path: String,
+	},
+	JobFailed {
+		job_name: String,
+		error: String,
+	},
+	UserRegistered {
+		username: String,
+	},
+	NewDeviceLogin {
+		username: String,
+		user_agent: String,
+		ip_address: String,
+	},
+	BookClubScheduleChanged {
+		book_club_name: String,
+	},
+	/// An administrative or security event which was recorded in the audit log
+	AuditEvent {
+		action: String,
+		actor: String,
+		target: String,
+	},
+	/// A message sent on demand to verify a notifier is configured correctly
+	Test,
+}
+
+fn pluralize(count: u64, singular: &str, plural: &str) -> String {
+	if count == 1 {
+		format!("{count} {singular}")
+	} else {
+		format!("{count} {plural}")
+	}
 }
 
 impl NotifierEvent {
+	/// A stable, machine-readable name for the event, e.g. `SCAN_COMPLETED`
+	pub fn name(&self) -> &'static str {
+		match self {
+			NotifierEvent::ScanCompleted { .. } => "SCAN_COMPLETED",
+			NotifierEvent::SeriesCreated { .. } => "SERIES_CREATED",
+			NotifierEvent::LibraryMissing { .. } => "LIBRARY_MISSING",
+			NotifierEvent::JobFailed { .. } => "JOB_FAILED",
+			NotifierEvent::UserRegistered { .. } => "USER_REGISTERED",
+			NotifierEvent::NewDeviceLogin { .. } => "NEW_DEVICE_LOGIN",
+			NotifierEvent::BookClubScheduleChanged { .. } => "BOOK_CLUB_SCHEDULE_CHANGED",
+			NotifierEvent::AuditEvent { .. } => "AUDIT_EVENT",
+			NotifierEvent::Test => "TEST",
+		}
+	}
+
+	/// A short, human-readable title for the event
+	pub fn title(&self) -> &'static str {
+		match self {
+			NotifierEvent::ScanCompleted { .. } => "Scan Completed!",
+			NotifierEvent::SeriesCreated { .. } => "New Series",
+			NotifierEvent::LibraryMissing { .. } => "Library Missing",
+			NotifierEvent::JobFailed { .. } => "Job Failed",
+			NotifierEvent::UserRegistered { .. } => "New User",
+			NotifierEvent::NewDeviceLogin { .. } => "New Device Login",
+			NotifierEvent::BookClubScheduleChanged { .. } => "Book Club Schedule Changed",
+			NotifierEvent::AuditEvent { .. } => "Audit Event",
+			NotifierEvent::Test => "Test Notification",
+		}
+	}
+
+	/// Whether the event represents something going wrong, which notifiers may choose to
+	/// style differently
+	pub fn is_problem(&self) -> bool {
+		matches!(
+			self,
+			NotifierEvent::LibraryMissing { .. } | NotifierEvent::JobFailed { .. }
+		)
+	}
+
 	pub fn into_message(self) -> String {
 		match self {
 			NotifierEvent::ScanCompleted {
 				books_added,
 				library_name,
-			} => {
-				let is_plural = books_added == 0 || books_added > 1;
-				let book_or_books = if is_plural { "books" } else { "book" };
-				format!(
-					"{} {} added to {}",
-					books_added, book_or_books, library_name
-				)
+			} => format!(
+				"{} added to {}",
+				pluralize(books_added, "book", "books"),
+				library_name
+			),
+			NotifierEvent::SeriesCreated {
+				series_added,
+				library_name,
+			} => format!(
+				"{} added to {}",
+				pluralize(series_added, "series", "series"),
+				library_name
+			),
+			NotifierEvent::LibraryMissing { library_name, path } => {
+				format!("{library_name} could not be found on disk at {path}")
+			},
+			NotifierEvent::JobFailed { job_name, error } => {
+				format!("{job_name} failed: {error}")
+			},
+			NotifierEvent::UserRegistered { username } => {
+				format!("{username} has joined the server")
+			},
+			NotifierEvent::NewDeviceLogin {
+				username,
+				user_agent,
+				ip_address,
+			} => format!("{username} logged in from a new device ({user_agent}, {ip_address})"),
+			NotifierEvent::BookClubScheduleChanged { book_club_name } => {
+				format!("The reading schedule for {book_club_name} has changed")
+			},
+			NotifierEvent::AuditEvent {
+				action,
+				actor,
+				target,
+			} => format!("{action} on {target} by {actor}"),
+			NotifierEvent::Test => {
+				"This is a test notification from Stump. If you can see this, the notifier is working!".to_string()
 			},
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_scan_completed_message() {
+		let event = NotifierEvent::ScanCompleted {
+			books_added: 1,
+			library_name: String::from("Comics"),
+		};
+		assert_eq!(event.into_message(), "1 book added to Comics");
+
+		let event = NotifierEvent::ScanCompleted {
+			books_added: 0,
+			library_name: String::from("Comics"),
+		};
+		assert_eq!(event.into_message(), "0 books added to Comics");
+	}
+
+	#[test]
+	fn test_job_failed_message() {
+		let event = NotifierEvent::JobFailed {
+			job_name: String::from("library_scan"),
+			error: String::from("Library could not be found on disk"),
+		};
+		assert!(event.is_problem());
+		assert_eq!(
+			event.into_message(),
+			"library_scan failed: Library could not be found on disk"
+		);
+	}
+
+	#[test]
+	fn test_audit_event_message() {
+		let event = NotifierEvent::AuditEvent {
+			action: String::from("LIBRARY_DELETED"),
+			actor: String::from("oromei"),
+			target: String::from("Comics"),
+		};
+		assert!(!event.is_problem());
+		assert_eq!(event.name(), "AUDIT_EVENT");
+		assert_eq!(event.into_message(), "LIBRARY_DELETED on Comics by oromei");
+	}
+}
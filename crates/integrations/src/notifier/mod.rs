@@ -1,17 +1,39 @@
+use std::time::Duration;
+
 mod discord_client;
 mod error;
 mod event;
+mod push_client;
+mod retry;
 mod telegram_client;
+mod webhook_client;
 
 pub use discord_client::DiscordClient;
+pub use error::{NotifierError, NotifierResult};
 pub use event::NotifierEvent;
+pub use push_client::{PushClient, PushService, GOTIFY_PRIORITIES, NTFY_PRIORITIES};
+pub use retry::{Delivery, RetryPolicy, MAX_DELIVERY_ATTEMPTS};
 pub use telegram_client::TelegramClient;
-
-use self::error::NotifierResult;
+pub use webhook_client::{
+	render_webhook_template, sign_webhook_body, validate_webhook_template, WebhookClient,
+	WEBHOOK_EVENT_HEADER, WEBHOOK_SIGNATURE_HEADER,
+};
 
 pub const NOTIFIER_ID: &str = "Stump Notifier";
 pub const FAVICON_URL: &str = "https://stumpapp.dev/favicon.png";
 
+/// How long a notifier waits for a response before giving up on a request
+pub const NOTIFIER_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build the HTTP client used by notifiers, which times out requests to unresponsive
+/// endpoints rather than waiting on them indefinitely
+pub(crate) fn http_client() -> reqwest::Client {
+	reqwest::Client::builder()
+		.timeout(NOTIFIER_REQUEST_TIMEOUT)
+		.build()
+		.expect("Failed to build notifier HTTP client")
+}
+
 #[async_trait::async_trait]
 pub trait Notifier {
 	// TODO: MessageConfig struct? So we can style according to NotifierEvent?
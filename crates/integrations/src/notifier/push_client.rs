@@ -0,0 +1,229 @@
+use std::ops::RangeInclusive;
+
+use serde_json::json;
+
+use super::{
+	error::{NotifierError, NotifierResult},
+	http_client,
+	retry::{send_with_retries, Delivery, RetryPolicy},
+	Notifier, NotifierEvent,
+};
+
+/// The priorities accepted by ntfy, from min to max
+pub const NTFY_PRIORITIES: RangeInclusive<u8> = 1..=5;
+/// The priorities accepted by Gotify, from lowest to highest
+pub const GOTIFY_PRIORITIES: RangeInclusive<u8> = 0..=10;
+
+/// The priority used when one is not configured, which is the default for both ntfy and
+/// Gotify
+const DEFAULT_PRIORITY: u8 = 3;
+/// The priority used for problem events when one is not configured
+const PROBLEM_PRIORITY: u8 = 4;
+
+/// The push services supported by [`PushClient`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushService {
+	/// https://docs.ntfy.sh/publish/#publish-as-json
+	Ntfy {
+		topic: String,
+		access_token: Option<String>,
+	},
+	/// https://gotify.net/api-docs#/message/createMessage
+	Gotify { app_token: String },
+}
+
+impl PushService {
+	/// The range of priorities the service accepts
+	pub fn priorities(&self) -> RangeInclusive<u8> {
+		match self {
+			PushService::Ntfy { .. } => NTFY_PRIORITIES,
+			PushService::Gotify { .. } => GOTIFY_PRIORITIES,
+		}
+	}
+}
+
+/// A notifier for self-hostable push services, i.e. ntfy and Gotify
+pub struct PushClient {
+	server_url: String,
+	service: PushService,
+	priority: Option<u8>,
+	retry_policy: RetryPolicy,
+	client: reqwest::Client,
+}
+
+impl PushClient {
+	fn new(server_url: String, service: PushService) -> Self {
+		Self {
+			server_url: server_url.trim_end_matches('/').to_string(),
+			service,
+			priority: None,
+			retry_policy: RetryPolicy::default(),
+			client: http_client(),
+		}
+	}
+
+	/// Create a client which publishes to a topic on an ntfy server, e.g. `https://ntfy.sh`
+	pub fn ntfy(server_url: String, topic: String, access_token: Option<String>) -> Self {
+		Self::new(
+			server_url,
+			PushService::Ntfy {
+				topic,
+				access_token,
+			},
+		)
+	}
+
+	/// Create a client which sends messages to a Gotify application
+	pub fn gotify(server_url: String, app_token: String) -> Self {
+		Self::new(server_url, PushService::Gotify { app_token })
+	}
+
+	/// Set the priority of sent messages. If not set, problem events are sent with a
+	/// slightly elevated priority. The priority must be within [`PushService::priorities`]
+	pub fn with_priority(mut self, priority: Option<u8>) -> NotifierResult<Self> {
+		let priorities = self.service.priorities();
+		if let Some(priority) = priority.filter(|p| !priorities.contains(p)) {
+			return Err(NotifierError::InvalidConfig(format!(
+				"Priority {priority} is not between {} and {}",
+				priorities.start(),
+				priorities.end()
+			)));
+		}
+
+		self.priority = priority;
+		Ok(self)
+	}
+
+	pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+		self.retry_policy = retry_policy;
+		self
+	}
+
+	fn priority_for(&self, event: &NotifierEvent) -> u8 {
+		self.priority.unwrap_or(if event.is_problem() {
+			PROBLEM_PRIORITY
+		} else {
+			DEFAULT_PRIORITY
+		})
+	}
+
+	/// Deliver the event, retrying according to the client's retry policy
+	pub async fn deliver(&self, event: NotifierEvent) -> NotifierResult<Delivery> {
+		let priority = self.priority_for(&event);
+		let mut body = Self::payload_from_event(event)?;
+		body["priority"] = priority.into();
+
+		match &self.service {
+			PushService::Ntfy {
+				topic,
+				access_token,
+			} => {
+				body["topic"] = topic.as_str().into();
+				send_with_retries(self.retry_policy, || {
+					let request = self.client.post(&self.server_url).json(&body);
+					match access_token {
+						Some(token) => request.bearer_auth(token),
+						None => request,
+					}
+				})
+				.await
+			},
+			PushService::Gotify { app_token } => {
+				let url = format!("{}/message", self.server_url);
+				send_with_retries(self.retry_policy, || {
+					self.client
+						.post(&url)
+						.header("X-Gotify-Key", app_token)
+						.json(&body)
+				})
+				.await
+			},
+		}
+	}
+}
+
+#[async_trait::async_trait]
+impl Notifier for PushClient {
+	fn payload_from_event(event: NotifierEvent) -> NotifierResult<serde_json::Value> {
+		Ok(json!({
+			"title": event.title(),
+			"message": event.into_message(),
+		}))
+	}
+
+	async fn send_message(&self, event: NotifierEvent) -> NotifierResult<()> {
+		self.deliver(event).await.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::test_utils::TestServer;
+
+	fn fast_policy() -> RetryPolicy {
+		RetryPolicy::new(2, Duration::from_millis(1))
+	}
+
+	#[test]
+	fn test_priority_must_be_in_range() {
+		let ntfy =
+			|| PushClient::ntfy(String::from("https://ntfy.sh"), String::new(), None);
+		assert!(ntfy().with_priority(Some(5)).is_ok());
+		assert!(ntfy().with_priority(Some(0)).is_err());
+		assert!(ntfy().with_priority(Some(6)).is_err());
+
+		let gotify = || PushClient::gotify(String::from("https://gotify"), String::new());
+		assert!(gotify().with_priority(Some(0)).is_ok());
+		assert!(gotify().with_priority(Some(10)).is_ok());
+		assert!(gotify().with_priority(Some(11)).is_err());
+		assert!(gotify().with_priority(None).is_ok());
+	}
+
+	#[tokio::test]
+	async fn test_ntfy_delivery() {
+		let server = TestServer::start(vec![200]).await;
+		let client = PushClient::ntfy(
+			format!("{}/", server.url),
+			String::from("stump"),
+			Some(String::from("tk_token")),
+		)
+		.with_retry_policy(fast_policy());
+
+		let event = NotifierEvent::JobFailed {
+			job_name: String::from("library_scan"),
+			error: String::from("oops"),
+		};
+		client.deliver(event).await.unwrap();
+
+		let request = &server.requests()[0];
+		assert_eq!(request.path, "/");
+		assert_eq!(request.header("authorization"), Some("Bearer tk_token"));
+
+		let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+		assert_eq!(body["topic"], "stump");
+		assert_eq!(body["title"], "Job Failed");
+		assert_eq!(body["priority"], PROBLEM_PRIORITY);
+	}
+
+	#[tokio::test]
+	async fn test_gotify_delivery() {
+		let server = TestServer::start(vec![200]).await;
+		let client = PushClient::gotify(server.url.clone(), String::from("app_token"))
+			.with_priority(Some(8))
+			.unwrap()
+			.with_retry_policy(fast_policy());
+
+		client.deliver(NotifierEvent::Test).await.unwrap();
+
+		let request = &server.requests()[0];
+		assert_eq!(request.path, "/message");
+		assert_eq!(request.header("x-gotify-key"), Some("app_token"));
+
+		let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+		assert_eq!(body["message"], NotifierEvent::Test.into_message());
+		assert_eq!(body["priority"], 8);
+	}
+}
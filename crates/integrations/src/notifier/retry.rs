@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, StatusCode};
+
+use super::error::{NotifierError, NotifierResult};
+
+/// The most attempts a notifier will make to deliver a message, regardless of its policy.
+/// Since the backoff doubles after each attempt, this also bounds how long a delivery may take
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+
+/// How many times, and how patiently, a notifier should try to deliver a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// The total number of attempts to make, including the first. The value is clamped
+	/// between 1 and [`MAX_DELIVERY_ATTEMPTS`]
+	pub max_attempts: u32,
+	/// How long to wait before the first retry. The wait doubles after each failed attempt
+	pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_attempts: 3,
+			initial_backoff: Duration::from_secs(1),
+		}
+	}
+}
+
+impl RetryPolicy {
+	pub fn new(max_attempts: u32, initial_backoff: Duration) -> Self {
+		Self {
+			max_attempts,
+			initial_backoff,
+		}
+	}
+
+	/// The time to wait before the given retry, where the first retry is 1
+	pub fn backoff_for(&self, retry: u32) -> Duration {
+		let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+		self.initial_backoff.saturating_mul(factor)
+	}
+}
+
+/// The outcome of a successful delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delivery {
+	/// The number of attempts it took to deliver the message
+	pub attempts: u32,
+	/// The status code of the final response, if the notifier made an HTTP request
+	pub status_code: Option<u16>,
+}
+
+/// Whether a response with the given status is worth retrying. Client errors (other than
+/// rate limiting) indicate a misconfiguration, which another attempt won't fix
+fn is_retryable(status: StatusCode) -> bool {
+	status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Send a request, retrying with exponential backoff on network errors, rate limiting and
+/// server errors. The request is rebuilt for each attempt by `build_request`
+pub(crate) async fn send_with_retries<F>(
+	policy: RetryPolicy,
+	build_request: F,
+) -> NotifierResult<Delivery>
+where
+	F: Fn() -> RequestBuilder,
+{
+	let max_attempts = policy.max_attempts.clamp(1, MAX_DELIVERY_ATTEMPTS);
+	let mut attempts = 0;
+
+	loop {
+		attempts += 1;
+
+		let (status_code, message) = match build_request().send().await {
+			Ok(response) if response.status().is_success() => {
+				return Ok(Delivery {
+					attempts,
+					status_code: Some(response.status().as_u16()),
+				});
+			},
+			Ok(response) => {
+				let status = response.status();
+				let message = response
+					.text()
+					.await
+					.ok()
+					.filter(|text| !text.is_empty())
+					.unwrap_or_else(|| status.to_string());
+
+				if !is_retryable(status) {
+					return Err(NotifierError::DeliveryFailed {
+						attempts,
+						status_code: Some(status.as_u16()),
+						message,
+					});
+				}
+
+				(Some(status.as_u16()), message)
+			},
+			Err(error) => (None, error.to_string()),
+		};
+
+		if attempts >= max_attempts {
+			return Err(NotifierError::DeliveryFailed {
+				attempts,
+				status_code,
+				message,
+			});
+		}
+
+		let backoff = policy.backoff_for(attempts);
+		tracing::debug!(
+			attempts,
+			?status_code,
+			?backoff,
+			"Retrying notifier delivery"
+		);
+		tokio::time::sleep(backoff).await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::TestServer;
+
+	fn fast_policy(max_attempts: u32) -> RetryPolicy {
+		RetryPolicy::new(max_attempts, Duration::from_millis(1))
+	}
+
+	#[test]
+	fn test_backoff_doubles() {
+		let policy = RetryPolicy::default();
+		assert_eq!(policy.backoff_for(1), Duration::from_secs(1));
+		assert_eq!(policy.backoff_for(2), Duration::from_secs(2));
+		assert_eq!(policy.backoff_for(3), Duration::from_secs(4));
+	}
+
+	#[tokio::test]
+	async fn test_retries_server_errors() {
+		let server = TestServer::start(vec![500, 503, 200]).await;
+		let client = reqwest::Client::new();
+
+		let delivery = send_with_retries(fast_policy(3), || client.post(&server.url))
+			.await
+			.unwrap();
+
+		assert_eq!(delivery.attempts, 3);
+		assert_eq!(delivery.status_code, Some(200));
+		assert_eq!(server.requests().len(), 3);
+	}
+
+	#[tokio::test]
+	async fn test_gives_up_after_max_attempts() {
+		let server = TestServer::start(vec![500]).await;
+		let client = reqwest::Client::new();
+
+		let result = send_with_retries(fast_policy(2), || client.post(&server.url)).await;
+
+		assert!(matches!(
+			result,
+			Err(NotifierError::DeliveryFailed {
+				attempts: 2,
+				status_code: Some(500),
+				..
+			})
+		));
+		assert_eq!(server.requests().len(), 2);
+	}
+
+	#[tokio::test]
+	async fn test_caps_max_attempts() {
+		let server = TestServer::start(vec![500]).await;
+		let client = reqwest::Client::new();
+
+		let result = send_with_retries(RetryPolicy::new(100, Duration::ZERO), || {
+			client.post(&server.url)
+		})
+		.await;
+
+		assert!(matches!(
+			result,
+			Err(NotifierError::DeliveryFailed {
+				attempts: MAX_DELIVERY_ATTEMPTS,
+				..
+			})
+		));
+		assert_eq!(server.requests().len(), MAX_DELIVERY_ATTEMPTS as usize);
+	}
+
+	#[tokio::test]
+	async fn test_does_not_retry_client_errors() {
+		let server = TestServer::start(vec![400, 200]).await;
+		let client = reqwest::Client::new();
+
+		let result = send_with_retries(fast_policy(3), || client.post(&server.url)).await;
+
+		assert!(matches!(
+			result,
+			Err(NotifierError::DeliveryFailed {
+				attempts: 1,
+				status_code: Some(400),
+				..
+			})
+		));
+		assert_eq!(server.requests().len(), 1);
+	}
+}
@@ -2,7 +2,7 @@ use crate::Notifier;
 
 use super::{
 	error::{NotifierError, NotifierResult},
-	NotifierEvent,
+	http_client, NotifierEvent,
 };
 
 pub struct TelegramClient {
@@ -13,7 +13,7 @@ pub struct TelegramClient {
 
 impl TelegramClient {
 	pub fn new(token: String, chat_id: String) -> Self {
-		let client = reqwest::Client::new();
+		let client = http_client();
 		Self {
 			token,
 			chat_id,
@@ -31,13 +31,18 @@ impl Notifier for TelegramClient {
 	}
 	async fn send_message(&self, event: NotifierEvent) -> NotifierResult<()> {
 		let token = self.token.clone();
-		let chat_id = self.chat_id.clone();
 		let message = event.into_message();
+		// Messages may contain arbitrary user-provided text (e.g. library names or user
+		// agents), so they are passed as encoded query params
 		let response = self
-		.client
-		.post(format!("https://api.telegram.org/bot{token}/sendMessage?chat_id={chat_id}&text={message}"))
-		.send()
-		.await?;
+			.client
+			.post(format!("https://api.telegram.org/bot{token}/sendMessage"))
+			.query(&[
+				("chat_id", self.chat_id.as_str()),
+				("text", message.as_str()),
+			])
+			.send()
+			.await?;
 		if !response.status().is_success() {
 			let errmsg = response
 				.text()
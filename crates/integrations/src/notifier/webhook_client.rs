@@ -0,0 +1,227 @@
+use data_encoding::HEXLOWER;
+use reqwest::Method;
+use ring::hmac;
+use serde_json::json;
+
+use super::{
+	error::{NotifierError, NotifierResult},
+	http_client,
+	retry::{send_with_retries, Delivery, RetryPolicy},
+	Notifier, NotifierEvent,
+};
+
+/// The header containing the name of the event being delivered
+pub const WEBHOOK_EVENT_HEADER: &str = "X-Stump-Event";
+/// The header containing the HMAC-SHA256 signature of the request body, formatted as
+/// `sha256=<hex digest>`. Only sent when a signing secret is configured
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Stump-Signature";
+
+/// Render a body template for the given event. The placeholders `{{event}}`, `{{title}}`
+/// and `{{message}}` are replaced with JSON strings (including the quotes), so they should
+/// not be quoted in the template. For example:
+///
+/// ```json
+/// { "text": {{message}}, "source": "stump" }
+/// ```
+pub fn render_webhook_template(
+	template: &str,
+	event: NotifierEvent,
+) -> NotifierResult<serde_json::Value> {
+	let name = serde_json::Value::from(event.name()).to_string();
+	let title = serde_json::Value::from(event.title()).to_string();
+	let message = serde_json::Value::from(event.into_message()).to_string();
+
+	let rendered = template
+		.replace("{{event}}", &name)
+		.replace("{{title}}", &title)
+		.replace("{{message}}", &message);
+
+	serde_json::from_str(&rendered).map_err(|error| {
+		NotifierError::InvalidConfig(format!(
+			"Body template does not render to valid JSON: {error}"
+		))
+	})
+}
+
+/// Check that a body template renders to valid JSON
+pub fn validate_webhook_template(template: &str) -> NotifierResult<()> {
+	render_webhook_template(template, NotifierEvent::Test).map(|_| ())
+}
+
+/// Compute the value of the signature header for the given body
+pub fn sign_webhook_body(secret: &str, body: &[u8]) -> String {
+	let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+	let tag = hmac::sign(&key, body);
+	format!("sha256={}", HEXLOWER.encode(tag.as_ref()))
+}
+
+/// A notifier which sends a JSON body to an arbitrary HTTP endpoint
+pub struct WebhookClient {
+	url: String,
+	method: Method,
+	headers: Vec<(String, String)>,
+	body_template: Option<String>,
+	signing_secret: Option<String>,
+	retry_policy: RetryPolicy,
+	client: reqwest::Client,
+}
+
+impl WebhookClient {
+	pub fn new(url: String) -> Self {
+		Self {
+			url,
+			method: Method::POST,
+			headers: Vec::new(),
+			body_template: None,
+			signing_secret: None,
+			retry_policy: RetryPolicy::default(),
+			client: http_client(),
+		}
+	}
+
+	/// Set the HTTP method to send with, e.g. `PUT`
+	pub fn with_method(mut self, method: &str) -> NotifierResult<Self> {
+		self.method =
+			Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| {
+				NotifierError::InvalidConfig(format!("Invalid HTTP method: {method}"))
+			})?;
+		Ok(self)
+	}
+
+	/// Add headers which are sent with every request
+	pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+		self.headers = headers;
+		self
+	}
+
+	/// Set the template for the request body. See [`render_webhook_template`]
+	pub fn with_body_template(mut self, template: Option<String>) -> Self {
+		self.body_template = template;
+		self
+	}
+
+	/// Set the secret used to sign the request body
+	pub fn with_signing_secret(mut self, secret: Option<String>) -> Self {
+		self.signing_secret = secret;
+		self
+	}
+
+	pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+		self.retry_policy = retry_policy;
+		self
+	}
+
+	/// Deliver the event, retrying according to the client's retry policy
+	pub async fn deliver(&self, event: NotifierEvent) -> NotifierResult<Delivery> {
+		let event_name = event.name();
+		let body = match &self.body_template {
+			Some(template) => render_webhook_template(template, event)?,
+			None => Self::payload_from_event(event)?,
+		};
+		let body = serde_json::to_vec(&body).map_err(|error| {
+			NotifierError::RequestFailed(format!("Failed to serialize body: {error}"))
+		})?;
+		let signature = self
+			.signing_secret
+			.as_deref()
+			.map(|secret| sign_webhook_body(secret, &body));
+
+		send_with_retries(self.retry_policy, || {
+			let mut request = self
+				.client
+				.request(self.method.clone(), &self.url)
+				.header(reqwest::header::CONTENT_TYPE, "application/json")
+				.header(WEBHOOK_EVENT_HEADER, event_name);
+			for (name, value) in &self.headers {
+				request = request.header(name, value);
+			}
+			if let Some(signature) = &signature {
+				request = request.header(WEBHOOK_SIGNATURE_HEADER, signature);
+			}
+			request.body(body.clone())
+		})
+		.await
+	}
+}
+
+#[async_trait::async_trait]
+impl Notifier for WebhookClient {
+	fn payload_from_event(event: NotifierEvent) -> NotifierResult<serde_json::Value> {
+		Ok(json!({
+			"event": event.name(),
+			"title": event.title(),
+			"message": event.into_message(),
+		}))
+	}
+
+	async fn send_message(&self, event: NotifierEvent) -> NotifierResult<()> {
+		self.deliver(event).await.map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::test_utils::TestServer;
+
+	#[test]
+	fn test_render_template_escapes_values() {
+		let event = NotifierEvent::UserRegistered {
+			username: String::from("\"quoted\" user"),
+		};
+		let rendered = render_webhook_template(
+			r#"{ "text": {{message}}, "kind": {{event}} }"#,
+			event,
+		)
+		.unwrap();
+		assert_eq!(rendered["text"], "\"quoted\" user has joined the server");
+		assert_eq!(rendered["kind"], "USER_REGISTERED");
+	}
+
+	#[test]
+	fn test_validate_template() {
+		assert!(validate_webhook_template(r#"{ "title": {{title}} }"#).is_ok());
+		// Placeholders render as JSON strings, so quoting them produces invalid JSON
+		assert!(validate_webhook_template(r#"{ "title": "{{title}}" }"#).is_err());
+	}
+
+	#[test]
+	fn test_invalid_method() {
+		let client = WebhookClient::new(String::from("http://localhost"));
+		assert!(client.with_method("NOT A METHOD").is_err());
+	}
+
+	#[tokio::test]
+	async fn test_deliver_signs_and_retries() {
+		let server = TestServer::start(vec![500, 200]).await;
+		let client = WebhookClient::new(format!("{}/hook", server.url))
+			.with_method("put")
+			.unwrap()
+			.with_headers(vec![(String::from("X-Custom"), String::from("value"))])
+			.with_signing_secret(Some(String::from("secret")))
+			.with_retry_policy(RetryPolicy::new(3, Duration::from_millis(1)));
+
+		let delivery = client.deliver(NotifierEvent::Test).await.unwrap();
+		assert_eq!(delivery.attempts, 2);
+		assert_eq!(delivery.status_code, Some(200));
+
+		let requests = server.requests();
+		assert_eq!(requests.len(), 2);
+
+		let request = &requests[1];
+		assert_eq!(request.method, "PUT");
+		assert_eq!(request.path, "/hook");
+		assert_eq!(request.header("x-custom"), Some("value"));
+		assert_eq!(request.header(WEBHOOK_EVENT_HEADER), Some("TEST"));
+		assert_eq!(
+			request.header(WEBHOOK_SIGNATURE_HEADER),
+			Some(sign_webhook_body("secret", request.body.as_bytes()).as_str())
+		);
+
+		let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+		assert_eq!(body["event"], "TEST");
+		assert_eq!(body["title"], "Test Notification");
+	}
+}
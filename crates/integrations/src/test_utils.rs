@@ -0,0 +1,149 @@
+use std::sync::{
+	atomic::{AtomicUsize, Ordering},
+	Arc, Mutex,
+};
+
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::TcpListener,
+};
+
+/// A request received by the [`TestServer`]
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+	pub method: String,
+	pub path: String,
+	pub headers: Vec<(String, String)>,
+	pub body: String,
+}
+
+impl ReceivedRequest {
+	pub fn header(&self, name: &str) -> Option<&str> {
+		self.headers
+			.iter()
+			.find(|(key, _)| key.eq_ignore_ascii_case(name))
+			.map(|(_, value)| value.as_str())
+	}
+}
+
+/// A minimal HTTP server which records the requests it receives and responds to each
+/// connection with a status and body chosen by the responder
+pub struct TestServer {
+	pub url: String,
+	pub requests: Arc<Mutex<Vec<ReceivedRequest>>>,
+}
+
+impl TestServer {
+	/// Start a server which responds with the next status in `statuses` (repeating the last
+	/// one) and a plain text body
+	pub async fn start(statuses: Vec<u16>) -> Self {
+		let counter = AtomicUsize::new(0);
+		Self::serve(move |_| {
+			let index = counter.fetch_add(1, Ordering::SeqCst);
+			let status = statuses
+				.get(index)
+				.or(statuses.last())
+				.copied()
+				.unwrap_or(200);
+			(status, "ok".to_string())
+		})
+		.await
+	}
+
+	/// Start a server which responds with the body of the first fixture whose path is a
+	/// prefix of the request path (including the query string), or a 404 when none match
+	pub async fn start_with_fixtures(
+		fixtures: Vec<(&'static str, &'static str)>,
+	) -> Self {
+		Self::serve(move |request| {
+			fixtures
+				.iter()
+				.find(|(path, _)| request.path.starts_with(path))
+				.map_or((404, "{}".to_string()), |(_, body)| (200, body.to_string()))
+		})
+		.await
+	}
+
+	async fn serve<F>(responder: F) -> Self
+	where
+		F: Fn(&ReceivedRequest) -> (u16, String) + Send + 'static,
+	{
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let requests = Arc::new(Mutex::new(Vec::new()));
+
+		let received = requests.clone();
+		tokio::spawn(async move {
+			loop {
+				let Ok((mut stream, _)) = listener.accept().await else {
+					break;
+				};
+
+				let request = read_request(&mut stream).await;
+				let (status, body) = responder(&request);
+				received.lock().unwrap().push(request);
+
+				let response = format!(
+					"HTTP/1.1 {status} Test\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+					body.len()
+				);
+				let _ = stream.write_all(response.as_bytes()).await;
+				let _ = stream.shutdown().await;
+			}
+		});
+
+		Self { url, requests }
+	}
+
+	pub fn requests(&self) -> Vec<ReceivedRequest> {
+		self.requests.lock().unwrap().clone()
+	}
+}
+
+async fn read_request(stream: &mut tokio::net::TcpStream) -> ReceivedRequest {
+	let mut buffer = Vec::new();
+	let mut chunk = [0u8; 1024];
+
+	let header_end = loop {
+		let read = stream.read(&mut chunk).await.unwrap_or(0);
+		if read == 0 {
+			break buffer.len();
+		}
+		buffer.extend_from_slice(&chunk[..read]);
+		if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+			break position + 4;
+		}
+	};
+
+	let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+	let mut lines = head.split("\r\n");
+	let mut request_line = lines.next().unwrap_or_default().split(' ');
+	let method = request_line.next().unwrap_or_default().to_string();
+	let path = request_line.next().unwrap_or_default().to_string();
+	let headers = lines
+		.filter_map(|line| line.split_once(':'))
+		.map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+		.collect::<Vec<_>>();
+
+	let content_length = headers
+		.iter()
+		.find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+		.and_then(|(_, value)| value.parse::<usize>().ok())
+		.unwrap_or(0);
+
+	let mut body = buffer[header_end..].to_vec();
+	while body.len() < content_length {
+		let read = stream.read(&mut chunk).await.unwrap_or(0);
+		if read == 0 {
+			break;
+		}
+		body.extend_from_slice(&chunk[..read]);
+	}
+
+	ReceivedRequest {
+		method,
+		path,
+		headers,
+		body: String::from_utf8_lossy(&body).to_string(),
+	}
+}
@@ -112,7 +112,7 @@ fn generate_filter_match_arm(
 
 	// Note: This is a temporary hack until the migration to SeaORM is complete and a better
 	// smart filter solution is in place.
-	let method = if inner_name_str == "tags" {
+	let method = if matches!(inner_name_str.as_str(), "tags" | "reviews") {
 		format_ident!("some")
 	} else {
 		format_ident!("is")
@@ -2,10 +2,15 @@ import { Meta } from 'nextra'
 
 export default {
 	'api-keys': 'API Keys',
+	'audit-log': 'Audit Log',
 	'book-clubs': 'Book Clubs',
 	email: 'Email',
 	'file-explorer': 'File Explorer',
+	'metadata-providers': 'Metadata Providers',
 	upload: 'File Uploads',
 	'smart-list': 'Smart Lists',
 	'reading-list': 'Reading Lists',
+	search: 'Search',
+	'single-sign-on': 'Single Sign-On',
+	'two-factor-authentication': 'Two-Factor Authentication',
 } satisfies Meta
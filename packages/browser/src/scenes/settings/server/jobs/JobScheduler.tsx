@@ -1,23 +1,37 @@
 import { zodResolver } from '@hookform/resolvers/zod'
-import { useJobSchedulerConfig, useLibraries } from '@stump/client'
-import { Alert, Button, ComboBox, Form, Input, Label, NativeSelect } from '@stump/components'
-import { Construction } from 'lucide-react'
-import { useEffect, useState } from 'react'
+import {
+	useCreateJobSchedule,
+	useJobScheduleActions,
+	useJobSchedulesQuery,
+	useLibraries,
+} from '@stump/client'
+import {
+	Badge,
+	Button,
+	Card,
+	ComboBox,
+	Form,
+	Input,
+	Label,
+	NativeSelect,
+	Text,
+} from '@stump/components'
+import { JobSchedule, ScheduledJobKind } from '@stump/sdk'
+import dayjs from 'dayjs'
+import { Pause, Play, Trash2, Zap } from 'lucide-react'
+import { useState } from 'react'
 import { useForm } from 'react-hook-form'
 import toast from 'react-hot-toast'
 import { useMediaMatch } from 'rooks'
 import z from 'zod'
 
-const schema = z.object({
-	excluded_library_ids: z.array(z.string()).optional(),
-	interval_secs: z
-		.number()
-		.positive()
-		.int()
-		.min(300, 'You cannot set an interval less than five minutes')
-		.optional(),
-})
-type FormValues = z.infer<typeof schema>
+const JOB_KINDS: { label: string; value: ScheduledJobKind }[] = [
+	{ label: 'Library scan', value: 'LIBRARY_SCAN' },
+	{ label: 'Thumbnail generation', value: 'THUMBNAIL_GENERATION' },
+	{ label: 'Media analysis', value: 'MEDIA_ANALYSIS' },
+	{ label: 'Log pruning', value: 'LOG_PRUNING' },
+	{ label: 'Session cleanup', value: 'SESSION_CLEANUP' },
+]
 
 const INTERVAL_PRESETS = [
 	{ label: 'Every 6 hours', value: 21600 },
@@ -27,111 +41,227 @@ const INTERVAL_PRESETS = [
 	{ label: 'Once a month', value: 2592000 },
 ]
 
-const getCorrespondingPreset = (seconds: number) =>
-	INTERVAL_PRESETS.find((preset) => preset.value === seconds)
+const schema = z
+	.object({
+		cron_expression: z.string().optional(),
+		excluded_library_ids: z.array(z.string()).optional(),
+		interval_secs: z
+			.number()
+			.positive()
+			.int()
+			.min(60, 'You cannot set an interval less than one minute')
+			.optional(),
+		job_kind: z.enum([
+			'LIBRARY_SCAN',
+			'THUMBNAIL_GENERATION',
+			'MEDIA_ANALYSIS',
+			'LOG_PRUNING',
+			'SESSION_CLEANUP',
+		]),
+		name: z.string().min(1, 'A name is required'),
+	})
+	.refine((values) => !!values.cron_expression || !!values.interval_secs, {
+		message: 'Either a cron expression or an interval is required',
+		path: ['interval_secs'],
+	})
+type FormValues = z.infer<typeof schema>
+
+const describeTrigger = ({ cron_expression, interval_secs }: JobSchedule) => {
+	if (cron_expression) {
+		return `Cron: ${cron_expression}`
+	}
+
+	const preset = INTERVAL_PRESETS.find(({ value }) => value === interval_secs)
+	return preset?.label ?? `Every ${interval_secs} seconds`
+}
 
 export default function JobScheduler() {
 	const { libraries } = useLibraries()
-	const { config, update } = useJobSchedulerConfig()
+	const { schedules } = useJobSchedulesQuery()
+	const { create } = useCreateJobSchedule()
+	const { deleteSchedule, pause, resume, trigger } = useJobScheduleActions()
 
 	const isSmallViewport = useMediaMatch('(max-width: 768px)')
-	const [intervalPreset, setIntervalPreset] = useState(
-		getCorrespondingPreset(config?.interval_secs || -1)?.value ?? -1,
-	)
+	const [intervalPreset, setIntervalPreset] = useState(-1)
 
-	const form = useForm({
+	const form = useForm<FormValues>({
 		defaultValues: {
-			excluded_library_ids: config?.excluded_libraries.map((library) => library.id),
-			interval_secs: config?.interval_secs,
+			excluded_library_ids: [],
+			job_kind: 'LIBRARY_SCAN',
+			name: '',
 		},
 		resolver: zodResolver(schema),
 	})
 
-	const [excluded_library_ids, interval_secs] = form.watch([
-		'excluded_library_ids',
-		'interval_secs',
-	])
+	const [excluded_library_ids, job_kind] = form.watch(['excluded_library_ids', 'job_kind'])
 
-	const handleSubmit = async ({ interval_secs, excluded_library_ids }: FormValues) => {
-		update(
+	const handleSubmit = ({ cron_expression, interval_secs, ...values }: FormValues) => {
+		create(
 			{
-				excluded_library_ids: excluded_library_ids ?? null,
-				interval_secs: interval_secs ?? null,
+				...values,
+				cron_expression: cron_expression || null,
+				excluded_library_ids: values.excluded_library_ids ?? [],
+				interval_secs: cron_expression ? null : (interval_secs ?? null),
 			},
 			{
 				onError: (error) => {
 					console.error(error)
-					toast.error('Failed to update job scheduler config')
+					toast.error('Failed to create job schedule')
 				},
 				onSuccess: () => {
-					toast.success('Scheduler config updated!')
+					toast.success('Job schedule created!')
+					form.reset()
+					setIntervalPreset(-1)
 				},
 			},
 		)
 	}
 
 	const handleIntervalPresetChange = (value?: string) => {
-		if (!value) {
+		const parsed = value ? parseInt(value, 10) : NaN
+		if (isNaN(parsed) || parsed === -1) {
 			setIntervalPreset(-1)
 			return
 		}
 
-		const parsed = parseInt(value, 10)
-		if (!isNaN(parsed)) {
-			setIntervalPreset(parsed)
-		}
+		setIntervalPreset(parsed)
+		form.setValue('interval_secs', parsed)
 	}
 
-	useEffect(() => {
-		if (intervalPreset !== undefined && intervalPreset !== -1) {
-			form.setValue('interval_secs', intervalPreset)
-		}
-	}, [form, intervalPreset])
-
-	useEffect(() => {
-		// if not preset matches the current interval, set it to custom
-		const preset = INTERVAL_PRESETS.find((preset) => preset.value === interval_secs)
-		setIntervalPreset(preset?.value ?? -1)
-	}, [interval_secs])
-
-	useEffect(() => {
-		if (config) {
-			setIntervalPreset(getCorrespondingPreset(config.interval_secs)?.value ?? -1)
-			form.setValue(
-				'excluded_library_ids',
-				config.excluded_libraries.map(({ id }) => id),
-			)
-			form.setValue('interval_secs', config.interval_secs)
+	const handleAction = async (action: Promise<unknown>, message: string) => {
+		try {
+			await action
+			toast.success(message)
+		} catch (error) {
+			console.error(error)
+			toast.error('The job schedule could not be updated')
 		}
-	}, [form, config])
+	}
 
 	return (
 		<div className="my-2 flex flex-col gap-6">
-			<Alert level="warning" icon={Construction}>
-				<Alert.Content>
-					Stump currently only supports scheduling scanner jobs. This will be extended to support
-					other job types in the future.
-				</Alert.Content>
-			</Alert>
+			{!!schedules?.length && (
+				<Card className="divide-y divide-edge">
+					{schedules.map((schedule) => (
+						<div
+							key={schedule.id}
+							className="flex flex-col gap-2 p-3 md:flex-row md:items-center md:justify-between"
+						>
+							<div className="flex flex-col gap-1">
+								<div className="flex items-center gap-2">
+									<Text className="font-medium">{schedule.name}</Text>
+									<Badge size="xs">
+										{JOB_KINDS.find(({ value }) => value === schedule.job_kind)?.label}
+									</Badge>
+									{schedule.is_paused && (
+										<Badge size="xs" variant="warning">
+											Paused
+										</Badge>
+									)}
+								</div>
+								<Text size="sm" variant="muted">
+									{describeTrigger(schedule)}
+									{schedule.next_run_at && !schedule.is_paused
+										? ` · Next run ${dayjs(schedule.next_run_at).fromNow()}`
+										: ''}
+								</Text>
+							</div>
+
+							<div className="flex items-center gap-1">
+								<Button
+									size="sm"
+									variant="ghost"
+									onClick={() => handleAction(trigger(schedule.id), 'Job queued')}
+								>
+									<Zap className="mr-1.5 h-4 w-4" />
+									Run now
+								</Button>
+								{schedule.is_paused ? (
+									<Button
+										size="sm"
+										variant="ghost"
+										onClick={() => handleAction(resume(schedule.id), 'Schedule resumed')}
+									>
+										<Play className="mr-1.5 h-4 w-4" />
+										Resume
+									</Button>
+								) : (
+									<Button
+										size="sm"
+										variant="ghost"
+										onClick={() => handleAction(pause(schedule.id), 'Schedule paused')}
+									>
+										<Pause className="mr-1.5 h-4 w-4" />
+										Pause
+									</Button>
+								)}
+								<Button
+									size="sm"
+									variant="ghost"
+									onClick={() => handleAction(deleteSchedule(schedule.id), 'Schedule deleted')}
+								>
+									<Trash2 className="mr-1.5 h-4 w-4" />
+									Delete
+								</Button>
+							</div>
+						</div>
+					))}
+				</Card>
+			)}
 
 			<Form form={form} onSubmit={handleSubmit}>
+				<div className="flex w-full flex-col gap-2 md:flex-row md:items-end lg:w-2/3">
+					<Input
+						variant="primary"
+						label="Name"
+						placeholder='e.g. "Nightly scan"'
+						fullWidth
+						errorMessage={form.formState.errors.name?.message}
+						{...form.register('name')}
+					/>
+
+					<div className="flex-shrink-0">
+						<Label htmlFor="job_kind">Job</Label>
+						<NativeSelect
+							id="job_kind"
+							value={job_kind}
+							options={JOB_KINDS}
+							onChange={(e) => form.setValue('job_kind', e.target.value as ScheduledJobKind)}
+						/>
+					</div>
+				</div>
+
+				<div className="flex w-full flex-col gap-2 md:flex-row md:items-end lg:w-2/3">
+					<Input
+						variant="primary"
+						label="Cron expression"
+						description="A standard 5-field cron expression. Takes precedence over the interval"
+						descriptionPosition="top"
+						placeholder='e.g. "0 3 * * *" for every day at 3 AM'
+						fullWidth
+						{...form.register('cron_expression')}
+					/>
+				</div>
+
 				<div className="flex w-full flex-col gap-2 md:flex-row md:items-end lg:w-2/3">
 					<Input
 						variant="primary"
 						type="number"
 						label="Interval"
-						description="How often the scheduler should initiate scans (in seconds). If left empty, the scheduler will be disabled"
+						description="How often the job should run (in seconds), if no cron expression is set"
 						descriptionPosition="top"
 						placeholder='e.g. "86400" for once a day'
 						fullWidth
+						errorMessage={form.formState.errors.interval_secs?.message}
 						{...form.register('interval_secs', {
-							valueAsNumber: true,
+							setValueAs: (value) => (value === '' ? undefined : parseInt(value, 10)),
 						})}
 					/>
 
 					<div className="flex-shrink-0">
 						<Label htmlFor="intervalPreset">Interval preset</Label>
 						<NativeSelect
+							id="intervalPreset"
 							value={intervalPreset}
 							options={INTERVAL_PRESETS}
 							onChange={(e) => handleIntervalPresetChange(e.target.value)}
@@ -140,21 +270,23 @@ export default function JobScheduler() {
 					</div>
 				</div>
 
-				<div className="flex w-full flex-col gap-4 md:flex-row md:items-end md:justify-between lg:w-2/3">
-					<ComboBox
-						label="Excluded libraries"
-						description="Libraries that will be excluded from the scheduled scans"
-						descriptionPosition="top"
-						isMultiSelect
-						value={excluded_library_ids}
-						options={(libraries || []).map((library) => ({
-							label: library.name,
-							value: library.id,
-						}))}
-						onChange={(value) => (value ? form.setValue('excluded_library_ids', value) : null)}
-						size={isSmallViewport ? 'full' : 'default'}
-					/>
-				</div>
+				{job_kind === 'LIBRARY_SCAN' && (
+					<div className="flex w-full flex-col gap-4 md:flex-row md:items-end md:justify-between lg:w-2/3">
+						<ComboBox
+							label="Excluded libraries"
+							description="Libraries that will be excluded from the scheduled scans"
+							descriptionPosition="top"
+							isMultiSelect
+							value={excluded_library_ids}
+							options={(libraries || []).map((library) => ({
+								label: library.name,
+								value: library.id,
+							}))}
+							onChange={(value) => (value ? form.setValue('excluded_library_ids', value) : null)}
+							size={isSmallViewport ? 'full' : 'default'}
+						/>
+					</div>
+				)}
 
 				<Button
 					type="submit"
@@ -163,7 +295,7 @@ export default function JobScheduler() {
 					disabled={form.formState.isSubmitting}
 					className="flex-shrink-0 md:w-32"
 				>
-					Save changes
+					Add schedule
 				</Button>
 			</Form>
 		</div>
@@ -1,6 +1,15 @@
-import type { PersistedJob, UpdateSchedulerConfig } from '@stump/sdk'
+import type { CreateOrUpdateJobSchedule, JobSchedule, PersistedJob } from '@stump/sdk'
+import { AxiosError } from 'axios'
 
-import { PageQueryOptions, useMutation, usePageQuery, useQuery } from '../client'
+import {
+	MutationOptions,
+	PageQueryOptions,
+	QueryOptions,
+	useMutation,
+	usePageQuery,
+	useQuery,
+} from '../client'
+import { invalidateQueries } from '../invalidate'
 import { useSDK } from '../sdk'
 
 type UseJobsQueryParams = PageQueryOptions<PersistedJob> & {
@@ -27,19 +36,103 @@ export function useJobsQuery({ params, ...options }: UseJobsQueryParams = {}) {
 	}
 }
 
-export function useJobSchedulerConfig() {
+export function useJobSchedulesQuery(options: QueryOptions<JobSchedule[]> = {}) {
 	const { sdk } = useSDK()
-	const { data: config, ...restReturn } = useQuery([sdk.job.keys.getSchedulerConfig], () =>
-		sdk.job.getSchedulerConfig(),
+	const { data: schedules, ...restReturn } = useQuery(
+		[sdk.job.keys.getSchedules],
+		() => sdk.job.getSchedules(),
+		options,
 	)
 
+	return {
+		schedules,
+		...restReturn,
+	}
+}
+
+type UseCreateJobScheduleOptions = MutationOptions<
+	JobSchedule,
+	AxiosError,
+	CreateOrUpdateJobSchedule
+>
+export function useCreateJobSchedule(options: UseCreateJobScheduleOptions = {}) {
+	const { sdk } = useSDK()
+	const {
+		mutate: create,
+		mutateAsync: createAsync,
+		...restReturn
+	} = useMutation([sdk.job.keys.createSchedule], (payload) => sdk.job.createSchedule(payload), {
+		...options,
+		onSuccess: async (...args) => {
+			await invalidateQueries({ keys: [sdk.job.keys.getSchedules] })
+			options.onSuccess?.(...args)
+		},
+	})
+
+	return {
+		create,
+		createAsync,
+		...restReturn,
+	}
+}
+
+type UseUpdateJobScheduleOptions = { id: string } & MutationOptions<
+	JobSchedule,
+	AxiosError,
+	CreateOrUpdateJobSchedule
+>
+export function useUpdateJobSchedule({ id, ...options }: UseUpdateJobScheduleOptions) {
+	const { sdk } = useSDK()
 	const {
 		mutate: update,
-		isLoading: isUpdating,
-		isError: isUpdateError,
-	} = useMutation([sdk.job.keys.updateSchedulerConfig], (payload: UpdateSchedulerConfig) =>
-		sdk.job.updateSchedulerConfig(payload),
+		mutateAsync: updateAsync,
+		...restReturn
+	} = useMutation([sdk.job.keys.updateSchedule], (payload) => sdk.job.updateSchedule(id, payload), {
+		...options,
+		onSuccess: async (...args) => {
+			await invalidateQueries({ keys: [sdk.job.keys.getSchedules] })
+			options.onSuccess?.(...args)
+		},
+	})
+
+	return {
+		update,
+		updateAsync,
+		...restReturn,
+	}
+}
+
+/**
+ * A hook for the actions which can be taken on an existing job schedule, i.e. pausing,
+ * resuming, triggering and deleting it
+ */
+export function useJobScheduleActions() {
+	const { sdk } = useSDK()
+
+	const onSuccess = () => invalidateQueries({ keys: [sdk.job.keys.getSchedules] })
+
+	const { mutateAsync: pause } = useMutation(
+		[sdk.job.keys.pauseSchedule],
+		(id: string) => sdk.job.pauseSchedule(id),
+		{ onSuccess },
+	)
+	const { mutateAsync: resume } = useMutation(
+		[sdk.job.keys.resumeSchedule],
+		(id: string) => sdk.job.resumeSchedule(id),
+		{ onSuccess },
+	)
+	const { mutateAsync: trigger } = useMutation(
+		[sdk.job.keys.triggerSchedule],
+		(id: string) => sdk.job.triggerSchedule(id),
+		{
+			onSuccess: () => invalidateQueries({ keys: [sdk.job.keys.getSchedules, sdk.job.keys.get] }),
+		},
+	)
+	const { mutateAsync: deleteSchedule } = useMutation(
+		[sdk.job.keys.deleteSchedule],
+		(id: string) => sdk.job.deleteSchedule(id),
+		{ onSuccess },
 	)
 
-	return { config, isUpdateError, isUpdating, update, ...restReturn }
+	return { deleteSchedule, pause, resume, trigger }
 }
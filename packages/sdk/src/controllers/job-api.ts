@@ -1,5 +1,5 @@
 import { APIBase } from '../base'
-import { JobSchedulerConfig, Pageable, PersistedJob, UpdateSchedulerConfig } from '../types'
+import { CreateOrUpdateJobSchedule, JobSchedule, Pageable, PersistedJob } from '../types'
 import { ClassQueryKeys, PagedQueryParams } from './types'
 import { createRouteURLHandler } from './utils'
 
@@ -54,22 +54,75 @@ export class JobAPI extends APIBase {
 	}
 
 	/**
-	 * Get the config for the job scheduler
+	 * Fetch all job schedules
 	 */
-	async getSchedulerConfig(): Promise<JobSchedulerConfig> {
-		const { data: config } = await this.axios.get<JobSchedulerConfig>(jobURL('scheduler-config'))
-		return config
+	async getSchedules(): Promise<JobSchedule[]> {
+		const { data: schedules } = await this.axios.get<JobSchedule[]>(jobURL('schedules'))
+		return schedules
 	}
 
 	/**
-	 * Update the config for the job scheduler
+	 * Fetch a job schedule by its ID
 	 */
-	async updateSchedulerConfig(config: UpdateSchedulerConfig): Promise<JobSchedulerConfig> {
-		const { data: updatedConfig } = await this.axios.post<JobSchedulerConfig>(
-			jobURL('scheduler-config'),
-			config,
+	async getScheduleByID(id: string): Promise<JobSchedule> {
+		const { data: schedule } = await this.axios.get<JobSchedule>(jobURL(`schedules/${id}`))
+		return schedule
+	}
+
+	/**
+	 * Create a new job schedule
+	 */
+	async createSchedule(payload: CreateOrUpdateJobSchedule): Promise<JobSchedule> {
+		const { data: createdSchedule } = await this.axios.post<JobSchedule>(
+			jobURL('schedules'),
+			payload,
+		)
+		return createdSchedule
+	}
+
+	/**
+	 * Update a job schedule
+	 */
+	async updateSchedule(id: string, payload: CreateOrUpdateJobSchedule): Promise<JobSchedule> {
+		const { data: updatedSchedule } = await this.axios.put<JobSchedule>(
+			jobURL(`schedules/${id}`),
+			payload,
+		)
+		return updatedSchedule
+	}
+
+	/**
+	 * Delete a job schedule
+	 */
+	async deleteSchedule(id: string): Promise<JobSchedule> {
+		const { data: deletedSchedule } = await this.axios.delete<JobSchedule>(
+			jobURL(`schedules/${id}`),
 		)
-		return updatedConfig
+		return deletedSchedule
+	}
+
+	/**
+	 * Pause a job schedule, so it no longer runs automatically
+	 */
+	async pauseSchedule(id: string): Promise<JobSchedule> {
+		const { data: schedule } = await this.axios.post<JobSchedule>(jobURL(`schedules/${id}/pause`))
+		return schedule
+	}
+
+	/**
+	 * Resume a paused job schedule
+	 */
+	async resumeSchedule(id: string): Promise<JobSchedule> {
+		const { data: schedule } = await this.axios.post<JobSchedule>(jobURL(`schedules/${id}/resume`))
+		return schedule
+	}
+
+	/**
+	 * Run the job of a schedule immediately, regardless of when it is next due
+	 */
+	async triggerSchedule(id: string): Promise<JobSchedule> {
+		const { data: schedule } = await this.axios.post<JobSchedule>(jobURL(`schedules/${id}/trigger`))
+		return schedule
 	}
 
 	/**
@@ -78,12 +131,18 @@ export class JobAPI extends APIBase {
 	get keys(): ClassQueryKeys<InstanceType<typeof JobAPI>> {
 		return {
 			cancel: 'job.cancel',
+			createSchedule: 'job.createSchedule',
 			delete: 'job.delete',
 			deleteAll: 'job.deleteAll',
+			deleteSchedule: 'job.deleteSchedule',
 			get: 'job.get',
 			getByID: 'job.getByID',
-			getSchedulerConfig: 'job.getSchedulerConfig',
-			updateSchedulerConfig: 'job.updateSchedulerConfig',
+			getScheduleByID: 'job.getScheduleByID',
+			getSchedules: 'job.getSchedules',
+			pauseSchedule: 'job.pauseSchedule',
+			resumeSchedule: 'job.resumeSchedule',
+			triggerSchedule: 'job.triggerSchedule',
+			updateSchedule: 'job.updateSchedule',
 		}
 	}
 }
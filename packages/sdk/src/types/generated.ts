@@ -55,7 +55,7 @@ export type ThumbnailGenerationJobParams = { variant: ThumbnailGenerationJobVari
 
 export type ThumbnailGenerationOutput = { visited_files: number; skipped_files: number; generated_thumbnails: number; removed_thumbnails: number }
 
-export type User = { id: string; username: string; is_server_owner: boolean; avatar_url: string | null; created_at: string; last_login: string | null; is_locked: boolean; permissions: UserPermission[]; max_sessions_allowed?: number | null; login_sessions_count?: number | null; user_preferences?: UserPreferences | null; login_activity?: LoginActivity[] | null; age_restriction?: AgeRestriction | null; active_reading_sessions?: ActiveReadingSession[] | null; finished_reading_sessions?: FinishedReadingSession[] | null }
+export type User = { id: string; username: string; email: string | null; is_server_owner: boolean; avatar_url: string | null; created_at: string; last_login: string | null; is_locked: boolean; permissions: UserPermission[]; max_sessions_allowed?: number | null; login_sessions_count?: number | null; user_preferences?: UserPreferences | null; login_activity?: LoginActivity[] | null; age_restriction?: AgeRestriction | null; active_reading_sessions?: ActiveReadingSession[] | null; finished_reading_sessions?: FinishedReadingSession[] | null }
 
 /**
  * A partial representation of a user, which does not include all fields. This should be
@@ -68,20 +68,58 @@ export type PartialUser = { id: string; username: string; is_server_owner: boole
  * Permissions that can be granted to a user. Some permissions are implied by others,
  * and will be automatically granted if the "parent" permission is granted.
  */
-export type UserPermission = "feature:api_keys" | "feature:koreader_sync" | "bookclub:read" | "bookclub:create" | "emailer:read" | "emailer:create" | "emailer:manage" | "email:send" | "email:arbitrary_send" | "smartlist:read" | "file:explorer" | "file:upload" | "file:download" | "library:create" | "library:edit" | "library:scan" | "library:manage" | "library:delete" | "user:read" | "user:manage" | "notifier:read" | "notifier:create" | "notifier:manage" | "notifier:delete" | "server:manage"
+export type UserPermission = "feature:api_keys" | "feature:koreader_sync" | "bookclub:read" | "bookclub:create" | "emailer:read" | "emailer:create" | "emailer:manage" | "email:send" | "email:arbitrary_send" | "smartlist:read" | "collection:manage" | "file:explorer" | "file:upload" | "file:download" | "library:create" | "library:edit" | "library:scan" | "library:manage" | "library:delete" | "user:read" | "user:manage" | "notifier:read" | "notifier:create" | "notifier:manage" | "notifier:delete" | "server:manage"
 
 export type AgeRestriction = { age: number; restrict_on_unset: boolean }
 
+/**
+ * An invitation for someone to register an account on the server, even when registration
+ * is otherwise closed. The invited user is created with the permissions, age restriction
+ * and hidden libraries of the invitation. Invitations may only be redeemed once
+ */
+export type ServerInvitation = { id: string; email: string | null; granted_permissions: UserPermission[]; age_restriction: AgeRestriction | null; hidden_library_ids?: string[] | null; created_at: string; expires_at: string }
+
 /**
  * An API key which can be used to interact with the API. API keys are scoped to a user,
  * so all actions taken with an API key are done as if the user was taking them.
  */
-export type APIKey = { id: number; name: string; permissions: APIKeyPermissions; created_at: string; last_used_at: string | null; expires_at: string | null }
+export type APIKey = { id: number; name: string; permissions: APIKeyPermissions; scopes: APIKeyScopes; created_at: string; last_used_at: string | null; expires_at: string | null }
 
 export type InheritPermissionValue = "inherit"
 
 export type APIKeyPermissions = InheritPermissionValue | UserPermission[]
 
+/**
+ * A family of routes which an API key can be restricted to
+ */
+export type APIKeyRouteScope = "opds" | "koreader" | "progress_sync"
+
+/**
+ * The scopes of an API key, which restrict what it can be used for on top of its permissions.
+ * A key with the default scopes is unrestricted, i.e. empty lists do not restrict anything
+ */
+export type APIKeyScopes = { library_ids?: string[]; series_ids?: string[]; read_only?: boolean; routes?: APIKeyRouteScope[] }
+
+/**
+ * A persisted record of an administrative or security event, e.g. a library being deleted
+ */
+export type AuditLog = { id: string; action: AuditAction; actor_id: string | null; actor_username: string | null; target_type: AuditTargetType; target_id: string | null; target_name: string | null; changes: AuditLogChange[]; timestamp: string }
+
+/**
+ * The administrative and security actions which are recorded in the audit log
+ */
+export type AuditAction = "LIBRARY_CREATED" | "LIBRARY_UPDATED" | "LIBRARY_DELETED" | "LIBRARY_ACCESS_CHANGED" | "USER_CREATED" | "USER_UPDATED" | "USER_DELETED" | "USER_LOCKED" | "USER_UNLOCKED" | "USER_SESSIONS_REVOKED" | "USER_TWO_FACTOR_RESET" | "USER_PASSWORD_RESET" | "API_KEY_CREATED" | "API_KEY_UPDATED" | "API_KEY_DELETED" | "API_KEY_SECRET_REGENERATED" | "EMAILER_CREATED" | "EMAILER_UPDATED" | "EMAILER_DELETED" | "EMAIL_SENT"
+
+/**
+ * The kinds of entities an audit log entry can be about
+ */
+export type AuditTargetType = "LIBRARY" | "USER" | "API_KEY" | "EMAILER"
+
+/**
+ * A change to a single field of the target of an audit log entry
+ */
+export type AuditLogChange = { field: string; before: string | null; after: string | null }
+
 export type SupportedFont = "atkinsonhyperlegible" | "bitter" | "charis" | "inter" | "librebaskerville" | "literata" | "nunito" | "opendyslexic"
 
 export type NavigationMode = "SIDEBAR" | "TOPBAR"
@@ -100,6 +138,12 @@ export type UserPreferences = { id: string; locale: string; app_theme: string; e
 
 export type LoginActivity = { id: string; ip_address: string; user_agent: string; authentication_successful: boolean; timestamp: string; user?: User | null }
 
+/**
+ * An identity from an external OpenID Connect provider which is linked to a user, and
+ * can be used to log in as them
+ */
+export type ExternalIdentity = { id: string; issuer: string; subject: string; email: string | null; created_at: string; last_login_at: string | null }
+
 export type EmailerSendTo = { device_id: number } | { email: string }
 
 /**
@@ -172,7 +216,7 @@ export type Series = { id: string; name: string; path: string; description: stri
 /**
  * Struct representing the metadata for a processed file.
  */
-export type MediaMetadata = { title?: string | null; series?: string | null; number?: number | null; volume?: number | null; summary?: string | null; notes?: string | null; age_rating?: number | null; genre?: string[] | null; year?: number | null; month?: number | null; day?: number | null; writers?: string[] | null; pencillers?: string[] | null; inkers?: string[] | null; colorists?: string[] | null; letterers?: string[] | null; cover_artists?: string[] | null; editors?: string[] | null; publisher?: string | null; links?: string[] | null; characters?: string[] | null; teams?: string[] | null; page_count?: number | null; page_dimensions?: PageDimensionsEntity | null }
+export type MediaMetadata = { title?: string | null; series?: string | null; number?: number | null; volume?: number | null; summary?: string | null; notes?: string | null; age_rating?: number | null; genre?: string[] | null; year?: number | null; month?: number | null; day?: number | null; writers?: string[] | null; pencillers?: string[] | null; inkers?: string[] | null; colorists?: string[] | null; letterers?: string[] | null; cover_artists?: string[] | null; editors?: string[] | null; publisher?: string | null; language?: string | null; links?: string[] | null; characters?: string[] | null; teams?: string[] | null; identifiers?: string[] | null; page_count?: number | null; page_dimensions?: PageDimensionsEntity | null }
 
 export type Media = { id: string; name: string; size: number; extension: string; pages: number; updated_at: string; created_at: string; modified_at: string | null; hash: string | null; koreader_hash: string | null; path: string; status: FileStatus; series_id: string; metadata: MediaMetadata | null; series?: Series | null; active_reading_session?: ActiveReadingSession | null; finished_reading_sessions: FinishedReadingSession[] | null; current_page?: number | null; current_epubcfi?: string | null; is_completed?: boolean | null; tags?: Tag[] | null; bookmarks?: Bookmark[] | null }
 
@@ -182,9 +226,151 @@ export type Media = { id: string; name: string; size: number; extension: string;
  */
 export type Bookmark = { id: string; preview_content: string | null; epubcfi: string | null; page: number | null; book_id: string; book?: Media | null; user_id?: string | null; user?: User | null }
 
-export type MediaAnnotation = { id: string; highlighted_text: string | null; page: number | null; page_coordinates_x: number | null; page_coordinates_y: number | null; epubcfi: string | null; notes: string | null; media_id: string; media?: Media | null }
+/**
+ * A user's annotation of a book, which may be a highlight of some text, a note, or both.
+ * Annotations of EPUBs are located by an epubcfi, while annotations of image-based books
+ * are located by a page and (optionally) coordinates on that page
+ */
+export type MediaAnnotation = { id: string; highlighted_text: string | null; page: number | null; page_coordinates_x: number | null; page_coordinates_y: number | null; epubcfi: string | null; notes: string | null; created_at: string; updated_at: string; user_id: string; media_id: string; media?: Media | null }
+
+/**
+ * The annotations of a single book
+ */
+export type AnnotatedBook = { media_id: string; title: string; authors: string[]; pages: number | null; annotations: MediaAnnotation[] }
+
+/**
+ * The format to export annotations as
+ */
+export type AnnotationExportFormat = "markdown" | "json" | "koreader"
+
+/**
+ * Why the media in a [DuplicateCluster] are considered duplicates
+ */
+export type DuplicateReason = "CHECKSUM" | "COVER_HASH"
+
+/**
+ * A group of media which are likely duplicates of one another, as found by the duplicate
+ * analysis job
+ */
+export type DuplicateCluster = { id: string; confidence: number; reason: DuplicateReason; created_at: string; media?: Media[] | null }
 
-export type ActiveReadingSession = { id: string; page: number | null; epubcfi: string | null; percentage_completed: number | null; elapsed_seconds: number | null; started_at: string; media_id: string; media: Media | null; user_id: string; user: User | null }
+/**
+ * The reading activity within a single day, week or month
+ */
+export type ReadingActivity = { period: string; books_completed: number; pages_read: number; seconds_read: number }
+
+/**
+ * The number of books completed for a genre, writer or publisher
+ */
+export type ReadingStatsCount = { name: string; books_completed: number }
+
+/**
+ * The reading activity on a single device. Sessions which weren't recorded from a registered
+ * device (e.g. those from the web reader) are grouped under a `None` device
+ */
+export type DeviceReadingStats = { device_id: string | null; device_name: string | null; books_completed: number; pages_read: number; seconds_read: number }
+
+/**
+ * Statistics about reading activity. Activity is attributed to the day a book was finished,
+ * or the day it was last read for books which are still in progress
+ */
+export type ReadingStats = { books_completed: number; books_in_progress: number; pages_read: number; seconds_read: number; current_streak_days: number; longest_streak_days: number; daily: ReadingActivity[]; weekly: ReadingActivity[]; monthly: ReadingActivity[]; top_genres: ReadingStatsCount[]; top_writers: ReadingStatsCount[]; top_publishers: ReadingStatsCount[]; devices: DeviceReadingStats[] }
+
+/**
+ * A book which stood out in a [YearInReview]
+ */
+export type ReadingStatsBook = { media_id: string; title: string; pages: number; completed_at: string }
+
+/**
+ * A summary of a single year of reading
+ */
+export type YearInReview = { year: number; books_completed: number; pages_read: number; seconds_read: number; longest_streak_days: number; busiest_month: ReadingActivity | null; monthly: ReadingActivity[]; top_genres: ReadingStatsCount[]; top_writers: ReadingStatsCount[]; top_publishers: ReadingStatsCount[]; first_book: ReadingStatsBook | null; last_book: ReadingStatsBook | null; longest_book: ReadingStatsBook | null }
+
+/**
+ * The reading activity of a single user, used in the server-wide statistics
+ */
+export type UserReadingSummary = { user_id: string; username: string | null; books_completed: number; books_in_progress: number; pages_read: number; seconds_read: number; last_read_at: string | null }
+
+/**
+ * Reading statistics across every user on the server
+ */
+export type ServerReadingStats = { stats: ReadingStats; users: UserReadingSummary[] }
+
+/**
+ * A book returned by a full-text search, with the highlighted text which matched
+ */
+export type MediaSearchResult = { media: Media; score: number; highlighted_title: string; snippet: string }
+
+/**
+ * The providers Stump can fetch metadata from
+ */
+export type MetadataProviderType = "GOOGLE_BOOKS" | "OPEN_LIBRARY" | "COMIC_VINE"
+
+/**
+ * A single field of [MediaMetadata]
+ */
+export type MediaMetadataField = "title" | "series" | "number" | "volume" | "summary" | "notes" | "age_rating" | "genre" | "year" | "month" | "day" | "writers" | "pencillers" | "inkers" | "colorists" | "letterers" | "cover_artists" | "editors" | "publisher" | "language" | "links" | "characters" | "teams" | "identifiers" | "page_count"
+
+/**
+ * A single field of [SeriesMetadata]
+ */
+export type SeriesMetadataField = "title" | "summary" | "publisher" | "imprint" | "comicid" | "volume" | "booktype" | "age_rating" | "status"
+
+/**
+ * A possible match for a book or series found by a metadata provider
+ */
+export type MetadataCandidate = { id: string; provider: MetadataProviderType; provider_id: string; confidence: number; url: string | null; cover_url: string | null; media_metadata?: MediaMetadata | null; series_metadata?: SeriesMetadata | null; media_id: string | null; series_id: string | null; created_at: string }
+
+/**
+ * Where the value of a metadata field came from
+ */
+export type MetadataSource = "FILE" | "USER" | "GOOGLE_BOOKS" | "OPEN_LIBRARY" | "COMIC_VINE"
+
+/**
+ * The state of a single metadata field of a book or series
+ */
+export type MetadataFieldState = { field: string; locked: boolean; source: MetadataSource | null; updated_at: string }
+
+/**
+ * A change to a single metadata field of a book or series
+ */
+export type MetadataFieldChange = { id: string; field: string; source: MetadataSource | null; old_value: string | null; new_value: string | null; job_id: string | null; user_id: string | null; media_id: string | null; series_id: string | null; changed_at: string }
+
+/**
+ * The outcome of writing metadata to a book or series
+ */
+export type AppliedMetadata = { applied: string[]; locked: string[] }
+
+/**
+ * Options for the metadata fetch job
+ */
+export type MetadataFetchOptions = { providers?: MetadataProviderType[]; include_series?: boolean; auto_apply_threshold: number | null; lock_applied?: boolean }
+
+/**
+ * A user's review of a book, consisting of a required rating and optional written content
+ */
+export type Review = { id: string; rating: number; content: string | null; is_private: boolean; created_at: string; updated_at: string; media_id: string; media?: Media | null; user_id: string; user?: PartialUser | null }
+
+/**
+ * The number of reviews with a specific rating
+ */
+export type ReviewRatingCount = { rating: number; count: number }
+
+/**
+ * Aggregate rating statistics for a set of reviews, e.g. all reviews of a book or of the
+ * books in a series
+ */
+export type ReviewStats = { review_count: number; average_rating: number | null; distribution: ReviewRatingCount[] }
+
+/**
+ * A position within an EPUB, modeled after a Readium locator. It ties together the
+ * different ways a position is expressed by the web reader, OPDS clients and KOReader.
+ * 
+ * See https://readium.org/architecture/models/locators/
+ */
+export type ReadingLocator = { href: string; progression: number; total_progression: number; cfi: string | null; xpointer: string | null }
+
+export type ActiveReadingSession = { id: string; page: number | null; epubcfi: string | null; percentage_completed: number | null; locator: ReadingLocator | null; elapsed_seconds: number | null; started_at: string; media_id: string; media: Media | null; user_id: string; user: User | null }
 
 export type FinishedReadingSession = { id: string; started_at: string; completed_at: string; elapsed_seconds: number | null; media_id: string; media: Media | null; user_id: string; user: User | null }
 
@@ -242,9 +428,11 @@ export type SmartFilter<T> = { groups: FilterGroup<T>[] }
 
 export type TagSmartFilter = { name: Filter<string> }
 
-export type MediaSmartFilter = { name: Filter<string> } | { size: Filter<number> } | { extension: Filter<string> } | { created_at: Filter<string> } | { updated_at: Filter<string> } | { status: Filter<string> } | { path: Filter<string> } | { pages: Filter<number> } | { metadata: MediaMetadataSmartFilter } | { series: SeriesSmartFilter } | { tags: TagSmartFilter }
+export type ReviewSmartFilter = { rating: Filter<number> }
+
+export type MediaSmartFilter = { name: Filter<string> } | { size: Filter<number> } | { extension: Filter<string> } | { created_at: Filter<string> } | { updated_at: Filter<string> } | { status: Filter<string> } | { path: Filter<string> } | { pages: Filter<number> } | { metadata: MediaMetadataSmartFilter } | { series: SeriesSmartFilter } | { tags: TagSmartFilter } | { reviews: ReviewSmartFilter }
 
-export type MediaMetadataSmartFilter = { publisher: Filter<string> } | { genre: Filter<string> } | { characters: Filter<string> } | { colorists: Filter<string> } | { writers: Filter<string> } | { pencillers: Filter<string> } | { letterers: Filter<string> } | { inkers: Filter<string> } | { editors: Filter<string> } | { age_rating: Filter<number> } | { year: Filter<number> } | { month: Filter<number> } | { day: Filter<number> }
+export type MediaMetadataSmartFilter = { title: Filter<string> } | { publisher: Filter<string> } | { genre: Filter<string> } | { characters: Filter<string> } | { colorists: Filter<string> } | { writers: Filter<string> } | { pencillers: Filter<string> } | { letterers: Filter<string> } | { inkers: Filter<string> } | { editors: Filter<string> } | { age_rating: Filter<number> } | { year: Filter<number> } | { month: Filter<number> } | { day: Filter<number> }
 
 export type SeriesMetadataSmartFilter = { age_rating: Filter<number> } | { meta_type: Filter<string> } | { title: Filter<string> } | { summary: Filter<string> } | { publisher: Filter<string> } | { imprint: Filter<string> } | { comicid: Filter<number> } | { booktype: Filter<string> } | { volume: Filter<number> } | { status: Filter<string> }
 
@@ -288,9 +476,29 @@ export type UpdateEpubProgress = { epubcfi: string; percentage: number; is_compl
 
 export type EpubContent = { label: string; content: string; children: EpubContent[]; play_order: number }
 
+/**
+ * An entry in the outline (bookmarks) of a PDF, which typically serves as its table of
+ * contents
+ */
+export type PdfOutlineItem = { title: string; page: number | null; children: PdfOutlineItem[] }
+
 export type JobStatus = "RUNNING" | "PAUSED" | "COMPLETED" | "CANCELLED" | "FAILED" | "QUEUED"
 
-export type JobSchedulerConfig = { id: string; interval_secs: number; excluded_libraries: Library[] }
+/**
+ * The kinds of jobs which may be run on a schedule
+ */
+export type ScheduledJobKind = "LIBRARY_SCAN" | "THUMBNAIL_GENERATION" | "MEDIA_ANALYSIS" | "LOG_PRUNING" | "SESSION_CLEANUP"
+
+/**
+ * Optional, job-specific options for a schedule. Options which don't apply to the
+ * schedule's job kind are ignored.
+ */
+export type ScheduledJobConfig = { force_regenerate?: boolean; retention_days?: number | null }
+
+/**
+ * A named schedule which periodically enqueues a job of a given kind
+ */
+export type JobSchedule = { id: string; name: string; job_kind: ScheduledJobKind; cron_expression: string | null; interval_secs: number | null; config: ScheduledJobConfig; is_paused: boolean; last_run_at: string | null; next_run_at: string | null; created_at: string; included_libraries: Library[] | null; excluded_libraries: Library[] | null }
 
 export type ReadingListItem = { display_order: number; media_id: string; reading_list_id: string; media: Media | null }
 
@@ -300,6 +508,22 @@ export type ReadingList = { id: string; name: string; creating_user_id: string;
 
 export type CreateReadingList = { id: string; media_ids: string[]; visibility: ReadingListVisibility | null }
 
+/**
+ * The kind of entity a collection item refers to
+ */
+export type CollectionItemKind = "MEDIA" | "SERIES"
+
+/**
+ * A single entry in a collection, which is either a book or an entire series
+ */
+export type CollectionItem = { id: string; display_order: number; kind: CollectionItemKind; collection_id: string; media_id: string | null; media?: Media | null; series_id: string | null; series?: Series | null }
+
+/**
+ * A server-wide collection of series and books, curated by admins. Unlike reading lists,
+ * collections are owned by the server rather than an individual user
+ */
+export type Collection = { id: string; name: string; description: string | null; visibility: EntityVisibility; created_at: string; updated_at: string; items?: CollectionItem[] | null; shared_with_users?: PartialUser[] | null }
+
 /**
  * The resize mode to use when generating a thumbnail.
  */
@@ -323,6 +547,17 @@ export type ImageFormat = "Webp" | "Jpeg" | "Png"
  */
 export type ImageProcessorOptions = { resize_options?: ImageResizeOptions | null; format: ImageFormat; quality?: number | null; page?: number | null }
 
+/**
+ * Which half of a double-page spread to return when splitting a page
+ */
+export type PageSplit = "left" | "right"
+
+/**
+ * Transformations to apply to a page before it is delivered to a client. Transformations
+ * are applied in the following order: split, trim, resize and finally encode.
+ */
+export type PageTransformOptions = { max_width?: number | null; max_height?: number | null; upscale?: boolean; format?: ImageFormat | null; quality?: number | null; trim?: boolean; split?: PageSplit | null }
+
 export type DirectoryListing = { parent: string | null; files: DirectoryListingFile[] }
 
 export type DirectoryListingFile = { is_directory: boolean; name: string; path: string }
@@ -362,11 +597,33 @@ export type CreatedToken = { access_token: string; expires_at: string }
 
 export type LoginResponse = User | { for_user: User; token: CreatedToken }
 
-export type LoginOrRegisterArgs = { username: string; password: string }
+export type LoginOrRegisterArgs = { username: string; password: string; two_factor_code?: string | null }
+
+export type OidcStatus = { enabled: boolean }
 
-export type CreateUser = { username: string; password: string; permissions?: UserPermission[]; age_restriction: AgeRestriction | null; max_sessions_allowed?: number | null }
+export type TwoFactorStatus = { enabled: boolean; required: boolean; recovery_codes_remaining: number }
 
-export type UpdateUser = { username: string; password: string | null; avatar_url: string | null; permissions?: UserPermission[]; age_restriction: AgeRestriction | null; max_sessions_allowed?: number | null }
+/**
+ * A started enrollment in two-factor authentication, which is enabled once a code generated
+ * from the secret is confirmed
+ */
+export type TwoFactorEnrollment = { secret: string; otpauth_uri: string }
+
+export type TwoFactorCode = { code: string }
+
+/**
+ * Newly generated recovery codes, which can each be used once in place of a code from an
+ * authenticator app. They are not stored in plain text, so they can only be shown once
+ */
+export type TwoFactorRecoveryCodes = { recovery_codes: string[] }
+
+export type RequestPasswordReset = { email: string }
+
+export type ConfirmPasswordReset = { token: string; password: string }
+
+export type CreateUser = { username: string; password: string; email?: string | null; permissions?: UserPermission[]; age_restriction: AgeRestriction | null; max_sessions_allowed?: number | null }
+
+export type UpdateUser = { username: string; password: string | null; email?: string | null; avatar_url: string | null; permissions?: UserPermission[]; age_restriction: AgeRestriction | null; max_sessions_allowed?: number | null }
 
 export type UpdateUserPreferences = { id: string; locale: string; preferred_layout_mode: string; primary_navigation_mode: string; layout_max_width_px: number | null; app_theme: string; enable_gradients: boolean; app_font: SupportedFont; show_query_indicator: boolean; enable_live_refetch: boolean; enable_discord_presence: boolean; enable_compact_display: boolean; enable_double_sidebar: boolean; enable_replace_primary_sidebar: boolean; enable_hide_scrollbar: boolean; enable_job_overlay: boolean; prefer_accent_color: boolean; show_thumbnails_in_headers: boolean }
 
@@ -375,7 +632,7 @@ export type DeleteUser = { hard_delete: boolean | null }
 /**
  * The request body for creating or updating an API key
  */
-export type CreateOrUpdateAPIKey = { name: string; permissions: APIKeyPermissions; expires_at?: string | null }
+export type CreateOrUpdateAPIKey = { name: string; permissions: APIKeyPermissions; scopes?: APIKeyScopes; expires_at?: string | null }
 
 /**
  * The response after creating a new API key
@@ -407,6 +664,8 @@ export type PatchEmailDevice = { name: string | null; email: string | null; forb
 
 export type LogFilter = { level?: LogLevel | null; job_id?: string | null; timestamp?: ValueOrRange<string> | null }
 
+export type AuditLogFilter = { action?: AuditAction | null; actor_id?: string | null; target_type?: AuditTargetType | null; target_id?: string | null; since?: string | null; until?: string | null }
+
 export type LibraryBaseFilter = { id?: string[]; name?: string[]; path?: string[]; search?: string | null }
 
 export type LibraryRelationFilter = { series?: SeriesBaseFilter | null }
@@ -468,6 +727,26 @@ export type LibraryStatsParams = { all_users?: boolean }
  */
 export type PutMediaCompletionStatus = { is_complete: boolean; page?: number | null }
 
+/**
+ * Options for writing the metadata of books to their files
+ */
+export type WriteMetadataParams = { convert_rar_to_zip?: boolean }
+
+/**
+ * The books to write metadata to file for
+ */
+export type WriteMediaMetadataToFiles = { media_ids: string[]; convert_rar_to_zip?: boolean }
+
+/**
+ * Options for the duplicate analysis job
+ */
+export type StartDuplicateAnalysis = { force?: boolean }
+
+/**
+ * How to resolve a cluster of duplicate books
+ */
+export type ResolveDuplicateCluster = { keep_media_id: string; soft_delete_others?: boolean; merge_progress?: boolean }
+
 /**
  * Represents whether a media item is marked as completed and the last time it was completed.
  */
@@ -481,7 +760,34 @@ export type DeleteBookmark = { epubcfi: string }
 
 export type SeriesIsComplete = { is_complete: boolean; completed_at: string | null }
 
-export type UpdateSchedulerConfig = { interval_secs: number | null; excluded_library_ids: string[] | null }
+export type CreateOrUpdateJobSchedule = { name: string; job_kind: ScheduledJobKind; cron_expression: string | null; interval_secs: number | null; config?: ScheduledJobConfig; included_library_ids?: string[]; excluded_library_ids?: string[]; is_paused?: boolean }
+
+export type CreateOrUpdateReview = { rating: number; content?: string | null; is_private?: boolean }
+
+export type TopRatedParams = { limit?: number | null; min_reviews?: number | null }
+
+export type RatedMedia = { media: Media; stats: ReviewStats }
+
+export type RatedSeries = { series: Series; stats: ReviewStats }
+
+export type CreateOrUpdateAnnotation = { highlighted_text?: string | null; epubcfi?: string | null; page?: number | null; page_coordinates_x?: number | null; page_coordinates_y?: number | null; notes?: string | null }
+
+export type AnnotationExportParams = { format?: AnnotationExportFormat; media_id?: string | null }
+
+export type CreateServerInvitation = { email?: string | null; permissions?: UserPermission[]; age_restriction?: AgeRestriction | null; hidden_library_ids?: string[]; expires_in_days?: number | null }
+
+export type CreatedServerInvitation = { invitation: ServerInvitation; token: string; invitation_url: string | null }
+
+export type RedeemServerInvitation = { token: string; username: string; password: string }
+
+export type CreateOrUpdateCollection = { name: string; description?: string | null; visibility?: EntityVisibility; shared_with_user_ids?: string[] }
+
+/**
+ * A single item to place in a collection
+ */
+export type CollectionItemInput = { kind: CollectionItemKind; id: string }
+
+export type SetCollectionItems = { items: CollectionItemInput[] }
 
 export type GetBookClubsParams = { all?: boolean }
 
@@ -539,6 +845,20 @@ export type SmartListMeta = { matched_books: number; matched_series: number; mat
 
 export type CreateOrUpdateSmartListView = ({ book_columns: ReactTableColumnSort[]; group_columns: ReactTableColumnSort[]; book_sorting: ReactTableGlobalSort[] | null; group_sorting: ReactTableGlobalSort[] | null; enable_multi_sort?: boolean | null; search?: string | null }) & { name: string }
 
+export type MediaSearchParams = { query: string }
+
+export type FetchMetadata = ({ providers?: MetadataProviderType[]; include_series?: boolean; auto_apply_threshold: number | null; lock_applied?: boolean }) & { media_ids?: string[]; series_id: string | null; library_id: string | null }
+
+export type ApplyMetadataCandidate = { fields: string[] | null; lock?: boolean }
+
+export type LockMetadataField = { locked: boolean }
+
+export type MetadataHistoryParams = { field: string | null }
+
+export type ReadingStatsParams = { from?: string | null; to?: string | null; utc_offset_minutes?: number | null }
+
+export type YearInReviewParams = { year?: number | null; utc_offset_minutes?: number | null }
+
 export type UploadConfig = { enabled: boolean; max_file_upload_size: number }
 
 /**
@@ -569,7 +889,7 @@ export type UploadConfig = { enabled: boolean; max_file_upload_size: number }
  * }
  * ```
  */
-export type StumpConfig = { profile: string; port: number; verbosity: number; pretty_logs: boolean; db_path: string | null; client_dir: string; custom_templates_dir: string | null; config_dir: string; allowed_origins: string[]; pdfium_path: string | null; enable_swagger: boolean; enable_koreader_sync: boolean; password_hash_cost: number; session_ttl: number; access_token_ttl: number; expired_session_cleanup_interval: number; max_scanner_concurrency: number; max_thumbnail_concurrency: number; max_image_upload_size: number; enable_upload: boolean; max_file_upload_size: number }
+export type StumpConfig = { profile: string; port: number; verbosity: number; pretty_logs: boolean; db_path: string | null; client_dir: string; custom_templates_dir: string | null; config_dir: string; allowed_origins: string[]; pdfium_path: string | null; enable_swagger: boolean; enable_koreader_sync: boolean; password_hash_cost: number; session_ttl: number; access_token_ttl: number; expired_session_cleanup_interval: number; max_scanner_concurrency: number; max_thumbnail_concurrency: number; max_image_upload_size: number; enable_upload: boolean; max_file_upload_size: number; page_cache_size: number; index_epub_content: boolean; oidc_issuer_url: string | null; oidc_client_id: string | null; oidc_redirect_uri: string | null; oidc_scopes: string[]; oidc_auto_provision: boolean; oidc_permissions_claim: string | null; oidc_permission_mappings: string[]; oidc_age_restriction_claim: string | null; two_factor_required_for_owner: boolean; two_factor_required_for_managers: boolean; audit_log_retention_days: number; audit_log_notifications: boolean }
 
 // DESKTOP TYPE GENERATION
 